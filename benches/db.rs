@@ -0,0 +1,312 @@
+//! Storage hot paths against a synthetic dataset.
+//!
+//! ```sh
+//! BENCH_MESSAGES=20000 BENCH_CONTACTS=50 cargo bench --bench db
+//! BENCH_SAVE=baseline.json cargo bench --bench db
+//! BENCH_BASELINE=baseline.json cargo bench --bench db
+//! BENCH_ACCOUNT_CONTACTS=200 BENCH_ACCOUNT_MESSAGES=500 cargo bench --bench db
+//! ```
+//!
+//! The startup and paging measurements run against an account from
+//! `test_support::generate`, sized by the `BENCH_ACCOUNT_*` variables.
+//!
+//! With `BENCH_BASELINE` the run fails when a measurement is slower than
+//! the saved one by more than `BENCH_TOLERANCE` percent (default 20).
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::Keys;
+use nostrtalk::db::{
+    ChannelSubscription, DbChannelMessage, DbContact, DbEvent, DbMessage, MessageTagInfo,
+    TestDatabase,
+};
+use nostrtalk::test_support::{self, AccountSpec, SyntheticAccount};
+use nostrtalk::utils::dm_builder;
+use sqlx::SqlitePool;
+use url::Url;
+
+struct Measurement {
+    name: &'static str,
+    ops: usize,
+    elapsed: Duration,
+}
+impl Measurement {
+    fn ops_per_sec(&self) -> f64 {
+        self.ops as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+    fn per_op(&self) -> Duration {
+        self.elapsed / self.ops.max(1) as u32
+    }
+}
+
+struct Dataset {
+    keys: Keys,
+    contacts: Vec<XOnlyPublicKey>,
+    events: Vec<nostr::Event>,
+}
+impl Dataset {
+    /// Direct messages spread over the contacts, half of them sent by the user
+    fn generate(messages: usize, contacts: usize) -> Self {
+        let keys = Keys::generate();
+        let contact_keys: Vec<Keys> = (0..contacts.max(1)).map(|_| Keys::generate()).collect();
+        let events = (0..messages)
+            .map(|n| {
+                let contact = &contact_keys[n % contact_keys.len()];
+                let content = format!("synthetic message number {}", n);
+                let (sender, receiver) = if n % 2 == 0 {
+                    (&keys, contact.public_key())
+                } else {
+                    (contact, keys.public_key())
+                };
+                dm_builder(sender, &receiver, None, &content)
+                    .expect("build dm")
+                    .to_event(sender)
+                    .expect("sign dm")
+            })
+            .collect();
+        Self {
+            contacts: contact_keys.iter().map(|k| k.public_key()).collect(),
+            keys,
+            events,
+        }
+    }
+}
+
+async fn bench_insert(pool: &SqlitePool, dataset: &Dataset) -> (Measurement, Vec<DbEvent>) {
+    let relay_url = Url::parse(BENCH_RELAY).expect("relay url");
+    let start = Instant::now();
+    let mut db_events = Vec::with_capacity(dataset.events.len());
+    for ns_event in &dataset.events {
+        if let Some(db_event) = DbEvent::insert(pool, &relay_url, ns_event)
+            .await
+            .expect("insert event")
+        {
+            db_events.push(db_event);
+        }
+    }
+    let measurement = Measurement {
+        name: "DbEvent::insert",
+        ops: dataset.events.len(),
+        elapsed: start.elapsed(),
+    };
+    (measurement, db_events)
+}
+
+async fn store_messages(pool: &SqlitePool, dataset: &Dataset, db_events: &[DbEvent]) {
+    for db_event in db_events {
+        let tag_info =
+            MessageTagInfo::from_event_tags(&db_event.event_hash, &db_event.pubkey, &db_event.tags)
+                .expect("message tags");
+        let is_users = tag_info.from_pubkey == dataset.keys.public_key();
+        let chat_pubkey = tag_info
+            .chat_pubkey(&dataset.keys)
+            .expect("message of the user");
+        DbMessage::insert_confirmed(pool, db_event, &chat_pubkey, is_users)
+            .await
+            .expect("insert message");
+    }
+}
+
+async fn bench_fetch_chat(pool: &SqlitePool, dataset: &Dataset) -> (Measurement, Vec<DbMessage>) {
+    let mut fetched = vec![];
+    let start = Instant::now();
+    for _ in 0..FETCH_ROUNDS {
+        fetched.clear();
+        for contact in &dataset.contacts {
+            fetched.extend(
+                DbMessage::fetch_chat(pool, contact)
+                    .await
+                    .expect("fetch chat"),
+            );
+        }
+    }
+    let measurement = Measurement {
+        name: "DbMessage::fetch_chat",
+        ops: FETCH_ROUNDS * dataset.contacts.len(),
+        elapsed: start.elapsed(),
+    };
+    (measurement, fetched)
+}
+
+fn bench_decrypt(dataset: &Dataset, db_events: &[DbEvent], messages: &[DbMessage]) -> Measurement {
+    let by_id: HashMap<i64, &DbEvent> = db_events.iter().map(|e| (e.event_id, e)).collect();
+    let start = Instant::now();
+    for db_message in messages {
+        let db_event = by_id[&db_message.event_id];
+        let tag_info =
+            MessageTagInfo::from_event_tags(&db_event.event_hash, &db_event.pubkey, &db_event.tags)
+                .expect("message tags");
+        db_message
+            .decrypt_message(&dataset.keys, &tag_info)
+            .expect("decrypt");
+    }
+    Measurement {
+        name: "DbMessage::decrypt_message",
+        ops: messages.len(),
+        elapsed: start.elapsed(),
+    }
+}
+
+/// What the app loads when it opens: contacts, channels and the first page of
+/// every conversation
+async fn bench_startup(pool: &SqlitePool, account: &SyntheticAccount) -> Measurement {
+    let start = Instant::now();
+    for _ in 0..FETCH_ROUNDS {
+        DbContact::fetch(pool).await.expect("fetch contacts");
+        for subscription in ChannelSubscription::fetch(pool)
+            .await
+            .expect("fetch channels")
+        {
+            DbChannelMessage::fetch(pool, &subscription.channel_id)
+                .await
+                .expect("fetch channel messages");
+        }
+        for contact in &account.contacts {
+            DbMessage::fetch_chat(pool, contact)
+                .await
+                .expect("fetch chat");
+        }
+    }
+    Measurement {
+        name: "startup",
+        ops: FETCH_ROUNDS,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Scrolls every conversation back to its first message
+async fn bench_paging(pool: &SqlitePool, account: &SyntheticAccount) -> Measurement {
+    let mut pages = 0;
+    let start = Instant::now();
+    for contact in &account.contacts {
+        let mut page = DbMessage::fetch_chat(pool, contact)
+            .await
+            .expect("fetch chat");
+        while let Some(oldest) = page.last() {
+            pages += 1;
+            page = DbMessage::fetch_chat_more(pool, contact, oldest.created_at)
+                .await
+                .expect("fetch chat page");
+        }
+    }
+    Measurement {
+        name: "DbMessage::fetch_chat_more",
+        ops: pages,
+        elapsed: start.elapsed(),
+    }
+}
+
+fn report(measurements: &[Measurement]) {
+    println!(
+        "{:<28} {:>10} {:>12} {:>12} {:>12}",
+        "bench", "ops", "total", "per op", "ops/s"
+    );
+    for m in measurements {
+        println!(
+            "{:<28} {:>10} {:>12.2?} {:>12.2?} {:>12.0}",
+            m.name,
+            m.ops,
+            m.elapsed,
+            m.per_op(),
+            m.ops_per_sec()
+        );
+    }
+}
+
+/// Names of the measurements slower than the baseline
+fn regressions(measurements: &[Measurement], baseline: &HashMap<String, f64>) -> Vec<String> {
+    let tolerance = env_or("BENCH_TOLERANCE", DEFAULT_TOLERANCE) as f64 / 100.0;
+    measurements
+        .iter()
+        .filter_map(|m| {
+            let base = baseline.get(m.name)?;
+            let current = m.ops_per_sec();
+            (current < base * (1.0 - tolerance)).then(|| {
+                format!(
+                    "{}: {:.0} ops/s, baseline {:.0} ops/s",
+                    m.name, current, base
+                )
+            })
+        })
+        .collect()
+}
+
+fn env_or(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[tokio::main]
+async fn main() {
+    let messages = env_or("BENCH_MESSAGES", DEFAULT_MESSAGES);
+    let contacts = env_or("BENCH_CONTACTS", DEFAULT_CONTACTS);
+    println!(
+        "Generating {} messages with {} contacts...",
+        messages, contacts
+    );
+    let dataset = Dataset::generate(messages, contacts);
+
+    let db = TestDatabase::new().await.expect("open database");
+    let pool = db.pool();
+
+    let (insert, db_events) = bench_insert(pool, &dataset).await;
+    store_messages(pool, &dataset, &db_events).await;
+    let (fetch_chat, fetched) = bench_fetch_chat(pool, &dataset).await;
+    let decrypt = bench_decrypt(&dataset, &db_events, &fetched);
+
+    let spec = AccountSpec {
+        contacts: env_or("BENCH_ACCOUNT_CONTACTS", AccountSpec::default().contacts),
+        messages_per_contact: env_or("BENCH_ACCOUNT_MESSAGES", DEFAULT_ACCOUNT_MESSAGES),
+        channels: env_or("BENCH_ACCOUNT_CHANNELS", AccountSpec::default().channels),
+        ..AccountSpec::default()
+    };
+    println!(
+        "Generating an account with {} direct and {} channel messages...",
+        spec.dm_count(),
+        spec.channel_message_count()
+    );
+    let account_db = TestDatabase::new().await.expect("open database");
+    let account = test_support::generate(account_db.pool(), account_db.cache_pool(), &spec)
+        .await
+        .expect("generate account");
+    let startup = bench_startup(account_db.pool(), &account).await;
+    let paging = bench_paging(account_db.pool(), &account).await;
+
+    let measurements = [insert, fetch_chat, decrypt, startup, paging];
+    report(&measurements);
+
+    if let Ok(path) = std::env::var("BENCH_SAVE") {
+        let saved: HashMap<&str, f64> = measurements
+            .iter()
+            .map(|m| (m.name, m.ops_per_sec()))
+            .collect();
+        let json = serde_json::to_string_pretty(&saved).expect("baseline json");
+        std::fs::write(&path, json).expect("write baseline");
+        println!("Baseline saved to {}", path);
+    }
+
+    if let Ok(path) = std::env::var("BENCH_BASELINE") {
+        let json = std::fs::read_to_string(&path).expect("read baseline");
+        let baseline: HashMap<String, f64> = serde_json::from_str(&json).expect("baseline json");
+        let slower = regressions(&measurements, &baseline);
+        if !slower.is_empty() {
+            for line in &slower {
+                eprintln!("Regression - {}", line);
+            }
+            std::process::exit(1);
+        }
+        println!("No regressions against {}", path);
+    }
+}
+
+const BENCH_RELAY: &str = "wss://bench.relay";
+const DEFAULT_MESSAGES: usize = 5000;
+const DEFAULT_CONTACTS: usize = 20;
+const DEFAULT_TOLERANCE: usize = 20;
+/// Enough for a few pages of each conversation
+const DEFAULT_ACCOUNT_MESSAGES: usize = 250;
+const FETCH_ROUNDS: usize = 10;
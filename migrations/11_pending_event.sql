@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS pending_event (
+    event_hash TEXT PRIMARY KEY,
+    json TEXT NOT NULL,
+    journaled_at INTEGER NOT NULL
+);
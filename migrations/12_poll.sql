@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS poll (
+    poll_hash TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    author TEXT NOT NULL,
+    question TEXT NOT NULL,
+    -- JSON array of options
+    options TEXT NOT NULL,
+    -- UNIX timestamp as integer milliseconds
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS poll_channel_id_index ON poll(channel_id);
+
+-- Only the latest vote of each voter counts
+CREATE TABLE IF NOT EXISTS poll_vote (
+    poll_hash TEXT NOT NULL,
+    voter TEXT NOT NULL,
+    option_id TEXT NOT NULL,
+    -- UNIX timestamp as integer milliseconds
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (poll_hash, voter)
+);
@@ -0,0 +1,19 @@
+-- NIP-53 live activities, only the latest version of each one
+CREATE TABLE IF NOT EXISTS live_activity (
+    author TEXT NOT NULL,
+    -- d tag
+    identifier TEXT NOT NULL,
+    event_hash TEXT NOT NULL,
+    host TEXT NOT NULL,
+    title TEXT NOT NULL,
+    summary TEXT,
+    image_url TEXT,
+    streaming_url TEXT,
+    -- planned, live or ended
+    status TEXT NOT NULL,
+    -- UNIX timestamp as integer milliseconds
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (author, identifier)
+);
+
+CREATE INDEX IF NOT EXISTS live_activity_host_index ON live_activity(host);
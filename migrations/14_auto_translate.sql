@@ -0,0 +1,4 @@
+-- Conversations translated as messages arrive
+CREATE TABLE IF NOT EXISTS auto_translate (
+    chat_pubkey TEXT PRIMARY KEY
+);
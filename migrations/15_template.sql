@@ -0,0 +1,6 @@
+-- Reusable message snippets
+CREATE TABLE IF NOT EXISTS template (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    content TEXT NOT NULL
+);
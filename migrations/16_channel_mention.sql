@@ -0,0 +1,8 @@
+-- Channel messages that mention the user
+CREATE TABLE IF NOT EXISTS channel_mention (
+    event_id INTEGER PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    seen INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS channel_mention_channel_index ON channel_mention(channel_id);
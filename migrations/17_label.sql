@@ -0,0 +1,15 @@
+-- Colored labels assignable to conversations and channels
+CREATE TABLE IF NOT EXISTS label (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    color TEXT NOT NULL,
+    notify INTEGER NOT NULL DEFAULT 0
+);
+
+-- Contact public key or channel id
+CREATE TABLE IF NOT EXISTS conversation_label (
+    label_id INTEGER NOT NULL,
+    conversation TEXT NOT NULL,
+    PRIMARY KEY (label_id, conversation),
+    FOREIGN KEY (label_id) REFERENCES label(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE relay ADD COLUMN anonymous_auth INTEGER NOT NULL DEFAULT 0;
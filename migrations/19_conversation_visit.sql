@@ -0,0 +1,8 @@
+-- How often and how recently each conversation was opened, ranks the quick switcher
+CREATE TABLE IF NOT EXISTS conversation_visit (
+    -- Contact public key or channel id
+    conversation TEXT PRIMARY KEY,
+    visits INTEGER NOT NULL DEFAULT 0,
+    -- UNIX timestamp as integer milliseconds
+    last_visit INTEGER NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Unsent composer text, one per conversation
+CREATE TABLE IF NOT EXISTS draft (
+    -- Contact public key or channel id
+    conversation TEXT PRIMARY KEY,
+    -- 1 when the conversation is a channel
+    is_channel INTEGER NOT NULL DEFAULT 0,
+    content TEXT NOT NULL,
+    -- UNIX timestamp as integer milliseconds
+    updated_at INTEGER NOT NULL
+);
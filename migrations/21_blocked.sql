@@ -0,0 +1,8 @@
+-- Users whose events are dropped before being stored
+CREATE TABLE IF NOT EXISTS blocked (
+    pubkey TEXT PRIMARY KEY,
+    -- 1 when the block is also in the user's public mute list
+    on_mute_list INTEGER NOT NULL DEFAULT 0,
+    -- UNIX timestamp as integer milliseconds
+    created_at INTEGER NOT NULL
+);
@@ -0,0 +1,3 @@
+-- When the relay was added, UNIX timestamp as integer milliseconds.
+-- NULL for relays added before this column existed
+ALTER TABLE relay ADD COLUMN created_at INTEGER;
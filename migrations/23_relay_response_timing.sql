@@ -0,0 +1,4 @@
+-- When the event was handed to the relays and when this relay answered,
+-- UNIX timestamps as integer milliseconds. NULL for answers stored before
+ALTER TABLE relay_response ADD COLUMN sent_at INTEGER;
+ALTER TABLE relay_response ADD COLUMN responded_at INTEGER;
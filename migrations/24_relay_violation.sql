@@ -0,0 +1,14 @@
+-- Relays caught flooding a subscription, one row per relay and kind of violation
+CREATE TABLE IF NOT EXISTS relay_violation (
+    relay_url TEXT NOT NULL,
+    -- flood, over_limit or replay
+    kind TEXT NOT NULL,
+    -- Last subscription caught
+    subscription_id TEXT NOT NULL,
+    -- Subscriptions caught so far
+    count INTEGER NOT NULL DEFAULT 1,
+    -- UNIX timestamps as integer milliseconds
+    first_at INTEGER NOT NULL,
+    last_at INTEGER NOT NULL,
+    PRIMARY KEY (relay_url, kind)
+);
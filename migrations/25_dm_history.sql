@@ -0,0 +1,9 @@
+-- Oldest DM synced at login, UNIX timestamp as integer milliseconds. NULL for all of it
+ALTER TABLE user_config ADD COLUMN dm_sync_since INTEGER;
+
+-- How far back each conversation's history was fetched, beyond the login window
+CREATE TABLE IF NOT EXISTS dm_history (
+    chat_pubkey TEXT PRIMARY KEY,
+    -- UNIX timestamp as integer milliseconds
+    fetched_since INTEGER NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Private notes about the contact, only stored locally
+ALTER TABLE contact ADD COLUMN notes TEXT;
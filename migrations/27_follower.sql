@@ -0,0 +1,10 @@
+-- Users whose contact list includes the user, indexed from their kind 3 events
+CREATE TABLE IF NOT EXISTS follower (
+    pubkey TEXT PRIMARY KEY,
+    -- first contact list seen with the user in it
+    event_hash TEXT NOT NULL,
+    -- created_at of that contact list, UNIX timestamp as integer milliseconds
+    followed_at INTEGER NOT NULL,
+    -- 1 once shown in the activity panel
+    seen INTEGER NOT NULL DEFAULT 0
+);
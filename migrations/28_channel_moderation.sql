@@ -0,0 +1,12 @@
+-- Hide (kind 43) and mute (kind 44) events tagging a channel,
+-- only those by the owner or a co-moderator are honored
+CREATE TABLE IF NOT EXISTS channel_moderation (
+    event_hash TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    author TEXT NOT NULL,
+    kind INTEGER NOT NULL,
+    -- hash of the hidden message or the muted public key
+    target TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS channel_moderation_channel_id_index ON channel_moderation(channel_id);
@@ -0,0 +1,11 @@
+-- Named sets of relays, one row per relay of each profile
+CREATE TABLE IF NOT EXISTS relay_profile (
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    read INTEGER NOT NULL DEFAULT 1,
+    write INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (name, url)
+);
+
+-- Profile last switched to, NULL when the relays were never switched
+ALTER TABLE user_config ADD COLUMN active_relay_profile TEXT;
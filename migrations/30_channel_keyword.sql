@@ -0,0 +1,9 @@
+-- Words watched in a channel, matching messages go to the inbox
+CREATE TABLE IF NOT EXISTS channel_keyword (
+    channel_id TEXT NOT NULL,
+    keyword TEXT NOT NULL,
+    PRIMARY KEY (channel_id, keyword)
+);
+
+-- Watched word found in the message, NULL when the user was mentioned
+ALTER TABLE channel_mention ADD COLUMN keyword TEXT;
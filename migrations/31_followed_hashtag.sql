@@ -0,0 +1,5 @@
+-- Hashtags followed by the user, each one has its own sidebar entry
+CREATE TABLE IF NOT EXISTS followed_hashtag (
+    hashtag TEXT PRIMARY KEY,
+    followed_at INTEGER NOT NULL
+);
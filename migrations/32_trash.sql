@@ -0,0 +1,4 @@
+-- Deleted contacts and cleared conversations stay in the trash until purged,
+-- UNIX timestamp as integer milliseconds, NULL when not deleted
+ALTER TABLE contact ADD COLUMN deleted_at INTEGER;
+ALTER TABLE message ADD COLUMN deleted_at INTEGER;
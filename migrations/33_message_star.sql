@@ -0,0 +1,2 @@
+-- Starred messages, can be kept when clearing the conversation
+ALTER TABLE message ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Attachments of direct messages saved to the download directory
+CREATE TABLE IF NOT EXISTS attachment_download (
+    event_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    path TEXT NOT NULL,
+    saved_at INTEGER NOT NULL,
+    PRIMARY KEY (event_id, url),
+    FOREIGN KEY (event_id) REFERENCES event(event_id) ON DELETE CASCADE
+);
+
+-- Conversations whose received attachments are saved as they arrive
+CREATE TABLE IF NOT EXISTS auto_save_attachments (
+    chat_pubkey TEXT PRIMARY KEY
+);
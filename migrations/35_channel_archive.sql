@@ -0,0 +1,5 @@
+-- Channels left without a live subscription, kept browsable read-only
+CREATE TABLE IF NOT EXISTS channel_archive (
+    channel_id TEXT PRIMARY KEY,
+    archived_at INTEGER NOT NULL
+);
@@ -0,0 +1,12 @@
+-- NIP-11 document of each relay, refreshed on a schedule
+ALTER TABLE relay ADD COLUMN document TEXT;
+ALTER TABLE relay ADD COLUMN document_etag TEXT;
+ALTER TABLE relay ADD COLUMN document_fetched_at INTEGER;
+
+-- Differences found when a relay's document was refreshed, kept until dismissed
+CREATE TABLE IF NOT EXISTS relay_document_change (
+    id INTEGER PRIMARY KEY,
+    relay_url TEXT NOT NULL,
+    change TEXT NOT NULL,
+    detected_at INTEGER NOT NULL
+);
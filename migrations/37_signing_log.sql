@@ -0,0 +1,22 @@
+-- Every event signed with the user's keys, rows are never changed or removed
+CREATE TABLE IF NOT EXISTS signing_log (
+    id INTEGER PRIMARY KEY,
+    event_hash TEXT NOT NULL,
+    kind INTEGER NOT NULL,
+    target TEXT,
+    -- JSON array of the relay urls the event was meant for
+    relays TEXT NOT NULL,
+    signed_at INTEGER NOT NULL
+);
+
+CREATE TRIGGER IF NOT EXISTS signing_log_no_update
+BEFORE UPDATE ON signing_log
+BEGIN
+    SELECT RAISE(ABORT, 'signing_log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS signing_log_no_delete
+BEFORE DELETE ON signing_log
+BEGIN
+    SELECT RAISE(ABORT, 'signing_log is append-only');
+END;
@@ -0,0 +1,4 @@
+-- Newest channel message the user has scrolled past, synced between devices
+ALTER TABLE channel_subscription ADD COLUMN last_read_event TEXT;
+-- UNIX timestamp as integer milliseconds of that message
+ALTER TABLE channel_subscription ADD COLUMN last_read_at INTEGER;
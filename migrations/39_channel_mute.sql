@@ -0,0 +1,2 @@
+-- Muted channels keep receiving messages without counting them as unread
+ALTER TABLE channel_subscription ADD COLUMN muted INTEGER NOT NULL DEFAULT 0;
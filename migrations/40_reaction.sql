@@ -0,0 +1,12 @@
+-- NIP-25 reactions, by the hash of the event they react to
+CREATE TABLE IF NOT EXISTS reaction (
+    reaction_hash TEXT PRIMARY KEY,
+    target_hash TEXT NOT NULL,
+    author TEXT NOT NULL,
+    content TEXT NOT NULL,
+    -- UNIX timestamp as integer milliseconds
+    created_at INTEGER NOT NULL,
+    UNIQUE (target_hash, author, content)
+);
+
+CREATE INDEX IF NOT EXISTS reaction_target_hash_index ON reaction(target_hash);
@@ -0,0 +1,3 @@
+-- Deleted by the author with a NIP-09 request, the row stays where the message was
+ALTER TABLE message ADD COLUMN retracted_at INTEGER;
+ALTER TABLE channel_message ADD COLUMN retracted_at INTEGER;
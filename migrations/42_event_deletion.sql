@@ -0,0 +1,9 @@
+-- NIP-09 requests whose target hadn't arrived yet, applied when it does
+CREATE TABLE IF NOT EXISTS event_deletion (
+    target_hash TEXT NOT NULL,
+    -- who asked, only the author of the target can delete it
+    author TEXT NOT NULL,
+    -- UNIX timestamp as integer milliseconds
+    deleted_at INTEGER NOT NULL,
+    PRIMARY KEY (target_hash, author)
+);
@@ -0,0 +1,7 @@
+-- Events of conversations purged from the trash, relays sending them again
+-- don't bring them back
+CREATE TABLE IF NOT EXISTS purged_event (
+    event_hash TEXT PRIMARY KEY,
+    -- UNIX timestamp as integer milliseconds
+    purged_at INTEGER NOT NULL
+);
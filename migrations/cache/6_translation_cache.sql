@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS translation_cache (
+    event_hash TEXT NOT NULL,
+    target_lang TEXT NOT NULL,
+    -- language detected by the provider
+    source_lang TEXT,
+    content TEXT NOT NULL,
+    PRIMARY KEY (event_hash, target_lang)
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS lightning_check (
+    -- lud16 or lud06 as written in the profile
+    address TEXT PRIMARY KEY,
+    -- the LNURL-pay endpoint answered with a payRequest
+    valid INTEGER NOT NULL,
+    checked_at INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS image_color (
+    event_hash TEXT NOT NULL,
+    kind INTEGER NOT NULL,
+    -- dominant color as 0xRRGGBB, computed once when the image is downloaded
+    color INTEGER NOT NULL,
+    PRIMARY KEY (event_hash, kind)
+);
@@ -0,0 +1,6 @@
+-- Co-moderators named by the channel creator in their latest kind 41
+CREATE TABLE IF NOT EXISTS channel_moderator_map (
+    channel_id TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    PRIMARY KEY (channel_id, public_key)
+);
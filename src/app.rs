@@ -1,10 +1,16 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::text;
 use iced::{executor, subscription, window, Application, Command, Settings};
 
 use crate::components::inform_card;
-use crate::config;
+use crate::config::{self, Appearance, AutoLockConfig, Presence, PresenceConfig};
+use crate::error::BackendClosed;
+use crate::ipc;
 use crate::net::{backend_connect, BackEndConnection, BackendEvent, ToBackend};
 use crate::style;
-use crate::views::{self, Router};
+use crate::types::NostrLink;
+use crate::views::{self, lock, Router};
 use crate::widget::Element;
 
 #[derive(Debug, Clone)]
@@ -12,6 +18,9 @@ pub enum Message {
     RouterMessage(views::Message),
     BackEndEvent(BackendEvent),
     RuntimeEvent(iced::Event),
+    InstanceRequest(ipc::Request),
+    IdleTick,
+    Lock(lock::Message),
 }
 pub enum AppState {
     Loading,
@@ -31,23 +40,77 @@ impl AppState {
     }
 }
 
+/// Time since the last keyboard or mouse input
+struct IdleTracker {
+    auto_lock: AutoLockConfig,
+    presence: PresenceConfig,
+    /// Last status sent to the relays
+    published: Option<Presence>,
+    last_input: Instant,
+}
+impl IdleTracker {
+    fn new(auto_lock: AutoLockConfig, presence: PresenceConfig) -> Self {
+        Self {
+            auto_lock,
+            presence,
+            published: None,
+            last_input: Instant::now(),
+        }
+    }
+    /// Status to publish, only when it changed since the last one
+    fn presence_change(&mut self, now: Instant) -> Option<Presence> {
+        let idle_for = now.saturating_duration_since(self.last_input);
+        let presence = self.presence.resolve(idle_for)?;
+        if self.published == Some(presence) {
+            return None;
+        }
+        self.published = Some(presence);
+        Some(presence)
+    }
+    fn needs_ticks(&self) -> bool {
+        self.auto_lock.enabled || self.presence.publish
+    }
+    fn input(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+    fn should_lock(&self, now: Instant) -> bool {
+        let idle_for = Duration::from_secs(u64::from(self.auto_lock.idle_minutes) * 60);
+        self.auto_lock.enabled && now.saturating_duration_since(self.last_input) >= idle_for
+    }
+}
+
 pub struct App {
     state: AppState,
     color_theme: Option<style::Theme>,
+    appearance: Appearance,
+    /// Pending events the backend is waiting for before closing
+    syncing_before_exit: Option<usize>,
+    /// `nostr:` link received before the backend connected
+    pending_link: Option<NostrLink>,
+    idle: IdleTracker,
+    /// Shown instead of the router until the secret key is entered again
+    lock: Option<lock::State>,
+    logged_in: bool,
 }
 
 impl Application for App {
     type Theme = crate::style::Theme;
     type Executor = executor::Default;
     type Message = Message;
-    type Flags = ();
+    type Flags = Option<NostrLink>;
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Message>) {
+    fn new(link: Self::Flags) -> (Self, Command<Message>) {
         let config = config::Config::load();
         (
             Self {
                 state: AppState::Loading,
                 color_theme: Some(config.theme),
+                appearance: Appearance::from(&config),
+                syncing_before_exit: None,
+                pending_link: link,
+                idle: IdleTracker::new(config.auto_lock, config.presence),
+                lock: None,
+                logged_in: false,
             },
             Command::none(),
         )
@@ -62,31 +125,59 @@ impl Application for App {
         let mut subscriptions = vec![];
         let backend_subscription = backend_connect().map(Message::BackEndEvent);
 
+        // nothing behind the lock screen reacts to the keyboard
         let app_sub = match &self.state {
-            AppState::Loaded { router, .. } => router.subscription().map(Message::RouterMessage),
+            AppState::Loaded { router, .. } if self.lock.is_none() => {
+                router.subscription().map(Message::RouterMessage)
+            }
             _ => iced::Subscription::none(),
         };
+        if self.logged_in && self.idle.needs_ticks() && self.lock.is_none() {
+            subscriptions.push(
+                iced::time::every(Duration::from_secs(IDLE_CHECK_SECS)).map(|_| Message::IdleTick),
+            );
+        }
 
         let runtime_events = subscription::events().map(Message::RuntimeEvent);
+        let instance_requests = ipc::listen().map(Message::InstanceRequest);
 
         subscriptions.push(backend_subscription);
         subscriptions.push(app_sub);
         subscriptions.push(runtime_events);
+        subscriptions.push(instance_requests);
 
         iced::Subscription::batch(subscriptions)
     }
 
     fn view(&self) -> Element<Self::Message> {
+        if let Some(pending) = self.syncing_before_exit {
+            return inform_card(
+                "Syncing before exit…",
+                text(format!("Waiting for relays to confirm {} events", pending)),
+            );
+        }
+        if let Some(lock) = &self.lock {
+            return lock.view().map(Message::Lock);
+        }
         match &self.state {
             AppState::Loading => inform_card("Loading App", "Please wait..."),
-            AppState::Loaded { router, .. } => {
-                router.view(self.color_theme).map(Message::RouterMessage)
-            }
+            AppState::Loaded { router, .. } => router
+                .view(self.color_theme, self.appearance)
+                .map(Message::RouterMessage),
         }
     }
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
         match message {
             Message::RuntimeEvent(event) => {
+                if matches!(
+                    event,
+                    iced::Event::Keyboard(_) | iced::Event::Mouse(_) | iced::Event::Touch(_)
+                ) {
+                    self.idle.input(Instant::now());
+                    if self.sync_presence().is_err() {
+                        return window::close();
+                    }
+                }
                 if let iced::Event::Window(window::Event::CloseRequested) = event {
                     match &mut self.state {
                         AppState::Loading => {
@@ -110,6 +201,37 @@ impl Application for App {
                     }
                 }
             }
+            Message::InstanceRequest(request) => {
+                if let ipc::Request::Open(link) = request {
+                    match &mut self.state {
+                        AppState::Loading => self.pending_link = Some(link),
+                        AppState::Loaded { router, conn, .. } => {
+                            if let Err(_e) = router.open_link(link, conn) {
+                                return window::close();
+                            }
+                        }
+                    }
+                }
+                return window::gain_focus();
+            }
+            Message::IdleTick => {
+                if self.logged_in && self.lock.is_none() && self.idle.should_lock(Instant::now()) {
+                    tracing::info!("Locking after being idle");
+                    self.lock = Some(lock::State::new());
+                }
+                if self.sync_presence().is_err() {
+                    return window::close();
+                }
+            }
+            Message::Lock(msg) => {
+                if let (Some(lock), AppState::Loaded { conn, .. }) =
+                    (&mut self.lock, &mut self.state)
+                {
+                    if let Err(_e) = lock.update(msg, conn) {
+                        return window::close();
+                    }
+                }
+            }
             Message::RouterMessage(msg) => {
                 if let AppState::Loaded { router, conn, .. } = &mut self.state {
                     match router.update(msg, conn) {
@@ -124,15 +246,89 @@ impl Application for App {
                 if let BackendEvent::ThemeChanged(theme) = &event {
                     self.color_theme = Some(theme.to_owned());
                 }
+                if let BackendEvent::DimImagesChanged(dim) = &event {
+                    self.appearance.dim_images = *dim;
+                }
+                if let BackendEvent::TimeFormatChanged(format) = &event {
+                    self.appearance.time_format = *format;
+                }
+                if let BackendEvent::TextDirectionChanged(direction) = &event {
+                    self.appearance.text_direction = *direction;
+                }
+                if let BackendEvent::StartupViewChanged(startup_view) = &event {
+                    if let AppState::Loaded { router, .. } = &mut self.state {
+                        router.set_startup_view(*startup_view);
+                    }
+                }
+                if let BackendEvent::AutoLockChanged(auto_lock) = &event {
+                    self.idle.auto_lock = *auto_lock;
+                }
+                if let BackendEvent::PresenceChanged(presence) = &event {
+                    if self.presence_changed(*presence).is_err() {
+                        return window::close();
+                    }
+                }
+                if let BackendEvent::LoginSuccess | BackendEvent::CreateAccountSuccess = &event {
+                    self.logged_in = true;
+                    self.idle.input(Instant::now());
+                    self.idle.published = None;
+                    if self.sync_presence().is_err() {
+                        return window::close();
+                    }
+                }
 
                 match event {
                     BackendEvent::ShutdownDone => {
                         return window::close();
                     }
+                    BackendEvent::SyncingBeforeExit(pending) if pending > 0 => {
+                        self.syncing_before_exit = Some(pending);
+                    }
+                    BackendEvent::Unlocked => {
+                        self.lock = None;
+                        self.idle.input(Instant::now());
+                    }
+                    BackendEvent::UnlockFailed => {
+                        if let Some(lock) = &mut self.lock {
+                            lock.unlock_failed();
+                        }
+                    }
+                    BackendEvent::LogoutSuccess => {
+                        self.syncing_before_exit = None;
+                        self.logged_in = false;
+                        self.lock = None;
+                        if let AppState::Loaded { router, conn, .. } = &mut self.state {
+                            match router.backend_event(BackendEvent::LogoutSuccess, conn) {
+                                Ok(cmd) => return cmd.map(Message::RouterMessage),
+                                Err(_e) => return window::close(),
+                            }
+                        }
+                    }
                     BackendEvent::Connected(mut conn) => {
-                        let router = Router::new(&mut conn);
+                        let startup_view = config::Config::load().startup_view;
+                        let mut router = Router::new(&mut conn, startup_view);
+                        if let Some(link) = self.pending_link.take() {
+                            if let Err(_e) = router.open_link(link, &mut conn) {
+                                return window::close();
+                            }
+                        }
                         self.state = AppState::loaded(conn, router);
                     }
+                    BackendEvent::Response(id, inner) => {
+                        if let AppState::Loaded { router, conn, .. } = &mut self.state {
+                            // the requesting view matches the response by id,
+                            // every other view still gets the plain event
+                            let events = [BackendEvent::Response(id, inner.clone()), *inner];
+                            let mut commands = vec![];
+                            for event in events {
+                                match router.backend_event(event, conn) {
+                                    Ok(cmd) => commands.push(cmd.map(Message::RouterMessage)),
+                                    Err(_e) => return window::close(),
+                                }
+                            }
+                            return Command::batch(commands);
+                        }
+                    }
                     other => {
                         if let AppState::Loaded { router, conn, .. } = &mut self.state {
                             match router.backend_event(other, conn) {
@@ -149,8 +345,37 @@ impl Application for App {
     }
 }
 
-pub async fn run() {
+impl App {
+    /// Publishes the status when the idle time or the settings changed it
+    fn sync_presence(&mut self) -> Result<(), BackendClosed> {
+        if !self.logged_in {
+            return Ok(());
+        }
+        if let AppState::Loaded { conn, .. } = &mut self.state {
+            if let Some(presence) = self.idle.presence_change(Instant::now()) {
+                conn.send(ToBackend::PublishPresence(presence))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Turning publishing off clears an away status left on the relays
+    fn presence_changed(&mut self, presence: PresenceConfig) -> Result<(), BackendClosed> {
+        let was_away = self.idle.published == Some(Presence::Away);
+        self.idle.presence = presence;
+        self.idle.published = None;
+        if let (false, true, true, AppState::Loaded { conn, .. }) =
+            (presence.publish, was_away, self.logged_in, &mut self.state)
+        {
+            conn.send(ToBackend::PublishPresence(Presence::Available))?;
+        }
+        self.sync_presence()
+    }
+}
+
+pub async fn run(link: Option<NostrLink>) {
     App::run(Settings {
+        flags: link,
         exit_on_close_request: false,
         id: Some(String::from("nostrtalk")),
         window: window::Settings {
@@ -169,3 +394,63 @@ const APP_WIDTH: u32 = 800;
 const APP_HEIGHT: u32 = 600;
 const APP_MIN_WIDTH: u32 = 600;
 const APP_MIN_HEIGHT: u32 = 400;
+/// How often the idle time is compared to the auto-lock and away settings
+const IDLE_CHECK_SECS: u64 = 15;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StatusChoice;
+
+    #[test]
+    fn locks_after_idle_minutes() {
+        let mut idle = IdleTracker::new(
+            AutoLockConfig {
+                enabled: true,
+                idle_minutes: 5,
+            },
+            PresenceConfig::default(),
+        );
+        let start = Instant::now();
+        idle.input(start);
+        assert!(!idle.should_lock(start + Duration::from_secs(4 * 60)));
+        assert!(idle.should_lock(start + Duration::from_secs(5 * 60)));
+
+        idle.input(start + Duration::from_secs(5 * 60));
+        assert!(!idle.should_lock(start + Duration::from_secs(6 * 60)));
+
+        idle.auto_lock.enabled = false;
+        assert!(!idle.should_lock(start + Duration::from_secs(60 * 60)));
+    }
+
+    #[test]
+    fn goes_away_when_idle_and_back_on_input() {
+        let presence = PresenceConfig {
+            publish: true,
+            auto_away: true,
+            away_minutes: 10,
+            status: StatusChoice::Automatic,
+        };
+        let mut idle = IdleTracker::new(AutoLockConfig::default(), presence);
+        let start = Instant::now();
+        idle.input(start);
+        let minutes = |m| start + Duration::from_secs(m * 60);
+
+        assert_eq!(idle.presence_change(start), Some(Presence::Available));
+        assert_eq!(idle.presence_change(minutes(5)), None);
+        assert_eq!(idle.presence_change(minutes(10)), Some(Presence::Away));
+        assert_eq!(idle.presence_change(minutes(20)), None);
+        idle.input(minutes(21));
+        assert_eq!(idle.presence_change(minutes(21)), Some(Presence::Available));
+
+        idle.presence.status = StatusChoice::Away;
+        assert_eq!(idle.presence_change(minutes(21)), Some(Presence::Away));
+        idle.presence.status = StatusChoice::Automatic;
+        idle.presence.auto_away = false;
+        assert_eq!(idle.presence_change(minutes(90)), Some(Presence::Available));
+
+        idle.presence.publish = false;
+        idle.published = None;
+        assert_eq!(idle.presence_change(minutes(90)), None);
+    }
+}
@@ -1,16 +1,21 @@
-use chrono::{Datelike, NaiveDateTime, Utc};
+use chrono::NaiveDateTime;
 use iced::widget::image::Handle;
 use iced::widget::{button, column, container, image, row, text};
-use iced::{alignment, Length};
+use iced::{alignment, Color, Length};
 use unicode_segmentation::UnicodeSegmentation;
 
-use crate::consts::YMD_FORMAT;
-use crate::db::{DbContact, ImageDownloaded};
+use crate::components::badge_overlay::Corner;
+use crate::components::contact_search::{highlighted_text, ContactMatch};
+use crate::components::label_chip::label_dot;
+use crate::components::{BadgeOverlay, DimmedImage};
+use crate::config::{Appearance, TimeFormatConfig};
+use crate::db::{DbContact, ImageDownloaded, LabelNotify, Labels};
 use crate::error::BackendClosed;
+use crate::icon::{bell_slash_icon, solid_circle_icon};
 use crate::net::{self, BackEndConnection, ImageSize};
 use crate::style;
 use crate::types::ChatMessage;
-use crate::utils::from_naive_utc_to_local;
+use crate::utils::time_format;
 use crate::widget::Element;
 
 #[derive(Debug, Clone)]
@@ -79,7 +84,15 @@ pub struct ChatContact {
     mode: CardMode,
     pub contact: DbContact,
     profile_img_handle: image::Handle,
+    /// Dominant color of the profile picture
+    accent: Option<Color>,
     chat_info: ChatInfo,
+    /// On the user's mute list, new messages don't count as unseen
+    muted: bool,
+    /// Override from the conversation's labels
+    label_notify: LabelNotify,
+    /// The contact mentioned the user in a channel and it wasn't seen yet
+    mentioned: bool,
 }
 
 impl ChatContact {
@@ -96,53 +109,72 @@ impl ChatContact {
             mode: CardMode::Full,
             contact: db_contact.clone(),
             profile_img_handle,
+            accent: db_contact.profile_accent(),
             chat_info: ChatInfo::default(),
+            muted: false,
+            label_notify: LabelNotify::Default,
+            mentioned: false,
         })
     }
-    pub fn view(&self, active_id: Option<i32>) -> Element<MessageWrapper> {
+    /// `search_match` highlights what the contact search matched,
+    /// `selected` marks the result picked with the keyboard
+    pub fn view<'a>(
+        &'a self,
+        active_id: Option<i32>,
+        labels: &Labels,
+        search_match: Option<&ContactMatch>,
+        selected: bool,
+        appearance: Appearance,
+    ) -> Element<'a, MessageWrapper> {
         let size = ImageSize::Small;
         let card_active = active_id.map(|id| id == self.id);
         let (width, height) = size.get_width_height().unwrap();
-        let pic_container = container(image(self.profile_img_handle.to_owned()))
-            .width(width as f32)
-            .height(height as f32);
+        let pic_container = container(DimmedImage::new(
+            self.profile_img_handle.to_owned(),
+            appearance.dim_images,
+        ))
+        .width(width as f32)
+        .height(height as f32);
+        let avatar = self.make_avatar(pic_container);
 
         let btn_content: Element<_> = match self.mode {
-            CardMode::Small => column![pic_container, self.make_notifications()].into(),
+            CardMode::Small => avatar,
             CardMode::Full => {
                 // --- TOP ROW ---
-                let last_date_cp = self.make_last_date();
+                let last_date_cp = self.make_last_date(&appearance.time_format);
+                let label_dots = labels
+                    .of(&self.label_key())
+                    .fold(row![].spacing(2), |row, label| row.push(label_dot(label)));
+                let name = self.contact.select_name();
+                let name_cp: Element<_> = match search_match {
+                    Some(search_match) if search_match.text == name => {
+                        highlighted_text(&name, &search_match.positions, 24)
+                    }
+                    _ => text(&name).size(24).into(),
+                };
                 let card_top_row = container(
-                    row![text(self.contact.select_name()).size(24), last_date_cp,].spacing(5),
+                    row![name_cp, label_dots, last_date_cp,]
+                        .align_items(alignment::Alignment::Center)
+                        .spacing(5),
                 )
                 .width(Length::Fill);
 
-                let card_bottom_row = iced_lazy::responsive(|size| {
-                    // --- BOTTOM ROW ---
-                    let content = &self.chat_info.last_message;
-                    let left_pixels = size.width - NOTIFICATION_COUNT_WIDTH - 5.0; //spacing;
-                    let pixel_p_char = 8.0; // 8px = 1 char
-                    let taker = (left_pixels / pixel_p_char).floor() as usize;
-                    let content = if taker > content.len() {
-                        content.to_owned()
-                    } else {
-                        let truncated = content.graphemes(true).take(taker).collect::<String>();
-                        format!("{}...", &truncated)
-                    };
-                    let last_message_cp = container(text(&content).size(18.0)).width(Length::Fill);
-
-                    container(
-                        row![last_message_cp, self.make_notifications()]
-                            .align_items(alignment::Alignment::Center)
-                            .spacing(5),
-                    )
-                    .width(Length::Fill)
-                    .into()
-                });
+                let card_bottom_row: Element<_> = match search_match {
+                    // matched another field than the name, shown instead of the last message
+                    Some(search_match) if search_match.text != name => row![
+                        text(format!("{}: ", search_match.field))
+                            .size(16)
+                            .style(style::Text::Placeholder),
+                        highlighted_text(&search_match.text, &search_match.positions, 16)
+                    ]
+                    .align_items(alignment::Alignment::Center)
+                    .into(),
+                    _ => self.make_last_message(),
+                };
 
                 let expanded_card = column![card_top_row, card_bottom_row].width(Length::Fill);
 
-                row![pic_container, expanded_card,]
+                row![avatar, expanded_card,]
                     .width(Length::Fill)
                     .spacing(2)
                     .into()
@@ -155,6 +187,9 @@ impl ChatContact {
                 card_style = style::Button::ActiveContactCard;
             }
         }
+        if selected {
+            card_style = style::Button::SelectedContactCard;
+        }
 
         button(btn_content)
             .width(Length::Fill)
@@ -164,21 +199,31 @@ impl ChatContact {
             .into()
     }
 
-    fn make_last_date(&self) -> Element<'_, MessageWrapper> {
+    fn make_last_message(&self) -> Element<'_, MessageWrapper> {
+        iced_lazy::responsive(|size| {
+            let content = &self.chat_info.last_message;
+            let left_pixels = size.width;
+            let pixel_p_char = 8.0; // 8px = 1 char
+            let taker = (left_pixels / pixel_p_char).floor() as usize;
+            let content = if taker > content.len() {
+                content.to_owned()
+            } else {
+                let truncated = content.graphemes(true).take(taker).collect::<String>();
+                format!("{}...", &truncated)
+            };
+            container(text(&content).size(18.0))
+                .width(Length::Fill)
+                .into()
+        })
+        .into()
+    }
+
+    fn make_last_date(&self, format: &TimeFormatConfig) -> Element<'_, MessageWrapper> {
         let Some(date) = &self.chat_info.last_message_time else {
             return text("").into();
         };
 
-        let local_day = from_naive_utc_to_local(*date);
-        let local_now = from_naive_utc_to_local(Utc::now().naive_utc());
-        let date_format = if local_day.day() == local_now.day() {
-            "%H:%M"
-        } else {
-            // TODO: get local system language
-            // settings menu to change it
-            YMD_FORMAT
-        };
-        container(text(&local_day.format(date_format)).size(18.0))
+        container(text(time_format::short(*date, format)).size(18.0))
             .align_x(alignment::Horizontal::Right)
             .width(Length::Fill)
             .into()
@@ -186,7 +231,26 @@ impl ChatContact {
 
     pub fn new_message(&mut self, chat_message: ChatMessage) {
         self.update_headers(chat_message);
-        self.chat_info.add();
+        if !self.is_muted() {
+            self.chat_info.add();
+        }
+    }
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+    fn is_muted(&self) -> bool {
+        match self.label_notify {
+            LabelNotify::Always => false,
+            LabelNotify::Muted => true,
+            LabelNotify::Default => self.muted,
+        }
+    }
+    pub fn update_labels(&mut self, labels: &Labels) {
+        self.label_notify = labels.notify(&self.label_key());
+    }
+    /// Key used to assign labels to the conversation
+    pub fn label_key(&self) -> String {
+        self.contact.pubkey().to_string()
     }
     pub fn update_headers(&mut self, chat_message: ChatMessage) {
         self.chat_info.update_headers(&chat_message);
@@ -194,12 +258,23 @@ impl ChatContact {
     pub fn reset_unseen(&mut self) {
         self.chat_info.unseen_messages = 0;
     }
+    pub fn has_unseen(&self) -> bool {
+        self.chat_info.unseen_messages > 0
+    }
     pub fn update_chat_info(&mut self, new_info: ChatInfo) {
         self.chat_info.update(new_info);
     }
+    /// Replaces the preview and unread count even with older ones, after a clear
+    pub fn set_chat_info(&mut self, new_info: ChatInfo) {
+        self.chat_info = new_info;
+    }
     pub fn update_image(&mut self, image: ImageDownloaded) {
         let path = image.sized_image(ImageSize::Small);
         self.profile_img_handle = Handle::from_path(path);
+        self.accent = image.accent();
+    }
+    pub fn accent(&self) -> Option<Color> {
+        self.accent
     }
     pub fn update_contact(
         &mut self,
@@ -207,6 +282,7 @@ impl ChatContact {
         conn: &mut BackEndConnection,
     ) -> Result<(), BackendClosed> {
         self.profile_img_handle = db_contact.profile_image(ImageSize::Small, conn)?;
+        self.accent = db_contact.profile_accent();
         self.contact = db_contact;
         Ok(())
     }
@@ -217,21 +293,54 @@ impl ChatContact {
         self.mode = CardMode::Full;
     }
 
-    fn make_notifications<'a>(&self) -> Element<'a, MessageWrapper> {
+    /// Profile picture with the unread count, mention and muted badges.
+    /// There is no presence yet, so no online ring
+    fn make_avatar<'a>(
+        &self,
+        pic: impl Into<Element<'a, MessageWrapper>>,
+    ) -> Element<'a, MessageWrapper> {
+        let muted = self.is_muted();
+        BadgeOverlay::new(pic)
+            .badge_maybe(Corner::TopRight, self.make_unseen_badge())
+            .badge_maybe(
+                Corner::TopLeft,
+                self.mentioned.then(|| {
+                    solid_circle_icon()
+                        .size(MENTION_DOT_SIZE)
+                        .style(style::Text::Danger)
+                }),
+            )
+            .badge_maybe(
+                Corner::BottomRight,
+                muted.then(|| {
+                    container(bell_slash_icon().size(MUTED_ICON_SIZE))
+                        .padding(2)
+                        .style(style::Container::Frame)
+                }),
+            )
+            .into()
+    }
+
+    /// Muted conversations show the muted badge instead
+    fn make_unseen_badge<'a>(&self) -> Option<Element<'a, MessageWrapper>> {
+        if self.is_muted() {
+            return None;
+        }
         let count_txt = match self.chat_info.unseen_messages {
-            0 => return text("").into(),
+            0 => return None,
             1..=99 => self.chat_info.unseen_messages.to_string(),
             _ => "99+".into(),
         };
-
-        container(
-            button(text(count_txt).size(16))
-                .padding([2, 4])
-                .style(style::Button::Notification),
+        Some(
+            button(text(count_txt).size(14))
+                .padding([0, 4])
+                .style(style::Button::Notification)
+                .into(),
         )
-        .width(NOTIFICATION_COUNT_WIDTH)
-        .align_x(alignment::Horizontal::Right)
-        .into()
+    }
+
+    pub fn set_mentioned(&mut self, mentioned: bool) {
+        self.mentioned = mentioned;
     }
 
     pub(crate) fn height(&self) -> f32 {
@@ -244,4 +353,5 @@ impl ChatContact {
 }
 
 pub(crate) const CARD_HEIGHT: f32 = 80.0;
-const NOTIFICATION_COUNT_WIDTH: f32 = 30.0;
+const MENTION_DOT_SIZE: u16 = 12;
+const MUTED_ICON_SIZE: u16 = 12;
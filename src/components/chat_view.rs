@@ -1,15 +1,31 @@
 use crate::components::chat_contact::ChatContact;
+use crate::components::hover_card::{self, HoverCards};
+use crate::components::label_chip::label_chip;
 use crate::components::{common_scrollable, Responsive};
+use crate::config::{Appearance, SendKey, TimeFormatConfig};
 use crate::consts::YMD_FORMAT;
-use crate::icon::{dots_vertical_icon, file_icon_regular, search_icon, send_icon};
+use crate::db::{DbTemplate, DbTranslation, Labels};
+use crate::icon::{
+    delete_icon, dots_vertical_icon, download_icon, edit_icon, file_icon_regular, folder_open_icon,
+    search_icon, send_icon, xmark_icon,
+};
+use crate::net::pdf_export::PdfExportStatus;
+use crate::net::uploads::{UploadState, UploadStatus};
 use crate::style;
 use crate::types::chat_message::{self, ChatMessage};
-use crate::utils::from_naive_utc_to_local;
+use crate::types::markdown::{self, Block};
+use crate::types::slash_command::{suggestions, SlashCommand};
+use crate::types::{LightningAddress, NostrLink};
+use crate::utils::{from_naive_utc_to_local, time_format};
 use crate::widget::{Button, Container, Element};
 use chrono::{Datelike, NaiveDateTime};
-use iced::widget::{button, column, container, row, scrollable, text, text_input};
-use iced::{Alignment, Length, Point, Size};
+use iced::widget::{
+    button, checkbox, column, container, row, scrollable, text, text_input, tooltip, Space,
+};
+use iced::{keyboard, subscription, Alignment, Color, Event, Length, Point, Size, Subscription};
 use nostr::secp256k1::XOnlyPublicKey;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -19,136 +35,869 @@ pub enum Message {
     Scrolled(scrollable::RelativeOffset),
     OpenContactProfile,
     ChatRightClick(ChatMessage, Point),
+    /// Left button went down on a message
+    ChatPress(ChatMessage),
+    ChatRelease(ChatMessage),
+    SelectionCopyPressed,
+    SelectionForwardPressed,
+    SelectionDeletePressed,
+    SelectionCancelPressed,
     ChannelOpenModalPressed,
     ChannelSearchPressed,
     ChannelMenuPressed,
     ChannelUserNamePressed(XOnlyPublicKey),
+    ToggleAutoTranslate,
+    ToggleAutoSaveAttachments,
+    TemplatesPressed,
+    TemplateSelected(DbTemplate),
+    CommandSelected(&'static str),
+    /// Label id, assigned to or removed from the conversation
+    ToggleLabel(i64),
+    PdfExportPressed,
+    PdfIncludeImagesToggled(bool),
+    /// Whether images are embedded
+    PdfExportConfirm(bool),
+    ArchiveExportPressed,
+    ClearHistoryPressed,
+    ClearKeepStarredToggled(bool),
+    /// Whether starred messages are kept
+    ClearHistoryConfirm(bool),
+    FetchOlderHistory,
+    TipPressed,
+    ModifiersChanged(keyboard::Modifiers),
+    TogglePreview,
+    NostrLinkPressed(NostrLink),
+    HashtagPressed(String),
+    /// Event id of the message and the reaction
+    ReactionPressed(i64, String),
+    ResendDuplicatePressed,
+    DiscardDuplicatePressed,
+    /// Second copy of a message sent twice
+    DeleteDuplicatePressed(ChatMessage),
+    /// Shift was held, image metadata is kept for this file
+    AttachPressed(bool),
+    /// Upload id
+    CancelUploadPressed(u64),
+    ResumeUploadPressed(u64),
+    /// Hides an upload that can't be tried again
+    DismissUploadPressed,
+    HoverCard(hover_card::Message),
+}
+
+/// Print / PDF export bar under the navbar
+enum PdfExportPanel {
+    Options { include_images: bool },
+    Exporting { done: usize, total: usize },
+    Finished(String),
+}
+
+/// Older messages fetched from the relays, above the first message
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HistoryFetch {
+    Idle,
+    /// Messages since this date were requested
+    Fetching(NaiveDateTime),
+    Complete,
+}
+
+/// Lightning address of the contact, `valid` is None until checked
+struct LightningTip {
+    address: LightningAddress,
+    valid: Option<bool>,
 }
 
 pub struct ChatView {
     dm_msg_input: String,
+    show_templates: bool,
+    command_error: Option<String>,
+    pdf_export: Option<PdfExportPanel>,
+    history: HistoryFetch,
+    lightning: Option<LightningTip>,
+    send_key: SendKey,
+    /// Enter adds a new line instead of sending
+    shift_held: bool,
+    /// Enter sends when the send key is Ctrl+Enter
+    ctrl_held: bool,
+    /// Composer shows the formatted message
+    preview: bool,
+    /// Presses right after a send are ignored
+    last_send: Option<Instant>,
+    /// Message the backend held as a duplicate, until the user decides
+    duplicate: Option<String>,
+    /// Confirmation bar to clear the conversation, with the keep starred option
+    clear_history: Option<bool>,
+    /// Attachment on its way to the media server, shown above the input
+    upload: Option<UploadStatus>,
 }
 impl ChatView {
     pub fn new() -> Self {
         Self {
             dm_msg_input: "".into(),
+            send_key: SendKey::default(),
+            shift_held: false,
+            ctrl_held: false,
+            preview: false,
+            show_templates: false,
+            command_error: None,
+            pdf_export: None,
+            history: HistoryFetch::Idle,
+            lightning: None,
+            last_send: None,
+            duplicate: None,
+            clear_history: None,
+            upload: None,
+        }
+    }
+    /// Modifiers state for the composer
+    pub fn subscription() -> Subscription<Message> {
+        subscription::events_with(|event, _status| match event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Message::ModifiersChanged(modifiers))
+            }
+            _ => None,
+        })
+    }
+    pub fn set_modifiers(&mut self, modifiers: keyboard::Modifiers) {
+        self.shift_held = modifiers.shift();
+        self.ctrl_held = modifiers.command();
+    }
+    /// Ctrl, or Cmd on macOS, is held
+    pub fn ctrl_held(&self) -> bool {
+        self.ctrl_held
+    }
+    pub fn set_send_key(&mut self, send_key: SendKey) {
+        self.send_key = send_key;
+    }
+    /// Whether Enter with the held modifiers sends the message
+    fn enter_sends(&self) -> bool {
+        match self.send_key {
+            SendKey::Enter => !self.shift_held,
+            SendKey::CtrlEnter => self.ctrl_held,
+        }
+    }
+    pub fn toggle_preview(&mut self) {
+        self.preview = !self.preview;
+    }
+    pub fn set_lightning(&mut self, address: Option<LightningAddress>) {
+        self.lightning = address.map(|address| LightningTip {
+            address,
+            valid: None,
+        });
+    }
+    pub fn lightning_checked(&mut self, address: &str, valid: bool) {
+        if let Some(tip) = &mut self.lightning {
+            if tip.address.as_str() == address {
+                tip.valid = Some(valid);
+            }
+        }
+    }
+    pub fn history_fetching(&mut self, since: NaiveDateTime) {
+        self.history = HistoryFetch::Fetching(since);
+    }
+    pub fn history_fetched(&mut self) {
+        self.history = HistoryFetch::Idle;
+    }
+    pub fn history_complete(&mut self) {
+        self.history = HistoryFetch::Complete;
+    }
+    /// Another conversation was opened
+    pub fn reset_history(&mut self) {
+        self.history = HistoryFetch::Idle;
+        self.duplicate = None;
+        self.clear_history = None;
+        self.upload = None;
+    }
+    pub fn toggle_clear_history(&mut self) {
+        self.clear_history = match self.clear_history {
+            Some(_) => None,
+            None => Some(true),
+        };
+    }
+    pub fn set_clear_keep_starred(&mut self, keep_starred: bool) {
+        if self.clear_history.is_some() {
+            self.clear_history = Some(keep_starred);
+        }
+    }
+    pub fn close_clear_history(&mut self) {
+        self.clear_history = None;
+    }
+    /// False for a double click or a repeated Enter, the message was already sent
+    pub fn debounce_send(&mut self, now: Instant) -> bool {
+        if let Some(last_send) = self.last_send {
+            if now.saturating_duration_since(last_send) < SEND_DEBOUNCE {
+                return false;
+            }
+        }
+        self.last_send = Some(now);
+        true
+    }
+    pub fn confirm_duplicate(&mut self, content: String) {
+        self.duplicate = Some(content);
+    }
+    pub fn take_duplicate(&mut self) -> Option<String> {
+        self.duplicate.take()
+    }
+    /// The address of an uploaded file is added to the message.
+    /// Returns true when the input changed
+    pub fn upload_status(&mut self, status: UploadStatus) -> bool {
+        match &status.state {
+            UploadState::Uploaded(url) => {
+                let input = self.dm_msg_input.trim_end();
+                self.dm_msg_input = match input.is_empty() {
+                    true => url.to_owned(),
+                    false => format!("{} {}", input, url),
+                };
+                self.upload = None;
+                true
+            }
+            UploadState::Cancelled => {
+                self.upload = None;
+                false
+            }
+            _ => {
+                self.upload = Some(status);
+                false
+            }
+        }
+    }
+    pub fn dismiss_upload(&mut self) {
+        self.upload = None;
+    }
+    pub fn dm_msg_input(&self) -> &str {
+        &self.dm_msg_input
+    }
+    /// Opens or closes the export bar, an export in progress is kept on screen
+    pub fn toggle_pdf_export(&mut self) {
+        self.pdf_export = match self.pdf_export {
+            Some(PdfExportPanel::Exporting { .. }) => return,
+            Some(_) => None,
+            None => Some(PdfExportPanel::Options {
+                include_images: false,
+            }),
+        };
+    }
+    pub fn set_pdf_include_images(&mut self, include_images: bool) {
+        if let Some(PdfExportPanel::Options { .. }) = self.pdf_export {
+            self.pdf_export = Some(PdfExportPanel::Options { include_images });
+        }
+    }
+    pub fn pdf_export_status(&mut self, status: PdfExportStatus) {
+        self.pdf_export = Some(match status {
+            PdfExportStatus::Progress { done, total } => PdfExportPanel::Exporting { done, total },
+            PdfExportStatus::Saved { path, unprintable } if unprintable > 0 => {
+                PdfExportPanel::Finished(format!(
+                    "Saved to {}, but {} characters the PDF fonts can't show are printed as \"?\"",
+                    path.display(),
+                    unprintable
+                ))
+            }
+            PdfExportStatus::Saved { path, .. } => {
+                PdfExportPanel::Finished(format!("Saved to {}", path.display()))
+            }
+            PdfExportStatus::Failed(e) => PdfExportPanel::Finished(format!("Export failed: {}", e)),
+        });
+    }
+    /// No file was picked
+    pub fn pdf_export_cancelled(&mut self) {
+        if self.pdf_export.is_some() {
+            self.pdf_export = Some(PdfExportPanel::Options {
+                include_images: false,
+            });
         }
     }
     pub fn update_dm_msg(&mut self, text: String) {
+        if text.is_empty() {
+            self.preview = false;
+        }
         self.dm_msg_input = text;
+        self.command_error = None;
+    }
+    pub fn set_command_error(&mut self, error: String) {
+        self.command_error = Some(error);
+    }
+    /// Fills the input with `/name ` so the arguments can be typed,
+    /// returns where the cursor should be placed
+    pub fn select_command(&mut self, name: &str) -> usize {
+        self.update_dm_msg(format!("/{} ", name));
+        self.dm_msg_input.chars().count()
+    }
+    pub fn toggle_templates(&mut self) {
+        self.show_templates = !self.show_templates;
+    }
+    /// Template named by a `/name` input
+    pub fn typed_template<'a>(&self, templates: &'a [DbTemplate]) -> Option<&'a DbTemplate> {
+        let name = template_query(&self.dm_msg_input)?;
+        templates.iter().find(|t| t.name == name)
+    }
+    /// Replaces a typed `/name` or appends to the input,
+    /// returns where the cursor should be placed
+    pub fn insert_template(&mut self, template: &DbTemplate, contact_name: &str) -> usize {
+        let (content, cursor) = template.expand(contact_name);
+        let prefix = match template_query(&self.dm_msg_input) {
+            Some(_) => "".to_owned(),
+            None if self.dm_msg_input.is_empty() => "".to_owned(),
+            None => format!("{} ", self.dm_msg_input.trim_end()),
+        };
+        self.dm_msg_input = format!("{}{}", prefix, content);
+        self.show_templates = false;
+        prefix.chars().count() + cursor
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn channel_view<'a>(
         &'a self,
         scrollable_id: &'a scrollable::Id,
         chat_input_id: &'a text_input::Id,
         messages: &'a [ChatMessage],
+        accents: &'a HashMap<XOnlyPublicKey, Color>,
         name: &str,
         members: i32,
         disable_input: bool,
+        labels: &'a Labels,
+        conversation: &str,
+        hover_cards: &'a HoverCards,
+        appearance: Appearance,
     ) -> Element<'a, Message> {
-        let chat_messages = create_channel_content(scrollable_id, messages);
-        let mut message_input =
-            text_input("Write a message...", &self.dm_msg_input).id(chat_input_id.clone());
-        let mut send_btn =
-            button(send_icon().style(style::Text::Primary)).style(style::Button::Invisible);
-
-        if !disable_input {
-            message_input = message_input
-                .on_submit(Message::DMSentPress(self.dm_msg_input.clone()))
-                .on_input(Message::DMNMessageChange);
-            send_btn = send_btn.on_press(Message::DMSentPress(self.dm_msg_input.clone()));
-        }
-
-        let msg_input_row = container(row![message_input, send_btn].spacing(5))
-            .style(style::Container::Default)
-            .height(CHAT_INPUT_HEIGHT)
-            .padding([10, 5]);
+        let chat_messages =
+            create_channel_content(scrollable_id, messages, accents, hover_cards, appearance);
+        let msg_input_row = self.composer(chat_input_id, None, !disable_input);
+        // templates are only offered in direct messages
+        let picker = self.composer_popup(&[], name);
 
         container(column![
             channel_navbar(name, members),
+            self.pdf_export_bar(false),
+            labels_bar(labels, conversation),
             chat_messages,
+            picker,
+            self.duplicate_bar(),
             msg_input_row
         ])
         .width(Length::Fill)
         .into()
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn view<'a>(
         &'a self,
         scrollable_id: &'a scrollable::Id,
         chat_input_id: &'a text_input::Id,
         messages: &'a [ChatMessage],
         active_chat: Option<&'a ChatContact>,
+        translations: &'a HashMap<i64, DbTranslation>,
+        starred: &'a HashSet<i64>,
+        auto_translate: bool,
+        auto_save_attachments: bool,
+        templates: &'a [DbTemplate],
+        labels: &'a Labels,
+        selection: Option<&'a HashSet<i64>>,
+        hover_cards: &'a HoverCards,
+        appearance: Appearance,
     ) -> Element<'a, Message> {
         let Some(active_contact) = active_chat else {
             return container(text("Select a chat to start messaging"))
-            .center_x()
-            .center_y()
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .style(style::Container::Background)
-            .into();
+                .center_x()
+                .center_y()
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(style::Container::Background)
+                .into();
         };
 
-        let chat_messages = create_chat_content(scrollable_id, messages);
-        let message_input = text_input("Write a message...", &self.dm_msg_input)
-            .on_submit(Message::DMSentPress(self.dm_msg_input.clone()))
-            .on_input(Message::DMNMessageChange)
-            .id(chat_input_id.clone());
-        let send_btn = button(send_icon().style(style::Text::Primary))
+        let chat_messages = create_chat_content(
+            scrollable_id,
+            messages,
+            translations,
+            starred,
+            selection,
+            active_contact.accent(),
+            self.history,
+            hover_cards,
+            appearance,
+        );
+        let templates_btn = button(edit_icon().style(style::Text::Primary))
             .style(style::Button::Invisible)
-            .on_press(Message::DMSentPress(self.dm_msg_input.clone()));
-        let msg_input_row = container(row![message_input, send_btn].spacing(5))
-            .style(style::Container::Default)
-            .height(CHAT_INPUT_HEIGHT)
-            .padding([10, 5]);
+            .on_press(Message::TemplatesPressed);
+        let msg_input_row = match selection {
+            Some(selected) => selection_bar(selected.len()),
+            None => self.composer(chat_input_id, Some(templates_btn.into()), true),
+        };
+        let picker = self.composer_popup(templates, &active_contact.contact.select_name());
         // Todo: add/remove user button
         // if user is unkown
         let add_or_remove_user = text("");
 
         container(column![
-            chat_navbar(active_contact),
+            chat_navbar(
+                active_contact,
+                auto_translate,
+                auto_save_attachments,
+                self.lightning.as_ref(),
+                hover_cards,
+            ),
+            self.pdf_export_bar(true),
+            self.clear_history_bar(),
+            labels_bar(labels, &active_contact.label_key()),
             add_or_remove_user,
             chat_messages,
+            picker,
+            self.duplicate_bar(),
             msg_input_row
         ])
         .width(Length::Fill)
         .into()
     }
+
+    /// Lines above the one being typed, the preview and the character counter.
+    /// Enter sends or adds a line depending on the send key,
+    /// Backspace on an empty line joins it to the previous one
+    fn composer<'a>(
+        &'a self,
+        chat_input_id: &'a text_input::Id,
+        leading: Option<Element<'a, Message>>,
+        enabled: bool,
+    ) -> Element<'a, Message> {
+        let (head, line) = match self.dm_msg_input.rfind('\n') {
+            Some(idx) => self.dm_msg_input.split_at(idx + 1),
+            None => ("", self.dm_msg_input.as_str()),
+        };
+
+        let placeholder = format!("Write a message... ({})", self.send_key.hint());
+        let mut message_input = text_input(&placeholder, line).id(chat_input_id.clone());
+        let mut send_btn =
+            button(send_icon().style(style::Text::Primary)).style(style::Button::Invisible);
+        if enabled {
+            let head_1 = head.to_owned();
+            let line_is_empty = line.is_empty();
+            let submit = if self.enter_sends() {
+                Message::DMSentPress(self.dm_msg_input.clone())
+            } else {
+                Message::DMNMessageChange(format!("{}\n", self.dm_msg_input))
+            };
+            message_input = message_input.on_submit(submit).on_input(move |new_line| {
+                if line_is_empty && new_line.is_empty() {
+                    // backspace on an empty line
+                    Message::DMNMessageChange(head_1.trim_end_matches('\n').to_owned())
+                } else {
+                    Message::DMNMessageChange(format!("{}{}", head_1, new_line))
+                }
+            });
+            send_btn = send_btn.on_press(Message::DMSentPress(self.dm_msg_input.clone()));
+        }
+        let preview_btn = button(text(if self.preview { "Edit" } else { "Preview" }).size(14))
+            .style(style::Button::Invisible)
+            .on_press(Message::TogglePreview);
+
+        let mut input_row = row![].spacing(5).align_items(Alignment::Center);
+        if let Some(leading) = leading {
+            input_row = input_row.push(leading);
+        }
+        if enabled {
+            // one attachment at a time, the next waits for the bar to clear
+            let mut attach_btn = button(file_icon_regular().style(style::Text::Primary))
+                .style(style::Button::Invisible);
+            if self.upload.is_none() {
+                attach_btn = attach_btn.on_press(Message::AttachPressed(self.shift_held));
+            }
+            input_row = input_row.push(
+                tooltip(
+                    attach_btn,
+                    "Attach a file, hold Shift to keep the image's metadata",
+                    tooltip::Position::Top,
+                )
+                .style(style::Container::TooltipBg),
+            );
+        }
+        let input_row = input_row
+            .push(message_input)
+            .push(preview_btn)
+            .push(send_btn);
+
+        let above: Element<_> = if self.preview && !self.dm_msg_input.is_empty() {
+            container(common_scrollable(markdown_preview(&self.dm_msg_input)))
+                .max_height(COMPOSER_MAX_HEIGHT)
+                .width(Length::Fill)
+                .padding(5)
+                .style(style::Container::Frame)
+                .into()
+        } else if !head.is_empty() {
+            container(common_scrollable(text(head.trim_end_matches('\n'))))
+                .max_height(COMPOSER_MAX_HEIGHT)
+                .width(Length::Fill)
+                .padding([0, 5])
+                .into()
+        } else {
+            text("").into()
+        };
+
+        let count = self.dm_msg_input.chars().count();
+        let counter: Element<_> = if count > 0 {
+            let style = if count >= MAX_CONTENT_LENGTH * CONTENT_WARN_PERCENT / 100 {
+                style::Text::Danger
+            } else {
+                style::Text::Placeholder
+            };
+            row![
+                Space::with_width(Length::Fill),
+                text(format!("{}/{}", count, MAX_CONTENT_LENGTH))
+                    .size(12)
+                    .style(style)
+            ]
+            .into()
+        } else {
+            text("").into()
+        };
+
+        container(column![self.upload_bar(), above, input_row, counter].spacing(2))
+            .style(style::Container::Default)
+            .padding([10, 5])
+            .into()
+    }
+
+    /// `with_archive` offers the zip of the encrypted DMs besides the PDF
+    fn pdf_export_bar(&self, with_archive: bool) -> Element<'_, Message> {
+        let Some(panel) = &self.pdf_export else {
+            return text("").into();
+        };
+        let content: Element<_> = match panel {
+            PdfExportPanel::Options { include_images } => {
+                let archive_btn: Element<_> = if with_archive {
+                    tooltip(
+                        button(text("Archive (zip)").size(14))
+                            .style(style::Button::Bordered)
+                            .on_press(Message::ArchiveExportPressed),
+                        "Signed encrypted messages, their transcript and a manifest to verify them",
+                        tooltip::Position::Bottom,
+                    )
+                    .style(style::Container::TooltipBg)
+                    .into()
+                } else {
+                    text("").into()
+                };
+                row![
+                    text("Print / PDF export").width(Length::Fill),
+                    checkbox(
+                        "Include images",
+                        *include_images,
+                        Message::PdfIncludeImagesToggled
+                    ),
+                    button(text("Export").size(14))
+                        .style(style::Button::Primary)
+                        .on_press(Message::PdfExportConfirm(*include_images)),
+                    archive_btn
+                ]
+                .align_items(Alignment::Center)
+                .spacing(10)
+                .into()
+            }
+            PdfExportPanel::Exporting { done, total } => {
+                text(format!("Exporting... {} of {} messages", done, total))
+                    .style(style::Text::Placeholder)
+                    .into()
+            }
+            PdfExportPanel::Finished(result) => text(result).into(),
+        };
+        let close_btn: Element<_> = match panel {
+            PdfExportPanel::Exporting { .. } => text("").into(),
+            _ => button(xmark_icon().size(14))
+                .style(style::Button::Invisible)
+                .on_press(Message::PdfExportPressed)
+                .into(),
+        };
+        container(
+            row![container(content).width(Length::Fill), close_btn]
+                .align_items(Alignment::Center)
+                .spacing(10),
+        )
+        .padding([5, 10])
+        .width(Length::Fill)
+        .style(style::Container::Foreground)
+        .into()
+    }
+
+    fn clear_history_bar(&self) -> Element<'_, Message> {
+        let Some(keep_starred) = self.clear_history else {
+            return text("").into();
+        };
+        container(
+            row![
+                text("Clear the history of this conversation on this device?").width(Length::Fill),
+                checkbox(
+                    "Keep starred",
+                    keep_starred,
+                    Message::ClearKeepStarredToggled
+                ),
+                button(text("Cancel").size(14))
+                    .style(style::Button::MenuBtn)
+                    .on_press(Message::ClearHistoryPressed),
+                button(text("Clear history").size(14))
+                    .style(style::Button::Danger)
+                    .on_press(Message::ClearHistoryConfirm(keep_starred)),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        )
+        .padding([5, 10])
+        .width(Length::Fill)
+        .style(style::Container::Foreground)
+        .into()
+    }
+
+    /// Progress of the attachment, with cancel and, after a failure, resume
+    fn upload_bar(&self) -> Element<'_, Message> {
+        let Some(upload) = &self.upload else {
+            return text("").into();
+        };
+        let mut content = row![].align_items(Alignment::Center).spacing(10);
+        match &upload.state {
+            UploadState::Sending { sent, total } => {
+                let progress = match total {
+                    0 => "Preparing...".to_owned(),
+                    total => format!("{}%", sent * 100 / total),
+                };
+                content = content
+                    .push(text(&upload.name).size(14).width(Length::Fill))
+                    .push(text(progress).size(14).style(style::Text::Placeholder))
+                    .push(
+                        button(text("Cancel").size(14))
+                            .style(style::Button::MenuBtn)
+                            .on_press(Message::CancelUploadPressed(upload.id)),
+                    );
+            }
+            UploadState::Failed { error, resumable } => {
+                content = content.push(
+                    text(format!("{}: {}", upload.name, error))
+                        .size(14)
+                        .style(style::Text::Danger)
+                        .width(Length::Fill),
+                );
+                if *resumable {
+                    content = content
+                        .push(
+                            button(text("Cancel").size(14))
+                                .style(style::Button::MenuBtn)
+                                .on_press(Message::CancelUploadPressed(upload.id)),
+                        )
+                        .push(
+                            button(text("Resume").size(14))
+                                .style(style::Button::Primary)
+                                .on_press(Message::ResumeUploadPressed(upload.id)),
+                        );
+                } else {
+                    content = content.push(
+                        button(xmark_icon().size(14))
+                            .style(style::Button::Invisible)
+                            .on_press(Message::DismissUploadPressed),
+                    );
+                }
+            }
+            UploadState::Uploaded(_) | UploadState::Cancelled => (),
+        }
+        container(content)
+            .padding([5, 10])
+            .width(Length::Fill)
+            .style(style::Container::Foreground)
+            .into()
+    }
+
+    fn duplicate_bar(&self) -> Element<'_, Message> {
+        if self.duplicate.is_none() {
+            return text("").into();
+        }
+        container(
+            row![
+                text("You sent the same message a moment ago. Send it again?").width(Length::Fill),
+                button(text("Discard").size(14))
+                    .style(style::Button::MenuBtn)
+                    .on_press(Message::DiscardDuplicatePressed),
+                button(text("Send again").size(14))
+                    .style(style::Button::Primary)
+                    .on_press(Message::ResendDuplicatePressed)
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        )
+        .padding([5, 10])
+        .width(Length::Fill)
+        .style(style::Container::Foreground)
+        .into()
+    }
+
+    /// Command errors, command suggestions and matching templates
+    fn composer_popup<'a>(
+        &self,
+        templates: &'a [DbTemplate],
+        contact_name: &str,
+    ) -> Element<'a, Message> {
+        if let Some(error) = &self.command_error {
+            return container(text(error).style(style::Text::Danger))
+                .padding(10)
+                .width(Length::Fill)
+                .style(style::Container::Foreground)
+                .into();
+        }
+
+        let commands = suggestions(&self.dm_msg_input);
+        let query = template_query(&self.dm_msg_input);
+        if !self.show_templates && query.is_none() && commands.is_empty() {
+            return text("").into();
+        }
+        let matches: Vec<_> = templates
+            .iter()
+            .filter(|t| query.map_or(true, |q| t.name.starts_with(q)))
+            .collect();
+        if matches.is_empty() && commands.is_empty() {
+            if !self.show_templates {
+                return text("").into();
+            }
+            return container(
+                text("No templates, add them in Settings > Templates")
+                    .style(style::Text::Placeholder),
+            )
+            .padding(10)
+            .width(Length::Fill)
+            .style(style::Container::Foreground)
+            .into();
+        }
+
+        let entries = commands
+            .into_iter()
+            .fold(column![], |col, command| col.push(command_row(command)));
+        let entries = matches.into_iter().fold(entries, |col, template| {
+            let (preview, _) = template.expand(contact_name);
+            col.push(
+                button(
+                    row![
+                        text(format!("/{}", template.name)).width(TEMPLATE_NAME_WIDTH),
+                        text(preview).style(style::Text::Placeholder)
+                    ]
+                    .spacing(10),
+                )
+                .width(Length::Fill)
+                .style(style::Button::ContextMenuButton)
+                .on_press(Message::TemplateSelected(template.to_owned())),
+            )
+        });
+        container(common_scrollable(entries))
+            .max_height(TEMPLATE_PICKER_MAX_HEIGHT)
+            .width(Length::Fill)
+            .padding(5)
+            .style(style::Container::Foreground)
+            .into()
+    }
+}
+
+fn markdown_preview<'a>(content: &str) -> Element<'a, Message> {
+    markdown::parse(content)
+        .into_iter()
+        .fold(column![].spacing(5), |col, block| {
+            let block: Element<_> = match block {
+                Block::Heading(level, title) => text(title)
+                    .size(HEADING_SIZE.saturating_sub(level as u16 * 2))
+                    .into(),
+                Block::Bullet(item) => row![text("•"), text(item)].spacing(5).into(),
+                Block::Quote(quote) => container(text(quote).style(style::Text::Placeholder))
+                    .padding([0, 0, 0, 10])
+                    .into(),
+                Block::Code(code) => container(text(code).size(14))
+                    .padding(5)
+                    .width(Length::Fill)
+                    .style(style::Container::Foreground)
+                    .into(),
+                Block::Paragraph(paragraph) => text(paragraph).into(),
+            };
+            col.push(block)
+        })
+        .into()
+}
+
+fn command_row<'a>(command: &'static SlashCommand) -> Element<'a, Message> {
+    button(
+        row![
+            text(format!("/{} {}", command.name, command.args)).width(TEMPLATE_NAME_WIDTH),
+            text(command.description).style(style::Text::Placeholder)
+        ]
+        .spacing(10),
+    )
+    .width(Length::Fill)
+    .style(style::Button::ContextMenuButton)
+    .on_press(Message::CommandSelected(command.name))
+    .into()
 }
 
+/// Text after `/` when the input is a single template command
+fn template_query(input: &str) -> Option<&str> {
+    input
+        .strip_prefix('/')
+        .filter(|q| !q.contains(char::is_whitespace))
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_chat_content<'a>(
     scrollable_id: &'a scrollable::Id,
     messages: &'a [ChatMessage],
+    translations: &'a HashMap<i64, DbTranslation>,
+    starred: &'a HashSet<i64>,
+    selection: Option<&'a HashSet<i64>>,
+    accent: Option<Color>,
+    history: HistoryFetch,
+    hover_cards: &'a HoverCards,
+    appearance: Appearance,
 ) -> Element<'a, Message> {
     let lazy = Responsive::new(move |_size| {
         if messages.is_empty() {
-            return container(text("No messages"))
-                .center_x()
-                .center_y()
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .style(style::Container::Background)
-                .into();
+            return container(
+                column![text("No messages"), history_row(history)]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+            )
+            .center_x()
+            .center_y()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(style::Container::Background)
+            .into();
         }
 
-        let mut col = column![];
+        let mut col = column![history_row(history)];
         let mut last_date: Option<NaiveDateTime> = None;
+        let mut previous_msg: Option<&ChatMessage> = None;
 
         for msg in messages {
             if let Some(msg_date) = msg.display_time() {
                 if let Some(last) = last_date {
                     if last.day() != msg_date.day() {
-                        col = col.push(chat_day_divider(*msg_date));
+                        col = col.push(chat_day_divider(*msg_date, &appearance.time_format));
                     }
                 } else {
-                    col = col.push(chat_day_divider(*msg_date));
+                    col = col.push(chat_day_divider(*msg_date, &appearance.time_format));
                 }
                 last_date = Some(*msg_date);
             }
 
-            let msg_view = msg.view(false).map(map_chat_msgs);
+            let translation = msg.event_id().and_then(|id| translations.get(&id));
+            let msg_view = msg
+                .view(false, translation, accent, hover_cards, appearance)
+                .map(map_chat_msgs);
+            let is_selected = msg
+                .event_id()
+                .zip(selection)
+                .map_or(false, |(id, selected)| selected.contains(&id));
 
-            col = col.push(msg_view);
+            if is_selected {
+                col = col.push(
+                    container(msg_view)
+                        .width(Length::Fill)
+                        .style(style::Container::Highlight),
+                );
+            } else {
+                col = col.push(msg_view);
+            }
+            if msg.event_id().map_or(false, |id| starred.contains(&id)) {
+                col = col.push(starred_row(msg));
+            }
+            if previous_msg.map_or(false, |previous| msg.is_duplicate_of(previous)) {
+                col = col.push(duplicate_row(msg));
+            }
+            previous_msg = Some(msg);
         }
 
         let scrollable = common_scrollable(col)
@@ -169,9 +918,92 @@ fn create_chat_content<'a>(
         .into()
 }
 
-fn chat_day_divider<Message: 'static>(date: NaiveDateTime) -> Element<'static, Message> {
-    let local_date = from_naive_utc_to_local(date);
-    let text_container = container(text(local_date.format(YMD_FORMAT).to_string()))
+/// Takes the place of the composer while messages are selected
+fn selection_bar<'a>(count: usize) -> Element<'a, Message> {
+    let action = |label: &'static str, message: Message| {
+        let btn = button(text(label).size(14)).style(style::Button::Bordered);
+        if count > 0 {
+            btn.on_press(message)
+        } else {
+            btn
+        }
+    };
+    container(
+        row![
+            text(format!("{} selected", count)).width(Length::Fill),
+            action("Copy", Message::SelectionCopyPressed),
+            action("Forward", Message::SelectionForwardPressed),
+            action("Delete", Message::SelectionDeletePressed).style(style::Button::Danger),
+            button(text("Cancel").size(14))
+                .style(style::Button::MenuBtn)
+                .on_press(Message::SelectionCancelPressed),
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding([10, 10])
+    .width(Length::Fill)
+    .style(style::Container::Default)
+    .into()
+}
+
+/// Under a starred message, on its side of the chat
+fn starred_row(msg: &ChatMessage) -> Element<'static, Message> {
+    let star = text("★ Starred").size(14).style(style::Text::Placeholder);
+    let row = if matches!(msg, ChatMessage::UserMessage(_)) {
+        row![Space::with_width(Length::Fill), star]
+    } else {
+        row![star, Space::with_width(Length::Fill)]
+    };
+    row.padding([0, 30]).into()
+}
+
+/// Under the second copy of a message sent twice
+fn duplicate_row(msg: &ChatMessage) -> Element<'static, Message> {
+    row![
+        Space::with_width(Length::Fill),
+        text("Sent twice").size(14).style(style::Text::Placeholder),
+        button(text("Delete duplicate").size(14))
+            .padding(0)
+            .style(style::Button::Link)
+            .on_press(Message::DeleteDuplicatePressed(msg.to_owned()))
+    ]
+    .align_items(Alignment::Center)
+    .spacing(10)
+    .padding([0, 30])
+    .into()
+}
+
+fn history_row(history: HistoryFetch) -> Element<'static, Message> {
+    let content: Element<_> = match history {
+        HistoryFetch::Idle => button(text("Fetch older history").size(14))
+            .style(style::Button::Bordered)
+            .on_press(Message::FetchOlderHistory)
+            .into(),
+        HistoryFetch::Fetching(since) => text(format!(
+            "Fetching messages since {}...",
+            from_naive_utc_to_local(since).format(YMD_FORMAT)
+        ))
+        .size(14)
+        .style(style::Text::Placeholder)
+        .into(),
+        HistoryFetch::Complete => text("The whole history is synced")
+            .size(14)
+            .style(style::Text::Placeholder)
+            .into(),
+    };
+    container(content)
+        .width(Length::Fill)
+        .center_x()
+        .padding([10, 0])
+        .into()
+}
+
+fn chat_day_divider<Message: 'static>(
+    date: NaiveDateTime,
+    format: &TimeFormatConfig,
+) -> Element<'static, Message> {
+    let text_container = container(text(time_format::date(date, format)))
         .style(style::Container::ChatDateDivider)
         .padding([5, 10]);
     container(text_container)
@@ -182,17 +1014,26 @@ fn chat_day_divider<Message: 'static>(date: NaiveDateTime) -> Element<'static, M
         .into()
 }
 
-fn chat_navbar(active_contact: &ChatContact) -> Container<'_, Message> {
+fn chat_navbar<'a>(
+    active_contact: &'a ChatContact,
+    auto_translate: bool,
+    auto_save_attachments: bool,
+    lightning: Option<&LightningTip>,
+    hover_cards: &'a HoverCards,
+) -> Container<'a, Message> {
     container(
-        row![header_details(active_contact), header_action_buttons()]
-            .spacing(5)
-            .width(Length::Fill),
+        row![
+            header_details(active_contact, hover_cards),
+            header_action_buttons(auto_translate, auto_save_attachments, lightning)
+        ]
+        .spacing(5)
+        .width(Length::Fill),
     )
     .height(NAVBAR_HEIGHT)
     .style(style::Container::Foreground)
 }
 
-fn header_details(chat: &ChatContact) -> Button<'_, Message> {
+fn header_details<'a>(chat: &'a ChatContact, hover_cards: &'a HoverCards) -> Element<'a, Message> {
     let local_message_date = chat
         .last_message_date()
         .map(from_naive_utc_to_local)
@@ -216,19 +1057,118 @@ fn header_details(chat: &ChatContact) -> Button<'_, Message> {
     } else {
         text(chat.contact.select_name()).size(20).into()
     };
+    let user_name = hover_cards.wrap(chat.contact.pubkey(), user_name, Message::HoverCard);
 
-    button(column![user_name, text(local_message_date).size(16)])
+    let details = button(column![user_name, text(local_message_date).size(16)])
         .padding([5, 0, 0, 5])
         .style(style::Button::Invisible)
         .on_press(Message::OpenContactProfile)
         .height(Length::Fill)
+        .width(Length::Fill);
+
+    // private notes are shown on hover
+    match chat.contact.get_notes() {
+        Some(notes) => tooltip(details, notes, tooltip::Position::Bottom)
+            .style(style::Container::TooltipBg)
+            .into(),
+        None => details.into(),
+    }
+}
+
+/// Every label, the assigned ones highlighted
+fn labels_bar<'a>(labels: &'a Labels, conversation: &str) -> Element<'a, Message> {
+    if labels.is_empty() {
+        return text("").into();
+    }
+    let chips = labels.list.iter().fold(row![].spacing(5), |row, label| {
+        row.push(label_chip(
+            label,
+            labels.has(conversation, label.id),
+            Message::ToggleLabel(label.id),
+        ))
+    });
+    container(chips)
+        .padding([5, 10])
         .width(Length::Fill)
+        .style(style::Container::Foreground)
+        .into()
+}
+
+fn tip_button<'a>(lightning: Option<&LightningTip>) -> Element<'a, Message> {
+    let Some(tip) = lightning else {
+        return text("").into();
+    };
+    let (btn, tooltip_text) = match tip.valid {
+        Some(false) => (
+            button(text("⚡ tip").style(style::Text::Alpha(0.5))),
+            format!("{} is not reachable", tip.address),
+        ),
+        _ => (
+            button(text("⚡ tip")).on_press(Message::TipPressed),
+            format!("Tip {}", tip.address),
+        ),
+    };
+    tooltip(
+        btn.style(style::Button::Invisible),
+        tooltip_text,
+        tooltip::Position::Bottom,
+    )
+    .style(style::Container::TooltipBg)
+    .into()
 }
 
-fn header_action_buttons<'a>() -> Element<'a, Message> {
-    row![button(file_icon_regular())
+fn header_action_buttons<'a>(
+    auto_translate: bool,
+    auto_save_attachments: bool,
+    lightning: Option<&LightningTip>,
+) -> Element<'a, Message> {
+    let translate_style = if auto_translate {
+        style::Text::Primary
+    } else {
+        style::Text::Alpha(0.5)
+    };
+    let translate_btn = button(text("Aa").style(translate_style))
         .style(style::Button::Invisible)
-        .on_press(Message::OpenContactProfile)]
+        .on_press(Message::ToggleAutoTranslate);
+    let auto_save_style = if auto_save_attachments {
+        style::Text::Primary
+    } else {
+        style::Text::Alpha(0.5)
+    };
+    let auto_save_btn = tooltip(
+        button(folder_open_icon().style(auto_save_style))
+            .style(style::Button::Invisible)
+            .on_press(Message::ToggleAutoSaveAttachments),
+        if auto_save_attachments {
+            "Saving received files"
+        } else {
+            "Save received files"
+        },
+        tooltip::Position::Bottom,
+    )
+    .style(style::Container::TooltipBg);
+    let export_btn = button(download_icon())
+        .style(style::Button::Invisible)
+        .on_press(Message::PdfExportPressed);
+    let clear_btn = tooltip(
+        button(delete_icon())
+            .style(style::Button::Invisible)
+            .on_press(Message::ClearHistoryPressed),
+        "Clear history",
+        tooltip::Position::Bottom,
+    )
+    .style(style::Container::TooltipBg);
+    row![
+        tip_button(lightning),
+        translate_btn,
+        auto_save_btn,
+        export_btn,
+        clear_btn,
+        button(file_icon_regular())
+            .style(style::Button::Invisible)
+            .on_press(Message::OpenContactProfile)
+    ]
+    .spacing(5)
     .padding(10)
     .align_items(Alignment::End)
     .into()
@@ -237,6 +1177,9 @@ fn header_action_buttons<'a>() -> Element<'a, Message> {
 fn create_channel_content<'a>(
     scrollable_id: &'a scrollable::Id,
     messages: &'a [ChatMessage],
+    accents: &'a HashMap<XOnlyPublicKey, Color>,
+    hover_cards: &'a HoverCards,
+    appearance: Appearance,
 ) -> Element<'a, Message> {
     let lazy = Responsive::new(move |_size| {
         if messages.is_empty() {
@@ -257,17 +1200,20 @@ fn create_channel_content<'a>(
             if let Some(msg_date) = msg.display_time() {
                 if let Some(last) = last_date {
                     if last.day() != msg_date.day() {
-                        col = col.push(chat_day_divider(*msg_date));
+                        col = col.push(chat_day_divider(*msg_date, &appearance.time_format));
                     }
                 } else {
-                    col = col.push(chat_day_divider(*msg_date));
+                    col = col.push(chat_day_divider(*msg_date, &appearance.time_format));
                 }
                 last_date = Some(*msg_date);
             }
 
             let show_name = msg.show_name(previous_msg.as_ref());
 
-            let msg_view = msg.view(show_name).map(map_chat_msgs);
+            let accent = msg.author().and_then(|author| accents.get(author)).copied();
+            let msg_view = msg
+                .view(show_name, None, accent, hover_cards, appearance)
+                .map(map_chat_msgs);
 
             col = col.push(msg_view);
 
@@ -295,7 +1241,15 @@ fn create_channel_content<'a>(
 fn map_chat_msgs(message: chat_message::Message) -> Message {
     match message {
         chat_message::Message::ChatRightClick(msg, point) => Message::ChatRightClick(msg, point),
+        chat_message::Message::ChatPress(msg) => Message::ChatPress(msg),
+        chat_message::Message::ChatRelease(msg) => Message::ChatRelease(msg),
         chat_message::Message::UserNameClick(author) => Message::ChannelUserNamePressed(author),
+        chat_message::Message::NostrLinkPressed(link) => Message::NostrLinkPressed(link),
+        chat_message::Message::HashtagPressed(hashtag) => Message::HashtagPressed(hashtag),
+        chat_message::Message::ReactionPressed(event_id, content) => {
+            Message::ReactionPressed(event_id, content)
+        }
+        chat_message::Message::HoverCard(message) => Message::HoverCard(message),
     }
 }
 
@@ -333,11 +1287,22 @@ fn channel_header_action_buttons<'a>() -> Element<'a, Message> {
         .style(style::Button::Invisible)
         .on_press(Message::ChannelMenuPressed);
 
-    row![src_btn, menu_btn]
+    let export_btn = button(download_icon())
+        .style(style::Button::Invisible)
+        .on_press(Message::PdfExportPressed);
+
+    row![src_btn, export_btn, menu_btn]
         .padding(10)
         .align_items(Alignment::End)
         .into()
 }
 
 const NAVBAR_HEIGHT: f32 = 50.0;
-const CHAT_INPUT_HEIGHT: f32 = 50.0;
+const TEMPLATE_NAME_WIDTH: u16 = 120;
+const TEMPLATE_PICKER_MAX_HEIGHT: u16 = 200;
+const COMPOSER_MAX_HEIGHT: u16 = 150;
+const HEADING_SIZE: u16 = 30;
+/// Common `max_content_length` advertised by relays in NIP-11
+const MAX_CONTENT_LENGTH: usize = 8196;
+const CONTENT_WARN_PERCENT: usize = 90;
+const SEND_DEBOUNCE: Duration = Duration::from_millis(500);
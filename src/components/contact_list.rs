@@ -1,35 +1,133 @@
 use crate::components::chat_contact::{self, ChatContact};
 use crate::components::common_scrollable;
+use crate::components::contact_search::{rank_chats, ContactMatch};
+use crate::components::hover_card::{self, HoverCards};
+use crate::components::label_chip::label_chip;
+use crate::components::quick_switcher::{draft_snippet, SwitcherItem, SwitcherTarget};
+use crate::config::Appearance;
+use crate::db::Labels;
+use crate::icon::double_check_icon;
 use crate::style;
-use crate::utils::chat_matches_search;
 use crate::widget::Element;
-use iced::widget::{button, column, container, row, scrollable, text, text_input};
-use iced::{alignment, Length};
+use iced::widget::{button, column, container, row, scrollable, text, text_input, tooltip};
+use iced::{alignment, keyboard, subscription, Event, Length, Subscription};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     AddContactPress,
     SearchContactInputChange(String),
+    /// Enter in the search opens the selected result
+    SearchSubmit,
+    SearchNext,
+    SearchPrevious,
     ContactPress(i32),
+    LabelFilterPress(i64),
+    DraftPress(usize),
+    MarkAllReadPress,
+    HoverCard(hover_card::Message),
 }
 pub struct ContactList {
     search_input: String,
+    /// Only conversations with this label are shown
+    label_filter: Option<i64>,
+    /// Position in the search results picked with the arrow keys
+    selected: usize,
 }
 impl ContactList {
     pub fn new() -> Self {
         Self {
             search_input: "".into(),
+            label_filter: None,
+            selected: 0,
+        }
+    }
+    /// Arrows move the selection in the search results
+    pub fn subscription() -> Subscription<Message> {
+        subscription::events_with(|event, _status| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. }) => match key_code {
+                keyboard::KeyCode::Down => Some(Message::SearchNext),
+                keyboard::KeyCode::Up => Some(Message::SearchPrevious),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+    /// Pressing the active filter clears it
+    pub fn toggle_label_filter(&mut self, label_id: i64) {
+        if self.label_filter == Some(label_id) {
+            self.label_filter = None;
+        } else {
+            self.label_filter = Some(label_id);
+        }
+    }
+    /// Clears the filter if its label was deleted
+    pub fn update_labels(&mut self, labels: &Labels) {
+        if let Some(label_id) = self.label_filter {
+            if !labels.list.iter().any(|l| l.id == label_id) {
+                self.label_filter = None;
+            }
+        }
+    }
+    fn matches_filter(&self, chat: &ChatContact, labels: &Labels) -> bool {
+        match self.label_filter {
+            Some(label_id) => labels.has(&chat.label_key(), label_id),
+            None => true,
         }
     }
     pub fn search_input_change(&mut self, text: String) {
         self.search_input = text;
+        self.selected = 0;
+    }
+    fn is_searching(&self) -> bool {
+        !self.search_input.trim().is_empty()
+    }
+    /// Chats to show, ranked with their match while searching
+    fn results<'a>(
+        &self,
+        chats: &'a [ChatContact],
+        labels: &Labels,
+    ) -> Vec<(&'a ChatContact, Option<ContactMatch>)> {
+        if !self.is_searching() {
+            return chats
+                .iter()
+                .filter(|chat| self.matches_filter(chat, labels))
+                .map(|chat| (chat, None))
+                .collect();
+        }
+        rank_chats(chats, &self.search_input)
+            .into_iter()
+            .map(|(idx, search_match)| (&chats[idx], Some(search_match)))
+            .filter(|(chat, _)| self.matches_filter(chat, labels))
+            .collect()
+    }
+    pub fn select_next(&mut self, chats: &[ChatContact], labels: &Labels) {
+        if self.is_searching() && self.selected + 1 < self.results(chats, labels).len() {
+            self.selected += 1;
+        }
     }
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+    /// Id of the selected search result
+    pub fn selected_chat(&self, chats: &[ChatContact], labels: &Labels) -> Option<i32> {
+        if !self.is_searching() {
+            return None;
+        }
+        self.results(chats, labels)
+            .get(self.selected)
+            .map(|(chat, _)| chat.id)
+    }
+    #[allow(clippy::too_many_arguments)]
     pub fn view<'a>(
         &'a self,
         scrollable_id: &'a scrollable::Id,
         chats: &'a [ChatContact],
         show_only_profile: bool,
         active_idx: Option<i32>,
+        labels: &'a Labels,
+        drafts: &'a [SwitcherItem],
+        hover_cards: &'a HoverCards,
+        appearance: Appearance,
     ) -> Element<'a, Message> {
         // --- FIRST SPLIT ---
         let contact_list: Element<_> = if chats.is_empty() {
@@ -42,14 +140,34 @@ impl ContactList {
             .width(Length::Fill)
             .into()
         } else {
-            let contact_list = chats
-                .iter()
-                .filter(|chat| chat_matches_search(chat, &self.search_input))
-                .fold(column![].padding(8).spacing(4), |col, chat| {
-                    col.push(chat.view(active_idx).map(|m| match m.message {
-                        chat_contact::Message::ContactPress(idx) => Message::ContactPress(idx),
-                    }))
-                });
+            let searching = self.is_searching();
+            let contact_list = self.results(chats, labels).into_iter().enumerate().fold(
+                column![].padding(8).spacing(4),
+                |col, (position, (chat, search_match))| {
+                    let selected = searching && position == self.selected;
+                    let chat_view = chat
+                        .view(
+                            active_idx,
+                            labels,
+                            search_match.as_ref(),
+                            selected,
+                            appearance,
+                        )
+                        .map(|m| match m.message {
+                            chat_contact::Message::ContactPress(idx) => Message::ContactPress(idx),
+                        });
+                    // search results show who the contact is before opening it
+                    if searching {
+                        col.push(hover_cards.wrap(
+                            chat.contact.pubkey(),
+                            chat_view,
+                            Message::HoverCard,
+                        ))
+                    } else {
+                        col.push(chat_view)
+                    }
+                },
+            );
             common_scrollable(contact_list)
                 .id(scrollable_id.clone())
                 .into()
@@ -58,11 +176,27 @@ impl ContactList {
             true => text("").into(),
             false => text_input("Search", &self.search_input)
                 .on_input(Message::SearchContactInputChange)
+                .on_submit(Message::SearchSubmit)
                 .style(style::TextInput::ChatSearch)
                 .into(),
         };
+        let mark_all_read: Element<_> =
+            if show_only_profile || !chats.iter().any(ChatContact::has_unseen) {
+                text("").into()
+            } else {
+                tooltip(
+                    button(double_check_icon().size(16))
+                        .padding(5)
+                        .on_press(Message::MarkAllReadPress)
+                        .style(style::Button::MenuBtn),
+                    "Mark all as read",
+                    tooltip::Position::Bottom,
+                )
+                .style(style::Container::TooltipBg)
+                .into()
+            };
         let search_container = container(
-            row![search_contact]
+            row![search_contact, mark_all_read]
                 .spacing(5)
                 .align_items(alignment::Alignment::Center),
         )
@@ -70,11 +204,56 @@ impl ContactList {
         .width(Length::Fill)
         .height(NAVBAR_HEIGHT);
 
-        container(column![search_container, contact_list])
-            .height(Length::Fill)
-            .width(Length::Fill)
-            .style(style::Container::Frame)
-            .into()
+        let filter_bar: Element<_> = if show_only_profile || labels.is_empty() {
+            text("").into()
+        } else {
+            let chips = labels.list.iter().fold(row![].spacing(5), |row, label| {
+                let active = self.label_filter.map_or(true, |id| id == label.id);
+                row.push(label_chip(
+                    label,
+                    active,
+                    Message::LabelFilterPress(label.id),
+                ))
+            });
+            container(chips).padding([0, 10, 5, 10]).into()
+        };
+
+        let drafts_section: Element<_> = if show_only_profile || drafts.is_empty() {
+            text("").into()
+        } else {
+            let list = drafts.iter().enumerate().fold(
+                column![text("Drafts").size(14).style(style::Text::Placeholder)].spacing(2),
+                |col, (idx, item)| {
+                    let kind = match item.target {
+                        SwitcherTarget::Contact(_) => "",
+                        SwitcherTarget::Channel(_) => "# ",
+                    };
+                    let snippet = item.draft.as_deref().map(draft_snippet).unwrap_or_default();
+                    col.push(
+                        button(column![
+                            text(format!("{}{}", kind, item.name)).size(16),
+                            text(snippet).size(14).style(style::Text::Placeholder),
+                        ])
+                        .width(Length::Fill)
+                        .padding(5)
+                        .style(style::Button::MenuBtn)
+                        .on_press(Message::DraftPress(idx)),
+                    )
+                },
+            );
+            container(list).padding([0, 10, 5, 10]).into()
+        };
+
+        container(column![
+            search_container,
+            filter_bar,
+            drafts_section,
+            contact_list
+        ])
+        .height(Length::Fill)
+        .width(Length::Fill)
+        .style(style::Container::Frame)
+        .into()
     }
 }
 
@@ -1,4 +1,4 @@
-use iced::widget::{button, container, row, text, tooltip};
+use iced::widget::{button, container, row, text, tooltip, Space};
 use iced::Length;
 use nostr::prelude::ToBech32;
 
@@ -17,6 +17,7 @@ pub enum Message {
 pub struct ContactRow {
     contact: DbContact,
     pubkey: String,
+    is_saving: bool,
 }
 
 impl From<ContactRow> for DbContact {
@@ -39,8 +40,14 @@ impl ContactRow {
                 .pubkey()
                 .to_bech32()
                 .unwrap_or(db_contact.pubkey().to_string()),
+            is_saving: false,
         }
     }
+    /// Marks the row as waiting for the backend to confirm a change
+    pub fn saving(mut self, is_saving: bool) -> Self {
+        self.is_saving = is_saving;
+        self
+    }
     pub fn header<M: 'static>() -> Element<'static, M> {
         row![
             container(text("Public Key")).width(Length::Fixed(PUBKEY_CELL_WIDTH)),
@@ -63,6 +70,17 @@ impl ContactRow {
         .into()
     }
     pub fn view(&self) -> Element<'static, Message> {
+        let mut edit_btn = button(edit_icon().size(16));
+        let mut delete_btn = button(delete_icon().size(16));
+        let saving_txt: Element<_> = if self.is_saving {
+            text("saving…").style(style::Text::Placeholder).into()
+        } else {
+            // only allow changes once the backend confirmed the last one
+            edit_btn = edit_btn.on_press(Message::EditContact(self.into()));
+            delete_btn = delete_btn.on_press(Message::DeleteContact(self.contact.clone()));
+            text("").into()
+        };
+
         row![
             container(text(hide_string(&self.pubkey, 6))).width(Length::Fixed(PUBKEY_CELL_WIDTH)),
             container(text(&self.contact.get_petname().unwrap_or("".into())))
@@ -74,13 +92,20 @@ impl ContactRow {
             container(text(&self.contact.get_display_name().unwrap_or("".into())))
                 .width(Length::Fixed(NAME_CELL_WIDTH_MIN))
                 .max_width(NAME_CELL_WIDTH_MAX),
-            container(text(
-                &self
-                    .contact
-                    .get_relay_url()
-                    .map(|url| url.to_string())
-                    .unwrap_or("".into())
-            ))
+            container(
+                row![
+                    text(
+                        &self
+                            .contact
+                            .get_relay_url()
+                            .map(|url| url.to_string())
+                            .unwrap_or("".into())
+                    ),
+                    Space::with_width(Length::Fill),
+                    saving_txt
+                ]
+                .spacing(5)
+            )
             .width(Length::Fill),
             container(
                 tooltip(
@@ -93,9 +118,7 @@ impl ContactRow {
             .width(Length::Fixed(EDIT_BTN_WIDTH)),
             container(
                 tooltip(
-                    button(edit_icon().size(16))
-                        .on_press(Message::EditContact(self.into()))
-                        .width(Length::Fixed(EDIT_BTN_WIDTH)),
+                    edit_btn.width(Length::Fixed(EDIT_BTN_WIDTH)),
                     "Edit Contact",
                     tooltip::Position::Left
                 )
@@ -103,9 +126,7 @@ impl ContactRow {
             ),
             container(
                 tooltip(
-                    button(delete_icon().size(16))
-                        .on_press(Message::DeleteContact(self.contact.clone()))
-                        .style(style::Button::Danger),
+                    delete_btn.style(style::Button::Danger),
                     "Delete Contact",
                     tooltip::Position::Left
                 )
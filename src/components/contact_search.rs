@@ -0,0 +1,162 @@
+//! Ranked search over the conversations of the contact list.
+
+use iced::widget::{row, text};
+use nostr::prelude::ToBech32;
+
+use crate::components::chat_contact::ChatContact;
+use crate::db::DbContact;
+use crate::style;
+use crate::utils::fuzzy::{fuzzy_match, highlight_runs};
+use crate::widget::Element;
+
+/// Field of the contact the search matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchField {
+    Petname,
+    DisplayName,
+    Name,
+    Nip05,
+    Npub,
+}
+
+impl MatchField {
+    /// Names the user gave or sees win over better matches in other fields
+    fn bonus(&self) -> i64 {
+        match self {
+            MatchField::Petname => 30,
+            MatchField::DisplayName | MatchField::Name => 20,
+            MatchField::Nip05 => 10,
+            MatchField::Npub => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for MatchField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            MatchField::Petname => "Petname",
+            MatchField::DisplayName => "Display name",
+            MatchField::Name => "Name",
+            MatchField::Nip05 => "NIP-05",
+            MatchField::Npub => "npub",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContactMatch {
+    pub field: MatchField,
+    /// Value of the field
+    pub text: String,
+    /// Matched characters of `text`
+    pub positions: Vec<usize>,
+    score: i64,
+}
+
+/// Best field of the contact for the query, `None` when nothing matches or the
+/// query is empty. The npub only matches by its prefix, fuzzy matches in keys are noise
+pub fn match_contact(contact: &DbContact, query: &str) -> Option<ContactMatch> {
+    let query = query.trim();
+    if query.is_empty() {
+        return None;
+    }
+    let fields = [
+        (MatchField::Petname, contact.get_petname()),
+        (MatchField::DisplayName, contact.get_display_name()),
+        (MatchField::Name, contact.get_profile_name()),
+        (MatchField::Nip05, contact.get_nip05()),
+    ];
+    let best = fields
+        .into_iter()
+        .filter_map(|(field, text)| {
+            let text = text.filter(|text| !text.trim().is_empty())?;
+            let matched = fuzzy_match(query, &text)?;
+            Some(ContactMatch {
+                field,
+                text,
+                positions: matched.positions,
+                score: matched.score + field.bonus(),
+            })
+        })
+        // the first field wins ties
+        .reduce(|best, other| {
+            if other.score > best.score {
+                other
+            } else {
+                best
+            }
+        });
+    if best.is_some() {
+        return best;
+    }
+
+    let npub = contact.pubkey().to_bech32().ok()?;
+    npub.starts_with(&query.to_lowercase())
+        .then(|| ContactMatch {
+            field: MatchField::Npub,
+            positions: (0..query.chars().count()).collect(),
+            score: MatchField::Npub.bonus(),
+            text: npub,
+        })
+}
+
+/// Indexes of the matching chats with their match, best first.
+/// Equal matches keep the order of the list
+pub fn rank_chats(chats: &[ChatContact], query: &str) -> Vec<(usize, ContactMatch)> {
+    let mut results: Vec<_> = chats
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, chat)| match_contact(&chat.contact, query).map(|m| (idx, m)))
+        .collect();
+    results.sort_by(|(_, a), (_, b)| b.score.cmp(&a.score));
+    results
+}
+
+/// Text with the matched characters in the primary color
+pub fn highlighted_text<'a, M: 'a>(
+    content: &str,
+    positions: &[usize],
+    size: u16,
+) -> Element<'a, M> {
+    highlight_runs(content, positions)
+        .into_iter()
+        .fold(row![], |row, (run, matched)| {
+            let run = text(run).size(size);
+            row.push(if matched {
+                run.style(style::Text::Primary)
+            } else {
+                run
+            })
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Keys;
+
+    #[test]
+    fn petname_beats_npub_and_ranks_prefix_first() {
+        let keys = Keys::generate();
+        let contact = DbContact::new(&keys.public_key()).with_petname("Alice Liddell");
+
+        let matched = match_contact(&contact, "ali").unwrap();
+        assert_eq!(matched.field, MatchField::Petname);
+        assert_eq!(matched.positions, vec![0, 1, 2]);
+
+        let npub = keys.public_key().to_bech32().unwrap();
+        let matched = match_contact(&contact, &npub[..10]).unwrap();
+        assert_eq!(matched.field, MatchField::Npub);
+        assert_eq!(matched.positions.len(), 10);
+
+        assert!(match_contact(&contact, "zzz").is_none());
+        assert!(match_contact(&contact, "  ").is_none());
+
+        let other = DbContact::new(&Keys::generate().public_key()).with_petname("Malia");
+        let prefix = match_contact(&contact, "ali").unwrap();
+        let inner = match_contact(&other, "ali").unwrap();
+        assert!(prefix.score > inner.score);
+    }
+}
@@ -0,0 +1,211 @@
+//! Small widgets layered over the corners of another one, like the
+//! unread count and status marks on an avatar.
+
+use iced_native::event::{self, Event};
+use iced_native::layout;
+use iced_native::mouse;
+use iced_native::renderer;
+use iced_native::widget::{Operation, Tree};
+use iced_native::{Clipboard, Layout, Length, Point, Rectangle, Shell, Size, Widget};
+
+use crate::style::Theme;
+use crate::widget::Element;
+
+pub type Renderer = iced::Renderer<Theme>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Content with badges drawn on top of it, each one inside a corner.
+/// Badges are decorations: events and the mouse only reach the content.
+#[allow(missing_debug_implementations)]
+pub struct BadgeOverlay<'a, Message> {
+    content: Element<'a, Message>,
+    badges: Vec<(Corner, Element<'a, Message>)>,
+}
+
+impl<'a, Message: 'a> BadgeOverlay<'a, Message> {
+    pub fn new(content: impl Into<Element<'a, Message>>) -> Self {
+        Self {
+            content: content.into(),
+            badges: vec![],
+        }
+    }
+
+    /// Later badges are drawn over earlier ones in the same corner
+    pub fn badge(mut self, corner: Corner, badge: impl Into<Element<'a, Message>>) -> Self {
+        self.badges.push((corner, badge.into()));
+        self
+    }
+
+    /// Adds the badge only when there is one
+    pub fn badge_maybe(
+        self,
+        corner: Corner,
+        badge: Option<impl Into<Element<'a, Message>>>,
+    ) -> Self {
+        match badge {
+            Some(badge) => self.badge(corner, badge),
+            None => self,
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message, Renderer> for BadgeOverlay<'a, Message> {
+    fn children(&self) -> Vec<Tree> {
+        std::iter::once(&self.content)
+            .chain(self.badges.iter().map(|(_, badge)| badge))
+            .map(Tree::new)
+            .collect()
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        let children: Vec<&Element<'a, Message>> = std::iter::once(&self.content)
+            .chain(self.badges.iter().map(|(_, badge)| badge))
+            .collect();
+        tree.diff_children(&children);
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let content = self.content.as_widget().layout(renderer, limits);
+        let size = content.size();
+        let badge_limits = layout::Limits::new(Size::ZERO, size);
+
+        let mut children = vec![content];
+        for (corner, badge) in &self.badges {
+            let mut node = badge.as_widget().layout(renderer, &badge_limits);
+            let badge_size = node.size();
+            let x = match corner {
+                Corner::TopLeft | Corner::BottomLeft => 0.0,
+                Corner::TopRight | Corner::BottomRight => size.width - badge_size.width,
+            };
+            let y = match corner {
+                Corner::TopLeft | Corner::TopRight => 0.0,
+                Corner::BottomLeft | Corner::BottomRight => size.height - badge_size.height,
+            };
+            node.move_to(Point::new(x.max(0.0), y.max(0.0)));
+            children.push(node);
+        }
+
+        layout::Node::with_children(size, children)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        if let Some(content_layout) = layout.children().next() {
+            self.content.as_widget().operate(
+                &mut tree.children[0],
+                content_layout,
+                renderer,
+                operation,
+            );
+        }
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let Some(content_layout) = layout.children().next() else {
+            return event::Status::Ignored;
+        };
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            content_layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let Some(content_layout) = layout.children().next() else {
+            return mouse::Interaction::default();
+        };
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            content_layout,
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        renderer_style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        let mut layouts = layout.children();
+        let Some(content_layout) = layouts.next() else {
+            return;
+        };
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            renderer_style,
+            content_layout,
+            cursor_position,
+            viewport,
+        );
+
+        for (((_, badge), badge_tree), badge_layout) in
+            self.badges.iter().zip(&tree.children[1..]).zip(layouts)
+        {
+            badge.as_widget().draw(
+                badge_tree,
+                renderer,
+                theme,
+                renderer_style,
+                badge_layout,
+                cursor_position,
+                viewport,
+            );
+        }
+    }
+}
+
+impl<'a, Message: 'a> From<BadgeOverlay<'a, Message>> for Element<'a, Message> {
+    fn from(overlay: BadgeOverlay<'a, Message>) -> Element<'a, Message> {
+        Element::new(overlay)
+    }
+}
@@ -0,0 +1,274 @@
+//! Picks the part of an image to keep by dragging and scrolling over it.
+
+use iced::widget::image::Handle;
+use iced_native::event::{self, Event};
+use iced_native::image::Renderer as _;
+use iced_native::layout;
+use iced_native::mouse;
+use iced_native::renderer::{self, Renderer as _};
+use iced_native::widget::{tree, Tree};
+use iced_native::{Clipboard, Color, Layout, Length, Point, Rectangle, Shell, Size, Widget};
+
+use crate::style::Theme;
+use crate::types::CropRect;
+use crate::widget::Element;
+
+pub type Renderer = iced::Renderer<Theme>;
+
+/// Image fitted in the area with everything outside `crop` darkened.
+/// Dragging moves the crop and the mouse wheel zooms it.
+#[allow(missing_debug_implementations)]
+pub struct CropArea<'a, Message> {
+    handle: Handle,
+    image_size: (u32, u32),
+    crop: CropRect,
+    on_change: Box<dyn Fn(CropRect) -> Message + 'a>,
+    width: Length,
+    height: Length,
+}
+
+impl<'a, Message> CropArea<'a, Message> {
+    /// `image_size` is the size of the image in pixels, the crop is relative to it
+    pub fn new(
+        handle: Handle,
+        image_size: (u32, u32),
+        crop: CropRect,
+        on_change: impl Fn(CropRect) -> Message + 'a,
+    ) -> Self {
+        Self {
+            handle,
+            image_size,
+            crop,
+            on_change: Box::new(on_change),
+            width: Length::Fill,
+            height: Length::Fixed(DEFAULT_HEIGHT),
+        }
+    }
+
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    /// Where the image is drawn and its scale, keeping its aspect ratio
+    fn image_bounds(&self, bounds: Rectangle) -> (Rectangle, f32) {
+        let (image_width, image_height) = (
+            self.image_size.0.max(1) as f32,
+            self.image_size.1.max(1) as f32,
+        );
+        let scale = (bounds.width / image_width).min(bounds.height / image_height);
+        let (width, height) = (image_width * scale, image_height * scale);
+        let image_bounds = Rectangle {
+            x: bounds.x + (bounds.width - width) / 2.0,
+            y: bounds.y + (bounds.height - height) / 2.0,
+            width,
+            height,
+        };
+        (image_bounds, scale)
+    }
+
+    fn crop_bounds(&self, bounds: Rectangle) -> Rectangle {
+        let (image_bounds, scale) = self.image_bounds(bounds);
+        Rectangle {
+            x: image_bounds.x + self.crop.x * scale,
+            y: image_bounds.y + self.crop.y * scale,
+            width: self.crop.width * scale,
+            height: self.crop.height * scale,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Last cursor position while dragging
+    drag_origin: Option<Point>,
+}
+
+impl<'a, Message> Widget<Message, Renderer> for CropArea<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn width(&self) -> Length {
+        self.width
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(self.width).height(self.height);
+        layout::Node::new(limits.resolve(Size::ZERO))
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+        let (image_width, image_height) = self.image_size;
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if self.crop_bounds(bounds).contains(cursor_position) {
+                    state.drag_origin = Some(cursor_position);
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.drag_origin.take().is_some() {
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some(origin) = state.drag_origin {
+                    let (_, scale) = self.image_bounds(bounds);
+                    let moved = self.crop.moved(
+                        (position.x - origin.x) / scale,
+                        (position.y - origin.y) / scale,
+                        image_width,
+                        image_height,
+                    );
+                    state.drag_origin = Some(position);
+                    shell.publish((self.on_change)(moved));
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                if bounds.contains(cursor_position) {
+                    let y = match delta {
+                        mouse::ScrollDelta::Lines { y, .. } => y,
+                        mouse::ScrollDelta::Pixels { y, .. } => y,
+                    };
+                    if y != 0.0 {
+                        // scrolling up zooms in, making the crop smaller
+                        let factor = if y > 0.0 { 1.0 / ZOOM_STEP } else { ZOOM_STEP };
+                        let zoomed = self.crop.zoomed(factor, image_width, image_height);
+                        shell.publish((self.on_change)(zoomed));
+                    }
+                    return event::Status::Captured;
+                }
+            }
+            _ => (),
+        }
+
+        event::Status::Ignored
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        if state.drag_origin.is_some() {
+            mouse::Interaction::Grabbing
+        } else if self.crop_bounds(layout.bounds()).contains(cursor_position) {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _renderer_style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let (image_bounds, _) = self.image_bounds(bounds);
+        let crop = self.crop_bounds(bounds);
+
+        renderer.draw(self.handle.clone(), image_bounds);
+
+        // darkens the four sides around the crop
+        let outside = [
+            Rectangle {
+                height: crop.y - image_bounds.y,
+                ..image_bounds
+            },
+            Rectangle {
+                y: crop.y + crop.height,
+                height: image_bounds.y + image_bounds.height - crop.y - crop.height,
+                ..image_bounds
+            },
+            Rectangle {
+                y: crop.y,
+                width: crop.x - image_bounds.x,
+                height: crop.height,
+                ..image_bounds
+            },
+            Rectangle {
+                x: crop.x + crop.width,
+                y: crop.y,
+                width: image_bounds.x + image_bounds.width - crop.x - crop.width,
+                height: crop.height,
+            },
+        ];
+        for side in outside
+            .into_iter()
+            .filter(|side| side.width > 0.0 && side.height > 0.0)
+        {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: side,
+                    border_radius: 0.0.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                OUTSIDE_COLOR,
+            );
+        }
+
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: crop,
+                border_radius: 0.0.into(),
+                border_width: BORDER_WIDTH,
+                border_color: Color::WHITE,
+            },
+            Color::TRANSPARENT,
+        );
+    }
+}
+
+impl<'a, Message: 'a> From<CropArea<'a, Message>> for Element<'a, Message> {
+    fn from(area: CropArea<'a, Message>) -> Element<'a, Message> {
+        Element::new(area)
+    }
+}
+
+const DEFAULT_HEIGHT: f32 = 320.0;
+const ZOOM_STEP: f32 = 1.1;
+const BORDER_WIDTH: f32 = 2.0;
+const OUTSIDE_COLOR: Color = Color {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 0.6,
+};
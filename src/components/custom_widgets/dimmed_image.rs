@@ -0,0 +1,164 @@
+//! Dims its content in dark themes to avoid the glare of bright images.
+
+use iced::widget::image::{self, Handle};
+use iced_native::event::{self, Event};
+use iced_native::layout;
+use iced_native::mouse;
+use iced_native::renderer::{self, Renderer as _};
+use iced_native::widget::{Operation, Tree};
+use iced_native::{Clipboard, Color, Layout, Length, Point, Rectangle, Shell, Widget};
+
+use crate::style::{Theme, ThemeType};
+use crate::widget::Element;
+
+pub type Renderer = iced::Renderer<Theme>;
+
+/// Image covered by a translucent layer of the background color
+/// when `dim` is on and the theme is dark.
+#[allow(missing_debug_implementations)]
+pub struct DimmedImage<'a, Message> {
+    content: Element<'a, Message>,
+    dim: bool,
+}
+
+impl<'a, Message: 'a> DimmedImage<'a, Message> {
+    pub fn new(handle: Handle, dim: bool) -> Self {
+        Self::with_content(image::Image::new(handle), dim)
+    }
+
+    /// Sized image or any other widget showing one
+    pub fn with_content(content: impl Into<Element<'a, Message>>, dim: bool) -> Self {
+        Self {
+            content: content.into(),
+            dim,
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message, Renderer> for DimmedImage<'a, Message> {
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        renderer_style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            renderer_style,
+            layout,
+            cursor_position,
+            viewport,
+        );
+
+        if let Some(overlay) = overlay_color(theme, self.dim) {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: layout.bounds(),
+                    border_radius: 0.0.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                overlay,
+            );
+        }
+    }
+}
+
+/// Background color at `DIM_OPACITY`, None when nothing should be dimmed
+fn overlay_color(theme: &Theme, dim: bool) -> Option<Color> {
+    if !dim {
+        return None;
+    }
+    match theme.theme_meta().theme_type {
+        ThemeType::Light => None,
+        ThemeType::Dark => {
+            let mut color = theme.palette().base.background;
+            color.a = DIM_OPACITY;
+            Some(color)
+        }
+    }
+}
+
+impl<'a, Message: 'a> From<DimmedImage<'a, Message>> for Element<'a, Message> {
+    fn from(image: DimmedImage<'a, Message>) -> Element<'a, Message> {
+        Element::new(image)
+    }
+}
+
+const DIM_OPACITY: f32 = 0.25;
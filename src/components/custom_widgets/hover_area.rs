@@ -0,0 +1,312 @@
+//! Shows an element over the content while the pointer is on either of them.
+
+use iced_native::event::{self, Event};
+use iced_native::layout;
+use iced_native::mouse;
+use iced_native::overlay;
+use iced_native::renderer;
+use iced_native::widget::{tree, Operation, Tree};
+use iced_native::{Clipboard, Layout, Length, Point, Rectangle, Shell, Size, Widget};
+
+use crate::style::Theme;
+use crate::widget::Element;
+
+pub type Renderer = iced::Renderer<Theme>;
+
+/// Content with a card shown under it on hover.
+/// The card stays open while the pointer moves onto it, so it can hold buttons
+#[allow(missing_debug_implementations)]
+pub struct HoverArea<'a, B, Message>
+where
+    B: Fn() -> Element<'a, Message>,
+{
+    content: Element<'a, Message>,
+    /// Built only while the card is open
+    card: B,
+    on_open: Option<Message>,
+}
+
+impl<'a, B, Message> HoverArea<'a, B, Message>
+where
+    B: Fn() -> Element<'a, Message>,
+{
+    pub fn new(content: impl Into<Element<'a, Message>>, card: B) -> Self {
+        Self {
+            content: content.into(),
+            card,
+            on_open: None,
+        }
+    }
+
+    /// The message to emit when the card opens.
+    #[must_use]
+    pub fn on_open(mut self, message: Message) -> Self {
+        self.on_open = Some(message);
+        self
+    }
+}
+
+/// Local state of the [`HoverArea`].
+struct State {
+    over_content: bool,
+    over_card: bool,
+    card: Tree,
+}
+impl State {
+    fn is_open(&self) -> bool {
+        self.over_content || self.over_card
+    }
+}
+
+impl<'a, B, Message> Widget<Message, Renderer> for HoverArea<'a, B, Message>
+where
+    B: Fn() -> Element<'a, Message>,
+    Message: 'a + Clone,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State {
+            over_content: false,
+            over_card: false,
+            card: Tree::empty(),
+        })
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let status = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        );
+
+        let state = tree.state.downcast_mut::<State>();
+        match event {
+            // over the open card the content gets a position outside of it
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let over_content = layout.bounds().contains(cursor_position);
+                if over_content && !state.is_open() {
+                    if let Some(message) = &self.on_open {
+                        shell.publish(message.clone());
+                    }
+                }
+                state.over_content = over_content;
+            }
+            Event::Mouse(mouse::Event::CursorLeft) => {
+                state.over_content = false;
+                state.over_card = false;
+            }
+            _ => (),
+        }
+        status
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        renderer_style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            renderer_style,
+            layout,
+            cursor_position,
+            viewport,
+        );
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        let state = tree.state.downcast_mut::<State>();
+        if !state.is_open() {
+            return self
+                .content
+                .as_widget_mut()
+                .overlay(&mut tree.children[0], layout, renderer);
+        }
+
+        let card = (self.card)();
+        state.card.diff(card.as_widget());
+        let bounds = layout.bounds();
+        let position = Point::new(bounds.x, bounds.y + bounds.height);
+        Some(overlay::Element::new(
+            position,
+            Box::new(CardOverlay {
+                state,
+                card,
+                content_height: bounds.height,
+            }),
+        ))
+    }
+}
+
+impl<'a, B, Message> From<HoverArea<'a, B, Message>> for Element<'a, Message>
+where
+    B: 'a + Fn() -> Element<'a, Message>,
+    Message: 'a + Clone,
+{
+    fn from(area: HoverArea<'a, B, Message>) -> Element<'a, Message> {
+        Element::new(area)
+    }
+}
+
+/// The card of an open [`HoverArea`]
+struct CardOverlay<'a, Message> {
+    state: &'a mut State,
+    card: Element<'a, Message>,
+    /// The card goes above the content when there's no room under it
+    content_height: f32,
+}
+
+impl<'a, Message> overlay::Overlay<Message, Renderer> for CardOverlay<'a, Message>
+where
+    Message: 'a + Clone,
+{
+    fn layout(&self, renderer: &Renderer, bounds: Size, position: Point) -> layout::Node {
+        let limits = layout::Limits::new(Size::ZERO, bounds);
+        let mut node = self.card.as_widget().layout(renderer, &limits);
+        let size = node.size();
+
+        let x = position.x.min(bounds.width - size.width).max(0.0);
+        let y = if position.y + size.height > bounds.height {
+            (position.y - self.content_height - size.height).max(0.0)
+        } else {
+            position.y
+        };
+        node.move_to(Point::new(x, y));
+        node
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<Message>,
+    ) -> event::Status {
+        if let Event::Mouse(mouse::Event::CursorMoved { .. }) = event {
+            self.state.over_card = layout.bounds().contains(cursor_position);
+        }
+        self.card.as_widget_mut().on_event(
+            &mut self.state.card,
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.card.as_widget().mouse_interaction(
+            &self.state.card,
+            layout,
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+    ) {
+        self.card.as_widget().draw(
+            &self.state.card,
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor_position,
+            &layout.bounds(),
+        );
+    }
+}
@@ -6,3 +6,15 @@ pub use responsive::Responsive;
 
 pub mod floating_element;
 pub use floating_element::FloatingElement;
+
+pub mod dimmed_image;
+pub use dimmed_image::DimmedImage;
+
+pub mod crop_area;
+pub use crop_area::CropArea;
+
+pub mod badge_overlay;
+pub use badge_overlay::BadgeOverlay;
+
+pub mod hover_area;
+pub use hover_area::HoverArea;
@@ -0,0 +1,348 @@
+//! Compact profile shown while hovering a name or an avatar.
+//!
+//! The profile is read from the cache the first time a card opens and asked
+//! to the relays again, the NIP-05 address is checked once per session.
+//! Neither lookup is made in do-not-track mode, the cached profile is shown.
+//! The host view handles opening the conversation and the full profile.
+
+use std::collections::{HashMap, HashSet};
+
+use iced::widget::{button, column, container, image::Handle, row, text};
+use iced::Alignment;
+use nostr::prelude::ToBech32;
+use nostr::secp256k1::XOnlyPublicKey;
+
+use crate::components::{DimmedImage, HoverArea};
+use crate::consts::default_profile_image;
+use crate::db::{DbContact, ProfileCache};
+use crate::error::BackendClosed;
+use crate::net::{BackEndConnection, BackendEvent, ImageKind, ImageSize, ToBackend};
+use crate::style;
+use crate::utils::hide_string;
+use crate::widget::Element;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// The pointer reached a name or avatar
+    Opened(XOnlyPublicKey),
+    MessagePressed(XOnlyPublicKey),
+    ProfilePressed(XOnlyPublicKey),
+    MutePressed(XOnlyPublicKey),
+}
+
+/// What the view showing the card has to do
+pub enum Action {
+    /// Open the conversation, like a `nostr:` profile link
+    OpenChat(XOnlyPublicKey),
+    ShowProfile(DbContact),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Nip05State {
+    Checking,
+    Verified,
+    /// The address doesn't answer or points to another key
+    NotVerified,
+}
+
+struct CardProfile {
+    cache: Option<ProfileCache>,
+    image: Handle,
+    /// Address in the metadata with the result of its check
+    nip05: Option<(String, Nip05State)>,
+}
+impl CardProfile {
+    fn new() -> Self {
+        Self {
+            cache: None,
+            image: Handle::from_memory(default_profile_image(CARD_IMAGE_SIZE)),
+            nip05: None,
+        }
+    }
+
+    /// Keeps the result of the last check while the address is the same
+    fn set_profile(&mut self, profile: ProfileCache) {
+        self.image = match &profile.profile_pic_cache {
+            Some(image) => Handle::from_path(image.sized_image(CARD_IMAGE_SIZE)),
+            None => Handle::from_memory(default_profile_image(CARD_IMAGE_SIZE)),
+        };
+        let address = profile
+            .metadata
+            .nip05
+            .as_deref()
+            .map(str::trim)
+            .filter(|address| !address.is_empty());
+        self.nip05 = match (address, self.nip05.take()) {
+            (Some(address), Some(checked)) if checked.0 == address => Some(checked),
+            (Some(address), _) => Some((address.to_owned(), Nip05State::Checking)),
+            (None, _) => None,
+        };
+        self.cache = Some(profile);
+    }
+
+    fn name(&self, pubkey: &XOnlyPublicKey) -> String {
+        self.cache
+            .as_ref()
+            .and_then(|cache| {
+                let metadata = &cache.metadata;
+                metadata.display_name.clone().or(metadata.name.clone())
+            })
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| hide_string(&pubkey.to_string(), 4))
+    }
+
+    /// Picture in the metadata that wasn't downloaded yet
+    fn missing_image(&self, pubkey: &XOnlyPublicKey) -> Option<ToBackend> {
+        let cache = self.cache.as_ref()?;
+        if cache.profile_pic_cache.is_some() {
+            return None;
+        }
+        Some(ToBackend::DownloadImage {
+            image_url: cache.metadata.picture.clone()?,
+            kind: ImageKind::Profile,
+            identifier: pubkey.to_string(),
+            event_hash: cache.event_hash,
+        })
+    }
+
+    /// Address waiting for its check
+    fn unchecked_nip05(&self) -> Option<&str> {
+        match &self.nip05 {
+            Some((address, Nip05State::Checking)) => Some(address),
+            _ => None,
+        }
+    }
+}
+
+/// Profiles of the cards opened so far, shared by every name of a view
+#[derive(Default)]
+pub struct HoverCards {
+    profiles: HashMap<XOnlyPublicKey, CardProfile>,
+    muted: HashSet<XOnlyPublicKey>,
+    do_not_track: bool,
+    dim_images: bool,
+}
+impl HoverCards {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchMutedUsers)?;
+        conn.send(ToBackend::GetDoNotTrack)?;
+        conn.send(ToBackend::GetDimImages)?;
+        Ok(Self::default())
+    }
+
+    /// The public key with the profile known to the card
+    pub fn contact(&self, pubkey: &XOnlyPublicKey) -> DbContact {
+        let contact = DbContact::new(pubkey);
+        let cache = self
+            .profiles
+            .get(pubkey)
+            .and_then(|card| card.cache.as_ref());
+        match cache {
+            Some(cache) => contact.with_profile_cache(cache),
+            None => contact,
+        }
+    }
+
+    pub fn backend_event(
+        &mut self,
+        event: &BackendEvent,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match event {
+            BackendEvent::GotProfileCache(pubkey, profile) => {
+                if let Some(card) = self.profiles.get_mut(pubkey) {
+                    card.set_profile(profile.to_owned());
+                    if let Some(download) = card.missing_image(pubkey) {
+                        conn.send(download)?;
+                    }
+                    match card.unchecked_nip05() {
+                        Some(address) if !self.do_not_track => {
+                            conn.send(ToBackend::ResolveNip05(address.to_owned()))?;
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            BackendEvent::UpdatedMetadata(pubkey) => {
+                if self.profiles.contains_key(pubkey) {
+                    conn.send(ToBackend::FetchProfileCache(pubkey.to_owned()))?;
+                }
+            }
+            BackendEvent::ImageDownloaded(image) => {
+                let owner = self.profiles.iter().find(|(_, card)| {
+                    card.cache
+                        .as_ref()
+                        .map_or(false, |cache| cache.event_hash == image.event_hash)
+                });
+                if let Some((pubkey, _)) = owner {
+                    conn.send(ToBackend::FetchProfileCache(pubkey.to_owned()))?;
+                }
+            }
+            BackendEvent::Nip05Resolved {
+                address, profile, ..
+            } => self.nip05_checked(address, Some(&profile.pubkey)),
+            BackendEvent::Nip05Failed(address, _) => self.nip05_checked(address, None),
+            BackendEvent::GotMutedUsers(muted) => self.muted = muted.to_owned(),
+            BackendEvent::DoNotTrack(do_not_track) => self.do_not_track = *do_not_track,
+            BackendEvent::DimImagesChanged(dim) => self.dim_images = *dim,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Result of the lookup of an address, `None` when it failed
+    fn nip05_checked(&mut self, address: &str, resolved: Option<&XOnlyPublicKey>) {
+        for (pubkey, card) in self.profiles.iter_mut() {
+            if let Some((card_address, state)) = &mut card.nip05 {
+                if card_address == address {
+                    *state = if resolved == Some(pubkey) {
+                        Nip05State::Verified
+                    } else {
+                        Nip05State::NotVerified
+                    };
+                }
+            }
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<Option<Action>, BackendClosed> {
+        match message {
+            Message::Opened(pubkey) => {
+                if !self.profiles.contains_key(&pubkey) {
+                    self.profiles.insert(pubkey, CardProfile::new());
+                    conn.send(ToBackend::FetchProfileCache(pubkey))?;
+                    if !self.do_not_track {
+                        conn.send(ToBackend::RefreshMetadata(vec![pubkey]))?;
+                    }
+                }
+            }
+            Message::MessagePressed(pubkey) => return Ok(Some(Action::OpenChat(pubkey))),
+            Message::ProfilePressed(pubkey) => {
+                return Ok(Some(Action::ShowProfile(self.contact(&pubkey))))
+            }
+            Message::MutePressed(pubkey) => {
+                let mute = !self.muted.contains(&pubkey);
+                conn.send(ToBackend::MuteUser(pubkey, mute))?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Shows the card of `pubkey` while the pointer is on `content`
+    pub fn wrap<'a, M: 'a + Clone>(
+        &'a self,
+        pubkey: &XOnlyPublicKey,
+        content: impl Into<Element<'a, M>>,
+        on_card: fn(Message) -> M,
+    ) -> Element<'a, M> {
+        let pubkey = pubkey.to_owned();
+        HoverArea::new(content, move || self.card_view(pubkey).map(on_card))
+            .on_open(on_card(Message::Opened(pubkey)))
+            .into()
+    }
+
+    fn card_view(&self, pubkey: XOnlyPublicKey) -> Element<'_, Message> {
+        let card = self.profiles.get(&pubkey);
+        let name = card.map_or_else(|| hide_string(&pubkey.to_string(), 4), |c| c.name(&pubkey));
+        let npub = pubkey.to_bech32().unwrap_or_else(|_| pubkey.to_string());
+        let image = card.map_or_else(
+            || Handle::from_memory(default_profile_image(CARD_IMAGE_SIZE)),
+            |c| c.image.to_owned(),
+        );
+
+        let header = row![
+            container(DimmedImage::new(image, self.dim_images))
+                .width(CARD_IMAGE_WIDTH)
+                .height(CARD_IMAGE_WIDTH),
+            column![
+                text(name).size(18),
+                text(hide_string(&npub, 8))
+                    .size(12)
+                    .style(style::Text::Placeholder),
+            ]
+            .spacing(2)
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let (nip05, nip05_style) = match card.and_then(|c| c.nip05.as_ref()) {
+            Some((address, Nip05State::Verified)) => {
+                (format!("✓ {}", address), style::Text::Primary)
+            }
+            Some((address, Nip05State::Checking)) if self.do_not_track => (
+                format!("{} (not checked)", address),
+                style::Text::Placeholder,
+            ),
+            Some((address, Nip05State::Checking)) => (
+                format!("{} (checking...)", address),
+                style::Text::Placeholder,
+            ),
+            Some((address, Nip05State::NotVerified)) => {
+                (format!("{} (not verified)", address), style::Text::Danger)
+            }
+            None => ("No NIP-05 address".to_owned(), style::Text::Placeholder),
+        };
+        let nip05 = text(nip05).size(14).style(nip05_style);
+
+        let action = |label: &'static str, message: Message| {
+            button(text(label).size(14))
+                .padding([2, 8])
+                .style(style::Button::Bordered)
+                .on_press(message)
+        };
+        let mute_label = if self.muted.contains(&pubkey) {
+            "Unmute"
+        } else {
+            "Mute"
+        };
+        let actions = row![
+            action("Message", Message::MessagePressed(pubkey)),
+            action("Profile", Message::ProfilePressed(pubkey)),
+            action(mute_label, Message::MutePressed(pubkey)),
+        ]
+        .spacing(5);
+
+        container(column![header, nip05, actions].spacing(10))
+            .padding(10)
+            .width(CARD_WIDTH)
+            .style(style::Container::ForegroundBordered)
+            .into()
+    }
+}
+
+const CARD_WIDTH: u16 = 260;
+const CARD_IMAGE_WIDTH: u16 = 48;
+const CARD_IMAGE_SIZE: ImageSize = ImageSize::Small;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Keys;
+
+    #[test]
+    fn checks_the_address_against_the_card_key() {
+        let (alice, bob) = (Keys::generate().public_key(), Keys::generate().public_key());
+        let mut cards = HoverCards::default();
+        for pubkey in [alice, bob] {
+            let mut card = CardProfile::new();
+            card.nip05 = Some(("alice@example.com".into(), Nip05State::Checking));
+            cards.profiles.insert(pubkey, card);
+        }
+
+        cards.nip05_checked("alice@example.com", Some(&alice));
+        let state = |pubkey| cards.profiles[&pubkey].nip05.as_ref().map(|(_, s)| *s);
+        assert_eq!(state(alice), Some(Nip05State::Verified));
+        assert_eq!(state(bob), Some(Nip05State::NotVerified));
+
+        cards.nip05_checked("alice@example.com", None);
+        assert_eq!(
+            cards.profiles[&alice].nip05.as_ref().map(|(_, s)| *s),
+            Some(Nip05State::NotVerified)
+        );
+        assert!(cards.contact(&alice).get_profile_cache().is_none());
+    }
+}
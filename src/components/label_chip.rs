@@ -0,0 +1,52 @@
+use iced::widget::{button, row, text};
+use iced::{Alignment, Color};
+
+use crate::db::DbLabel;
+use crate::icon::solid_circle_icon;
+use crate::style;
+use crate::widget::Element;
+
+pub fn label_color(label: &DbLabel) -> Color {
+    let (r, g, b) = label.rgb();
+    Color::from_rgb8(r, g, b)
+}
+
+/// Colored dot and name, faded when not `active`
+pub fn label_chip<'a, M: Clone + 'a>(label: &DbLabel, active: bool, on_press: M) -> Element<'a, M> {
+    let alpha = if active { 1.0 } else { INACTIVE_ALPHA };
+    let color = Color {
+        a: alpha,
+        ..label_color(label)
+    };
+    let name_style = if active {
+        style::Text::Normal
+    } else {
+        style::Text::Alpha(INACTIVE_ALPHA)
+    };
+    button(
+        row![
+            solid_circle_icon()
+                .size(CHIP_TEXT_SIZE)
+                .style(style::Text::Color(color)),
+            text(&label.name).size(CHIP_TEXT_SIZE).style(name_style)
+        ]
+        .align_items(Alignment::Center)
+        .spacing(4),
+    )
+    .padding([2, 6])
+    .style(style::Button::Bordered)
+    .on_press(on_press)
+    .into()
+}
+
+/// Small colored dot for contact cards
+pub fn label_dot<'a, M: 'a>(label: &DbLabel) -> Element<'a, M> {
+    solid_circle_icon()
+        .size(DOT_SIZE)
+        .style(style::Text::Color(label_color(label)))
+        .into()
+}
+
+const INACTIVE_ALPHA: f32 = 0.4;
+const CHIP_TEXT_SIZE: u16 = 14;
+const DOT_SIZE: u16 = 10;
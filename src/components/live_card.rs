@@ -0,0 +1,63 @@
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Color, Length};
+
+use crate::db::{DbContact, DbLiveActivity};
+use crate::style;
+use crate::utils::hide_string;
+use crate::widget::Element;
+
+/// "Live now" card, `on_join` receives the streaming url
+pub fn live_card<'a, M: Clone + 'a>(
+    activity: &'a DbLiveActivity,
+    host: Option<&DbContact>,
+    on_join: fn(String) -> M,
+) -> Element<'a, M> {
+    let host_name = host
+        .map(|c| c.select_name())
+        .unwrap_or(hide_string(&activity.host.to_string(), 6));
+
+    let badge = container(
+        text("LIVE NOW")
+            .size(12)
+            .style(style::Text::Color(Color::WHITE)),
+    )
+    .padding([2, 6])
+    .style(style::Container::Highlight);
+
+    let title = if activity.title.is_empty() {
+        "Untitled live"
+    } else {
+        activity.title.as_str()
+    };
+
+    let mut info = column![
+        row![badge, text(title).size(18)]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        text(format!("Hosted by {}", host_name))
+            .size(14)
+            .style(style::Text::Placeholder),
+    ]
+    .spacing(5);
+    if let Some(summary) = &activity.summary {
+        info = info.push(text(summary).size(14));
+    }
+
+    let mut join_btn = button("Join stream").style(style::Button::HighlightButton);
+    if let Some(url) = &activity.streaming_url {
+        join_btn = join_btn.on_press(on_join(url.to_owned()));
+    }
+
+    container(
+        row![info, Space::with_width(Length::Fill), join_btn]
+            .align_items(Alignment::Center)
+            .spacing(10),
+    )
+    .padding(10)
+    .width(Length::Fill)
+    .max_width(MAX_CARD_WIDTH)
+    .style(style::Container::Foreground)
+    .into()
+}
+
+const MAX_CARD_WIDTH: u16 = 800;
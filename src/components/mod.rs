@@ -4,8 +4,14 @@ pub mod chat_contact;
 pub mod chat_view;
 pub mod contact_list;
 pub mod contact_row;
+pub mod contact_search;
 mod copy_btn;
 mod custom_widgets;
+pub mod hover_card;
+pub mod label_chip;
+pub mod live_card;
+pub mod poll;
+pub mod quick_switcher;
 pub mod relay_row;
 mod scrollables;
 pub mod status_bar;
@@ -16,7 +22,10 @@ pub use async_file_importer::AsyncFileImporter;
 pub use card::{card, inform_card};
 pub use contact_row::ContactRow;
 pub use copy_btn::copy_btn;
-pub use custom_widgets::{floating_element, FloatingElement, MouseArea, Responsive};
+pub use custom_widgets::{
+    badge_overlay, floating_element, BadgeOverlay, CropArea, DimmedImage, FloatingElement,
+    HoverArea, MouseArea, Responsive,
+};
 pub use relay_row::RelayRow;
 pub use scrollables::{common_scrollable, invisible_scrollable};
 pub use status_bar::StatusBar;
@@ -0,0 +1,212 @@
+use iced::widget::{button, column, container, row, text, text_input, Space};
+use iced::{Alignment, Length};
+use nostr::EventId;
+
+use crate::db::{DbPoll, PollTally};
+use crate::icon::{plus_icon, xmark_icon};
+use crate::style;
+use crate::widget::Element;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    QuestionChange(String),
+    OptionChange(usize, String),
+    AddOption,
+    RemoveOption(usize),
+    Submit,
+    Cancel,
+}
+
+/// What the composer asks the parent view to do
+pub enum ComposerAction {
+    Publish {
+        question: String,
+        options: Vec<String>,
+    },
+    Close,
+}
+
+pub struct PollComposer {
+    question: String,
+    options: Vec<String>,
+}
+impl PollComposer {
+    pub fn new() -> Self {
+        Self {
+            question: "".into(),
+            options: vec!["".into(), "".into()],
+        }
+    }
+    fn filled_options(&self) -> Vec<String> {
+        self.options
+            .iter()
+            .map(|o| o.trim().to_owned())
+            .filter(|o| !o.is_empty())
+            .collect()
+    }
+    fn is_valid(&self) -> bool {
+        !self.question.trim().is_empty() && self.filled_options().len() >= MIN_OPTIONS
+    }
+    pub fn update(&mut self, message: Message) -> Option<ComposerAction> {
+        match message {
+            Message::QuestionChange(question) => self.question = question,
+            Message::OptionChange(idx, option) => {
+                if let Some(o) = self.options.get_mut(idx) {
+                    *o = option;
+                }
+            }
+            Message::AddOption => {
+                if self.options.len() < MAX_OPTIONS {
+                    self.options.push("".into());
+                }
+            }
+            Message::RemoveOption(idx) => {
+                if self.options.len() > MIN_OPTIONS {
+                    self.options.remove(idx);
+                }
+            }
+            Message::Submit => {
+                if self.is_valid() {
+                    return Some(ComposerAction::Publish {
+                        question: self.question.trim().to_owned(),
+                        options: self.filled_options(),
+                    });
+                }
+            }
+            Message::Cancel => return Some(ComposerAction::Close),
+        }
+        None
+    }
+    pub fn view(&self) -> Element<'_, Message> {
+        let question = text_input("Question", &self.question).on_input(Message::QuestionChange);
+
+        let can_remove = self.options.len() > MIN_OPTIONS;
+        let options =
+            self.options
+                .iter()
+                .enumerate()
+                .fold(column![].spacing(5), |col, (idx, option)| {
+                    let mut remove_btn =
+                        button(xmark_icon().size(12)).style(style::Button::MenuBtn);
+                    if can_remove {
+                        remove_btn = remove_btn.on_press(Message::RemoveOption(idx));
+                    }
+                    col.push(
+                        row![
+                            text_input(&format!("Option {}", idx + 1), option)
+                                .on_input(move |o| Message::OptionChange(idx, o)),
+                            remove_btn
+                        ]
+                        .align_items(Alignment::Center)
+                        .spacing(5),
+                    )
+                });
+
+        let mut add_btn = button(
+            row![plus_icon().size(12), text("Option").size(14)]
+                .align_items(Alignment::Center)
+                .spacing(2),
+        )
+        .style(style::Button::MenuBtn);
+        if self.options.len() < MAX_OPTIONS {
+            add_btn = add_btn.on_press(Message::AddOption);
+        }
+
+        let mut publish_btn = button("Publish poll");
+        if self.is_valid() {
+            publish_btn = publish_btn.on_press(Message::Submit);
+        }
+        let buttons = row![
+            add_btn,
+            Space::with_width(Length::Fill),
+            button("Cancel")
+                .on_press(Message::Cancel)
+                .style(style::Button::Bordered),
+            publish_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(5);
+
+        container(column![question, options, buttons].spacing(10))
+            .padding(10)
+            .style(style::Container::Foreground)
+            .into()
+    }
+}
+
+/// Poll with a results bar per option, `on_vote` is `None` when voting is disabled
+pub fn poll_card<'a, M: Clone + 'a>(
+    poll: &'a DbPoll,
+    tally: &'a PollTally,
+    on_vote: Option<fn(EventId, String) -> M>,
+) -> Element<'a, M> {
+    let total = tally.total();
+    let options = poll
+        .options
+        .iter()
+        .fold(column![].spacing(5), |col, option| {
+            let count = tally.count(&option.id);
+            let is_users = tally.user_vote.as_deref() == Some(option.id.as_str());
+
+            let mut vote_btn = button(text(&option.label).size(14))
+                .width(OPTION_BTN_WIDTH)
+                .style(if is_users {
+                    style::Button::Primary
+                } else {
+                    style::Button::Bordered
+                });
+            if let Some(on_vote) = on_vote {
+                vote_btn = vote_btn.on_press(on_vote(poll.poll_hash, option.id.to_owned()));
+            }
+
+            col.push(
+                row![
+                    vote_btn,
+                    results_bar(count, total),
+                    text(count).size(14).width(COUNT_WIDTH)
+                ]
+                .align_items(Alignment::Center)
+                .spacing(5),
+            )
+        });
+
+    container(
+        column![
+            text(&poll.question).size(18),
+            options,
+            text(format!("{} votes", total))
+                .size(12)
+                .style(style::Text::Placeholder)
+        ]
+        .spacing(5),
+    )
+    .padding(10)
+    .style(style::Container::Foreground)
+    .into()
+}
+
+fn results_bar<'a, M: 'a>(count: u32, total: u32) -> Element<'a, M> {
+    let bar: Element<_> = if count == 0 {
+        Space::with_width(Length::Fill).into()
+    } else {
+        // portions only need to keep the proportion
+        let filled = (count * BAR_RESOLUTION / total).max(1) as u16;
+        let empty = (BAR_RESOLUTION as u16).saturating_sub(filled);
+        let mut bar = row![container(Space::with_height(Length::Fill))
+            .width(Length::FillPortion(filled))
+            .height(Length::Fill)
+            .style(style::Container::Highlight)];
+        if empty > 0 {
+            bar = bar.push(Space::with_width(Length::FillPortion(empty)));
+        }
+        bar.into()
+    };
+    container(bar).width(Length::Fill).height(BAR_HEIGHT).into()
+}
+
+const MIN_OPTIONS: usize = 2;
+const MAX_OPTIONS: usize = 10;
+const OPTION_BTN_WIDTH: u16 = 150;
+const COUNT_WIDTH: u16 = 30;
+const BAR_HEIGHT: u16 = 12;
+const BAR_RESOLUTION: u32 = 100;
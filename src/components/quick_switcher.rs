@@ -0,0 +1,299 @@
+use iced::widget::{button, column, container, text, text_input};
+use iced::{keyboard, subscription, Command, Event, Length, Subscription};
+use nostr::EventId;
+use once_cell::sync::Lazy;
+
+use crate::components::card;
+use crate::db::DbContact;
+use crate::error::BackendClosed;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::utils::fuzzy::fuzzy_score;
+use crate::widget::Element;
+
+static INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+
+#[derive(Debug, Clone)]
+pub enum SwitcherTarget {
+    Contact(DbContact),
+    Channel(EventId),
+}
+
+/// Conversation listed by the switcher, `score` is its frecency
+#[derive(Debug, Clone)]
+pub struct SwitcherItem {
+    pub target: SwitcherTarget,
+    pub name: String,
+    pub score: f64,
+    /// Unsent composer text
+    pub draft: Option<String>,
+}
+
+impl SwitcherItem {
+    /// Private notes of a contact
+    fn notes(&self) -> Option<&str> {
+        match &self.target {
+            SwitcherTarget::Contact(contact) => contact.get_notes(),
+            SwitcherTarget::Channel(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Open,
+    Close,
+    QueryChange(String),
+    Next,
+    Previous,
+    Submit,
+    ItemPressed(usize),
+}
+
+pub enum Action {
+    Open(SwitcherTarget),
+    Close,
+}
+
+pub struct QuickSwitcher {
+    items: Vec<SwitcherItem>,
+    query: String,
+    /// Indexes into `items`, best match first
+    results: Vec<usize>,
+    selected: usize,
+}
+impl QuickSwitcher {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchSwitcherItems)?;
+        Ok(Self {
+            items: vec![],
+            query: "".into(),
+            results: vec![],
+            selected: 0,
+        })
+    }
+
+    /// Ctrl+T opens, arrows move the selection
+    pub fn subscription() -> Subscription<Message> {
+        subscription::events_with(|event, _status| match event {
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }) => match key_code {
+                keyboard::KeyCode::T if modifiers.command() => Some(Message::Open),
+                keyboard::KeyCode::Down => Some(Message::Next),
+                keyboard::KeyCode::Up => Some(Message::Previous),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    pub fn focus<M: 'static>() -> Command<M> {
+        text_input::focus(INPUT_ID.clone())
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent) {
+        if let BackendEvent::GotSwitcherItems(items) = event {
+            self.items = items;
+            self.filter();
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::Open => (),
+            Message::Close => return Some(Action::Close),
+            Message::QueryChange(query) => {
+                self.query = query;
+                self.filter();
+            }
+            Message::Next => {
+                if self.selected + 1 < self.results.len() {
+                    self.selected += 1;
+                }
+            }
+            Message::Previous => self.selected = self.selected.saturating_sub(1),
+            Message::Submit => return self.open(self.selected),
+            Message::ItemPressed(position) => return self.open(position),
+        }
+        None
+    }
+
+    fn open(&self, position: usize) -> Option<Action> {
+        let idx = self.results.get(position)?;
+        Some(Action::Open(self.items[*idx].target.to_owned()))
+    }
+
+    fn filter(&mut self) {
+        self.results = rank(&self.items, &self.query);
+        self.selected = 0;
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let input = text_input("Jump to a conversation", &self.query)
+            .id(INPUT_ID.clone())
+            .on_input(Message::QueryChange)
+            .on_submit(Message::Submit)
+            .padding(10);
+
+        let results =
+            self.results
+                .iter()
+                .enumerate()
+                .fold(column![].spacing(2), |col, (position, idx)| {
+                    let item = &self.items[*idx];
+                    let kind = match item.target {
+                        SwitcherTarget::Contact(_) => "",
+                        SwitcherTarget::Channel(_) => "# ",
+                    };
+                    let btn_style = if position == self.selected {
+                        style::Button::ActiveMenuBtn
+                    } else {
+                        style::Button::MenuBtn
+                    };
+                    let mut content = column![text(format!("{}{}", kind, item.name))];
+                    if let Some(draft) = &item.draft {
+                        content = content.push(
+                            text(format!("Draft: {}", draft_snippet(draft)))
+                                .size(14)
+                                .style(style::Text::Placeholder),
+                        );
+                    }
+                    if let Some(notes) = item.notes().filter(|n| contains(n, &self.query)) {
+                        content = content.push(
+                            text(format!("Notes: {}", draft_snippet(notes)))
+                                .size(14)
+                                .style(style::Text::Placeholder),
+                        );
+                    }
+                    col.push(
+                        button(content)
+                            .width(Length::Fill)
+                            .padding(8)
+                            .style(btn_style)
+                            .on_press(Message::ItemPressed(position)),
+                    )
+                });
+        let results: Element<_> = if self.results.is_empty() {
+            text("No conversations found")
+                .style(style::Text::Placeholder)
+                .into()
+        } else {
+            results.into()
+        };
+
+        card(
+            column![input, container(results).padding([5, 0])].spacing(10),
+            text("Enter to open, Esc to close")
+                .size(14)
+                .style(style::Text::Placeholder),
+        )
+        .max_width(SWITCHER_WIDTH)
+        .into()
+    }
+}
+
+/// First line of the draft, shortened
+pub fn draft_snippet(draft: &str) -> String {
+    let line = draft.lines().next().unwrap_or_default();
+    if line.chars().count() > DRAFT_SNIPPET_LEN || draft.lines().nth(1).is_some() {
+        let short: String = line.chars().take(DRAFT_SNIPPET_LEN).collect();
+        format!("{}...", short.trim_end())
+    } else {
+        line.to_owned()
+    }
+}
+
+/// Indexes of the matching items, best fuzzy match first and frecency breaking ties.
+/// Items whose draft or contact notes contain the query come after the name matches.
+fn rank(items: &[SwitcherItem], query: &str) -> Vec<usize> {
+    let mut matches: Vec<(usize, i64)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| {
+            fuzzy_score(query, &item.name)
+                .or_else(|| text_matches(query, item).then_some(0))
+                .map(|score| (idx, score))
+        })
+        .collect();
+    matches.sort_by(|(a_idx, a_score), (b_idx, b_score)| {
+        let (a, b) = (&items[*a_idx], &items[*b_idx]);
+        b_score
+            .cmp(a_score)
+            .then(b.score.total_cmp(&a.score))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    matches
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn text_matches(query: &str, item: &SwitcherItem) -> bool {
+    item.draft
+        .as_deref()
+        .map_or(false, |draft| contains(draft, query))
+        || item.notes().map_or(false, |notes| contains(notes, query))
+}
+
+/// Case insensitive, an empty query matches nothing
+fn contains(text: &str, query: &str) -> bool {
+    let query = query.trim().to_lowercase();
+    !query.is_empty() && text.to_lowercase().contains(&query)
+}
+
+const SWITCHER_WIDTH: f32 = 450.0;
+const MAX_RESULTS: usize = 10;
+const DRAFT_SNIPPET_LEN: usize = 60;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(name: &str, score: f64) -> SwitcherItem {
+        SwitcherItem {
+            target: SwitcherTarget::Channel(EventId::from_slice(&[1; 32]).unwrap()),
+            name: name.into(),
+            score,
+            draft: None,
+        }
+    }
+
+    #[test]
+    fn frecency_breaks_ties() {
+        let items = vec![
+            channel("rust", 10.0),
+            channel("rust", 300.0),
+            channel("go", 900.0),
+        ];
+        assert_eq!(rank(&items, "rust"), vec![1, 0]);
+        assert_eq!(rank(&items, ""), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn drafts_are_searchable() {
+        let mut items = vec![channel("rust", 10.0), channel("meetup", 10.0)];
+        items[0].draft = Some("see you at the meetup".into());
+        assert_eq!(rank(&items, "meetup"), vec![1, 0]);
+        assert_eq!(rank(&items, "see you"), vec![0]);
+    }
+
+    #[test]
+    fn contact_notes_are_searchable() {
+        let keys = nostr::Keys::generate();
+        let contact = DbContact::new(&keys.public_key()).with_notes("Met at the Rust meetup");
+        let items = vec![
+            channel("meetup", 10.0),
+            SwitcherItem {
+                target: SwitcherTarget::Contact(contact),
+                name: "alice".into(),
+                score: 900.0,
+                draft: None,
+            },
+        ];
+        assert_eq!(rank(&items, "meetup"), vec![0, 1]);
+        assert_eq!(rank(&items, "rust meetup"), vec![1]);
+    }
+}
@@ -16,6 +16,7 @@ pub enum Message {
     DeleteRelay,
     ToggleRead,
     ToggleWrite,
+    ToggleAnonymousAuth,
     OpenRelayDocument(DbRelay),
     ReconnectRelay,
 }
@@ -63,6 +64,11 @@ impl RelayRow {
             Message::ToggleWrite => {
                 conn.send(net::ToBackend::ToggleRelayWrite(self.db_relay.to_owned()))?;
             }
+            Message::ToggleAnonymousAuth => {
+                conn.send(net::ToBackend::ToggleRelayAnonymousAuth(
+                    self.db_relay.to_owned(),
+                ))?;
+            }
         }
         Ok(Command::none())
     }
@@ -97,6 +103,16 @@ impl RelayRow {
             container(text("Write"))
                 .center_x()
                 .width(Length::Fixed(CHECKBOX_CELL_WIDTH)),
+            container(
+                tooltip(
+                    text("Anon"),
+                    "Authenticate with a throwaway key instead of your identity",
+                    tooltip::Position::Top
+                )
+                .style(style::Container::TooltipBg)
+            )
+            .center_x()
+            .width(Length::Fixed(CHECKBOX_CELL_WIDTH)),
             container(text(""))
                 .center_x()
                 .width(Length::Fixed(ACTION_ICON_WIDTH)),
@@ -118,13 +134,11 @@ impl RelayRow {
             .style(style::Button::Primary)
             .width(Length::Fixed(ACTION_ICON_WIDTH));
 
-        if let Some(information) = &self.db_relay.information {
-            if information.document.is_some() {
-                doc_btn = doc_btn.on_press(MessageWrapper::new(
-                    self.id,
-                    Message::OpenRelayDocument(self.db_relay.clone()),
-                ));
-            }
+        if self.db_relay.document().is_some() {
+            doc_btn = doc_btn.on_press(MessageWrapper::new(
+                self.id,
+                Message::OpenRelayDocument(self.db_relay.clone()),
+            ));
         }
 
         let document_btn = tooltip(doc_btn, "Relay Document", tooltip::Position::Left)
@@ -182,6 +196,11 @@ impl RelayRow {
                 )))
                 .center_x()
                 .width(Length::Fixed(CHECKBOX_CELL_WIDTH)),
+                container(checkbox("", self.db_relay.anonymous_auth, |_| {
+                    MessageWrapper::new(self.id, Message::ToggleAnonymousAuth)
+                }))
+                .center_x()
+                .width(Length::Fixed(CHECKBOX_CELL_WIDTH)),
                 document_btn,
                 reconnect_btn,
                 delete_btn,
@@ -1,10 +1,10 @@
-use iced::widget::{button, container, row, text, Space};
+use iced::widget::{button, container, row, text, tooltip, Space};
 use iced::Subscription;
 use iced::{alignment, Alignment, Command, Length};
 
 use crate::consts::NOSTRTALK_VERSION;
 use crate::error::BackendClosed;
-use crate::icon::signal_icon;
+use crate::icon::{eye_slash_icon, regular_bell_icon, signal_icon, triangle_warn_icon};
 use crate::net::{self, BackEndConnection, BackendEvent};
 use crate::style;
 use crate::views::{GoToView, RouterCommand};
@@ -14,27 +14,77 @@ use crate::widget::Element;
 pub enum Message {
     GoToAbout,
     GoToNetwork,
+    ToggleRelayProfiles,
+    SwitchRelayProfile(String),
     Tick,
 }
 pub struct StatusBar {
     relays_connected: usize,
+    backpressure: bool,
+    /// Metadata requests wait for the relays' budget
+    throttling: bool,
+    do_not_track: bool,
+    restarting: bool,
+    /// Latest NTP offset in microseconds
+    clock_offset: Option<i64>,
+    relay_profiles: Vec<String>,
+    active_relay_profile: Option<String>,
+    /// Other profiles are listed to switch to
+    relay_profiles_open: bool,
+    /// Unseen DMs and unread channel messages
+    unread_total: i64,
+    /// New messages arrived, counted again on the next tick
+    unread_stale: bool,
 }
 impl StatusBar {
-    pub fn new() -> Self {
-        Self {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(net::ToBackend::GetDoNotTrack)?;
+        conn.send(net::ToBackend::FetchRelayProfiles)?;
+        conn.send(net::ToBackend::FetchUnreadTotal)?;
+        Ok(Self {
             relays_connected: 0,
-        }
+            backpressure: false,
+            throttling: false,
+            do_not_track: false,
+            restarting: false,
+            clock_offset: None,
+            relay_profiles: vec![],
+            active_relay_profile: None,
+            relay_profiles_open: false,
+            unread_total: 0,
+            unread_stale: false,
+        })
     }
     pub fn backend_event(
         &mut self,
         event: BackendEvent,
         _conn: &mut BackEndConnection,
     ) -> Command<Message> {
-        if let BackendEvent::GotRelayStatusList(list) = event {
-            self.relays_connected = list
-                .iter()
-                .filter(|(_url, status)| status.is_connected())
-                .count();
+        match event {
+            BackendEvent::GotRelayStatusList(list) => {
+                self.relays_connected = list
+                    .iter()
+                    .filter(|(_url, status)| status.is_connected())
+                    .count();
+            }
+            BackendEvent::Backpressure(congested) => self.backpressure = congested,
+            BackendEvent::Throttling(throttling) => self.throttling = throttling,
+            BackendEvent::DoNotTrack(do_not_track) => self.do_not_track = do_not_track,
+            BackendEvent::BackendRestarting(_) => self.restarting = true,
+            BackendEvent::BackendRestarted => self.restarting = false,
+            BackendEvent::NtpDriftChanged(offset) => self.clock_offset = Some(offset),
+            BackendEvent::GotRelayProfiles { profiles, active } => {
+                self.relay_profiles = profiles.into_iter().map(|p| p.name).collect();
+                self.active_relay_profile = active;
+            }
+            BackendEvent::UnreadTotal(total) => {
+                self.unread_total = total;
+                self.unread_stale = false;
+            }
+            BackendEvent::ReceivedDM { .. }
+            | BackendEvent::ReceivedChannelMessage(..)
+            | BackendEvent::GotChatMessages(..) => self.unread_stale = true,
+            _ => (),
         }
         Command::none()
     }
@@ -47,8 +97,20 @@ impl StatusBar {
         match message {
             Message::GoToAbout => command.change_route(GoToView::About),
             Message::GoToNetwork => command.change_route(GoToView::Network),
+            Message::ToggleRelayProfiles => self.relay_profiles_open = !self.relay_profiles_open,
+            Message::SwitchRelayProfile(name) => {
+                self.relay_profiles_open = false;
+                conn.send(net::ToBackend::SwitchRelayProfile(name))?;
+            }
             Message::Tick => {
-                conn.send(net::ToBackend::GetRelayStatusList)?;
+                // don't pile up more work while the backend is catching up
+                if !self.backpressure {
+                    conn.send(net::ToBackend::GetRelayStatusList)?;
+                    if self.unread_stale {
+                        self.unread_stale = false;
+                        conn.send(net::ToBackend::FetchUnreadTotal)?;
+                    }
+                }
             }
         }
         Ok(command)
@@ -72,8 +134,95 @@ impl StatusBar {
         .on_press(Message::GoToNetwork)
         .style(style::Button::StatusBarButton);
 
+        let busy: Element<_> = if self.restarting {
+            text("Reconnecting backend…")
+                .size(18)
+                .style(style::Text::Danger)
+                .into()
+        } else if self.backpressure {
+            text("Busy…").size(18).into()
+        } else if self.throttling {
+            tooltip(
+                text("Throttling…").size(18),
+                "Waiting to stay within the relays' request limits",
+                tooltip::Position::Top,
+            )
+            .style(style::Container::TooltipBg)
+            .into()
+        } else {
+            text("").into()
+        };
+
+        let do_not_track: Element<_> = if self.do_not_track {
+            tooltip(
+                button(eye_slash_icon().size(12))
+                    .height(Length::Fill)
+                    .padding([0, 2])
+                    .on_press(Message::GoToNetwork)
+                    .style(style::Button::StatusBarButton),
+                "Do not track",
+                tooltip::Position::Top,
+            )
+            .style(style::Container::TooltipBg)
+            .into()
+        } else {
+            text("").into()
+        };
+
+        let clock_drift: Element<_> = match self.clock_offset {
+            Some(offset) if offset.abs() >= CLOCK_DRIFT_WARNING_MICROS => tooltip(
+                button(
+                    row![
+                        triangle_warn_icon().size(12),
+                        text(format!("{:+.1}s", offset as f64 / 1_000_000.0)).size(14)
+                    ]
+                    .spacing(2)
+                    .align_items(Alignment::Center),
+                )
+                .height(Length::Fill)
+                .padding([0, 2])
+                .on_press(Message::GoToNetwork)
+                .style(style::Button::StatusBarButton),
+                "System clock differs from network time. Timestamps are being corrected.",
+                tooltip::Position::Top,
+            )
+            .style(style::Container::TooltipBg)
+            .into(),
+            _ => text("").into(),
+        };
+
+        let unread: Element<_> = if self.unread_total > 0 {
+            let count_txt = if self.unread_total > 99 {
+                "99+".to_owned()
+            } else {
+                self.unread_total.to_string()
+            };
+            tooltip(
+                row![regular_bell_icon().size(12), text(count_txt).size(18)]
+                    .spacing(2)
+                    .align_items(Alignment::Center),
+                "Unread messages",
+                tooltip::Position::Top,
+            )
+            .style(style::Container::TooltipBg)
+            .into()
+        } else {
+            text("").into()
+        };
+
         container(
-            row![about, Space::with_width(Length::Fill), signal].align_items(Alignment::Center),
+            row![
+                about,
+                Space::with_width(Length::Fill),
+                busy,
+                clock_drift,
+                do_not_track,
+                unread,
+                self.relay_profiles_view(),
+                signal
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5),
         )
         .padding(0)
         .align_x(alignment::Horizontal::Right)
@@ -85,5 +234,47 @@ impl StatusBar {
     }
 }
 
+impl StatusBar {
+    /// Active relay profile, when open the others are listed before it
+    fn relay_profiles_view(&self) -> Element<'static, Message> {
+        if self.relay_profiles.is_empty() {
+            return text("").into();
+        }
+        let active = self
+            .active_relay_profile
+            .clone()
+            .unwrap_or_else(|| "No profile".into());
+        let others = self
+            .relay_profiles
+            .iter()
+            .filter(|name| Some(*name) != self.active_relay_profile.as_ref())
+            .filter(|_| self.relay_profiles_open)
+            .fold(row![].spacing(5), |row, name| {
+                row.push(
+                    button(text(name).size(14))
+                        .height(Length::Fill)
+                        .padding([0, 2])
+                        .on_press(Message::SwitchRelayProfile(name.to_owned()))
+                        .style(style::Button::StatusBarButton),
+                )
+            });
+        let active = tooltip(
+            button(text(active).size(14).style(style::Text::Primary))
+                .height(Length::Fill)
+                .padding([0, 2])
+                .on_press(Message::ToggleRelayProfiles)
+                .style(style::Button::StatusBarButton),
+            "Relay profile",
+            tooltip::Position::Top,
+        )
+        .style(style::Container::TooltipBg);
+        row![others, active]
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .into()
+    }
+}
+
 pub const STATUS_BAR_HEIGHT: f32 = 20.0;
+const CLOCK_DRIFT_WARNING_MICROS: i64 = 1_000_000;
 const TICK_INTERVAL_MILLIS: u64 = 500;
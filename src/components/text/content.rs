@@ -0,0 +1,517 @@
+//! Rendering of text written by other users: messages, profiles and channel descriptions.
+//!
+//! Nothing in the content is interpreted but links and hashtags, control characters are removed,
+//! absurdly long words are cut and long content is collapsed until expanded.
+//! Right-to-left lines are reordered for display and the content is aligned
+//! to the right when it starts in a right-to-left language.
+
+use std::borrow::Cow;
+
+use iced::widget::{button, column, text, Row};
+use iced::Alignment;
+use iced_lazy::Component;
+use url::Url;
+
+use crate::components::hover_card::{self, HoverCards};
+use crate::config::TextDirection;
+use crate::net::ingress::sanitize_text;
+use crate::style;
+use crate::types::hashtag::{normalize_hashtag, split_inline_hashtag};
+use crate::types::NostrLink;
+use crate::utils::text_direction::{visual_line, Direction};
+use crate::widget::{Column, Element, Renderer};
+
+/// Piece of a line of content
+#[derive(Debug, Clone, PartialEq)]
+pub enum Span {
+    Text(String),
+    /// http or https address
+    Url(String),
+    /// `nostr:` URI as written
+    Nostr(String, NostrLink),
+    /// Hashtag as written, without the `#`
+    Hashtag(String),
+}
+impl Span {
+    fn is_link(&self) -> bool {
+        !matches!(self, Span::Text(_))
+    }
+    fn len(&self) -> usize {
+        match self {
+            Span::Text(text) | Span::Url(text) | Span::Nostr(text, _) => text.chars().count(),
+            Span::Hashtag(hashtag) => hashtag.chars().count() + 1,
+        }
+    }
+}
+
+/// Splits each line in text, links and hashtags
+pub fn parse(content: &str) -> Vec<Vec<Span>> {
+    content.lines().map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut plain = String::new();
+    for token in line.split_inclusive(char::is_whitespace) {
+        let word = token.trim_end();
+        let (lead, word) = split_leading_punctuation(word);
+        let (link, rest) = split_trailing_punctuation(word);
+        let span = if is_url(link) {
+            Some((Span::Url(link.to_owned()), rest))
+        } else if link.starts_with(NOSTR_SCHEME) {
+            NostrLink::parse(link).map(|parsed| (Span::Nostr(link.to_owned(), parsed), rest))
+        } else {
+            split_inline_hashtag(word)
+                .map(|(hashtag, rest)| (Span::Hashtag(hashtag.to_owned()), rest))
+        };
+        match span {
+            Some((span, rest)) => {
+                plain.push_str(lead);
+                if !plain.is_empty() {
+                    spans.push(Span::Text(std::mem::take(&mut plain)));
+                }
+                spans.push(span);
+                plain.push_str(rest);
+                plain.push_str(&token[lead.len() + word.len()..]);
+            }
+            None => plain.push_str(token),
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::Text(plain));
+    }
+    spans
+}
+
+/// Hashtags joined to the text around them
+fn hashtags_as_text(spans: Vec<Span>) -> Vec<Span> {
+    let mut merged: Vec<Span> = vec![];
+    for span in spans {
+        let span = match span {
+            Span::Hashtag(hashtag) => Span::Text(format!("#{}", hashtag)),
+            other => other,
+        };
+        if let (Some(Span::Text(last)), Span::Text(text)) = (merged.last_mut(), &span) {
+            last.push_str(text);
+            continue;
+        }
+        merged.push(span);
+    }
+    merged
+}
+
+fn is_url(word: &str) -> bool {
+    (word.starts_with("https://") || word.starts_with("http://"))
+        && Url::parse(word).map_or(false, |url| url.host().is_some())
+}
+
+/// Opening parenthesis or quotes before a link
+fn split_leading_punctuation(word: &str) -> (&str, &str) {
+    let start = word
+        .find(|c| !matches!(c, '(' | '"' | '\'' | '<'))
+        .unwrap_or(word.len());
+    word.split_at(start)
+}
+
+/// Punctuation ending a sentence isn't part of the link,
+/// a closing parenthesis is kept when the link opened one
+fn split_trailing_punctuation(word: &str) -> (&str, &str) {
+    let mut end = word.len();
+    for (idx, c) in word.char_indices().rev() {
+        let keep_paren = c == ')' && word[..idx].contains('(');
+        if !matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | '"' | '\'' | ')') || keep_paren {
+            break;
+        }
+        end = idx;
+    }
+    word.split_at(end)
+}
+
+/// Prefix shown while collapsed, `None` when the content is short enough
+pub fn collapsed(content: &str) -> Option<&str> {
+    let mut end = content.len();
+    if let Some((idx, _)) = content.match_indices('\n').nth(MAX_COLLAPSED_LINES - 1) {
+        end = idx;
+    }
+    if let Some((idx, _)) = content.char_indices().nth(MAX_COLLAPSED_CHARS) {
+        if idx < end {
+            // cuts between words when there is one to cut at
+            end = content[..idx]
+                .rfind(char::is_whitespace)
+                .filter(|space| *space > 0)
+                .unwrap_or(idx);
+        }
+    }
+    (end < content.len()).then(|| content[..end].trim_end())
+}
+
+/// Cuts words longer than `max` chars
+pub fn shorten_words(text: &str, max: usize) -> Cow<str> {
+    if !text
+        .split_whitespace()
+        .any(|word| word.chars().count() > max)
+    {
+        return Cow::Borrowed(text);
+    }
+    let shortened = text
+        .split_inclusive(char::is_whitespace)
+        .map(|token| {
+            let word = token.trim_end();
+            if word.chars().count() > max {
+                let cut: String = word.chars().take(max).collect();
+                format!("{}…{}", cut, &token[word.len()..])
+            } else {
+                token.to_owned()
+            }
+        })
+        .collect();
+    Cow::Owned(shortened)
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    ToggleExpanded,
+    UrlPressed(String),
+    NostrPressed(String, NostrLink),
+    HashtagPressed(String),
+    HoverCard(hover_card::Message),
+}
+
+#[derive(Default)]
+pub struct State {
+    expanded: bool,
+}
+
+/// User content with clickable links.
+/// Addresses open in the browser, `nostr:` URIs go to `on_nostr_link`
+/// or to the system handler when it's not set.
+/// Hashtags are chips when `on_hashtag` is set, plain text otherwise.
+/// Mentions show a hover card when `mention_cards` is set.
+#[allow(missing_debug_implementations)]
+pub struct RichText<'a, Message> {
+    content: String,
+    size: u16,
+    style: style::Text,
+    on_nostr_link: Option<Box<dyn Fn(NostrLink) -> Message + 'a>>,
+    on_hashtag: Option<Box<dyn Fn(String) -> Message + 'a>>,
+    mention_cards: Option<MentionCards<'a, Message>>,
+    text_direction: TextDirection,
+}
+
+type MentionCards<'a, Message> = (
+    &'a HoverCards,
+    Box<dyn Fn(hover_card::Message) -> Message + 'a>,
+);
+
+impl<'a, Message> RichText<'a, Message> {
+    pub fn new(content: &str) -> Self {
+        Self {
+            content: sanitize_text(content).into_owned(),
+            size: DEFAULT_SIZE,
+            style: style::Text::Default,
+            on_nostr_link: None,
+            on_hashtag: None,
+            mention_cards: None,
+            text_direction: TextDirection::Auto,
+        }
+    }
+
+    pub fn size(mut self, size: u16) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn style(mut self, style: style::Text) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn on_nostr_link(mut self, on_nostr_link: impl Fn(NostrLink) -> Message + 'a) -> Self {
+        self.on_nostr_link = Some(Box::new(on_nostr_link));
+        self
+    }
+
+    /// Receives the hashtag normalized
+    pub fn on_hashtag(mut self, on_hashtag: impl Fn(String) -> Message + 'a) -> Self {
+        self.on_hashtag = Some(Box::new(on_hashtag));
+        self
+    }
+
+    pub fn mention_cards(
+        mut self,
+        cards: &'a HoverCards,
+        on_card: impl Fn(hover_card::Message) -> Message + 'a,
+    ) -> Self {
+        self.mention_cards = Some((cards, Box::new(on_card)));
+        self
+    }
+
+    /// Direction chosen in the appearance settings, detected per line by default
+    pub fn text_direction(mut self, setting: TextDirection) -> Self {
+        self.text_direction = setting;
+        self
+    }
+
+    fn line_view(
+        &self,
+        spans: &[Span],
+        direction: Direction,
+        expanded: bool,
+    ) -> Element<'_, Event> {
+        let mut elements: Vec<Element<_>> = spans
+            .iter()
+            .map(|span| self.span_view(span, direction, expanded))
+            .collect();
+        let has_links = spans.iter().any(Span::is_link);
+        let length: usize = spans.iter().map(Span::len).sum();
+        // rows don't wrap, long lines stack their pieces instead
+        if has_links && length <= MAX_INLINE_CHARS {
+            if direction.is_rtl() {
+                elements.reverse();
+            }
+            Row::with_children(elements)
+                .align_items(Alignment::Center)
+                .into()
+        } else {
+            Column::with_children(elements)
+                .align_items(alignment_of(direction))
+                .into()
+        }
+    }
+
+    fn span_view(&self, span: &Span, direction: Direction, expanded: bool) -> Element<'_, Event> {
+        match span {
+            Span::Text(content) => {
+                let content = if expanded {
+                    Cow::Borrowed(content.as_str())
+                } else {
+                    shorten_words(content, MAX_WORD_CHARS)
+                };
+                // reordered before wrapping, wrapped right-to-left lines
+                // read from the bottom row up
+                let content = visual_line(&content, direction).into_owned();
+                text(content)
+                    .size(self.size)
+                    .style(self.style)
+                    .horizontal_alignment(direction.alignment())
+                    .into()
+            }
+            Span::Url(url) => link_button(url, self.size, Event::UrlPressed(url.to_owned())),
+            Span::Nostr(uri, link) => {
+                let link_btn = link_button(
+                    uri,
+                    self.size,
+                    Event::NostrPressed(uri.to_owned(), link.to_owned()),
+                );
+                match (link, &self.mention_cards) {
+                    (NostrLink::Profile(pubkey, _), Some((cards, _))) => {
+                        cards.wrap(pubkey, link_btn, Event::HoverCard)
+                    }
+                    _ => link_btn,
+                }
+            }
+            Span::Hashtag(hashtag) => {
+                button(text(format!("#{}", hashtag)).size(self.size.saturating_sub(2)))
+                    .padding([0, 6])
+                    .style(style::Button::Bordered)
+                    .on_press(Event::HashtagPressed(hashtag.to_owned()))
+                    .into()
+            }
+        }
+    }
+}
+
+fn alignment_of(direction: Direction) -> Alignment {
+    match direction {
+        Direction::LeftToRight => Alignment::Start,
+        Direction::RightToLeft => Alignment::End,
+    }
+}
+
+fn link_button<'a>(label: &str, size: u16, event: Event) -> Element<'a, Event> {
+    let label = if label.chars().count() > MAX_LINK_CHARS {
+        let cut: String = label.chars().take(MAX_LINK_CHARS).collect();
+        format!("{}…", cut)
+    } else {
+        label.to_owned()
+    };
+    button(text(label).size(size))
+        .padding(0)
+        .style(style::Button::Link)
+        .on_press(event)
+        .into()
+}
+
+impl<'a, Message> Component<Message, Renderer> for RichText<'a, Message> {
+    type State = State;
+    type Event = Event;
+
+    fn update(&mut self, state: &mut Self::State, event: Self::Event) -> Option<Message> {
+        match event {
+            Event::ToggleExpanded => state.expanded = !state.expanded,
+            Event::UrlPressed(url) => {
+                if let Err(e) = webbrowser::open(&url) {
+                    tracing::error!("Failed to open link: {}", e);
+                }
+            }
+            Event::NostrPressed(uri, link) => match &self.on_nostr_link {
+                Some(on_nostr_link) => return Some(on_nostr_link(link)),
+                None => {
+                    if let Err(e) = webbrowser::open(&uri) {
+                        tracing::error!("Failed to open link: {}", e);
+                    }
+                }
+            },
+            Event::HashtagPressed(hashtag) => {
+                if let (Some(on_hashtag), Some(hashtag)) =
+                    (&self.on_hashtag, normalize_hashtag(&hashtag))
+                {
+                    return Some(on_hashtag(hashtag));
+                }
+            }
+            Event::HoverCard(message) => {
+                if let Some((_, on_card)) = &self.mention_cards {
+                    return Some(on_card(message));
+                }
+            }
+        }
+        None
+    }
+
+    fn view(&self, state: &Self::State) -> Element<'_, Self::Event> {
+        let collapsed = collapsed(&self.content);
+        let shown = match collapsed {
+            Some(prefix) if !state.expanded => prefix,
+            _ => &self.content,
+        };
+        let direction = Direction::of(shown, self.text_direction);
+        let lines = shown
+            .lines()
+            .zip(parse(shown))
+            .fold(column![], |col, (line, spans)| {
+                let spans = if self.on_hashtag.is_some() {
+                    spans
+                } else {
+                    hashtags_as_text(spans)
+                };
+                // each line is a paragraph with its own direction
+                let line_direction = Direction::of_line(line, direction, self.text_direction);
+                col.push(self.line_view(&spans, line_direction, state.expanded))
+            })
+            .align_items(alignment_of(direction));
+        if collapsed.is_none() {
+            return lines.into();
+        }
+        let toggle_label = if state.expanded {
+            "Show less"
+        } else {
+            "Show more"
+        };
+        column![
+            lines,
+            button(text(toggle_label).size(self.size.saturating_sub(4)))
+                .padding(0)
+                .style(style::Button::Link)
+                .on_press(Event::ToggleExpanded)
+        ]
+        .spacing(2)
+        .align_items(alignment_of(direction))
+        .into()
+    }
+}
+
+impl<'a, Message: 'a> From<RichText<'a, Message>> for Element<'a, Message> {
+    fn from(rich_text: RichText<'a, Message>) -> Self {
+        iced_lazy::component(rich_text)
+    }
+}
+
+/// User content with clickable links, see [`RichText`]
+pub fn rich_text<'a, Message>(content: &str) -> RichText<'a, Message> {
+    RichText::new(content)
+}
+
+const NOSTR_SCHEME: &str = "nostr:";
+const DEFAULT_SIZE: u16 = 18;
+const MAX_WORD_CHARS: usize = 80;
+const MAX_LINK_CHARS: usize = 60;
+/// Lines with links up to this length are kept on one row
+const MAX_INLINE_CHARS: usize = 60;
+const MAX_COLLAPSED_LINES: usize = 20;
+const MAX_COLLAPSED_CHARS: usize = 1_500;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_links_in_text() {
+        let line = "see https://example.com/a_(b) and (https://nostr.how). <b>ok</b>";
+        assert_eq!(
+            parse_line(line),
+            vec![
+                Span::Text("see ".into()),
+                Span::Url("https://example.com/a_(b)".into()),
+                Span::Text(" and (".into()),
+                Span::Url("https://nostr.how".into()),
+                Span::Text("). <b>ok</b>".into()),
+            ]
+        );
+        assert_eq!(
+            parse_line("nostr:hello https:// ftp://example.com"),
+            vec![Span::Text("nostr:hello https:// ftp://example.com".into())]
+        );
+    }
+
+    #[test]
+    fn parses_nostr_uris() {
+        let event_id = nostr::EventId::from_slice(&[1; 32]).unwrap();
+        let note = nostr::prelude::ToBech32::to_bech32(&event_id).unwrap();
+        let uri = format!("nostr:{}", note);
+        let spans = parse_line(&format!("look {}!", uri));
+        assert_eq!(
+            spans,
+            vec![
+                Span::Text("look ".into()),
+                Span::Nostr(uri, NostrLink::Event(event_id, vec![])),
+                Span::Text("!".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_hashtags() {
+        assert_eq!(
+            parse_line("about (#Rust), #1 and C#"),
+            vec![
+                Span::Text("about (".into()),
+                Span::Hashtag("Rust".into()),
+                Span::Text("), #1 and C#".into()),
+            ]
+        );
+        assert_eq!(
+            hashtags_as_text(parse_line("about #rust today")),
+            vec![Span::Text("about #rust today".into())]
+        );
+    }
+
+    #[test]
+    fn collapses_long_content() {
+        assert_eq!(collapsed("short\nmessage"), None);
+
+        let lines = vec!["line"; MAX_COLLAPSED_LINES + 5].join("\n");
+        let prefix = collapsed(&lines).unwrap();
+        assert_eq!(prefix.lines().count(), MAX_COLLAPSED_LINES);
+
+        let words = "word ".repeat(MAX_COLLAPSED_CHARS);
+        let prefix = collapsed(&words).unwrap();
+        assert!(prefix.chars().count() <= MAX_COLLAPSED_CHARS);
+        assert!(prefix.ends_with("word"));
+    }
+
+    #[test]
+    fn cuts_long_words() {
+        assert_eq!(shorten_words("a b", 3), "a b");
+        assert_eq!(shorten_words("abcdef ok\n", 3), "abc… ok\n");
+    }
+}
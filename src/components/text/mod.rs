@@ -1,2 +1,4 @@
+mod content;
 mod title;
+pub use content::{rich_text, RichText};
 pub use title::title;
@@ -12,6 +12,7 @@ pub struct TextInputGroup<'a, Message: Clone + 'a> {
     is_invalid: bool,
     invalid_message: String,
     is_disabled: bool,
+    is_password: bool,
 }
 
 impl<'a, Message: Clone + 'a> TextInputGroup<'a, Message> {
@@ -30,6 +31,7 @@ impl<'a, Message: Clone + 'a> TextInputGroup<'a, Message> {
             is_invalid: false,
             invalid_message: String::from(""),
             is_disabled: false,
+            is_password: false,
         }
     }
 
@@ -59,6 +61,12 @@ impl<'a, Message: Clone + 'a> TextInputGroup<'a, Message> {
         self
     }
 
+    /// Hides what is typed
+    pub fn password(mut self) -> Self {
+        self.is_password = true;
+        self
+    }
+
     pub fn build(self) -> Element<'a, Message> {
         text_input_group(
             self.label_str,
@@ -70,10 +78,12 @@ impl<'a, Message: Clone + 'a> TextInputGroup<'a, Message> {
             self.is_invalid,
             &self.invalid_message,
             self.is_disabled,
+            self.is_password,
         )
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn text_input_group<'a, Message: Clone + 'a>(
     label_str: &str,
     placeholder: &str,
@@ -84,6 +94,7 @@ fn text_input_group<'a, Message: Clone + 'a>(
     is_invalid: bool,
     invalid_message: &str,
     is_disabled: bool,
+    is_password: bool,
 ) -> Element<'a, Message> {
     let text_input_style = if is_invalid {
         style::TextInput::Invalid
@@ -111,6 +122,9 @@ fn text_input_group<'a, Message: Clone + 'a>(
     let label_row = row![label, tooltip].spacing(5);
 
     let mut txt_input = text_input(placeholder, value).style(text_input_style);
+    if is_password {
+        txt_input = txt_input.password();
+    }
     if !is_disabled {
         txt_input = txt_input.on_input(on_change);
         if let Some(on_submit) = on_submit {
@@ -1,5 +1,5 @@
-use directories::ProjectDirs;
-use std::{fs, io::Write, path::PathBuf};
+use directories::{ProjectDirs, UserDirs};
+use std::{fs, io::Write, path::PathBuf, time::Duration};
 use tokio::io::AsyncWriteExt;
 
 use serde::{Deserialize, Serialize};
@@ -25,6 +25,443 @@ pub enum Error {
 pub struct Config {
     /// Theme of the application
     pub theme: Theme,
+    /// Hold contact list changes until the user reviews and publishes them
+    #[serde(default)]
+    pub stage_contact_changes: bool,
+    /// Provider used to translate messages
+    #[serde(default)]
+    pub translation: TranslationConfig,
+    /// Don't send anything besides the user's explicit actions
+    #[serde(default)]
+    pub do_not_track: bool,
+    /// How images are prepared before being uploaded
+    #[serde(default)]
+    pub attachments: AttachmentConfig,
+    /// Limits enforced by the background pruning
+    #[serde(default)]
+    pub storage: StorageConfig,
+    /// Turning relays' read and write off based on their activity
+    #[serde(default)]
+    pub relay_tuning: RelayTuningConfig,
+    /// Shows the raw REQ console in the settings
+    #[serde(default)]
+    pub developer_console: bool,
+    /// Media is downloaded through this service instead of from its host
+    #[serde(default)]
+    pub media_proxy: MediaProxyConfig,
+    /// Key that sends the message in the composers
+    #[serde(default)]
+    pub send_key: SendKey,
+    /// Images are dimmed in dark themes
+    #[serde(default)]
+    pub dim_images: bool,
+    /// Locking the app after a while without input
+    #[serde(default)]
+    pub auto_lock: AutoLockConfig,
+    /// How times and dates are written
+    #[serde(default)]
+    pub time_format: TimeFormatConfig,
+    /// Status published to the contacts
+    #[serde(default)]
+    pub presence: PresenceConfig,
+    /// Where attachments of direct messages are saved
+    #[serde(default)]
+    pub downloads: DownloadConfig,
+    /// Direction of messages and profile fields, detected from their script unless forced
+    #[serde(default)]
+    pub text_direction: TextDirection,
+    /// Opened once the client finished preparing after the login
+    #[serde(default)]
+    pub startup_view: StartupView,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TranslationConfig {
+    /// LibreTranslate compatible endpoint, translation is off without it
+    pub endpoint: Option<String>,
+    pub api_key: Option<String>,
+    /// Language code messages are translated into
+    pub target_lang: String,
+}
+impl TranslationConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.endpoint
+            .as_ref()
+            .map_or(false, |endpoint| !endpoint.trim().is_empty())
+    }
+}
+impl Default for TranslationConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            api_key: None,
+            target_lang: DEFAULT_TARGET_LANG.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentConfig {
+    /// Re-encode images, dropping EXIF and GPS data
+    pub strip_metadata: bool,
+    /// Larger images are downscaled to fit, in pixels
+    pub max_dimension: Option<u32>,
+    /// From 1 to 100
+    pub jpeg_quality: u8,
+    /// NIP-96 media server profile images are uploaded to
+    #[serde(default = "default_upload_server")]
+    pub upload_server: String,
+    /// Larger attachments are refused before being read, in megabytes
+    #[serde(default = "default_max_upload_mb")]
+    pub max_upload_mb: u64,
+}
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            strip_metadata: true,
+            max_dimension: Some(DEFAULT_MAX_DIMENSION),
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            upload_server: default_upload_server(),
+            max_upload_mb: default_max_upload_mb(),
+        }
+    }
+}
+impl AttachmentConfig {
+    pub fn max_upload_bytes(&self) -> u64 {
+        self.max_upload_mb * BYTES_PER_MB
+    }
+}
+
+fn default_upload_server() -> String {
+    DEFAULT_UPLOAD_SERVER.into()
+}
+fn default_max_upload_mb() -> u64 {
+    DEFAULT_MAX_UPLOAD_MB
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageConfig {
+    /// Oldest messages are pruned past this size, in megabytes
+    pub max_db_size_mb: Option<u64>,
+    /// Newest messages kept in each channel
+    pub max_channel_messages: Option<u32>,
+    /// Direct messages are never pruned unless this is set
+    pub prune_dms: bool,
+}
+impl StorageConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_db_size_mb.is_some() || self.max_channel_messages.is_some()
+    }
+    pub fn max_db_size_bytes(&self) -> Option<u64> {
+        self.max_db_size_mb.map(|mb| mb * BYTES_PER_MB)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RelayTuningConfig {
+    /// Suggestions are applied at login instead of only being shown
+    pub auto_apply: bool,
+    /// Relays that delivered nothing new for this long stop being read
+    pub idle_days: u32,
+    /// Metadata REQs sent to each relay per minute, the rest wait their turn
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+}
+impl Default for RelayTuningConfig {
+    fn default() -> Self {
+        Self {
+            auto_apply: false,
+            idle_days: DEFAULT_IDLE_DAYS,
+            requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+        }
+    }
+}
+
+fn default_requests_per_minute() -> u32 {
+    DEFAULT_REQUESTS_PER_MINUTE
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaProxyConfig {
+    pub enabled: bool,
+    /// With `{url}` the media address is put there, like `https://images.example.com/?url={url}`,
+    /// without it every request goes through this HTTP proxy
+    pub address: String,
+}
+impl MediaProxyConfig {
+    pub fn is_rewrite(&self) -> bool {
+        self.address.contains(MEDIA_URL_PLACEHOLDER)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadConfig {
+    /// The system's download directory without it
+    pub directory: Option<PathBuf>,
+}
+impl DownloadConfig {
+    pub fn resolve_directory(&self) -> Option<PathBuf> {
+        self.directory
+            .clone()
+            .or_else(|| UserDirs::new().and_then(|dirs| dirs.download_dir().map(PathBuf::from)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoLockConfig {
+    pub enabled: bool,
+    /// Minutes without keyboard or mouse input before locking
+    pub idle_minutes: u32,
+}
+impl AutoLockConfig {
+    /// Choices shown in the settings
+    pub const MINUTES: [u32; 4] = [5, 15, 30, 60];
+}
+impl Default for AutoLockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: DEFAULT_LOCK_MINUTES,
+        }
+    }
+}
+
+/// NIP-38 status, published only when `publish` is on
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresenceConfig {
+    pub publish: bool,
+    /// Away after `away_minutes` without input and back on input
+    pub auto_away: bool,
+    pub away_minutes: u32,
+    /// Overrides the automatic status
+    pub status: StatusChoice,
+}
+impl PresenceConfig {
+    /// Choices shown in the settings
+    pub const MINUTES: [u32; 4] = [5, 10, 30, 60];
+
+    /// Status to publish after `idle_for` without input, `None` when nothing is published
+    pub fn resolve(&self, idle_for: Duration) -> Option<Presence> {
+        if !self.publish {
+            return None;
+        }
+        let presence = match self.status {
+            StatusChoice::Available => Presence::Available,
+            StatusChoice::Away => Presence::Away,
+            StatusChoice::Automatic
+                if self.auto_away
+                    && idle_for >= Duration::from_secs(u64::from(self.away_minutes) * 60) =>
+            {
+                Presence::Away
+            }
+            StatusChoice::Automatic => Presence::Available,
+        };
+        Some(presence)
+    }
+}
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            publish: false,
+            auto_away: true,
+            away_minutes: DEFAULT_AWAY_MINUTES,
+            status: StatusChoice::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatusChoice {
+    /// Follows the idle time when auto-away is on
+    #[default]
+    Automatic,
+    Available,
+    Away,
+}
+impl StatusChoice {
+    pub const ALL: [StatusChoice; 3] = [
+        StatusChoice::Automatic,
+        StatusChoice::Available,
+        StatusChoice::Away,
+    ];
+}
+impl std::fmt::Display for StatusChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusChoice::Automatic => write!(f, "Automatic"),
+            StatusChoice::Available => write!(f, "Available"),
+            StatusChoice::Away => write!(f, "Away"),
+        }
+    }
+}
+
+/// Published status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Available,
+    Away,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SendKey {
+    /// Shift+Enter adds a new line
+    #[default]
+    Enter,
+    /// Enter adds a new line
+    CtrlEnter,
+}
+impl SendKey {
+    pub const ALL: [SendKey; 2] = [SendKey::Enter, SendKey::CtrlEnter];
+
+    /// Shown in the composer placeholder
+    pub fn hint(&self) -> &'static str {
+        match self {
+            SendKey::Enter => "Enter to send, Shift+Enter for a new line",
+            SendKey::CtrlEnter => "Ctrl+Enter to send, Enter for a new line",
+        }
+    }
+}
+impl std::fmt::Display for SendKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendKey::Enter => write!(f, "Enter sends"),
+            SendKey::CtrlEnter => write!(f, "Ctrl+Enter sends"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeFormatConfig {
+    pub clock: ClockFormat,
+    /// "5 min ago" instead of the time for recent messages
+    pub relative: bool,
+    pub date_order: DateOrder,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClockFormat {
+    /// 12 or 24 hours following the system locale
+    #[default]
+    Locale,
+    H24,
+    H12,
+}
+impl ClockFormat {
+    pub const ALL: [ClockFormat; 3] = [ClockFormat::Locale, ClockFormat::H24, ClockFormat::H12];
+}
+impl std::fmt::Display for ClockFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClockFormat::Locale => write!(f, "System"),
+            ClockFormat::H24 => write!(f, "24 hours"),
+            ClockFormat::H12 => write!(f, "12 hours"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateOrder {
+    /// Order and separator of the system locale
+    #[default]
+    Locale,
+    /// 2023-12-31
+    YearMonthDay,
+    /// 31/12/2023
+    DayMonthYear,
+    /// 12/31/2023
+    MonthDayYear,
+}
+impl DateOrder {
+    pub const ALL: [DateOrder; 4] = [
+        DateOrder::Locale,
+        DateOrder::YearMonthDay,
+        DateOrder::DayMonthYear,
+        DateOrder::MonthDayYear,
+    ];
+}
+impl std::fmt::Display for DateOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateOrder::Locale => write!(f, "System"),
+            DateOrder::YearMonthDay => write!(f, "Year-month-day"),
+            DateOrder::DayMonthYear => write!(f, "Day/month/year"),
+            DateOrder::MonthDayYear => write!(f, "Month/day/year"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextDirection {
+    /// From the first letter, right to left for Arabic or Hebrew
+    #[default]
+    Auto,
+    /// Forced, for testing layouts
+    LeftToRight,
+    RightToLeft,
+}
+impl TextDirection {
+    pub const ALL: [TextDirection; 3] = [
+        TextDirection::Auto,
+        TextDirection::LeftToRight,
+        TextDirection::RightToLeft,
+    ];
+}
+impl std::fmt::Display for TextDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextDirection::Auto => write!(f, "Automatic"),
+            TextDirection::LeftToRight => write!(f, "Left to right"),
+            TextDirection::RightToLeft => write!(f, "Right to left"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StartupView {
+    /// Conversation visited last, a contact's chat or a channel
+    LastChat,
+    #[default]
+    ChatList,
+    /// Channel search
+    Channels,
+    Inbox,
+}
+impl StartupView {
+    pub const ALL: [StartupView; 4] = [
+        StartupView::LastChat,
+        StartupView::ChatList,
+        StartupView::Channels,
+        StartupView::Inbox,
+    ];
+}
+impl std::fmt::Display for StartupView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StartupView::LastChat => write!(f, "Last open chat"),
+            StartupView::ChatList => write!(f, "Chat list"),
+            StartupView::Channels => write!(f, "Channels"),
+            StartupView::Inbox => write!(f, "Inbox"),
+        }
+    }
+}
+
+/// Appearance settings the views are drawn with, kept by the app
+/// and passed down to the views with the theme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Appearance {
+    /// Images are dimmed in dark themes
+    pub dim_images: bool,
+    pub time_format: TimeFormatConfig,
+    pub text_direction: TextDirection,
+}
+impl From<&Config> for Appearance {
+    fn from(config: &Config) -> Self {
+        Self {
+            dim_images: config.dim_images,
+            time_format: config.time_format,
+            text_direction: config.text_direction,
+        }
+    }
 }
 
 impl Config {
@@ -65,6 +502,111 @@ impl Config {
         Ok(())
     }
 
+    pub async fn set_stage_contact_changes(stage: bool) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.stage_contact_changes = stage;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_do_not_track(do_not_track: bool) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.do_not_track = do_not_track;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_developer_console(enabled: bool) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.developer_console = enabled;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_media_proxy(media_proxy: MediaProxyConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.media_proxy = media_proxy;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_send_key(send_key: SendKey) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.send_key = send_key;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_dim_images(dim_images: bool) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.dim_images = dim_images;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_auto_lock(auto_lock: AutoLockConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.auto_lock = auto_lock;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_presence(presence: PresenceConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.presence = presence;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_downloads(downloads: DownloadConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.downloads = downloads;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_time_format(time_format: TimeFormatConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.time_format = time_format;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_text_direction(text_direction: TextDirection) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.text_direction = text_direction;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_startup_view(startup_view: StartupView) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.startup_view = startup_view;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_translation(translation: TranslationConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.translation = translation;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_storage(storage: StorageConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.storage = storage;
+        config.save().await?;
+        Ok(())
+    }
+
+    pub async fn set_relay_tuning(relay_tuning: RelayTuningConfig) -> Result<(), Error> {
+        let mut config = Self::load_file_async().await?;
+        config.relay_tuning = relay_tuning;
+        config.save().await?;
+        Ok(())
+    }
+
     pub async fn save(&self) -> Result<(), Error> {
         let config_dir = config_dir()?;
 
@@ -106,3 +648,14 @@ fn config_dir() -> Result<PathBuf, Error> {
 }
 
 const CONFIG_FILENAME: &str = "config.toml";
+const DEFAULT_TARGET_LANG: &str = "en";
+const DEFAULT_MAX_DIMENSION: u32 = 2048;
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+const DEFAULT_UPLOAD_SERVER: &str = "https://nostr.build";
+const DEFAULT_MAX_UPLOAD_MB: u64 = 20;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+const DEFAULT_IDLE_DAYS: u32 = 14;
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 20;
+const DEFAULT_LOCK_MINUTES: u32 = 15;
+const DEFAULT_AWAY_MINUTES: u32 = 10;
+pub const MEDIA_URL_PLACEHOLDER: &str = "{url}";
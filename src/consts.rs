@@ -1,6 +1,7 @@
 use crate::net::ImageSize;
 
 pub(crate) const YMD_FORMAT: &str = "%Y-%m-%d";
+pub(crate) const NOSTR_URI_PREFIX: &str = "nostr:";
 pub(crate) const APP_PROJECT_DIRS: (&str, &str, &str) = ("com.nostrtalk", "", "NostrTalk");
 pub(crate) const WELCOME_IMAGE: &[u8] = include_bytes!("../assets/welcome_img.jpg");
 pub(crate) const RELAYS_IMAGE: &[u8] = include_bytes!("../assets/relays_img_2.jpg");
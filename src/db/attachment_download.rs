@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use chrono::Utc;
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+}
+
+/// Attachment of a message saved to the download directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentDownload {
+    pub event_id: i64,
+    pub url: String,
+    pub path: PathBuf,
+}
+
+impl AttachmentDownload {
+    pub async fn fetch(pool: &SqlitePool, event_id: i64) -> Result<Vec<Self>, Error> {
+        let sql = "SELECT * FROM attachment_download WHERE event_id = ? ORDER BY saved_at;";
+        let downloads = sqlx::query_as::<_, Self>(sql)
+            .bind(event_id)
+            .fetch_all(pool)
+            .await?;
+        Ok(downloads)
+    }
+
+    pub async fn insert(pool: &SqlitePool, download: &AttachmentDownload) -> Result<(), Error> {
+        let sql = r#"
+            INSERT OR REPLACE INTO attachment_download (event_id, url, path, saved_at)
+            VALUES (?, ?, ?, ?)
+        "#;
+        sqlx::query(sql)
+            .bind(download.event_id)
+            .bind(&download.url)
+            .bind(download.path.to_string_lossy().to_string())
+            .bind(Utc::now().timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Conversations whose received attachments are saved as they arrive
+pub struct AutoSaveAttachments;
+
+impl AutoSaveAttachments {
+    pub async fn is_enabled(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+    ) -> Result<bool, Error> {
+        let sql = "SELECT chat_pubkey FROM auto_save_attachments WHERE chat_pubkey = ?;";
+        let found = sqlx::query_scalar::<_, String>(sql)
+            .bind(chat_pubkey.to_string())
+            .fetch_optional(pool)
+            .await?;
+        Ok(found.is_some())
+    }
+
+    pub async fn set(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        let sql = if enabled {
+            "INSERT OR IGNORE INTO auto_save_attachments (chat_pubkey) VALUES (?);"
+        } else {
+            "DELETE FROM auto_save_attachments WHERE chat_pubkey = ?;"
+        };
+        sqlx::query(sql)
+            .bind(chat_pubkey.to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for AttachmentDownload {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            event_id: row.try_get::<i64, &str>("event_id")?,
+            url: row.try_get::<String, &str>("url")?,
+            path: PathBuf::from(row.try_get::<String, &str>("path")?),
+        })
+    }
+}
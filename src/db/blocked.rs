@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, Utc};
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::millis_to_naive_or_err;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Blocked user, unlike muting their events are never stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbBlocked {
+    pub pubkey: XOnlyPublicKey,
+    /// Also listed in the user's NIP-51 mute list
+    pub on_mute_list: bool,
+    pub created_at: NaiveDateTime,
+}
+
+impl DbBlocked {
+    /// Newest first
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbBlocked>, Error> {
+        let sql = "SELECT * FROM blocked ORDER BY created_at DESC";
+        let blocked = sqlx::query_as::<_, DbBlocked>(sql).fetch_all(pool).await?;
+        Ok(blocked)
+    }
+
+    pub async fn fetch_pubkeys(pool: &SqlitePool) -> Result<HashSet<XOnlyPublicKey>, Error> {
+        Ok(Self::fetch(pool)
+            .await?
+            .into_iter()
+            .map(|blocked| blocked.pubkey)
+            .collect())
+    }
+
+    pub async fn insert(
+        pool: &SqlitePool,
+        pubkey: &XOnlyPublicKey,
+        on_mute_list: bool,
+    ) -> Result<(), Error> {
+        let sql = r#"
+            INSERT INTO blocked (pubkey, on_mute_list, created_at) VALUES (?, ?, ?)
+            ON CONFLICT(pubkey) DO UPDATE SET on_mute_list = excluded.on_mute_list
+        "#;
+        sqlx::query(sql)
+            .bind(pubkey.to_string())
+            .bind(on_mute_list)
+            .bind(Utc::now().timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the removed block
+    pub async fn delete(
+        pool: &SqlitePool,
+        pubkey: &XOnlyPublicKey,
+    ) -> Result<Option<DbBlocked>, Error> {
+        let removed = sqlx::query_as::<_, DbBlocked>("SELECT * FROM blocked WHERE pubkey = ?")
+            .bind(pubkey.to_string())
+            .fetch_optional(pool)
+            .await?;
+        sqlx::query("DELETE FROM blocked WHERE pubkey = ?")
+            .bind(pubkey.to_string())
+            .execute(pool)
+            .await?;
+        Ok(removed)
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbBlocked {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let pubkey = row.try_get::<String, &str>("pubkey")?;
+        let pubkey = XOnlyPublicKey::from_str(&pubkey).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "pubkey".into(),
+            source: Box::new(e),
+        })?;
+        Ok(Self {
+            pubkey,
+            on_mute_list: row.try_get::<bool, &str>("on_mute_list")?,
+            created_at: millis_to_naive_or_err(
+                row.try_get::<i64, &str>("created_at")?,
+                "created_at",
+            )?,
+        })
+    }
+}
+
+pub(crate) const MUTE_LIST_KIND: u64 = 10000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use nostr::Keys;
+
+    #[tokio::test]
+    async fn unblock_returns_the_block() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let pubkey = Keys::generate().public_key();
+
+        DbBlocked::insert(pool, &pubkey, false).await.unwrap();
+        DbBlocked::insert(pool, &pubkey, true).await.unwrap();
+        assert_eq!(DbBlocked::fetch_pubkeys(pool).await.unwrap().len(), 1);
+
+        let removed = DbBlocked::delete(pool, &pubkey).await.unwrap().unwrap();
+        assert!(removed.on_mute_list);
+        assert!(DbBlocked::delete(pool, &pubkey).await.unwrap().is_none());
+    }
+}
@@ -1,16 +1,19 @@
 use thiserror::Error;
 
 use chrono::NaiveDateTime;
-use nostr::{secp256k1::XOnlyPublicKey, EventId};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use nostr::{secp256k1::XOnlyPublicKey, EventId, Tag, TagKind};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use sqlx::{sqlite::SqliteRow, FromRow, Row, SqlitePool};
 
 use crate::{
     net::ImageKind,
     types::ChannelMetadata,
     utils::{
-        channel_id_from_tags, channel_meta_or_err, event_hash_or_err, millis_to_naive_or_err,
-        ns_event_to_millis, public_key_or_err,
+        channel_id_from_tags, channel_meta_or_err, event_hash_or_err, handle_decode_error,
+        millis_to_naive_or_err, ns_event_to_millis, public_key_or_err,
     },
 };
 
@@ -19,7 +22,7 @@ pub enum Error {
     #[error("Sqlx error: {0}")]
     Sqlx(#[from] sqlx::Error),
 
-    #[error("Error parsing JSON content into nostr::Metadata: {0}")]
+    #[error("Error parsing JSON content into ChannelMetadata: {0}")]
     JsonToMetadata(String),
 
     #[error("Can't update channel without id")]
@@ -50,6 +53,8 @@ pub struct ChannelCache {
     pub metadata: ChannelMetadata,
     pub image_cache: Option<ImageDownloaded>,
     pub members: Vec<XOnlyPublicKey>,
+    /// Named by the creator, their hide and mute events are honored like the creator's
+    pub moderators: Vec<XOnlyPublicKey>,
 }
 impl ChannelCache {
     pub async fn insert_member_from_event(
@@ -91,11 +96,39 @@ impl ChannelCache {
         for channel_cache in &mut results {
             channel_cache.fetch_img_cache(cache_pool).await?;
             channel_cache.fetch_members(cache_pool).await?;
+            channel_cache.fetch_moderators(cache_pool).await?;
         }
 
         Ok(results)
     }
 
+    /// Channels where both public keys are members, by name
+    pub async fn fetch_shared(
+        cache_pool: &SqlitePool,
+        user_pubkey: &XOnlyPublicKey,
+        other_pubkey: &XOnlyPublicKey,
+    ) -> Result<Vec<ChannelCache>, Error> {
+        let query = r#"
+            SELECT c.*
+            FROM channel_cache c
+            INNER JOIN channel_member_map a ON a.channel_id = c.creation_event_hash
+            INNER JOIN channel_member_map b ON b.channel_id = c.creation_event_hash
+            WHERE a.public_key = ? AND b.public_key = ?
+        "#;
+        let mut results = sqlx::query_as::<_, ChannelCache>(query)
+            .bind(user_pubkey.to_string())
+            .bind(other_pubkey.to_string())
+            .fetch_all(cache_pool)
+            .await?;
+
+        for channel_cache in &mut results {
+            channel_cache.fetch_img_cache(cache_pool).await?;
+        }
+        results.sort_by_key(|c| c.metadata.name.clone().unwrap_or_default().to_lowercase());
+
+        Ok(results)
+    }
+
     pub async fn fetch_by_channel_id(
         cache_pool: &SqlitePool,
         channel_id: &nostr::EventId,
@@ -108,16 +141,74 @@ impl ChannelCache {
         if let Some(cache) = &mut result {
             cache.fetch_img_cache(cache_pool).await?;
             cache.fetch_members(cache_pool).await?;
+            cache.fetch_moderators(cache_pool).await?;
         }
         Ok(result)
     }
 
+    /// Caches of the subscribed channels in a single query on the main pool,
+    /// through the attached cache
+    pub async fn fetch_subscribed(pool: &SqlitePool) -> Result<Vec<ChannelCache>, Error> {
+        let query = format!(
+            "{} INNER JOIN channel_subscription s ON s.channel_id = c.creation_event_hash",
+            batch_query()
+        );
+        let results = sqlx::query(&query)
+            .try_map(|row: SqliteRow| from_batch_row(&row))
+            .fetch_all(pool)
+            .await?;
+        Ok(results)
+    }
+
+    /// Caches of the archived channels, latest archived first
+    pub async fn fetch_archived(pool: &SqlitePool) -> Result<Vec<ChannelCache>, Error> {
+        let query = format!(
+            "{} INNER JOIN channel_archive a ON a.channel_id = c.creation_event_hash \
+             ORDER BY a.archived_at DESC",
+            batch_query()
+        );
+        let results = sqlx::query(&query)
+            .try_map(|row: SqliteRow| from_batch_row(&row))
+            .fetch_all(pool)
+            .await?;
+        Ok(results)
+    }
+
+    /// Caches of the channels that have one, by id, in a single query on the
+    /// main pool through the attached cache
+    pub async fn fetch_by_channel_ids(
+        pool: &SqlitePool,
+        channel_ids: &[EventId],
+    ) -> Result<HashMap<EventId, ChannelCache>, Error> {
+        if channel_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; channel_ids.len()].join(", ");
+        let query = format!(
+            "{} WHERE c.creation_event_hash IN ({})",
+            batch_query(),
+            placeholders
+        );
+        let mut query = sqlx::query(&query);
+        for channel_id in channel_ids {
+            query = query.bind(channel_id.to_string());
+        }
+        let results = query
+            .try_map(|row: SqliteRow| from_batch_row(&row))
+            .fetch_all(pool)
+            .await?;
+        Ok(results
+            .into_iter()
+            .map(|cache| (cache.channel_id, cache))
+            .collect())
+    }
+
     // If the channel is not in the database, it will be inserted.
     pub async fn fetch_insert(
         cache_pool: &SqlitePool,
         ns_event: &nostr::Event,
     ) -> Result<ChannelCache, Error> {
-        let metadata = nostr::Metadata::from_json(&ns_event.content)
+        let metadata = ChannelMetadata::from_json(&ns_event.content)
             .map_err(|_| Error::JsonToMetadata(ns_event.content.clone()))?;
         let channel_id = &ns_event.id;
         let creator_pubkey = &ns_event.pubkey;
@@ -158,15 +249,26 @@ impl ChannelCache {
             .ok_or(Error::NotFoundChannelInTags(ns_event.id.to_owned()))?;
 
         // Check if channel already exists in the database or error
-        Self::fetch_by_channel_id(cache_pool, &channel_id)
+        let cache = Self::fetch_by_channel_id(cache_pool, &channel_id)
             .await?
             .ok_or(Error::NotFoundChannelToUpdate(channel_id.to_owned()))?;
 
-        let metadata = nostr::Metadata::from_json(&ns_event.content)
+        let metadata = ChannelMetadata::from_json(&ns_event.content)
             .map_err(|_| Error::JsonToMetadata(ns_event.content.clone()))?;
         let updated_event_hash = ns_event.id;
         let updated_at_millis = ns_event_to_millis(ns_event.created_at);
 
+        // only the creator's latest metadata counts, it also names the moderators
+        let is_outdated = cache.updated_at.map_or(false, |updated_at| {
+            updated_at_millis < updated_at.timestamp_millis()
+        });
+        if ns_event.pubkey != cache.creator_pubkey || is_outdated {
+            tracing::debug!("Skipping channel update: {}", &ns_event.id);
+            return Ok(cache);
+        }
+
+        let mut tx = cache_pool.begin().await?;
+
         let update_query = r#"
             UPDATE channel_cache
             SET metadata=?, updated_event_hash=?, updated_at=?
@@ -178,9 +280,25 @@ impl ChannelCache {
             .bind(updated_event_hash.to_string())
             .bind(updated_at_millis)
             .bind(channel_id.to_string())
-            .execute(cache_pool)
+            .execute(&mut tx)
             .await?;
 
+        sqlx::query("DELETE FROM channel_moderator_map WHERE channel_id = ?;")
+            .bind(channel_id.to_string())
+            .execute(&mut tx)
+            .await?;
+        for moderator in moderators_from_tags(&ns_event.tags, &cache.creator_pubkey) {
+            sqlx::query(
+                "INSERT OR IGNORE INTO channel_moderator_map (channel_id, public_key) VALUES (?, ?)",
+            )
+            .bind(channel_id.to_string())
+            .bind(moderator.to_string())
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
         let channel_cache = Self::fetch_by_channel_id(cache_pool, &channel_id)
             .await?
             .ok_or(Error::ChannelNotInDatabase)?;
@@ -205,11 +323,61 @@ impl ChannelCache {
         Ok(())
     }
 
+    async fn fetch_moderators(&mut self, cache_pool: &SqlitePool) -> Result<(), Error> {
+        let query = "SELECT public_key FROM channel_moderator_map WHERE channel_id = ?;";
+        let rows = sqlx::query(query)
+            .bind(self.channel_id.to_string())
+            .fetch_all(cache_pool)
+            .await?;
+
+        let mut moderators = Vec::new();
+        for row in rows {
+            let moderator = row.try_get::<String, &str>("public_key")?;
+            moderators.push(public_key_or_err(&moderator, "public_key")?);
+        }
+        self.moderators = moderators;
+        Ok(())
+    }
+
     pub fn last_event_hash(&self) -> &EventId {
         self.updated_event_hash.as_ref().unwrap_or(&self.channel_id)
     }
+
+    /// The creator or one of the moderators
+    pub fn can_moderate(&self, pubkey: &XOnlyPublicKey) -> bool {
+        &self.creator_pubkey == pubkey || self.moderators.contains(pubkey)
+    }
 }
 
+/// `["p", <pubkey>, <relay>, "moderator"]` tags of a kind 41
+pub fn moderators_from_tags(tags: &[Tag], creator: &XOnlyPublicKey) -> Vec<XOnlyPublicKey> {
+    let mut moderators = vec![];
+    for tag in tags {
+        if let [kind, pubkey, _, role] = tag.as_vec().as_slice() {
+            if kind != "p" || role != MODERATOR_ROLE {
+                continue;
+            }
+            match XOnlyPublicKey::from_str(pubkey) {
+                Ok(pubkey) if &pubkey != creator && !moderators.contains(&pubkey) => {
+                    moderators.push(pubkey)
+                }
+                Ok(_) => (),
+                Err(e) => tracing::debug!("Invalid moderator public key: {}", e),
+            }
+        }
+    }
+    moderators
+}
+
+pub fn moderator_tag(pubkey: &XOnlyPublicKey) -> Tag {
+    Tag::Generic(
+        TagKind::P,
+        vec![pubkey.to_string(), "".into(), MODERATOR_ROLE.into()],
+    )
+}
+
+pub(crate) const MODERATOR_ROLE: &str = "moderator";
+
 async fn fetch_channel_members(
     cache_pool: &SqlitePool,
     channel_id: &EventId,
@@ -230,6 +398,48 @@ async fn fetch_channel_members(
     Ok(members)
 }
 
+/// Channels of the attached cache with their image, and their members and
+/// moderators as JSON arrays, read with `from_batch_row`
+fn batch_query() -> String {
+    let (image_columns, image_joins) = ImageDownloaded::joined_sql(
+        "image",
+        "COALESCE(c.updated_event_hash, c.creation_event_hash)",
+        ImageKind::Channel,
+    );
+    format!(
+        r#"
+        SELECT c.*, {},
+            (SELECT json_group_array(public_key) FROM cache.channel_member_map
+                WHERE channel_id = c.creation_event_hash) AS members,
+            (SELECT json_group_array(public_key) FROM cache.channel_moderator_map
+                WHERE channel_id = c.creation_event_hash) AS moderators
+        FROM cache.channel_cache c
+        {}
+        "#,
+        image_columns, image_joins
+    )
+}
+
+fn from_batch_row(row: &SqliteRow) -> Result<ChannelCache, sqlx::Error> {
+    let mut cache = ChannelCache::from_row(row)?;
+    if cache.metadata.picture.is_some() {
+        cache.image_cache = ImageDownloaded::from_joined_row(row, "image", ImageKind::Channel)?;
+    }
+    cache.members = public_keys_or_err(row, "members")?;
+    cache.moderators = public_keys_or_err(row, "moderators")?;
+    Ok(cache)
+}
+
+fn public_keys_or_err(row: &SqliteRow, index: &str) -> Result<Vec<XOnlyPublicKey>, sqlx::Error> {
+    let json: String = row.try_get(index)?;
+    let public_keys: Vec<String> =
+        serde_json::from_str(&json).map_err(|e| handle_decode_error(e, index))?;
+    public_keys
+        .iter()
+        .map(|public_key| public_key_or_err(public_key, index))
+        .collect()
+}
+
 impl sqlx::FromRow<'_, SqliteRow> for ChannelCache {
     fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
         let metadata: String = row.try_get("metadata")?;
@@ -263,6 +473,117 @@ impl sqlx::FromRow<'_, SqliteRow> for ChannelCache {
             updated_event_hash,
             image_cache: None,
             members: vec![],
+            moderators: vec![],
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use nostr::Keys;
+
+    #[tokio::test]
+    async fn shared_channels_need_both_members() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.cache_pool();
+        let user = Keys::generate().public_key();
+        let other = Keys::generate().public_key();
+        let only_user = EventId::from_slice(&[1; 32]).unwrap();
+        let both = EventId::from_slice(&[2; 32]).unwrap();
+        for channel_id in [&only_user, &both] {
+            sqlx::query(
+                "INSERT INTO channel_cache (creation_event_hash, creator_pubkey, created_at, metadata) VALUES (?, ?, 0, '{}')",
+            )
+            .bind(channel_id.to_string())
+            .bind(user.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+            ChannelCache::insert_member(pool, channel_id, &user)
+                .await
+                .unwrap();
+        }
+        ChannelCache::insert_member(pool, &both, &other)
+            .await
+            .unwrap();
+
+        let shared = ChannelCache::fetch_shared(pool, &user, &other)
+            .await
+            .unwrap();
+        let ids: Vec<_> = shared.iter().map(|c| c.channel_id).collect();
+        assert_eq!(ids, vec![both]);
+    }
+
+    #[tokio::test]
+    async fn batches_join_the_attached_cache() {
+        let db = TestDatabase::new().await.unwrap();
+        let (pool, cache_pool) = (db.pool(), db.cache_pool());
+
+        let creator = Keys::generate().public_key();
+        let member = Keys::generate().public_key();
+        let subscribed = EventId::from_slice(&[1; 32]).unwrap();
+        let other = EventId::from_slice(&[2; 32]).unwrap();
+        for channel_id in [&subscribed, &other] {
+            sqlx::query(
+                "INSERT INTO channel_cache (creation_event_hash, creator_pubkey, created_at, metadata) VALUES (?, ?, 0, '{}')",
+            )
+            .bind(channel_id.to_string())
+            .bind(creator.to_string())
+            .execute(cache_pool)
+            .await
+            .unwrap();
+            ChannelCache::insert_member(cache_pool, channel_id, &member)
+                .await
+                .unwrap();
+        }
+        sqlx::query("INSERT INTO channel_subscription (channel_id, subscribed_at) VALUES (?, 0)")
+            .bind(subscribed.to_string())
+            .execute(pool)
+            .await
+            .unwrap();
+
+        sqlx::query("INSERT INTO channel_moderator_map (channel_id, public_key) VALUES (?, ?)")
+            .bind(other.to_string())
+            .bind(member.to_string())
+            .execute(cache_pool)
+            .await
+            .unwrap();
+
+        let caches = ChannelCache::fetch_subscribed(pool).await.unwrap();
+        assert_eq!(caches.len(), 1);
+        assert_eq!(caches[0].channel_id, subscribed);
+        assert_eq!(caches[0].members, vec![member]);
+        assert!(caches[0].moderators.is_empty());
+        assert!(caches[0].image_cache.is_none());
+
+        let unknown = EventId::from_slice(&[3; 32]).unwrap();
+        let caches = ChannelCache::fetch_by_channel_ids(pool, &[subscribed, other, unknown])
+            .await
+            .unwrap();
+        assert_eq!(caches.len(), 2);
+        assert!(!caches.contains_key(&unknown));
+        let single = ChannelCache::fetch_by_channel_id(cache_pool, &other)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(caches[&other].members, single.members);
+        assert_eq!(caches[&other].moderators, vec![member]);
+        assert_eq!(caches[&other].moderators, single.moderators);
+    }
+
+    #[test]
+    fn reads_moderator_tags() {
+        let creator = Keys::generate().public_key();
+        let moderator = Keys::generate().public_key();
+        let member = Keys::generate().public_key();
+        let tags = vec![
+            moderator_tag(&moderator),
+            moderator_tag(&moderator),
+            moderator_tag(&creator),
+            Tag::PubKey(member, None),
+        ];
+        assert_eq!(moderators_from_tags(&tags, &creator), vec![moderator]);
+    }
+}
@@ -0,0 +1,106 @@
+use std::ops::Range;
+
+use nostr::EventId;
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Words watched in a channel, messages containing them are treated as mentions
+pub struct ChannelKeywords;
+
+impl ChannelKeywords {
+    pub async fn fetch(pool: &SqlitePool, channel_id: &EventId) -> Result<Vec<String>, Error> {
+        let sql = "SELECT keyword FROM channel_keyword WHERE channel_id = ? ORDER BY keyword;";
+        let keywords = sqlx::query_scalar(sql)
+            .bind(channel_id.to_string())
+            .fetch_all(pool)
+            .await?;
+        Ok(keywords)
+    }
+
+    /// Replaces the channel's keywords, blank ones are skipped
+    pub async fn set(
+        pool: &SqlitePool,
+        channel_id: &EventId,
+        keywords: &[String],
+    ) -> Result<(), Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM channel_keyword WHERE channel_id = ?;")
+            .bind(channel_id.to_string())
+            .execute(&mut tx)
+            .await?;
+        let sql = "INSERT OR IGNORE INTO channel_keyword (channel_id, keyword) VALUES (?, ?);";
+        for keyword in keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()) {
+            sqlx::query(sql)
+                .bind(channel_id.to_string())
+                .bind(keyword)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// First keyword found in the content
+    pub fn matching<'a>(content: &str, keywords: &'a [String]) -> Option<&'a str> {
+        keywords
+            .iter()
+            .map(String::as_str)
+            .find(|keyword| find_keyword(content, keyword).is_some())
+    }
+}
+
+/// Byte range of the first occurrence of `keyword` as whole words, ignoring case
+pub fn find_keyword(content: &str, keyword: &str) -> Option<Range<usize>> {
+    let keyword: Vec<char> = keyword
+        .trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect();
+    if keyword.is_empty() {
+        return None;
+    }
+    let is_boundary = |c: Option<char>| c.map_or(true, |c| !c.is_alphanumeric());
+    for (start, _) in content.char_indices() {
+        if !is_boundary(content[..start].chars().next_back()) {
+            continue;
+        }
+        let mut lowered = Vec::with_capacity(keyword.len());
+        let mut end = start;
+        for c in content[start..].chars() {
+            if lowered.len() >= keyword.len() {
+                break;
+            }
+            lowered.extend(c.to_lowercase());
+            end += c.len_utf8();
+        }
+        if lowered == keyword && is_boundary(content[end..].chars().next()) {
+            return Some(start..end);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_whole_words_ignoring_case() {
+        let content = "Trusting Rust, says Zoë";
+        assert_eq!(find_keyword(content, "rust"), Some(9..13));
+        assert_eq!(find_keyword(content, "says zoë"), Some(15..content.len()));
+        assert_eq!(find_keyword(content, "ZOË"), Some(20..content.len()));
+        assert_eq!(find_keyword(content, "trust"), None);
+        assert_eq!(find_keyword(content, "  "), None);
+
+        let keywords = vec!["go".to_owned(), "rust".to_owned()];
+        assert_eq!(ChannelKeywords::matching(content, &keywords), Some("rust"));
+        assert_eq!(ChannelKeywords::matching("going", &keywords), None);
+    }
+}
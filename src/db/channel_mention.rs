@@ -0,0 +1,169 @@
+use nostr::prelude::ToBech32;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Tag, TagKind};
+use sqlx::{FromRow, Row, SqlitePool};
+use thiserror::Error;
+
+use super::DbChannelMessage;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Channel messages directed at the user, shown in the inbox until seen
+pub struct ChannelMention;
+
+impl ChannelMention {
+    /// A `p` tag with the user's key or their npub in the content
+    pub fn mentions_user(ns_event: &nostr::Event, pubkey: &XOnlyPublicKey) -> bool {
+        let tagged = ns_event
+            .tags
+            .iter()
+            .any(|tag| matches!(tag, Tag::PubKey(tag_pubkey, _) if tag_pubkey == pubkey));
+        if tagged {
+            return true;
+        }
+        match pubkey.to_bech32() {
+            Ok(npub) => ns_event.content.contains(&npub),
+            Err(_) => false,
+        }
+    }
+
+    /// Carries the `["mention", "here"]` tag, only honored when the author owns the channel
+    pub fn is_broadcast(ns_event: &nostr::Event) -> bool {
+        ns_event.tags.iter().any(|tag| {
+            matches!(tag.as_vec().as_slice(), [kind, value] if kind == MENTION_TAG && value == BROADCAST_MENTION)
+        })
+    }
+
+    /// `@here` typed as a word
+    pub fn has_broadcast_keyword(content: &str) -> bool {
+        content
+            .split(|c: char| c.is_whitespace() || ",.!?:;".contains(c))
+            .any(|word| word == BROADCAST_KEYWORD)
+    }
+
+    pub fn broadcast_tag() -> Tag {
+        Tag::Generic(
+            TagKind::Custom(MENTION_TAG.into()),
+            vec![BROADCAST_MENTION.into()],
+        )
+    }
+
+    /// `keyword` is the watched word found, `None` when the user was mentioned
+    pub async fn insert(
+        pool: &SqlitePool,
+        event_id: i64,
+        channel_id: &EventId,
+        keyword: Option<&str>,
+    ) -> Result<(), Error> {
+        let sql = "INSERT OR IGNORE INTO channel_mention (event_id, channel_id, keyword) VALUES (?, ?, ?);";
+        sqlx::query(sql)
+            .bind(event_id)
+            .bind(channel_id.to_string())
+            .bind(keyword)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Unseen mentions with the watched word they matched, newest first
+    pub async fn fetch_unseen(
+        pool: &SqlitePool,
+    ) -> Result<Vec<(DbChannelMessage, Option<String>)>, Error> {
+        let sql = r#"
+            SELECT channel_message.*, channel_mention.keyword FROM channel_message
+            INNER JOIN channel_mention ON channel_mention.event_id = channel_message.event_id
+            WHERE channel_mention.seen = 0 AND channel_message.retracted_at IS NULL
+            ORDER BY channel_message.created_at DESC
+            LIMIT ?;
+        "#;
+        let rows = sqlx::query(sql).bind(INBOX_LIMIT).fetch_all(pool).await?;
+        let mut mentions = vec![];
+        for row in rows {
+            let keyword = row.try_get::<Option<String>, &str>("keyword")?;
+            mentions.push((DbChannelMessage::from_row(&row)?, keyword));
+        }
+        Ok(mentions)
+    }
+
+    pub async fn mark_seen(pool: &SqlitePool, event_id: i64) -> Result<(), Error> {
+        let sql = "UPDATE channel_mention SET seen = 1 WHERE event_id = ?;";
+        sqlx::query(sql).bind(event_id).execute(pool).await?;
+        Ok(())
+    }
+
+    /// Opening a channel clears its mentions
+    pub async fn mark_channel_seen(pool: &SqlitePool, channel_id: &EventId) -> Result<(), Error> {
+        let sql = "UPDATE channel_mention SET seen = 1 WHERE channel_id = ?;";
+        sqlx::query(sql)
+            .bind(channel_id.to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+pub const INBOX_LIMIT: i64 = 100;
+pub(crate) const MENTION_TAG: &str = "mention";
+pub(crate) const BROADCAST_MENTION: &str = "here";
+pub(crate) const BROADCAST_KEYWORD: &str = "@here";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys, Kind};
+
+    #[test]
+    fn detects_tagged_user() {
+        let user_keys = Keys::generate();
+        let author_keys = Keys::generate();
+        let event = EventBuilder::new(
+            Kind::ChannelMessage,
+            "hello",
+            &[Tag::PubKey(user_keys.public_key(), None)],
+        )
+        .to_event(&author_keys)
+        .unwrap();
+        assert!(ChannelMention::mentions_user(
+            &event,
+            &user_keys.public_key()
+        ));
+        assert!(!ChannelMention::mentions_user(
+            &event,
+            &author_keys.public_key()
+        ));
+    }
+
+    #[test]
+    fn detects_npub_in_content() {
+        let user_keys = Keys::generate();
+        let author_keys = Keys::generate();
+        let npub = user_keys.public_key().to_bech32().unwrap();
+        let event = EventBuilder::new(Kind::ChannelMessage, format!("hey {}", npub), &[])
+            .to_event(&author_keys)
+            .unwrap();
+        assert!(ChannelMention::mentions_user(
+            &event,
+            &user_keys.public_key()
+        ));
+    }
+
+    #[test]
+    fn detects_broadcast() {
+        let keys = Keys::generate();
+        let event = EventBuilder::new(
+            Kind::ChannelMessage,
+            "@here meeting",
+            &[ChannelMention::broadcast_tag()],
+        )
+        .to_event(&keys)
+        .unwrap();
+        assert!(ChannelMention::is_broadcast(&event));
+
+        assert!(ChannelMention::has_broadcast_keyword("hey @here, look"));
+        assert!(!ChannelMention::has_broadcast_keyword("mail@here.com"));
+    }
+}
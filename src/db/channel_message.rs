@@ -31,6 +31,8 @@ pub struct DbChannelMessage {
     pub created_at: NaiveDateTime,
     pub relay_url: Url,
     pub content: String,
+    /// Deleted by its author with a NIP-09 request
+    pub retracted_at: Option<NaiveDateTime>,
 }
 impl DbChannelMessage {
     pub fn display_name(&self) -> String {
@@ -82,9 +84,14 @@ impl DbChannelMessage {
 
                 let sql = r#"
                     INSERT INTO channel_message (
-                        event_id, channel_id, author, is_users, created_at, relay_url, content
+                        event_id, channel_id, author, is_users, created_at, relay_url, content,
+                        retracted_at
                     )
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7,
+                        -- its deletion may have come first
+                        (SELECT deleted_at FROM event_deletion
+                        WHERE target_hash = ?8 AND author = ?3)
+                    );
                 "#;
 
                 let output = sqlx::query(sql)
@@ -95,6 +102,7 @@ impl DbChannelMessage {
                     .bind(db_event.created_at.timestamp_millis())
                     .bind(db_event.relay_url.as_ref())
                     .bind(&db_event.content)
+                    .bind(db_event.event_hash.to_string())
                     .execute(pool)
                     .await?;
 
@@ -109,6 +117,24 @@ impl DbChannelMessage {
             }
         }
     }
+
+    /// Returns false when the message was already retracted or isn't a channel message
+    pub async fn retract(
+        pool: &SqlitePool,
+        event_id: i64,
+        retracted_at: NaiveDateTime,
+    ) -> Result<bool, Error> {
+        let sql = r#"
+            UPDATE channel_message SET retracted_at = ?
+            WHERE event_id = ? AND retracted_at IS NULL;
+        "#;
+        let output = sqlx::query(sql)
+            .bind(retracted_at.timestamp_millis())
+            .bind(event_id)
+            .execute(pool)
+            .await?;
+        Ok(output.rows_affected() > 0)
+    }
 }
 
 impl sqlx::FromRow<'_, SqliteRow> for DbChannelMessage {
@@ -130,6 +156,10 @@ impl sqlx::FromRow<'_, SqliteRow> for DbChannelMessage {
 
         let content: String = row.try_get("content")?;
         let is_users: bool = row.try_get("is_users")?;
+        let retracted_at = row
+            .try_get::<Option<i64>, &str>("retracted_at")?
+            .map(|millis| millis_to_naive_or_err(millis, "retracted_at"))
+            .transpose()?;
 
         Ok(DbChannelMessage {
             event_id,
@@ -139,6 +169,7 @@ impl sqlx::FromRow<'_, SqliteRow> for DbChannelMessage {
             created_at,
             relay_url,
             content,
+            retracted_at,
         })
     }
 }
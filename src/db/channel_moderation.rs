@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Kind, Marker, Tag};
+use sqlx::{Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::public_key_or_err;
+
+use super::{ChannelCache, DbChannelMessage};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("Moderation event without channel: {0}")]
+    MissingChannelId(EventId),
+
+    #[error("Moderation event without target: {0}")]
+    MissingTarget(EventId),
+
+    #[error("Not a moderation kind: {0}")]
+    UnexpectedKind(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModAction {
+    HideMessage(EventId),
+    MuteUser(XOnlyPublicKey),
+}
+
+/// Hide or mute event tagging a channel, stored whoever wrote it
+/// since the moderators can change later
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbChannelModeration {
+    pub event_hash: EventId,
+    pub channel_id: EventId,
+    pub author: XOnlyPublicKey,
+    pub action: ModAction,
+}
+
+impl DbChannelModeration {
+    pub async fn create_tables(pool: &SqlitePool) -> Result<(), Error> {
+        sqlx::query(include_str!("../../migrations/28_channel_moderation.sql"))
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The channel is the `root` event tag or the first one,
+    /// a hidden message is the other event tag
+    pub fn from_ns_event(ns_event: &nostr::Event) -> Result<Self, Error> {
+        let event_tags: Vec<_> = ns_event
+            .tags
+            .iter()
+            .filter_map(|tag| match tag {
+                Tag::Event(event_id, _, marker) => Some((*event_id, marker.clone())),
+                _ => None,
+            })
+            .collect();
+        let channel_id = event_tags
+            .iter()
+            .find(|(_, marker)| marker == &Some(Marker::Root))
+            .or_else(|| event_tags.first())
+            .map(|(event_id, _)| *event_id)
+            .ok_or(Error::MissingChannelId(ns_event.id))?;
+
+        let action = match ns_event.kind {
+            Kind::ChannelHideMessage => event_tags
+                .iter()
+                .find(|(event_id, _)| event_id != &channel_id)
+                .map(|(event_id, _)| ModAction::HideMessage(*event_id)),
+            Kind::ChannelMuteUser => ns_event.tags.iter().find_map(|tag| match tag {
+                Tag::PubKey(pubkey, _) => Some(ModAction::MuteUser(pubkey.to_owned())),
+                _ => None,
+            }),
+            other => return Err(Error::UnexpectedKind(other.as_u32())),
+        };
+
+        Ok(Self {
+            event_hash: ns_event.id,
+            channel_id,
+            author: ns_event.pubkey,
+            action: action.ok_or(Error::MissingTarget(ns_event.id))?,
+        })
+    }
+
+    /// Returns false when the event was already stored
+    pub async fn insert(&self, pool: &SqlitePool) -> Result<bool, Error> {
+        let (kind, target) = match &self.action {
+            ModAction::HideMessage(event_hash) => {
+                (Kind::ChannelHideMessage, event_hash.to_string())
+            }
+            ModAction::MuteUser(pubkey) => (Kind::ChannelMuteUser, pubkey.to_string()),
+        };
+        let sql = r#"
+            INSERT OR IGNORE INTO channel_moderation (event_hash, channel_id, author, kind, target)
+            VALUES (?, ?, ?, ?, ?);
+        "#;
+        let output = sqlx::query(sql)
+            .bind(self.event_hash.to_string())
+            .bind(self.channel_id.to_string())
+            .bind(self.author.to_string())
+            .bind(kind.as_u32())
+            .bind(target)
+            .execute(pool)
+            .await?;
+        Ok(output.rows_affected() > 0)
+    }
+}
+
+/// Messages and users removed from a channel by its owner or moderators
+#[derive(Debug)]
+pub struct Moderation {
+    owner: XOnlyPublicKey,
+    /// Database ids of the hidden messages
+    hidden: HashSet<i64>,
+    muted: HashSet<XOnlyPublicKey>,
+}
+
+impl Moderation {
+    pub async fn fetch(pool: &SqlitePool, cache: &ChannelCache) -> Result<Self, Error> {
+        let sql = r#"
+            SELECT m.author, e.event_id
+            FROM channel_moderation m
+            INNER JOIN event e ON e.event_hash = m.target
+            WHERE m.channel_id = ? AND m.kind = ?;
+        "#;
+        let rows = sqlx::query(sql)
+            .bind(cache.channel_id.to_string())
+            .bind(Kind::ChannelHideMessage.as_u32())
+            .fetch_all(pool)
+            .await?;
+        let mut hidden = HashSet::new();
+        for row in rows {
+            let author = public_key_or_err(&row.try_get::<String, &str>("author")?, "author")?;
+            if cache.can_moderate(&author) {
+                hidden.insert(row.try_get::<i64, &str>("event_id")?);
+            }
+        }
+
+        let sql =
+            "SELECT author, target FROM channel_moderation WHERE channel_id = ? AND kind = ?;";
+        let rows = sqlx::query(sql)
+            .bind(cache.channel_id.to_string())
+            .bind(Kind::ChannelMuteUser.as_u32())
+            .fetch_all(pool)
+            .await?;
+        let mut muted = HashSet::new();
+        for row in rows {
+            let author = public_key_or_err(&row.try_get::<String, &str>("author")?, "author")?;
+            if cache.can_moderate(&author) {
+                let target = row.try_get::<String, &str>("target")?;
+                muted.insert(public_key_or_err(&target, "target")?);
+            }
+        }
+
+        Ok(Self {
+            owner: cache.creator_pubkey,
+            hidden,
+            muted,
+        })
+    }
+
+    /// Nobody moderates the owner's messages
+    pub fn hides(&self, ch_msg: &DbChannelMessage) -> bool {
+        if self.owner == ch_msg.author {
+            return false;
+        }
+        self.hidden.contains(&ch_msg.event_id) || self.muted.contains(&ch_msg.author)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys};
+
+    #[test]
+    fn reads_hide_and_mute_events() {
+        let keys = Keys::generate();
+        let channel_id = EventId::from_slice(&[1; 32]).unwrap();
+        let message = EventId::from_slice(&[2; 32]).unwrap();
+        let muted = Keys::generate().public_key();
+
+        let hide = EventBuilder::new(
+            Kind::ChannelHideMessage,
+            "spam",
+            &[
+                Tag::Event(message, None, Some(Marker::Reply)),
+                Tag::Event(channel_id, None, Some(Marker::Root)),
+            ],
+        )
+        .to_event(&keys)
+        .unwrap();
+        let moderation = DbChannelModeration::from_ns_event(&hide).unwrap();
+        assert_eq!(moderation.channel_id, channel_id);
+        assert_eq!(moderation.action, ModAction::HideMessage(message));
+
+        let mute = EventBuilder::new(
+            Kind::ChannelMuteUser,
+            "",
+            &[Tag::Event(channel_id, None, None), Tag::PubKey(muted, None)],
+        )
+        .to_event(&keys)
+        .unwrap();
+        let moderation = DbChannelModeration::from_ns_event(&mute).unwrap();
+        assert_eq!(moderation.action, ModAction::MuteUser(muted));
+
+        // a hide event needs the message besides the channel
+        let only_channel = EventBuilder::new(
+            Kind::ChannelHideMessage,
+            "",
+            &[Tag::Event(channel_id, None, None)],
+        )
+        .to_event(&keys)
+        .unwrap();
+        assert!(matches!(
+            DbChannelModeration::from_ns_event(&only_channel),
+            Err(Error::MissingTarget(_))
+        ));
+    }
+}
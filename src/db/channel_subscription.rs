@@ -1,9 +1,12 @@
+use std::collections::{HashMap, HashSet};
+
 use chrono::{NaiveDateTime, Utc};
 use nostr::EventId;
+use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
 use thiserror::Error;
 
-use crate::utils::{event_hash_or_err, millis_to_naive_or_err};
+use crate::utils::{event_hash_or_err, millis_to_naive, millis_to_naive_or_err};
 
 use super::UserConfig;
 
@@ -21,6 +24,11 @@ pub struct ChannelSubscription {
     pub id: i64,
     pub channel_id: EventId,
     pub subscribed_at: NaiveDateTime,
+    /// Newest message scrolled past, here or on another device
+    pub last_read_event: Option<EventId>,
+    pub last_read_at: Option<NaiveDateTime>,
+    /// Messages arrive but are not counted as unread
+    pub muted: bool,
 }
 
 impl ChannelSubscription {
@@ -31,6 +39,15 @@ impl ChannelSubscription {
         Ok(channels)
     }
 
+    pub async fn is_subscribed(pool: &SqlitePool, channel_id: &EventId) -> Result<bool, Error> {
+        let sql = "SELECT COUNT(*) FROM channel_subscription WHERE channel_id = ?;";
+        let count: i64 = sqlx::query_scalar(sql)
+            .bind(channel_id.to_string())
+            .fetch_one(pool)
+            .await?;
+        Ok(count > 0)
+    }
+
     pub async fn insert(pool: &SqlitePool, channel_id: &EventId) -> Result<Self, Error> {
         let utc_now = UserConfig::get_corrected_time(pool)
             .await
@@ -54,6 +71,97 @@ impl ChannelSubscription {
         Ok(channel)
     }
 
+    /// Moves the read position forward, an older one is ignored.
+    /// Returns true when it moved
+    pub async fn mark_read(
+        pool: &SqlitePool,
+        channel_id: &EventId,
+        event_hash: &EventId,
+        read_at: NaiveDateTime,
+    ) -> Result<bool, Error> {
+        let sql = r#"
+            UPDATE channel_subscription SET last_read_event = ?1, last_read_at = ?2
+            WHERE channel_id = ?3 AND (last_read_at IS NULL OR last_read_at < ?2);
+        "#;
+        let output = sqlx::query(sql)
+            .bind(event_hash.to_string())
+            .bind(read_at.timestamp_millis())
+            .bind(channel_id.to_string())
+            .execute(pool)
+            .await?;
+        Ok(output.rows_affected() > 0)
+    }
+
+    /// Moves the read position to the newest message of the channel.
+    /// Returns false when it was already there
+    pub async fn mark_all_read(pool: &SqlitePool, channel_id: &EventId) -> Result<bool, Error> {
+        let sql = r#"
+            WITH newest AS (
+                SELECT event.event_hash, m.created_at
+                FROM channel_message m
+                JOIN event ON event.event_id = m.event_id
+                WHERE m.channel_id = ?1
+                ORDER BY m.created_at DESC
+                LIMIT 1
+            )
+            UPDATE channel_subscription
+            SET last_read_event = (SELECT event_hash FROM newest),
+                last_read_at = (SELECT created_at FROM newest)
+            WHERE channel_id = ?1
+                AND (SELECT created_at FROM newest) > COALESCE(last_read_at, subscribed_at);
+        "#;
+        let output = sqlx::query(sql)
+            .bind(channel_id.to_string())
+            .execute(pool)
+            .await?;
+        Ok(output.rows_affected() > 0)
+    }
+
+    /// Returns true when the channel is subscribed
+    pub async fn set_muted(
+        pool: &SqlitePool,
+        channel_id: &EventId,
+        muted: bool,
+    ) -> Result<bool, Error> {
+        let sql = "UPDATE channel_subscription SET muted = ? WHERE channel_id = ?;";
+        let output = sqlx::query(sql)
+            .bind(muted)
+            .bind(channel_id.to_string())
+            .execute(pool)
+            .await?;
+        Ok(output.rows_affected() > 0)
+    }
+
+    pub async fn fetch_muted(pool: &SqlitePool) -> Result<HashSet<EventId>, Error> {
+        let sql = "SELECT channel_id FROM channel_subscription WHERE muted = 1;";
+        let rows: Vec<String> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|channel_id| EventId::from_hex(channel_id).ok())
+            .collect())
+    }
+
+    /// Messages of others after the read position, or after subscribing
+    /// when the channel was never read. Muted channels have none
+    pub async fn unread_counts(pool: &SqlitePool) -> Result<HashMap<EventId, i64>, Error> {
+        let sql = r#"
+            SELECT s.channel_id, COUNT(m.event_id) AS unread
+            FROM channel_subscription s
+            LEFT JOIN channel_message m ON m.channel_id = s.channel_id
+                AND m.is_users = 0
+                AND s.muted = 0
+                AND m.created_at > COALESCE(s.last_read_at, s.subscribed_at)
+            GROUP BY s.channel_id;
+        "#;
+        let rows: Vec<(String, i64)> = sqlx::query_as(sql).fetch_all(pool).await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(channel_id, unread)| {
+                EventId::from_hex(channel_id).ok().map(|id| (id, unread))
+            })
+            .collect())
+    }
+
     pub async fn delete(pool: &SqlitePool, channel_id: &EventId) -> Result<(), Error> {
         let sql = "DELETE FROM channel_subscription WHERE channel_id = ?;";
 
@@ -66,6 +174,79 @@ impl ChannelSubscription {
     }
 }
 
+/// Read position of one channel in the synced event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadMarker {
+    pub event_id: EventId,
+    /// UNIX timestamp in milliseconds of the message
+    pub read_at: i64,
+}
+
+/// Content of the read positions event, keyed by channel id
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadMarkers(pub HashMap<EventId, ReadMarker>);
+impl ReadMarkers {
+    /// Channels that were never read are left out
+    pub fn from_subscriptions(subscriptions: &[ChannelSubscription]) -> Self {
+        Self(
+            subscriptions
+                .iter()
+                .filter_map(|sub| {
+                    let marker = ReadMarker {
+                        event_id: sub.last_read_event?,
+                        read_at: sub.last_read_at?.timestamp_millis(),
+                    };
+                    Some((sub.channel_id, marker))
+                })
+                .collect(),
+        )
+    }
+
+    /// Moves every subscribed channel to its marker when it is ahead.
+    /// Returns the channels that moved
+    pub async fn apply(&self, pool: &SqlitePool) -> Result<Vec<EventId>, Error> {
+        let mut moved = vec![];
+        for (channel_id, marker) in &self.0 {
+            let Ok(read_at) = millis_to_naive(marker.read_at) else {
+                continue;
+            };
+            if ChannelSubscription::mark_read(pool, channel_id, &marker.event_id, read_at).await? {
+                moved.push(*channel_id);
+            }
+        }
+        Ok(moved)
+    }
+}
+
+/// NIP-78 application data, replaced by every new position
+pub(crate) const CHANNEL_READ_KIND: u64 = 30078;
+/// `d` tag of the read positions event
+pub(crate) const CHANNEL_READ_ID: &str = "nostrtalk/channel-read";
+
+/// Channels without a live subscription whose history stays browsable
+pub struct ChannelArchive;
+
+impl ChannelArchive {
+    pub async fn insert(pool: &SqlitePool, channel_id: &EventId) -> Result<(), Error> {
+        let sql = "INSERT OR REPLACE INTO channel_archive (channel_id, archived_at) VALUES (?, ?);";
+        sqlx::query(sql)
+            .bind(channel_id.to_string())
+            .bind(Utc::now().timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, channel_id: &EventId) -> Result<(), Error> {
+        let sql = "DELETE FROM channel_archive WHERE channel_id = ?;";
+        sqlx::query(sql)
+            .bind(channel_id.to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
 impl sqlx::FromRow<'_, SqliteRow> for ChannelSubscription {
     fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
         let subscribed_at = row.try_get::<i64, &str>("subscribed_at")?;
@@ -74,10 +255,131 @@ impl sqlx::FromRow<'_, SqliteRow> for ChannelSubscription {
         let channel_id: String = row.try_get("channel_id")?;
         let channel_id = event_hash_or_err(&channel_id, "channel_id")?;
 
+        let last_read_event = row
+            .try_get::<Option<String>, &str>("last_read_event")?
+            .map(|hash| event_hash_or_err(&hash, "last_read_event"))
+            .transpose()?;
+        let last_read_at = row
+            .try_get::<Option<i64>, &str>("last_read_at")?
+            .map(|millis| millis_to_naive_or_err(millis, "last_read_at"))
+            .transpose()?;
+
         Ok(ChannelSubscription {
             id: row.try_get("id")?,
             channel_id,
             subscribed_at,
+            last_read_event,
+            last_read_at,
+            muted: row.try_get("muted")?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DbChannelMessage, DbEvent, TestDatabase};
+    use crate::test_support::{generate, AccountSpec};
+
+    #[tokio::test]
+    async fn read_position_only_moves_forward() {
+        let db = TestDatabase::new().await.unwrap();
+        let (pool, cache_pool) = (db.pool(), db.cache_pool());
+        let spec = AccountSpec {
+            contacts: 0,
+            channels: 1,
+            members_per_channel: 2,
+            messages_per_channel: 6,
+            ..AccountSpec::default()
+        };
+        let account = generate(pool, cache_pool, &spec).await.unwrap();
+        let channel_id = account.channels[0];
+        let messages = DbChannelMessage::fetch(pool, &channel_id).await.unwrap();
+        let hash_of = |idx: usize| {
+            let pool = pool.clone();
+            let event_id = messages[idx].event_id;
+            async move {
+                DbEvent::fetch_id(&pool, event_id)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .event_hash
+            }
+        };
+
+        // the history from before subscribing is not unread
+        let unread = ChannelSubscription::unread_counts(pool).await.unwrap();
+        assert_eq!(unread[&channel_id], 0);
+
+        let second = hash_of(1).await;
+        let read_at = messages[1].created_at;
+        assert!(
+            ChannelSubscription::mark_read(pool, &channel_id, &second, read_at)
+                .await
+                .unwrap()
+        );
+        let first = hash_of(0).await;
+        let earlier = messages[0].created_at;
+        assert!(
+            !ChannelSubscription::mark_read(pool, &channel_id, &first, earlier)
+                .await
+                .unwrap()
+        );
+
+        // the user wrote the third and the last ones
+        let unread = ChannelSubscription::unread_counts(pool).await.unwrap();
+        assert_eq!(unread[&channel_id], 2);
+
+        let subscriptions = ChannelSubscription::fetch(pool).await.unwrap();
+        let markers = ReadMarkers::from_subscriptions(&subscriptions);
+        assert_eq!(markers.0[&channel_id].event_id, second);
+        let json = serde_json::to_string(&markers).unwrap();
+        assert_eq!(serde_json::from_str::<ReadMarkers>(&json).unwrap(), markers);
+        assert!(markers.apply(pool).await.unwrap().is_empty());
+
+        assert!(ChannelSubscription::set_muted(pool, &channel_id, true)
+            .await
+            .unwrap());
+        let unread = ChannelSubscription::unread_counts(pool).await.unwrap();
+        assert_eq!(unread[&channel_id], 0);
+        let muted = ChannelSubscription::fetch_muted(pool).await.unwrap();
+        assert!(muted.contains(&channel_id));
+    }
+
+    #[tokio::test]
+    async fn marks_the_whole_channel_read() {
+        let db = TestDatabase::new().await.unwrap();
+        let (pool, cache_pool) = (db.pool(), db.cache_pool());
+        let spec = AccountSpec {
+            contacts: 0,
+            channels: 1,
+            members_per_channel: 2,
+            messages_per_channel: 6,
+            ..AccountSpec::default()
+        };
+        let account = generate(pool, cache_pool, &spec).await.unwrap();
+        let channel_id = account.channels[0];
+        let messages = DbChannelMessage::fetch(pool, &channel_id).await.unwrap();
+        let first = DbEvent::fetch_id(pool, messages[0].event_id)
+            .await
+            .unwrap()
+            .unwrap();
+        ChannelSubscription::mark_read(pool, &channel_id, &first.event_hash, first.created_at)
+            .await
+            .unwrap();
+        let unread = ChannelSubscription::unread_counts(pool).await.unwrap();
+        assert!(unread[&channel_id] > 0);
+
+        assert!(ChannelSubscription::mark_all_read(pool, &channel_id)
+            .await
+            .unwrap());
+        let unread = ChannelSubscription::unread_counts(pool).await.unwrap();
+        assert_eq!(unread[&channel_id], 0);
+        let newest = messages.iter().max_by_key(|m| m.created_at).unwrap();
+        let subscription = ChannelSubscription::fetch(pool).await.unwrap().remove(0);
+        assert_eq!(subscription.last_read_at, Some(newest.created_at));
+        assert!(!ChannelSubscription::mark_all_read(pool, &channel_id)
+            .await
+            .unwrap());
+    }
+}
@@ -4,7 +4,7 @@ use nostr::prelude::{FromBech32, ToBech32};
 use nostr::EventId;
 use nostr::{secp256k1::XOnlyPublicKey, Tag};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use sqlx::{sqlite::SqliteRow, FromRow, Row, SqlitePool};
 use std::str::FromStr;
 use thiserror::Error;
 use url::Url;
@@ -62,6 +62,8 @@ pub struct DbContact {
     pubkey: XOnlyPublicKey,
     relay_url: Option<Url>,
     petname: Option<String>,
+    /// Private annotations, never published
+    notes: Option<String>,
     created_at: NaiveDateTime,
     updated_at: NaiveDateTime,
     status: ContactStatus,
@@ -109,13 +111,15 @@ impl PartialEq for DbContact {
 }
 
 impl DbContact {
-    const FETCH_QUERY: &'static str = r#"SELECT * FROM contact"#;
+    /// Contacts in the trash are left out, see `db::trash`
+    const FETCH_QUERY: &'static str = r#"SELECT * FROM contact WHERE deleted_at IS NULL"#;
 
     pub fn new(pubkey: &XOnlyPublicKey) -> Self {
         Self {
             pubkey: *pubkey,
             relay_url: None,
             petname: None,
+            notes: None,
             status: ContactStatus::Unknown,
             created_at: chrono::Utc::now().naive_utc(),
             updated_at: chrono::Utc::now().naive_utc(),
@@ -191,6 +195,9 @@ impl DbContact {
     pub fn get_petname(&self) -> Option<String> {
         self.petname.clone()
     }
+    pub fn get_notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
     pub fn get_profile_cache(&self) -> Option<ProfileCache> {
         self.profile_cache.clone()
     }
@@ -215,9 +222,18 @@ impl DbContact {
         }
         None
     }
+    pub fn get_nip05(&self) -> Option<String> {
+        self.profile_cache
+            .as_ref()
+            .and_then(|profile| profile.metadata.nip05.to_owned())
+    }
     pub fn get_relay_url(&self) -> Option<Url> {
         self.relay_url.clone()
     }
+    /// When it was added to the contacts
+    pub fn get_created_at(&self) -> NaiveDateTime {
+        self.created_at
+    }
     pub fn with_profile_cache(mut self, cache: &ProfileCache) -> Self {
         self.profile_cache = Some(cache.clone());
         self
@@ -231,6 +247,35 @@ impl DbContact {
         self.petname = Some(petname.to_owned());
         self
     }
+    /// Blank notes are removed
+    pub fn with_notes(mut self, notes: &str) -> Self {
+        self.notes = (!notes.trim().is_empty()).then(|| notes.trim_end().to_owned());
+        self
+    }
+
+    /// Fields of `duplicate` fill the blank ones, both notes are kept
+    pub fn merged_with(mut self, duplicate: &DbContact) -> Self {
+        let is_blank = |s: &Option<String>| s.as_deref().map_or(true, |s| s.trim().is_empty());
+        if is_blank(&self.petname) {
+            self.petname = duplicate.petname.clone();
+        }
+        if self.relay_url.is_none() {
+            self.relay_url = duplicate.relay_url.clone();
+        }
+        self.notes = match (&self.notes, &duplicate.notes) {
+            (Some(notes), Some(other)) if notes != other => Some(format!("{}\n\n{}", notes, other)),
+            (None, other) => other.clone(),
+            (notes, _) => notes.clone(),
+        };
+        if self.profile_cache.is_none() {
+            self.profile_cache = duplicate.profile_cache.clone();
+        }
+        if let ContactStatus::Known = duplicate.status {
+            self.status = ContactStatus::Known;
+        }
+        self.created_at = self.created_at.min(duplicate.created_at);
+        self
+    }
 
     pub fn select_name(&self) -> String {
         if let Some(petname) = &self.get_petname() {
@@ -260,6 +305,14 @@ impl DbContact {
         Ok(())
     }
 
+    /// Dominant color of the cached profile picture
+    pub fn profile_accent(&self) -> Option<iced::Color> {
+        self.profile_cache
+            .as_ref()
+            .and_then(|cache| cache.profile_pic_cache.as_ref())
+            .and_then(|image| image.accent())
+    }
+
     pub fn profile_image(
         &self,
         size: ImageSize,
@@ -295,23 +348,30 @@ impl DbContact {
         Ok(db_contacts)
     }
 
-    pub async fn fetch(
-        pool: &SqlitePool,
-        cache_pool: &SqlitePool,
-    ) -> Result<Vec<DbContact>, Error> {
-        let mut db_contacts: Vec<DbContact> = sqlx::query_as::<_, DbContact>(Self::FETCH_QUERY)
+    /// Contacts with their profile cache, joined from the attached cache database
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbContact>, Error> {
+        let sql = Self::with_profile_query("");
+        let db_contacts = sqlx::query(&sql)
+            .try_map(|row: SqliteRow| Self::from_profile_row(&row))
             .fetch_all(pool)
             .await?;
+        Ok(db_contacts)
+    }
 
-        for mut db_contact in &mut db_contacts {
-            if let Some(cache) =
-                ProfileCache::fetch_by_public_key(cache_pool, db_contact.pubkey()).await?
-            {
-                db_contact.profile_cache = Some(cache.to_owned());
-            }
-        }
+    /// `FETCH_QUERY` with the profile columns of `ProfileCache::joined_sql`,
+    /// `filter` is appended to the where clause
+    fn with_profile_query(filter: &str) -> String {
+        let (columns, joins) = ProfileCache::joined_sql("contact.pubkey");
+        format!(
+            "SELECT contact.*, {} FROM contact {} WHERE contact.deleted_at IS NULL {}",
+            columns, joins, filter
+        )
+    }
 
-        Ok(db_contacts)
+    fn from_profile_row(row: &SqliteRow) -> Result<DbContact, sqlx::Error> {
+        let mut db_contact = DbContact::from_row(row)?;
+        db_contact.profile_cache = ProfileCache::from_joined_row(row)?;
+        Ok(db_contact)
     }
 
     pub async fn insert(pool: &SqlitePool, pubkey: &XOnlyPublicKey) -> Result<i64, Error> {
@@ -319,72 +379,45 @@ impl DbContact {
             .await
             .unwrap_or(Utc::now().naive_utc());
 
-        let output =
-            sqlx::query("INSERT INTO contact (pubkey, created_at, updated_at) VALUES (?, ?, ?);")
-                .bind(&pubkey.to_string())
-                .bind(utc_now.timestamp_millis())
-                .bind(utc_now.timestamp_millis())
-                .execute(pool)
-                .await?;
+        // a contact in the trash comes back
+        let sql = r#"
+            INSERT INTO contact (pubkey, created_at, updated_at) VALUES (?, ?, ?)
+            ON CONFLICT(pubkey) DO UPDATE SET deleted_at = NULL, updated_at = excluded.updated_at;
+        "#;
+        let output = sqlx::query(sql)
+            .bind(&pubkey.to_string())
+            .bind(utc_now.timestamp_millis())
+            .bind(utc_now.timestamp_millis())
+            .execute(pool)
+            .await?;
 
         Ok(output.last_insert_rowid())
     }
 
     pub async fn fetch_insert(
         pool: &SqlitePool,
-        cache_pool: &SqlitePool,
         pubkey: &XOnlyPublicKey,
     ) -> Result<DbContact, Error> {
-        let sql = format!("{} WHERE pubkey = ?", Self::FETCH_QUERY);
-
-        let result = sqlx::query_as::<_, DbContact>(&sql)
-            .bind(&pubkey.to_string())
-            .fetch_optional(pool)
-            .await?;
-
-        let mut db_contact = if result.is_none() {
-            let last_insert_rowid = Self::insert(pool, pubkey).await?;
-            let sql = format!("{} WHERE id = ?", Self::FETCH_QUERY);
-            sqlx::query_as::<_, DbContact>(&sql)
-                .bind(last_insert_rowid)
-                .fetch_one(pool)
-                .await?
-        } else {
-            result.unwrap()
-        };
-
-        if let Some(cache) =
-            ProfileCache::fetch_by_public_key(cache_pool, db_contact.pubkey()).await?
-        {
-            db_contact = db_contact.with_profile_cache(&cache);
+        if let Some(db_contact) = Self::fetch_one(pool, pubkey).await? {
+            return Ok(db_contact);
         }
-
-        Ok(db_contact)
+        Self::insert(pool, pubkey).await?;
+        Self::fetch_one(pool, pubkey)
+            .await?
+            .ok_or_else(|| Error::NotFoundContact(pubkey.to_string()))
     }
 
     pub async fn fetch_one(
         pool: &SqlitePool,
-        cache_pool: &SqlitePool,
         pubkey: &XOnlyPublicKey,
     ) -> Result<Option<DbContact>, Error> {
-        let sql = format!("{} WHERE pubkey = ?", Self::FETCH_QUERY);
-
-        let result = sqlx::query_as::<_, DbContact>(&sql)
+        let sql = Self::with_profile_query("AND contact.pubkey = ?");
+        let db_contact = sqlx::query(&sql)
             .bind(&pubkey.to_string())
+            .try_map(|row: SqliteRow| Self::from_profile_row(&row))
             .fetch_optional(pool)
             .await?;
-
-        if let Some(mut db_contact) = result {
-            if let Some(cache) =
-                ProfileCache::fetch_by_public_key(cache_pool, db_contact.pubkey()).await?
-            {
-                db_contact = db_contact.with_profile_cache(&cache);
-            }
-
-            Ok(Some(db_contact))
-        } else {
-            Ok(None)
-        }
+        Ok(db_contact)
     }
 
     pub async fn upsert_contact(pool: &SqlitePool, contact: &DbContact) -> Result<(), Error> {
@@ -398,7 +431,7 @@ impl DbContact {
         // SQL queries as static strings
         const UPDATE_SQL: &str = r#"
             UPDATE contact 
-            SET relay_url=?, petname=?, updated_at=?
+            SET relay_url=?, petname=?, updated_at=?, deleted_at=NULL
             WHERE pubkey=?
         "#;
         const INSERT_SQL: &str = r#"
@@ -450,13 +483,14 @@ impl DbContact {
 
         let sql = r#"
             UPDATE contact 
-            SET relay_url=?, petname=?, status=?, updated_at=?
+            SET relay_url=?, petname=?, notes=?, status=?, updated_at=?
             WHERE pubkey=?
         "#;
 
         sqlx::query(sql)
             .bind(&contact.relay_url.as_ref().map(|url| url.to_string()))
             .bind(&contact.petname)
+            .bind(&contact.notes)
             .bind(contact.status as u8)
             .bind(utc_now.timestamp_millis())
             .bind(&contact.pubkey.to_string())
@@ -466,25 +500,98 @@ impl DbContact {
         Ok(())
     }
 
+    /// Moves the contact to the trash, it's purged after `trash::RETENTION_DAYS`
     pub async fn delete(pool: &SqlitePool, contact: &DbContact) -> Result<(), Error> {
-        let sql = "DELETE FROM contact WHERE pubkey=?";
+        let utc_now = UserConfig::get_corrected_time(pool)
+            .await
+            .unwrap_or(Utc::now().naive_utc());
+        let sql = "UPDATE contact SET deleted_at=? WHERE pubkey=?";
 
         sqlx::query(sql)
+            .bind(utc_now.timestamp_millis())
             .bind(&contact.pubkey.to_string())
             .execute(pool)
             .await?;
 
         Ok(())
     }
-    pub async fn delete_all(pool: &SqlitePool) -> Result<(), Error> {
-        let sql = "DELETE FROM contact;";
+    /// Removes the contacts that are not in `kept`, the others keep their
+    /// notes and creation date. Contacts in the trash are kept
+    pub async fn delete_missing(pool: &SqlitePool, kept: &[XOnlyPublicKey]) -> Result<(), Error> {
+        let sql = if kept.is_empty() {
+            "DELETE FROM contact WHERE deleted_at IS NULL;".to_owned()
+        } else {
+            let placeholders = vec!["?"; kept.len()].join(", ");
+            format!(
+                "DELETE FROM contact WHERE deleted_at IS NULL AND pubkey NOT IN ({});",
+                placeholders
+            )
+        };
+        let mut query = sqlx::query(&sql);
+        for pubkey in kept {
+            query = query.bind(pubkey.to_string());
+        }
+        query.execute(pool).await?;
 
-        sqlx::query(sql).execute(pool).await?;
+        Ok(())
+    }
+    /// Stored row of the contact, in the trash or not, to put back with `restore_snapshot`
+    pub async fn snapshot(
+        pool: &SqlitePool,
+        pubkey: &XOnlyPublicKey,
+    ) -> Result<Option<ContactSnapshot>, Error> {
+        let sql = "SELECT * FROM contact WHERE pubkey=?";
+        let snapshot = sqlx::query(sql)
+            .bind(&pubkey.to_string())
+            .try_map(|row: SqliteRow| {
+                Ok(ContactSnapshot {
+                    contact: DbContact::from_row(&row)?,
+                    deleted_at: row.get("deleted_at"),
+                })
+            })
+            .fetch_optional(pool)
+            .await?;
+        Ok(snapshot)
+    }
+    /// Undoes the changes made to the contact since `snapshot`, a contact
+    /// that wasn't stored then is removed
+    pub async fn restore_snapshot(
+        pool: &SqlitePool,
+        pubkey: &XOnlyPublicKey,
+        snapshot: Option<ContactSnapshot>,
+    ) -> Result<(), Error> {
+        let Some(ContactSnapshot {
+            contact,
+            deleted_at,
+        }) = snapshot
+        else {
+            sqlx::query("DELETE FROM contact WHERE pubkey=?")
+                .bind(&pubkey.to_string())
+                .execute(pool)
+                .await?;
+            return Ok(());
+        };
+
+        let sql = r#"
+            UPDATE contact 
+            SET relay_url=?, petname=?, notes=?, status=?, updated_at=?, deleted_at=?
+            WHERE pubkey=?
+        "#;
+        sqlx::query(sql)
+            .bind(&contact.relay_url.as_ref().map(|url| url.to_string()))
+            .bind(&contact.petname)
+            .bind(&contact.notes)
+            .bind(contact.status as u8)
+            .bind(contact.updated_at.timestamp_millis())
+            .bind(deleted_at)
+            .bind(&pubkey.to_string())
+            .execute(pool)
+            .await?;
 
         Ok(())
     }
     pub async fn has_contact(pool: &SqlitePool, pubkey: &XOnlyPublicKey) -> Result<bool, Error> {
-        let sql = "SELECT EXISTS(SELECT 1 FROM contact WHERE pubkey=?)";
+        let sql = "SELECT EXISTS(SELECT 1 FROM contact WHERE pubkey=? AND deleted_at IS NULL)";
 
         let exists: (bool,) = sqlx::query_as(sql)
             .bind(pubkey.to_string())
@@ -495,6 +602,13 @@ impl DbContact {
     }
 }
 
+/// Contact row as it was before a change, see `DbContact::snapshot`
+#[derive(Debug, Clone)]
+pub struct ContactSnapshot {
+    contact: DbContact,
+    deleted_at: Option<i64>,
+}
+
 impl sqlx::FromRow<'_, SqliteRow> for DbContact {
     fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
         let pubkey = row.try_get::<String, &str>("pubkey")?;
@@ -514,6 +628,7 @@ impl sqlx::FromRow<'_, SqliteRow> for DbContact {
             .transpose()?;
 
         let petname: Option<String> = row.get("petname");
+        let notes: Option<String> = row.get("notes");
 
         Ok(DbContact {
             profile_cache: None,
@@ -521,8 +636,47 @@ impl sqlx::FromRow<'_, SqliteRow> for DbContact {
             created_at,
             updated_at,
             petname,
+            notes,
             relay_url,
             status: row.get::<u8, &str>("status").into(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use nostr::Keys;
+
+    #[tokio::test]
+    async fn restores_contact_snapshot() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let added = Keys::generate().public_key();
+        let edited = Keys::generate().public_key();
+
+        DbContact::insert(pool, &edited).await.unwrap();
+        let contact = DbContact::new(&edited).with_notes("Met at the Rust meetup");
+        DbContact::update(pool, &contact).await.unwrap();
+
+        let before_add = DbContact::snapshot(pool, &added).await.unwrap();
+        let before_edit = DbContact::snapshot(pool, &edited).await.unwrap();
+        DbContact::insert(pool, &added).await.unwrap();
+        DbContact::update(pool, &contact.clone().with_notes(""))
+            .await
+            .unwrap();
+        DbContact::delete(pool, &contact).await.unwrap();
+
+        DbContact::restore_snapshot(pool, &added, before_add)
+            .await
+            .unwrap();
+        DbContact::restore_snapshot(pool, &edited, before_edit)
+            .await
+            .unwrap();
+
+        assert!(DbContact::snapshot(pool, &added).await.unwrap().is_none());
+        let restored = DbContact::fetch_one(pool, &edited).await.unwrap().unwrap();
+        assert_eq!(restored.get_notes(), Some("Met at the Rust meetup"));
+    }
+}
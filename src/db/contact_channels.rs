@@ -0,0 +1,153 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use nostr::{secp256k1::XOnlyPublicKey, EventId};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::db::ChannelCache;
+use crate::utils::{event_hash_or_err, millis_to_naive_or_err, public_key_or_err};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// A contact posted in a channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactActivity {
+    pub channel_id: EventId,
+    pub author: XOnlyPublicKey,
+    pub created_at: NaiveDateTime,
+}
+
+/// Channel some of the user's contacts are active in
+#[derive(Debug, Clone)]
+pub struct ContactChannel {
+    pub channel_id: EventId,
+    /// Most recently active first
+    pub contacts: Vec<XOnlyPublicKey>,
+    pub contact_names: Vec<String>,
+    pub last_active: NaiveDateTime,
+    pub cache: Option<ChannelCache>,
+}
+
+impl ContactActivity {
+    /// Latest message of each contact in each stored channel
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<ContactActivity>, Error> {
+        let sql = r#"
+            SELECT channel_id, author, MAX(created_at) AS created_at
+            FROM channel_message
+            WHERE is_users = 0 AND author IN (SELECT pubkey FROM contact WHERE deleted_at IS NULL)
+            GROUP BY channel_id, author
+        "#;
+        let activity = sqlx::query_as::<_, ContactActivity>(sql)
+            .fetch_all(pool)
+            .await?;
+        Ok(activity)
+    }
+}
+
+/// Most distinct contacts first, then the most recently active.
+/// Channels in `subscribed` are left out.
+pub fn rank(
+    activity: &[ContactActivity],
+    subscribed: &HashSet<EventId>,
+    limit: usize,
+) -> Vec<ContactChannel> {
+    let mut by_channel: HashMap<EventId, HashMap<XOnlyPublicKey, NaiveDateTime>> = HashMap::new();
+    for act in activity {
+        if subscribed.contains(&act.channel_id) {
+            continue;
+        }
+        let last = by_channel
+            .entry(act.channel_id)
+            .or_default()
+            .entry(act.author)
+            .or_insert(act.created_at);
+        *last = (*last).max(act.created_at);
+    }
+
+    let mut channels: Vec<_> = by_channel
+        .into_iter()
+        .filter_map(|(channel_id, authors)| {
+            let mut authors: Vec<_> = authors.into_iter().collect();
+            authors.sort_by(|a, b| b.1.cmp(&a.1));
+            let last_active = authors.first()?.1;
+            Some(ContactChannel {
+                channel_id,
+                contacts: authors.into_iter().map(|(author, _)| author).collect(),
+                contact_names: vec![],
+                last_active,
+                cache: None,
+            })
+        })
+        .collect();
+    channels.sort_by(|a, b| {
+        b.contacts
+            .len()
+            .cmp(&a.contacts.len())
+            .then(b.last_active.cmp(&a.last_active))
+    });
+    channels.truncate(limit);
+    channels
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for ContactActivity {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let channel_id = row.try_get::<String, &str>("channel_id")?;
+        let author = row.try_get::<String, &str>("author")?;
+        Ok(Self {
+            channel_id: event_hash_or_err(&channel_id, "channel_id")?,
+            author: public_key_or_err(&author, "author")?,
+            created_at: millis_to_naive_or_err(
+                row.try_get::<i64, &str>("created_at")?,
+                "created_at",
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Keys;
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDateTime::from_timestamp_opt(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn ranks_by_distinct_contacts_then_recency() {
+        let quiet = EventId::from_slice(&[1; 32]).unwrap();
+        let busy = EventId::from_slice(&[2; 32]).unwrap();
+        let recent = EventId::from_slice(&[3; 32]).unwrap();
+        let joined = EventId::from_slice(&[4; 32]).unwrap();
+        let (alice, bob) = (Keys::generate().public_key(), Keys::generate().public_key());
+        let act = |channel_id, author, secs| ContactActivity {
+            channel_id,
+            author,
+            created_at: at(secs),
+        };
+        let activity = vec![
+            act(quiet, alice, 10),
+            act(busy, alice, 20),
+            act(busy, bob, 30),
+            // the same contact twice counts once
+            act(recent, alice, 40),
+            act(recent, alice, 50),
+            act(joined, alice, 60),
+            act(joined, bob, 60),
+        ];
+        let subscribed = HashSet::from([joined]);
+
+        let ranked = rank(&activity, &subscribed, 10);
+        let order: Vec<_> = ranked.iter().map(|c| c.channel_id).collect();
+        assert_eq!(order, vec![busy, recent, quiet]);
+        assert_eq!(ranked[0].contacts, vec![bob, alice]);
+        assert_eq!(ranked[1].last_active, at(50));
+
+        assert_eq!(rank(&activity, &subscribed, 1).len(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+use crate::db::DbContact;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("{0}")]
+    FromContact(#[from] crate::db::contact::Error),
+
+    #[error("A contact can't be merged with itself")]
+    SameContact,
+}
+
+/// Two contacts of the same person folded into one
+#[derive(Debug, Clone)]
+pub struct ContactMerge {
+    pub kept: DbContact,
+    pub duplicate: XOnlyPublicKey,
+    /// Messages moved to the kept conversation
+    pub messages: u64,
+}
+
+impl ContactMerge {
+    /// Moves the conversation and labels of `duplicate` to `keep`, fills the blank
+    /// fields of `keep` with the duplicate's and deletes the duplicate for good.
+    /// The messages stay readable, decryption uses the keys in their events
+    pub async fn apply(
+        pool: &SqlitePool,
+        keep: &DbContact,
+        duplicate: &DbContact,
+    ) -> Result<ContactMerge, Error> {
+        if keep.pubkey() == duplicate.pubkey() {
+            return Err(Error::SameContact);
+        }
+        let kept = keep.to_owned().merged_with(duplicate);
+        let keep_key = kept.pubkey().to_string();
+        let dup_key = duplicate.pubkey().to_string();
+
+        let mut tx = pool.begin().await?;
+        let messages = sqlx::query("UPDATE message SET chat_pubkey = ?1 WHERE chat_pubkey = ?2")
+            .bind(&keep_key)
+            .bind(&dup_key)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        // labels are unioned, the kept conversation's settings and draft win
+        let moves = [
+            r#"
+            INSERT OR IGNORE INTO conversation_label (label_id, conversation)
+            SELECT label_id, ?1 FROM conversation_label WHERE conversation = ?2
+            "#,
+            r#"
+            INSERT INTO conversation_visit (conversation, visits, last_visit)
+            SELECT ?1, visits, last_visit FROM conversation_visit WHERE conversation = ?2
+            ON CONFLICT(conversation) DO UPDATE SET
+                visits = visits + excluded.visits,
+                last_visit = MAX(last_visit, excluded.last_visit)
+            "#,
+            "UPDATE OR IGNORE draft SET conversation = ?1 WHERE conversation = ?2",
+            "UPDATE OR IGNORE auto_translate SET chat_pubkey = ?1 WHERE chat_pubkey = ?2",
+            "UPDATE OR IGNORE auto_save_attachments SET chat_pubkey = ?1 WHERE chat_pubkey = ?2",
+        ];
+        for sql in moves {
+            sqlx::query(sql)
+                .bind(&keep_key)
+                .bind(&dup_key)
+                .execute(&mut tx)
+                .await?;
+        }
+        // what's left belongs to the duplicate's key, history is fetched per key
+        let cleanups = [
+            "DELETE FROM conversation_label WHERE conversation = ?",
+            "DELETE FROM conversation_visit WHERE conversation = ?",
+            "DELETE FROM draft WHERE conversation = ?",
+            "DELETE FROM auto_translate WHERE chat_pubkey = ?",
+            "DELETE FROM auto_save_attachments WHERE chat_pubkey = ?",
+            "DELETE FROM dm_history WHERE chat_pubkey = ?",
+        ];
+        for sql in cleanups {
+            sqlx::query(sql).bind(&dup_key).execute(&mut tx).await?;
+        }
+
+        let sql = r#"
+            UPDATE contact
+            SET relay_url = ?1, petname = ?2, notes = ?3, created_at = ?4,
+                status = MAX(status, (SELECT status FROM contact WHERE pubkey = ?5))
+            WHERE pubkey = ?6
+        "#;
+        sqlx::query(sql)
+            .bind(kept.get_relay_url().map(|url| url.to_string()))
+            .bind(kept.get_petname())
+            .bind(kept.get_notes())
+            .bind(kept.get_created_at().timestamp_millis())
+            .bind(&dup_key)
+            .bind(&keep_key)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("DELETE FROM contact WHERE pubkey = ?")
+            .bind(&dup_key)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            "Merged contact {} into {}, {} messages moved",
+            dup_key,
+            keep_key,
+            messages
+        );
+        let kept = DbContact::fetch_one(pool, kept.pubkey())
+            .await?
+            .unwrap_or(kept);
+        Ok(ContactMerge {
+            kept,
+            duplicate: duplicate.pubkey().to_owned(),
+            messages,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DbLabel, DbMessage, LabelNotify, TestDatabase};
+    use crate::test_support::{generate, AccountSpec};
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn merges_conversations_and_labels() {
+        let db = TestDatabase::new().await.unwrap();
+        let (pool, cache_pool) = (db.pool(), db.cache_pool());
+        let spec = AccountSpec {
+            contacts: 2,
+            messages_per_contact: 4,
+            channels: 0,
+            interval: Duration::seconds(10),
+            ..Default::default()
+        };
+        let account = generate(pool, cache_pool, &spec).await.unwrap();
+        let (keep, duplicate) = (account.contacts[0], account.contacts[1]);
+
+        let label = DbLabel::insert(pool, "friends", "#00ff00", LabelNotify::Default)
+            .await
+            .unwrap();
+        DbLabel::assign(pool, label.id, &duplicate.to_string())
+            .await
+            .unwrap();
+        let dup_contact = DbContact::fetch_one(pool, &duplicate)
+            .await
+            .unwrap()
+            .unwrap()
+            .with_petname("alice")
+            .with_notes("met at the conference");
+        DbContact::update(pool, &dup_contact).await.unwrap();
+        let keep_contact = DbContact::fetch_one(pool, &keep).await.unwrap().unwrap();
+
+        let merge = ContactMerge::apply(pool, &keep_contact, &dup_contact)
+            .await
+            .unwrap();
+
+        assert_eq!(merge.messages, 4);
+        assert_eq!(merge.kept.get_petname().as_deref(), Some("alice"));
+        assert_eq!(merge.kept.get_notes(), Some("met at the conference"));
+        let contacts = DbContact::fetch(pool).await.unwrap();
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].pubkey(), &keep);
+        let chat = DbMessage::fetch_chat(pool, &keep).await.unwrap();
+        assert_eq!(chat.len(), 8);
+        let labels = DbLabel::fetch_labels(pool).await.unwrap();
+        assert!(labels.has(&keep.to_string(), label.id));
+        assert!(!labels.has(&duplicate.to_string(), label.id));
+        assert!(matches!(
+            ContactMerge::apply(pool, &merge.kept, &merge.kept).await,
+            Err(Error::SameContact)
+        ));
+    }
+}
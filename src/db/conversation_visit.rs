@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::millis_to_naive_or_err;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Opened conversation, contact public key or channel id
+#[derive(Debug, Clone)]
+pub struct ConversationVisit {
+    pub conversation: String,
+    pub visits: i64,
+    pub last_visit: NaiveDateTime,
+}
+
+impl ConversationVisit {
+    pub async fn record(pool: &SqlitePool, conversation: &str) -> Result<(), Error> {
+        let sql = r#"
+            INSERT INTO conversation_visit (conversation, visits, last_visit) VALUES (?, 1, ?)
+            ON CONFLICT(conversation) DO UPDATE SET visits = visits + 1, last_visit = excluded.last_visit
+        "#;
+        sqlx::query(sql)
+            .bind(conversation)
+            .bind(Utc::now().timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Conversations from the one opened last
+    pub async fn fetch_recent(pool: &SqlitePool) -> Result<Vec<String>, Error> {
+        let sql = "SELECT conversation FROM conversation_visit ORDER BY last_visit DESC";
+        Ok(sqlx::query_scalar(sql).fetch_all(pool).await?)
+    }
+
+    /// Frecency of every visited conversation
+    pub async fn fetch_scores(pool: &SqlitePool) -> Result<HashMap<String, f64>, Error> {
+        let now = Utc::now().naive_utc();
+        let visits = sqlx::query_as::<_, Self>("SELECT * FROM conversation_visit")
+            .fetch_all(pool)
+            .await?;
+        Ok(visits
+            .into_iter()
+            .map(|visit| {
+                let score = visit.frecency(now);
+                (visit.conversation, score)
+            })
+            .collect())
+    }
+
+    /// Visits weighted by how long ago the last one was, like browser history
+    pub fn frecency(&self, now: NaiveDateTime) -> f64 {
+        let age_days = (now - self.last_visit).num_days();
+        let weight = match age_days {
+            d if d < 4 => 100.0,
+            d if d < 14 => 70.0,
+            d if d < 31 => 50.0,
+            d if d < 90 => 30.0,
+            _ => 10.0,
+        };
+        self.visits as f64 * weight
+    }
+}
+
+/// Visited contacts by frecency, split in groups of `sizes`.
+/// Contacts never opened are left out
+pub fn prefetch_waves(
+    scores: &HashMap<String, f64>,
+    contacts: &[XOnlyPublicKey],
+    sizes: &[usize],
+) -> Vec<Vec<XOnlyPublicKey>> {
+    let mut visited: Vec<_> = contacts
+        .iter()
+        .filter_map(|pubkey| {
+            scores
+                .get(&pubkey.to_string())
+                .map(|score| (pubkey.to_owned(), *score))
+        })
+        .collect();
+    visited.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    let mut visited = visited.into_iter().map(|(pubkey, _)| pubkey);
+    sizes
+        .iter()
+        .map(|size| visited.by_ref().take(*size).collect::<Vec<_>>())
+        .filter(|wave| !wave.is_empty())
+        .collect()
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for ConversationVisit {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            conversation: row.try_get::<String, &str>("conversation")?,
+            visits: row.try_get::<i64, &str>("visits")?,
+            last_visit: millis_to_naive_or_err(
+                row.try_get::<i64, &str>("last_visit")?,
+                "last_visit",
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn visit(visits: i64, days_ago: i64, now: NaiveDateTime) -> ConversationVisit {
+        ConversationVisit {
+            conversation: "chat".into(),
+            visits,
+            last_visit: now - Duration::days(days_ago),
+        }
+    }
+
+    #[test]
+    fn recent_visits_weigh_more() {
+        let now = Utc::now().naive_utc();
+        assert!(visit(2, 1, now).frecency(now) > visit(2, 40, now).frecency(now));
+        assert!(visit(10, 100, now).frecency(now) < visit(2, 1, now).frecency(now));
+    }
+
+    #[test]
+    fn splits_visited_contacts_in_waves() {
+        let contacts: Vec<_> = (0..4)
+            .map(|_| nostr::Keys::generate().public_key())
+            .collect();
+        let scores = HashMap::from([
+            (contacts[0].to_string(), 10.0),
+            (contacts[1].to_string(), 300.0),
+            (contacts[2].to_string(), 70.0),
+        ]);
+
+        let waves = prefetch_waves(&scores, &contacts, &[1, 5, 5]);
+        assert_eq!(
+            waves,
+            vec![vec![contacts[1]], vec![contacts[2], contacts[0]]]
+        );
+        assert!(prefetch_waves(&HashMap::new(), &contacts, &[1, 5]).is_empty());
+    }
+}
@@ -1,9 +1,14 @@
-use crate::{consts::APP_PROJECT_DIRS, db::UserConfig};
+use crate::{
+    consts::APP_PROJECT_DIRS,
+    db::{DbRelay, DbRelayResponse, UserConfig},
+};
 use thiserror::Error;
 
 use directories::ProjectDirs;
+use sqlx::sqlite::SqlitePoolOptions;
 use sqlx::SqlitePool;
 use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -20,24 +25,42 @@ pub enum Error {
         "Database version is newer than supported by this executable (v{current} > v{db_ver})"
     )]
     NewerDbVersion { current: usize, db_ver: usize },
+
+    #[error("{0}")]
+    FromDbRelay(#[from] crate::db::relay::Error),
+
+    #[error("{0}")]
+    FromDbRelayResponse(#[from] crate::db::relay_response::Error),
 }
 
+/// The user's database and the cache shared by every account.
+/// Connections of `pool` also see the cache as the `cache` schema,
+/// so queries can join both, e.g. `JOIN cache.profile_meta_cache`.
 #[derive(Debug, Clone)]
 pub struct Database {
     pub pool: SqlitePool,
     pub cache_pool: SqlitePool,
+    /// Schema versions from and to, when migrations ran at startup
+    pub migrated: Option<(usize, usize)>,
 }
 
 impl Database {
     pub async fn new(pubkey: &str) -> Result<Self, Error> {
-        let pool = db_pool(pubkey).await?;
-        let cache_pool = get_cache_pool().await?;
-        let s = Self { pool, cache_pool };
+        let (cache_pool, cache_file) = get_cache_pool().await?;
+        let (pool, migrated) = db_pool(pubkey, cache_file).await?;
+        let s = Self {
+            pool,
+            cache_pool,
+            migrated,
+        };
         Ok(s)
     }
 }
 
-async fn db_pool(pubkey: &str) -> Result<SqlitePool, Error> {
+async fn db_pool(
+    pubkey: &str,
+    cache_file: Option<PathBuf>,
+) -> Result<(SqlitePool, Option<(usize, usize)>), Error> {
     let dirs = ProjectDirs::from(APP_PROJECT_DIRS.0, APP_PROJECT_DIRS.1, APP_PROJECT_DIRS.2)
         .ok_or(Error::NotFoundProjectDirectory)?;
     let data_dir = dirs.data_dir();
@@ -59,19 +82,23 @@ async fn db_pool(pubkey: &str) -> Result<SqlitePool, Error> {
     };
 
     tracing::info!("Connecting database");
-    let pool = SqlitePool::connect(&db_url).await?;
-    upgrade_db(&pool).await?;
-    Ok(pool)
+    let pool = match cache_file {
+        Some(cache_file) => connect_with_cache(&db_url, &cache_file).await?,
+        None => SqlitePool::connect(&db_url).await?,
+    };
+    let migrated = upgrade_db(&pool).await?;
+    Ok((pool, migrated))
 }
 
-async fn get_cache_pool() -> Result<SqlitePool, Error> {
+/// Also returns the cache file, `None` when the cache is in memory
+async fn get_cache_pool() -> Result<(SqlitePool, Option<PathBuf>), Error> {
     let dirs = ProjectDirs::from(APP_PROJECT_DIRS.0, APP_PROJECT_DIRS.1, APP_PROJECT_DIRS.2)
         .ok_or(Error::NotFoundProjectDirectory)?;
     let cache_dir = dirs.cache_dir();
     std::fs::create_dir_all(cache_dir)?;
 
-    let db_url = if IN_MEMORY {
-        "sqlite::memory:".to_owned()
+    let (db_url, cache_file) = if IN_MEMORY {
+        ("sqlite::memory:".to_owned(), None)
     } else {
         let mut path_ext = String::new();
         for dir in cache_dir.iter() {
@@ -82,7 +109,11 @@ async fn get_cache_pool() -> Result<SqlitePool, Error> {
                 path_ext.push_str(&format!("/{}", p));
             }
         }
-        format!("sqlite://{}.db3?mode=rwc", &path_ext)
+        let cache_file = PathBuf::from(format!("{}.db3", cache_dir.display()));
+        (
+            format!("sqlite://{}.db3?mode=rwc", &path_ext),
+            Some(cache_file),
+        )
     };
 
     tracing::info!("Connecting to cache database");
@@ -92,7 +123,75 @@ async fn get_cache_pool() -> Result<SqlitePool, Error> {
 
     upgrade_cache_db(&cache_pool).await?;
 
-    Ok(cache_pool)
+    Ok((cache_pool, cache_file))
+}
+
+/// Pool whose connections attach the cache database as the `cache` schema.
+/// An in memory cache can't be shared between connections, without a cache
+/// file the joined queries fail.
+pub async fn connect_with_cache(db_url: &str, cache_file: &Path) -> Result<SqlitePool, Error> {
+    let cache_file = cache_file.to_string_lossy().to_string();
+    let pool = SqlitePoolOptions::new()
+        .after_connect(move |conn, _meta| {
+            let cache_file = cache_file.clone();
+            Box::pin(async move {
+                sqlx::query("ATTACH DATABASE ? AS cache;")
+                    .bind(cache_file)
+                    .execute(conn)
+                    .await?;
+                Ok(())
+            })
+        })
+        .connect(db_url)
+        .await?;
+    Ok(pool)
+}
+
+/// Databases in temporary files, set up like at startup: the cache is
+/// attached to the user's pool, both are migrated and the kind handlers
+/// create their tables. The files are removed when it drops
+#[cfg(any(test, feature = "test-support"))]
+pub struct TestDatabase {
+    pub db: Database,
+    _dir: tempfile::TempDir,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl TestDatabase {
+    pub async fn new() -> Result<Self, crate::Error> {
+        let dir = tempfile::tempdir()?;
+        let cache_file = dir.path().join("cache.db3");
+        let cache_url = format!("sqlite://{}?mode=rwc", cache_file.display());
+        let cache_pool = SqlitePool::connect(&cache_url)
+            .await
+            .map_err(Error::SqlxError)?;
+        upgrade_cache_db(&cache_pool).await?;
+
+        let db_file = dir.path().join("user.db3");
+        let db_url = format!("sqlite://{}?mode=rwc", db_file.display());
+        let pool = connect_with_cache(&db_url, &cache_file).await?;
+        upgrade_db(&pool).await?;
+        for handler in crate::net::kind::KindRegistry::with_defaults().handlers() {
+            handler.setup(&pool).await?;
+        }
+
+        Ok(Self {
+            db: Database {
+                pool,
+                cache_pool,
+                migrated: None,
+            },
+            _dir: dir,
+        })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.db.pool
+    }
+
+    pub fn cache_pool(&self) -> &SqlitePool {
+        &self.db.cache_pool
+    }
 }
 
 pub async fn upgrade_cache_db(cache_pool: &SqlitePool) -> Result<(), Error> {
@@ -102,10 +201,12 @@ pub async fn upgrade_cache_db(cache_pool: &SqlitePool) -> Result<(), Error> {
     Ok(())
 }
 
-/// Upgrade DB to latest version, and execute pragma settings
-pub async fn upgrade_db(pool: &SqlitePool) -> Result<(), Error> {
+/// Upgrade DB to latest version, and execute pragma settings.
+/// Returns the versions migrated from and to, new databases aren't migrations.
+pub async fn upgrade_db(pool: &SqlitePool) -> Result<Option<(usize, usize)>, Error> {
     // check the version.
     let mut curr_version = curr_db_version(pool).await?;
+    let from_version = curr_version;
     tracing::info!("DB version = {:?}", curr_version);
 
     match curr_version.cmp(&DB_VERSION) {
@@ -118,12 +219,111 @@ pub async fn upgrade_db(pool: &SqlitePool) -> Result<(), Error> {
 
             // for initialized but out-of-date schemas, proceed to
             // upgrade sequentially until we are current.
-            /* if curr_version == 1 {
+            if curr_version == 1 {
                 curr_version = mig_1_to_2(pool).await?;
-            } */
-            /* if curr_version == 2 {
+            }
+            if curr_version == 2 {
                 curr_version = mig_2_to_3(pool).await?;
-            } */
+            }
+            if curr_version == 3 {
+                curr_version = mig_3_to_4(pool).await?;
+            }
+            if curr_version == 4 {
+                curr_version = mig_4_to_5(pool).await?;
+            }
+            if curr_version == 5 {
+                curr_version = mig_5_to_6(pool).await?;
+            }
+            if curr_version == 6 {
+                curr_version = mig_6_to_7(pool).await?;
+            }
+            if curr_version == 7 {
+                curr_version = mig_7_to_8(pool).await?;
+            }
+            if curr_version == 8 {
+                curr_version = mig_8_to_9(pool).await?;
+            }
+            if curr_version == 9 {
+                curr_version = mig_9_to_10(pool).await?;
+            }
+            if curr_version == 10 {
+                curr_version = mig_10_to_11(pool).await?;
+            }
+            if curr_version == 11 {
+                curr_version = mig_11_to_12(pool).await?;
+            }
+
+            if curr_version == 12 {
+                curr_version = mig_12_to_13(pool).await?;
+            }
+
+            if curr_version == 13 {
+                curr_version = mig_13_to_14(pool).await?;
+            }
+
+            if curr_version == 14 {
+                curr_version = mig_14_to_15(pool).await?;
+            }
+
+            if curr_version == 15 {
+                curr_version = mig_15_to_16(pool).await?;
+            }
+
+            if curr_version == 16 {
+                curr_version = mig_16_to_17(pool).await?;
+            }
+
+            if curr_version == 17 {
+                curr_version = mig_17_to_18(pool).await?;
+            }
+
+            if curr_version == 18 {
+                curr_version = mig_18_to_19(pool).await?;
+            }
+
+            if curr_version == 19 {
+                curr_version = mig_19_to_20(pool).await?;
+            }
+
+            if curr_version == 20 {
+                curr_version = mig_20_to_21(pool).await?;
+            }
+
+            if curr_version == 21 {
+                curr_version = mig_21_to_22(pool).await?;
+            }
+
+            if curr_version == 22 {
+                curr_version = mig_22_to_23(pool).await?;
+            }
+
+            if curr_version == 23 {
+                curr_version = mig_23_to_24(pool).await?;
+            }
+
+            if curr_version == 24 {
+                curr_version = mig_24_to_25(pool).await?;
+            }
+
+            if curr_version == 25 {
+                curr_version = mig_25_to_26(pool).await?;
+            }
+
+            if curr_version == 26 {
+                curr_version = mig_26_to_27(pool).await?;
+            }
+
+            if curr_version == 27 {
+                curr_version = mig_27_to_28(pool).await?;
+            }
+
+            if curr_version == 28 {
+                curr_version = mig_28_to_29(pool).await?;
+            }
+
+            if curr_version == 29 {
+                curr_version = mig_29_to_30(pool).await?;
+            }
 
             if curr_version == DB_VERSION {
                 tracing::info!("All migration scripts completed successfully (v{DB_VERSION})");
@@ -145,7 +345,8 @@ pub async fn upgrade_db(pool: &SqlitePool) -> Result<(), Error> {
     // Setup PRAGMA
     // sqlx::query(STARTUP_SQL).execute(pool).await?;
     // tracing::debug!("SQLite PRAGMA startup completed");
-    Ok(())
+    let migrated = from_version > 0 && from_version < curr_version;
+    Ok(migrated.then_some((from_version, curr_version)))
 }
 
 /// Determine the current application database schema version.
@@ -173,14 +374,281 @@ const _UPGRADE_SQL: [&str; 0] = [
 // include_str!("../../migrations/migration.sql")
 ];
 
-/* async fn mig_1_to_2(pool: &SqlitePool) -> Result<usize, Error> {
-    sqlx::query(include_str!("../migrations/002.sql")).execute(pool).await?;
+async fn mig_1_to_2(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/11_pending_event.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 2).await?;
     tracing::info!("database schema upgraded v1 -> v2");
     Ok(2)
-} */
+}
+
+async fn mig_2_to_3(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/14_auto_translate.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 3).await?;
+    tracing::info!("database schema upgraded v2 -> v3");
+    Ok(3)
+}
+
+async fn mig_3_to_4(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/15_template.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 4).await?;
+    tracing::info!("database schema upgraded v3 -> v4");
+    Ok(4)
+}
+
+async fn mig_4_to_5(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/16_channel_mention.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 5).await?;
+    tracing::info!("database schema upgraded v4 -> v5");
+    Ok(5)
+}
+
+async fn mig_5_to_6(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/17_label.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 6).await?;
+    tracing::info!("database schema upgraded v5 -> v6");
+    Ok(6)
+}
+
+async fn mig_6_to_7(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/18_relay_anonymous_auth.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 7).await?;
+    tracing::info!("database schema upgraded v6 -> v7");
+    Ok(7)
+}
+
+async fn mig_7_to_8(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/19_conversation_visit.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 8).await?;
+    tracing::info!("database schema upgraded v7 -> v8");
+    Ok(8)
+}
+
+/// No schema change, relay URLs written differently are merged into one row
+async fn mig_8_to_9(pool: &SqlitePool) -> Result<usize, Error> {
+    let removed = DbRelay::merge_duplicates(pool).await?;
+    DbRelayResponse::merge_duplicate_urls(pool).await?;
+    if removed > 0 {
+        tracing::info!("merged {} duplicate relays", removed);
+    }
+    set_db_version(pool, 9).await?;
+    tracing::info!("database schema upgraded v8 -> v9");
+    Ok(9)
+}
+
+async fn mig_9_to_10(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/20_draft.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 10).await?;
+    tracing::info!("database schema upgraded v9 -> v10");
+    Ok(10)
+}
+
+async fn mig_10_to_11(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/21_blocked.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 11).await?;
+    tracing::info!("database schema upgraded v10 -> v11");
+    Ok(11)
+}
+
+async fn mig_11_to_12(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/22_relay_created_at.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 12).await?;
+    tracing::info!("database schema upgraded v11 -> v12");
+    Ok(12)
+}
+
+async fn mig_12_to_13(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!(
+        "../../migrations/23_relay_response_timing.sql"
+    ))
+    .execute(pool)
+    .await?;
+    set_db_version(pool, 13).await?;
+    tracing::info!("database schema upgraded v12 -> v13");
+    Ok(13)
+}
+
+async fn mig_13_to_14(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/24_relay_violation.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 14).await?;
+    tracing::info!("database schema upgraded v13 -> v14");
+    Ok(14)
+}
+
+async fn mig_14_to_15(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/25_dm_history.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 15).await?;
+    tracing::info!("database schema upgraded v14 -> v15");
+    Ok(15)
+}
+
+async fn mig_15_to_16(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/26_contact_notes.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 16).await?;
+    tracing::info!("database schema upgraded v15 -> v16");
+    Ok(16)
+}
+
+async fn mig_16_to_17(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/29_relay_profile.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 17).await?;
+    tracing::info!("database schema upgraded v16 -> v17");
+    Ok(17)
+}
+
+async fn mig_17_to_18(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/30_channel_keyword.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 18).await?;
+    tracing::info!("database schema upgraded v17 -> v18");
+    Ok(18)
+}
+
+async fn mig_18_to_19(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/31_followed_hashtag.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 19).await?;
+    tracing::info!("database schema upgraded v18 -> v19");
+    Ok(19)
+}
+
+async fn mig_19_to_20(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/32_trash.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 20).await?;
+    tracing::info!("database schema upgraded v19 -> v20");
+    Ok(20)
+}
+
+async fn mig_20_to_21(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/33_message_star.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 21).await?;
+    tracing::info!("database schema upgraded v20 -> v21");
+    Ok(21)
+}
+
+async fn mig_21_to_22(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/34_attachment_download.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 22).await?;
+    tracing::info!("database schema upgraded v21 -> v22");
+    Ok(22)
+}
+
+async fn mig_22_to_23(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/35_channel_archive.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 23).await?;
+    tracing::info!("database schema upgraded v22 -> v23");
+    Ok(23)
+}
+
+async fn mig_23_to_24(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/36_relay_document.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 24).await?;
+    tracing::info!("database schema upgraded v23 -> v24");
+    Ok(24)
+}
+
+async fn mig_24_to_25(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/37_signing_log.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 25).await?;
+    tracing::info!("database schema upgraded v24 -> v25");
+    Ok(25)
+}
+
+async fn mig_25_to_26(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/38_channel_read.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 26).await?;
+    tracing::info!("database schema upgraded v25 -> v26");
+    Ok(26)
+}
+
+async fn mig_26_to_27(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/39_channel_mute.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 27).await?;
+    tracing::info!("database schema upgraded v26 -> v27");
+    Ok(27)
+}
+
+async fn mig_27_to_28(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/41_message_retraction.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 28).await?;
+    tracing::info!("database schema upgraded v27 -> v28");
+    Ok(28)
+}
+
+async fn mig_28_to_29(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/42_event_deletion.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 29).await?;
+    tracing::info!("database schema upgraded v28 -> v29");
+    Ok(29)
+}
+
+async fn mig_29_to_30(pool: &SqlitePool) -> Result<usize, Error> {
+    sqlx::query(include_str!("../../migrations/43_purged_event.sql"))
+        .execute(pool)
+        .await?;
+    set_db_version(pool, 30).await?;
+    tracing::info!("database schema upgraded v29 -> v30");
+    Ok(30)
+}
+
+async fn set_db_version(pool: &SqlitePool, version: usize) -> Result<(), sqlx::Error> {
+    // PRAGMA doesn't accept bound parameters
+    let sql = format!("PRAGMA user_version = {};", version);
+    sqlx::query(&sql).execute(pool).await?;
+    Ok(())
+}
 
 /// Latest database version
-pub const DB_VERSION: usize = 1;
+pub const DB_VERSION: usize = 30;
 
 const INITIAL_SETUP: [&str; 9] = [
     include_str!("../../migrations/1_setup.sql"),
@@ -194,12 +662,16 @@ const INITIAL_SETUP: [&str; 9] = [
     include_str!("../../migrations/10_subscribed_channel.sql"),
 ];
 
-const CACHE_SETUP: [&str; 5] = [
+const CACHE_SETUP: [&str; 9] = [
     include_str!("../../migrations/cache/1_setup.sql"),
     include_str!("../../migrations/cache/2_profile_meta_cache.sql"),
     include_str!("../../migrations/cache/3_channel_cache.sql"),
     include_str!("../../migrations/cache/4_image_cache.sql"),
     include_str!("../../migrations/cache/5_channel_member_map.sql"),
+    include_str!("../../migrations/cache/6_translation_cache.sql"),
+    include_str!("../../migrations/cache/7_lightning_check.sql"),
+    include_str!("../../migrations/cache/8_image_color.sql"),
+    include_str!("../../migrations/cache/9_channel_moderator_map.sql"),
 ];
 
 const IN_MEMORY: bool = false;
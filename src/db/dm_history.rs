@@ -0,0 +1,142 @@
+use chrono::{Duration, NaiveDateTime};
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+use crate::utils::millis_to_naive_or_err;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// How much DM history is synced at first login
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDepth {
+    LastMonth,
+    SixMonths,
+    All,
+}
+impl SyncDepth {
+    pub const ALL: [SyncDepth; 3] = [SyncDepth::LastMonth, SyncDepth::SixMonths, SyncDepth::All];
+
+    /// Oldest message to sync, `None` for everything
+    pub fn since(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            SyncDepth::LastMonth => Some(now - Duration::days(30)),
+            SyncDepth::SixMonths => Some(now - Duration::days(182)),
+            SyncDepth::All => None,
+        }
+    }
+}
+impl std::fmt::Display for SyncDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncDepth::LastMonth => write!(f, "Last month"),
+            SyncDepth::SixMonths => write!(f, "6 months"),
+            SyncDepth::All => write!(f, "All"),
+        }
+    }
+}
+
+/// Per conversation history fetched with "Fetch older history"
+pub struct DmHistory;
+
+impl DmHistory {
+    pub async fn fetched_since(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+    ) -> Result<Option<NaiveDateTime>, Error> {
+        let sql = "SELECT fetched_since FROM dm_history WHERE chat_pubkey = ?";
+        let millis: Option<i64> = sqlx::query_scalar(sql)
+            .bind(chat_pubkey.to_string())
+            .fetch_optional(pool)
+            .await?;
+        let since = millis
+            .map(|millis| millis_to_naive_or_err(millis, "fetched_since"))
+            .transpose()?;
+        Ok(since)
+    }
+
+    /// Only moves the window back
+    pub async fn extend(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+        since: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let sql = r#"
+            INSERT INTO dm_history (chat_pubkey, fetched_since) VALUES (?, ?)
+            ON CONFLICT(chat_pubkey) DO UPDATE SET
+                fetched_since = MIN(fetched_since, excluded.fetched_since)
+        "#;
+        sqlx::query(sql)
+            .bind(chat_pubkey.to_string())
+            .bind(since.timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Window for the next "Fetch older history", `None` once everything was synced
+pub fn next_history_window(
+    fetched_since: Option<NaiveDateTime>,
+    login_since: Option<NaiveDateTime>,
+) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let until = fetched_since.or(login_since)?;
+    Some((until - Duration::days(HISTORY_STEP_DAYS), until))
+}
+
+const HISTORY_STEP_DAYS: i64 = 90;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use chrono::NaiveDate;
+    use nostr::Keys;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn history_window_steps_back_from_the_oldest_fetch() {
+        let login = Some(date(2024, 6, 1));
+        assert_eq!(
+            next_history_window(None, login),
+            Some((date(2024, 3, 3), date(2024, 6, 1)))
+        );
+        assert_eq!(
+            next_history_window(Some(date(2024, 3, 3)), login),
+            Some((date(2023, 12, 4), date(2024, 3, 3)))
+        );
+        assert_eq!(next_history_window(None, None), None);
+    }
+
+    #[tokio::test]
+    async fn extend_keeps_the_oldest_window() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let chat_pubkey = Keys::generate().public_key();
+
+        assert_eq!(
+            DmHistory::fetched_since(pool, &chat_pubkey).await.unwrap(),
+            None
+        );
+        DmHistory::extend(pool, &chat_pubkey, date(2024, 3, 3))
+            .await
+            .unwrap();
+        DmHistory::extend(pool, &chat_pubkey, date(2024, 5, 1))
+            .await
+            .unwrap();
+        assert_eq!(
+            DmHistory::fetched_since(pool, &chat_pubkey).await.unwrap(),
+            Some(date(2024, 3, 3))
+        );
+    }
+}
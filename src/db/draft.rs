@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, Utc};
+use nostr::{secp256k1::XOnlyPublicKey, EventId};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::millis_to_naive_or_err;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Conversation a draft belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DraftTarget {
+    Contact(XOnlyPublicKey),
+    Channel(EventId),
+}
+impl DraftTarget {
+    /// Same key used by the conversation visits and labels
+    pub fn conversation(&self) -> String {
+        match self {
+            DraftTarget::Contact(pubkey) => pubkey.to_string(),
+            DraftTarget::Channel(channel_id) => channel_id.to_hex(),
+        }
+    }
+    fn is_channel(&self) -> bool {
+        matches!(self, DraftTarget::Channel(_))
+    }
+}
+
+/// Unsent composer text
+#[derive(Debug, Clone)]
+pub struct DbDraft {
+    pub target: DraftTarget,
+    pub content: String,
+    pub updated_at: NaiveDateTime,
+}
+
+impl DbDraft {
+    /// Newest first
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbDraft>, Error> {
+        let sql = "SELECT * FROM draft ORDER BY updated_at DESC";
+        let drafts = sqlx::query_as::<_, DbDraft>(sql).fetch_all(pool).await?;
+        Ok(drafts)
+    }
+
+    /// Saves the composer text, an empty text removes the draft
+    pub async fn save(pool: &SqlitePool, target: &DraftTarget, content: &str) -> Result<(), Error> {
+        if content.trim().is_empty() {
+            return Self::delete(pool, target).await;
+        }
+        let sql = r#"
+            INSERT INTO draft (conversation, is_channel, content, updated_at) VALUES (?, ?, ?, ?)
+            ON CONFLICT(conversation) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at
+        "#;
+        sqlx::query(sql)
+            .bind(target.conversation())
+            .bind(target.is_channel())
+            .bind(content)
+            .bind(Utc::now().timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, target: &DraftTarget) -> Result<(), Error> {
+        sqlx::query("DELETE FROM draft WHERE conversation = ?")
+            .bind(target.conversation())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbDraft {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let conversation = row.try_get::<String, &str>("conversation")?;
+        let target = if row.try_get::<bool, &str>("is_channel")? {
+            EventId::from_hex(&conversation)
+                .map(DraftTarget::Channel)
+                .map_err(|e| sqlx::Error::ColumnDecode {
+                    index: "conversation".into(),
+                    source: Box::new(e),
+                })?
+        } else {
+            XOnlyPublicKey::from_str(&conversation)
+                .map(DraftTarget::Contact)
+                .map_err(|e| sqlx::Error::ColumnDecode {
+                    index: "conversation".into(),
+                    source: Box::new(e),
+                })?
+        };
+        Ok(Self {
+            target,
+            content: row.try_get::<String, &str>("content")?,
+            updated_at: millis_to_naive_or_err(
+                row.try_get::<i64, &str>("updated_at")?,
+                "updated_at",
+            )?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use nostr::Keys;
+
+    #[tokio::test]
+    async fn empty_draft_is_removed() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let target = DraftTarget::Contact(Keys::generate().public_key());
+
+        DbDraft::save(pool, &target, "hello").await.unwrap();
+        DbDraft::save(pool, &target, "hello there").await.unwrap();
+        let drafts = DbDraft::fetch(pool).await.unwrap();
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].target, target);
+        assert_eq!(drafts[0].content, "hello there");
+
+        DbDraft::save(pool, &target, "  ").await.unwrap();
+        assert!(DbDraft::fetch(pool).await.unwrap().is_empty());
+    }
+}
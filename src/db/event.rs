@@ -5,6 +5,7 @@ use thiserror::Error;
 use url::Url;
 
 use crate::{
+    db::event_filter::{EventFilter, SqlArg},
     db::DbRelayResponse,
     utils::{handle_decode_error, millis_to_naive_or_err, ns_event_to_millis, url_or_err},
 };
@@ -85,6 +86,49 @@ impl DbEvent {
         Ok(output)
     }
 
+    /// Events matching the export filter, oldest first
+    pub async fn fetch_filtered(
+        pool: &SqlitePool,
+        filter: &EventFilter,
+    ) -> Result<Vec<DbEvent>, Error> {
+        let (clause, args) = filter.where_clause();
+        let sql = format!("{}{} ORDER BY created_at", Self::FETCH_QUERY, clause);
+        let mut query = sqlx::query_as::<_, DbEvent>(&sql);
+        for arg in args {
+            query = match arg {
+                SqlArg::Int(value) => query.bind(value),
+                SqlArg::Text(value) => query.bind(value),
+            };
+        }
+        Ok(query.fetch_all(pool).await?)
+    }
+
+    pub async fn count_filtered(pool: &SqlitePool, filter: &EventFilter) -> Result<i64, Error> {
+        let (clause, args) = filter.where_clause();
+        let sql = format!("SELECT COUNT(*) FROM event{}", clause);
+        let mut query = sqlx::query_scalar::<_, i64>(&sql);
+        for arg in args {
+            query = match arg {
+                SqlArg::Int(value) => query.bind(value),
+                SqlArg::Text(value) => query.bind(value),
+            };
+        }
+        Ok(query.fetch_one(pool).await?)
+    }
+
+    /// Direct messages stored without their message row
+    pub async fn fetch_dms_without_message(pool: &SqlitePool) -> Result<Vec<DbEvent>, Error> {
+        let sql = format!(
+            "{} WHERE kind = ? AND event_id NOT IN (SELECT event_id FROM message)",
+            Self::FETCH_QUERY
+        );
+        let output = sqlx::query_as::<_, DbEvent>(&sql)
+            .bind(nostr::Kind::EncryptedDirectMessage.as_u32())
+            .fetch_all(pool)
+            .await?;
+        Ok(output)
+    }
+
     pub async fn fetch_id(pool: &SqlitePool, event_id: i64) -> Result<Option<DbEvent>, Error> {
         let sql = format!("{} WHERE event_id = ?", Self::FETCH_QUERY);
         Ok(sqlx::query_as::<_, DbEvent>(&sql)
@@ -108,6 +152,16 @@ impl DbEvent {
         Ok(Self::fetch_hash(pool, event_hash).await?.is_some())
     }
 
+    /// Purged from the trash, see `db::trash`
+    pub async fn was_purged(pool: &SqlitePool, event_hash: &EventId) -> Result<bool, Error> {
+        let sql = "SELECT 1 FROM purged_event WHERE event_hash = ?";
+        let found = sqlx::query(sql)
+            .bind(event_hash.to_string())
+            .fetch_optional(pool)
+            .await?;
+        Ok(found.is_some())
+    }
+
     //fetch last event from db
     pub async fn fetch_last(pool: &SqlitePool) -> Result<Option<DbEvent>, Error> {
         let sql = format!("{} ORDER BY event_id DESC LIMIT 1", Self::FETCH_QUERY);
@@ -165,7 +219,9 @@ impl DbEvent {
         relay_url: &Url,
         ns_event: &nostr::Event,
     ) -> Result<Option<DbEvent>, Error> {
-        if Self::has_event(pool, &ns_event.id).await? {
+        if Self::has_event(pool, &ns_event.id).await?
+            || Self::was_purged(pool, &ns_event.id).await?
+        {
             return Ok(None);
         }
 
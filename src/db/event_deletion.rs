@@ -0,0 +1,53 @@
+use chrono::NaiveDateTime;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::EventId;
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// NIP-09 request kept for an event that may arrive after it.
+/// Messages inserted later look it up and are stored already retracted
+pub struct DbEventDeletion;
+
+impl DbEventDeletion {
+    /// Keeps the earliest request of `author` for the event
+    pub async fn insert(
+        pool: &SqlitePool,
+        target_hash: &EventId,
+        author: &XOnlyPublicKey,
+        deleted_at: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let sql = r#"
+            INSERT INTO event_deletion (target_hash, author, deleted_at) VALUES (?, ?, ?)
+            ON CONFLICT(target_hash, author)
+            DO UPDATE SET deleted_at = MIN(deleted_at, excluded.deleted_at)
+        "#;
+        sqlx::query(sql)
+            .bind(target_hash.to_string())
+            .bind(author.to_string())
+            .bind(deleted_at.timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether `author` asked to delete the event
+    pub async fn exists(
+        pool: &SqlitePool,
+        target_hash: &EventId,
+        author: &XOnlyPublicKey,
+    ) -> Result<bool, Error> {
+        let sql = "SELECT 1 FROM event_deletion WHERE target_hash = ? AND author = ?";
+        let found = sqlx::query(sql)
+            .bind(target_hash.to_string())
+            .bind(author.to_string())
+            .fetch_optional(pool)
+            .await?;
+        Ok(found.is_some())
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::NaiveDateTime;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Kind};
+
+/// Stored events to export, every part left empty matches everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    pub kinds: Vec<Kind>,
+    pub authors: Vec<XOnlyPublicKey>,
+    /// Inclusive, UTC
+    pub since: Option<NaiveDateTime>,
+    /// Exclusive, UTC
+    pub until: Option<NaiveDateTime>,
+    /// The channels' creation events and messages
+    pub channels: Vec<EventId>,
+}
+
+/// Value bound to a placeholder of the filter's clause
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SqlArg {
+    Int(i64),
+    Text(String),
+}
+
+impl EventFilter {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// ` WHERE ...` with `?` placeholders and the values to bind in order,
+    /// empty when the filter is
+    pub fn where_clause(&self) -> (String, Vec<SqlArg>) {
+        let mut conditions = vec![];
+        let mut args = vec![];
+        if !self.kinds.is_empty() {
+            conditions.push(format!("kind IN ({})", placeholders(self.kinds.len())));
+            args.extend(
+                self.kinds
+                    .iter()
+                    .map(|kind| SqlArg::Int(i64::from(kind.as_u32()))),
+            );
+        }
+        if !self.authors.is_empty() {
+            conditions.push(format!("pubkey IN ({})", placeholders(self.authors.len())));
+            args.extend(
+                self.authors
+                    .iter()
+                    .map(|author| SqlArg::Text(author.to_string())),
+            );
+        }
+        if let Some(since) = self.since {
+            conditions.push("created_at >= ?".to_owned());
+            args.push(SqlArg::Int(since.timestamp_millis()));
+        }
+        if let Some(until) = self.until {
+            conditions.push("created_at < ?".to_owned());
+            args.push(SqlArg::Int(until.timestamp_millis()));
+        }
+        if !self.channels.is_empty() {
+            let channel_placeholders = placeholders(self.channels.len());
+            conditions.push(format!(
+                "(event_hash IN ({0}) OR event_id IN \
+                 (SELECT event_id FROM channel_message WHERE channel_id IN ({0})))",
+                channel_placeholders
+            ));
+            for _ in 0..2 {
+                args.extend(
+                    self.channels
+                        .iter()
+                        .map(|channel_id| SqlArg::Text(channel_id.to_hex())),
+                );
+            }
+        }
+
+        if conditions.is_empty() {
+            (String::new(), args)
+        } else {
+            (format!(" WHERE {}", conditions.join(" AND ")), args)
+        }
+    }
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use nostr::Keys;
+
+    #[test]
+    fn builds_clause_with_args_in_order() {
+        assert_eq!(
+            EventFilter::default().where_clause(),
+            (String::new(), vec![])
+        );
+
+        let author = Keys::generate().public_key();
+        let channel_id = EventId::from_slice(&[1; 32]).unwrap();
+        let since = NaiveDate::from_ymd_opt(2023, 5, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let filter = EventFilter {
+            kinds: vec![Kind::TextNote, Kind::ChannelMessage],
+            authors: vec![author],
+            since: Some(since),
+            until: None,
+            channels: vec![channel_id],
+        };
+        let (clause, args) = filter.where_clause();
+        assert_eq!(
+            clause,
+            " WHERE kind IN (?, ?) AND pubkey IN (?) AND created_at >= ? AND \
+             (event_hash IN (?) OR event_id IN \
+             (SELECT event_id FROM channel_message WHERE channel_id IN (?)))"
+        );
+        assert_eq!(
+            args,
+            vec![
+                SqlArg::Int(1),
+                SqlArg::Int(42),
+                SqlArg::Text(author.to_string()),
+                SqlArg::Int(since.timestamp_millis()),
+                SqlArg::Text(channel_id.to_hex()),
+                SqlArg::Text(channel_id.to_hex()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Hashtags kept in a subscription, stored without the `#` and in lowercase
+pub struct FollowedHashtags;
+
+impl FollowedHashtags {
+    /// Oldest first, the order of the sidebar entries
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<String>, Error> {
+        let sql = "SELECT hashtag FROM followed_hashtag ORDER BY followed_at, hashtag;";
+        let hashtags = sqlx::query_scalar(sql).fetch_all(pool).await?;
+        Ok(hashtags)
+    }
+
+    pub async fn follow(pool: &SqlitePool, hashtag: &str) -> Result<(), Error> {
+        let sql = "INSERT OR IGNORE INTO followed_hashtag (hashtag, followed_at) VALUES (?, ?);";
+        sqlx::query(sql)
+            .bind(hashtag)
+            .bind(Utc::now().timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn unfollow(pool: &SqlitePool, hashtag: &str) -> Result<(), Error> {
+        let sql = "DELETE FROM followed_hashtag WHERE hashtag = ?;";
+        sqlx::query(sql).bind(hashtag).execute(pool).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::NaiveDateTime;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Tag};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::{
+    event_hash_or_err, millis_to_naive_or_err, ns_event_to_naive, public_key_or_err,
+};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("{0}")]
+    FromUtils(#[from] crate::utils::Error),
+}
+
+/// Someone who added the user to their contact list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbFollower {
+    pub pubkey: XOnlyPublicKey,
+    /// First contact list seen with the user in it
+    pub event_hash: EventId,
+    pub followed_at: NaiveDateTime,
+    /// Already shown in the activity panel
+    pub seen: bool,
+}
+
+impl DbFollower {
+    pub async fn create_tables(pool: &SqlitePool) -> Result<(), Error> {
+        sqlx::query(include_str!("../../migrations/27_follower.sql"))
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The author of a contact list listing `user`, `None` otherwise
+    pub fn from_contact_list(
+        ns_event: &nostr::Event,
+        user: &XOnlyPublicKey,
+    ) -> Result<Option<Self>, Error> {
+        let lists_user = ns_event.tags.iter().any(|tag| match tag {
+            Tag::PubKey(pubkey, _) => pubkey == user,
+            Tag::ContactList { pk, .. } => pk == user,
+            _ => false,
+        });
+        if !lists_user || &ns_event.pubkey == user {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            pubkey: ns_event.pubkey,
+            event_hash: ns_event.id,
+            followed_at: ns_event_to_naive(ns_event.created_at)?,
+            seen: false,
+        }))
+    }
+
+    /// Returns false when the follower was already known
+    pub async fn insert(pool: &SqlitePool, follower: &DbFollower) -> Result<bool, Error> {
+        let sql = r#"
+            INSERT INTO follower (pubkey, event_hash, followed_at, seen) VALUES (?, ?, ?, ?)
+            ON CONFLICT(pubkey) DO NOTHING
+        "#;
+        let result = sqlx::query(sql)
+            .bind(follower.pubkey.to_string())
+            .bind(follower.event_hash.to_string())
+            .bind(follower.followed_at.timestamp_millis())
+            .bind(follower.seen)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Newest first
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbFollower>, Error> {
+        let sql = "SELECT * FROM follower ORDER BY followed_at DESC";
+        let followers = sqlx::query_as::<_, DbFollower>(sql).fetch_all(pool).await?;
+        Ok(followers)
+    }
+
+    pub async fn mark_all_seen(pool: &SqlitePool) -> Result<(), Error> {
+        sqlx::query("UPDATE follower SET seen = 1 WHERE seen = 0")
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbFollower {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let pubkey = row.try_get::<String, &str>("pubkey")?;
+        let event_hash = row.try_get::<String, &str>("event_hash")?;
+        Ok(Self {
+            pubkey: public_key_or_err(&pubkey, "pubkey")?,
+            event_hash: event_hash_or_err(&event_hash, "event_hash")?,
+            followed_at: millis_to_naive_or_err(
+                row.try_get::<i64, &str>("followed_at")?,
+                "followed_at",
+            )?,
+            seen: row.try_get::<bool, &str>("seen")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use nostr::{EventBuilder, Keys};
+
+    fn contact_list(keys: &Keys, contacts: &[XOnlyPublicKey]) -> nostr::Event {
+        let tags: Vec<Tag> = contacts.iter().map(|pk| Tag::PubKey(*pk, None)).collect();
+        EventBuilder::new(nostr::Kind::ContactList, "", &tags)
+            .to_event(keys)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn indexes_lists_with_the_user() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let user = Keys::generate().public_key();
+        let other = Keys::generate();
+
+        let without_user = contact_list(&other, &[Keys::generate().public_key()]);
+        assert!(DbFollower::from_contact_list(&without_user, &user)
+            .unwrap()
+            .is_none());
+
+        let with_user = contact_list(&other, &[user]);
+        let follower = DbFollower::from_contact_list(&with_user, &user)
+            .unwrap()
+            .unwrap();
+        assert_eq!(follower.pubkey, other.public_key());
+        assert!(DbFollower::insert(pool, &follower).await.unwrap());
+        // a newer list from the same follower keeps the first one
+        assert!(!DbFollower::insert(pool, &follower).await.unwrap());
+
+        DbFollower::mark_all_seen(pool).await.unwrap();
+        let followers = DbFollower::fetch(pool).await.unwrap();
+        assert_eq!(followers.len(), 1);
+        assert!(followers[0].seen);
+    }
+}
@@ -35,26 +35,93 @@ pub struct ImageDownloaded {
     pub path: PathBuf,
     pub kind: ImageKind,
     pub event_hash: EventId,
+    /// Most common saturated color, only for profile pictures
+    pub dominant_color: Option<[u8; 3]>,
 }
 impl ImageDownloaded {
+    /// Accent color for the owner's messages
+    pub fn accent(&self) -> Option<iced::Color> {
+        self.dominant_color
+            .map(|[r, g, b]| iced::Color::from_rgb8(r, g, b))
+    }
+    /// Resized copy next to the original. Caches made before the copies existed
+    /// fall back to the original.
     pub fn sized_image(&self, size: ImageSize) -> PathBuf {
+        if let ImageSize::Original = size {
+            return self.path.to_owned();
+        }
         let sized_file_name = image_filename(self.kind, size, "png");
         // replace filename with new
-        self.path.with_file_name(sized_file_name)
+        let sized = self.path.with_file_name(sized_file_name);
+        if sized.exists() {
+            sized
+        } else {
+            self.path.to_owned()
+        }
     }
     pub async fn fetch(
         cache_pool: &SqlitePool,
         event_hash: &EventId,
         kind: ImageKind,
     ) -> Result<Option<ImageDownloaded>, Error> {
-        Ok(sqlx::query_as::<_, ImageDownloaded>(
-            "SELECT * FROM image_cache WHERE event_hash = ? AND kind = ?",
-        )
-        .bind(event_hash.to_string())
-        .bind(kind.as_i32())
-        .fetch_optional(cache_pool)
-        .await?)
+        let query = r#"
+            SELECT image_cache.*, image_color.color AS dominant_color
+            FROM image_cache
+            LEFT JOIN image_color
+                ON image_color.event_hash = image_cache.event_hash
+                AND image_color.kind = image_cache.kind
+            WHERE image_cache.event_hash = ? AND image_cache.kind = ?
+        "#;
+        Ok(sqlx::query_as::<_, ImageDownloaded>(query)
+            .bind(event_hash.to_string())
+            .bind(kind.as_i32())
+            .fetch_optional(cache_pool)
+            .await?)
+    }
+    /// Columns and joins bringing the image of `event_hash` into a query on the
+    /// main pool, read back with `from_joined_row` and the same `prefix`
+    pub(crate) fn joined_sql(prefix: &str, event_hash: &str, kind: ImageKind) -> (String, String) {
+        let columns = format!(
+            "{p}.path AS {p}_path, {p}.event_hash AS {p}_event_hash, {p}_color.color AS {p}_color",
+            p = prefix
+        );
+        let joins = format!(
+            r#"
+            LEFT JOIN cache.image_cache {p}
+                ON {p}.event_hash = {hash} AND {p}.kind = {kind}
+            LEFT JOIN cache.image_color {p}_color
+                ON {p}_color.event_hash = {p}.event_hash AND {p}_color.kind = {kind}
+            "#,
+            p = prefix,
+            hash = event_hash,
+            kind = kind.as_i32()
+        );
+        (columns, joins)
+    }
+
+    /// `None` when the joined row has no cached image
+    pub(crate) fn from_joined_row(
+        row: &SqliteRow,
+        prefix: &str,
+        kind: ImageKind,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let path_col = format!("{}_path", prefix);
+        let Some(path) = row.try_get::<Option<String>, _>(path_col.as_str())? else {
+            return Ok(None);
+        };
+        let hash_col = format!("{}_event_hash", prefix);
+        let event_hash: String = row.try_get(hash_col.as_str())?;
+        let event_hash = event_hash_or_err(&event_hash, &hash_col)?;
+        let dominant_color: Option<i64> = row.try_get(format!("{}_color", prefix).as_str())?;
+
+        Ok(Some(Self {
+            path: PathBuf::from(path),
+            kind,
+            event_hash,
+            dominant_color: dominant_color.map(unpack_color),
+        }))
     }
+
     pub async fn insert(
         cache_pool: &SqlitePool,
         image: &ImageDownloaded,
@@ -75,6 +142,17 @@ impl ImageDownloaded {
             .execute(cache_pool)
             .await?;
 
+        if let Some(color) = image.dominant_color {
+            sqlx::query(
+                "INSERT OR REPLACE INTO image_color (event_hash, kind, color) VALUES (?, ?, ?)",
+            )
+            .bind(&image.event_hash.to_string())
+            .bind(image.kind.as_i32())
+            .bind(pack_color(color))
+            .execute(cache_pool)
+            .await?;
+        }
+
         let cache = Self::fetch(cache_pool, &image.event_hash, image.kind)
             .await?
             .ok_or(Error::ImageCacheNotFound(
@@ -102,6 +180,11 @@ impl ImageDownloaded {
                     .bind(kind.as_i32())
                     .execute(cache_pool)
                     .await?;
+                sqlx::query("DELETE FROM image_color WHERE event_hash = ? AND kind = ?")
+                    .bind(&event_hash.to_string())
+                    .bind(kind.as_i32())
+                    .execute(cache_pool)
+                    .await?;
 
                 Ok(())
             }
@@ -120,28 +203,69 @@ impl sqlx::FromRow<'_, SqliteRow> for ImageDownloaded {
         let event_hash: String = row.get("event_hash");
         let event_hash = event_hash_or_err(&event_hash, "event_hash")?;
 
+        let dominant_color: Option<i64> = row.try_get("dominant_color").unwrap_or(None);
+
         Ok(Self {
             path,
             kind,
             event_hash,
+            dominant_color: dominant_color.map(unpack_color),
         })
     }
 }
 
-async fn delete_images(cache: ImageDownloaded) -> Result<(), Error> {
-    tokio::fs::remove_file(cache.path).await?;
+/// Stored as 0xRRGGBB
+fn pack_color([r, g, b]: [u8; 3]) -> i64 {
+    ((r as i64) << 16) | ((g as i64) << 8) | b as i64
+}
 
-    match cache.kind {
-        ImageKind::Profile => {
-            let med_path = image_filename(cache.kind, ImageSize::Medium, "png");
-            tokio::fs::remove_file(med_path).await?;
+fn unpack_color(color: i64) -> [u8; 3] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
 
-            let sm_path = image_filename(cache.kind, ImageSize::Small, "png");
-            tokio::fs::remove_file(sm_path).await?;
+async fn delete_images(cache: ImageDownloaded) -> Result<(), Error> {
+    // every kind gets resized copies next to the original
+    for size in [ImageSize::Medium, ImageSize::Small] {
+        let sized = cache.sized_image(size);
+        if sized != cache.path {
+            tokio::fs::remove_file(sized).await?;
         }
-        ImageKind::Banner => {}
-        ImageKind::Channel => {}
     }
+    tokio::fs::remove_file(cache.path).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sized_image_falls_back_to_original() {
+        let dir = std::env::temp_dir().join("nostrtalk_sized_image_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let kind = ImageKind::Profile;
+        let image = ImageDownloaded {
+            path: dir.join(image_filename(kind, ImageSize::Original, "jpeg")),
+            kind,
+            event_hash: EventId::from_slice(&[1; 32]).unwrap(),
+            dominant_color: None,
+        };
+        let small = dir.join(image_filename(kind, ImageSize::Small, "png"));
+        _ = std::fs::remove_file(&small);
+
+        assert_eq!(image.sized_image(ImageSize::Original), image.path);
+        assert_eq!(image.sized_image(ImageSize::Small), image.path);
+
+        std::fs::write(&small, b"").unwrap();
+        assert_eq!(image.sized_image(ImageSize::Small), small);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dominant_color_round_trips() {
+        for color in [[0, 0, 0], [255, 255, 255], [18, 200, 99]] {
+            assert_eq!(unpack_color(pack_color(color)), color);
+        }
+    }
+}
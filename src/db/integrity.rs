@@ -0,0 +1,109 @@
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// What the startup check found and fixed
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    /// Schema versions from and to
+    pub migrated: Option<(usize, usize)>,
+    /// Problems reported by sqlite, these need manual action
+    pub corruption: Vec<String>,
+    /// Messages whose event was gone
+    pub orphan_messages_removed: u64,
+    /// Cached images whose file was gone, they are downloaded again
+    pub missing_images_removed: u64,
+    /// Direct messages whose message row was recreated from the stored event
+    pub messages_restored: u64,
+}
+impl IntegrityReport {
+    pub fn repairs(&self) -> u64 {
+        self.orphan_messages_removed + self.missing_images_removed + self.messages_restored
+    }
+
+    pub fn needs_action(&self) -> bool {
+        !self.corruption.is_empty()
+    }
+
+    /// Only worth showing when something changed or needs the user
+    pub fn should_report(&self) -> bool {
+        self.migrated.is_some() || self.repairs() > 0 || self.needs_action()
+    }
+}
+
+pub async fn check_integrity(
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+    migrated: Option<(usize, usize)>,
+) -> Result<IntegrityReport, Error> {
+    tracing::info!("Database integrity check");
+    let mut corruption = integrity_problems(pool).await?;
+    corruption.extend(
+        integrity_problems(cache_pool)
+            .await?
+            .into_iter()
+            .map(|problem| format!("cache: {}", problem)),
+    );
+
+    let sql = "DELETE FROM message WHERE event_id NOT IN (SELECT event_id FROM event)";
+    let orphan_messages_removed = sqlx::query(sql).execute(pool).await?.rows_affected();
+
+    let missing_images_removed = remove_missing_images(cache_pool).await?;
+
+    Ok(IntegrityReport {
+        migrated,
+        corruption,
+        orphan_messages_removed,
+        missing_images_removed,
+        messages_restored: 0,
+    })
+}
+
+async fn integrity_problems(pool: &SqlitePool) -> Result<Vec<String>, Error> {
+    let rows: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check;")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().filter(|row| row != INTEGRITY_OK).collect())
+}
+
+async fn remove_missing_images(cache_pool: &SqlitePool) -> Result<u64, Error> {
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT id, path FROM image_cache")
+        .fetch_all(cache_pool)
+        .await?;
+    let mut removed = 0;
+    for (id, path) in rows {
+        if tokio::fs::metadata(&path).await.is_err() {
+            sqlx::query("DELETE FROM image_cache WHERE id = ?")
+                .bind(id)
+                .execute(cache_pool)
+                .await?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+const INTEGRITY_OK: &str = "ok";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_only_changes() {
+        let mut report = IntegrityReport::default();
+        assert!(!report.should_report());
+
+        report.missing_images_removed = 2;
+        assert!(report.should_report());
+        assert!(!report.needs_action());
+
+        report.corruption.push("row 3 missing from index".into());
+        assert!(report.needs_action());
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("Label not found: {0}")]
+    LabelNotFound(String),
+
+    #[error("Label name can't be empty")]
+    EmptyName,
+
+    #[error("Invalid label color: {0}")]
+    InvalidColor(String),
+}
+
+/// Overrides the conversation's notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelNotify {
+    Default = 0,
+    Always = 1,
+    Muted = 2,
+}
+impl LabelNotify {
+    pub const ALL: [LabelNotify; 3] = [Self::Default, Self::Always, Self::Muted];
+
+    pub fn from_i32(value: i32) -> Self {
+        match value {
+            1 => Self::Always,
+            2 => Self::Muted,
+            _ => Self::Default,
+        }
+    }
+    pub fn to_i32(self) -> i32 {
+        self as i32
+    }
+}
+impl std::fmt::Display for LabelNotify {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LabelNotify::Default => write!(f, "Default"),
+            LabelNotify::Always => write!(f, "Always notify"),
+            LabelNotify::Muted => write!(f, "Muted"),
+        }
+    }
+}
+
+/// Colored label, like the ones in email clients.
+/// `color` is stored as `#rrggbb`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbLabel {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+    pub notify: LabelNotify,
+}
+
+impl DbLabel {
+    const FETCH_QUERY: &'static str = "SELECT * FROM label";
+
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbLabel>, Error> {
+        let sql = format!("{} ORDER BY name", Self::FETCH_QUERY);
+        let output = sqlx::query_as::<_, DbLabel>(&sql).fetch_all(pool).await?;
+        Ok(output)
+    }
+
+    pub async fn insert(
+        pool: &SqlitePool,
+        name: &str,
+        color: &str,
+        notify: LabelNotify,
+    ) -> Result<DbLabel, Error> {
+        let name = normalize_name(name)?;
+        parse_color(color)?;
+        let sql = "INSERT INTO label (name, color, notify) VALUES (?, ?, ?)";
+        let output = sqlx::query(sql)
+            .bind(&name)
+            .bind(color)
+            .bind(notify.to_i32())
+            .execute(pool)
+            .await?;
+        let sql = format!("{} WHERE id = ?", Self::FETCH_QUERY);
+        let label = sqlx::query_as::<_, DbLabel>(&sql)
+            .bind(output.last_insert_rowid())
+            .fetch_optional(pool)
+            .await?
+            .ok_or(Error::LabelNotFound(name))?;
+        Ok(label)
+    }
+
+    pub async fn update(pool: &SqlitePool, label: &DbLabel) -> Result<(), Error> {
+        parse_color(&label.color)?;
+        let sql = "UPDATE label SET name=?, color=?, notify=? WHERE id=?";
+        sqlx::query(sql)
+            .bind(normalize_name(&label.name)?)
+            .bind(&label.color)
+            .bind(label.notify.to_i32())
+            .bind(label.id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, label: &DbLabel) -> Result<(), Error> {
+        let sql = "DELETE FROM conversation_label WHERE label_id=?";
+        sqlx::query(sql).bind(label.id).execute(pool).await?;
+        let sql = "DELETE FROM label WHERE id=?";
+        sqlx::query(sql).bind(label.id).execute(pool).await?;
+        Ok(())
+    }
+
+    /// Conversation is a contact public key or a channel id
+    pub async fn toggle(pool: &SqlitePool, label_id: i64, conversation: &str) -> Result<(), Error> {
+        let sql = "DELETE FROM conversation_label WHERE label_id=? AND conversation=?";
+        let output = sqlx::query(sql)
+            .bind(label_id)
+            .bind(conversation)
+            .execute(pool)
+            .await?;
+        if output.rows_affected() == 0 {
+            let sql = "INSERT INTO conversation_label (label_id, conversation) VALUES (?, ?)";
+            sqlx::query(sql)
+                .bind(label_id)
+                .bind(conversation)
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn assign(pool: &SqlitePool, label_id: i64, conversation: &str) -> Result<(), Error> {
+        let sql = "INSERT OR IGNORE INTO conversation_label (label_id, conversation) VALUES (?, ?)";
+        sqlx::query(sql)
+            .bind(label_id)
+            .bind(conversation)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fetch_labels(pool: &SqlitePool) -> Result<Labels, Error> {
+        let labels = Self::fetch(pool).await?;
+        let sql = "SELECT label_id, conversation FROM conversation_label";
+        let rows = sqlx::query_as::<_, (i64, String)>(sql)
+            .fetch_all(pool)
+            .await?;
+        Ok(Labels::new(labels, rows))
+    }
+
+    /// Red, green and blue components
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        parse_color(&self.color).unwrap_or(FALLBACK_RGB)
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbLabel {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(DbLabel {
+            id: row.try_get::<i64, &str>("id")?,
+            name: row.try_get::<String, &str>("name")?,
+            color: row.try_get::<String, &str>("color")?,
+            notify: LabelNotify::from_i32(row.try_get::<i32, &str>("notify")?),
+        })
+    }
+}
+
+/// Every label and the conversations they are assigned to
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    pub list: Vec<DbLabel>,
+    assigned: HashMap<String, Vec<i64>>,
+}
+impl Labels {
+    fn new(list: Vec<DbLabel>, rows: Vec<(i64, String)>) -> Self {
+        let mut assigned: HashMap<String, Vec<i64>> = HashMap::new();
+        for (label_id, conversation) in rows {
+            assigned.entry(conversation).or_default().push(label_id);
+        }
+        Self { list, assigned }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn has(&self, conversation: &str, label_id: i64) -> bool {
+        self.assigned
+            .get(conversation)
+            .map_or(false, |ids| ids.contains(&label_id))
+    }
+
+    pub fn of<'a>(&'a self, conversation: &str) -> impl Iterator<Item = &'a DbLabel> + 'a {
+        let ids = self.assigned.get(conversation).cloned().unwrap_or_default();
+        self.list.iter().filter(move |l| ids.contains(&l.id))
+    }
+
+    /// `Always` wins over `Muted` when labels disagree
+    pub fn notify(&self, conversation: &str) -> LabelNotify {
+        self.of(conversation)
+            .map(|l| l.notify)
+            .fold(LabelNotify::Default, |acc, notify| match (acc, notify) {
+                (LabelNotify::Always, _) | (_, LabelNotify::Always) => LabelNotify::Always,
+                (LabelNotify::Muted, _) | (_, LabelNotify::Muted) => LabelNotify::Muted,
+                _ => LabelNotify::Default,
+            })
+    }
+}
+
+fn normalize_name(name: &str) -> Result<String, Error> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::EmptyName);
+    }
+    Ok(name.to_owned())
+}
+
+fn parse_color(color: &str) -> Result<(u8, u8, u8), Error> {
+    let invalid = || Error::InvalidColor(color.to_owned());
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+const FALLBACK_RGB: (u8, u8, u8) = (128, 128, 128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(id: i64, notify: LabelNotify) -> DbLabel {
+        DbLabel {
+            id,
+            name: format!("label {}", id),
+            color: "#ff8800".into(),
+            notify,
+        }
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse_color("#ff8800").unwrap(), (255, 136, 0));
+        assert!(parse_color("ff8800").is_err());
+        assert!(parse_color("#ff88").is_err());
+        assert!(parse_color("#gg8800").is_err());
+    }
+
+    #[test]
+    fn finds_assigned_labels() {
+        let labels = Labels::new(
+            vec![
+                label(1, LabelNotify::Default),
+                label(2, LabelNotify::Default),
+            ],
+            vec![(2, "chat".into())],
+        );
+        assert!(labels.has("chat", 2));
+        assert!(!labels.has("chat", 1));
+        assert!(!labels.has("other", 2));
+        let ids: Vec<_> = labels.of("chat").map(|l| l.id).collect();
+        assert_eq!(ids, vec![2]);
+    }
+
+    #[test]
+    fn always_notify_wins() {
+        let labels = Labels::new(
+            vec![label(1, LabelNotify::Muted), label(2, LabelNotify::Always)],
+            vec![(1, "muted".into()), (1, "both".into()), (2, "both".into())],
+        );
+        assert_eq!(labels.notify("muted"), LabelNotify::Muted);
+        assert_eq!(labels.notify("both"), LabelNotify::Always);
+        assert_eq!(labels.notify("none"), LabelNotify::Default);
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::{NaiveDateTime, Utc};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::millis_to_naive_or_err;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+}
+
+/// Last reachability check of a lightning address, stored in the cache database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightningCheck {
+    pub address: String,
+    pub valid: bool,
+    pub checked_at: NaiveDateTime,
+}
+
+impl LightningCheck {
+    pub fn new(address: &str, valid: bool) -> Self {
+        Self {
+            address: address.to_owned(),
+            valid,
+            checked_at: Utc::now().naive_utc(),
+        }
+    }
+
+    /// Checks are redone once a day
+    pub fn is_fresh(&self, now: NaiveDateTime) -> bool {
+        now - self.checked_at < chrono::Duration::hours(CHECK_TTL_HOURS)
+    }
+
+    pub async fn fetch(cache_pool: &SqlitePool, address: &str) -> Result<Option<Self>, Error> {
+        let sql = "SELECT * FROM lightning_check WHERE address = ?;";
+        let check = sqlx::query_as::<_, Self>(sql)
+            .bind(address)
+            .fetch_optional(cache_pool)
+            .await?;
+        Ok(check)
+    }
+
+    pub async fn insert(cache_pool: &SqlitePool, check: &LightningCheck) -> Result<(), Error> {
+        let sql = r#"
+            INSERT OR REPLACE INTO lightning_check (address, valid, checked_at)
+            VALUES (?, ?, ?)
+        "#;
+        sqlx::query(sql)
+            .bind(&check.address)
+            .bind(check.valid)
+            .bind(check.checked_at.timestamp_millis())
+            .execute(cache_pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for LightningCheck {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            address: row.try_get::<String, &str>("address")?,
+            valid: row.try_get::<bool, &str>("valid")?,
+            checked_at: millis_to_naive_or_err(
+                row.try_get::<i64, &str>("checked_at")?,
+                "checked_at",
+            )?,
+        })
+    }
+}
+
+const CHECK_TTL_HOURS: i64 = 24;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+
+    #[tokio::test]
+    async fn stores_the_last_check() {
+        let db = TestDatabase::new().await.unwrap();
+        let cache_pool = db.cache_pool();
+
+        let address = "satoshi@example.com";
+        assert_eq!(
+            LightningCheck::fetch(cache_pool, address).await.unwrap(),
+            None
+        );
+
+        LightningCheck::insert(cache_pool, &LightningCheck::new(address, false))
+            .await
+            .unwrap();
+        let check = LightningCheck::new(address, true);
+        LightningCheck::insert(cache_pool, &check).await.unwrap();
+
+        let stored = LightningCheck::fetch(cache_pool, address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.valid);
+        assert!(stored.is_fresh(check.checked_at + chrono::Duration::hours(1)));
+        assert!(!stored.is_fresh(check.checked_at + chrono::Duration::hours(CHECK_TTL_HOURS)));
+    }
+}
@@ -0,0 +1,261 @@
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use nostr::prelude::*;
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::{
+    event_hash_or_err, handle_decode_error, millis_to_naive_or_err, ns_event_to_naive,
+    public_key_or_err,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+
+    #[error("{0}")]
+    FromUtils(#[from] crate::utils::Error),
+
+    #[error("Live activity without identifier: {0}")]
+    MissingIdentifier(EventId),
+
+    #[error("Unknown live activity status: {0}")]
+    UnknownStatus(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveStatus {
+    Planned,
+    Live,
+    Ended,
+}
+impl LiveStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiveStatus::Planned => "planned",
+            LiveStatus::Live => "live",
+            LiveStatus::Ended => "ended",
+        }
+    }
+}
+impl FromStr for LiveStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "planned" => Ok(LiveStatus::Planned),
+            "live" => Ok(LiveStatus::Live),
+            "ended" => Ok(LiveStatus::Ended),
+            other => Err(Error::UnknownStatus(other.to_owned())),
+        }
+    }
+}
+
+/// Latest version of a kind 30311 event
+#[derive(Debug, Clone)]
+pub struct DbLiveActivity {
+    pub author: XOnlyPublicKey,
+    pub identifier: String,
+    pub event_hash: EventId,
+    /// First `p` tag with the Host role, the author when missing
+    pub host: XOnlyPublicKey,
+    pub title: String,
+    pub summary: Option<String>,
+    pub image_url: Option<String>,
+    pub streaming_url: Option<String>,
+    pub status: LiveStatus,
+    pub created_at: NaiveDateTime,
+}
+
+impl DbLiveActivity {
+    pub async fn create_tables(pool: &SqlitePool) -> Result<(), Error> {
+        sqlx::query(include_str!("../../migrations/13_live_activity.sql"))
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub fn from_ns_event(ns_event: &nostr::Event) -> Result<Self, Error> {
+        let tags: Vec<Vec<String>> = ns_event.tags.iter().map(|t| t.as_vec()).collect();
+        let find = |name: &str| {
+            tags.iter()
+                .find(|t| t.len() > 1 && t[0] == name)
+                .map(|t| t[1].to_owned())
+        };
+
+        let identifier = find("d").ok_or(Error::MissingIdentifier(ns_event.id))?;
+        let host = tags
+            .iter()
+            .find_map(|t| match t.as_slice() {
+                [p, pubkey, _, role, ..] if p == "p" && role.eq_ignore_ascii_case(HOST_ROLE) => {
+                    XOnlyPublicKey::from_str(pubkey).ok()
+                }
+                _ => None,
+            })
+            .unwrap_or(ns_event.pubkey);
+        let status = match find("status") {
+            Some(status) => status.parse()?,
+            None => LiveStatus::Planned,
+        };
+
+        Ok(Self {
+            author: ns_event.pubkey,
+            identifier,
+            event_hash: ns_event.id,
+            host,
+            title: find("title").unwrap_or_default(),
+            summary: find("summary"),
+            image_url: find("image"),
+            streaming_url: find("streaming"),
+            status,
+            created_at: ns_event_to_naive(ns_event.created_at)?,
+        })
+    }
+
+    /// Live and updated recently, relays keep stale "live" events around
+    pub fn is_live(&self) -> bool {
+        self.status == LiveStatus::Live
+            && self.created_at > Utc::now().naive_utc() - Duration::seconds(LIVE_STALE_SECS)
+    }
+
+    /// Keeps only the newest version, returns false when ignored
+    pub async fn upsert(pool: &SqlitePool, activity: &DbLiveActivity) -> Result<bool, Error> {
+        let sql = r#"
+            INSERT INTO live_activity
+                (author, identifier, event_hash, host, title, summary,
+                image_url, streaming_url, status, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(author, identifier) DO UPDATE SET
+                event_hash = excluded.event_hash,
+                host = excluded.host,
+                title = excluded.title,
+                summary = excluded.summary,
+                image_url = excluded.image_url,
+                streaming_url = excluded.streaming_url,
+                status = excluded.status,
+                created_at = excluded.created_at
+            WHERE excluded.created_at > live_activity.created_at
+        "#;
+
+        let result = sqlx::query(sql)
+            .bind(activity.author.to_string())
+            .bind(&activity.identifier)
+            .bind(activity.event_hash.to_string())
+            .bind(activity.host.to_string())
+            .bind(&activity.title)
+            .bind(&activity.summary)
+            .bind(&activity.image_url)
+            .bind(&activity.streaming_url)
+            .bind(activity.status.as_str())
+            .bind(activity.created_at.timestamp_millis())
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Activities currently live, newest first
+    pub async fn fetch_live(pool: &SqlitePool) -> Result<Vec<Self>, Error> {
+        let sql = r#"
+            SELECT * FROM live_activity
+            WHERE status = ? AND created_at > ?
+            ORDER BY created_at DESC;
+        "#;
+        let stale_before = Utc::now().naive_utc() - Duration::seconds(LIVE_STALE_SECS);
+        let activities = sqlx::query_as::<_, Self>(sql)
+            .bind(LiveStatus::Live.as_str())
+            .bind(stale_before.timestamp_millis())
+            .fetch_all(pool)
+            .await?;
+        Ok(activities)
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbLiveActivity {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let author = row.try_get::<String, &str>("author")?;
+        let event_hash = row.try_get::<String, &str>("event_hash")?;
+        let host = row.try_get::<String, &str>("host")?;
+        let status = row.try_get::<String, &str>("status")?;
+        let status = status
+            .parse()
+            .map_err(|e| handle_decode_error(e, "status"))?;
+        let created_at = row.try_get::<i64, &str>("created_at")?;
+
+        Ok(Self {
+            author: public_key_or_err(&author, "author")?,
+            identifier: row.try_get::<String, &str>("identifier")?,
+            event_hash: event_hash_or_err(&event_hash, "event_hash")?,
+            host: public_key_or_err(&host, "host")?,
+            title: row.try_get::<String, &str>("title")?,
+            summary: row.try_get::<Option<String>, &str>("summary")?,
+            image_url: row.try_get::<Option<String>, &str>("image_url")?,
+            streaming_url: row.try_get::<Option<String>, &str>("streaming_url")?,
+            status,
+            created_at: millis_to_naive_or_err(created_at, "created_at")?,
+        })
+    }
+}
+
+pub(crate) const LIVE_ACTIVITY_KIND: u64 = 30311;
+const HOST_ROLE: &str = "host";
+/// NIP-53: live events not updated for an hour may be considered ended
+const LIVE_STALE_SECS: i64 = 60 * 60;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live_event(keys: &Keys, tags: &[Vec<&str>]) -> nostr::Event {
+        let tags: Vec<Tag> = tags
+            .iter()
+            .map(|t| Tag::parse(t.iter().map(|s| s.to_string()).collect()).unwrap())
+            .collect();
+        EventBuilder::new(Kind::Custom(LIVE_ACTIVITY_KIND), "", &tags)
+            .to_event(keys)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_host_and_status() {
+        let keys = Keys::generate();
+        let host = Keys::generate().public_key();
+        let host_str = host.to_string();
+        let event = live_event(
+            &keys,
+            &[
+                vec!["d", "stream-1"],
+                vec!["title", "Building nostr apps"],
+                vec!["streaming", "https://example.com/live.m3u8"],
+                vec!["status", "live"],
+                vec!["p", &host_str, "wss://relay.example.com", "Host"],
+            ],
+        );
+
+        let activity = DbLiveActivity::from_ns_event(&event).unwrap();
+        assert_eq!(activity.identifier, "stream-1");
+        assert_eq!(activity.host, host);
+        assert_eq!(activity.status, LiveStatus::Live);
+        assert!(activity.is_live());
+    }
+
+    #[test]
+    fn author_hosts_when_no_host_tag() {
+        let keys = Keys::generate();
+        let event = live_event(&keys, &[vec!["d", "stream-1"], vec!["status", "ended"]]);
+
+        let activity = DbLiveActivity::from_ns_event(&event).unwrap();
+        assert_eq!(activity.host, keys.public_key());
+        assert!(!activity.is_live());
+    }
+
+    #[test]
+    fn identifier_is_required() {
+        let keys = Keys::generate();
+        let event = live_event(&keys, &[vec!["status", "live"]]);
+        assert!(DbLiveActivity::from_ns_event(&event).is_err());
+    }
+}
@@ -1,9 +1,11 @@
 use super::DbEvent;
+use crate::net::ingress::sanitize_text;
 use crate::utils::{message_status_or_err, millis_to_naive_or_err, public_key_or_err, url_or_err};
 use chrono::NaiveDateTime;
 use nostr::{nips::nip04, secp256k1::XOnlyPublicKey, EventId, Keys};
 use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use std::collections::HashMap;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -92,6 +94,9 @@ pub struct DbMessage {
     pub created_at: chrono::NaiveDateTime,
     pub status: MessageStatus,
     pub relay_url: nostr::Url,
+    /// Deleted by its author with a NIP-09 request
+    #[serde(default)]
+    pub retracted_at: Option<NaiveDateTime>,
 }
 
 impl DbMessage {
@@ -101,23 +106,25 @@ impl DbMessage {
         self.status.is_unseen()
     }
 
+    /// Decrypted content cleaned of control characters
     pub fn decrypt_message(&self, keys: &Keys, tag_info: &MessageTagInfo) -> Result<String, Error> {
         let users_secret_key = keys.secret_key()?;
-        if self.is_users {
+        let content = if self.is_users {
             nip04::decrypt(
                 &users_secret_key,
                 &tag_info.to_pubkey,
                 &self.encrypted_content,
             )
-            .map_err(|e| Error::Decryption(e.to_string()))
+            .map_err(|e| Error::Decryption(e.to_string()))?
         } else {
             nip04::decrypt(
                 &users_secret_key,
                 &tag_info.from_pubkey,
                 &self.encrypted_content,
             )
-            .map_err(|e| Error::Decryption(e.to_string()))
-        }
+            .map_err(|e| Error::Decryption(e.to_string()))?
+        };
+        Ok(sanitize_text(&content).into_owned())
     }
 
     pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbMessage>, Error> {
@@ -142,7 +149,7 @@ impl DbMessage {
         let sql = r#"
             SELECT COUNT(*)
             FROM message
-            WHERE chat_pubkey = ? AND status = ?
+            WHERE chat_pubkey = ? AND status = ? AND deleted_at IS NULL AND retracted_at IS NULL
         "#;
 
         let count: (i64,) = sqlx::query_as(sql)
@@ -154,6 +161,25 @@ impl DbMessage {
         Ok(count.0)
     }
 
+    /// Unseen messages of every chat, newest first
+    pub async fn fetch_unseen(pool: &SqlitePool, limit: i64) -> Result<Vec<DbMessage>, Error> {
+        let sql = r#"
+            SELECT *
+            FROM message
+            WHERE status = ? AND is_users = 0 AND deleted_at IS NULL AND retracted_at IS NULL
+            ORDER BY created_at DESC
+            LIMIT ?
+        "#;
+
+        let messages = sqlx::query_as::<_, DbMessage>(sql)
+            .bind(MessageStatus::Delivered.to_i32())
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(messages)
+    }
+
     pub async fn fetch_chat(
         pool: &SqlitePool,
         chat_pubkey: &XOnlyPublicKey,
@@ -161,7 +187,7 @@ impl DbMessage {
         let sql = r#"
             SELECT *
             FROM message
-            WHERE chat_pubkey = ?
+            WHERE chat_pubkey = ? AND deleted_at IS NULL
             ORDER BY created_at DESC
             LIMIT 100
         "#;
@@ -182,7 +208,8 @@ impl DbMessage {
         let sql = r#"
             SELECT *
             FROM message
-            WHERE chat_pubkey=? and created_at < ? ORDER BY created_at DESC
+            WHERE chat_pubkey=? AND created_at < ? AND deleted_at IS NULL
+            ORDER BY created_at DESC
             LIMIT 100
         "#;
         let messages = sqlx::query_as::<_, DbMessage>(sql)
@@ -201,7 +228,7 @@ impl DbMessage {
         let sql = r#"
             SELECT *
             FROM message
-            WHERE chat_pubkey=?
+            WHERE chat_pubkey=? AND deleted_at IS NULL
             ORDER BY created_at DESC
             LIMIT 1
         "#;
@@ -214,6 +241,49 @@ impl DbMessage {
         Ok(message)
     }
 
+    /// Date of the latest message of each chat
+    pub async fn fetch_last_dates(
+        pool: &SqlitePool,
+    ) -> Result<HashMap<XOnlyPublicKey, NaiveDateTime>, Error> {
+        let sql = r#"
+            SELECT chat_pubkey, MAX(created_at)
+            FROM message
+            WHERE deleted_at IS NULL
+            GROUP BY chat_pubkey
+        "#;
+        let rows = sqlx::query_as::<_, (String, i64)>(sql)
+            .fetch_all(pool)
+            .await?;
+        let mut dates = HashMap::new();
+        for (chat_pubkey, created_at) in rows {
+            dates.insert(
+                public_key_or_err(&chat_pubkey, "chat_pubkey")?,
+                millis_to_naive_or_err(created_at, "created_at")?,
+            );
+        }
+        Ok(dates)
+    }
+
+    /// Every message of the chat, oldest first
+    pub async fn fetch_chat_all(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+    ) -> Result<Vec<DbMessage>, Error> {
+        let sql = r#"
+            SELECT *
+            FROM message
+            WHERE chat_pubkey = ? AND deleted_at IS NULL
+            ORDER BY created_at ASC
+        "#;
+
+        let messages = sqlx::query_as::<_, DbMessage>(sql)
+            .bind(&chat_pubkey.to_string())
+            .fetch_all(pool)
+            .await?;
+
+        Ok(messages)
+    }
+
     pub async fn insert_confirmed(
         pool: &SqlitePool,
         db_event: &DbEvent,
@@ -230,8 +300,11 @@ impl DbMessage {
             None => {
                 let sql = r#"
                     INSERT INTO message 
-                    (event_id, content, chat_pubkey, is_users, created_at, status, relay_url)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);
+                    (event_id, content, chat_pubkey, is_users, created_at, status, relay_url,
+                    retracted_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7,
+                    -- its deletion may have come first
+                    (SELECT deleted_at FROM event_deletion WHERE target_hash = ?8 AND author = ?9));
                 "#;
 
                 sqlx::query(sql)
@@ -242,6 +315,8 @@ impl DbMessage {
                     .bind(db_event.created_at.timestamp_millis())
                     .bind(MessageStatus::Delivered.to_i32())
                     .bind(&db_event.relay_url.to_string())
+                    .bind(db_event.event_hash.to_string())
+                    .bind(db_event.pubkey.to_string())
                     .execute(pool)
                     .await?;
 
@@ -289,6 +364,136 @@ impl DbMessage {
         Ok(())
     }
 
+    /// Marks the messages of every chat as seen, returns how many were unseen
+    pub(crate) async fn reset_unseen_all(pool: &SqlitePool) -> Result<u64, Error> {
+        let sql = "UPDATE message SET status = ? WHERE status = ?";
+        let output = sqlx::query(sql)
+            .bind(MessageStatus::Seen.to_i32())
+            .bind(MessageStatus::Delivered.to_i32())
+            .execute(pool)
+            .await?;
+        Ok(output.rows_affected())
+    }
+
+    /// Unseen messages of every chat
+    pub async fn count_unseen(pool: &SqlitePool) -> Result<i64, Error> {
+        let sql = r#"
+            SELECT COUNT(*)
+            FROM message
+            WHERE status = ? AND is_users = 0 AND deleted_at IS NULL AND retracted_at IS NULL
+        "#;
+        let count: (i64,) = sqlx::query_as(sql)
+            .bind(MessageStatus::Delivered.to_i32())
+            .fetch_one(pool)
+            .await?;
+        Ok(count.0)
+    }
+
+    pub async fn set_starred(pool: &SqlitePool, event_id: i64, starred: bool) -> Result<(), Error> {
+        sqlx::query("UPDATE message SET starred = ? WHERE event_id = ?")
+            .bind(starred)
+            .bind(event_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Event ids of the chat's starred messages
+    pub async fn fetch_starred(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+    ) -> Result<Vec<i64>, Error> {
+        let sql = r#"
+            SELECT event_id
+            FROM message
+            WHERE chat_pubkey = ? AND starred = 1 AND deleted_at IS NULL
+        "#;
+        let event_ids = sqlx::query_scalar(sql)
+            .bind(&chat_pubkey.to_string())
+            .fetch_all(pool)
+            .await?;
+        Ok(event_ids)
+    }
+
+    /// Moves the chat's messages to the trash in batches, so a long conversation
+    /// doesn't hold the database. Returns how many were moved
+    pub async fn clear_chat(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+        keep_starred: bool,
+        deleted_at: NaiveDateTime,
+    ) -> Result<u64, Error> {
+        let sql = format!(
+            r#"
+            UPDATE message
+            SET deleted_at = ?
+            WHERE event_id IN (
+                SELECT event_id FROM message
+                WHERE chat_pubkey = ? AND deleted_at IS NULL {}
+                LIMIT ?
+            )
+        "#,
+            if keep_starred { "AND starred = 0" } else { "" }
+        );
+        let mut cleared = 0;
+        loop {
+            let batch = sqlx::query(&sql)
+                .bind(deleted_at.timestamp_millis())
+                .bind(&chat_pubkey.to_string())
+                .bind(CLEAR_BATCH_SIZE)
+                .execute(pool)
+                .await?
+                .rows_affected();
+            cleared += batch;
+            if batch < CLEAR_BATCH_SIZE as u64 {
+                return Ok(cleared);
+            }
+        }
+    }
+
+    /// Moves the selected messages of the chat to the trash, in batches
+    pub async fn trash_messages(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+        event_ids: &[i64],
+        deleted_at: NaiveDateTime,
+    ) -> Result<u64, Error> {
+        let mut trashed = 0;
+        for batch in event_ids.chunks(CLEAR_BATCH_SIZE as usize) {
+            let sql = format!(
+                r#"
+                UPDATE message
+                SET deleted_at = ?
+                WHERE chat_pubkey = ? AND deleted_at IS NULL AND event_id IN ({})
+            "#,
+                vec!["?"; batch.len()].join(", ")
+            );
+            let mut query = sqlx::query(&sql)
+                .bind(deleted_at.timestamp_millis())
+                .bind(chat_pubkey.to_string());
+            for event_id in batch {
+                query = query.bind(event_id);
+            }
+            trashed += query.execute(pool).await?.rows_affected();
+        }
+        Ok(trashed)
+    }
+
+    /// Returns false when the message was already retracted or isn't a DM
+    pub async fn retract(
+        pool: &SqlitePool,
+        event_id: i64,
+        retracted_at: NaiveDateTime,
+    ) -> Result<bool, Error> {
+        let sql = "UPDATE message SET retracted_at = ? WHERE event_id = ? AND retracted_at IS NULL";
+        let output = sqlx::query(sql)
+            .bind(retracted_at.timestamp_millis())
+            .bind(event_id)
+            .execute(pool)
+            .await?;
+        Ok(output.rows_affected() > 0)
+    }
+
     pub(crate) async fn mark_seen(pool: &SqlitePool, event_id: i64) -> Result<(), Error> {
         let sql = r#"
             UPDATE message
@@ -318,6 +523,11 @@ impl sqlx::FromRow<'_, SqliteRow> for DbMessage {
         let relay_url: String = row.try_get("relay_url")?;
         let relay_url = url_or_err(&relay_url, "relay_url")?;
 
+        let retracted_at = row
+            .try_get::<Option<i64>, &str>("retracted_at")?
+            .map(|millis| millis_to_naive_or_err(millis, "retracted_at"))
+            .transpose()?;
+
         Ok(DbMessage {
             event_id: row.try_get::<i64, &str>("event_id")?,
             encrypted_content: row.try_get::<String, &str>("content")?,
@@ -326,6 +536,7 @@ impl sqlx::FromRow<'_, SqliteRow> for DbMessage {
             created_at,
             status,
             relay_url,
+            retracted_at,
         })
     }
 }
@@ -361,3 +572,6 @@ impl MessageStatus {
         }
     }
 }
+
+/// Messages moved to the trash per statement when clearing a chat
+const CLEAR_BATCH_SIZE: i64 = 500;
@@ -1,25 +1,89 @@
+pub(crate) mod attachment_download;
+pub(crate) mod blocked;
 pub(crate) mod channel_cache;
+pub(crate) mod channel_keyword;
+pub(crate) mod channel_mention;
 pub(crate) mod channel_message;
+pub(crate) mod channel_moderation;
 pub(crate) mod channel_subscription;
 pub(crate) mod contact;
+pub(crate) mod contact_channels;
+pub(crate) mod contact_merge;
+pub(crate) mod conversation_visit;
 pub(crate) mod database;
+pub(crate) mod dm_history;
+pub(crate) mod draft;
 pub(crate) mod event;
+pub(crate) mod event_deletion;
+pub(crate) mod event_filter;
+pub(crate) mod followed_hashtag;
+pub(crate) mod follower;
 pub(crate) mod image_cache;
+pub(crate) mod integrity;
+pub(crate) mod label;
+pub(crate) mod lightning_check;
+pub(crate) mod live_activity;
 pub(crate) mod message;
+pub(crate) mod pending_event;
+pub(crate) mod poll;
 pub(crate) mod profile_cache;
+pub(crate) mod reaction;
 pub(crate) mod relay;
+pub(crate) mod relay_activity;
+pub(crate) mod relay_document_change;
+pub(crate) mod relay_profile;
 pub(crate) mod relay_response;
+pub(crate) mod relay_violation;
+pub(crate) mod signing_log;
+pub(crate) mod stats;
+pub(crate) mod storage;
+pub(crate) mod template;
+pub(crate) mod translation;
+pub(crate) mod trash;
 pub(crate) mod user_config;
 
+pub use attachment_download::{AttachmentDownload, AutoSaveAttachments};
+pub use blocked::DbBlocked;
 pub use channel_cache::ChannelCache;
+pub use channel_keyword::ChannelKeywords;
+pub use channel_mention::ChannelMention;
 pub use channel_message::DbChannelMessage;
-pub use channel_subscription::ChannelSubscription;
-pub use contact::DbContact;
+pub use channel_moderation::{DbChannelModeration, Moderation};
+pub use channel_subscription::{ChannelArchive, ChannelSubscription, ReadMarkers};
+pub use contact::{ContactSnapshot, DbContact};
+pub use contact_channels::{ContactActivity, ContactChannel};
+pub use contact_merge::ContactMerge;
+pub use conversation_visit::ConversationVisit;
+#[cfg(any(test, feature = "test-support"))]
+pub use database::TestDatabase;
 pub use database::{upgrade_cache_db, upgrade_db, Database};
+pub use dm_history::{DmHistory, SyncDepth};
+pub use draft::{DbDraft, DraftTarget};
 pub use event::DbEvent;
+pub use event_deletion::DbEventDeletion;
+pub use event_filter::EventFilter;
+pub use followed_hashtag::FollowedHashtags;
+pub use follower::DbFollower;
 pub use image_cache::ImageDownloaded;
+pub use integrity::IntegrityReport;
+pub use label::{DbLabel, LabelNotify, Labels};
+pub use lightning_check::LightningCheck;
+pub use live_activity::{DbLiveActivity, LiveStatus};
 pub use message::{DbMessage, MessageStatus, MessageTagInfo};
+pub use pending_event::DbPendingEvent;
+pub use poll::{DbPoll, DbPollVote, PollOption, PollTally};
 pub use profile_cache::ProfileCache;
-pub use relay::DbRelay;
-pub use relay_response::DbRelayResponse;
+pub use reaction::{DbReaction, ReactionCount};
+pub use relay::{CachedDocument, DbRelay};
+pub use relay_activity::{RelayActivity, RelaySuggestion, TuningChange};
+pub use relay_document_change::DbRelayDocumentChange;
+pub use relay_profile::{DbRelayProfile, ProfileRelay, RelaySwitch};
+pub use relay_response::{DbRelayResponse, ResponseStatus};
+pub use relay_violation::{DbRelayViolation, ViolationKind};
+pub use signing_log::SigningLogEntry;
+pub use stats::{AccountStats, MonthlyDms};
+pub use storage::{PruneReport, StorageUsage};
+pub use template::DbTemplate;
+pub use translation::{AutoTranslate, DbTranslation};
+pub use trash::{Trash, TrashItem, TrashKind};
 pub use user_config::UserConfig;
@@ -0,0 +1,67 @@
+use chrono::{NaiveDateTime, Utc};
+use nostr::prelude::*;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::{handle_decode_error, millis_to_naive_or_err};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+}
+
+/// Event sent to the relays that didn't get any confirmation before the app closed.
+/// It is sent again at the next login.
+#[derive(Debug, Clone)]
+pub struct DbPendingEvent {
+    pub ns_event: nostr::Event,
+    pub journaled_at: NaiveDateTime,
+}
+
+impl DbPendingEvent {
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<Self>, Error> {
+        let sql = "SELECT * FROM pending_event ORDER BY journaled_at ASC;";
+        let events = sqlx::query_as::<_, Self>(sql).fetch_all(pool).await?;
+        Ok(events)
+    }
+
+    pub async fn insert(pool: &SqlitePool, ns_event: &nostr::Event) -> Result<(), Error> {
+        let sql = r#"
+            INSERT OR REPLACE INTO pending_event (event_hash, json, journaled_at)
+            VALUES (?, ?, ?)
+        "#;
+
+        sqlx::query(sql)
+            .bind(ns_event.id.to_string())
+            .bind(ns_event.as_json())
+            .bind(Utc::now().naive_utc().timestamp_millis())
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, event_hash: &EventId) -> Result<(), Error> {
+        sqlx::query("DELETE FROM pending_event WHERE event_hash = ?;")
+            .bind(event_hash.to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbPendingEvent {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let json: String = row.try_get("json")?;
+        let ns_event = nostr::Event::from_json(json).map_err(|e| handle_decode_error(e, "json"))?;
+
+        let journaled_at = row.try_get::<i64, &str>("journaled_at")?;
+        let journaled_at = millis_to_naive_or_err(journaled_at, "journaled_at")?;
+
+        Ok(DbPendingEvent {
+            ns_event,
+            journaled_at,
+        })
+    }
+}
@@ -0,0 +1,278 @@
+use chrono::NaiveDateTime;
+use nostr::prelude::*;
+use nostr::secp256k1::XOnlyPublicKey;
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::{
+    event_hash_or_err, handle_decode_error, millis_to_naive_or_err, ns_event_to_naive,
+    public_key_or_err,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+
+    #[error("JSON (de)serialization error: {0}")]
+    FromSerdeJson(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    FromUtils(#[from] crate::utils::Error),
+
+    #[error("Poll without channel: {0}")]
+    MissingChannelId(EventId),
+
+    #[error("Poll without options: {0}")]
+    NoOptions(EventId),
+
+    #[error("Vote without poll: {0}")]
+    MissingPollId(EventId),
+
+    #[error("Vote without response: {0}")]
+    MissingResponse(EventId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollOption {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DbPoll {
+    pub poll_hash: EventId,
+    pub channel_id: EventId,
+    pub author: XOnlyPublicKey,
+    pub question: String,
+    pub options: Vec<PollOption>,
+    pub created_at: NaiveDateTime,
+}
+
+impl DbPoll {
+    pub async fn create_tables(pool: &SqlitePool) -> Result<(), Error> {
+        sqlx::query(include_str!("../../migrations/12_poll.sql"))
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub fn from_ns_event(ns_event: &nostr::Event) -> Result<Self, Error> {
+        let channel_id = tag_event_id(&ns_event.tags, Marker::Root)
+            .ok_or(Error::MissingChannelId(ns_event.id))?;
+        let options: Vec<_> = ns_event
+            .tags
+            .iter()
+            .filter_map(|tag| match tag.as_vec().as_slice() {
+                [kind, id, label] if kind == OPTION_TAG => Some(PollOption {
+                    id: id.to_owned(),
+                    label: label.to_owned(),
+                }),
+                _ => None,
+            })
+            .collect();
+        if options.is_empty() {
+            return Err(Error::NoOptions(ns_event.id));
+        }
+        Ok(Self {
+            poll_hash: ns_event.id,
+            channel_id,
+            author: ns_event.pubkey,
+            question: ns_event.content.to_owned(),
+            options,
+            created_at: ns_event_to_naive(ns_event.created_at)?,
+        })
+    }
+
+    /// Returns false if the poll was already stored
+    pub async fn insert(pool: &SqlitePool, poll: &DbPoll) -> Result<bool, Error> {
+        let sql = r#"
+            INSERT OR IGNORE INTO poll
+                (poll_hash, channel_id, author, question, options, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+        "#;
+
+        let result = sqlx::query(sql)
+            .bind(poll.poll_hash.to_string())
+            .bind(poll.channel_id.to_string())
+            .bind(poll.author.to_string())
+            .bind(&poll.question)
+            .bind(serde_json::to_string(&poll.options)?)
+            .bind(poll.created_at.timestamp_millis())
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn fetch_one(pool: &SqlitePool, poll_hash: &EventId) -> Result<Option<Self>, Error> {
+        let sql = "SELECT * FROM poll WHERE poll_hash = ?;";
+        let poll = sqlx::query_as::<_, Self>(sql)
+            .bind(poll_hash.to_string())
+            .fetch_optional(pool)
+            .await?;
+        Ok(poll)
+    }
+
+    pub async fn fetch_channel(
+        pool: &SqlitePool,
+        channel_id: &EventId,
+    ) -> Result<Vec<Self>, Error> {
+        let sql = "SELECT * FROM poll WHERE channel_id = ? ORDER BY created_at ASC;";
+        let polls = sqlx::query_as::<_, Self>(sql)
+            .bind(channel_id.to_string())
+            .fetch_all(pool)
+            .await?;
+        Ok(polls)
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbPoll {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let poll_hash = row.try_get::<String, &str>("poll_hash")?;
+        let channel_id = row.try_get::<String, &str>("channel_id")?;
+        let author = row.try_get::<String, &str>("author")?;
+        let options = row.try_get::<String, &str>("options")?;
+        let options =
+            serde_json::from_str(&options).map_err(|e| handle_decode_error(e, "options"))?;
+        let created_at = row.try_get::<i64, &str>("created_at")?;
+
+        Ok(Self {
+            poll_hash: event_hash_or_err(&poll_hash, "poll_hash")?,
+            channel_id: event_hash_or_err(&channel_id, "channel_id")?,
+            author: public_key_or_err(&author, "author")?,
+            question: row.try_get::<String, &str>("question")?,
+            options,
+            created_at: millis_to_naive_or_err(created_at, "created_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DbPollVote {
+    pub poll_hash: EventId,
+    pub voter: XOnlyPublicKey,
+    pub option_id: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl DbPollVote {
+    pub fn from_ns_event(ns_event: &nostr::Event) -> Result<Self, Error> {
+        let poll_hash =
+            tag_event_id(&ns_event.tags, Marker::Reply).ok_or(Error::MissingPollId(ns_event.id))?;
+        let option_id = ns_event
+            .tags
+            .iter()
+            .find_map(|tag| match tag.as_vec().as_slice() {
+                [kind, id] if kind == RESPONSE_TAG => Some(id.to_owned()),
+                _ => None,
+            })
+            .ok_or(Error::MissingResponse(ns_event.id))?;
+        Ok(Self {
+            poll_hash,
+            voter: ns_event.pubkey,
+            option_id,
+            created_at: ns_event_to_naive(ns_event.created_at)?,
+        })
+    }
+
+    /// Keeps only the newest vote of each voter, returns false when ignored
+    pub async fn upsert(pool: &SqlitePool, vote: &DbPollVote) -> Result<bool, Error> {
+        let sql = r#"
+            INSERT INTO poll_vote (poll_hash, voter, option_id, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(poll_hash, voter) DO UPDATE SET
+                option_id = excluded.option_id,
+                created_at = excluded.created_at
+            WHERE excluded.created_at > poll_vote.created_at
+        "#;
+
+        let result = sqlx::query(sql)
+            .bind(vote.poll_hash.to_string())
+            .bind(vote.voter.to_string())
+            .bind(&vote.option_id)
+            .bind(vote.created_at.timestamp_millis())
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Votes per option of a poll
+#[derive(Debug, Clone)]
+pub struct PollTally {
+    pub poll_hash: EventId,
+    pub counts: Vec<(String, u32)>,
+    /// Option chosen by the user, if any
+    pub user_vote: Option<String>,
+}
+
+impl PollTally {
+    pub async fn fetch(
+        pool: &SqlitePool,
+        poll: &DbPoll,
+        user: &XOnlyPublicKey,
+    ) -> Result<Self, Error> {
+        let sql = r#"
+            SELECT option_id, COUNT(*) AS total
+            FROM poll_vote
+            WHERE poll_hash = ?
+            GROUP BY option_id
+        "#;
+        let rows = sqlx::query(sql)
+            .bind(poll.poll_hash.to_string())
+            .fetch_all(pool)
+            .await?;
+
+        // options nobody voted for still get a bar
+        let counts = poll
+            .options
+            .iter()
+            .map(|option| {
+                let total = rows
+                    .iter()
+                    .find(|row| row.get::<String, &str>("option_id") == option.id)
+                    .map(|row| row.get::<u32, &str>("total"))
+                    .unwrap_or(0);
+                (option.id.to_owned(), total)
+            })
+            .collect();
+
+        let sql = "SELECT option_id FROM poll_vote WHERE poll_hash = ? AND voter = ?;";
+        let user_vote = sqlx::query_scalar::<_, String>(sql)
+            .bind(poll.poll_hash.to_string())
+            .bind(user.to_string())
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(Self {
+            poll_hash: poll.poll_hash,
+            counts,
+            user_vote,
+        })
+    }
+    pub fn total(&self) -> u32 {
+        self.counts.iter().map(|(_, count)| count).sum()
+    }
+    pub fn count(&self, option_id: &str) -> u32 {
+        self.counts
+            .iter()
+            .find(|(id, _)| id == option_id)
+            .map(|(_, count)| *count)
+            .unwrap_or(0)
+    }
+}
+
+fn tag_event_id(tags: &[Tag], marker: Marker) -> Option<EventId> {
+    tags.iter().find_map(|tag| match tag {
+        Tag::Event(event_id, _, Some(m)) if m == &marker => Some(event_id.to_owned()),
+        _ => None,
+    })
+}
+
+pub(crate) const POLL_KIND: u64 = 1068;
+pub(crate) const POLL_RESPONSE_KIND: u64 = 1018;
+pub(crate) const OPTION_TAG: &str = "option";
+pub(crate) const RESPONSE_TAG: &str = "response";
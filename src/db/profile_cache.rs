@@ -9,6 +9,7 @@ use chrono::NaiveDateTime;
 use nostr::{secp256k1::XOnlyPublicKey, EventId};
 use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use std::collections::HashMap;
 use thiserror::Error;
 use url::Url;
 
@@ -68,6 +69,92 @@ impl ProfileCache {
         Ok(result)
     }
 
+    /// Profiles of the public keys that have one, with their images, in a
+    /// single query on the main pool through the attached cache
+    pub async fn fetch_by_public_keys(
+        pool: &SqlitePool,
+        public_keys: &[XOnlyPublicKey],
+    ) -> Result<HashMap<XOnlyPublicKey, ProfileCache>, Error> {
+        if public_keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let (columns, joins) = Self::joined_sql("pk.value");
+        let placeholders = vec!["(?)"; public_keys.len()].join(", ");
+        let sql = format!(
+            "WITH pk(value) AS (VALUES {}) SELECT {} FROM pk {}",
+            placeholders, columns, joins
+        );
+        let mut query = sqlx::query(&sql);
+        for public_key in public_keys {
+            query = query.bind(public_key.to_string());
+        }
+        let profiles = query
+            .try_map(|row: SqliteRow| Self::from_joined_row(&row))
+            .fetch_all(pool)
+            .await?;
+        Ok(profiles
+            .into_iter()
+            .flatten()
+            .map(|profile| (profile.public_key, profile))
+            .collect())
+    }
+
+    /// Columns and joins bringing the profile matching the `public_key` column
+    /// and its images into a query on the main pool, read with `from_joined_row`
+    pub(crate) fn joined_sql(public_key: &str) -> (String, String) {
+        let (picture_columns, picture_joins) =
+            ImageDownloaded::joined_sql("picture", "profile.event_hash", ImageKind::Profile);
+        let (banner_columns, banner_joins) =
+            ImageDownloaded::joined_sql("banner", "profile.event_hash", ImageKind::Banner);
+        let columns = format!(
+            r#"profile.public_key AS profile_public_key, profile.updated_at AS profile_updated_at,
+            profile.event_hash AS profile_event_hash, profile.from_relay AS profile_from_relay,
+            profile.metadata AS profile_metadata, {}, {}"#,
+            picture_columns, banner_columns
+        );
+        let joins = format!(
+            "LEFT JOIN cache.profile_meta_cache profile ON profile.public_key = {} {} {}",
+            public_key, picture_joins, banner_joins
+        );
+        (columns, joins)
+    }
+
+    /// `None` when the joined row has no profile
+    pub(crate) fn from_joined_row(row: &SqliteRow) -> Result<Option<Self>, sqlx::Error> {
+        let Some(metadata) = row.try_get::<Option<String>, &str>("profile_metadata")? else {
+            return Ok(None);
+        };
+        let metadata = profile_meta_or_err(&metadata, "profile_metadata")?;
+
+        let event_hash: String = row.try_get("profile_event_hash")?;
+        let event_hash = event_hash_or_err(&event_hash, "profile_event_hash")?;
+
+        let public_key = row.try_get::<String, &str>("profile_public_key")?;
+        let public_key = public_key_or_err(&public_key, "profile_public_key")?;
+
+        let from_relay = row.try_get::<String, &str>("profile_from_relay")?;
+        let from_relay = url_or_err(&from_relay, "profile_from_relay")?;
+
+        let updated_at = millis_to_naive_or_err(
+            row.try_get::<i64, &str>("profile_updated_at")?,
+            "profile_updated_at",
+        )?;
+
+        Ok(Some(Self {
+            public_key,
+            updated_at,
+            event_hash,
+            metadata,
+            from_relay,
+            profile_pic_cache: ImageDownloaded::from_joined_row(
+                row,
+                "picture",
+                ImageKind::Profile,
+            )?,
+            banner_pic_cache: ImageDownloaded::from_joined_row(row, "banner", ImageKind::Banner)?,
+        }))
+    }
+
     // pub async fn fetch_channel_members(
     //     cache_pool: &SqlitePool,
     //     channel_id: &EventId,
@@ -185,3 +272,68 @@ impl sqlx::FromRow<'_, SqliteRow> for ProfileCache {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys, Metadata};
+
+    #[tokio::test]
+    async fn fetches_a_batch_of_profiles_with_images() {
+        let cache_file = tempfile::NamedTempFile::new().unwrap();
+        let db_file = tempfile::NamedTempFile::new().unwrap();
+        let cache_pool = SqlitePool::connect(cache_file.path().to_str().unwrap())
+            .await
+            .unwrap();
+        crate::db::upgrade_cache_db(&cache_pool).await.unwrap();
+        let pool = crate::db::database::connect_with_cache(
+            db_file.path().to_str().unwrap(),
+            cache_file.path(),
+        )
+        .await
+        .unwrap();
+
+        let relay_url = Url::parse("wss://relay.example.com").unwrap();
+        let (alice, bob, carol) = (Keys::generate(), Keys::generate(), Keys::generate());
+        for (keys, name) in [(&alice, "Alice"), (&bob, "Bob")] {
+            let ns_event = EventBuilder::set_metadata(Metadata::new().name(name))
+                .to_event(keys)
+                .unwrap();
+            ProfileCache::insert(&cache_pool, &relay_url, ns_event)
+                .await
+                .unwrap();
+        }
+        let alice_profile = ProfileCache::fetch_by_public_key(&cache_pool, &alice.public_key())
+            .await
+            .unwrap()
+            .unwrap();
+        ImageDownloaded::insert(
+            &cache_pool,
+            &ImageDownloaded {
+                path: "alice.png".into(),
+                kind: ImageKind::Profile,
+                event_hash: alice_profile.event_hash,
+                dominant_color: Some([10, 20, 30]),
+            },
+        )
+        .await
+        .unwrap();
+
+        let public_keys = [alice.public_key(), bob.public_key(), carol.public_key()];
+        let profiles = ProfileCache::fetch_by_public_keys(&pool, &public_keys)
+            .await
+            .unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert!(!profiles.contains_key(&carol.public_key()));
+
+        let alice_batched = &profiles[&alice.public_key()];
+        let picture = alice_batched.profile_pic_cache.as_ref().unwrap();
+        assert_eq!(picture.dominant_color, Some([10, 20, 30]));
+        assert_eq!(alice_batched.metadata, alice_profile.metadata);
+        assert!(alice_batched.banner_pic_cache.is_none());
+        assert_eq!(
+            profiles[&bob.public_key()].metadata.name.as_deref(),
+            Some("Bob")
+        );
+    }
+}
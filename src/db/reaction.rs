@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Tag};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::{
+    event_hash_or_err, millis_to_naive_or_err, ns_event_to_naive, public_key_or_err,
+};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("JSON (de)serialization error: {0}")]
+    FromSerdeJson(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    FromUtils(#[from] crate::utils::Error),
+
+    #[error("Reaction without event: {0}")]
+    MissingEventId(EventId),
+}
+
+/// NIP-25 reaction to a direct or channel message
+#[derive(Debug, Clone)]
+pub struct DbReaction {
+    pub reaction_hash: EventId,
+    /// Event reacted to, the last `e` tag
+    pub target_hash: EventId,
+    pub author: XOnlyPublicKey,
+    /// [`LIKE`] or an emoji
+    pub content: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl DbReaction {
+    pub async fn create_tables(pool: &SqlitePool) -> Result<(), Error> {
+        sqlx::query(include_str!("../../migrations/40_reaction.sql"))
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub fn from_ns_event(ns_event: &nostr::Event) -> Result<Self, Error> {
+        let target_hash = ns_event
+            .tags
+            .iter()
+            .rev()
+            .find_map(|tag| match tag {
+                Tag::Event(event_id, _, _) => Some(event_id.to_owned()),
+                _ => None,
+            })
+            .ok_or(Error::MissingEventId(ns_event.id))?;
+        // an empty content is read as a like
+        let content = match ns_event.content.trim() {
+            "" => LIKE.to_owned(),
+            content => content.to_owned(),
+        };
+        Ok(Self {
+            reaction_hash: ns_event.id,
+            target_hash,
+            author: ns_event.pubkey,
+            content,
+            created_at: ns_event_to_naive(ns_event.created_at)?,
+        })
+    }
+
+    /// Returns false when the author already reacted the same way
+    pub async fn insert(pool: &SqlitePool, reaction: &DbReaction) -> Result<bool, Error> {
+        let sql = r#"
+            INSERT OR IGNORE INTO reaction
+                (reaction_hash, target_hash, author, content, created_at)
+            VALUES (?, ?, ?, ?, ?)
+        "#;
+        let result = sqlx::query(sql)
+            .bind(reaction.reaction_hash.to_string())
+            .bind(reaction.target_hash.to_string())
+            .bind(reaction.author.to_string())
+            .bind(&reaction.content)
+            .bind(reaction.created_at.timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Reaction of `author` to the event with this content, if any
+    pub async fn fetch_by(
+        pool: &SqlitePool,
+        target_hash: &EventId,
+        author: &XOnlyPublicKey,
+        content: &str,
+    ) -> Result<Option<Self>, Error> {
+        let sql = "SELECT * FROM reaction WHERE target_hash = ? AND author = ? AND content = ?;";
+        let reaction = sqlx::query_as::<_, Self>(sql)
+            .bind(target_hash.to_string())
+            .bind(author.to_string())
+            .bind(content)
+            .fetch_optional(pool)
+            .await?;
+        Ok(reaction)
+    }
+
+    /// Removes the reaction along with its stored event
+    pub async fn delete(pool: &SqlitePool, reaction_hash: &EventId) -> Result<(), Error> {
+        let mut tx = pool.begin().await?;
+        for sql in [
+            "DELETE FROM reaction WHERE reaction_hash = ?",
+            "DELETE FROM event WHERE event_hash = ?",
+        ] {
+            sqlx::query(sql)
+                .bind(reaction_hash.to_string())
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbReaction {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let reaction_hash = row.try_get::<String, &str>("reaction_hash")?;
+        let target_hash = row.try_get::<String, &str>("target_hash")?;
+        let author = row.try_get::<String, &str>("author")?;
+        let created_at = row.try_get::<i64, &str>("created_at")?;
+
+        Ok(Self {
+            reaction_hash: event_hash_or_err(&reaction_hash, "reaction_hash")?,
+            target_hash: event_hash_or_err(&target_hash, "target_hash")?,
+            author: public_key_or_err(&author, "author")?,
+            content: row.try_get::<String, &str>("content")?,
+            created_at: millis_to_naive_or_err(created_at, "created_at")?,
+        })
+    }
+}
+
+/// Reactions to a message with the same content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionCount {
+    pub content: String,
+    pub count: u32,
+    /// The user is one of the authors
+    pub by_user: bool,
+}
+
+impl ReactionCount {
+    /// Likes are shown as a heart, like most clients do
+    pub fn label(&self) -> &str {
+        match self.content.as_str() {
+            LIKE => LIKE_LABEL,
+            content => content,
+        }
+    }
+
+    /// Reactions to the stored events with these `event_id`s, in the order they first arrived
+    pub async fn fetch(
+        pool: &SqlitePool,
+        event_ids: &[i64],
+        user: &XOnlyPublicKey,
+    ) -> Result<HashMap<i64, Vec<Self>>, Error> {
+        let sql = r#"
+            SELECT event.event_id, reaction.content,
+                COUNT(*) AS total, MAX(reaction.author = ?2) AS by_user
+            FROM reaction
+            JOIN event ON event.event_hash = reaction.target_hash
+            WHERE event.event_id IN (SELECT value FROM json_each(?1))
+            GROUP BY event.event_id, reaction.content
+            ORDER BY MIN(reaction.created_at), reaction.content
+        "#;
+        let rows = sqlx::query(sql)
+            .bind(serde_json::to_string(event_ids)?)
+            .bind(user.to_string())
+            .fetch_all(pool)
+            .await?;
+
+        let mut counts: HashMap<i64, Vec<Self>> = HashMap::new();
+        for row in rows {
+            counts
+                .entry(row.try_get::<i64, &str>("event_id")?)
+                .or_default()
+                .push(Self {
+                    content: row.try_get::<String, &str>("content")?,
+                    count: row.try_get::<u32, &str>("total")?,
+                    by_user: row.try_get::<bool, &str>("by_user")?,
+                });
+        }
+        Ok(counts)
+    }
+
+    pub async fn fetch_one(
+        pool: &SqlitePool,
+        event_id: i64,
+        user: &XOnlyPublicKey,
+    ) -> Result<Vec<Self>, Error> {
+        let mut counts = Self::fetch(pool, &[event_id], user).await?;
+        Ok(counts.remove(&event_id).unwrap_or_default())
+    }
+}
+
+/// NIP-25 content of a like
+pub(crate) const LIKE: &str = "+";
+const LIKE_LABEL: &str = "♥";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbEvent;
+    use crate::db::TestDatabase;
+    use crate::utils::reaction_builder;
+    use nostr::{EventBuilder, Keys, Url};
+
+    #[tokio::test]
+    async fn counts_reactions_per_message() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let url = Url::parse("wss://relay.example.com").unwrap();
+        let (author, user, other) = (Keys::generate(), Keys::generate(), Keys::generate());
+
+        let note = EventBuilder::new_text_note("hello", &[])
+            .to_event(&author)
+            .unwrap();
+        let db_note = DbEvent::insert(pool, &url, &note).await.unwrap().unwrap();
+        let react = |keys: &Keys, content: &str| {
+            let ns_event = reaction_builder(&note.id, &author.public_key(), content)
+                .to_event(keys)
+                .unwrap();
+            DbReaction::from_ns_event(&ns_event).unwrap()
+        };
+
+        let like = react(&user, "");
+        assert_eq!(like.content, LIKE);
+        assert_eq!(like.target_hash, note.id);
+        assert!(DbReaction::insert(pool, &like).await.unwrap());
+        assert!(!DbReaction::insert(pool, &react(&user, "+")).await.unwrap());
+        assert!(DbReaction::insert(pool, &react(&other, "+")).await.unwrap());
+        assert!(DbReaction::insert(pool, &react(&other, "🔥"))
+            .await
+            .unwrap());
+
+        let counts = ReactionCount::fetch_one(pool, db_note.event_id, &user.public_key())
+            .await
+            .unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!((counts[0].label(), counts[0].count), (LIKE_LABEL, 2));
+        assert!(counts[0].by_user);
+        assert_eq!((counts[1].label(), counts[1].count), ("🔥", 1));
+        assert!(!counts[1].by_user);
+
+        let own = DbReaction::fetch_by(pool, &note.id, &user.public_key(), LIKE)
+            .await
+            .unwrap()
+            .unwrap();
+        DbReaction::delete(pool, &own.reaction_hash).await.unwrap();
+        let counts = ReactionCount::fetch_one(pool, db_note.event_id, &user.public_key())
+            .await
+            .unwrap();
+        assert_eq!((counts[0].count, counts[0].by_user), (1, false));
+
+        let untagged = EventBuilder::new(nostr::Kind::Reaction, "+", &[])
+            .to_event(&user)
+            .unwrap();
+        assert!(DbReaction::from_ns_event(&untagged).is_err());
+    }
+}
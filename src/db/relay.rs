@@ -1,9 +1,11 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use nostr::nips::nip11::RelayInformationDocument;
 use ns_client::RelayInformation;
 use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
 use thiserror::Error;
 use url::Url;
 
-use crate::utils::url_or_err;
+use crate::utils::{canonical_relay_url, millis_to_naive_or_err, relay_doc_or_err, url_or_err};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -27,12 +29,37 @@ pub struct DbRelay {
     pub read: bool,
     pub write: bool,
     pub advertise: bool,
+    /// Answer AUTH with a throwaway key derived for this relay
+    pub anonymous_auth: bool,
     pub information: Option<RelayInformation>,
+    /// Last NIP-11 document fetched over HTTP
+    pub cached_document: Option<CachedDocument>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedDocument {
+    pub document: RelayInformationDocument,
+    /// Sent back as `If-None-Match` so an unchanged document isn't downloaded again
+    pub etag: Option<String>,
+    pub fetched_at: NaiveDateTime,
+}
+impl CachedDocument {
+    pub fn is_stale(&self, now: NaiveDateTime) -> bool {
+        now - self.fetched_at > Duration::hours(DOCUMENT_MAX_AGE_HOURS)
+    }
 }
 
 impl DbRelay {
     const FETCH_QUERY: &'static str = "SELECT * FROM relay";
 
+    /// The cached document, or the one from the connection until there's one
+    pub fn document(&self) -> Option<&RelayInformationDocument> {
+        self.cached_document
+            .as_ref()
+            .map(|cached| &cached.document)
+            .or_else(|| self.information.as_ref()?.document.as_ref())
+    }
+
     pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbRelay>, Error> {
         let sql = Self::FETCH_QUERY.to_owned();
         let output = sqlx::query_as::<_, DbRelay>(&sql).fetch_all(pool).await?;
@@ -42,15 +69,17 @@ impl DbRelay {
     pub async fn fetch_by_url(pool: &SqlitePool, url: &Url) -> Result<Option<DbRelay>, Error> {
         let sql = format!("{} WHERE url = ?", Self::FETCH_QUERY);
         Ok(sqlx::query_as::<_, DbRelay>(&sql)
-            .bind(&url.to_string())
+            .bind(canonical_relay_url(url).to_string())
             .fetch_optional(pool)
             .await?)
     }
 
     pub async fn insert(pool: &SqlitePool, url: &Url) -> Result<DbRelay, Error> {
-        let sql = "INSERT INTO relay (url) VALUES (?)";
+        let url = &canonical_relay_url(url);
+        let sql = "INSERT INTO relay (url, created_at) VALUES (?, ?)";
         sqlx::query(sql)
-            .bind(&url.to_string())
+            .bind(url.to_string())
+            .bind(Utc::now().timestamp_millis())
             .execute(pool)
             .await?;
         let db_relay = Self::fetch_by_url(pool, url)
@@ -60,25 +89,100 @@ impl DbRelay {
     }
 
     pub async fn update(pool: &SqlitePool, relay: &DbRelay) -> Result<(), Error> {
-        let sql = "UPDATE relay SET read=?, write=?, advertise=? WHERE id=?";
+        let sql = "UPDATE relay SET read=?, write=?, advertise=?, anonymous_auth=? WHERE id=?";
         sqlx::query(sql)
             .bind(relay.read)
             .bind(relay.write)
             .bind(relay.advertise)
+            .bind(relay.anonymous_auth)
             .bind(relay.id)
             .execute(pool)
             .await?;
         Ok(())
     }
 
+    pub async fn save_document(
+        pool: &SqlitePool,
+        url: &Url,
+        document: &RelayInformationDocument,
+        etag: Option<&str>,
+    ) -> Result<(), Error> {
+        let sql = "UPDATE relay SET document=?, document_etag=?, document_fetched_at=? WHERE url=?";
+        sqlx::query(sql)
+            .bind(serde_json::to_string(document)?)
+            .bind(etag)
+            .bind(Utc::now().timestamp_millis())
+            .bind(canonical_relay_url(url).to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The relay answered that the document didn't change
+    pub async fn touch_document(pool: &SqlitePool, url: &Url) -> Result<(), Error> {
+        let sql = "UPDATE relay SET document_fetched_at=? WHERE url=?";
+        sqlx::query(sql)
+            .bind(Utc::now().timestamp_millis())
+            .bind(canonical_relay_url(url).to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn delete(pool: &SqlitePool, url: &Url) -> Result<(), Error> {
         let sql = "DELETE FROM relay WHERE url=?";
         sqlx::query(sql)
-            .bind(&url.to_string())
+            .bind(canonical_relay_url(url).to_string())
             .execute(pool)
             .await?;
         Ok(())
     }
+
+    /// Rewrites every relay URL to its canonical form. Rows that end up with the
+    /// same URL are merged into the oldest one, keeping any flag that was set.
+    /// Returns how many rows were removed.
+    pub async fn merge_duplicates(pool: &SqlitePool) -> Result<usize, Error> {
+        let mut tx = pool.begin().await?;
+        let relays = sqlx::query_as::<_, DbRelay>("SELECT * FROM relay ORDER BY id")
+            .fetch_all(&mut tx)
+            .await?;
+
+        let mut kept: Vec<DbRelay> = vec![];
+        let mut removed = 0;
+        for relay in relays {
+            let url = canonical_relay_url(&relay.url);
+            if let Some(first) = kept.iter_mut().find(|r| r.url == url) {
+                first.read |= relay.read;
+                first.write |= relay.write;
+                first.advertise |= relay.advertise;
+                first.anonymous_auth |= relay.anonymous_auth;
+                sqlx::query("DELETE FROM relay WHERE id=?")
+                    .bind(relay.id)
+                    .execute(&mut tx)
+                    .await?;
+                removed += 1;
+            } else {
+                kept.push(DbRelay { url, ..relay });
+            }
+        }
+
+        let sql =
+            "UPDATE relay SET url=?, read=?, write=?, advertise=?, anonymous_auth=? WHERE id=?";
+        for relay in &kept {
+            sqlx::query(sql)
+                .bind(relay.url.to_string())
+                .bind(relay.read)
+                .bind(relay.write)
+                .bind(relay.advertise)
+                .bind(relay.anonymous_auth)
+                .bind(relay.id)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(removed)
+    }
 }
 
 impl sqlx::FromRow<'_, SqliteRow> for DbRelay {
@@ -92,7 +196,25 @@ impl sqlx::FromRow<'_, SqliteRow> for DbRelay {
             read: row.try_get::<bool, &str>("read")?,
             write: row.try_get::<bool, &str>("write")?,
             advertise: row.try_get::<bool, &str>("advertise")?,
+            anonymous_auth: row.try_get::<bool, &str>("anonymous_auth")?,
             information: None,
+            cached_document: cached_document_from_row(row)?,
         })
     }
 }
+
+fn cached_document_from_row(row: &SqliteRow) -> Result<Option<CachedDocument>, sqlx::Error> {
+    let document = row.try_get::<Option<String>, &str>("document")?;
+    let fetched_at = row.try_get::<Option<i64>, &str>("document_fetched_at")?;
+    let (Some(document), Some(fetched_at)) = (document, fetched_at) else {
+        return Ok(None);
+    };
+    Ok(Some(CachedDocument {
+        document: relay_doc_or_err(&document, "document")?,
+        etag: row.try_get::<Option<String>, &str>("document_etag")?,
+        fetched_at: millis_to_naive_or_err(fetched_at, "document_fetched_at")?,
+    }))
+}
+
+/// Documents older than this are fetched again
+const DOCUMENT_MAX_AGE_HOURS: i64 = 24;
@@ -0,0 +1,216 @@
+use chrono::{Duration, NaiveDateTime};
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+use url::Url;
+
+use crate::db::DbRelay;
+use crate::utils::{millis_to_naive_or_err, url_or_err};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// What a relay did for the user lately
+#[derive(Debug, Clone)]
+pub struct RelayActivity {
+    pub url: Url,
+    /// `None` for relays added before this was recorded
+    pub added_at: Option<NaiveDateTime>,
+    /// Newest contact event this relay was the first to deliver
+    pub last_delivery: Option<NaiveDateTime>,
+    /// Answers to the user's own events
+    pub accepted: i64,
+    pub rejected: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuningChange {
+    DisableRead,
+    DisableWrite,
+}
+impl std::fmt::Display for TuningChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TuningChange::DisableRead => write!(f, "Turn read off"),
+            TuningChange::DisableWrite => write!(f, "Turn write off"),
+        }
+    }
+}
+
+/// Change to a relay's read or write flag and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelaySuggestion {
+    pub url: Url,
+    pub change: TuningChange,
+    pub reason: String,
+}
+
+impl RelayActivity {
+    /// Activity of every relay in the list, publishes are counted since `since`
+    pub async fn fetch(
+        pool: &SqlitePool,
+        user_pubkey: &XOnlyPublicKey,
+        since: NaiveDateTime,
+    ) -> Result<Vec<RelayActivity>, Error> {
+        let sql = r#"
+            SELECT r.url, r.created_at AS added_at, d.last_delivery,
+                IFNULL(p.accepted, 0) AS accepted, IFNULL(p.rejected, 0) AS rejected
+            FROM relay r
+            LEFT JOIN (
+                SELECT relay_url, MAX(created_at) AS last_delivery FROM event
+                WHERE pubkey IN (SELECT pubkey FROM contact WHERE deleted_at IS NULL)
+                GROUP BY relay_url
+            ) d ON d.relay_url = r.url
+            LEFT JOIN (
+                SELECT rr.relay_url, SUM(rr.status = 1) AS accepted, SUM(rr.status = 0) AS rejected
+                FROM relay_response rr
+                JOIN event e ON e.event_id = rr.event_id
+                WHERE e.pubkey = ? AND e.created_at >= ?
+                GROUP BY rr.relay_url
+            ) p ON p.relay_url = r.url
+        "#;
+        let activity = sqlx::query_as::<_, RelayActivity>(sql)
+            .bind(user_pubkey.to_string())
+            .bind(since.timestamp_millis())
+            .fetch_all(pool)
+            .await?;
+        Ok(activity)
+    }
+}
+
+/// Read off for relays idle for `idle_days`, write off for relays that rejected
+/// everything lately. At least one relay is left reading and one writing.
+pub fn suggest(
+    relays: &[DbRelay],
+    activity: &[RelayActivity],
+    now: NaiveDateTime,
+    idle_days: u32,
+) -> Vec<RelaySuggestion> {
+    let idle_since = now - Duration::days(idle_days as i64);
+    let mut readers = relays.iter().filter(|r| r.read).count();
+    let mut writers = relays.iter().filter(|r| r.write).count();
+    let mut suggestions = vec![];
+
+    for relay in relays {
+        let Some(act) = activity.iter().find(|a| a.url == relay.url) else {
+            continue;
+        };
+        let is_new = act.added_at.map_or(false, |added| added > idle_since);
+
+        let is_idle = act.last_delivery.map_or(true, |last| last < idle_since);
+        if relay.read && !is_new && is_idle && readers > 1 {
+            readers -= 1;
+            let reason = match act.last_delivery {
+                Some(last) => format!(
+                    "Nothing new from your contacts since {}",
+                    last.format("%Y-%m-%d")
+                ),
+                None => "Never delivered anything new from your contacts".into(),
+            };
+            suggestions.push(RelaySuggestion {
+                url: relay.url.clone(),
+                change: TuningChange::DisableRead,
+                reason,
+            });
+        }
+
+        if relay.write && act.accepted == 0 && act.rejected >= MIN_REJECTIONS && writers > 1 {
+            writers -= 1;
+            suggestions.push(RelaySuggestion {
+                url: relay.url.clone(),
+                change: TuningChange::DisableWrite,
+                reason: format!(
+                    "Rejected all of your last {} events in {} days",
+                    act.rejected, idle_days
+                ),
+            });
+        }
+    }
+    suggestions
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for RelayActivity {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let url = row.try_get::<String, &str>("url")?;
+        let added_at = row
+            .try_get::<Option<i64>, &str>("added_at")?
+            .map(|millis| millis_to_naive_or_err(millis, "added_at"))
+            .transpose()?;
+        let last_delivery = row
+            .try_get::<Option<i64>, &str>("last_delivery")?
+            .map(|millis| millis_to_naive_or_err(millis, "last_delivery"))
+            .transpose()?;
+        Ok(Self {
+            url: url_or_err(&url, "url")?,
+            added_at,
+            last_delivery,
+            accepted: row.try_get::<i64, &str>("accepted")?,
+            rejected: row.try_get::<i64, &str>("rejected")?,
+        })
+    }
+}
+
+/// Fewer rejections could be a relay having a bad moment
+const MIN_REJECTIONS: i64 = 5;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn relay(url: &str) -> DbRelay {
+        DbRelay {
+            id: 0,
+            url: Url::parse(url).unwrap(),
+            read: true,
+            write: true,
+            advertise: false,
+            anonymous_auth: false,
+            information: None,
+            cached_document: None,
+        }
+    }
+
+    fn activity(url: &str, delivered_days_ago: Option<i64>, rejected: i64) -> RelayActivity {
+        let now = Utc::now().naive_utc();
+        RelayActivity {
+            url: Url::parse(url).unwrap(),
+            added_at: None,
+            last_delivery: delivered_days_ago.map(|days| now - Duration::days(days)),
+            accepted: 0,
+            rejected,
+        }
+    }
+
+    #[test]
+    fn idle_and_rejecting_relays_are_suggested() {
+        let relays = vec![relay("wss://a.com"), relay("wss://b.com")];
+        let activity = vec![
+            activity("wss://a.com", Some(1), 0),
+            activity("wss://b.com", Some(30), 8),
+        ];
+        let suggestions = suggest(&relays, &activity, Utc::now().naive_utc(), 14);
+        let changes: Vec<_> = suggestions.iter().map(|s| s.change).collect();
+        assert_eq!(
+            changes,
+            vec![TuningChange::DisableRead, TuningChange::DisableWrite]
+        );
+        assert!(suggestions
+            .iter()
+            .all(|s| s.url.as_str().starts_with("wss://b.com")));
+    }
+
+    #[test]
+    fn last_reading_relay_is_kept() {
+        let relays = vec![relay("wss://a.com"), relay("wss://b.com")];
+        let activity = vec![
+            activity("wss://a.com", None, 0),
+            activity("wss://b.com", None, 0),
+        ];
+        let suggestions = suggest(&relays, &activity, Utc::now().naive_utc(), 14);
+        assert_eq!(suggestions.len(), 1);
+    }
+}
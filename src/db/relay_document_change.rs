@@ -0,0 +1,81 @@
+use chrono::{NaiveDateTime, Utc};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+use url::Url;
+
+use crate::utils::{millis_to_naive_or_err, url_or_err};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Difference found in a relay's NIP-11 document, e.g. a new supported NIP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbRelayDocumentChange {
+    pub id: i64,
+    pub relay_url: Url,
+    pub change: String,
+    pub detected_at: NaiveDateTime,
+}
+
+impl DbRelayDocumentChange {
+    /// Most recent first
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<Self>, Error> {
+        let sql = "SELECT * FROM relay_document_change ORDER BY detected_at DESC, id";
+        let changes = sqlx::query_as::<_, Self>(sql).fetch_all(pool).await?;
+        Ok(changes)
+    }
+
+    /// Returns the inserted rows
+    pub async fn insert(
+        pool: &SqlitePool,
+        relay_url: &Url,
+        changes: &[String],
+    ) -> Result<Vec<Self>, Error> {
+        let now = Utc::now().timestamp_millis();
+        let sql =
+            "INSERT INTO relay_document_change (relay_url, change, detected_at) VALUES (?, ?, ?)";
+        let mut inserted = vec![];
+        for change in changes {
+            let output = sqlx::query(sql)
+                .bind(relay_url.as_str())
+                .bind(change)
+                .bind(now)
+                .execute(pool)
+                .await?;
+            inserted.push(Self {
+                id: output.last_insert_rowid(),
+                relay_url: relay_url.to_owned(),
+                change: change.to_owned(),
+                detected_at: millis_to_naive_or_err(now, "detected_at")?,
+            });
+        }
+        Ok(inserted)
+    }
+
+    /// Dismisses the changes of the relay
+    pub async fn delete_relay(pool: &SqlitePool, relay_url: &Url) -> Result<(), Error> {
+        sqlx::query("DELETE FROM relay_document_change WHERE relay_url = ?")
+            .bind(relay_url.as_str())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbRelayDocumentChange {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let relay_url = row.try_get::<String, &str>("relay_url")?;
+        Ok(Self {
+            id: row.try_get::<i64, &str>("id")?,
+            relay_url: url_or_err(&relay_url, "relay_url")?,
+            change: row.try_get::<String, &str>("change")?,
+            detected_at: millis_to_naive_or_err(
+                row.try_get::<i64, &str>("detected_at")?,
+                "detected_at",
+            )?,
+        })
+    }
+}
@@ -0,0 +1,260 @@
+use chrono::Utc;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+use url::Url;
+
+use crate::utils::{canonical_relay_url, url_or_err};
+
+use super::DbRelay;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("Relay profile not found: {0}")]
+    ProfileNotFound(String),
+
+    #[error("Relay profile name can't be empty")]
+    EmptyName,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileRelay {
+    pub url: Url,
+    pub read: bool,
+    pub write: bool,
+}
+
+/// Named set of relays with their read and write flags, e.g. home or travel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbRelayProfile {
+    pub name: String,
+    pub relays: Vec<ProfileRelay>,
+}
+
+impl DbRelayProfile {
+    const FETCH_QUERY: &'static str = "SELECT * FROM relay_profile";
+
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbRelayProfile>, Error> {
+        let sql = format!("{} ORDER BY name, url", Self::FETCH_QUERY);
+        let rows = sqlx::query_as::<_, ProfileRow>(&sql)
+            .fetch_all(pool)
+            .await?;
+        let mut profiles: Vec<DbRelayProfile> = vec![];
+        for ProfileRow { name, relay } in rows {
+            match profiles.last_mut() {
+                Some(profile) if profile.name == name => profile.relays.push(relay),
+                _ => profiles.push(DbRelayProfile {
+                    name,
+                    relays: vec![relay],
+                }),
+            }
+        }
+        Ok(profiles)
+    }
+
+    pub async fn fetch_by_name(
+        pool: &SqlitePool,
+        name: &str,
+    ) -> Result<Option<DbRelayProfile>, Error> {
+        let sql = format!("{} WHERE name = ? ORDER BY url", Self::FETCH_QUERY);
+        let rows = sqlx::query_as::<_, ProfileRow>(&sql)
+            .bind(name)
+            .fetch_all(pool)
+            .await?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(DbRelayProfile {
+            name: name.to_owned(),
+            relays: rows.into_iter().map(|row| row.relay).collect(),
+        }))
+    }
+
+    /// Stores the relays under `name`, replacing the profile if it exists
+    pub async fn save(
+        pool: &SqlitePool,
+        name: &str,
+        relays: &[DbRelay],
+    ) -> Result<DbRelayProfile, Error> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::EmptyName);
+        }
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM relay_profile WHERE name = ?")
+            .bind(name)
+            .execute(&mut tx)
+            .await?;
+        let sql = "INSERT INTO relay_profile (name, url, read, write) VALUES (?, ?, ?, ?)";
+        for relay in relays {
+            sqlx::query(sql)
+                .bind(name)
+                .bind(canonical_relay_url(&relay.url).to_string())
+                .bind(relay.read)
+                .bind(relay.write)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Self::fetch_by_name(pool, name)
+            .await?
+            .ok_or_else(|| Error::ProfileNotFound(name.to_owned()))
+    }
+
+    pub async fn delete(pool: &SqlitePool, name: &str) -> Result<(), Error> {
+        let sql = "DELETE FROM relay_profile WHERE name = ?";
+        sqlx::query(sql).bind(name).execute(pool).await?;
+        Ok(())
+    }
+}
+
+/// Changes that turn the current relays into a profile's
+#[derive(Debug, Clone, Default)]
+pub struct RelaySwitch {
+    pub removed: Vec<Url>,
+    pub added: Vec<ProfileRelay>,
+    /// Relays kept with other read or write flags, already updated
+    pub toggled: Vec<DbRelay>,
+}
+
+impl RelaySwitch {
+    pub fn plan(current: &[DbRelay], profile: &DbRelayProfile) -> Self {
+        let mut switch = Self::default();
+        for relay in current {
+            let url = canonical_relay_url(&relay.url);
+            match profile.relays.iter().find(|r| r.url == url) {
+                Some(wanted) if wanted.read != relay.read || wanted.write != relay.write => {
+                    switch.toggled.push(DbRelay {
+                        read: wanted.read,
+                        write: wanted.write,
+                        ..relay.to_owned()
+                    })
+                }
+                Some(_) => (),
+                None => switch.removed.push(url),
+            }
+        }
+        switch.added = profile
+            .relays
+            .iter()
+            .filter(|wanted| {
+                !current
+                    .iter()
+                    .any(|relay| canonical_relay_url(&relay.url) == wanted.url)
+            })
+            .cloned()
+            .collect();
+        switch
+    }
+
+    /// Stores every change in one transaction, returns the relays created
+    pub async fn apply(&self, pool: &SqlitePool) -> Result<Vec<DbRelay>, Error> {
+        let mut tx = pool.begin().await?;
+        for url in &self.removed {
+            sqlx::query("DELETE FROM relay WHERE url=?")
+                .bind(url.to_string())
+                .execute(&mut tx)
+                .await?;
+        }
+        for relay in &self.toggled {
+            sqlx::query("UPDATE relay SET read=?, write=? WHERE id=?")
+                .bind(relay.read)
+                .bind(relay.write)
+                .bind(relay.id)
+                .execute(&mut tx)
+                .await?;
+        }
+        let mut created = vec![];
+        for relay in &self.added {
+            sqlx::query("INSERT INTO relay (url, read, write, created_at) VALUES (?, ?, ?, ?)")
+                .bind(relay.url.to_string())
+                .bind(relay.read)
+                .bind(relay.write)
+                .bind(Utc::now().timestamp_millis())
+                .execute(&mut tx)
+                .await?;
+            let db_relay = sqlx::query_as::<_, DbRelay>("SELECT * FROM relay WHERE url=?")
+                .bind(relay.url.to_string())
+                .fetch_one(&mut tx)
+                .await?;
+            created.push(db_relay);
+        }
+        tx.commit().await?;
+        Ok(created)
+    }
+}
+
+struct ProfileRow {
+    name: String,
+    relay: ProfileRelay,
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for ProfileRow {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let url = row.try_get::<String, &str>("url")?;
+        Ok(ProfileRow {
+            name: row.try_get::<String, &str>("name")?,
+            relay: ProfileRelay {
+                url: url_or_err(&url, "url")?,
+                read: row.try_get::<bool, &str>("read")?,
+                write: row.try_get::<bool, &str>("write")?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db_relay(id: i32, url: &str, read: bool, write: bool) -> DbRelay {
+        DbRelay {
+            id,
+            url: Url::parse(url).unwrap(),
+            read,
+            write,
+            advertise: true,
+            anonymous_auth: false,
+            information: None,
+            cached_document: None,
+        }
+    }
+
+    fn profile_relay(url: &str, read: bool, write: bool) -> ProfileRelay {
+        ProfileRelay {
+            url: canonical_relay_url(&Url::parse(url).unwrap()),
+            read,
+            write,
+        }
+    }
+
+    #[test]
+    fn plans_switch_to_profile() {
+        let current = vec![
+            db_relay(1, "wss://home.example.com", true, true),
+            db_relay(2, "wss://both.example.com", true, true),
+            db_relay(3, "wss://same.example.com", true, false),
+        ];
+        let profile = DbRelayProfile {
+            name: "travel".into(),
+            relays: vec![
+                profile_relay("wss://both.example.com", false, true),
+                profile_relay("wss://same.example.com", true, false),
+                profile_relay("wss://travel.example.com", true, true),
+            ],
+        };
+
+        let switch = RelaySwitch::plan(&current, &profile);
+        assert_eq!(switch.removed, vec![canonical_relay_url(&current[0].url)]);
+        assert_eq!(
+            switch.added,
+            vec![profile_relay("wss://travel.example.com", true, true)]
+        );
+        assert_eq!(switch.toggled.len(), 1);
+        assert_eq!(switch.toggled[0].id, 2);
+        assert!(!switch.toggled[0].read && switch.toggled[0].write);
+    }
+}
@@ -1,3 +1,5 @@
+use chrono::{Duration, NaiveDateTime};
+use nostr::secp256k1::XOnlyPublicKey;
 use nostr::{EventId, Url};
 use serde::{Deserialize, Serialize};
 use sqlx::sqlite::SqliteRow;
@@ -5,9 +7,9 @@ use sqlx::{FromRow, Row, SqlitePool};
 use std::result::Result as StdResult;
 use thiserror::Error;
 
-use crate::utils::{event_hash_or_err, url_or_err};
+use crate::utils::{canonical_relay_url, event_hash_or_err, millis_to_naive_or_err, url_or_err};
 
-use super::DbEvent;
+use super::{DbContact, DbEvent};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -21,14 +23,20 @@ pub struct DbRelayResponse {
     pub event_hash: EventId,
     pub relay_url: Url,
     pub status: ResponseStatus,
+    /// When the event was handed to the relays
+    pub sent_at: Option<NaiveDateTime>,
+    /// When the relay's answer arrived
+    pub responded_at: Option<NaiveDateTime>,
 }
 impl DbRelayResponse {
     pub fn ok(event_id: i64, event_hash: &EventId, relay_url: &Url) -> Self {
         Self {
             event_id,
             event_hash: event_hash.to_owned(),
-            relay_url: relay_url.to_owned(),
+            relay_url: canonical_relay_url(relay_url),
             status: ResponseStatus::from_bool(true, None),
+            sent_at: None,
+            responded_at: None,
         }
     }
     pub fn error(
@@ -40,10 +48,30 @@ impl DbRelayResponse {
         Self {
             event_id,
             event_hash: event_hash.to_owned(),
-            relay_url: relay_url.to_owned(),
+            relay_url: canonical_relay_url(relay_url),
             status: ResponseStatus::from_bool(false, Some(error_message.to_owned())),
+            sent_at: None,
+            responded_at: None,
         }
     }
+    /// Without `sent_at` the send time of the other answers is used
+    pub fn answered(mut self, sent_at: Option<NaiveDateTime>, responded_at: NaiveDateTime) -> Self {
+        self.sent_at = sent_at;
+        self.responded_at = Some(responded_at);
+        self
+    }
+    /// Time between sending the event and the relay's answer
+    pub fn delay(&self) -> Option<Duration> {
+        Some(self.responded_at? - self.sent_at?)
+    }
+    /// Quickest accepted answer
+    pub fn time_to_first_confirmation(responses: &[DbRelayResponse]) -> Option<Duration> {
+        responses
+            .iter()
+            .filter(|response| response.status == ResponseStatus::Ok)
+            .filter_map(DbRelayResponse::delay)
+            .min()
+    }
     pub async fn fetch_by_event(
         pool: &SqlitePool,
         event_id: i64,
@@ -73,37 +101,127 @@ impl DbRelayResponse {
 
         let db_response = sqlx::query_as::<_, DbRelayResponse>(sql)
             .bind(response.event_id)
-            .bind(&response.relay_url.to_string())
+            .bind(canonical_relay_url(&response.relay_url).to_string())
             .fetch_optional(pool)
             .await?;
 
         Ok(db_response)
     }
+    /// Relays where the contact's messages arrive the most, best first
+    pub async fn fetch_chat_relays(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+        limit: u32,
+    ) -> Result<Vec<Url>, Error> {
+        let sql = r#"
+            SELECT r.relay_url, COUNT(*) AS total
+            FROM relay_response r
+            INNER JOIN message m ON m.event_id = r.event_id
+            WHERE m.chat_pubkey = ? AND m.is_users = 0 AND r.status = 1
+            GROUP BY r.relay_url
+            ORDER BY total DESC
+            LIMIT ?
+        "#;
+
+        let rows = sqlx::query(sql)
+            .bind(chat_pubkey.to_string())
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+
+        let relays = rows
+            .iter()
+            .map(|row| {
+                let relay_url = row.try_get::<String, &str>("relay_url")?;
+                url_or_err(&relay_url, "relay_url")
+            })
+            .collect::<StdResult<Vec<_>, _>>()?;
+
+        Ok(relays)
+    }
+    /// Preferred relays for a conversation, falls back to the relay
+    /// recommended in the contact list
+    pub async fn fetch_conversation_relays(
+        pool: &SqlitePool,
+        db_contact: &DbContact,
+        limit: u32,
+    ) -> Result<Vec<Url>, Error> {
+        let mut relays = Self::fetch_chat_relays(pool, db_contact.pubkey(), limit).await?;
+        if let Some(relay_url) = db_contact.get_relay_url() {
+            if relays.len() < limit as usize && !relays.contains(&relay_url) {
+                relays.push(relay_url);
+            }
+        }
+        Ok(relays)
+    }
     async fn insert(pool: &SqlitePool, response: &DbRelayResponse) -> Result<(), Error> {
         tracing::trace!("Inserting relay response: {:?}", response);
         let (status, error_message) = response.status.to_bool();
+        let sent_at = response.sent_at.map(|t| t.timestamp_millis());
+        let responded_at = response.responded_at.map(|t| t.timestamp_millis());
 
         if (Self::fetch_one(pool, response).await?).is_some() {
+            // stored when the event was, the timing comes afterwards
+            let sql = r#"
+                UPDATE relay_response
+                SET sent_at = COALESCE(sent_at, ?,
+                        (SELECT MIN(sent_at) FROM relay_response WHERE event_id = ?)),
+                    responded_at = COALESCE(responded_at, ?)
+                WHERE event_id = ? AND relay_url = ?
+            "#;
+            sqlx::query(sql)
+                .bind(sent_at)
+                .bind(response.event_id)
+                .bind(responded_at)
+                .bind(response.event_id)
+                .bind(canonical_relay_url(&response.relay_url).to_string())
+                .execute(pool)
+                .await?;
             return Ok(());
         }
 
         let sql = r#"
-            INSERT INTO relay_response (event_id, event_hash, relay_url, status, error_message)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO relay_response
+                (event_id, event_hash, relay_url, status, error_message, sent_at, responded_at)
+            VALUES (?, ?, ?, ?, ?,
+                COALESCE(?, (SELECT MIN(sent_at) FROM relay_response WHERE event_id = ?)), ?)
         "#;
 
         sqlx::query(sql)
             .bind(response.event_id)
             .bind(&response.event_hash.to_string())
-            .bind(&response.relay_url.to_string())
+            .bind(canonical_relay_url(&response.relay_url).to_string())
             .bind(status)
             .bind(error_message)
+            .bind(sent_at)
+            .bind(response.event_id)
+            .bind(responded_at)
             .execute(pool)
             .await?;
 
         Ok(())
     }
 
+    /// Answer from a relay to one of the user's events
+    pub async fn insert_answer(
+        pool: &SqlitePool,
+        db_event: &DbEvent,
+        relay_url: &nostr::Url,
+        status: ResponseStatus,
+        sent_at: Option<NaiveDateTime>,
+        responded_at: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let response = match status {
+            ResponseStatus::Ok => {
+                DbRelayResponse::ok(db_event.event_id, &db_event.event_hash, relay_url)
+            }
+            ResponseStatus::Error(e) => {
+                DbRelayResponse::error(db_event.event_id, &db_event.event_hash, relay_url, &e)
+            }
+        };
+        Self::insert(pool, &response.answered(sent_at, responded_at)).await
+    }
+
     pub async fn insert_ok(
         pool: &SqlitePool,
         relay_url: &nostr::Url,
@@ -114,6 +232,37 @@ impl DbRelayResponse {
         DbRelayResponse::insert(pool, &relay_response).await?;
         Ok(())
     }
+
+    /// Rewrites response URLs to their canonical form, a response already stored
+    /// under the canonical URL wins over the duplicate
+    pub async fn merge_duplicate_urls(pool: &SqlitePool) -> Result<(), Error> {
+        let mut tx = pool.begin().await?;
+        let urls: Vec<String> = sqlx::query_scalar("SELECT DISTINCT relay_url FROM relay_response")
+            .fetch_all(&mut tx)
+            .await?;
+
+        for url in urls {
+            let Ok(canonical) = Url::parse(&url).map(|u| canonical_relay_url(&u)) else {
+                continue;
+            };
+            let canonical = canonical.to_string();
+            if canonical == url {
+                continue;
+            }
+            sqlx::query("UPDATE OR IGNORE relay_response SET relay_url = ? WHERE relay_url = ?")
+                .bind(&canonical)
+                .bind(&url)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("DELETE FROM relay_response WHERE relay_url = ?")
+                .bind(&url)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -146,11 +295,80 @@ impl FromRow<'_, SqliteRow> for DbRelayResponse {
         let relay_url = url_or_err(&relay_url, "relay_url")?;
         let error_message = row.get::<Option<String>, &str>("error_message");
         let status = ResponseStatus::from_bool(row.try_get::<bool, &str>("status")?, error_message);
+        let sent_at = row
+            .try_get::<Option<i64>, &str>("sent_at")?
+            .map(|millis| millis_to_naive_or_err(millis, "sent_at"))
+            .transpose()?;
+        let responded_at = row
+            .try_get::<Option<i64>, &str>("responded_at")?
+            .map(|millis| millis_to_naive_or_err(millis, "responded_at"))
+            .transpose()?;
         Ok(DbRelayResponse {
             event_id: row.try_get::<i64, &str>("event_id")?,
             event_hash,
             relay_url,
             status,
+            sent_at,
+            responded_at,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use chrono::Utc;
+    use nostr::{EventBuilder, Keys};
+
+    #[tokio::test]
+    async fn later_answers_share_the_send_time() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let ns_event = EventBuilder::new_text_note("hi", &[])
+            .to_event(&Keys::generate())
+            .unwrap();
+        let first = Url::parse("wss://first.com").unwrap();
+        let second = Url::parse("wss://second.com").unwrap();
+        let sent_at = Utc::now().naive_utc();
+
+        // the first answer is stored with the event, without timing
+        let db_event = DbEvent::insert(pool, &first, &ns_event)
+            .await
+            .unwrap()
+            .unwrap();
+        DbRelayResponse::insert_answer(
+            pool,
+            &db_event,
+            &first,
+            ResponseStatus::Ok,
+            Some(sent_at),
+            sent_at + Duration::milliseconds(300),
+        )
+        .await
+        .unwrap();
+        DbRelayResponse::insert_answer(
+            pool,
+            &db_event,
+            &second,
+            ResponseStatus::Error("blocked".into()),
+            None,
+            sent_at + Duration::milliseconds(100),
+        )
+        .await
+        .unwrap();
+
+        let responses = DbRelayResponse::fetch_by_event(pool, db_event.event_id)
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 2);
+        assert!(responses
+            .iter()
+            .all(|r| r.delay().map(|d| d.num_milliseconds()).is_some()));
+        // the quicker answer was a rejection
+        assert_eq!(
+            DbRelayResponse::time_to_first_confirmation(&responses).map(|d| d.num_milliseconds()),
+            Some(300)
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, Utc};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+use url::Url;
+
+use crate::utils::{handle_decode_error, millis_to_naive_or_err, url_or_err};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("Unknown relay violation: {0}")]
+    UnknownKind(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// More events per second than can be processed
+    Flood,
+    /// Far more stored events than the filters asked for
+    OverLimit,
+    /// Same events sent again on the same subscription
+    Replay,
+    /// Events cut to the size limit or cleaned of control characters
+    Truncated,
+    /// Events over the size or tag limits, dropped
+    Rejected,
+}
+impl ViolationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViolationKind::Flood => "flood",
+            ViolationKind::OverLimit => "over_limit",
+            ViolationKind::Replay => "replay",
+            ViolationKind::Truncated => "truncated",
+            ViolationKind::Rejected => "rejected",
+        }
+    }
+}
+impl FromStr for ViolationKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flood" => Ok(ViolationKind::Flood),
+            "over_limit" => Ok(ViolationKind::OverLimit),
+            "replay" => Ok(ViolationKind::Replay),
+            "truncated" => Ok(ViolationKind::Truncated),
+            "rejected" => Ok(ViolationKind::Rejected),
+            other => Err(Error::UnknownKind(other.to_owned())),
+        }
+    }
+}
+impl std::fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ViolationKind::Flood => write!(f, "Sent events faster than they can be processed"),
+            ViolationKind::OverLimit => write!(f, "Returned far more events than requested"),
+            ViolationKind::Replay => write!(f, "Sent the same events over and over"),
+            ViolationKind::Truncated => write!(f, "Sent events that had to be cut or cleaned"),
+            ViolationKind::Rejected => write!(f, "Sent oversized or malformed events"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbRelayViolation {
+    pub relay_url: Url,
+    pub kind: ViolationKind,
+    pub subscription_id: String,
+    pub count: i64,
+    pub first_at: NaiveDateTime,
+    pub last_at: NaiveDateTime,
+}
+
+impl DbRelayViolation {
+    /// Most recent first
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbRelayViolation>, Error> {
+        let sql = "SELECT * FROM relay_violation ORDER BY last_at DESC";
+        let violations = sqlx::query_as::<_, DbRelayViolation>(sql)
+            .fetch_all(pool)
+            .await?;
+        Ok(violations)
+    }
+
+    /// Returns the updated row
+    pub async fn record(
+        pool: &SqlitePool,
+        relay_url: &Url,
+        kind: ViolationKind,
+        subscription_id: &str,
+    ) -> Result<DbRelayViolation, Error> {
+        let now = Utc::now().timestamp_millis();
+        let sql = r#"
+            INSERT INTO relay_violation (relay_url, kind, subscription_id, first_at, last_at)
+            VALUES (?1, ?2, ?3, ?4, ?4)
+            ON CONFLICT(relay_url, kind) DO UPDATE SET
+                subscription_id = excluded.subscription_id,
+                count = count + 1,
+                last_at = excluded.last_at
+        "#;
+        sqlx::query(sql)
+            .bind(relay_url.as_str())
+            .bind(kind.as_str())
+            .bind(subscription_id)
+            .bind(now)
+            .execute(pool)
+            .await?;
+        let violation = sqlx::query_as::<_, DbRelayViolation>(
+            "SELECT * FROM relay_violation WHERE relay_url = ? AND kind = ?",
+        )
+        .bind(relay_url.as_str())
+        .bind(kind.as_str())
+        .fetch_one(pool)
+        .await?;
+        Ok(violation)
+    }
+
+    pub async fn delete_relay(pool: &SqlitePool, relay_url: &Url) -> Result<(), Error> {
+        sqlx::query("DELETE FROM relay_violation WHERE relay_url = ?")
+            .bind(relay_url.as_str())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbRelayViolation {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let relay_url = row.try_get::<String, &str>("relay_url")?;
+        let kind = row.try_get::<String, &str>("kind")?;
+        let kind = kind.parse().map_err(|e| handle_decode_error(e, "kind"))?;
+        Ok(Self {
+            relay_url: url_or_err(&relay_url, "relay_url")?,
+            kind,
+            subscription_id: row.try_get::<String, &str>("subscription_id")?,
+            count: row.try_get::<i64, &str>("count")?,
+            first_at: millis_to_naive_or_err(row.try_get::<i64, &str>("first_at")?, "first_at")?,
+            last_at: millis_to_naive_or_err(row.try_get::<i64, &str>("last_at")?, "last_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+
+    #[tokio::test]
+    async fn record_counts_per_relay_and_kind() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let url = Url::parse("wss://relay.example.com").unwrap();
+
+        DbRelayViolation::record(pool, &url, ViolationKind::Flood, "Messages")
+            .await
+            .unwrap();
+        let again = DbRelayViolation::record(pool, &url, ViolationKind::Flood, "Channels")
+            .await
+            .unwrap();
+        assert_eq!(again.count, 2);
+        assert_eq!(again.subscription_id, "Channels");
+
+        DbRelayViolation::record(pool, &url, ViolationKind::Replay, "Messages")
+            .await
+            .unwrap();
+        assert_eq!(DbRelayViolation::fetch(pool).await.unwrap().len(), 2);
+
+        DbRelayViolation::delete_relay(pool, &url).await.unwrap();
+        assert!(DbRelayViolation::fetch(pool).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::{NaiveDateTime, Utc};
+use nostr::{EventId, Kind};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+use url::Url;
+
+use crate::utils::{event_hash_or_err, handle_decode_error, millis_to_naive_or_err};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("Serde JSON error: {0}")]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+/// Event signed with the user's keys, whether it was published or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningLogEntry {
+    pub id: i64,
+    pub event_hash: EventId,
+    pub kind: Kind,
+    /// Relay, url, event or public key the event points to
+    pub target: Option<String>,
+    /// Where it was meant to be sent, empty when it was kept locally
+    pub relays: Vec<Url>,
+    pub signed_at: NaiveDateTime,
+}
+
+impl SigningLogEntry {
+    /// Most recent first
+    pub async fn fetch(pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, Error> {
+        let sql = "SELECT * FROM signing_log ORDER BY signed_at DESC, id DESC LIMIT ?";
+        let entries = sqlx::query_as::<_, Self>(sql)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+        Ok(entries)
+    }
+
+    /// Latest entry of the event
+    pub async fn fetch_event(
+        pool: &SqlitePool,
+        event_hash: &EventId,
+    ) -> Result<Option<Self>, Error> {
+        let sql = "SELECT * FROM signing_log WHERE event_hash = ? ORDER BY id DESC LIMIT 1";
+        let entry = sqlx::query_as::<_, Self>(sql)
+            .bind(event_hash.to_hex())
+            .fetch_optional(pool)
+            .await?;
+        Ok(entry)
+    }
+
+    pub async fn count(pool: &SqlitePool) -> Result<i64, Error> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM signing_log")
+            .fetch_one(pool)
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn insert(
+        pool: &SqlitePool,
+        ns_event: &nostr::Event,
+        relays: &[Url],
+    ) -> Result<(), Error> {
+        let relays: Vec<&str> = relays.iter().map(Url::as_str).collect();
+        let sql = r#"
+            INSERT INTO signing_log (event_hash, kind, target, relays, signed_at)
+            VALUES (?, ?, ?, ?, ?)
+        "#;
+        sqlx::query(sql)
+            .bind(ns_event.id.to_hex())
+            .bind(ns_event.kind.as_u32())
+            .bind(signing_target(ns_event))
+            .bind(serde_json::to_string(&relays)?)
+            .bind(Utc::now().timestamp_millis())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Value of the first `relay`, `u`, `e` or `p` tag, in that order
+pub fn signing_target(ns_event: &nostr::Event) -> Option<String> {
+    TARGET_TAGS.iter().find_map(|name| {
+        ns_event
+            .tags
+            .iter()
+            .map(|tag| tag.as_vec())
+            .find(|values| values.first().map(String::as_str) == Some(*name))
+            .and_then(|values| values.get(1).cloned())
+    })
+}
+
+const TARGET_TAGS: [&str; 4] = ["relay", "u", "e", "p"];
+
+impl sqlx::FromRow<'_, SqliteRow> for SigningLogEntry {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let event_hash = row.try_get::<String, &str>("event_hash")?;
+        let relays = row.try_get::<String, &str>("relays")?;
+        let relays: Vec<String> =
+            serde_json::from_str(&relays).map_err(|e| handle_decode_error(e, "relays"))?;
+        Ok(Self {
+            id: row.try_get::<i64, &str>("id")?,
+            event_hash: event_hash_or_err(&event_hash, "event_hash")?,
+            kind: Kind::from(row.try_get::<u32, &str>("kind")? as u64),
+            target: row.try_get::<Option<String>, &str>("target")?,
+            relays: relays
+                .iter()
+                .filter_map(|relay| Url::parse(relay).ok())
+                .collect(),
+            signed_at: millis_to_naive_or_err(row.try_get::<i64, &str>("signed_at")?, "signed_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use nostr::{EventBuilder, Keys, Tag};
+
+    #[tokio::test]
+    async fn appends_entries_and_refuses_changes() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+
+        let keys = Keys::generate();
+        let contact = Keys::generate().public_key();
+        let ns_event = EventBuilder::new(Kind::TextNote, "hi", &[Tag::PubKey(contact, None)])
+            .to_event(&keys)
+            .unwrap();
+        let relay = Url::parse("wss://relay.example.com").unwrap();
+        SigningLogEntry::insert(pool, &ns_event, &[relay.clone()])
+            .await
+            .unwrap();
+
+        let entries = SigningLogEntry::fetch(pool, 10).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].event_hash, ns_event.id);
+        assert_eq!(entries[0].kind, Kind::TextNote);
+        assert_eq!(entries[0].target, Some(contact.to_string()));
+        assert_eq!(entries[0].relays, vec![relay]);
+
+        assert!(sqlx::query("DELETE FROM signing_log")
+            .execute(pool)
+            .await
+            .is_err());
+        assert!(sqlx::query("UPDATE signing_log SET kind = 0")
+            .execute(pool)
+            .await
+            .is_err());
+        assert_eq!(SigningLogEntry::count(pool).await.unwrap(), 1);
+    }
+}
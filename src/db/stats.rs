@@ -0,0 +1,182 @@
+use nostr::Kind;
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Account insights aggregated from the stored events
+#[derive(Debug, Clone, Default)]
+pub struct AccountStats {
+    /// Most stored kinds first
+    pub events_by_kind: Vec<(Kind, u64)>,
+    /// Relays that delivered the most stored events first
+    pub top_relays: Vec<(String, u64)>,
+    /// Last months with direct messages, oldest first
+    pub dm_volume: Vec<MonthlyDms>,
+    /// Bytes of stored events at the end of each month, oldest first
+    pub storage_growth: Vec<(String, u64)>,
+}
+impl AccountStats {
+    pub fn total_events(&self) -> u64 {
+        self.events_by_kind.iter().map(|(_, count)| count).sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyDms {
+    /// `YYYY-MM`
+    pub month: String,
+    pub sent: u64,
+    pub received: u64,
+}
+impl MonthlyDms {
+    pub fn total(&self) -> u64 {
+        self.sent + self.received
+    }
+}
+
+/// Aggregate queries over the whole database, may take a while on big accounts
+pub async fn compute_stats(pool: &SqlitePool) -> Result<AccountStats, Error> {
+    let sql = "SELECT kind, COUNT(*) FROM event GROUP BY kind ORDER BY COUNT(*) DESC";
+    let events_by_kind = sqlx::query_as::<_, (i64, i64)>(sql)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(kind, count)| (Kind::from(kind as u64), count as u64))
+        .collect();
+
+    let sql = r#"
+        SELECT relay_url, COUNT(*) FROM event
+        WHERE relay_url != ''
+        GROUP BY relay_url ORDER BY COUNT(*) DESC LIMIT ?
+    "#;
+    let top_relays = sqlx::query_as::<_, (String, i64)>(sql)
+        .bind(TOP_RELAYS)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(url, count)| (url, count as u64))
+        .collect();
+
+    let sql = r#"
+        SELECT strftime('%Y-%m', created_at / 1000, 'unixepoch') AS month,
+            SUM(is_users), SUM(1 - is_users)
+        FROM message GROUP BY month ORDER BY month DESC LIMIT ?
+    "#;
+    let mut dm_volume: Vec<_> = sqlx::query_as::<_, (String, i64, i64)>(sql)
+        .bind(MONTHS)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(month, sent, received)| MonthlyDms {
+            month,
+            sent: sent as u64,
+            received: received as u64,
+        })
+        .collect();
+    dm_volume.reverse();
+
+    let sql = r#"
+        SELECT strftime('%Y-%m', created_at / 1000, 'unixepoch') AS month,
+            SUM(LENGTH(content) + COALESCE(LENGTH(tags), 0) + LENGTH(sig))
+        FROM event GROUP BY month ORDER BY month ASC
+    "#;
+    let monthly_bytes = sqlx::query_as::<_, (String, i64)>(sql)
+        .fetch_all(pool)
+        .await?;
+    let storage_growth = cumulative(monthly_bytes);
+    let skip = storage_growth.len().saturating_sub(MONTHS as usize);
+
+    Ok(AccountStats {
+        events_by_kind,
+        top_relays,
+        dm_volume,
+        storage_growth: storage_growth.into_iter().skip(skip).collect(),
+    })
+}
+
+/// Running total of the monthly sizes
+fn cumulative(monthly: Vec<(String, i64)>) -> Vec<(String, u64)> {
+    monthly
+        .into_iter()
+        .scan(0, |total, (month, bytes)| {
+            *total += bytes.max(0) as u64;
+            Some((month, *total))
+        })
+        .collect()
+}
+
+const TOP_RELAYS: i64 = 10;
+const MONTHS: i64 = 12;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+
+    async fn insert_event(pool: &SqlitePool, id: i64, kind: u32, relay: &str, millis: i64) {
+        sqlx::query(
+            "INSERT INTO event (event_hash, pubkey, created_at, kind, content, sig, relay_url) VALUES (?, '', ?, ?, 'abcd', '', ?)",
+        )
+        .bind(id.to_string())
+        .bind(millis)
+        .bind(kind)
+        .bind(relay)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn aggregates_events_and_messages() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        // 2023-01-15 and 2023-02-15
+        let january = 1_673_740_800_000;
+        let february = 1_676_419_200_000;
+        insert_event(pool, 1, 4, "wss://a", january).await;
+        insert_event(pool, 2, 4, "wss://b", february).await;
+        insert_event(pool, 3, 0, "wss://a", february).await;
+        for (is_users, millis) in [(1, january), (0, january), (0, february)] {
+            sqlx::query(
+                "INSERT INTO message (content, chat_pubkey, is_users, created_at, status, relay_url) VALUES ('', '', ?, ?, 0, '')",
+            )
+            .bind(is_users)
+            .bind(millis)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+
+        let stats = compute_stats(pool).await.unwrap();
+        assert_eq!(
+            stats.events_by_kind,
+            vec![(Kind::EncryptedDirectMessage, 2), (Kind::Metadata, 1)]
+        );
+        assert_eq!(stats.total_events(), 3);
+        assert_eq!(stats.top_relays[0], ("wss://a".to_owned(), 2));
+        assert_eq!(
+            stats.dm_volume,
+            vec![
+                MonthlyDms {
+                    month: "2023-01".into(),
+                    sent: 1,
+                    received: 1
+                },
+                MonthlyDms {
+                    month: "2023-02".into(),
+                    sent: 0,
+                    received: 1
+                },
+            ]
+        );
+        assert_eq!(
+            stats.storage_growth,
+            vec![("2023-01".to_owned(), 4), ("2023-02".to_owned(), 8)]
+        );
+    }
+}
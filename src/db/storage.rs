@@ -0,0 +1,368 @@
+use nostr::EventId;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use thiserror::Error;
+
+use crate::config::StorageConfig;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// Bytes used by each kind of data
+#[derive(Debug, Clone, Default)]
+pub struct StorageUsage {
+    /// Raw events, including the ones that aren't messages
+    pub events: u64,
+    pub messages: u64,
+    pub channel_messages: u64,
+    /// Downloaded images on disk
+    pub images: u64,
+    /// Database file, free pages excluded
+    pub database: u64,
+}
+impl StorageUsage {
+    pub fn total(&self) -> u64 {
+        self.database + self.images
+    }
+}
+
+/// What a pruning run removed
+#[derive(Debug, Clone, Default)]
+pub struct PruneReport {
+    pub channel_messages_removed: u64,
+    pub messages_removed: u64,
+}
+impl PruneReport {
+    pub fn removed(&self) -> u64 {
+        self.channel_messages_removed + self.messages_removed
+    }
+}
+
+pub async fn fetch_usage(
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+) -> Result<StorageUsage, Error> {
+    let sql = "SELECT COALESCE(SUM(LENGTH(content) + COALESCE(LENGTH(tags), 0) + LENGTH(sig)), 0) FROM event";
+    let events: i64 = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    let sql = "SELECT COALESCE(SUM(LENGTH(content)), 0) FROM message";
+    let messages: i64 = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    let sql = "SELECT COALESCE(SUM(LENGTH(content)), 0) FROM channel_message";
+    let channel_messages: i64 = sqlx::query_scalar(sql).fetch_one(pool).await?;
+
+    let paths: Vec<String> = sqlx::query_scalar("SELECT path FROM image_cache")
+        .fetch_all(cache_pool)
+        .await?;
+    let mut images = 0;
+    for path in paths {
+        if let Ok(metadata) = tokio::fs::metadata(&path).await {
+            images += metadata.len();
+        }
+    }
+
+    Ok(StorageUsage {
+        events: events as u64,
+        messages: messages as u64,
+        channel_messages: channel_messages as u64,
+        images,
+        database: used_size(pool).await?,
+    })
+}
+
+/// Deletes the oldest messages until the limits are met, then compacts the database.
+/// Direct messages are only touched by the size limit and when `prune_dms` is set.
+pub async fn prune(pool: &SqlitePool, config: &StorageConfig) -> Result<PruneReport, Error> {
+    let mut report = PruneReport::default();
+
+    if let Some(max) = config.max_channel_messages {
+        report.channel_messages_removed += prune_channels_over(pool, max).await?;
+    }
+
+    if let Some(max_bytes) = config.max_db_size_bytes() {
+        while used_size(pool).await? > max_bytes {
+            let removed = prune_oldest_channel_messages(pool, PRUNE_BATCH).await?;
+            report.channel_messages_removed += removed;
+            if removed > 0 {
+                continue;
+            }
+            if !config.prune_dms {
+                tracing::warn!("Database over the size limit with only direct messages left");
+                break;
+            }
+            let removed = prune_oldest_messages(pool, PRUNE_BATCH).await?;
+            report.messages_removed += removed;
+            if removed == 0 {
+                break;
+            }
+        }
+    }
+
+    if report.removed() > 0 {
+        tracing::info!("Pruned {} messages, compacting", report.removed());
+        sqlx::query("VACUUM").execute(pool).await?;
+    }
+
+    Ok(report)
+}
+
+/// Keeps the newest `max` messages of every channel
+async fn prune_channels_over(pool: &SqlitePool, max: u32) -> Result<u64, Error> {
+    let over_limit = r#"
+        SELECT event_id FROM (
+            SELECT event_id, ROW_NUMBER() OVER (
+                PARTITION BY channel_id ORDER BY created_at DESC
+            ) AS position
+            FROM channel_message
+        ) WHERE position > ?
+    "#;
+    delete_channel_messages(pool, over_limit, max as i64).await
+}
+
+async fn prune_oldest_channel_messages(pool: &SqlitePool, limit: i64) -> Result<u64, Error> {
+    let oldest = "SELECT event_id FROM channel_message ORDER BY created_at ASC LIMIT ?";
+    delete_channel_messages(pool, oldest, limit).await
+}
+
+/// `select_ids` takes a single integer parameter
+async fn delete_channel_messages(
+    pool: &SqlitePool,
+    select_ids: &str,
+    param: i64,
+) -> Result<u64, Error> {
+    let mut tx = pool.begin().await?;
+    let sql = format!("CREATE TEMP TABLE {} AS {}", SELECTED_IDS, select_ids);
+    sqlx::query(&sql).bind(param).execute(&mut tx).await?;
+    let removed = delete_selected(&mut tx).await?;
+    tx.commit().await?;
+    Ok(removed)
+}
+
+async fn prune_oldest_messages(pool: &SqlitePool, limit: i64) -> Result<u64, Error> {
+    let oldest = "SELECT event_id FROM message ORDER BY created_at ASC LIMIT ?";
+    let mut tx = pool.begin().await?;
+    let sql = format!("CREATE TEMP TABLE {} AS {}", SELECTED_IDS, oldest);
+    sqlx::query(&sql).bind(limit).execute(&mut tx).await?;
+    let removed = delete_selected(&mut tx).await?;
+    tx.commit().await?;
+    Ok(removed)
+}
+
+/// Reactions stored for the event, they go with it when it's deleted
+pub async fn count_reactions(pool: &SqlitePool, event_hash: &EventId) -> Result<i64, Error> {
+    let sql = format!("SELECT COUNT(*) FROM event WHERE {}", REACTION_TO);
+    let count = sqlx::query_scalar(&sql)
+        .bind(event_hash.to_hex())
+        .fetch_one(pool)
+        .await?;
+    Ok(count)
+}
+
+/// Deletes the event, its reactions and every row derived from them,
+/// returns how many events were removed
+pub async fn delete_event(pool: &SqlitePool, event_hash: &EventId) -> Result<u64, Error> {
+    let hash = event_hash.to_hex();
+    let event_ids = format!(
+        "SELECT event_id FROM event WHERE event_hash = ? OR {}",
+        REACTION_TO
+    );
+    let mut tx = pool.begin().await?;
+    let sql = format!("CREATE TEMP TABLE {} AS {}", SELECTED_IDS, event_ids);
+    sqlx::query(&sql)
+        .bind(&hash)
+        .bind(&hash)
+        .execute(&mut tx)
+        .await?;
+    let removed = delete_selected(&mut tx).await?;
+    tx.commit().await?;
+    Ok(removed)
+}
+
+/// Deletes the events with the ids in `SELECTED_IDS` and every row made
+/// from them, then drops the table. Returns how many ids were selected
+async fn delete_selected(tx: &mut Transaction<'_, Sqlite>) -> Result<u64, Error> {
+    let selected: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", SELECTED_IDS))
+        .fetch_one(&mut *tx)
+        .await?;
+    let event_ids = format!("SELECT event_id FROM {}", SELECTED_IDS);
+    let hashes = format!(
+        "SELECT event_hash FROM event WHERE event_id IN ({})",
+        event_ids
+    );
+    for (table, column) in HASH_DERIVED_TABLES {
+        let sql = format!("DELETE FROM {} WHERE {} IN ({})", table, column, hashes);
+        sqlx::query(&sql).execute(&mut *tx).await?;
+    }
+    for table in DERIVED_TABLES.iter().chain(["event"].iter()) {
+        let sql = format!("DELETE FROM {} WHERE event_id IN ({})", table, event_ids);
+        sqlx::query(&sql).execute(&mut *tx).await?;
+    }
+    sqlx::query(&format!("DROP TABLE {}", SELECTED_IDS))
+        .execute(&mut *tx)
+        .await?;
+    Ok(selected as u64)
+}
+
+/// Pages in use, deleted rows stop counting before the database is compacted
+async fn used_size(pool: &SqlitePool) -> Result<u64, Error> {
+    let page_count: i64 = sqlx::query_scalar("PRAGMA page_count;")
+        .fetch_one(pool)
+        .await?;
+    let freelist_count: i64 = sqlx::query_scalar("PRAGMA freelist_count;")
+        .fetch_one(pool)
+        .await?;
+    let page_size: i64 = sqlx::query_scalar("PRAGMA page_size;")
+        .fetch_one(pool)
+        .await?;
+    Ok(((page_count - freelist_count) * page_size) as u64)
+}
+
+const PRUNE_BATCH: i64 = 500;
+
+/// Kind 7 events tagging the event hash bound once
+const REACTION_TO: &str = r#"kind = 7 AND EXISTS (
+    SELECT 1 FROM json_each(event.tags)
+    WHERE json_extract(value, '$[0]') = 'e' AND json_extract(value, '$[1]') = ?
+)"#;
+
+/// Ids of the events being deleted, kept while the tables they're read
+/// from are deleted from
+const SELECTED_IDS: &str = "temp.deleted_event_ids";
+
+/// Rows made from an event, keyed by its `event_id`
+const DERIVED_TABLES: [&str; 5] = [
+    "message",
+    "channel_message",
+    "channel_mention",
+    "relay_response",
+    "attachment_download",
+];
+
+/// Rows made from an event or pointing to it, keyed by its hash
+const HASH_DERIVED_TABLES: [(&str, &str); 5] = [
+    ("poll_vote", "poll_hash"),
+    ("poll", "poll_hash"),
+    ("channel_moderation", "event_hash"),
+    ("reaction", "target_hash"),
+    ("reaction", "reaction_hash"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+
+    async fn db_with_channel_messages(count: i64) -> TestDatabase {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        for id in 0..count {
+            for channel in ["a", "b"] {
+                sqlx::query(
+                    "INSERT INTO channel_message (channel_id, author, is_users, created_at, relay_url, content) VALUES (?, '', 0, ?, '', '')",
+                )
+                .bind(channel)
+                .bind(id)
+                .execute(pool)
+                .await
+                .unwrap();
+            }
+        }
+        db
+    }
+
+    #[tokio::test]
+    async fn keeps_newest_per_channel() {
+        let db = db_with_channel_messages(5).await;
+        let pool = db.pool();
+        let removed = prune_channels_over(pool, 2).await.unwrap();
+        assert_eq!(removed, 6);
+
+        let oldest: Vec<i64> =
+            sqlx::query_scalar("SELECT MIN(created_at) FROM channel_message GROUP BY channel_id")
+                .fetch_all(pool)
+                .await
+                .unwrap();
+        assert_eq!(oldest, vec![3, 3]);
+    }
+
+    #[tokio::test]
+    async fn pruning_removes_derived_rows() {
+        let db = db_with_channel_messages(5).await;
+        let pool = db.pool();
+        sqlx::query(
+            "INSERT INTO relay_response (event_id, event_hash, relay_url, status) SELECT event_id, event_id, '', 0 FROM channel_message",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        assert_eq!(prune_channels_over(pool, 2).await.unwrap(), 6);
+        let responses: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM relay_response")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(responses, 4);
+    }
+
+    #[tokio::test]
+    async fn no_limits_prunes_nothing() {
+        let db = db_with_channel_messages(3).await;
+        let report = prune(db.pool(), &StorageConfig::default()).await.unwrap();
+        assert_eq!(report.removed(), 0);
+    }
+
+    #[tokio::test]
+    async fn deletes_event_with_reactions_only() {
+        let db = db_with_channel_messages(0).await;
+        let pool = db.pool();
+        let spam = EventId::from_slice(&[1; 32]).unwrap();
+        let other = EventId::from_slice(&[2; 32]).unwrap();
+        let events = [
+            (1, spam.to_hex(), 4, "[]".to_owned()),
+            (
+                2,
+                "b".repeat(64),
+                7,
+                format!(r#"[["e","{}"]]"#, spam.to_hex()),
+            ),
+            (
+                3,
+                "c".repeat(64),
+                7,
+                format!(r#"[["e","{}"]]"#, other.to_hex()),
+            ),
+        ];
+        for (event_id, hash, kind, tags) in events {
+            sqlx::query(
+                "INSERT INTO event (event_id, event_hash, pubkey, created_at, kind, content, tags, sig, relay_url) VALUES (?, ?, '', 0, ?, '', ?, '', '')",
+            )
+            .bind(event_id)
+            .bind(hash)
+            .bind(kind)
+            .bind(tags)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO message (event_id, content, chat_pubkey, is_users, created_at, status, relay_url) VALUES (1, '', '', 0, 0, 0, '')",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        assert_eq!(count_reactions(pool, &spam).await.unwrap(), 1);
+        assert_eq!(delete_event(pool, &spam).await.unwrap(), 2);
+
+        let left: Vec<i64> = sqlx::query_scalar("SELECT event_id FROM event")
+            .fetch_all(pool)
+            .await
+            .unwrap();
+        assert_eq!(left, vec![3]);
+        let messages: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM message")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        assert_eq!(messages, 0);
+    }
+}
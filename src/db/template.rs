@@ -0,0 +1,169 @@
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+
+    #[error("Template name can't be empty")]
+    EmptyName,
+}
+
+/// Reusable message snippet.
+///
+/// `{name}` is replaced by the contact's name and
+/// `{cursor}` marks where the cursor goes after insertion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbTemplate {
+    pub id: i64,
+    pub name: String,
+    pub content: String,
+}
+
+impl DbTemplate {
+    const FETCH_QUERY: &'static str = "SELECT * FROM template";
+
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<DbTemplate>, Error> {
+        let sql = format!("{} ORDER BY name", Self::FETCH_QUERY);
+        let output = sqlx::query_as::<_, DbTemplate>(&sql)
+            .fetch_all(pool)
+            .await?;
+        Ok(output)
+    }
+
+    pub async fn fetch_by_name(pool: &SqlitePool, name: &str) -> Result<Option<DbTemplate>, Error> {
+        let sql = format!("{} WHERE name = ?", Self::FETCH_QUERY);
+        Ok(sqlx::query_as::<_, DbTemplate>(&sql)
+            .bind(name)
+            .fetch_optional(pool)
+            .await?)
+    }
+
+    pub async fn insert(pool: &SqlitePool, name: &str, content: &str) -> Result<DbTemplate, Error> {
+        let name = normalize_name(name)?;
+        let sql = "INSERT INTO template (name, content) VALUES (?, ?)";
+        sqlx::query(sql)
+            .bind(&name)
+            .bind(content)
+            .execute(pool)
+            .await?;
+        let template = Self::fetch_by_name(pool, &name)
+            .await?
+            .ok_or(Error::TemplateNotFound(name))?;
+        Ok(template)
+    }
+
+    pub async fn update(pool: &SqlitePool, template: &DbTemplate) -> Result<(), Error> {
+        let sql = "UPDATE template SET name=?, content=? WHERE id=?";
+        sqlx::query(sql)
+            .bind(normalize_name(&template.name)?)
+            .bind(&template.content)
+            .bind(template.id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, template: &DbTemplate) -> Result<(), Error> {
+        let sql = "DELETE FROM template WHERE id=?";
+        sqlx::query(sql).bind(template.id).execute(pool).await?;
+        Ok(())
+    }
+
+    /// Placeholders that will be replaced on insertion
+    pub fn placeholders(&self) -> Vec<&str> {
+        let mut found = vec![];
+        let mut rest = self.content.as_str();
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let placeholder = &rest[start + 1..start + len];
+            if !placeholder.is_empty() && !found.contains(&placeholder) {
+                found.push(placeholder);
+            }
+            rest = &rest[start + len + 1..];
+        }
+        found
+    }
+
+    /// Text to insert and the cursor position in chars
+    pub fn expand(&self, contact_name: &str) -> (String, usize) {
+        let content = self.content.replace(NAME_PLACEHOLDER, contact_name);
+        match content.find(CURSOR_PLACEHOLDER) {
+            Some(idx) => {
+                let cursor = content[..idx].chars().count();
+                (content.replacen(CURSOR_PLACEHOLDER, "", 1), cursor)
+            }
+            None => {
+                let cursor = content.chars().count();
+                (content, cursor)
+            }
+        }
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbTemplate {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(DbTemplate {
+            id: row.try_get::<i64, &str>("id")?,
+            name: row.try_get::<String, &str>("name")?,
+            content: row.try_get::<String, &str>("content")?,
+        })
+    }
+}
+
+/// Names are used after `/` in the chat input, so no spaces
+fn normalize_name(name: &str) -> Result<String, Error> {
+    let name = name.trim().trim_start_matches('/').replace(' ', "-");
+    if name.is_empty() {
+        return Err(Error::EmptyName);
+    }
+    Ok(name.to_lowercase())
+}
+
+pub const NAME_PLACEHOLDER: &str = "{name}";
+pub const CURSOR_PLACEHOLDER: &str = "{cursor}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(content: &str) -> DbTemplate {
+        DbTemplate {
+            id: 1,
+            name: "hello".into(),
+            content: content.into(),
+        }
+    }
+
+    #[test]
+    fn expands_name_and_places_cursor() {
+        let (text, cursor) = template("Hi {name}, {cursor} thanks!").expand("Alice");
+        assert_eq!(text, "Hi Alice,  thanks!");
+        assert_eq!(cursor, "Hi Alice, ".chars().count());
+    }
+
+    #[test]
+    fn cursor_defaults_to_end() {
+        let (text, cursor) = template("Olá {name}").expand("João");
+        assert_eq!(text, "Olá João");
+        assert_eq!(cursor, 8);
+    }
+
+    #[test]
+    fn lists_placeholders_once() {
+        let t = template("{name} {cursor} {name} {}");
+        assert_eq!(t.placeholders(), vec!["name", "cursor"]);
+    }
+
+    #[test]
+    fn normalizes_names() {
+        assert_eq!(normalize_name(" /Thank You ").unwrap(), "thank-you");
+        assert!(normalize_name("  ").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::EventId;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::event_hash_or_err;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    SqlxError(#[from] sqlx::Error),
+}
+
+/// Translated message content, stored in the cache database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbTranslation {
+    pub event_hash: EventId,
+    pub target_lang: String,
+    pub source_lang: Option<String>,
+    pub content: String,
+}
+
+impl DbTranslation {
+    pub async fn fetch(
+        cache_pool: &SqlitePool,
+        event_hash: &EventId,
+        target_lang: &str,
+    ) -> Result<Option<Self>, Error> {
+        let sql = "SELECT * FROM translation_cache WHERE event_hash = ? AND target_lang = ?;";
+        let translation = sqlx::query_as::<_, Self>(sql)
+            .bind(event_hash.to_string())
+            .bind(target_lang)
+            .fetch_optional(cache_pool)
+            .await?;
+        Ok(translation)
+    }
+
+    pub async fn insert(cache_pool: &SqlitePool, translation: &DbTranslation) -> Result<(), Error> {
+        let sql = r#"
+            INSERT OR REPLACE INTO translation_cache
+                (event_hash, target_lang, source_lang, content)
+            VALUES (?, ?, ?, ?)
+        "#;
+        sqlx::query(sql)
+            .bind(translation.event_hash.to_string())
+            .bind(&translation.target_lang)
+            .bind(&translation.source_lang)
+            .bind(&translation.content)
+            .execute(cache_pool)
+            .await?;
+        Ok(())
+    }
+}
+
+impl sqlx::FromRow<'_, SqliteRow> for DbTranslation {
+    fn from_row(row: &'_ SqliteRow) -> Result<Self, sqlx::Error> {
+        let event_hash = row.try_get::<String, &str>("event_hash")?;
+        Ok(Self {
+            event_hash: event_hash_or_err(&event_hash, "event_hash")?,
+            target_lang: row.try_get::<String, &str>("target_lang")?,
+            source_lang: row.try_get::<Option<String>, &str>("source_lang")?,
+            content: row.try_get::<String, &str>("content")?,
+        })
+    }
+}
+
+/// Conversations translated as messages arrive
+pub struct AutoTranslate;
+
+impl AutoTranslate {
+    pub async fn is_enabled(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+    ) -> Result<bool, Error> {
+        let sql = "SELECT chat_pubkey FROM auto_translate WHERE chat_pubkey = ?;";
+        let found = sqlx::query_scalar::<_, String>(sql)
+            .bind(chat_pubkey.to_string())
+            .fetch_optional(pool)
+            .await?;
+        Ok(found.is_some())
+    }
+
+    pub async fn set(
+        pool: &SqlitePool,
+        chat_pubkey: &XOnlyPublicKey,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        let sql = if enabled {
+            "INSERT OR IGNORE INTO auto_translate (chat_pubkey) VALUES (?);"
+        } else {
+            "DELETE FROM auto_translate WHERE chat_pubkey = ?;"
+        };
+        sqlx::query(sql)
+            .bind(chat_pubkey.to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+use chrono::{Duration, NaiveDateTime};
+use nostr::secp256k1::XOnlyPublicKey;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use thiserror::Error;
+
+use crate::utils::{millis_to_naive_or_err, public_key_or_err};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashKind {
+    Contact,
+    Conversation,
+}
+
+/// Deleted contact or cleared conversation, kept for `RETENTION_DAYS`
+#[derive(Debug, Clone)]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub pubkey: XOnlyPublicKey,
+    /// Petname of a contact, the backend fills in the profile name when missing
+    pub name: Option<String>,
+    /// Messages of a conversation, 0 for contacts
+    pub messages: i64,
+    pub deleted_at: NaiveDateTime,
+}
+
+impl TrashItem {
+    pub fn expires_at(&self) -> NaiveDateTime {
+        self.deleted_at + Duration::days(RETENTION_DAYS)
+    }
+}
+
+/// Rows flagged with `deleted_at` in the contact and message tables
+pub struct Trash;
+
+impl Trash {
+    /// Newest deletions first
+    pub async fn fetch(pool: &SqlitePool) -> Result<Vec<TrashItem>, Error> {
+        let sql = r#"
+            SELECT pubkey, petname, 0 AS messages, deleted_at
+            FROM contact
+            WHERE deleted_at IS NOT NULL
+        "#;
+        let mut items: Vec<TrashItem> = sqlx::query(sql)
+            .try_map(|row: SqliteRow| trash_item(TrashKind::Contact, &row, "pubkey"))
+            .fetch_all(pool)
+            .await?;
+
+        let sql = r#"
+            SELECT chat_pubkey, NULL AS petname, COUNT(*) AS messages, MAX(deleted_at) AS deleted_at
+            FROM message
+            WHERE deleted_at IS NOT NULL
+            GROUP BY chat_pubkey
+        "#;
+        let conversations = sqlx::query(sql)
+            .try_map(|row: SqliteRow| trash_item(TrashKind::Conversation, &row, "chat_pubkey"))
+            .fetch_all(pool)
+            .await?;
+
+        items.extend(conversations);
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(items)
+    }
+
+    pub async fn restore(
+        pool: &SqlitePool,
+        kind: TrashKind,
+        pubkey: &XOnlyPublicKey,
+    ) -> Result<(), Error> {
+        let sql = match kind {
+            TrashKind::Contact => {
+                "UPDATE contact SET deleted_at = NULL WHERE pubkey = ? AND deleted_at IS NOT NULL;"
+            }
+            TrashKind::Conversation => {
+                "UPDATE message SET deleted_at = NULL WHERE chat_pubkey = ? AND deleted_at IS NOT NULL;"
+            }
+        };
+        sqlx::query(sql)
+            .bind(pubkey.to_string())
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the item for good. The events of a conversation go with its messages,
+    /// otherwise the integrity check would restore them
+    pub async fn purge(
+        pool: &SqlitePool,
+        kind: TrashKind,
+        pubkey: &XOnlyPublicKey,
+        purged_at: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let purged_at = purged_at.timestamp_millis();
+        let mut tx = pool.begin().await?;
+        match kind {
+            TrashKind::Contact => {
+                sqlx::query("DELETE FROM contact WHERE pubkey = ? AND deleted_at IS NOT NULL;")
+                    .bind(pubkey.to_string())
+                    .execute(&mut tx)
+                    .await?;
+            }
+            TrashKind::Conversation => {
+                let trashed =
+                    "SELECT event_id FROM message WHERE chat_pubkey = ? AND deleted_at IS NOT NULL";
+                sqlx::query(&format!("{} ({});", RECORD_PURGED, trashed))
+                    .bind(purged_at)
+                    .bind(pubkey.to_string())
+                    .execute(&mut tx)
+                    .await?;
+                sqlx::query(&format!(
+                    "DELETE FROM event WHERE event_id IN ({});",
+                    trashed
+                ))
+                .bind(pubkey.to_string())
+                .execute(&mut tx)
+                .await?;
+                sqlx::query(
+                    "DELETE FROM message WHERE chat_pubkey = ? AND deleted_at IS NOT NULL;",
+                )
+                .bind(pubkey.to_string())
+                .execute(&mut tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Purges what was deleted more than `RETENTION_DAYS` before `now`,
+    /// returns how many contacts and messages were removed
+    pub async fn purge_expired(pool: &SqlitePool, now: NaiveDateTime) -> Result<u64, Error> {
+        let cutoff = (now - Duration::days(RETENTION_DAYS)).timestamp_millis();
+        let mut tx = pool.begin().await?;
+        let contacts = sqlx::query("DELETE FROM contact WHERE deleted_at < ?;")
+            .bind(cutoff)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        let expired = "SELECT event_id FROM message WHERE deleted_at < ?";
+        sqlx::query(&format!("{} ({});", RECORD_PURGED, expired))
+            .bind(now.timestamp_millis())
+            .bind(cutoff)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query(&format!(
+            "DELETE FROM event WHERE event_id IN ({});",
+            expired
+        ))
+        .bind(cutoff)
+        .execute(&mut tx)
+        .await?;
+        let messages = sqlx::query("DELETE FROM message WHERE deleted_at < ?;")
+            .bind(cutoff)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(contacts + messages)
+    }
+}
+
+/// Keeps the hashes of the events about to be purged, followed by the
+/// query of their ids, see `DbEvent::insert`
+const RECORD_PURGED: &str = r#"
+    INSERT OR IGNORE INTO purged_event (event_hash, purged_at)
+    SELECT event_hash, ? FROM event WHERE event_id IN
+"#;
+
+fn trash_item(
+    kind: TrashKind,
+    row: &SqliteRow,
+    pubkey_col: &str,
+) -> Result<TrashItem, sqlx::Error> {
+    let pubkey: String = row.try_get(pubkey_col)?;
+    let deleted_at: i64 = row.try_get("deleted_at")?;
+    Ok(TrashItem {
+        kind,
+        pubkey: public_key_or_err(&pubkey, pubkey_col)?,
+        name: row.try_get("petname")?,
+        messages: row.try_get("messages")?,
+        deleted_at: millis_to_naive_or_err(deleted_at, "deleted_at")?,
+    })
+}
+
+pub const RETENTION_DAYS: i64 = 30;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DbEvent, TestDatabase};
+    use nostr::{EventBuilder, Keys, Url};
+
+    #[tokio::test]
+    async fn restores_and_expires_trashed_items() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let contact = Keys::generate().public_key();
+        let chat = Keys::generate().public_key();
+        let now = NaiveDateTime::from_timestamp_opt(1_700_000_000, 0).unwrap();
+        let old = now - Duration::days(RETENTION_DAYS + 1);
+
+        sqlx::query(
+            "INSERT INTO contact (pubkey, petname, created_at, updated_at, deleted_at) VALUES (?, 'Bob', 0, 0, ?)",
+        )
+        .bind(contact.to_string())
+        .bind(now.timestamp_millis())
+        .execute(pool)
+        .await
+        .unwrap();
+        for event_id in [1, 2] {
+            sqlx::query(
+                "INSERT INTO message (event_id, content, chat_pubkey, is_users, created_at, status, relay_url, deleted_at) VALUES (?, '', ?, 0, 0, 2, '', ?)",
+            )
+            .bind(event_id)
+            .bind(chat.to_string())
+            .bind(old.timestamp_millis())
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+
+        let items = Trash::fetch(pool).await.unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].kind, TrashKind::Contact);
+        assert_eq!(items[0].name.as_deref(), Some("Bob"));
+        assert_eq!(items[1].messages, 2);
+
+        assert_eq!(Trash::purge_expired(pool, now).await.unwrap(), 2);
+        Trash::restore(pool, TrashKind::Contact, &contact)
+            .await
+            .unwrap();
+        assert!(Trash::fetch(pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn purged_events_are_not_stored_again() {
+        let db = TestDatabase::new().await.unwrap();
+        let pool = db.pool();
+        let url = Url::parse("wss://relay.example.com").unwrap();
+        let chat = Keys::generate();
+        let now = NaiveDateTime::from_timestamp_opt(1_700_000_000, 0).unwrap();
+
+        let note = EventBuilder::new_text_note("hello", &[])
+            .to_event(&chat)
+            .unwrap();
+        let db_event = DbEvent::insert(pool, &url, &note).await.unwrap().unwrap();
+        sqlx::query(
+            "INSERT INTO message (event_id, content, chat_pubkey, is_users, created_at, status, relay_url, deleted_at) VALUES (?, '', ?, 0, 0, 2, '', ?)",
+        )
+        .bind(db_event.event_id)
+        .bind(chat.public_key().to_string())
+        .bind(now.timestamp_millis())
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Trash::purge(pool, TrashKind::Conversation, &chat.public_key(), now)
+            .await
+            .unwrap();
+        assert!(DbEvent::fetch_hash(pool, &note.id).await.unwrap().is_none());
+        assert!(DbEvent::insert(pool, &url, &note).await.unwrap().is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     net::ntp::{correct_time_with_offset, system_now_microseconds, system_time_to_naive_utc},
-    utils::url_or_err,
+    utils::{millis_to_naive_or_err, url_or_err},
 };
 
 use chrono::NaiveDateTime;
@@ -57,14 +57,9 @@ impl UserConfig {
         Ok(has_logged_in != 0)
     }
 
-    pub(crate) async fn update_ntp_offset(pool: &SqlitePool, ntp_time: u64) -> Result<i64, Error> {
+    pub(crate) async fn update_ntp_offset(pool: &SqlitePool, offset: i64) -> Result<i64, Error> {
         tracing::debug!("update_ntp_offset");
 
-        let system_total_microseconds =
-            system_now_microseconds().map_err(|_| Error::SystemTimeBeforeUnixEpoch)?;
-
-        let offset = ntp_time as i64 - system_total_microseconds as i64;
-
         let query = "UPDATE user_config SET ntp_offset = ?1 WHERE id = 1;";
 
         sqlx::query(query).bind(offset).execute(pool).await?;
@@ -90,6 +85,44 @@ impl UserConfig {
 
         Ok(corrected_time)
     }
+    pub(crate) async fn set_dm_sync_since(
+        pool: &SqlitePool,
+        since: Option<NaiveDateTime>,
+    ) -> Result<(), Error> {
+        let query = "UPDATE user_config SET dm_sync_since = ? WHERE id = 1;";
+        sqlx::query(query)
+            .bind(since.map(|since| since.timestamp_millis()))
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+    /// Oldest DM synced at login, `None` for all of them
+    pub(crate) async fn get_dm_sync_since(
+        pool: &SqlitePool,
+    ) -> Result<Option<NaiveDateTime>, Error> {
+        let query = "SELECT dm_sync_since FROM user_config WHERE id = 1;";
+        let millis: Option<i64> = sqlx::query_scalar(query).fetch_one(pool).await?;
+        let since = millis
+            .map(|millis| millis_to_naive_or_err(millis, "dm_sync_since"))
+            .transpose()?;
+        Ok(since)
+    }
+    pub(crate) async fn set_active_relay_profile(
+        pool: &SqlitePool,
+        name: Option<&str>,
+    ) -> Result<(), Error> {
+        let query = "UPDATE user_config SET active_relay_profile = ? WHERE id = 1;";
+        sqlx::query(query).bind(name).execute(pool).await?;
+        Ok(())
+    }
+    /// Relay profile switched to last, `None` when the relays were never switched
+    pub(crate) async fn get_active_relay_profile(
+        pool: &SqlitePool,
+    ) -> Result<Option<String>, Error> {
+        let query = "SELECT active_relay_profile FROM user_config WHERE id = 1;";
+        let name: Option<String> = sqlx::query_scalar(query).fetch_one(pool).await?;
+        Ok(name)
+    }
     pub(crate) async fn get_ntp_offset(pool: &SqlitePool) -> Result<i64, Error> {
         let query = "SELECT ntp_offset FROM user_config WHERE id = 1;";
         let offset: i64 = sqlx::query_scalar(query).fetch_one(pool).await?;
@@ -47,6 +47,15 @@ pub enum Error {
     #[error("{0}")]
     FromChannelCache(#[from] crate::db::channel_cache::Error),
 
+    #[error("{0}")]
+    FromChannelMention(#[from] crate::db::channel_mention::Error),
+
+    #[error("{0}")]
+    FromChannelKeyword(#[from] crate::db::channel_keyword::Error),
+
+    #[error("{0}")]
+    FromChannelModeration(#[from] crate::db::channel_moderation::Error),
+
     #[error("{0}")]
     FromContact(#[from] crate::db::contact::Error),
 
@@ -56,18 +65,111 @@ pub enum Error {
     #[error("{0}")]
     FromEvent(#[from] crate::db::event::Error),
 
+    #[error("{0}")]
+    FromFollowedHashtag(#[from] crate::db::followed_hashtag::Error),
+
+    #[error("{0}")]
+    FromFollower(#[from] crate::db::follower::Error),
+
+    #[error("{0}")]
+    FromLiveActivity(#[from] crate::db::live_activity::Error),
+
     #[error("{0}")]
     FromMessage(#[from] crate::db::message::Error),
 
+    #[error("{0}")]
+    FromPoll(#[from] crate::db::poll::Error),
+
+    #[error("{0}")]
+    FromReaction(#[from] crate::db::reaction::Error),
+
+    #[error("{0}")]
+    FromEventDeletion(#[from] crate::db::event_deletion::Error),
+
     #[error("{0}")]
     FromProfileCache(#[from] crate::db::profile_cache::Error),
 
     #[error("{0}")]
     FromRelay(#[from] crate::db::relay::Error),
 
+    #[error("{0}")]
+    FromRelayProfile(#[from] crate::db::relay_profile::Error),
+
     #[error("{0}")]
     FromRelayResponse(#[from] crate::db::relay_response::Error),
 
+    #[error("{0}")]
+    FromTemplate(#[from] crate::db::template::Error),
+
+    #[error("{0}")]
+    FromLabel(#[from] crate::db::label::Error),
+
+    #[error("{0}")]
+    FromConversationVisit(#[from] crate::db::conversation_visit::Error),
+
+    #[error("{0}")]
+    FromDraft(#[from] crate::db::draft::Error),
+
+    #[error("{0}")]
+    FromBlocked(#[from] crate::db::blocked::Error),
+
+    #[error("{0}")]
+    FromTrash(#[from] crate::db::trash::Error),
+
+    #[error("{0}")]
+    FromRelayActivity(#[from] crate::db::relay_activity::Error),
+
+    #[error("{0}")]
+    FromRelayViolation(#[from] crate::db::relay_violation::Error),
+
+    #[error("{0}")]
+    FromRelayDocumentChange(#[from] crate::db::relay_document_change::Error),
+
+    #[error("{0}")]
+    FromSigningLog(#[from] crate::db::signing_log::Error),
+
+    #[error("{0}")]
+    FromDmHistory(#[from] crate::db::dm_history::Error),
+
+    #[error("{0}")]
+    FromContactChannels(#[from] crate::db::contact_channels::Error),
+
+    #[error("{0}")]
+    FromContactMerge(#[from] crate::db::contact_merge::Error),
+
+    #[error("{0}")]
+    FromLightningCheck(#[from] crate::db::lightning_check::Error),
+
+    #[error("{0}")]
+    FromIntegrity(#[from] crate::db::integrity::Error),
+
+    #[error("{0}")]
+    FromStorage(#[from] crate::db::storage::Error),
+
+    #[error("{0}")]
+    FromStats(#[from] crate::db::stats::Error),
+
+    #[error("{0}")]
+    FromTranslation(#[from] crate::db::translation::Error),
+
+    #[error("{0}")]
+    FromAttachmentDownload(#[from] crate::db::attachment_download::Error),
+
+    #[error("{0}")]
+    FromDownloads(#[from] crate::net::downloads::Error),
+
+    #[error("{0}")]
+    FromTranslationClient(#[from] crate::net::translation::Error),
+
+    #[error("{0}")]
+    FromImageSanitize(#[from] crate::net::image_sanitize::Error),
+
+    #[error("{0}")]
+    FromNip96(#[from] crate::net::nip96::Error),
+
+    #[error("{0}")]
+    FromUploads(#[from] crate::net::uploads::Error),
+
     #[error("{0}")]
     FromUserConfig(#[from] crate::db::user_config::Error),
 
@@ -83,6 +185,9 @@ pub enum Error {
     #[error("Not allowed to update to own pubkey as a contact")]
     SameContactUpdate,
 
+    #[error("Command can't be sent as a request: {0}")]
+    UncorrelatedCommand(String),
+
     #[error("{0}")]
     FromUrlParse(#[from] url::ParseError),
 
@@ -96,6 +201,23 @@ pub enum Error {
     UnexpectedEventKind(u32),
 }
 
+impl Error {
+    /// The backend can't go on without rebuilding its state
+    pub fn is_fatal(&self) -> bool {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(self);
+        while let Some(error) = source {
+            if let Some(sqlx_error) = error.downcast_ref::<sqlx::Error>() {
+                return matches!(
+                    sqlx_error,
+                    sqlx::Error::PoolClosed | sqlx::Error::PoolTimedOut | sqlx::Error::Io(_)
+                );
+            }
+            source = error.source();
+        }
+        false
+    }
+}
+
 #[derive(Error, Debug)]
 pub struct BackendClosed;
 impl std::fmt::Display for BackendClosed {
@@ -153,6 +153,26 @@ pub fn reply_icon() -> Text<'static> {
     solid_icon('\u{F3E5}')
 }
 
+pub fn eye_slash_icon() -> Text<'static> {
+    solid_icon('\u{F070}')
+}
+
+pub fn bell_slash_icon() -> Text<'static> {
+    solid_icon('\u{F1F6}')
+}
+
+pub fn pin_icon() -> Text<'static> {
+    solid_icon('\u{F08D}')
+}
+
+pub fn folder_open_icon() -> Text<'static> {
+    solid_icon('\u{F07C}')
+}
+
+pub fn clock_icon() -> Text<'static> {
+    regular_icon('\u{F017}')
+}
+
 // Fonts
 const SOLID_ICONS: Font = Font::External {
     name: "FA_Solid_Icons",
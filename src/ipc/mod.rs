@@ -0,0 +1,239 @@
+//! Single running instance. Later launches, like the OS opening a `nostr:` link,
+//! hand their request to the running app over a loopback socket and exit.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use directories::ProjectDirs;
+use futures::SinkExt;
+use iced::subscription;
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::consts::{APP_PROJECT_DIRS, NOSTR_URI_PREFIX};
+use crate::types::NostrLink;
+
+pub mod uri_handler;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Not found project directory")]
+    NotFoundProjectDirectory,
+
+    #[error("I/O Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Unexpected answer from port {0}")]
+    UnexpectedAnswer(u16),
+}
+
+/// Sent by a later launch to the running instance
+#[derive(Debug, Clone)]
+pub enum Request {
+    Focus,
+    Open(NostrLink),
+}
+impl Request {
+    fn parse_line(line: &str) -> Option<Self> {
+        match line.trim() {
+            FOCUS_LINE => Some(Self::Focus),
+            uri => NostrLink::parse(uri).map(Self::Open),
+        }
+    }
+}
+
+/// The first `nostr:` launch argument
+pub fn link_from_args(args: impl IntoIterator<Item = String>) -> Option<NostrLink> {
+    args.into_iter()
+        .find(|arg| arg.starts_with(NOSTR_URI_PREFIX))
+        .and_then(|uri| NostrLink::parse(&uri))
+}
+
+/// Hands the launch arguments to the running instance.
+/// `true` when it answered and this process should exit.
+pub fn forward_to_running(args: impl IntoIterator<Item = String>) -> bool {
+    let line = args
+        .into_iter()
+        .find(|arg| arg.starts_with(NOSTR_URI_PREFIX))
+        .unwrap_or_else(|| FOCUS_LINE.to_owned());
+    match send_line(&line) {
+        Ok(()) => {
+            tracing::info!("Request handed to the running instance");
+            true
+        }
+        Err(e) => {
+            tracing::debug!("No running instance: {}", e);
+            false
+        }
+    }
+}
+
+fn send_line(line: &str) -> Result<(), Error> {
+    let port: u16 = std::fs::read_to_string(port_file()?)?
+        .trim()
+        .parse()
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::InvalidData))?;
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    writeln!(stream, "{}", line)?;
+
+    // a stale port file may point to some other program
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer)?;
+    if answer.trim() != ACK_LINE {
+        return Err(Error::UnexpectedAnswer(port));
+    }
+    Ok(())
+}
+
+/// Listens for requests from later launches
+pub fn listen() -> iced::Subscription<Request> {
+    struct Listener;
+    let id = std::any::TypeId::of::<Listener>();
+
+    subscription::channel(id, IPC_CHANNEL_SIZE, |mut output| async move {
+        let listener = match bind().await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Single instance listener failed: {}", e);
+                return futures::future::pending().await;
+            }
+        };
+        let mut failing = false;
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    // errors like running out of file descriptors repeat until
+                    // something is closed, logged once and retried slowly
+                    if !failing {
+                        tracing::warn!("Failed to accept instance request: {}", e);
+                        failing = true;
+                    }
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            failing = false;
+            // a client that never sends its line doesn't hold the next ones
+            tokio::spawn(handle_connection(stream, output.clone()));
+        }
+    })
+}
+
+/// Reads a single line, dropping the client when it's slow or the line too long
+async fn handle_connection(
+    stream: tokio::net::TcpStream,
+    mut output: futures::channel::mpsc::Sender<Request>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = tokio::io::BufReader::new(reader).take(MAX_LINE_BYTES);
+    let mut line = String::new();
+    match tokio::time::timeout(READ_TIMEOUT, reader.read_line(&mut line)).await {
+        Ok(Ok(_)) if line.ends_with('\n') => (),
+        Ok(Ok(_)) => {
+            tracing::warn!("Ignoring instance request over {} bytes", MAX_LINE_BYTES);
+            return;
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Failed to read instance request: {}", e);
+            return;
+        }
+        Err(_) => {
+            tracing::warn!("Instance request timed out");
+            return;
+        }
+    }
+    match Request::parse_line(&line) {
+        Some(request) => {
+            _ = writer.write_all(format!("{}\n", ACK_LINE).as_bytes()).await;
+            _ = output.send(request).await;
+        }
+        None => tracing::warn!("Ignoring instance request: {}", line.trim()),
+    }
+}
+
+async fn bind() -> Result<TcpListener, Error> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let port = listener.local_addr()?.port();
+    let path = port_file()?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(&path, port.to_string()).await?;
+    tracing::info!("Listening for instance requests on port {}", port);
+    Ok(listener)
+}
+
+fn port_file() -> Result<PathBuf, Error> {
+    let dirs = ProjectDirs::from(APP_PROJECT_DIRS.0, APP_PROJECT_DIRS.1, APP_PROJECT_DIRS.2)
+        .ok_or(Error::NotFoundProjectDirectory)?;
+    Ok(dirs.data_dir().join(PORT_FILENAME))
+}
+
+const PORT_FILENAME: &str = "instance.port";
+const FOCUS_LINE: &str = "focus";
+const ACK_LINE: &str = "ok";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Later launches write their line right after connecting
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(200);
+/// Room for a `nostr:` link with a few relay hints
+const MAX_LINE_BYTES: u64 = 4096;
+const IPC_CHANNEL_SIZE: usize = 10;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::prelude::ToBech32;
+    use nostr::EventId;
+
+    #[test]
+    fn link_from_launch_args() {
+        let args = vec!["nostrtalk".to_string(), "--verbose".to_string()];
+        assert!(link_from_args(args).is_none());
+
+        let note = EventId::from_slice(&[1; 32]).unwrap().to_bech32().unwrap();
+        let args = vec!["nostrtalk".to_string(), format!("nostr:{}", note)];
+        assert!(matches!(link_from_args(args), Some(NostrLink::Event(..))));
+    }
+
+    #[tokio::test]
+    async fn drops_lines_over_the_limit() {
+        use futures::StreamExt;
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (output, mut requests) = futures::channel::mpsc::channel(1);
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let endless = vec![b'a'; MAX_LINE_BYTES as usize + 1];
+        client.write_all(&endless).await.unwrap();
+        handle_connection(stream, output.clone()).await;
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        client
+            .write_all(format!("{}\n", FOCUS_LINE).as_bytes())
+            .await
+            .unwrap();
+        handle_connection(stream, output).await;
+
+        assert!(matches!(requests.next().await, Some(Request::Focus)));
+        assert!(requests.next().await.is_none());
+    }
+
+    #[test]
+    fn parses_request_lines() {
+        assert!(matches!(
+            Request::parse_line(&format!("{}\n", FOCUS_LINE)),
+            Some(Request::Focus)
+        ));
+        assert!(Request::parse_line("garbage").is_none());
+    }
+}
@@ -0,0 +1,73 @@
+//! Registers the app as the system handler for `nostr:` URIs
+
+use std::path::Path;
+use std::process::Command;
+
+use super::Error;
+
+/// Points `nostr:` links to this executable, for the current user only
+pub fn register() -> Result<(), Error> {
+    let exe = std::env::current_exe()?;
+    register_for(&exe)
+}
+
+#[cfg(target_os = "linux")]
+fn register_for(exe: &Path) -> Result<(), Error> {
+    let dirs = directories::BaseDirs::new().ok_or(Error::NotFoundProjectDirectory)?;
+    let applications = dirs.data_dir().join("applications");
+    std::fs::create_dir_all(&applications)?;
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=NostrTalk\nExec=\"{}\" %u\nTerminal=false\nNoDisplay=true\nMimeType={};\n",
+        exe.display(),
+        LINUX_MIME_TYPE
+    );
+    std::fs::write(applications.join(LINUX_DESKTOP_FILE), entry)?;
+    run(Command::new("xdg-mime").args(["default", LINUX_DESKTOP_FILE, LINUX_MIME_TYPE]))
+}
+
+#[cfg(target_os = "windows")]
+fn register_for(exe: &Path) -> Result<(), Error> {
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    run(Command::new("reg").args(["add", WINDOWS_KEY, "/ve", "/d", "URL:Nostr Protocol", "/f"]))?;
+    run(Command::new("reg").args(["add", WINDOWS_KEY, "/v", "URL Protocol", "/d", "", "/f"]))?;
+    let open_key = format!("{}\\shell\\open\\command", WINDOWS_KEY);
+    run(Command::new("reg").args(["add", &open_key, "/ve", "/d", &command, "/f"]))
+}
+
+/// The scheme is declared by `CFBundleURLTypes` in the bundle's Info.plist,
+/// this only asks Launch Services to pick up the bundle again
+#[cfg(target_os = "macos")]
+fn register_for(exe: &Path) -> Result<(), Error> {
+    let bundle = exe
+        .ancestors()
+        .find(|path| path.extension().map_or(false, |ext| ext == "app"))
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "not an app bundle"))?;
+    run(Command::new(MACOS_LSREGISTER).arg("-f").arg(bundle))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn register_for(_exe: &Path) -> Result<(), Error> {
+    Err(std::io::Error::from(std::io::ErrorKind::Unsupported).into())
+}
+
+#[allow(dead_code)]
+fn run(command: &mut Command) -> Result<(), Error> {
+    let status = command.status()?;
+    if !status.success() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("{:?} exited with {}", command.get_program(), status),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+const LINUX_DESKTOP_FILE: &str = "nostrtalk-uri.desktop";
+#[cfg(target_os = "linux")]
+const LINUX_MIME_TYPE: &str = "x-scheme-handler/nostr";
+#[cfg(target_os = "windows")]
+const WINDOWS_KEY: &str = "HKCU\\Software\\Classes\\nostr";
+#[cfg(target_os = "macos")]
+const MACOS_LSREGISTER: &str = "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister";
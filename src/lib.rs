@@ -5,8 +5,11 @@ pub(crate) mod consts;
 pub mod db;
 pub(crate) mod error;
 pub(crate) mod icon;
+pub mod ipc;
 pub mod net;
 pub(crate) mod style;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 pub mod types;
 pub mod utils;
 pub(crate) mod views;
@@ -2,6 +2,7 @@
 
 use dotenv::dotenv;
 use nostrtalk::app;
+use nostrtalk::ipc;
 use nostrtalk::setup_logger;
 
 #[tokio::main]
@@ -10,5 +11,10 @@ async fn main() {
 
     setup_logger();
 
-    app::run().await;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if ipc::forward_to_running(args.clone()) {
+        return;
+    }
+
+    app::run(ipc::link_from_args(args)).await;
 }
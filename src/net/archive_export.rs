@@ -0,0 +1,284 @@
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use nostr::hashes::Hash;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::Url;
+use serde::Serialize;
+
+/// Direct message as it goes into a conversation archive
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    /// Signed event with the encrypted content, as received
+    pub event: nostr::Event,
+    /// Relays the event was received from or accepted by
+    pub relays: Vec<Url>,
+    /// `None` when the message could not be decrypted
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Manifest {
+    format: &'static str,
+    exported_at: String,
+    user_pubkey: String,
+    contact_pubkey: String,
+    verification: &'static str,
+    files: Vec<ManifestFile>,
+    messages: Vec<ManifestMessage>,
+}
+
+#[derive(Debug, Serialize)]
+struct ManifestFile {
+    name: &'static str,
+    size: usize,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ManifestMessage {
+    event_id: String,
+    author: String,
+    recipient: Option<String>,
+    created_at: u64,
+    relays: Vec<String>,
+    /// Hash of the decrypted text shown in the transcript
+    content_sha256: Option<String>,
+}
+
+/// Zip with the signed events, their decrypted transcript and a manifest
+/// tying each transcript entry to its event, so whoever receives it can
+/// check the signatures and, with either key, the decryption
+pub fn conversation_archive(
+    user_pubkey: &XOnlyPublicKey,
+    contact_pubkey: &XOnlyPublicKey,
+    contact_name: &str,
+    exported_at: NaiveDateTime,
+    entries: &[ArchiveEntry],
+) -> Result<Vec<u8>, serde_json::Error> {
+    let events: Vec<_> = entries.iter().map(|entry| &entry.event).collect();
+    let events_json = serde_json::to_vec_pretty(&events)?;
+    let transcript = transcript(
+        user_pubkey,
+        contact_pubkey,
+        contact_name,
+        exported_at,
+        entries,
+    );
+
+    let messages = entries
+        .iter()
+        .map(|entry| ManifestMessage {
+            event_id: entry.event.id.to_hex(),
+            author: entry.event.pubkey.to_string(),
+            recipient: entry.event.tags.iter().find_map(|tag| match tag {
+                nostr::Tag::PubKey(pubkey, _) => Some(pubkey.to_string()),
+                _ => None,
+            }),
+            created_at: entry.event.created_at.as_u64(),
+            relays: entry.relays.iter().map(Url::to_string).collect(),
+            content_sha256: entry.content.as_deref().map(|c| sha256_hex(c.as_bytes())),
+        })
+        .collect();
+    let manifest = Manifest {
+        format: MANIFEST_FORMAT,
+        exported_at: exported_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        user_pubkey: user_pubkey.to_string(),
+        contact_pubkey: contact_pubkey.to_string(),
+        verification: VERIFICATION_NOTE,
+        files: vec![
+            ManifestFile {
+                name: EVENTS_FILE,
+                size: events_json.len(),
+                sha256: sha256_hex(&events_json),
+            },
+            ManifestFile {
+                name: TRANSCRIPT_FILE,
+                size: transcript.len(),
+                sha256: sha256_hex(transcript.as_bytes()),
+            },
+        ],
+        messages,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+
+    Ok(zip_store(
+        &[
+            (MANIFEST_FILE, manifest_json.as_slice()),
+            (EVENTS_FILE, events_json.as_slice()),
+            (TRANSCRIPT_FILE, transcript.as_bytes()),
+        ],
+        exported_at,
+    ))
+}
+
+fn transcript(
+    user_pubkey: &XOnlyPublicKey,
+    contact_pubkey: &XOnlyPublicKey,
+    contact_name: &str,
+    exported_at: NaiveDateTime,
+    entries: &[ArchiveEntry],
+) -> String {
+    let mut transcript = format!(
+        "Conversation between {} (you) and {} ({})\n\
+         Exported {} UTC, {} messages\n\
+         Each message names the event in {} it was decrypted from.\n",
+        user_pubkey,
+        contact_pubkey,
+        contact_name,
+        exported_at.format("%Y-%m-%d %H:%M:%S"),
+        entries.len(),
+        EVENTS_FILE,
+    );
+    for entry in entries {
+        let author = if &entry.event.pubkey == user_pubkey {
+            "You"
+        } else {
+            contact_name
+        };
+        let time = NaiveDateTime::from_timestamp_opt(entry.event.created_at.as_i64(), 0)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default();
+        let content = entry
+            .content
+            .as_deref()
+            .unwrap_or("[could not be decrypted]");
+        transcript.push_str(&format!(
+            "\n{}  {}\nevent {}\n{}\n",
+            time,
+            author,
+            entry.event.id.to_hex(),
+            content
+        ));
+    }
+    transcript
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    nostr::hashes::sha256::Hash::hash(data).to_string()
+}
+
+/// Zip archive with the files stored uncompressed
+fn zip_store(files: &[(&str, &[u8])], modified: NaiveDateTime) -> Vec<u8> {
+    let dos_time =
+        ((modified.hour() << 11) | (modified.minute() << 5) | (modified.second() / 2)) as u16;
+    let dos_year = (modified.year().max(1980) - 1980) as u32;
+    let dos_date = ((dos_year << 9) | (modified.month() << 5) | modified.day()) as u16;
+
+    let mut zip = vec![];
+    let mut central = vec![];
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let crc = crc32(data);
+        let mut header = vec![];
+        header.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        header.extend_from_slice(&ZIP_UTF8_NAMES.to_le_bytes());
+        // stored, no compression
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&dos_time.to_le_bytes());
+        header.extend_from_slice(&dos_date.to_le_bytes());
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        // no extra field
+        header.extend_from_slice(&0u16.to_le_bytes());
+
+        zip.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        zip.extend_from_slice(&header);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(data);
+
+        central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        central.extend_from_slice(&header);
+        // comment length, disk number, internal and external attributes
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = zip.len() as u32;
+    zip.extend_from_slice(&central);
+    zip.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    // this disk and the one with the central directory
+    zip.extend_from_slice(&[0; 4]);
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&central_offset.to_le_bytes());
+    // comment length
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+const MANIFEST_FORMAT: &str = "nostrtalk-conversation-archive/1";
+const MANIFEST_FILE: &str = "manifest.json";
+const EVENTS_FILE: &str = "events.json";
+const TRANSCRIPT_FILE: &str = "transcript.txt";
+const VERIFICATION_NOTE: &str = "events.json holds the NIP-04 direct messages exactly as signed. \
+    Check each event's id and signature, then decrypt its content with either party's key: \
+    the SHA-256 of the result must match content_sha256 for that event id.";
+const ZIP_VERSION: u16 = 20;
+const ZIP_UTF8_NAMES: u16 = 1 << 11;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys};
+
+    #[test]
+    fn archives_events_with_manifest() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let user = Keys::generate();
+        let contact = Keys::generate();
+        let event = EventBuilder::new_encrypted_direct_msg(&user, contact.public_key(), "hello")
+            .unwrap()
+            .to_event(&user)
+            .unwrap();
+        let entries = vec![ArchiveEntry {
+            event: event.clone(),
+            relays: vec![Url::parse("wss://relay.example.com").unwrap()],
+            content: Some("hello".into()),
+        }];
+        let exported_at = NaiveDateTime::from_timestamp_opt(1_700_000_000, 0).unwrap();
+        let zip = conversation_archive(
+            &user.public_key(),
+            &contact.public_key(),
+            "Contact",
+            exported_at,
+            &entries,
+        )
+        .unwrap();
+
+        assert!(zip.starts_with(&0x04034b50u32.to_le_bytes()));
+        let end = &zip[zip.len() - 22..];
+        assert!(end.starts_with(&0x06054b50u32.to_le_bytes()));
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 3);
+
+        // files are stored, the manifest can be read straight from the zip
+        let manifest_start = 30 + MANIFEST_FILE.len();
+        let manifest_size = u32::from_le_bytes(zip[22..26].try_into().unwrap()) as usize;
+        let manifest: serde_json::Value =
+            serde_json::from_slice(&zip[manifest_start..manifest_start + manifest_size]).unwrap();
+        assert_eq!(manifest["messages"][0]["event_id"], event.id.to_hex());
+        assert_eq!(
+            manifest["messages"][0]["content_sha256"],
+            sha256_hex(b"hello")
+        );
+    }
+}
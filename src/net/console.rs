@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Filter};
+use url::Url;
+
+use crate::utils::ns_event_to_naive;
+
+/// Event returned to a console REQ, never stored
+#[derive(Debug, Clone)]
+pub struct ConsoleRow {
+    pub relay: Url,
+    pub event_id: EventId,
+    pub kind: u32,
+    pub author: XOnlyPublicKey,
+    pub created_at: Option<NaiveDateTime>,
+    /// Since the REQ was sent
+    pub elapsed: Duration,
+    pub json: String,
+}
+impl ConsoleRow {
+    pub fn new(relay: Url, ns_event: &nostr::Event, elapsed: Duration) -> Self {
+        Self {
+            relay,
+            event_id: ns_event.id,
+            kind: ns_event.kind.as_u32(),
+            author: ns_event.pubkey,
+            created_at: ns_event_to_naive(ns_event.created_at).ok(),
+            elapsed,
+            json: ns_event.as_json(),
+        }
+    }
+}
+
+/// A single filter object or an array of them
+pub fn parse_filters(json: &str) -> Result<Vec<Filter>, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let filters = match value {
+        serde_json::Value::Array(_) => serde_json::from_value::<Vec<Filter>>(value),
+        _ => serde_json::from_value::<Filter>(value).map(|filter| vec![filter]),
+    }
+    .map_err(|e| e.to_string())?;
+    if filters.is_empty() {
+        return Err("No filter given".into());
+    }
+    Ok(filters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_one_or_many_filters() {
+        let filters = parse_filters(r#"{"kinds":[1],"limit":10}"#).unwrap();
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].limit, Some(10));
+
+        let filters = parse_filters(r#"[{"kinds":[0]},{"kinds":[3],"limit":1}]"#).unwrap();
+        assert_eq!(filters.len(), 2);
+
+        assert!(parse_filters("[]").is_err());
+        assert!(parse_filters(r#"{"kinds":"one"}"#).is_err());
+        assert!(parse_filters("not json").is_err());
+    }
+}
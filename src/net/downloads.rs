@@ -0,0 +1,228 @@
+//! Attachments of direct messages saved to the download directory,
+//! in a folder for each contact.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+
+use super::reqwest_client::MediaClient;
+use crate::db::AttachmentDownload;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Request error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+
+    #[error("{0}")]
+    FromReqwestClient(#[from] super::reqwest_client::Error),
+
+    #[error("I/O Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Attachment too large: {0} bytes")]
+    TooLarge(u64),
+
+    #[error("No download directory, choose one in the settings")]
+    NoDirectory,
+
+    #[error("Message has no attachment")]
+    NoAttachment,
+}
+
+/// Links to files in the message, pages and other links are left out
+pub fn attachment_urls(content: &str) -> Vec<String> {
+    content
+        .split_whitespace()
+        .filter(|word| ATTACHMENT_URL_RE.is_match(word))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Folder of the contact's attachments, `name` is what the contact list shows
+pub fn contact_folder(download_dir: &Path, name: &str) -> PathBuf {
+    download_dir.join(sanitize_file_name(name))
+}
+
+/// Name safe for every platform, separators and reserved characters become `_`
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || RESERVED_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // Windows drops trailing dots and spaces, leading dots hide the file
+    let sanitized = sanitized.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if sanitized.is_empty() {
+        DEFAULT_FILE_NAME.to_owned()
+    } else {
+        sanitized.chars().take(MAX_NAME_CHARS).collect()
+    }
+}
+
+/// Last segment of the url's path
+pub fn file_name_from_url(url: &str) -> String {
+    let name = url::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.path_segments()
+                .and_then(|mut segments| segments.next_back().map(str::to_owned))
+        })
+        .unwrap_or_default();
+    sanitize_file_name(&name)
+}
+
+/// `name`, or "name (1).ext", "name (2).ext"... when taken
+pub fn candidate_path(dir: &Path, name: &str, attempt: usize) -> PathBuf {
+    if attempt == 0 {
+        return dir.join(name);
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    };
+    match extension {
+        Some(extension) => dir.join(format!("{} ({}).{}", stem, attempt, extension)),
+        None => dir.join(format!("{} ({})", stem, attempt)),
+    }
+}
+
+/// Downloads the attachments of the message into `dir`, never replacing a file
+pub async fn save_attachments(
+    media_client: &MediaClient,
+    dir: &Path,
+    event_id: i64,
+    urls: &[String],
+) -> Result<Vec<AttachmentDownload>, Error> {
+    if urls.is_empty() {
+        return Err(Error::NoAttachment);
+    }
+    tokio::fs::create_dir_all(dir).await?;
+    let mut saved = vec![];
+    for url in urls {
+        let path = save_attachment(media_client, dir, url).await?;
+        saved.push(AttachmentDownload {
+            event_id,
+            url: url.to_owned(),
+            path,
+        });
+    }
+    Ok(saved)
+}
+
+async fn save_attachment(
+    media_client: &MediaClient,
+    dir: &Path,
+    url: &str,
+) -> Result<PathBuf, Error> {
+    let response = media_client.get(url)?.send().await?.error_for_status()?;
+    if let Some(length) = response.content_length() {
+        if length > MAX_ATTACHMENT_BYTES {
+            return Err(Error::TooLarge(length));
+        }
+    }
+    let bytes = response.bytes().await?;
+    if bytes.len() as u64 > MAX_ATTACHMENT_BYTES {
+        return Err(Error::TooLarge(bytes.len() as u64));
+    }
+
+    let name = file_name_from_url(url);
+    // create_new fails on a taken name instead of racing a check
+    for attempt in 0..MAX_NAME_ATTEMPTS {
+        let path = candidate_path(dir, &name, attempt);
+        match tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+        {
+            Ok(mut file) => {
+                file.write_all(&bytes).await?;
+                file.flush().await?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(std::io::Error::from(ErrorKind::AlreadyExists).into())
+}
+
+/// Opens the file manager with the file selected where the platform allows it
+pub fn show_in_folder(path: &Path) -> Result<(), Error> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("explorer");
+        command.arg(format!("/select,{}", path.display()));
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+    command.spawn()?;
+    Ok(())
+}
+
+const DEFAULT_FILE_NAME: &str = "attachment";
+const RESERVED_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const MAX_NAME_CHARS: usize = 120;
+const MAX_NAME_ATTEMPTS: usize = 1000;
+const MAX_ATTACHMENT_BYTES: u64 = 100 * 1024 * 1024;
+
+static ATTACHMENT_URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^https?://\S+/[^/?#\s]+\.(?:png|jpe?g|gif|webp|svg|mp4|webm|mov|mp3|ogg|wav|flac|m4a|pdf|txt|md|csv|json|zip|gz|tar|7z|rar|docx?|xlsx?|pptx?|odt|ods|epub)(?:[?#]\S*)?$",
+    )
+    .expect("valid attachment url regex")
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_files_and_picks_free_names() {
+        let content =
+            "see https://files.example.com/a/report.pdf?dl=1 and https://example.com/page";
+        assert_eq!(
+            attachment_urls(content),
+            vec!["https://files.example.com/a/report.pdf?dl=1".to_owned()]
+        );
+        assert_eq!(
+            file_name_from_url("https://files.example.com/a/report.pdf?dl=1"),
+            "report.pdf"
+        );
+        assert_eq!(sanitize_file_name("../Ali:ce?"), "_Ali_ce_");
+        assert_eq!(sanitize_file_name(" .. "), DEFAULT_FILE_NAME);
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            candidate_path(dir.path(), "report.pdf", 0),
+            dir.path().join("report.pdf")
+        );
+        assert_eq!(
+            candidate_path(dir.path(), "report.pdf", 2),
+            dir.path().join("report (2).pdf")
+        );
+        assert_eq!(
+            candidate_path(dir.path(), ".env", 1),
+            dir.path().join(".env (1)")
+        );
+        assert_eq!(
+            contact_folder(dir.path(), "Bob/Builder"),
+            dir.path().join("Bob_Builder")
+        );
+    }
+}
@@ -1,6 +1,10 @@
+use chrono::NaiveDateTime;
 use nostr::{secp256k1::XOnlyPublicKey, Filter, Kind, Timestamp};
 
+use crate::db::poll::{POLL_KIND, POLL_RESPONSE_KIND};
 use crate::db::{DbContact, DbEvent};
+use crate::types::account_preview::DM_SAMPLE;
+use crate::types::SubName;
 
 fn to_secs(last_event: &Option<DbEvent>) -> u64 {
     last_event
@@ -62,15 +66,106 @@ pub fn contact_list_filter(public_key: XOnlyPublicKey, last_event: &Option<DbEve
         .since(Timestamp::from(to_secs(last_event)))
 }
 
-pub fn messages_filter(public_key: XOnlyPublicKey, last_event: &Option<DbEvent>) -> Vec<Filter> {
+/// `history_since` bounds the first sync to the depth chosen at first login
+pub fn messages_filter(
+    public_key: XOnlyPublicKey,
+    last_event: &Option<DbEvent>,
+    history_since: Option<NaiveDateTime>,
+) -> Vec<Filter> {
+    let since = to_secs(last_event).max(history_since.map_or(0, |since| since.timestamp() as u64));
+    let sent_msgs = Filter::new()
+        .kind(nostr::Kind::EncryptedDirectMessage)
+        .author(public_key.to_string())
+        .since(Timestamp::from(since));
+    let recv_msgs = Filter::new()
+        .kind(nostr::Kind::EncryptedDirectMessage)
+        .pubkey(public_key)
+        .since(Timestamp::from(since));
+
+    vec![sent_msgs, recv_msgs]
+}
+
+/// Reactions by the user, from any device, and to the user's messages.
+/// Reactions between other channel members aren't tagged with anything to follow
+pub fn reactions_filter(public_key: XOnlyPublicKey, last_event: &Option<DbEvent>) -> Vec<Filter> {
+    let since = Timestamp::from(to_secs(last_event));
+    let sent = Filter::new()
+        .kind(Kind::Reaction)
+        .author(public_key.to_string())
+        .since(since);
+    let received = Filter::new()
+        .kind(Kind::Reaction)
+        .pubkey(public_key)
+        .since(since);
+
+    vec![sent, received]
+}
+
+/// NIP-09 deletions by the user, from any device, and by the contacts.
+/// Deletions by channel members outside the contact list aren't followed
+pub fn deletions_filter<'a, C: IntoIterator<Item = &'a DbContact>>(
+    public_key: XOnlyPublicKey,
+    contact_list: C,
+    last_event: &Option<DbEvent>,
+) -> Filter {
+    let authors = std::iter::once(public_key.to_string())
+        .chain(contact_list.into_iter().map(|c| c.pubkey().to_string()))
+        .collect::<Vec<_>>();
+
+    Filter::new()
+        .authors(authors)
+        .kind(Kind::EventDeletion)
+        .since(Timestamp::from(to_secs(last_event)))
+}
+
+/// Messages exchanged with a few conversations since the same point as [`messages_filter`]
+pub fn messages_wave_filter(
+    public_key: XOnlyPublicKey,
+    chat_pubkeys: &[XOnlyPublicKey],
+    last_event: &Option<DbEvent>,
+    history_since: Option<NaiveDateTime>,
+) -> Vec<Filter> {
+    let since = to_secs(last_event).max(history_since.map_or(0, |since| since.timestamp() as u64));
+    let sent_msgs = Filter::new()
+        .kind(nostr::Kind::EncryptedDirectMessage)
+        .author(public_key.to_string())
+        .pubkeys(chat_pubkeys.to_vec())
+        .since(Timestamp::from(since));
+    let recv_msgs = Filter::new()
+        .kind(nostr::Kind::EncryptedDirectMessage)
+        .authors(
+            chat_pubkeys
+                .iter()
+                .map(|pubkey| pubkey.to_string())
+                .collect(),
+        )
+        .pubkey(public_key)
+        .since(Timestamp::from(since));
+
+    vec![sent_msgs, recv_msgs]
+}
+
+/// Messages of a single conversation between `since` and `until`
+pub fn dm_history_filter(
+    public_key: XOnlyPublicKey,
+    chat_pubkey: XOnlyPublicKey,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Vec<Filter> {
+    let since = Timestamp::from(since.timestamp() as u64);
+    let until = Timestamp::from(until.timestamp() as u64);
     let sent_msgs = Filter::new()
         .kind(nostr::Kind::EncryptedDirectMessage)
         .author(public_key.to_string())
-        .since(Timestamp::from(to_secs(last_event)));
+        .pubkey(chat_pubkey)
+        .since(since)
+        .until(until);
     let recv_msgs = Filter::new()
         .kind(nostr::Kind::EncryptedDirectMessage)
+        .author(chat_pubkey.to_string())
         .pubkey(public_key)
-        .since(Timestamp::from(to_secs(last_event)));
+        .since(since)
+        .until(until);
 
     vec![sent_msgs, recv_msgs]
 }
@@ -95,12 +190,20 @@ pub fn search_channel_details_filter(channel_id: &nostr::EventId) -> Vec<Filter>
             .kind(Kind::ChannelMetadata)
             .event(channel_id.to_owned())
             .until(Timestamp::now())
-            .limit(10),
+            .limit(CHANNEL_METADATA_LIMIT),
         Filter::new()
             .kind(Kind::ChannelMessage)
             .event(channel_id.to_owned())
             .until(Timestamp::now())
             .limit(CHANNEL_DETAILS_LIMIT),
+        Filter::new()
+            .kinds(vec![
+                Kind::Custom(POLL_KIND),
+                Kind::Custom(POLL_RESPONSE_KIND),
+            ])
+            .event(channel_id.to_owned())
+            .until(Timestamp::now())
+            .limit(CHANNEL_DETAILS_LIMIT),
     ]
 }
 
@@ -125,8 +228,95 @@ pub fn channel_details_filter(
             .kind(Kind::ChannelMuteUser)
             .events(channels.to_vec())
             .since(Timestamp::from(to_secs(last_event))),
+        Filter::new()
+            .kinds(vec![
+                Kind::Custom(POLL_KIND),
+                Kind::Custom(POLL_RESPONSE_KIND),
+            ])
+            .events(channels.to_vec())
+            .since(Timestamp::from(to_secs(last_event))),
     ]
 }
 
+/// Recent channel messages written by the user's contacts
+pub fn contact_channels_filter<'a, C: IntoIterator<Item = &'a DbContact>>(
+    contact_list: C,
+) -> Filter {
+    let contacts_pubkeys = contact_list
+        .into_iter()
+        .map(|c| c.pubkey().to_string())
+        .collect::<Vec<_>>();
+    let since =
+        (chrono::Utc::now() - chrono::Duration::days(CONTACT_CHANNELS_DAYS)).timestamp() as u64;
+
+    Filter::new()
+        .authors(contacts_pubkeys)
+        .kind(Kind::ChannelMessage)
+        .since(Timestamp::from(since))
+        .limit(CONTACT_CHANNELS_LIMIT)
+}
+
+pub fn channels_creation_filter(channels: &[nostr::EventId]) -> Filter {
+    Filter::new()
+        .kind(Kind::ChannelCreation)
+        .ids(channels.iter().map(|id| id.to_hex()).collect())
+}
+
+/// Latest notes and channel messages with the hashtag
+pub fn hashtag_feed_filter(hashtag: &str) -> Filter {
+    Filter::new()
+        .kinds(vec![Kind::TextNote, Kind::ChannelMessage])
+        .hashtag(hashtag)
+        .limit(HASHTAG_FEED_LIMIT)
+}
+
+/// Notes and channel messages with any of the hashtags from now on
+pub fn followed_hashtags_filter(hashtags: &[String]) -> Filter {
+    Filter::new()
+        .kinds(vec![Kind::TextNote, Kind::ChannelMessage])
+        .hashtags(hashtags.to_vec())
+        .since(Timestamp::now())
+}
+
+/// Newest profile and contact list of the user and a sample of their messages
+pub fn account_preview_filter(public_key: XOnlyPublicKey) -> Vec<Filter> {
+    let profile = Filter::new()
+        .author(public_key.to_string())
+        .kind(Kind::Metadata)
+        .limit(1);
+    let contact_list = Filter::new()
+        .author(public_key.to_string())
+        .kind(Kind::ContactList)
+        .limit(1);
+    let sent_msgs = Filter::new()
+        .kind(Kind::EncryptedDirectMessage)
+        .author(public_key.to_string())
+        .limit(DM_SAMPLE);
+    let recv_msgs = Filter::new()
+        .kind(Kind::EncryptedDirectMessage)
+        .pubkey(public_key)
+        .limit(DM_SAMPLE);
+    vec![profile, contact_list, sent_msgs, recv_msgs]
+}
+
+/// Sum of the filter limits of a named subscription, when it has them
+pub fn requested_limit(sub_name: &SubName) -> Option<usize> {
+    match sub_name {
+        SubName::SearchChannels => Some(CHANNEL_SEARCH_LIMIT),
+        SubName::SearchChannelsDetails(_) => {
+            Some(CHANNEL_METADATA_LIMIT + 2 * CHANNEL_DETAILS_LIMIT)
+        }
+        SubName::ResolveEvent | SubName::VerifyDeletion(_) => Some(1),
+        SubName::ContactChannels => Some(CONTACT_CHANNELS_LIMIT),
+        SubName::HashtagFeed(_) => Some(HASHTAG_FEED_LIMIT),
+        SubName::AccountPreview => Some(2 + 2 * DM_SAMPLE),
+        _ => None,
+    }
+}
+
 const CHANNEL_SEARCH_LIMIT: usize = 10;
+const CHANNEL_METADATA_LIMIT: usize = 10;
 const CHANNEL_DETAILS_LIMIT: usize = 1000;
+const CONTACT_CHANNELS_LIMIT: usize = 2000;
+const CONTACT_CHANNELS_DAYS: i64 = 30;
+const HASHTAG_FEED_LIMIT: usize = 100;
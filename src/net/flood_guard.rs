@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use nostr::{EventId, SubscriptionId};
+use url::Url;
+
+use crate::db::ViolationKind;
+use crate::types::SubName;
+
+/// Per relay and subscription counters, checked before an event is processed
+#[derive(Debug, Default)]
+pub struct FloodGuard {
+    subs: HashMap<(Url, SubscriptionId), SubStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accept,
+    /// Dropped without reporting
+    Drop,
+    /// Dropped, first time the subscription breaks this rule
+    Violation(ViolationKind),
+}
+
+#[derive(Debug)]
+struct SubStats {
+    window_start: Instant,
+    in_window: usize,
+    last_event: Instant,
+    /// Stored events, counted until EOSE
+    stored: usize,
+    eose: bool,
+    seen: HashSet<EventId>,
+    duplicates: usize,
+    reported: HashSet<ViolationKind>,
+}
+impl SubStats {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            in_window: 0,
+            last_event: now,
+            stored: 0,
+            eose: false,
+            seen: HashSet::new(),
+            duplicates: 0,
+            reported: HashSet::new(),
+        }
+    }
+    fn violation(&mut self, kind: ViolationKind) -> Verdict {
+        if self.reported.insert(kind) {
+            Verdict::Violation(kind)
+        } else {
+            Verdict::Drop
+        }
+    }
+}
+
+impl FloodGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `requested` is the sum of the subscription's filter limits, when known
+    pub fn check(
+        &mut self,
+        url: &Url,
+        subscription_id: &SubscriptionId,
+        event_id: EventId,
+        requested: Option<usize>,
+        now: Instant,
+    ) -> Verdict {
+        if self.subs.len() > MAX_TRACKED_SUBSCRIPTIONS {
+            self.subs
+                .retain(|_, stats| now.duration_since(stats.last_event) < STALE_AFTER);
+        }
+        let stats = self
+            .subs
+            .entry((url.to_owned(), subscription_id.to_owned()))
+            .or_insert_with(|| SubStats::new(now));
+        stats.last_event = now;
+
+        if now.duration_since(stats.window_start) >= RATE_WINDOW {
+            stats.window_start = now;
+            stats.in_window = 0;
+        }
+        stats.in_window += 1;
+        if stats.in_window > MAX_EVENTS_PER_WINDOW {
+            return stats.violation(ViolationKind::Flood);
+        }
+
+        if stats.seen.len() >= MAX_SEEN_IDS {
+            stats.seen.clear();
+        }
+        if !stats.seen.insert(event_id) {
+            stats.duplicates += 1;
+            if stats.duplicates > MAX_DUPLICATES {
+                return stats.violation(ViolationKind::Replay);
+            }
+            return Verdict::Drop;
+        }
+
+        if !stats.eose {
+            stats.stored += 1;
+            if let Some(limit) = requested {
+                if stats.stored > limit.saturating_mul(OVER_LIMIT_FACTOR) {
+                    return stats.violation(ViolationKind::OverLimit);
+                }
+            }
+        }
+
+        Verdict::Accept
+    }
+
+    /// Events after EOSE are live and don't count against the filter limits
+    pub fn end_of_stored(&mut self, url: &Url, subscription_id: &SubscriptionId) {
+        if let Some(stats) = self
+            .subs
+            .get_mut(&(url.to_owned(), subscription_id.to_owned()))
+        {
+            stats.eose = true;
+        }
+    }
+
+    /// Counts start over when a named subscription is sent again
+    pub fn restart(&mut self, sub_name: &SubName) {
+        let subscription_id = SubscriptionId::new(sub_name.to_string());
+        self.subs
+            .retain(|(_, sub_id), _| sub_id != &subscription_id);
+    }
+
+    pub fn forget_relay(&mut self, url: &Url) {
+        self.subs.retain(|(sub_url, _), _| sub_url != url);
+    }
+}
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+const MAX_EVENTS_PER_WINDOW: usize = 500;
+const MAX_DUPLICATES: usize = 50;
+const OVER_LIMIT_FACTOR: usize = 2;
+const MAX_SEEN_IDS: usize = 10_000;
+const MAX_TRACKED_SUBSCRIPTIONS: usize = 256;
+const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_id(n: u32) -> EventId {
+        let mut bytes = [0; 32];
+        bytes[..4].copy_from_slice(&n.to_be_bytes());
+        EventId::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn flood_is_reported_once_and_throttled() {
+        let mut guard = FloodGuard::new();
+        let url = Url::parse("wss://relay.example.com").unwrap();
+        let sub = SubscriptionId::new("Messages");
+        let now = Instant::now();
+
+        let verdicts: Vec<_> = (0..MAX_EVENTS_PER_WINDOW as u32 + 2)
+            .map(|n| guard.check(&url, &sub, event_id(n), None, now))
+            .collect();
+        assert!(verdicts[..MAX_EVENTS_PER_WINDOW]
+            .iter()
+            .all(|v| *v == Verdict::Accept));
+        assert_eq!(
+            verdicts[MAX_EVENTS_PER_WINDOW],
+            Verdict::Violation(ViolationKind::Flood)
+        );
+        assert_eq!(verdicts[MAX_EVENTS_PER_WINDOW + 1], Verdict::Drop);
+
+        // the next window is processed again
+        let later = now + RATE_WINDOW;
+        assert_eq!(
+            guard.check(&url, &sub, event_id(u32::MAX), None, later),
+            Verdict::Accept
+        );
+    }
+
+    #[test]
+    fn stored_events_over_the_limit_until_eose() {
+        let mut guard = FloodGuard::new();
+        let url = Url::parse("wss://relay.example.com").unwrap();
+        let sub = SubscriptionId::new("SearchChannels");
+        let now = Instant::now();
+
+        for n in 0..20 {
+            assert_eq!(
+                guard.check(&url, &sub, event_id(n), Some(10), now),
+                Verdict::Accept
+            );
+        }
+        assert_eq!(
+            guard.check(&url, &sub, event_id(20), Some(10), now),
+            Verdict::Violation(ViolationKind::OverLimit)
+        );
+
+        guard.end_of_stored(&url, &sub);
+        assert_eq!(
+            guard.check(&url, &sub, event_id(21), Some(10), now),
+            Verdict::Accept
+        );
+    }
+
+    #[test]
+    fn replays_are_dropped() {
+        let mut guard = FloodGuard::new();
+        let url = Url::parse("wss://relay.example.com").unwrap();
+        let sub = SubscriptionId::new("Channels");
+        let mut now = Instant::now();
+
+        assert_eq!(
+            guard.check(&url, &sub, event_id(1), None, now),
+            Verdict::Accept
+        );
+        for _ in 0..MAX_DUPLICATES {
+            now += RATE_WINDOW;
+            assert_eq!(
+                guard.check(&url, &sub, event_id(1), None, now),
+                Verdict::Drop
+            );
+        }
+        assert_eq!(
+            guard.check(&url, &sub, event_id(1), None, now),
+            Verdict::Violation(ViolationKind::Replay)
+        );
+        guard.restart(&SubName::Channels);
+        assert_eq!(
+            guard.check(&url, &sub, event_id(1), None, now),
+            Verdict::Accept
+        );
+
+        // other relays are tracked separately
+        let other = Url::parse("wss://other.example.com").unwrap();
+        assert_eq!(
+            guard.check(&other, &sub, event_id(1), None, now),
+            Verdict::Accept
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use image::io::Reader;
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageOutputFormat};
+use std::io::Cursor;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::config::AttachmentConfig;
+use crate::types::{CropRect, ProfileImage};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Image error: {0}")]
+    FromImage(#[from] image::error::ImageError),
+
+    #[error("I/O Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Unknown image format")]
+    UnknownFormat,
+
+    #[error("Sanitize task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+/// Image ready to be uploaded
+#[derive(Debug, Clone)]
+pub struct SanitizedImage {
+    pub bytes: Vec<u8>,
+    pub mime: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Reads and sanitizes the file in a blocking worker, `options` can override
+/// the configured defaults for a single send.
+pub async fn sanitize_image_file(
+    path: PathBuf,
+    options: AttachmentConfig,
+) -> Result<SanitizedImage, Error> {
+    let bytes = tokio::fs::read(path).await?;
+    tokio::task::spawn_blocking(move || sanitize_image(&bytes, &options)).await?
+}
+
+/// Re-encoding only keeps the pixels, dropping EXIF, GPS and any other metadata.
+/// The EXIF orientation is applied first so photos aren't shown sideways
+pub fn sanitize_image(bytes: &[u8], options: &AttachmentConfig) -> Result<SanitizedImage, Error> {
+    let reader = Reader::new(Cursor::new(bytes)).with_guessed_format()?;
+    let format = reader.format().ok_or(Error::UnknownFormat)?;
+    let image = reader.decode()?;
+
+    let needs_resize = options
+        .max_dimension
+        .map_or(false, |max| image.width() > max || image.height() > max);
+
+    if !options.strip_metadata && !needs_resize {
+        let (width, height) = image.dimensions();
+        return Ok(SanitizedImage {
+            bytes: bytes.to_vec(),
+            mime: mime_of(format),
+            width,
+            height,
+        });
+    }
+
+    let image = match exif_orientation(bytes) {
+        Some(orientation) => orient(image, orientation),
+        None => image,
+    };
+    let image = match options.max_dimension {
+        Some(max) if needs_resize => image.resize(max, max, image::imageops::FilterType::Lanczos3),
+        _ => image,
+    };
+
+    encode(image, format, options)
+}
+
+/// Orientation tag of the EXIF segment of a JPEG, `None` when there's none
+fn exif_orientation(bytes: &[u8]) -> Option<u16> {
+    if bytes.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    // segments after SOI, the metadata comes before the start of scan
+    let mut pos = 2;
+    while bytes.get(pos) == Some(&0xFF) {
+        let marker = *bytes.get(pos + 1)?;
+        if marker == JPEG_START_OF_SCAN {
+            return None;
+        }
+        let len = u16::from_be_bytes([*bytes.get(pos + 2)?, *bytes.get(pos + 3)?]) as usize;
+        let segment = bytes.get(pos + 4..pos + 2 + len)?;
+        if marker == JPEG_APP1 {
+            if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                return tiff_orientation(tiff);
+            }
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+/// Looks for the orientation in the first IFD of the TIFF header
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let read_u16 = |at: usize| {
+        let b = tiff.get(at..at + 2)?;
+        Some(if big_endian {
+            u16::from_be_bytes([b[0], b[1]])
+        } else {
+            u16::from_le_bytes([b[0], b[1]])
+        })
+    };
+    let read_u32 = |at: usize| {
+        let b = tiff.get(at..at + 4)?;
+        Some(if big_endian {
+            u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+        } else {
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+        })
+    };
+    let ifd = read_u32(4)? as usize;
+    let entries = read_u16(ifd)? as usize;
+    (0..entries).find_map(|i| {
+        let entry = ifd + 2 + i * 12;
+        // a SHORT value sits at the start of the value field
+        (read_u16(entry)? == EXIF_ORIENTATION_TAG).then(|| read_u16(entry + 8))?
+    })
+}
+
+/// Turns the pixels the way the EXIF orientation says they are displayed
+fn orient(image: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Reads the file, keeps the cropped area and resizes it for the profile
+/// in a blocking worker. Always re-encoded, so metadata is dropped.
+pub async fn crop_profile_image_file(
+    path: PathBuf,
+    crop: CropRect,
+    kind: ProfileImage,
+    options: AttachmentConfig,
+) -> Result<SanitizedImage, Error> {
+    let bytes = tokio::fs::read(path).await?;
+    tokio::task::spawn_blocking(move || crop_profile_image(&bytes, crop, kind, &options)).await?
+}
+
+pub fn crop_profile_image(
+    bytes: &[u8],
+    crop: CropRect,
+    kind: ProfileImage,
+    options: &AttachmentConfig,
+) -> Result<SanitizedImage, Error> {
+    let reader = Reader::new(Cursor::new(bytes)).with_guessed_format()?;
+    let format = reader.format().ok_or(Error::UnknownFormat)?;
+    let image = reader.decode()?;
+
+    let (x, y, width, height) = crop.pixels(image.width(), image.height());
+    let (output_width, output_height) = kind.output_size();
+    let image = image.crop_imm(x, y, width, height).resize_exact(
+        output_width,
+        output_height,
+        image::imageops::FilterType::Lanczos3,
+    );
+
+    encode(image, format, options)
+}
+
+fn encode(
+    image: DynamicImage,
+    format: ImageFormat,
+    options: &AttachmentConfig,
+) -> Result<SanitizedImage, Error> {
+    let (output, mime) = if keeps_alpha(format, &image) {
+        (ImageOutputFormat::Png, PNG_MIME)
+    } else {
+        (ImageOutputFormat::Jpeg(options.jpeg_quality), JPEG_MIME)
+    };
+    let image = match output {
+        ImageOutputFormat::Jpeg(_) => DynamicImage::ImageRgb8(image.to_rgb8()),
+        _ => image,
+    };
+
+    let mut encoded = Vec::new();
+    image.write_to(&mut encoded, output)?;
+    let (width, height) = image.dimensions();
+
+    Ok(SanitizedImage {
+        bytes: encoded,
+        mime,
+        width,
+        height,
+    })
+}
+
+/// Photos become JPEG, anything that could be transparent stays lossless
+fn keeps_alpha(format: ImageFormat, image: &DynamicImage) -> bool {
+    format != ImageFormat::Jpeg && image.color().has_alpha()
+}
+
+fn mime_of(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => PNG_MIME,
+        ImageFormat::Jpeg => JPEG_MIME,
+        ImageFormat::Gif => "image/gif",
+        ImageFormat::WebP => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+const PNG_MIME: &str = "image/png";
+const JPEG_MIME: &str = "image/jpeg";
+const JPEG_APP1: u8 = 0xE1;
+const JPEG_START_OF_SCAN: u8 = 0xDA;
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn jpeg_with_exif(width: u32, height: u32) -> Vec<u8> {
+        jpeg_with_app1(width, height, b"Exif\0\0GPSLatitude")
+    }
+
+    fn jpeg_with_app1(width: u32, height: u32, payload: &[u8]) -> Vec<u8> {
+        let image =
+            DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([200, 10, 10])));
+        let mut jpeg = Vec::new();
+        image
+            .write_to(&mut jpeg, ImageOutputFormat::Jpeg(90))
+            .unwrap();
+
+        // APP1 segment right after the SOI marker
+        let len = (payload.len() + 2) as u16;
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&len.to_be_bytes());
+        app1.extend_from_slice(payload);
+        jpeg.splice(2..2, app1);
+        jpeg
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn crops_and_resizes_the_banner() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(600, 600, Rgba([0, 0, 0, 0])));
+        let mut png = Vec::new();
+        image.write_to(&mut png, ImageOutputFormat::Png).unwrap();
+
+        let crop = CropRect::centered(600, 600, ProfileImage::Banner.aspect_ratio());
+        let banner = crop_profile_image(
+            &png,
+            crop,
+            ProfileImage::Banner,
+            &AttachmentConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!((banner.width, banner.height), (1500, 500));
+        assert_eq!(banner.mime, PNG_MIME);
+    }
+
+    #[test]
+    fn strips_exif() {
+        let jpeg = jpeg_with_exif(10, 10);
+        assert!(contains(&jpeg, b"Exif"));
+
+        let sanitized = sanitize_image(&jpeg, &AttachmentConfig::default()).unwrap();
+        assert!(!contains(&sanitized.bytes, b"Exif"));
+        assert_eq!(sanitized.mime, JPEG_MIME);
+    }
+
+    #[test]
+    fn applies_exif_orientation() {
+        // little endian TIFF with a single entry, orientation 6 is rotated 90° clockwise
+        let mut exif = b"Exif\0\0II\x2A\0\x08\0\0\0\x01\0".to_vec();
+        exif.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0]);
+        let jpeg = jpeg_with_app1(40, 20, &exif);
+
+        let sanitized = sanitize_image(&jpeg, &AttachmentConfig::default()).unwrap();
+        assert_eq!((sanitized.width, sanitized.height), (20, 40));
+        assert!(!contains(&sanitized.bytes, b"Exif"));
+    }
+
+    #[test]
+    fn keeps_original_when_disabled() {
+        let jpeg = jpeg_with_exif(10, 10);
+        let options = AttachmentConfig {
+            strip_metadata: false,
+            max_dimension: None,
+            ..AttachmentConfig::default()
+        };
+        let sanitized = sanitize_image(&jpeg, &options).unwrap();
+        assert_eq!(sanitized.bytes, jpeg);
+    }
+
+    #[test]
+    fn downscales_to_max_dimension() {
+        let jpeg = jpeg_with_exif(400, 200);
+        let options = AttachmentConfig {
+            max_dimension: Some(100),
+            ..AttachmentConfig::default()
+        };
+        let sanitized = sanitize_image(&jpeg, &options).unwrap();
+        assert_eq!((sanitized.width, sanitized.height), (100, 50));
+    }
+
+    #[test]
+    fn transparent_images_stay_png() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0])));
+        let mut png = Vec::new();
+        image.write_to(&mut png, ImageOutputFormat::Png).unwrap();
+
+        let sanitized = sanitize_image(&png, &AttachmentConfig::default()).unwrap();
+        assert_eq!(sanitized.mime, PNG_MIME);
+    }
+}
@@ -0,0 +1,163 @@
+//! Limits checked on every event received from a relay, before it is stored or shown.
+
+use std::borrow::Cow;
+
+use nostr::Kind;
+
+/// What happens to an event that reached the client
+#[derive(Debug)]
+pub enum Ingress {
+    Accept(nostr::Event),
+    /// Content cut to the size limit or cleaned of control characters
+    Truncated(nostr::Event),
+    Rejected(Rejection),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// Content that can't be cut, bytes
+    ContentTooLarge(usize),
+    TooManyTags(usize),
+}
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::ContentTooLarge(size) => write!(f, "content of {} bytes", size),
+            Rejection::TooManyTags(count) => write!(f, "{} tags", count),
+        }
+    }
+}
+
+/// Applies the limits, cutting and cleaning the content of text events.
+/// Encrypted or JSON content is never changed, the event is rejected instead.
+pub fn check(mut ns_event: nostr::Event) -> Ingress {
+    let max_tags = match ns_event.kind {
+        Kind::ContactList => MAX_CONTACT_LIST_TAGS,
+        _ => MAX_TAGS,
+    };
+    if ns_event.tags.len() > max_tags {
+        return Ingress::Rejected(Rejection::TooManyTags(ns_event.tags.len()));
+    }
+
+    if has_structured_content(&ns_event.kind) {
+        if ns_event.content.len() > MAX_CONTENT_BYTES {
+            return Ingress::Rejected(Rejection::ContentTooLarge(ns_event.content.len()));
+        }
+        return Ingress::Accept(ns_event);
+    }
+
+    let truncated = truncate_bytes(&ns_event.content, MAX_CONTENT_BYTES);
+    let cleaned = sanitize_text(truncated);
+    if cleaned.len() == ns_event.content.len() {
+        return Ingress::Accept(ns_event);
+    }
+    ns_event.content = cleaned.into_owned();
+    Ingress::Truncated(ns_event)
+}
+
+/// Removes control characters, keeping new lines and tabs, and the
+/// invisible characters that reorder or hide text
+pub fn sanitize_text(text: &str) -> Cow<str> {
+    if !text.chars().any(is_stripped) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.chars().filter(|c| !is_stripped(*c)).collect())
+}
+
+fn is_stripped(c: char) -> bool {
+    match c {
+        '\n' | '\t' => false,
+        // bidi embeddings, overrides and isolates
+        '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => true,
+        // byte order mark
+        '\u{FEFF}' => true,
+        c => c.is_control(),
+    }
+}
+
+/// Longest prefix within `max` bytes ending on a char boundary
+fn truncate_bytes(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Content that would be corrupted by cutting or cleaning it
+fn has_structured_content(kind: &Kind) -> bool {
+    matches!(
+        kind,
+        Kind::Metadata
+            | Kind::ContactList
+            | Kind::EncryptedDirectMessage
+            | Kind::ChannelCreation
+            | Kind::ChannelMetadata
+    )
+}
+
+const MAX_CONTENT_BYTES: usize = 64 * 1024;
+const MAX_TAGS: usize = 2_000;
+/// Contact lists tag every contact
+const MAX_CONTACT_LIST_TAGS: usize = 10_000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys, Tag};
+
+    fn event(kind: Kind, content: &str, tags: usize) -> nostr::Event {
+        let pubkey = Keys::generate().public_key();
+        let tags: Vec<Tag> = (0..tags).map(|_| Tag::PubKey(pubkey, None)).collect();
+        EventBuilder::new(kind, content, &tags)
+            .to_event(&Keys::generate())
+            .unwrap()
+    }
+
+    #[test]
+    fn strips_control_characters() {
+        assert_eq!(sanitize_text("line\nnext\ttab"), "line\nnext\ttab");
+        assert_eq!(sanitize_text("a\u{0}b\u{1b}[31mc\u{202E}d"), "ab[31mcd");
+
+        let ns_event = event(Kind::TextNote, "hi\u{7}", 0);
+        match check(ns_event) {
+            Ingress::Truncated(ns_event) => assert_eq!(ns_event.content, "hi"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            check(event(Kind::TextNote, "hi", 0)),
+            Ingress::Accept(_)
+        ));
+    }
+
+    #[test]
+    fn cuts_text_and_rejects_structured_content() {
+        // 3 bytes per char, the limit falls inside one
+        let long = "€".repeat(MAX_CONTENT_BYTES / 3 + 1);
+        match check(event(Kind::ChannelMessage, &long, 0)) {
+            Ingress::Truncated(ns_event) => {
+                assert_eq!(ns_event.content.len(), MAX_CONTENT_BYTES / 3 * 3)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(
+            check(event(Kind::EncryptedDirectMessage, &long, 0)),
+            Ingress::Rejected(Rejection::ContentTooLarge(size)) if size == long.len()
+        ));
+    }
+
+    #[test]
+    fn limits_tags() {
+        assert!(matches!(
+            check(event(Kind::TextNote, "", MAX_TAGS + 1)),
+            Ingress::Rejected(Rejection::TooManyTags(count)) if count == MAX_TAGS + 1
+        ));
+        assert!(matches!(
+            check(event(Kind::ContactList, "", MAX_TAGS + 1)),
+            Ingress::Accept(_)
+        ));
+    }
+}
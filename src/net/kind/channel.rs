@@ -0,0 +1,212 @@
+use crate::db::{
+    ChannelCache, ChannelKeywords, ChannelMention, ChannelSubscription, DbChannelMessage,
+    DbChannelModeration, DbEvent, Moderation,
+};
+use crate::error::Error;
+use crate::net::BackendEvent;
+use crate::utils::channel_id_from_tags;
+
+use futures_util::SinkExt;
+use nostr::{EventId, Keys, Kind};
+use sqlx::SqlitePool;
+use url::Url;
+
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+pub struct ChannelHandler;
+
+impl KindHandler for ChannelHandler {
+    fn name(&self) -> &'static str {
+        "Channel"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![
+            Kind::ChannelCreation,
+            Kind::ChannelMetadata,
+            Kind::ChannelMessage,
+            Kind::ChannelHideMessage,
+            Kind::ChannelMuteUser,
+        ]
+    }
+    fn setup<'a>(&'a self, pool: &'a SqlitePool) -> HandlerFuture<'a> {
+        Box::pin(async move { Ok(DbChannelModeration::create_tables(pool).await?) })
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let HandlerCtx {
+                output,
+                keys,
+                pool,
+                cache_pool,
+                url,
+            } = ctx;
+            match ns_event.kind {
+                Kind::ChannelCreation => {
+                    let cache = ChannelCache::fetch_insert(cache_pool, &ns_event).await?;
+                    _ = output.send(BackendEvent::ChannelCacheUpdated(cache)).await;
+                }
+                Kind::ChannelMetadata => {
+                    let cache = ChannelCache::update(cache_pool, &ns_event).await?;
+                    _ = output.send(BackendEvent::ChannelCacheUpdated(cache)).await;
+                }
+                Kind::ChannelHideMessage | Kind::ChannelMuteUser => {
+                    handle_moderation(output, pool, cache_pool, &ns_event).await?;
+                }
+                _ => {
+                    handle_channel_message(output, keys, pool, cache_pool, url, ns_event).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+    fn confirmed<'a>(&'a self, ctx: HandlerCtx<'a>, db_event: DbEvent) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            match db_event.kind {
+                // metadata edited by the user
+                Kind::ChannelMetadata => {
+                    let ns_event = db_event.to_ns_event()?;
+                    let cache = ChannelCache::update(ctx.cache_pool, &ns_event).await?;
+                    _ = ctx
+                        .output
+                        .send(BackendEvent::ChannelCacheUpdated(cache))
+                        .await;
+                }
+                Kind::ChannelMessage => {
+                    pending_channel_msg_confirmed(ctx.output, ctx.pool, &db_event).await?;
+                }
+                other => return Err(Error::NotSubscribedToKind(other)),
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Stores the message the user sent and replaces the one shown while pending
+async fn pending_channel_msg_confirmed(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    pool: &SqlitePool,
+    db_event: &DbEvent,
+) -> Result<(), Error> {
+    let ch_msg = DbChannelMessage::insert_confirmed(pool, db_event, true).await?;
+    _ = output
+        .send(BackendEvent::ConfirmedChannelMsg(
+            ch_msg.channel_id,
+            db_event.event_hash,
+            ch_msg.into(),
+        ))
+        .await;
+    Ok(())
+}
+
+pub async fn handle_channel_message(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+    relay_url: &Url,
+    ns_event: nostr::Event,
+) -> Result<(), Error> {
+    let Some(channel_id) = channel_id_from_tags(&ns_event.tags) else {
+        return Err(Error::ChannelIdNotFound(ns_event.id));
+    };
+
+    if let Some(db_event) = DbEvent::insert(pool, relay_url, &ns_event).await? {
+        let is_users = db_event.pubkey == keys.public_key();
+        let ch_msg = DbChannelMessage::insert_confirmed(pool, &db_event, is_users).await?;
+        // kept in the database, the moderators can change
+        let hidden = !is_users
+            && channel_moderation(pool, cache_pool, &channel_id)
+                .await?
+                .map_or(false, |moderation| moderation.hides(&ch_msg));
+        let mentioned = ChannelMention::mentions_user(&ns_event, &keys.public_key())
+            || owner_broadcast(pool, cache_pool, &channel_id, &ns_event).await?;
+        if !is_users && !hidden {
+            let keywords = ChannelKeywords::fetch(pool, &channel_id).await?;
+            // being mentioned wins over a watched word
+            let keyword = if mentioned {
+                None
+            } else {
+                ChannelKeywords::matching(&ns_event.content, &keywords)
+            };
+            if mentioned || keyword.is_some() {
+                ChannelMention::insert(pool, ch_msg.event_id, &channel_id, keyword).await?;
+            }
+        }
+
+        let rows_affected =
+            ChannelCache::insert_member(cache_pool, &channel_id, &db_event.pubkey).await?;
+        if rows_affected != 0 {
+            // TODO: check if this is IO heavy
+            if let Some(cache) = ChannelCache::fetch_by_channel_id(cache_pool, &channel_id).await? {
+                let _ = output.send(BackendEvent::ChannelCacheUpdated(cache)).await;
+            } else {
+                tracing::error!(
+                    "Failed to fetch channel cache for channel_id: {}",
+                    &channel_id
+                );
+            }
+        }
+
+        if !hidden {
+            let _ = output
+                .send(BackendEvent::ReceivedChannelMessage(
+                    channel_id,
+                    ch_msg.into(),
+                ))
+                .await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores a hide or mute event, the channel is told when its owner or a moderator wrote it
+async fn handle_moderation(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+    ns_event: &nostr::Event,
+) -> Result<(), Error> {
+    let moderation = DbChannelModeration::from_ns_event(ns_event)?;
+    if !moderation.insert(pool).await? {
+        return Ok(());
+    }
+    let honored = ChannelCache::fetch_by_channel_id(cache_pool, &moderation.channel_id)
+        .await?
+        .map_or(false, |cache| cache.can_moderate(&moderation.author));
+    if honored {
+        _ = output
+            .send(BackendEvent::ChannelModerated(moderation.channel_id))
+            .await;
+    }
+    Ok(())
+}
+
+/// Hide and mute events of the channel owner and moderators,
+/// `None` while the channel creation wasn't received
+pub async fn channel_moderation(
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+    channel_id: &EventId,
+) -> Result<Option<Moderation>, Error> {
+    match ChannelCache::fetch_by_channel_id(cache_pool, channel_id).await? {
+        Some(cache) => Ok(Some(Moderation::fetch(pool, &cache).await?)),
+        None => Ok(None),
+    }
+}
+
+/// `@here` from the channel owner, only subscribers are notified
+async fn owner_broadcast(
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+    channel_id: &EventId,
+    ns_event: &nostr::Event,
+) -> Result<bool, Error> {
+    if !ChannelMention::is_broadcast(ns_event) {
+        return Ok(false);
+    }
+    let is_owner = ChannelCache::fetch_by_channel_id(cache_pool, channel_id)
+        .await?
+        .map_or(false, |cache| cache.creator_pubkey == ns_event.pubkey);
+    Ok(is_owner && ChannelSubscription::is_subscribed(pool, channel_id).await?)
+}
@@ -0,0 +1,59 @@
+use crate::db::channel_subscription::{CHANNEL_READ_ID, CHANNEL_READ_KIND};
+use crate::db::{DbContact, DbEvent, ReadMarkers};
+use crate::net::send_unread_counts;
+
+use nostr::nips::nip04;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{Filter, Kind, Tag};
+
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+/// Channel read positions published by the user's other devices
+pub struct ChannelReadHandler;
+
+impl KindHandler for ChannelReadHandler {
+    fn name(&self) -> &'static str {
+        "ChannelRead"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::Custom(CHANNEL_READ_KIND)]
+    }
+    fn filters(
+        &self,
+        pubkey: XOnlyPublicKey,
+        _contacts: &[DbContact],
+        _last_event: &Option<DbEvent>,
+    ) -> Vec<Filter> {
+        // replaceable, the latest one is fetched regardless of last_event
+        vec![Filter::new()
+            .kind(Kind::Custom(CHANNEL_READ_KIND))
+            .author(pubkey.to_string())]
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            // other applications use the same kind with their own `d` tag
+            let is_read_markers = ns_event
+                .tags
+                .iter()
+                .any(|tag| matches!(tag, Tag::Identifier(id) if id == CHANNEL_READ_ID));
+            if ns_event.pubkey != ctx.keys.public_key() || !is_read_markers {
+                return Ok(());
+            }
+            let Ok(secret_key) = ctx.keys.secret_key() else {
+                return Ok(());
+            };
+            let Ok(content) = nip04::decrypt(&secret_key, &ns_event.pubkey, &ns_event.content)
+            else {
+                tracing::warn!("Undecryptable channel read positions: {}", ns_event.id);
+                return Ok(());
+            };
+            let markers: ReadMarkers = serde_json::from_str(&content)?;
+            let moved = markers.apply(ctx.pool).await?;
+            if !moved.is_empty() {
+                tracing::debug!("Read positions synced for {} channels", moved.len());
+                send_unread_counts(ctx.output, ctx.pool).await?;
+            }
+            Ok(())
+        })
+    }
+}
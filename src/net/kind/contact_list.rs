@@ -1,15 +1,62 @@
 use crate::{
-    db::{DbContact, DbRelayResponse},
+    db::{DbContact, DbFollower, DbRelayResponse},
     error::Error,
     utils::ns_event_to_millis,
 };
 use futures_util::SinkExt;
-use nostr::{Keys, Kind};
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{Filter, Keys, Kind};
 use sqlx::SqlitePool;
 use url::Url;
 
 use crate::{db::DbEvent, net::BackendEvent};
 
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+pub struct ContactListHandler;
+
+impl KindHandler for ContactListHandler {
+    fn name(&self) -> &'static str {
+        "ContactList"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::ContactList]
+    }
+    fn setup<'a>(&'a self, pool: &'a SqlitePool) -> HandlerFuture<'a> {
+        Box::pin(async move { Ok(DbFollower::create_tables(pool).await?) })
+    }
+    fn filters(
+        &self,
+        pubkey: XOnlyPublicKey,
+        _contacts: &[DbContact],
+        _last_event: &Option<DbEvent>,
+    ) -> Vec<Filter> {
+        // the user's own list comes with the built-in subscription
+        vec![Filter::new().kind(Kind::ContactList).pubkeys(vec![pubkey])]
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            if ns_event.pubkey != ctx.keys.public_key() {
+                // only the user's list replaces the stored one
+                return handle_other_contact_list(ctx.output, ctx.keys, ctx.pool, &ns_event).await;
+            }
+            if let Some(db_event) = received_contact_list(ctx.pool, ctx.url, &ns_event).await? {
+                handle_contact_list(ctx.output, ctx.keys, ctx.pool, ctx.url, db_event).await?;
+            }
+            Ok(())
+        })
+    }
+    fn confirmed<'a>(&'a self, ctx: HandlerCtx<'a>, db_event: DbEvent) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            _ = ctx
+                .output
+                .send(BackendEvent::ConfirmedContactList(db_event))
+                .await;
+            Ok(())
+        })
+    }
+}
+
 pub async fn received_contact_list(
     pool: &SqlitePool,
     url: &Url,
@@ -28,10 +75,16 @@ pub async fn received_contact_list(
                 tracing::info!("ContactList is older than the last one");
                 return Ok(None);
             } else {
-                // delete old and insert new contact list
+                // replace the old contact list, the contacts still in it are updated later
                 tracing::info!("ContactList is newer than the last one");
                 DbEvent::delete(pool, db_event.event_id).await?;
-                DbContact::delete_all(pool).await?;
+                let kept: Vec<_> = ns_event
+                    .tags
+                    .iter()
+                    .filter_map(|t| DbContact::from_tag(t).ok())
+                    .map(|c| c.pubkey().to_owned())
+                    .collect();
+                DbContact::delete_missing(pool, &kept).await?;
             }
         }
         None => {
@@ -52,8 +105,6 @@ pub async fn handle_contact_list(
 ) -> Result<(), Error> {
     if db_event.pubkey == keys.public_key() {
         handle_user_contact_list(output, keys, pool, url, db_event).await?;
-    } else {
-        handle_other_contact_list(db_event).await?;
     }
     Ok(())
 }
@@ -86,10 +137,19 @@ async fn handle_user_contact_list(
     Ok(())
 }
 
-async fn handle_other_contact_list(_db_event: DbEvent) -> Result<(), Error> {
-    // Others ContactList That Im in
-    // which means that someone else added me to their contact list
-    // so I could build a followers list from this
-    tracing::info!("*** Others ContactList That Im in ***");
+/// Someone else's contact list, indexed when the user is in it
+async fn handle_other_contact_list(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    pool: &SqlitePool,
+    ns_event: &nostr::Event,
+) -> Result<(), Error> {
+    let Some(follower) = DbFollower::from_contact_list(ns_event, &keys.public_key())? else {
+        return Ok(());
+    };
+    if DbFollower::insert(pool, &follower).await? {
+        tracing::info!("Added to the contact list of {}", follower.pubkey);
+        let _ = output.send(BackendEvent::NewFollower(follower)).await;
+    }
     Ok(())
 }
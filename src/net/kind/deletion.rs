@@ -0,0 +1,230 @@
+use crate::db::{DbChannelMessage, DbContact, DbEvent, DbEventDeletion, DbMessage, DbReaction};
+use crate::error::Error;
+use crate::net::filters::deletions_filter;
+use crate::net::{fetch_chat_info, BackendEvent};
+use crate::utils::{channel_id_from_tags, ns_event_to_naive};
+
+use chrono::NaiveDateTime;
+use futures_util::SinkExt;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{Filter, Keys, Kind, Tag};
+use sqlx::SqlitePool;
+
+use super::{send_reactions, HandlerCtx, HandlerFuture, KindHandler};
+
+/// NIP-09 deletions of direct messages, channel messages and reactions
+pub struct DeletionHandler;
+
+impl KindHandler for DeletionHandler {
+    fn name(&self) -> &'static str {
+        "Deletion"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::EventDeletion]
+    }
+    fn filters(
+        &self,
+        pubkey: XOnlyPublicKey,
+        contacts: &[DbContact],
+        last_event: &Option<DbEvent>,
+    ) -> Vec<Filter> {
+        vec![deletions_filter(pubkey, contacts, last_event)]
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            // stored so the next login asks only for newer ones
+            DbEvent::insert(ctx.pool, ctx.url, &ns_event).await?;
+            let retracted_at = ns_event_to_naive(ns_event.created_at)?;
+            let targets = ns_event.tags.iter().filter_map(|tag| match tag {
+                Tag::Event(event_hash, _, _) => Some(event_hash),
+                _ => None,
+            });
+            for event_hash in targets {
+                // kept for a target that arrives later, relays send in any order
+                DbEventDeletion::insert(ctx.pool, event_hash, &ns_event.pubkey, retracted_at)
+                    .await?;
+                let Some(db_event) = DbEvent::fetch_hash(ctx.pool, event_hash).await? else {
+                    continue;
+                };
+                // only the author can delete an event
+                if db_event.pubkey != ns_event.pubkey {
+                    tracing::debug!("Deletion of someone else's event: {}", event_hash);
+                    continue;
+                }
+                retract_event(ctx.output, ctx.keys, ctx.pool, &db_event, retracted_at).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Marks the message deleted by its author and tells the views to hide it.
+/// A deleted reaction is removed and the counts of its message sent again
+pub async fn retract_event(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    pool: &SqlitePool,
+    db_event: &DbEvent,
+    retracted_at: NaiveDateTime,
+) -> Result<(), Error> {
+    match db_event.kind {
+        Kind::EncryptedDirectMessage => {
+            if !DbMessage::retract(pool, db_event.event_id, retracted_at).await? {
+                return Ok(());
+            }
+            let Some(db_message) = DbMessage::fetch_by_event(pool, db_event.event_id).await? else {
+                return Ok(());
+            };
+            let Some(db_contact) = DbContact::fetch_one(pool, &db_message.chat_pubkey).await?
+            else {
+                return Ok(());
+            };
+            let chat_info = fetch_chat_info(keys, pool, db_contact.pubkey()).await?;
+            _ = output
+                .send(BackendEvent::DmRetracted(
+                    db_contact,
+                    db_event.event_id,
+                    chat_info,
+                ))
+                .await;
+        }
+        Kind::ChannelMessage => {
+            if !DbChannelMessage::retract(pool, db_event.event_id, retracted_at).await? {
+                return Ok(());
+            }
+            if let Some(channel_id) = channel_id_from_tags(&db_event.tags) {
+                _ = output
+                    .send(BackendEvent::ChannelMessageRetracted(
+                        channel_id,
+                        db_event.event_id,
+                    ))
+                    .await;
+            }
+        }
+        Kind::Reaction => {
+            let reaction = DbReaction::from_ns_event(&db_event.to_ns_event()?)?;
+            DbReaction::delete(pool, &reaction.reaction_hash).await?;
+            send_reactions(output, keys, pool, &reaction.target_hash).await?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TestDatabase;
+    use crate::net::kind::dm::handle_dm;
+    use crate::test_support::{generate, AccountSpec};
+    use crate::types::chat_message::RETRACTED_TEXT;
+    use crate::utils::{deletion_builder, dm_builder};
+    use url::Url;
+
+    #[tokio::test]
+    async fn only_the_author_retracts_a_message() {
+        let db = TestDatabase::new().await.unwrap();
+        let (pool, cache_pool) = (db.pool(), db.cache_pool());
+        let spec = AccountSpec {
+            contacts: 1,
+            messages_per_contact: 3,
+            channels: 1,
+            members_per_channel: 1,
+            messages_per_channel: 2,
+            ..AccountSpec::default()
+        };
+        let account = generate(pool, cache_pool, &spec).await.unwrap();
+        let keys = &account.keys;
+        let url = Url::parse("wss://relay.example.com").unwrap();
+        let (mut output, mut events) = futures::channel::mpsc::channel(10);
+
+        let chat = DbMessage::fetch_chat(pool, &account.contacts[0])
+            .await
+            .unwrap();
+        let channel = DbChannelMessage::fetch(pool, &account.channels[0])
+            .await
+            .unwrap();
+        // newest first, the user wrote the last one and the contact the one before
+        let (own_dm, contact_dm) = (&chat[0], &chat[1]);
+        let own_channel_msg = channel.iter().find(|m| m.is_users).unwrap();
+        let mut hashes = vec![];
+        for event_id in [
+            contact_dm.event_id,
+            own_dm.event_id,
+            own_channel_msg.event_id,
+        ] {
+            let db_event = DbEvent::fetch_id(pool, event_id).await.unwrap().unwrap();
+            hashes.push(db_event.event_hash);
+        }
+
+        for event_hash in &hashes {
+            let deletion = deletion_builder(event_hash).to_event(keys).unwrap();
+            let ctx = HandlerCtx {
+                output: &mut output,
+                keys,
+                pool,
+                cache_pool,
+                url: &url,
+            };
+            DeletionHandler.received(ctx, deletion).await.unwrap();
+        }
+
+        let Ok(Some(BackendEvent::DmRetracted(contact, event_id, chat_info))) = events.try_next()
+        else {
+            panic!("expected the DM to be retracted");
+        };
+        assert_eq!(contact.pubkey(), &account.contacts[0]);
+        assert_eq!(event_id, own_dm.event_id);
+        assert_eq!(chat_info.unwrap().last_message, RETRACTED_TEXT);
+        assert!(matches!(
+            events.try_next(),
+            Ok(Some(BackendEvent::ChannelMessageRetracted(channel_id, event_id)))
+                if channel_id == account.channels[0] && event_id == own_channel_msg.event_id
+        ));
+        assert!(events.try_next().is_err());
+
+        let chat = DbMessage::fetch_chat(pool, &account.contacts[0])
+            .await
+            .unwrap();
+        assert!(chat[0].retracted_at.is_some());
+        assert!(chat[1].retracted_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn deletion_before_the_message_is_kept() {
+        let db = TestDatabase::new().await.unwrap();
+        let (pool, cache_pool) = (db.pool(), db.cache_pool());
+        let keys = Keys::generate();
+        let contact = Keys::generate();
+        let url = Url::parse("wss://relay.example.com").unwrap();
+        let (mut output, mut events) = futures::channel::mpsc::channel(10);
+
+        let dm = dm_builder(&keys, &contact.public_key(), None, "oops")
+            .unwrap()
+            .to_event(&keys)
+            .unwrap();
+        for author in [&contact, &keys] {
+            let deletion = deletion_builder(&dm.id).to_event(author).unwrap();
+            let ctx = HandlerCtx {
+                output: &mut output,
+                keys: &keys,
+                pool,
+                cache_pool,
+                url: &url,
+            };
+            DeletionHandler.received(ctx, deletion).await.unwrap();
+        }
+        assert!(events.try_next().is_err());
+
+        handle_dm(&mut output, pool, &keys, &url, dm).await.unwrap();
+
+        let Ok(Some(BackendEvent::ReceivedDM { chat_message, .. })) = events.try_next() else {
+            panic!("expected the DM to be received");
+        };
+        assert!(chat_message.is_retracted());
+        let chat = DbMessage::fetch_chat(pool, &contact.public_key())
+            .await
+            .unwrap();
+        assert!(chat[0].retracted_at.is_some());
+    }
+}
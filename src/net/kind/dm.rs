@@ -1,18 +1,38 @@
-use crate::db::{DbContact, DbEvent, DbMessage, MessageTagInfo};
+use crate::db::{DbContact, DbEvent, DbMessage, DbRelayResponse, MessageTagInfo};
 use crate::error::Error;
 use crate::net::BackendEvent;
 use crate::types::ChatMessage;
 
 use futures_util::SinkExt;
 use nostr::secp256k1::XOnlyPublicKey;
-use nostr::{EventId, Keys};
+use nostr::{EventId, Keys, Kind};
 use sqlx::SqlitePool;
 use url::Url;
 
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+pub struct DmHandler;
+
+impl KindHandler for DmHandler {
+    fn name(&self) -> &'static str {
+        "Dm"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::EncryptedDirectMessage]
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move { handle_dm(ctx.output, ctx.pool, ctx.keys, ctx.url, ns_event).await })
+    }
+    fn confirmed<'a>(&'a self, ctx: HandlerCtx<'a>, db_event: DbEvent) -> HandlerFuture<'a> {
+        Box::pin(
+            async move { pending_dm_confirmed(ctx.output, ctx.pool, ctx.keys, &db_event).await },
+        )
+    }
+}
+
 pub async fn handle_dm(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
     pool: &SqlitePool,
-    cache_pool: &SqlitePool,
     keys: &Keys,
     url: &Url,
     ns_event: nostr::Event,
@@ -25,7 +45,7 @@ pub async fn handle_dm(
     if let Some(db_event) = DbEvent::insert(pool, url, &ns_event).await? {
         let db_message =
             DbMessage::insert_confirmed(pool, &db_event, &chat_pubkey, is_users).await?;
-        let db_contact = DbContact::fetch_insert(pool, cache_pool, &db_message.chat_pubkey).await?;
+        let db_contact = DbContact::fetch_insert(pool, &db_message.chat_pubkey).await?;
         let decrypted_content = db_message.decrypt_message(keys, &tag_info)?;
 
         let chat_message = if is_users {
@@ -41,6 +61,9 @@ pub async fn handle_dm(
                 relay_url: url.to_owned(),
             })
             .await;
+    } else if let Some(db_event) = DbEvent::fetch_hash(pool, &ns_event.id).await? {
+        // already stored, but record that it also arrived from this relay
+        DbRelayResponse::insert_ok(pool, url, &db_event).await?;
     }
 
     Ok(())
@@ -71,6 +94,20 @@ pub async fn pending_dm_confirmed(
     Ok(())
 }
 
+/// Recreates the message rows of stored DMs that lost them, returns how many
+pub async fn restore_dm_messages(pool: &SqlitePool, keys: &Keys) -> Result<u64, Error> {
+    let mut restored = 0;
+    for db_event in DbEvent::fetch_dms_without_message(pool).await? {
+        if let Some((is_users, _tag_info, chat_pubkey)) =
+            verify_dm(&db_event.event_hash, &db_event.pubkey, &db_event.tags, keys)?
+        {
+            DbMessage::insert_confirmed(pool, &db_event, &chat_pubkey, is_users).await?;
+            restored += 1;
+        }
+    }
+    Ok(restored)
+}
+
 fn verify_dm(
     event_hash: &EventId,
     event_pubkey: &XOnlyPublicKey,
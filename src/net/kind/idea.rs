@@ -43,8 +43,7 @@ impl DmData {
     ) -> Result<ChatMessage, MyError> {
         let db_message =
             DbMessage::insert_confirmed(&self.pool, &db_event, &chat_pubkey, is_users).await?;
-        let db_contact =
-            DbContact::fetch_insert(&self.pool, &self.cache_pool, &db_message.chat_pubkey).await?;
+        let db_contact = DbContact::fetch_insert(&self.pool, &db_message.chat_pubkey).await?;
         let decrypted_content = db_message.decrypt_message(&self.keys, &tag_info)?;
         Ok(ChatMessage::new(
             &db_message,
@@ -0,0 +1,60 @@
+use crate::db::live_activity::LIVE_ACTIVITY_KIND;
+use crate::db::{DbContact, DbEvent, DbLiveActivity};
+use crate::error::Error;
+use crate::net::BackendEvent;
+
+use futures_util::SinkExt;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{Filter, Kind};
+use sqlx::SqlitePool;
+
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+/// NIP-53 live activities hosted or published by contacts
+pub struct LiveActivityHandler;
+
+impl KindHandler for LiveActivityHandler {
+    fn name(&self) -> &'static str {
+        "LiveActivity"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::Custom(LIVE_ACTIVITY_KIND)]
+    }
+    fn setup<'a>(&'a self, pool: &'a SqlitePool) -> HandlerFuture<'a> {
+        Box::pin(async move { Ok(DbLiveActivity::create_tables(pool).await?) })
+    }
+    fn filters(
+        &self,
+        _pubkey: XOnlyPublicKey,
+        contacts: &[DbContact],
+        _last_event: &Option<DbEvent>,
+    ) -> Vec<Filter> {
+        if contacts.is_empty() {
+            return vec![];
+        }
+        // replaceable events, fetch the latest ones regardless of last_event
+        let pubkeys: Vec<_> = contacts.iter().map(|c| c.pubkey().to_owned()).collect();
+        let authors: Vec<_> = pubkeys.iter().map(|p| p.to_string()).collect();
+        vec![
+            Filter::new()
+                .kind(Kind::Custom(LIVE_ACTIVITY_KIND))
+                .authors(authors),
+            Filter::new()
+                .kind(Kind::Custom(LIVE_ACTIVITY_KIND))
+                .pubkeys(pubkeys),
+        ]
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let activity = DbLiveActivity::from_ns_event(&ns_event)?;
+            if DbLiveActivity::upsert(ctx.pool, &activity).await? {
+                let host = DbContact::fetch_one(ctx.pool, &activity.host).await?;
+                _ = ctx
+                    .output
+                    .send(BackendEvent::LiveActivityUpdated(activity, host))
+                    .await;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,52 @@
+use crate::db::{DbEvent, ProfileCache};
+use crate::error::Error;
+use crate::net::BackendEvent;
+
+use futures_util::SinkExt;
+use nostr::Kind;
+use sqlx::SqlitePool;
+use url::Url;
+
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+pub struct MetadataHandler;
+
+impl KindHandler for MetadataHandler {
+    fn name(&self) -> &'static str {
+        "Metadata"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::Metadata]
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            insert_metadata_event(ctx.output, ctx.cache_pool, ctx.url, ns_event).await
+        })
+    }
+    fn confirmed<'a>(&'a self, ctx: HandlerCtx<'a>, db_event: DbEvent) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let ns_event = db_event.to_ns_event()?;
+            insert_metadata_event(ctx.output, ctx.cache_pool, ctx.url, ns_event).await
+        })
+    }
+}
+
+pub async fn insert_metadata_event(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    cache_pool: &SqlitePool,
+    relay_url: &Url,
+    ns_event: nostr::Event,
+) -> Result<(), Error> {
+    let pubkey = ns_event.pubkey;
+    tracing::debug!("Received metadata event for public key: {}", &pubkey);
+
+    let rows_changed = ProfileCache::insert(cache_pool, relay_url, ns_event).await?;
+
+    if rows_changed == 0 {
+        tracing::debug!("Cache already up to date");
+    }
+
+    _ = output.send(BackendEvent::UpdatedMetadata(pubkey)).await;
+
+    Ok(())
+}
@@ -1,4 +1,22 @@
+mod channel;
+mod channel_read;
 mod contact_list;
+mod deletion;
 mod dm;
+mod live_activity;
+mod metadata;
+mod mute_list;
+mod poll;
+mod reaction;
+mod registry;
+pub use channel::*;
+pub use channel_read::*;
 pub use contact_list::*;
+pub use deletion::*;
 pub use dm::*;
+pub use live_activity::*;
+pub use metadata::*;
+pub use mute_list::*;
+pub use poll::*;
+pub use reaction::*;
+pub use registry::{HandlerCtx, HandlerFuture, KindHandler, KindRegistry};
@@ -0,0 +1,63 @@
+use crate::db::blocked::MUTE_LIST_KIND;
+use crate::db::{DbContact, DbEvent};
+use crate::net::{muted_pubkeys, BackendEvent};
+use crate::utils::ns_event_to_naive;
+
+use futures_util::SinkExt;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{Filter, Kind};
+
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+/// The user's NIP-51 mute list, stored so a mute from this app goes into the
+/// list published by other clients instead of replacing it
+pub struct MuteListHandler;
+
+impl KindHandler for MuteListHandler {
+    fn name(&self) -> &'static str {
+        "MuteList"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::Custom(MUTE_LIST_KIND)]
+    }
+    fn filters(
+        &self,
+        pubkey: XOnlyPublicKey,
+        _contacts: &[DbContact],
+        _last_event: &Option<DbEvent>,
+    ) -> Vec<Filter> {
+        // replaceable, the latest one is fetched regardless of last_event
+        vec![Filter::new()
+            .kind(Kind::Custom(MUTE_LIST_KIND))
+            .author(pubkey.to_string())]
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let user = ctx.keys.public_key();
+            if ns_event.pubkey != user {
+                return Ok(());
+            }
+            // relays may still serve lists it replaced
+            let created_at = ns_event_to_naive(ns_event.created_at)?;
+            let last = DbEvent::fetch_last_kind_pubkey(ctx.pool, ns_event.kind, &user).await?;
+            if last.map_or(false, |last| last.created_at >= created_at) {
+                return Ok(());
+            }
+            if DbEvent::insert(ctx.pool, ctx.url, &ns_event)
+                .await?
+                .is_some()
+            {
+                let muted = muted_pubkeys(&ns_event.tags);
+                _ = ctx
+                    .output
+                    .send(BackendEvent::GotMutedUsers(muted.into_iter().collect()))
+                    .await;
+            }
+            Ok(())
+        })
+    }
+    fn confirmed<'a>(&'a self, _ctx: HandlerCtx<'a>, _db_event: DbEvent) -> HandlerFuture<'a> {
+        // stored when confirmed, the views got the list when it was sent
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -0,0 +1,67 @@
+use crate::db::poll::{POLL_KIND, POLL_RESPONSE_KIND};
+use crate::db::{DbEvent, DbPoll, DbPollVote, PollTally};
+use crate::error::Error;
+use crate::net::BackendEvent;
+
+use futures_util::SinkExt;
+use nostr::{Keys, Kind};
+use sqlx::SqlitePool;
+
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+pub struct PollHandler;
+
+impl KindHandler for PollHandler {
+    fn name(&self) -> &'static str {
+        "Poll"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::Custom(POLL_KIND), Kind::Custom(POLL_RESPONSE_KIND)]
+    }
+    fn setup<'a>(&'a self, pool: &'a SqlitePool) -> HandlerFuture<'a> {
+        Box::pin(async move { Ok(DbPoll::create_tables(pool).await?) })
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move { handle_poll_event(ctx.output, ctx.keys, ctx.pool, &ns_event).await })
+    }
+    fn confirmed<'a>(&'a self, ctx: HandlerCtx<'a>, db_event: DbEvent) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let ns_event = db_event.to_ns_event()?;
+            handle_poll_event(ctx.output, ctx.keys, ctx.pool, &ns_event).await
+        })
+    }
+}
+
+/// Stores a poll or a vote and sends the updated tally.
+/// Safe to call again with the same event.
+pub async fn handle_poll_event(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    pool: &SqlitePool,
+    ns_event: &nostr::Event,
+) -> Result<(), Error> {
+    match ns_event.kind {
+        Kind::Custom(POLL_KIND) => {
+            let poll = DbPoll::from_ns_event(ns_event)?;
+            if DbPoll::insert(pool, &poll).await? {
+                let tally = PollTally::fetch(pool, &poll, &keys.public_key()).await?;
+                _ = output.send(BackendEvent::ReceivedPoll(poll, tally)).await;
+            }
+        }
+        Kind::Custom(POLL_RESPONSE_KIND) => {
+            let vote = DbPollVote::from_ns_event(ns_event)?;
+            if !DbPollVote::upsert(pool, &vote).await? {
+                return Ok(());
+            }
+            // votes can arrive before their poll
+            if let Some(poll) = DbPoll::fetch_one(pool, &vote.poll_hash).await? {
+                let tally = PollTally::fetch(pool, &poll, &keys.public_key()).await?;
+                _ = output
+                    .send(BackendEvent::PollTallyUpdated(poll.channel_id, tally))
+                    .await;
+            }
+        }
+        other => return Err(Error::UnexpectedEventKind(other.as_u32())),
+    }
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use crate::db::{DbContact, DbEvent, DbEventDeletion, DbReaction, ReactionCount};
+use crate::error::Error;
+use crate::net::filters::reactions_filter;
+use crate::net::BackendEvent;
+
+use futures_util::SinkExt;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{Filter, Keys, Kind};
+use sqlx::SqlitePool;
+
+use super::{HandlerCtx, HandlerFuture, KindHandler};
+
+/// NIP-25 reactions to direct and channel messages
+pub struct ReactionHandler;
+
+impl KindHandler for ReactionHandler {
+    fn name(&self) -> &'static str {
+        "Reaction"
+    }
+    fn kinds(&self) -> Vec<Kind> {
+        vec![Kind::Reaction]
+    }
+    fn setup<'a>(&'a self, pool: &'a SqlitePool) -> HandlerFuture<'a> {
+        Box::pin(async move { Ok(DbReaction::create_tables(pool).await?) })
+    }
+    fn filters(
+        &self,
+        pubkey: XOnlyPublicKey,
+        _contacts: &[DbContact],
+        last_event: &Option<DbEvent>,
+    ) -> Vec<Filter> {
+        reactions_filter(pubkey, last_event)
+    }
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            // kept with the other events so it goes when the message is deleted
+            DbEvent::insert(ctx.pool, ctx.url, &ns_event).await?;
+            handle_reaction(ctx.output, ctx.keys, ctx.pool, &ns_event).await
+        })
+    }
+    fn confirmed<'a>(&'a self, ctx: HandlerCtx<'a>, db_event: DbEvent) -> HandlerFuture<'a> {
+        Box::pin(async move {
+            let ns_event = db_event.to_ns_event()?;
+            handle_reaction(ctx.output, ctx.keys, ctx.pool, &ns_event).await
+        })
+    }
+}
+
+/// Stores the reaction and sends the new counts of the message it reacts to.
+/// Reactions to messages not stored yet are counted once the message arrives,
+/// reactions their author already deleted are dropped
+pub async fn handle_reaction(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    pool: &SqlitePool,
+    ns_event: &nostr::Event,
+) -> Result<(), Error> {
+    let reaction = DbReaction::from_ns_event(ns_event)?;
+    if DbEventDeletion::exists(pool, &reaction.reaction_hash, &reaction.author).await? {
+        tracing::debug!(
+            "Reaction deleted before it arrived: {}",
+            reaction.reaction_hash
+        );
+        return Ok(());
+    }
+    if !DbReaction::insert(pool, &reaction).await? {
+        return Ok(());
+    }
+    send_reactions(output, keys, pool, &reaction.target_hash).await
+}
+
+pub async fn send_reactions(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    pool: &SqlitePool,
+    target_hash: &nostr::EventId,
+) -> Result<(), Error> {
+    if let Some(db_event) = DbEvent::fetch_hash(pool, target_hash).await? {
+        let reactions =
+            ReactionCount::fetch_one(pool, db_event.event_id, &keys.public_key()).await?;
+        _ = output
+            .send(BackendEvent::ReactionsUpdated(db_event.event_id, reactions))
+            .await;
+    }
+    Ok(())
+}
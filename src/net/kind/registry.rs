@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{Filter, Keys, Kind};
+use sqlx::SqlitePool;
+use url::Url;
+
+use crate::db::{DbContact, DbEvent};
+use crate::error::Error;
+use crate::net::BackendEvent;
+
+use super::{
+    ChannelHandler, ChannelReadHandler, ContactListHandler, DeletionHandler, DmHandler,
+    LiveActivityHandler, MetadataHandler, MuteListHandler, PollHandler, ReactionHandler,
+};
+
+pub type HandlerFuture<'a> = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>;
+
+/// What a handler gets to process one event
+pub struct HandlerCtx<'a> {
+    pub output: &'a mut futures::channel::mpsc::Sender<BackendEvent>,
+    pub keys: &'a Keys,
+    pub pool: &'a SqlitePool,
+    pub cache_pool: &'a SqlitePool,
+    pub url: &'a Url,
+}
+
+/// Self-contained support for one or more event kinds
+pub trait KindHandler: Send + Sync {
+    /// Used to name the handler subscription
+    fn name(&self) -> &'static str;
+
+    /// Kinds routed to this handler
+    fn kinds(&self) -> Vec<Kind>;
+
+    /// Creates the tables owned by the handler, runs on every login
+    fn setup<'a>(&'a self, _pool: &'a SqlitePool) -> HandlerFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Filters subscribed on login, empty when the kind arrives
+    /// through the built-in subscriptions
+    fn filters(
+        &self,
+        _pubkey: XOnlyPublicKey,
+        _contacts: &[DbContact],
+        _last_event: &Option<DbEvent>,
+    ) -> Vec<Filter> {
+        vec![]
+    }
+
+    /// Event received from a relay
+    fn received<'a>(&'a self, ctx: HandlerCtx<'a>, ns_event: nostr::Event) -> HandlerFuture<'a>;
+
+    /// Event sent by the user, confirmed by a relay
+    fn confirmed<'a>(&'a self, _ctx: HandlerCtx<'a>, db_event: DbEvent) -> HandlerFuture<'a> {
+        Box::pin(async move { Err(Error::NotSubscribedToKind(db_event.kind)) })
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct KindRegistry {
+    handlers: Vec<Arc<dyn KindHandler>>,
+    by_kind: HashMap<u32, Arc<dyn KindHandler>>,
+}
+
+impl KindRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Registry with the kinds supported out of the box
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(ChannelHandler);
+        registry.register(ChannelReadHandler);
+        registry.register(ContactListHandler);
+        registry.register(DeletionHandler);
+        registry.register(DmHandler);
+        registry.register(LiveActivityHandler);
+        registry.register(MetadataHandler);
+        registry.register(MuteListHandler);
+        registry.register(PollHandler);
+        registry.register(ReactionHandler);
+        registry
+    }
+    /// A kind registered twice goes to the last handler
+    pub fn register(&mut self, handler: impl KindHandler + 'static) {
+        let handler: Arc<dyn KindHandler> = Arc::new(handler);
+        for kind in handler.kinds() {
+            if let Some(old) = self.by_kind.insert(kind.as_u32(), handler.clone()) {
+                tracing::warn!(
+                    "{} replaced {} for kind {:?}",
+                    handler.name(),
+                    old.name(),
+                    kind
+                );
+            }
+        }
+        self.handlers.push(handler);
+    }
+    pub fn get(&self, kind: &Kind) -> Option<Arc<dyn KindHandler>> {
+        self.by_kind.get(&kind.as_u32()).cloned()
+    }
+    pub fn handlers(&self) -> impl Iterator<Item = &Arc<dyn KindHandler>> {
+        self.handlers.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Dummy;
+    impl KindHandler for Dummy {
+        fn name(&self) -> &'static str {
+            "dummy"
+        }
+        fn kinds(&self) -> Vec<Kind> {
+            vec![Kind::EncryptedDirectMessage, Kind::Custom(30_000)]
+        }
+        fn received<'a>(&'a self, _ctx: HandlerCtx<'a>, _event: nostr::Event) -> HandlerFuture<'a> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[test]
+    fn defaults_cover_existing_kinds() {
+        let registry = KindRegistry::with_defaults();
+        for kind in [
+            Kind::ChannelCreation,
+            Kind::ChannelMetadata,
+            Kind::ChannelMessage,
+            Kind::ContactList,
+            Kind::EncryptedDirectMessage,
+            Kind::Metadata,
+        ] {
+            assert!(registry.get(&kind).is_some(), "missing {:?}", kind);
+        }
+        assert!(registry.get(&Kind::Custom(30_000)).is_none());
+    }
+
+    #[test]
+    fn last_registered_handler_wins() {
+        let mut registry = KindRegistry::with_defaults();
+        registry.register(Dummy);
+        let handler = registry.get(&Kind::EncryptedDirectMessage).unwrap();
+        assert_eq!(handler.name(), "dummy");
+        assert_eq!(registry.get(&Kind::Custom(30_000)).unwrap().name(), "dummy");
+    }
+}
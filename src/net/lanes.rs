@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::error::BackendClosed;
+
+use super::ToBackend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// UI-critical commands, processed before anything else
+    High,
+    /// Bulk fetches
+    Low,
+}
+
+/// Commands that didn't fit in their lane, kept in order until the backend catches up.
+/// Holds at most `OVERFLOW_SIZE` commands
+#[derive(Debug, Default)]
+struct Overflow {
+    high: VecDeque<ToBackend>,
+    low: VecDeque<ToBackend>,
+}
+impl Overflow {
+    fn queue(&mut self, priority: Priority) -> &mut VecDeque<ToBackend> {
+        match priority {
+            Priority::High => &mut self.high,
+            Priority::Low => &mut self.low,
+        }
+    }
+    fn push(&mut self, priority: Priority, input: ToBackend) {
+        if self.len() >= OVERFLOW_SIZE {
+            // bulk fetches can be requested again, user actions can't
+            if let Some(dropped) = self.low.pop_front() {
+                tracing::warn!("Overflow full, dropping: {:?}", dropped);
+            } else {
+                // only user actions are waiting, the backend is stuck
+                tracing::error!(
+                    "Overflow full of {:?} commands, dropping: {:?}",
+                    priority,
+                    input
+                );
+                return;
+            }
+        }
+        self.queue(priority).push_back(input);
+    }
+    fn len(&self) -> usize {
+        self.high.len() + self.low.len()
+    }
+    fn is_empty(&self) -> bool {
+        self.high.is_empty() && self.low.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SharedOverflow(Arc<Mutex<Overflow>>);
+impl SharedOverflow {
+    fn lock(&self) -> MutexGuard<'_, Overflow> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Frontend side of the lanes
+#[derive(Debug, Clone)]
+pub(crate) struct LaneSender {
+    high: mpsc::Sender<ToBackend>,
+    low: mpsc::Sender<ToBackend>,
+    overflow: SharedOverflow,
+}
+impl LaneSender {
+    /// When its lane is full the command waits in the overflow queue,
+    /// it's only dropped when the overflow is full too
+    pub(crate) fn send(&self, input: ToBackend) -> Result<(), BackendClosed> {
+        let priority = input.priority();
+        let mut overflow = self.overflow.lock();
+
+        // keep the order, nothing jumps ahead of what is already waiting
+        if !overflow.queue(priority).is_empty() {
+            if self.lane(priority).is_closed() {
+                return Err(BackendClosed);
+            }
+            overflow.push(priority, input);
+            return Ok(());
+        }
+
+        match self.lane(priority).try_send(input) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(input)) => {
+                tracing::warn!("{:?} lane full, queueing: {:?}", priority, input);
+                overflow.push(priority, input);
+                Ok(())
+            }
+            Err(TrySendError::Closed(_)) => Err(BackendClosed),
+        }
+    }
+    fn lane(&self, priority: Priority) -> &mpsc::Sender<ToBackend> {
+        match priority {
+            Priority::High => &self.high,
+            Priority::Low => &self.low,
+        }
+    }
+}
+
+/// Backend side of the lanes
+pub struct LaneReceiver {
+    high: mpsc::Receiver<ToBackend>,
+    low: mpsc::Receiver<ToBackend>,
+    overflow: SharedOverflow,
+    congested: bool,
+}
+impl LaneReceiver {
+    /// High priority commands first, then bulk ones.
+    /// Returns `None` when the frontend is gone.
+    pub async fn recv(&mut self) -> Option<ToBackend> {
+        if let Ok(input) = self.high.try_recv() {
+            return Some(input);
+        }
+        if let Some(input) = self.overflow.lock().high.pop_front() {
+            return Some(input);
+        }
+        if let Ok(input) = self.low.try_recv() {
+            return Some(input);
+        }
+        if let Some(input) = self.overflow.lock().low.pop_front() {
+            return Some(input);
+        }
+
+        tokio::select! {
+            biased;
+            input = self.high.recv() => input,
+            input = self.low.recv() => input,
+        }
+    }
+
+    /// Returns the new state when the lanes started or stopped overflowing
+    pub fn congestion_changed(&mut self) -> Option<bool> {
+        let congested = !self.overflow.lock().is_empty();
+        if congested != self.congested {
+            self.congested = congested;
+            Some(congested)
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn lanes() -> (LaneSender, LaneReceiver) {
+    let (high_tx, high_rx) = mpsc::channel(HIGH_LANE_SIZE);
+    let (low_tx, low_rx) = mpsc::channel(LOW_LANE_SIZE);
+    let overflow = SharedOverflow::default();
+    (
+        LaneSender {
+            high: high_tx,
+            low: low_tx,
+            overflow: overflow.clone(),
+        },
+        LaneReceiver {
+            high: high_rx,
+            low: low_rx,
+            overflow,
+            congested: false,
+        },
+    )
+}
+
+const HIGH_LANE_SIZE: usize = 256;
+const LOW_LANE_SIZE: usize = 1024;
+const OVERFLOW_SIZE: usize = 4096;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn high_lane_goes_first() {
+        let (sender, mut receiver) = lanes();
+        sender.send(ToBackend::FetchContacts).unwrap();
+        sender.send(ToBackend::MessageSeen(1)).unwrap();
+
+        assert!(matches!(
+            receiver.recv().await,
+            Some(ToBackend::MessageSeen(1))
+        ));
+        assert!(matches!(
+            receiver.recv().await,
+            Some(ToBackend::FetchContacts)
+        ));
+    }
+
+    #[tokio::test]
+    async fn full_lane_overflows_in_order() {
+        let (sender, mut receiver) = lanes();
+        for id in 0..(HIGH_LANE_SIZE as i64 + 10) {
+            sender.send(ToBackend::MessageSeen(id)).unwrap();
+        }
+        assert_eq!(receiver.congestion_changed(), Some(true));
+
+        for expected in 0..(HIGH_LANE_SIZE as i64 + 10) {
+            match receiver.recv().await {
+                Some(ToBackend::MessageSeen(id)) => assert_eq!(id, expected),
+                other => panic!("unexpected: {:?}", other),
+            }
+        }
+        assert_eq!(receiver.congestion_changed(), Some(false));
+    }
+
+    #[test]
+    fn full_overflow_drops_bulk_then_new_commands() {
+        let mut overflow = Overflow::default();
+        overflow.push(Priority::Low, ToBackend::FetchContacts);
+        for id in 0..OVERFLOW_SIZE as i64 {
+            overflow.push(Priority::High, ToBackend::MessageSeen(id));
+        }
+        assert!(overflow.low.is_empty());
+        assert_eq!(overflow.len(), OVERFLOW_SIZE);
+
+        overflow.push(Priority::High, ToBackend::MessageSeen(-1));
+        assert_eq!(overflow.len(), OVERFLOW_SIZE);
+        assert!(matches!(
+            overflow.high.back(),
+            Some(ToBackend::MessageSeen(id)) if *id == OVERFLOW_SIZE as i64 - 1
+        ));
+    }
+}
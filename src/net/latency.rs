@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpStream;
+use url::Url;
+
+use super::TaskOutput;
+use crate::Error;
+
+/// Measures every relay at the same time, each result is sent as soon as it's ready
+pub fn spawn_latency_checks(
+    urls: Vec<Url>,
+    sender: tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+) {
+    for url in urls {
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            let latency = measure(&url).await;
+            _ = sender
+                .send(Ok(TaskOutput::RelayLatency(url, latency)))
+                .await;
+        });
+    }
+}
+
+/// Time to open a TCP connection to the relay, `None` when unreachable
+pub async fn measure(url: &Url) -> Option<Duration> {
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+    let start = Instant::now();
+    match tokio::time::timeout(LATENCY_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_stream)) => Some(start.elapsed()),
+        Ok(Err(e)) => {
+            tracing::debug!("Relay {} unreachable: {}", url, e);
+            None
+        }
+        Err(_) => {
+            tracing::debug!("Relay {} timed out", url);
+            None
+        }
+    }
+}
+
+const LATENCY_TIMEOUT: Duration = Duration::from_secs(4);
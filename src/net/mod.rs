@@ -1,24 +1,31 @@
 use chrono::NaiveDateTime;
+use futures::FutureExt;
 use futures_util::SinkExt;
+use futures_util::StreamExt;
 use iced::subscription;
 use nostr::Metadata;
 use ns_client::Subscription;
 use rfd::AsyncFileDialog;
 use serde::Serialize;
-use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::signal;
 use tokio::sync::broadcast;
-use tokio::sync::mpsc::error::TrySendError;
 use url::Url;
 
+use nostr::nips::nip04;
 use nostr::secp256k1::XOnlyPublicKey;
 use nostr::EventId;
+use nostr::Filter;
 use nostr::Keys;
 use nostr::Kind;
 use nostr::RelayMessage;
 use nostr::SubscriptionId;
+use nostr::Tag;
+use nostr::Timestamp;
 
 use ns_client::NotificationEvent;
 use ns_client::RelayEvent;
@@ -26,82 +33,218 @@ use ns_client::RelayPool;
 
 use crate::components::async_file_importer::FileFilter;
 use crate::components::chat_contact::ChatInfo;
+use crate::components::quick_switcher::{SwitcherItem, SwitcherTarget};
+use crate::config::AutoLockConfig;
 use crate::config::Config;
+use crate::config::DownloadConfig;
+use crate::config::MediaProxyConfig;
+use crate::config::Presence;
+use crate::config::PresenceConfig;
+use crate::config::RelayTuningConfig;
+use crate::config::SendKey;
+use crate::config::StartupView;
+use crate::config::StorageConfig;
+use crate::config::TextDirection;
+use crate::config::TimeFormatConfig;
+use crate::config::TranslationConfig;
 use crate::consts::NIPS_LIST_MARKDOWN;
+use crate::consts::YMD_FORMAT;
+use crate::db::blocked::MUTE_LIST_KIND;
+use crate::db::channel_mention::INBOX_LIMIT;
+use crate::db::contact_channels::rank;
+use crate::db::conversation_visit::prefetch_waves;
+use crate::db::dm_history::next_history_window;
+use crate::db::integrity::check_integrity;
+use crate::db::relay_activity::suggest;
+use crate::db::relay_profile;
+use crate::db::stats::compute_stats;
+use crate::db::storage::{count_reactions, delete_event, fetch_usage, prune};
+use crate::db::AccountStats;
+use crate::db::AttachmentDownload;
+use crate::db::AutoSaveAttachments;
+use crate::db::AutoTranslate;
+use crate::db::ChannelArchive;
 use crate::db::ChannelCache;
+use crate::db::ChannelKeywords;
+use crate::db::ChannelMention;
 use crate::db::ChannelSubscription;
+use crate::db::ContactActivity;
+use crate::db::ContactChannel;
+use crate::db::ContactSnapshot;
+use crate::db::ConversationVisit;
 use crate::db::Database;
 use crate::db::DbChannelMessage;
 use crate::db::DbContact;
 use crate::db::DbEvent;
+use crate::db::DbFollower;
+use crate::db::DbLabel;
+use crate::db::DbLiveActivity;
 use crate::db::DbMessage;
+use crate::db::DbPoll;
+use crate::db::DbReaction;
 use crate::db::DbRelay;
+use crate::db::DbRelayDocumentChange;
+use crate::db::DbRelayProfile;
 use crate::db::DbRelayResponse;
+use crate::db::DbRelayViolation;
+use crate::db::DbTemplate;
+use crate::db::DbTranslation;
+use crate::db::DmHistory;
+use crate::db::EventFilter;
+use crate::db::FollowedHashtags;
 use crate::db::ImageDownloaded;
+use crate::db::IntegrityReport;
+use crate::db::LabelNotify;
+use crate::db::Labels;
+use crate::db::LightningCheck;
 use crate::db::MessageTagInfo;
+use crate::db::PollTally;
 use crate::db::ProfileCache;
+use crate::db::PruneReport;
+use crate::db::ReactionCount;
+use crate::db::RelaySwitch;
+use crate::db::ResponseStatus;
+use crate::db::SigningLogEntry;
+use crate::db::StorageUsage;
+use crate::db::SyncDepth;
+use crate::db::Trash;
+use crate::db::TrashItem;
+use crate::db::TrashKind;
 use crate::db::UserConfig;
+use crate::db::ViolationKind;
+use crate::db::{
+    ContactMerge, DbBlocked, DbDraft, DraftTarget, ReadMarkers, RelayActivity, RelaySuggestion,
+    TuningChange,
+};
 use crate::error::BackendClosed;
+use crate::net::console::{parse_filters, ConsoleRow};
+use crate::net::filters::account_preview_filter;
 use crate::net::filters::channel_details_filter;
 use crate::net::filters::channel_members_metadata_filter;
 use crate::net::filters::channel_search_filter;
+use crate::net::filters::channels_creation_filter;
+use crate::net::filters::contact_channels_filter;
 use crate::net::filters::contact_list_filter;
+use crate::net::filters::dm_history_filter;
+use crate::net::filters::followed_hashtags_filter;
+use crate::net::filters::hashtag_feed_filter;
 use crate::net::filters::members_metadata_filter;
 use crate::net::filters::messages_filter;
+use crate::net::filters::messages_wave_filter;
+use crate::net::filters::requested_limit;
 use crate::net::filters::user_metadata_filter;
-use crate::net::kind::handle_contact_list;
-use crate::net::kind::handle_dm;
-use crate::net::kind::received_contact_list;
-use crate::net::ntp::spawn_ntp_request;
+use crate::net::flood_guard::Verdict;
+use crate::net::image_sanitize::crop_profile_image_file;
+use crate::net::ingress::Ingress;
+use crate::net::kind::channel_moderation;
+use crate::net::kind::handle_poll_event;
+use crate::net::kind::restore_dm_messages;
+use crate::net::kind::retract_event;
+use crate::net::kind::send_reactions;
+use crate::net::kind::HandlerCtx;
+use crate::net::ntp::{spawn_ntp_request, spawn_ntp_sync, NtpSync};
+use crate::net::relay_document::{document_changes, spawn_document_refresh};
 use crate::net::reqwest_client::fetch_latest_version;
+use crate::net::reqwest_client::fetch_supported_nips;
+use crate::net::reqwest_client::RelayDocumentFetch;
+use crate::net::reqwest_client::{
+    check_lnurl_pay, check_url_reachable, fetch_lnurl_invoice, resolve_nip05, Nip05Profile,
+};
+use crate::net::simulation::{dev_mode, DryRun, Simulation, SIMULATED_FAILURE};
+use crate::net::translation::translate;
 use crate::style;
+use crate::types::chat_message::RETRACTED_TEXT;
+use crate::types::contacts_to_csv;
+use crate::types::relays_to_text;
+use crate::types::sort_inbox;
+use crate::types::AccountPreview;
 use crate::types::BackendState;
+use crate::types::ChannelMetadata;
 use crate::types::ChatMessage;
+use crate::types::ContactListDiff;
+use crate::types::ContactOp;
+use crate::types::CropRect;
+use crate::types::FollowerItem;
+use crate::types::HashtagNote;
+use crate::types::ImportedRelay;
+use crate::types::InboxItem;
+use crate::types::LightningAddress;
+use crate::types::Pairing;
 use crate::types::PendingEvent;
 use crate::types::PrefixedId;
+use crate::types::ProfileImage;
+use crate::types::RelayDeletion;
+use crate::types::RelayExportFormat;
 use crate::types::SubName;
+use crate::types::DELETION_NIP;
+use crate::utils::canonical_relay_url;
 use crate::utils::channel_id_from_tags;
+use crate::utils::from_naive_utc_to_local;
+use crate::utils::nevent_link;
+use crate::utils::ns_event_to_naive;
 use crate::utils::parse_nips_markdown;
+use crate::utils::relay_auth_keys;
 use crate::utils::NipData;
 use crate::views::login::BasicProfile;
 use crate::Error;
 
+pub(crate) mod archive_export;
+pub(crate) mod console;
+pub(crate) mod downloads;
 mod filters;
+pub(crate) mod flood_guard;
+pub mod image_sanitize;
+pub(crate) mod ingress;
 pub mod kind;
+mod lanes;
+mod latency;
+pub(crate) mod nip96;
 pub(crate) mod ntp;
+pub mod pdf_export;
+mod relay_document;
+mod request;
+pub(crate) mod request_budget;
 pub(crate) mod reqwest_client;
+pub(crate) mod send_guard;
+pub(crate) mod simulation;
+pub(crate) mod translation;
+pub(crate) mod uploads;
 
+use self::archive_export::{conversation_archive, ArchiveEntry};
+use self::downloads::{attachment_urls, contact_folder, save_attachments, show_in_folder};
 use self::filters::contact_list_metadata_filter;
 use self::filters::search_channel_details_filter;
-use self::kind::pending_dm_confirmed;
-use self::reqwest_client::download_image;
+use self::pdf_export::{spawn_pdf_export, PdfExportStatus};
+use self::reqwest_client::{download_image, MediaClient};
+use self::uploads::{Upload, UploadState, UploadStatus};
+pub use lanes::Priority;
+use lanes::{lanes, LaneReceiver, LaneSender};
+pub use request::{PendingRequest, RequestId};
 pub(crate) use reqwest_client::{image_filename, ImageKind, ImageSize};
 
 #[derive(Debug, Clone)]
 pub struct BackEndConnection {
-    sender: tokio::sync::mpsc::Sender<ToBackend>,
+    sender: LaneSender,
 }
 impl BackEndConnection {
-    pub fn new(sender: tokio::sync::mpsc::Sender<ToBackend>) -> Self {
+    pub(crate) fn new(sender: LaneSender) -> Self {
         Self { sender }
     }
     pub fn send(&mut self, input: ToBackend) -> Result<(), BackendClosed> {
-        if let Err(e) = self.sender.try_send(input) {
-            match e {
-                TrySendError::Full(_) => {
-                    // try send it later?
-                    tracing::error!("{}", e);
-                }
-                TrySendError::Closed(_) => return Err(BackendClosed),
-            }
-        }
-        Ok(())
+        self.sender.send(input)
+    }
+    /// Sends the command with a new `RequestId`.
+    /// The events it produces come back wrapped in `BackendEvent::Response`.
+    pub fn request(&mut self, input: ToBackend) -> Result<PendingRequest, BackendClosed> {
+        let id = RequestId::new();
+        self.send(ToBackend::Request(id, Box::new(input)))?;
+        Ok(PendingRequest::new(id))
     }
 }
 
 pub enum State {
     Start,
-    Ready(tokio::sync::mpsc::Receiver<ToBackend>),
+    Ready(LaneReceiver),
 }
 pub enum ClientState {
     Empty,
@@ -111,9 +254,37 @@ pub enum ClientState {
         keys: Keys,
         backend: BackendState,
         notifications: broadcast::Receiver<NotificationEvent>,
+        closing: Option<Closing>,
     },
 }
 
+/// Graceful shutdown in progress, waiting for the relays to confirm the pending events
+pub struct Closing {
+    reason: CloseReason,
+    deadline: tokio::time::Instant,
+}
+impl Closing {
+    fn new(reason: CloseReason) -> Self {
+        Self {
+            reason,
+            deadline: tokio::time::Instant::now() + Duration::from_secs(CLOSING_TIMEOUT_SECS),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy)]
+pub enum CloseReason {
+    Shutdown,
+    Logout,
+}
+impl CloseReason {
+    fn done_event(&self) -> BackendEvent {
+        match self {
+            CloseReason::Shutdown => BackendEvent::ShutdownDone,
+            CloseReason::Logout => BackendEvent::LogoutSuccess,
+        }
+    }
+}
+
 pub fn backend_connect() -> iced::Subscription<BackendEvent> {
     struct Backend;
     let id = std::any::TypeId::of::<Backend>();
@@ -125,12 +296,11 @@ pub fn backend_connect() -> iced::Subscription<BackendEvent> {
         loop {
             match &mut state {
                 State::Start => {
-                    let (sender, receiver) =
-                        tokio::sync::mpsc::channel::<ToBackend>(BACKEND_CHANNEL_SIZE);
+                    let (sender, receiver) = lanes();
                     state = State::Ready(receiver);
 
-                    shutdown_signal_task(sender.clone());
                     let backend_conn = BackEndConnection::new(sender);
+                    shutdown_signal_task(backend_conn.clone());
 
                     _ = output.send(BackendEvent::Connected(backend_conn)).await;
                 }
@@ -194,41 +364,87 @@ pub fn backend_connect() -> iced::Subscription<BackendEvent> {
                             backend,
                             keys,
                             notifications,
+                            closing,
                         } => {
+                            if let Some(Closing { reason, deadline }) = closing {
+                                if backend.pending_events.is_empty()
+                                    || tokio::time::Instant::now() >= *deadline
+                                {
+                                    let done_event = reason.done_event();
+                                    finish_closing(backend).await;
+                                    state = State::Start;
+                                    client_state = ClientState::Empty;
+                                    _ = output.send(done_event).await;
+                                    continue;
+                                }
+                            }
+                            let closing_deadline = closing
+                                .as_ref()
+                                .map(|c| c.deadline)
+                                .unwrap_or_else(tokio::time::Instant::now);
+                            let document_deadline =
+                                tokio::time::Instant::from_std(backend.next_document_check);
+                            let budget_slot = backend.request_budget.next_slot();
+                            let budget_deadline = budget_slot
+                                .map(tokio::time::Instant::from_std)
+                                .unwrap_or_else(tokio::time::Instant::now);
+
+                            // set when the client state must be rebuilt
+                            let mut restart: Option<String> = None;
+
                             tokio::select! {
                                 message = receiver.recv() => {
                                     tracing::trace!("Received message from frontend");
                                     if let Some(message) = message {
                                         match message {
+                                            other if closing.is_some() => {
+                                                // stop accepting new sends
+                                                tracing::info!("Closing, ignoring message: {:?}", other);
+                                            }
                                             ToBackend::Shutdown => {
                                                 tracing::info!("Shutdown received");
-                                                let _ = backend.logout().await;
-                                                state = State::Start;
-                                                client_state = ClientState::Empty;
-                                                _ = output.send(BackendEvent::ShutdownDone).await;
+                                                *closing = Some(Closing::new(CloseReason::Shutdown));
+                                                _ = output.send(BackendEvent::SyncingBeforeExit(backend.pending_events.len())).await;
                                             }
                                             ToBackend::Logout => {
-                                                let _ = backend.logout().await;
-                                                state = State::Start;
-                                                client_state = ClientState::Empty;
-                                                _ = output.send(BackendEvent::LogoutSuccess).await;
+                                                tracing::info!("Logout received");
+                                                *closing = Some(Closing::new(CloseReason::Logout));
+                                                _ = output.send(BackendEvent::SyncingBeforeExit(backend.pending_events.len())).await;
+                                            }
+                                            ToBackend::Request(id, message) => {
+                                                restart = supervise(async {
+                                                    process_request(&mut output, keys, backend, tasks_tx, id, *message).await;
+                                                    reject_simulated(&mut output, keys, backend, tasks_tx).await
+                                                }).await;
+                                                send_backpressure(&mut output, receiver).await;
                                             }
                                             other => {
-                                                if let Err(e) = process_message(&mut output, keys, backend, tasks_tx, other).await {
-                                                    // depending on the error, restart backend?
-                                                    tracing::error!("{}", e);
-                                                }
+                                                restart = supervise(async {
+                                                    process_message(&mut output, keys, backend, tasks_tx, other).await?;
+                                                    reject_simulated(&mut output, keys, backend, tasks_tx).await
+                                                }).await;
+                                                send_backpressure(&mut output, receiver).await;
                                             }
                                         }
 
                                     } else {
                                         tracing::info!("Front to backend channel closed");
-                                        let _ = backend.logout().await;
+                                        finish_closing(backend).await;
                                         _ = output.send(BackendEvent::LogoutSuccess).await;
                                         state = State::Start;
                                         client_state = ClientState::Empty;
                                     }
                                 }
+                                _ = tokio::time::sleep_until(closing_deadline), if closing.is_some() => {
+                                    tracing::info!("Timeout waiting for pending events");
+                                }
+                                _ = tokio::time::sleep_until(budget_deadline), if budget_slot.is_some() => {
+                                    restart = supervise(send_queued_requests(&mut output, backend)).await;
+                                }
+                                _ = tokio::time::sleep_until(document_deadline) => {
+                                    backend.next_document_check = Instant::now() + DOCUMENT_CHECK_INTERVAL;
+                                    restart = supervise(refresh_relay_documents(backend, tasks_tx, &[])).await;
+                                }
                                 notification = notifications.recv() => {
                                     tracing::trace!("Received notification from nostr");
                                     if let Ok(notification) = notification {
@@ -242,21 +458,13 @@ pub fn backend_connect() -> iced::Subscription<BackendEvent> {
                                             }
                                             RelayEvent::RelayInformation(info) => {
                                                 tracing::trace!("Relay info - {} - {:?}", &url, &info);
-                                                if let Err(e) = handle_relay_info(&mut output, backend, url, info).await {
-                                                    tracing::error!("{}", e);
-                                                }
+                                                restart = supervise(handle_relay_info(&mut output, backend, tasks_tx, url, info)).await;
                                             }
                                             RelayEvent::Timeout(subscription_id) => {
-                                                if let Err(e) = handle_eose(&mut output, keys, backend, url, subscription_id).await {
-                                                    // depending on the error, restart backend?
-                                                    tracing::error!("{}", e);
-                                                }
+                                                restart = supervise(handle_timeout(&mut output, keys, backend, url, subscription_id)).await;
                                             }
                                             RelayEvent::RelayMessage(message) => {
-                                                if let Err(e) = handle_relay_message(&mut output, keys, backend, tasks_tx, url, message).await{
-                                                    // depending on the error, restart backend?
-                                                    tracing::error!("{}", e);
-                                                }
+                                                restart = supervise(handle_relay_message(&mut output, keys, backend, tasks_tx, url, message)).await;
                                             }
                                             RelayEvent::SentSubscription(sub_id) => {
                                                 tracing::debug!("Sent subscription to {} - id: {}", url, sub_id);
@@ -268,21 +476,28 @@ pub fn backend_connect() -> iced::Subscription<BackendEvent> {
                                                 tracing::debug!("Sent event to {} - hash: {}", url, event_hash);
                                             }
                                         };
+                                    } else if let Err(broadcast::error::RecvError::Closed) = notification {
+                                        restart = Some("nostr notifications closed".into());
                                     } else {
-                                        tracing::info!("Nostr notification closed");
+                                        tracing::info!("Nostr notifications lagged");
                                     }
                                 },
                                 task_result = tasks_rx.recv() => {
                                     if let Some(task_result) = task_result {
-                                        if let Err(e) = handle_task_result(&mut output, keys, backend, task_result).await{
-                                            // depending on the error, restart backend?
-                                            tracing::error!("{}", e);
-                                        }
+                                        restart = supervise(handle_task_result(&mut output, keys, backend, task_result)).await;
                                     } else {
                                         tracing::trace!("Tasks channel closed");
                                     }
                                 }
                             };
+
+                            if let Some(reason) = restart {
+                                tracing::error!("Restarting backend: {}", reason);
+                                _ = output.send(BackendEvent::BackendRestarting(reason)).await;
+                                let keys = keys.to_owned();
+                                finish_closing(backend).await;
+                                client_state = restart_client(&mut output, &keys).await;
+                            }
                         }
                     }
                 }
@@ -291,6 +506,47 @@ pub fn backend_connect() -> iced::Subscription<BackendEvent> {
     })
 }
 
+/// Subscription closed before the relay sent all its stored events
+async fn handle_timeout(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    url: Url,
+    subscription_id: SubscriptionId,
+) -> Result<(), Error> {
+    if let Some(SubName::VerifyDeletion(prefixed)) = SubName::from_id(&subscription_id) {
+        update_relay_deletion(output, backend, &prefixed, |deletion| {
+            deletion.timed_out(&url)
+        })
+        .await;
+    }
+    handle_eose(output, keys, backend, url, subscription_id).await
+}
+
+/// Applies the change to the matching deletion request and reports it,
+/// requests with an answer from every relay are forgotten
+async fn update_relay_deletion(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &mut BackendState,
+    prefixed: &PrefixedId,
+    change: impl FnOnce(&mut RelayDeletion),
+) {
+    let deletions = &mut backend.relay_deletions;
+    let Some(idx) = deletions.iter().position(|d| d.matches(prefixed)) else {
+        return;
+    };
+    let deletion = &mut deletions[idx];
+    change(deletion);
+    let deletion = if deletion.is_done() {
+        deletions.remove(idx)
+    } else {
+        deletion.clone()
+    };
+    _ = output
+        .send(BackendEvent::RelayDeletionUpdated(deletion))
+        .await;
+}
+
 async fn handle_eose(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
     _keys: &Keys,
@@ -299,6 +555,7 @@ async fn handle_eose(
     subscription_id: SubscriptionId,
 ) -> Result<(), Error> {
     // tracing::info!("EOSE {} - {}", &url, &subscription_id);
+    backend.flood_guard.end_of_stored(&url, &subscription_id);
 
     if let Some(sub_type) = SubName::from_id(&subscription_id) {
         match sub_type {
@@ -309,8 +566,14 @@ async fn handle_eose(
                 let filter = contact_list_metadata_filter(&contact_list, &last_event);
                 let subscription = ns_client::Subscription::new(vec![filter])
                     .with_id(SubName::ContactListMetadata.to_string());
+                backend.flood_guard.restart(&SubName::ContactListMetadata);
                 tracing::debug!("contact_list_meta_sub: {:?}", subscription);
-                backend.nostr.relay_subscribe(&url, &subscription)?;
+                request_metadata(output, backend, &url, subscription).await?;
+            }
+            SubName::MessagesWave(wave) => {
+                if let Some(next) = backend.dm_prefetch.get(wave + 1) {
+                    backend.nostr.relay_subscribe(&url, next)?;
+                }
             }
             SubName::SearchChannels => {
                 // when eose of search_channels, fetch metadata
@@ -323,12 +586,84 @@ async fn handle_eose(
                     .send(BackendEvent::EOSESearchChannelsDetails(channel_id))
                     .await;
             }
+            SubName::Console => {
+                if let Some(started) = backend.console_started {
+                    _ = output
+                        .send(BackendEvent::ConsoleEose(url, started.elapsed()))
+                        .await;
+                }
+            }
+            SubName::ContactChannels => {
+                let channels = rank_contact_channels(backend).await?;
+                let missing: Vec<_> = channels
+                    .iter()
+                    .filter(|c| c.cache.is_none())
+                    .map(|c| c.channel_id)
+                    .collect();
+                if !missing.is_empty() {
+                    let subscription = Subscription::new(vec![channels_creation_filter(&missing)])
+                        .with_id(SubName::ContactChannelsMeta.to_string())
+                        .eose(Some(Duration::from_secs(10)));
+                    backend.nostr.relay_subscribe(&url, &subscription)?;
+                }
+                _ = output
+                    .send(BackendEvent::GotContactChannels {
+                        channels,
+                        searching: false,
+                    })
+                    .await;
+            }
+            SubName::VerifyDeletion(prefixed) => {
+                update_relay_deletion(output, backend, &prefixed, |deletion| {
+                    deletion.end_of_stored(&url)
+                })
+                .await;
+            }
+            SubName::HashtagFeed(hashtag) => {
+                _ = output.send(BackendEvent::HashtagFeedEose(hashtag)).await;
+            }
+            SubName::AccountPreview => {
+                backend.account_preview.relays_done += 1;
+                _ = output
+                    .send(BackendEvent::GotAccountPreview(
+                        backend.account_preview.clone(),
+                    ))
+                    .await;
+            }
+            SubName::ResolveEvent => {
+                if backend.resolve_relays.remove(&url) {
+                    backend.nostr.remove_relay(url.as_str())?;
+                    backend.flood_guard.forget_relay(&url);
+                }
+            }
+            SubName::DmHistory(_) => {
+                if let Some(chat_pubkey) =
+                    backend.dm_history_fetches.get(&subscription_id.to_string())
+                {
+                    _ = output
+                        .send(BackendEvent::DmHistoryFetched(chat_pubkey.to_owned()))
+                        .await;
+                }
+            }
             _other => (),
         }
     }
     Ok(())
 }
 
+async fn record_violation(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &mut BackendState,
+    url: &Url,
+    kind: ViolationKind,
+    subscription_id: &SubscriptionId,
+) -> Result<(), Error> {
+    let violation =
+        DbRelayViolation::record(backend.pool(), url, kind, &subscription_id.to_string()).await?;
+    _ = output.send(BackendEvent::RelayViolation(violation)).await;
+    Ok(())
+}
+
 pub async fn handle_event(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
     keys: &Keys,
@@ -339,7 +674,49 @@ pub async fn handle_event(
 ) -> Result<(), Error> {
     tracing::trace!("Event {} - {} - {:?}", &url, &subscription_id, &ns_event);
 
-    if let Some(sub_type) = SubName::from_id(&subscription_id) {
+    let sub_type = SubName::from_id(&subscription_id);
+    let requested = sub_type.as_ref().and_then(requested_limit);
+    match backend.flood_guard.check(
+        &url,
+        &subscription_id,
+        ns_event.id,
+        requested,
+        Instant::now(),
+    ) {
+        Verdict::Accept => (),
+        Verdict::Drop => return Ok(()),
+        Verdict::Violation(kind) => {
+            tracing::warn!("{} - {}: {}", &url, &subscription_id, kind);
+            record_violation(output, backend, &url, kind, &subscription_id).await?;
+            return Ok(());
+        }
+    }
+
+    let ns_event = match ingress::check(ns_event) {
+        Ingress::Accept(ns_event) => ns_event,
+        Ingress::Truncated(ns_event) => {
+            tracing::debug!("{} - {}: cut event {}", &url, &subscription_id, ns_event.id);
+            let kind = ViolationKind::Truncated;
+            record_violation(output, backend, &url, kind, &subscription_id).await?;
+            ns_event
+        }
+        Ingress::Rejected(rejection) => {
+            tracing::warn!("{} - {}: event with {}", &url, &subscription_id, rejection);
+            let kind = ViolationKind::Rejected;
+            record_violation(output, backend, &url, kind, &subscription_id).await?;
+            return Ok(());
+        }
+    };
+
+    if backend.is_blocked(&ns_event.pubkey) {
+        tracing::debug!("Dropped event from blocked user: {}", ns_event.pubkey);
+        return Ok(());
+    }
+
+    let resolving = matches!(sub_type, Some(SubName::ResolveEvent));
+    let event_hash = ns_event.id;
+
+    if let Some(sub_type) = sub_type {
         match sub_type {
             SubName::SearchChannels => {
                 if let Kind::ChannelCreation = ns_event.kind {
@@ -353,6 +730,74 @@ pub async fn handle_event(
                     return Err(Error::UnexpectedEventKind(ns_event.kind.as_u32()));
                 }
             }
+            SubName::VerifyDeletion(prefixed) => {
+                // anything but the deleted event is ignored
+                if PrefixedId::new(&ns_event.id.to_hex()) == prefixed {
+                    update_relay_deletion(output, backend, &prefixed, |deletion| {
+                        deletion.served(&url)
+                    })
+                    .await;
+                }
+                return Ok(());
+            }
+            SubName::Console => {
+                let elapsed = backend
+                    .console_started
+                    .map(|started| started.elapsed())
+                    .unwrap_or_default();
+                _ = output
+                    .send(BackendEvent::ConsoleEvent(ConsoleRow::new(
+                        url, &ns_event, elapsed,
+                    )))
+                    .await;
+                return Ok(());
+            }
+            SubName::HashtagFeed(_) | SubName::FollowedHashtags => {
+                // shown without being stored
+                if matches!(ns_event.kind, Kind::TextNote | Kind::ChannelMessage) {
+                    let author_name =
+                        ProfileCache::fetch_by_public_key(backend.cache_pool(), &ns_event.pubkey)
+                            .await?
+                            .and_then(|profile| {
+                                profile.metadata.display_name.or(profile.metadata.name)
+                            });
+                    let note = HashtagNote::from_ns_event(&ns_event, author_name)?;
+                    let event = if let SubName::FollowedHashtags = sub_type {
+                        BackendEvent::NewHashtagNote(note)
+                    } else {
+                        BackendEvent::GotHashtagNote(note)
+                    };
+                    _ = output.send(event).await;
+                }
+                return Ok(());
+            }
+            SubName::AccountPreview => {
+                // only counted, the first sync stores them
+                backend
+                    .account_preview
+                    .add_event(&ns_event, &keys.public_key());
+                return Ok(());
+            }
+            SubName::ContactChannels => {
+                // only used for ranking, the user is not subscribed to these channels
+                if let Some(channel_id) = channel_id_from_tags(&ns_event.tags) {
+                    backend.contact_channel_activity.push(ContactActivity {
+                        channel_id,
+                        author: ns_event.pubkey,
+                        created_at: ns_event_to_naive(ns_event.created_at)?,
+                    });
+                }
+                return Ok(());
+            }
+            SubName::ContactChannelsMeta => {
+                if let Kind::ChannelCreation = ns_event.kind {
+                    let cache = ChannelCache::fetch_insert(backend.cache_pool(), &ns_event).await?;
+                    _ = output.send(BackendEvent::ContactChannelCache(cache)).await;
+                    return Ok(());
+                } else {
+                    return Err(Error::UnexpectedEventKind(ns_event.kind.as_u32()));
+                }
+            }
             SubName::SearchChannelsDetails(_prefixed_id) => {
                 let cache_pool = backend.cache_pool();
                 let Some(_channel_id) = channel_id_from_tags(&ns_event.tags) else {
@@ -374,6 +819,8 @@ pub async fn handle_event(
                         //     ))
                         //     .await;
                     }
+                    // kinds with a handler are processed below
+                    other if backend.kinds().get(&other).is_some() => (),
                     other => {
                         return Err(Error::UnexpectedEventKind(other.as_u32()));
                     }
@@ -385,47 +832,213 @@ pub async fn handle_event(
 
     if let Some(pending) = backend.pending_events.remove(&ns_event.id) {
         confirm_pending(output, keys, backend, &url, pending).await?;
+    } else if let Some(handler) = backend.kinds().get(&ns_event.kind) {
+        let ctx = HandlerCtx {
+            output,
+            keys,
+            pool: backend.pool(),
+            cache_pool: backend.cache_pool(),
+            url: &url,
+        };
+        handler.received(ctx, ns_event).await?;
     } else {
-        match ns_event.kind {
-            Kind::ChannelCreation => {
-                let cache_pool = backend.cache_pool();
-                let cache = ChannelCache::fetch_insert(cache_pool, &ns_event).await?;
-                _ = output.send(BackendEvent::ChannelCacheUpdated(cache)).await;
-            }
-            Kind::ChannelMetadata => {
-                let cache_pool = backend.cache_pool();
-                let cache = ChannelCache::update(cache_pool, &ns_event).await?;
-                _ = output.send(BackendEvent::ChannelCacheUpdated(cache)).await;
-            }
-            Kind::ChannelMessage => {
-                let pool = backend.pool();
-                let cache_pool = backend.cache_pool();
-                handle_channel_message(output, keys, pool, cache_pool, &url, ns_event).await?;
-            }
-            Kind::ContactList => {
-                let pool = backend.pool();
-                if let Some(db_event) = received_contact_list(pool, &url, &ns_event).await? {
-                    handle_contact_list(output, keys, pool, &url, db_event).await?;
-                }
-            }
-            Kind::EncryptedDirectMessage => {
-                let pool = backend.pool();
-                let cache_pool = backend.cache_pool();
-                handle_dm(output, pool, cache_pool, keys, &url, ns_event).await?;
-            }
-            Kind::Metadata => {
-                let cache_pool = backend.cache_pool();
-                insert_metadata_event(output, cache_pool, &url, ns_event).await?;
-            }
-            _other_kind => {
-                tracing::info!("Other kind event: {:?}", _other_kind);
-                // _ = output
-                //     .send(BackendEvent::OtherKindEventInserted(db_event))
-                //     .await;
+        tracing::info!("Other kind event: {:?}", ns_event.kind);
+    }
+
+    if resolving {
+        locate_event(output, keys, backend, &event_hash).await?;
+    }
+
+    Ok(())
+}
+
+/// Moves the read position and publishes every channel's position for the
+/// other devices, unless the user opted out of tracking signals
+async fn mark_channel_read(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    channel_id: &EventId,
+    event_id: i64,
+) -> Result<(), Error> {
+    let pool = backend.pool();
+    let Some(db_event) = DbEvent::fetch_id(pool, event_id).await? else {
+        return Ok(());
+    };
+    if !ChannelSubscription::mark_read(pool, channel_id, &db_event.event_hash, db_event.created_at)
+        .await?
+    {
+        return Ok(());
+    }
+    channel_read_moved(output, keys, backend).await
+}
+
+/// Marks every message of the channel as read, the position is shared like [`mark_channel_read`]
+async fn mark_channel_all_read(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    channel_id: &EventId,
+) -> Result<(), Error> {
+    if !ChannelSubscription::mark_all_read(backend.pool(), channel_id).await? {
+        return Ok(());
+    }
+    channel_read_moved(output, keys, backend).await
+}
+
+async fn channel_read_moved(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+) -> Result<(), Error> {
+    send_unread_counts(output, backend.pool()).await?;
+    let publish = ToBackend::PublishChannelReadMarkers;
+    if is_held_back(backend, &publish) {
+        return Ok(());
+    }
+    publish_channel_read_markers(keys, backend).await
+}
+
+async fn publish_channel_read_markers(
+    keys: &Keys,
+    backend: &mut BackendState,
+) -> Result<(), Error> {
+    let markers =
+        ReadMarkers::from_subscriptions(&ChannelSubscription::fetch(backend.pool()).await?);
+    backend.new_channel_read_event(keys, &markers).await?;
+    Ok(())
+}
+
+/// Unread count of every subscribed channel, followed by the total with the unseen DMs
+pub(crate) async fn send_unread_counts(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    pool: &sqlx::SqlitePool,
+) -> Result<(), Error> {
+    let counts = ChannelSubscription::unread_counts(pool).await?;
+    let total = unread_total(pool, &counts).await?;
+    _ = output.send(BackendEvent::ChannelUnreadCounts(counts)).await;
+    _ = output.send(BackendEvent::UnreadTotal(total)).await;
+    Ok(())
+}
+
+async fn unread_total(
+    pool: &sqlx::SqlitePool,
+    channel_counts: &HashMap<EventId, i64>,
+) -> Result<i64, Error> {
+    Ok(DbMessage::count_unseen(pool).await? + channel_counts.values().sum::<i64>())
+}
+
+/// Sends the conversation of a stored DM or channel event, returns false when it is not stored
+async fn locate_event(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &BackendState,
+    event_hash: &EventId,
+) -> Result<bool, Error> {
+    let pool = backend.pool();
+    let Some(db_event) = DbEvent::fetch_hash(pool, event_hash).await? else {
+        return Ok(false);
+    };
+    match db_event.kind {
+        Kind::EncryptedDirectMessage => (),
+        Kind::ChannelCreation => {
+            _ = output.send(BackendEvent::ChannelLocated(*event_hash)).await;
+            return Ok(true);
+        }
+        Kind::ChannelMessage | Kind::ChannelMetadata => {
+            if let Some(channel_id) = channel_id_from_tags(&db_event.tags) {
+                _ = output.send(BackendEvent::ChannelLocated(channel_id)).await;
             }
+            return Ok(true);
+        }
+        other => {
+            tracing::info!("Linked event can't be opened: {:?}", other);
+            return Ok(true);
+        }
+    }
+    let tag_info = MessageTagInfo::from_event_tags(event_hash, &db_event.pubkey, &db_event.tags)?;
+    if let Some(chat_pubkey) = tag_info.chat_pubkey(keys) {
+        if let Some(db_contact) = DbContact::fetch_one(pool, &chat_pubkey).await? {
+            _ = output
+                .send(BackendEvent::EventLocated(db_contact, db_event.event_id))
+                .await;
         }
     }
+    Ok(true)
+}
+
+/// Saves the attachments of a received message when its conversation has auto-save on
+async fn auto_save_attachments(
+    keys: &Keys,
+    backend: &mut BackendState,
+    task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+    ns_event: &nostr::Event,
+) -> Result<(), Error> {
+    if !AutoSaveAttachments::is_enabled(backend.pool(), &ns_event.pubkey).await? {
+        return Ok(());
+    }
+    let Some(db_event) = DbEvent::fetch_hash(backend.pool(), &ns_event.id).await? else {
+        return Ok(());
+    };
+    // every relay sends its copy
+    if backend.attachment_saves.contains(&db_event.event_id)
+        || !AttachmentDownload::fetch(backend.pool(), db_event.event_id)
+            .await?
+            .is_empty()
+    {
+        return Ok(());
+    }
+    let Ok(secret_key) = keys.secret_key() else {
+        return Ok(());
+    };
+    let Ok(content) = nip04::decrypt(&secret_key, &ns_event.pubkey, &ns_event.content) else {
+        return Ok(());
+    };
+    let urls = attachment_urls(&content);
+    if urls.is_empty() {
+        return Ok(());
+    }
+    let db_contact = DbContact::fetch_insert(backend.pool(), &ns_event.pubkey).await?;
+    spawn_attachment_save(
+        backend,
+        task_tx,
+        &db_contact,
+        db_event.event_id,
+        urls,
+        false,
+    )
+}
 
+/// Downloads into the contact's folder of the download directory
+fn spawn_attachment_save(
+    backend: &mut BackendState,
+    task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+    db_contact: &DbContact,
+    event_id: i64,
+    urls: Vec<String>,
+    reveal: bool,
+) -> Result<(), Error> {
+    let download_dir = backend
+        .downloads
+        .resolve_directory()
+        .ok_or(downloads::Error::NoDirectory)?;
+    let dir = contact_folder(&download_dir, &db_contact.select_name());
+    backend.attachment_saves.insert(event_id);
+    let media_client = backend.media_client.clone();
+    let task_tx_1 = task_tx.clone();
+    tokio::spawn(async move {
+        let result = save_attachments(&media_client, &dir, event_id, &urls)
+            .await
+            .map(|downloads| TaskOutput::AttachmentsSaved {
+                event_id,
+                downloads,
+                reveal,
+            })
+            .map_err(|e| e.into());
+        if let Err(e) = task_tx_1.send(result).await {
+            tracing::error!("Error sending attachments saved event: {}", e);
+        }
+    });
     Ok(())
 }
 
@@ -433,10 +1046,12 @@ async fn handle_relay_message(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
     keys: &Keys,
     backend: &mut BackendState,
-    _task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+    task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
     url: Url,
     message: RelayMessage,
 ) -> Result<(), Error> {
+    // responses are stored and matched against the relay list by this spelling
+    let url = canonical_relay_url(&url);
     match message {
         RelayMessage::Ok {
             event_id: event_hash,
@@ -452,17 +1067,66 @@ async fn handle_relay_message(
                 &error_msg
             );
 
+            // answer to a deletion request, the deleted event is asked again
+            let deleted_hash = backend
+                .relay_deletions
+                .iter()
+                .find(|deletion| deletion.deletion_hash == Some(event_hash))
+                .map(|deletion| deletion.event_hash);
+            if let Some(deleted_hash) = deleted_hash {
+                let result = if status { Ok(()) } else { Err(error_msg) };
+                if result.is_ok() {
+                    let subscription =
+                        Subscription::new(vec![Filter::new().id(deleted_hash.to_hex())])
+                            .with_id(SubName::verify_deletion(&deleted_hash).to_string())
+                            .eose(Some(DELETION_VERIFY_TIMEOUT));
+                    backend.nostr.relay_subscribe(&url, &subscription)?;
+                }
+                let prefixed = PrefixedId::new(&deleted_hash.to_hex());
+                update_relay_deletion(output, backend, &prefixed, |deletion| {
+                    deletion.answered(&url, result)
+                })
+                .await;
+                return Ok(());
+            }
+
             if !status {
+                // kept until the event is stored, nothing can reference it before
+                if let Some(pending) = backend.pending_events.get_mut(&event_hash) {
+                    pending.add_rejection(&url, &error_msg);
+                    _ = output
+                        .send(BackendEvent::PendingRejected(event_hash, error_msg.clone()))
+                        .await;
+                } else if let Some(db_event) =
+                    DbEvent::fetch_hash(backend.pool(), &event_hash).await?
+                {
+                    DbRelayResponse::insert_answer(
+                        backend.pool(),
+                        &db_event,
+                        &url,
+                        ResponseStatus::Error(error_msg.clone()),
+                        None,
+                        chrono::Utc::now().naive_utc(),
+                    )
+                    .await?;
+                }
                 _ = output.send(BackendEvent::RelayError(url, error_msg)).await;
                 return Ok(());
             }
 
-            // TODO:
-            // se estou conectado a dois relays e o primeiro me manda um ok
-            // irá tirar este evento do pending e inserir no db
-            // mas o segundo ok não irá fazer nada
             if let Some(pending) = backend.pending_events.remove(&event_hash) {
                 confirm_pending(output, keys, backend, &url, pending).await?;
+            } else if let Some(db_event) = DbEvent::fetch_hash(backend.pool(), &event_hash).await? {
+                // later relays only add their answer to the timeline
+                DbRelayResponse::insert_answer(
+                    backend.pool(),
+                    &db_event,
+                    &url,
+                    ResponseStatus::Ok,
+                    None,
+                    chrono::Utc::now().naive_utc(),
+                )
+                .await?;
             }
         }
         RelayMessage::EndOfStoredEvents(subscription_id) => {
@@ -472,13 +1136,30 @@ async fn handle_relay_message(
             subscription_id,
             event: ns_event,
         } => {
+            let received_dm = (ns_event.kind == Kind::EncryptedDirectMessage
+                && ns_event.pubkey != keys.public_key())
+            .then(|| ns_event.clone());
             handle_event(output, keys, backend, url, subscription_id, *ns_event).await?;
+            if let Some(ns_event) = received_dm {
+                // a failed download must not hold up the relay's events
+                if let Err(e) = auto_save_attachments(keys, backend, task_tx, &ns_event).await {
+                    tracing::error!("Failed to save attachments: {}", e);
+                }
+            }
         }
         RelayMessage::Notice { message } => {
             tracing::info!("Relay message: Notice: {}", message);
         }
         RelayMessage::Auth { challenge } => {
-            backend.new_auth_event(keys, &url, challenge).await?;
+            let anonymous = DbRelay::fetch_by_url(backend.pool(), &url)
+                .await?
+                .map_or(false, |relay| relay.anonymous_auth);
+            if anonymous {
+                let auth_keys = relay_auth_keys(keys, &url)?;
+                backend.new_auth_event(&auth_keys, &url, challenge).await?;
+            } else {
+                backend.new_auth_event(keys, &url, challenge).await?;
+            }
         }
         RelayMessage::Count {
             subscription_id: _,
@@ -494,6 +1175,39 @@ async fn handle_relay_message(
     Ok(())
 }
 
+/// Answers the sends failed by the simulation as if every relay refused them
+async fn reject_simulated(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+) -> Result<(), Error> {
+    let failed = backend.simulation.take_failed();
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let relays = DbRelay::fetch(backend.pool()).await?;
+    for event_id in failed {
+        for db_relay in &relays {
+            let message = RelayMessage::Ok {
+                event_id,
+                status: false,
+                message: SIMULATED_FAILURE.to_owned(),
+            };
+            handle_relay_message(
+                output,
+                keys,
+                backend,
+                task_tx,
+                db_relay.url.clone(),
+                message,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
 async fn confirm_pending(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
     keys: &Keys,
@@ -501,24 +1215,44 @@ async fn confirm_pending(
     url: &Url,
     pending: PendingEvent,
 ) -> Result<(), Error> {
+    backend.remove_from_journal(&pending).await?;
     let pool = backend.pool();
-    let cache_pool = backend.cache_pool();
+    let responded_at = chrono::Utc::now().naive_utc();
     if let Some(db_event) = DbEvent::insert(pool, url, pending.ns_event()).await? {
-        match db_event.kind {
-            Kind::ContactList => {
-                _ = output
-                    .send(BackendEvent::ConfirmedContactList(db_event))
-                    .await;
-            }
-            Kind::Metadata => {
-                insert_metadata_event(output, cache_pool, url, db_event.to_ns_event()?).await?;
-            }
-            Kind::EncryptedDirectMessage => {
-                pending_dm_confirmed(output, pool, keys, &db_event).await?;
-            }
-            _ => {
-                return Err(Error::NotSubscribedToKind(db_event.kind));
-            }
+        let Some(handler) = backend.kinds().get(&db_event.kind) else {
+            return Err(Error::NotSubscribedToKind(db_event.kind));
+        };
+        let ctx = HandlerCtx {
+            output,
+            keys,
+            pool,
+            cache_pool: backend.cache_pool(),
+            url,
+        };
+        handler.confirmed(ctx, db_event).await?;
+    }
+
+    if let Some(db_event) = DbEvent::fetch_hash(pool, pending.id()).await? {
+        let sent_at = Some(pending.sent_at());
+        DbRelayResponse::insert_answer(
+            pool,
+            &db_event,
+            url,
+            ResponseStatus::Ok,
+            sent_at,
+            responded_at,
+        )
+        .await?;
+        for (relay_url, error_msg, rejected_at) in pending.rejections() {
+            DbRelayResponse::insert_answer(
+                pool,
+                &db_event,
+                relay_url,
+                ResponseStatus::Error(error_msg.to_owned()),
+                sent_at,
+                *rejected_at,
+            )
+            .await?;
         }
     }
     Ok(())
@@ -534,9 +1268,25 @@ async fn get_clients(
     let nostr = RelayPool::new();
     let notifications = nostr.notifications();
     let nips_data = parse_nips_markdown(NIPS_LIST_MARKDOWN)?;
-    let backend = BackendState::new(db_client, req_client, nostr, nips_data, create_account);
+    let config = Config::load_file_async().await?;
+    let mut backend = BackendState::new(
+        db_client,
+        req_client,
+        nostr,
+        nips_data,
+        create_account,
+        config.do_not_track,
+        config.relay_tuning.requests_per_minute,
+    );
+    backend.media_client = MediaClient::new(&config.media_proxy);
+    backend.downloads = config.downloads;
+    match check_and_repair(keys, &backend).await {
+        Ok(report) => backend.integrity_report = report.should_report().then_some(report),
+        Err(e) => tracing::error!("Integrity check failed: {}", e),
+    }
 
-    spawn_ntp_request(tasks_tx.clone());
+    spawn_ntp_sync(tasks_tx.clone());
+    spawn_storage_pruning(backend.pool().clone(), tasks_tx.clone());
 
     Ok(ClientState::Connected {
         tasks_rx,
@@ -544,18 +1294,129 @@ async fn get_clients(
         keys: keys.to_owned(),
         backend,
         notifications,
+        closing: None,
     })
 }
 
-fn shutdown_signal_task(sender: tokio::sync::mpsc::Sender<ToBackend>) {
-    tokio::spawn(async move {
-        let ctrl_c = async {
-            signal::ctrl_c()
-                .await
-                .expect("failed to install Ctrl+C handler");
-        };
+/// Runs a backend step, catching panics. Returns why the client state
+/// must be rebuilt, when it does.
+async fn supervise<F>(step: F) -> Option<String>
+where
+    F: std::future::Future<Output = Result<(), Error>>,
+{
+    match AssertUnwindSafe(step).catch_unwind().await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) if e.is_fatal() => Some(e.to_string()),
+        Ok(Err(e)) => {
+            tracing::error!("{}", e);
+            None
+        }
+        Err(panic) => Some(panic_message(panic)),
+    }
+}
 
-        #[cfg(unix)]
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .unwrap_or_else(|| "unknown panic".into()),
+    }
+}
+
+/// Rebuilds the backend from the databases and subscribes again
+async fn restart_client(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+) -> ClientState {
+    let mut client_state = match get_clients(keys, None).await {
+        Ok(client_state) => client_state,
+        Err(e) => {
+            tracing::error!("{}", e);
+            _ = output.send(BackendEvent::FailedToStartClient).await;
+            return ClientState::Empty;
+        }
+    };
+    if let ClientState::Connected { backend, .. } = &mut client_state {
+        if let Err(e) = prepare_client(keys, backend).await {
+            tracing::error!("{}", e);
+        }
+    }
+    _ = output.send(BackendEvent::BackendRestarted).await;
+    client_state
+}
+
+async fn check_and_repair(keys: &Keys, backend: &BackendState) -> Result<IntegrityReport, Error> {
+    let mut report =
+        check_integrity(backend.pool(), backend.cache_pool(), backend.migrated()).await?;
+    report.messages_restored = restore_dm_messages(backend.pool(), keys).await?;
+    Ok(report)
+}
+
+/// Enforces the storage limits every `PRUNE_INTERVAL_SECS`, until the backend is dropped
+fn spawn_storage_pruning(
+    pool: sqlx::SqlitePool,
+    sender: tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(PRUNE_INTERVAL_SECS)).await;
+            if sender.is_closed() || pool.is_closed() {
+                break;
+            }
+            let storage = match Config::load_file_async().await {
+                Ok(config) => config.storage,
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    continue;
+                }
+            };
+            if !storage.is_enabled() {
+                continue;
+            }
+            let result = prune(&pool, &storage)
+                .await
+                .map(TaskOutput::Pruned)
+                .map_err(Error::from);
+            if sender.send(result).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Saves what the relays didn't confirm and drops the pool
+async fn finish_closing(backend: &mut BackendState) {
+    match backend.persist_pending().await {
+        Ok(0) => (),
+        Ok(count) => tracing::info!("Unconfirmed events saved to the journal: {}", count),
+        Err(e) => tracing::error!("{}", e),
+    }
+    if let Err(e) = backend.logout().await {
+        tracing::error!("{}", e);
+    }
+}
+
+async fn send_backpressure(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    receiver: &mut LaneReceiver,
+) {
+    if let Some(congested) = receiver.congestion_changed() {
+        tracing::info!("Backpressure: {}", congested);
+        _ = output.send(BackendEvent::Backpressure(congested)).await;
+    }
+}
+
+fn shutdown_signal_task(mut conn: BackEndConnection) {
+    tokio::spawn(async move {
+        let ctrl_c = async {
+            signal::ctrl_c()
+                .await
+                .expect("failed to install Ctrl+C handler");
+        };
+
+        #[cfg(unix)]
         let terminate = async {
             signal::unix::signal(signal::unix::SignalKind::terminate())
                 .expect("failed to install signal handler")
@@ -571,16 +1432,26 @@ fn shutdown_signal_task(sender: tokio::sync::mpsc::Sender<ToBackend>) {
             _ = terminate => {},
         }
 
-        _ = sender.send(ToBackend::Shutdown).await;
+        _ = conn.send(ToBackend::Shutdown);
     });
 }
 
 async fn handle_relay_info(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
     backend: &mut BackendState,
+    task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
     url: Url,
     info: ns_client::RelayInformation,
 ) -> Result<(), Error> {
+    let url = canonical_relay_url(&url);
+    let connected = matches!(info.status, ns_client::RelayStatus::Connected);
+    if !connected && !info.error_messages.is_empty() {
+        backend.relays_with_errors.insert(url.clone());
+    } else if connected && backend.relays_with_errors.remove(&url) {
+        // the relay may have been down for an upgrade
+        refresh_relay_documents(backend, task_tx, &[url.clone()]).await?;
+    }
+
     let db_relay = DbRelay::fetch_by_url(backend.pool(), &url)
         .await?
         .map(|mut db_relay| {
@@ -595,24 +1466,113 @@ async fn handle_relay_info(
     Ok(())
 }
 
+/// Fetches the documents older than a day and the `force` ones
+async fn refresh_relay_documents(
+    backend: &mut BackendState,
+    task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+    force: &[Url],
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let relays: Vec<_> = DbRelay::fetch(backend.pool())
+        .await?
+        .into_iter()
+        .filter(|relay| {
+            force.contains(&relay.url)
+                || relay
+                    .cached_document
+                    .as_ref()
+                    .map_or(true, |cached| cached.is_stale(now))
+        })
+        .filter(|relay| backend.documents_refreshing.insert(relay.url.clone()))
+        .map(|relay| {
+            let etag = relay.cached_document.and_then(|cached| cached.etag);
+            (relay.url, etag)
+        })
+        .collect();
+    if !relays.is_empty() {
+        spawn_document_refresh(backend.req_client.clone(), relays, task_tx.clone());
+    }
+    Ok(())
+}
+
+/// Stores the fetched document, what changed is kept until the user dismisses it
+async fn handle_relay_document(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &mut BackendState,
+    url: Url,
+    fetched: Result<RelayDocumentFetch, String>,
+) -> Result<(), Error> {
+    backend.documents_refreshing.remove(&url);
+    let pool = backend.pool();
+    match fetched {
+        Err(e) => tracing::info!("Relay document of {}: {}", url, e),
+        Ok(RelayDocumentFetch::NotModified) => DbRelay::touch_document(pool, &url).await?,
+        Ok(RelayDocumentFetch::Fetched { document, etag }) => {
+            let previous = DbRelay::fetch_by_url(pool, &url)
+                .await?
+                .and_then(|relay| relay.cached_document);
+            DbRelay::save_document(pool, &url, &document, etag.as_deref()).await?;
+            let changes = previous
+                .map(|cached| document_changes(&cached.document, &document))
+                .unwrap_or_default();
+            if !changes.is_empty() {
+                let changes = DbRelayDocumentChange::insert(pool, &url, &changes).await?;
+                _ = output
+                    .send(BackendEvent::RelayDocumentChanged(changes))
+                    .await;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub enum TaskOutput {
-    Ntp(u64, String),
+    Ntp(NtpSync),
     LatestVersion(String),
     ImageDownloaded(ImageDownloaded),
+    /// Event id of the translated message
+    Translated(i64, DbTranslation),
+    Pruned(PruneReport),
+    RelayLatency(Url, Option<Duration>),
+    /// Error as text, a failed fetch only gets logged
+    RelayDocument(Url, Result<RelayDocumentFetch, String>),
+    PdfExport(PdfExportStatus),
+    /// Looked up address and the failure reason
+    Nip05(String, Result<Nip05Profile, String>),
+    LightningChecked(LightningCheck),
+    /// Zapped sats and the invoice or the failure reason
+    ZapInvoice(u64, Result<String, String>),
+    Stats(AccountStats),
+    UrlChecked(String, bool),
+    OwnNip05(String, Result<(), String>),
+    /// Uploaded address or the failure reason
+    ProfileImageUploaded(ProfileImage, Result<String, String>),
+    /// Event to delete and whether each relay advertises NIP-09
+    DeletionSupport(i64, EventId, Vec<(Url, bool)>),
+    /// `reveal` opens the file manager when done
+    AttachmentsSaved {
+        event_id: i64,
+        downloads: Vec<AttachmentDownload>,
+        reveal: bool,
+    },
+    /// Body of the upload is ready, kept for resuming
+    UploadPrepared(u64, Arc<nip96::PreparedUpload>),
+    Upload(u64, UploadState),
 }
 
 async fn handle_task_result(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
-    _keys: &Keys,
+    keys: &Keys,
     backend: &mut BackendState,
     result: Result<TaskOutput, Error>,
 ) -> Result<(), Error> {
     let task_result = result?;
     match task_result {
-        TaskOutput::Ntp(ntp_time, server) => {
-            tracing::info!("NTP time: {}", ntp_time);
-            backend.update_ntp(ntp_time, &server);
-            let last_ntp_offset = UserConfig::update_ntp_offset(backend.pool(), ntp_time).await?;
+        TaskOutput::Ntp(sync) => {
+            tracing::info!("NTP offset: {} from {:?}", sync.offset, sync.servers);
+            let previous = backend.update_ntp(&sync);
+            let last_ntp_offset =
+                UserConfig::update_ntp_offset(backend.pool(), sync.offset).await?;
             let (_ntp_offset, ntp_server) = backend.synced_ntp();
             _ = output
                 .send(BackendEvent::NtpInfo {
@@ -620,14 +1580,145 @@ async fn handle_task_result(
                     ntp_server,
                 })
                 .await;
+            let drifted = previous.map_or(true, |previous| {
+                (last_ntp_offset - previous).abs() >= NTP_DRIFT_THRESHOLD_MICROS
+            });
+            if drifted {
+                _ = output
+                    .send(BackendEvent::NtpDriftChanged(last_ntp_offset))
+                    .await;
+            }
         }
         TaskOutput::ImageDownloaded(image) => {
             ImageDownloaded::insert(backend.cache_pool(), &image).await?;
             _ = output.send(BackendEvent::ImageDownloaded(image)).await;
         }
+        TaskOutput::UploadPrepared(id, prepared) => {
+            if let Some(upload) = backend.uploads.get_mut(&id) {
+                upload.set_prepared(prepared);
+            }
+        }
+        TaskOutput::Upload(id, state) => {
+            // a failed upload stays until it's resumed or cancelled
+            let finished = match &state {
+                UploadState::Sending { .. } => false,
+                UploadState::Failed { resumable, .. } => !resumable,
+                UploadState::Uploaded(_) | UploadState::Cancelled => true,
+            };
+            let status = if finished {
+                backend.uploads.remove(&id).map(|u| u.status(id, state))
+            } else {
+                backend.uploads.get(&id).map(|u| u.status(id, state))
+            };
+            // cancelled uploads can still have progress queued
+            if let Some(status) = status {
+                _ = output.send(BackendEvent::UploadProgress(status)).await;
+            }
+        }
+        TaskOutput::AttachmentsSaved {
+            event_id,
+            downloads,
+            reveal,
+        } => {
+            backend.attachment_saves.remove(&event_id);
+            for download in &downloads {
+                AttachmentDownload::insert(backend.pool(), download).await?;
+            }
+            if let (true, Some(download)) = (reveal, downloads.first()) {
+                show_in_folder(&download.path)?;
+            }
+        }
         TaskOutput::LatestVersion(version) => {
             _ = output.send(BackendEvent::LatestVersion(version)).await;
         }
+        TaskOutput::Translated(event_id, translation) => {
+            DbTranslation::insert(backend.cache_pool(), &translation).await?;
+            _ = output
+                .send(BackendEvent::MessageTranslated(event_id, translation))
+                .await;
+        }
+        TaskOutput::Pruned(report) => {
+            if report.removed() > 0 {
+                _ = output.send(BackendEvent::StoragePruned(report)).await;
+            }
+        }
+        TaskOutput::Stats(stats) => {
+            _ = output.send(BackendEvent::GotAccountStats(stats)).await;
+        }
+        TaskOutput::UrlChecked(url, reachable) => {
+            _ = output.send(BackendEvent::UrlChecked(url, reachable)).await;
+        }
+        TaskOutput::ZapInvoice(sats, result) => {
+            _ = output.send(BackendEvent::ZapInvoice(sats, result)).await;
+        }
+        TaskOutput::DeletionSupport(event_id, event_hash, support) => {
+            let mut deletion = RelayDeletion::new(event_id, event_hash, support);
+            if deletion.is_supported() {
+                let deletion_hash = backend.new_deletion_event(keys, &event_hash).await?;
+                deletion.deletion_hash = Some(deletion_hash);
+                backend.relay_deletions.push(deletion.clone());
+
+                let pool = backend.pool();
+                if let Some(db_event) = DbEvent::fetch_id(pool, event_id).await? {
+                    let now = UserConfig::get_corrected_time(pool)
+                        .await
+                        .unwrap_or(chrono::Utc::now().naive_utc());
+                    retract_event(output, keys, pool, &db_event, now).await?;
+                }
+            }
+            _ = output
+                .send(BackendEvent::RelayDeletionUpdated(deletion))
+                .await;
+        }
+        TaskOutput::ProfileImageUploaded(kind, result) => {
+            _ = output
+                .send(BackendEvent::ProfileImageUploaded(kind, result))
+                .await;
+        }
+        TaskOutput::OwnNip05(address, result) => {
+            _ = output
+                .send(BackendEvent::OwnNip05Verified(address, result))
+                .await;
+        }
+        TaskOutput::RelayLatency(url, latency) => {
+            _ = output.send(BackendEvent::RelayLatency(url, latency)).await;
+        }
+        TaskOutput::RelayDocument(url, fetched) => {
+            handle_relay_document(output, backend, url, fetched).await?;
+        }
+        TaskOutput::PdfExport(status) => {
+            _ = output.send(BackendEvent::PdfExport(status)).await;
+        }
+        TaskOutput::Nip05(address, Ok(profile)) => {
+            let cache =
+                ProfileCache::fetch_by_public_key(backend.cache_pool(), &profile.pubkey).await?;
+            if cache.is_none() {
+                // the preview is filled in when the metadata arrives
+                let subscription =
+                    Subscription::new(vec![members_metadata_filter([&profile.pubkey])])
+                        .with_id(SubscriptionId::generate().to_string())
+                        .eose(Some(Duration::from_secs(10)));
+                backend.nostr.subscribe(&subscription)?;
+            }
+            _ = output
+                .send(BackendEvent::Nip05Resolved {
+                    address,
+                    profile,
+                    cache,
+                })
+                .await;
+        }
+        TaskOutput::LightningChecked(check) => {
+            LightningCheck::insert(backend.cache_pool(), &check).await?;
+            _ = output
+                .send(BackendEvent::LightningChecked(check.address, check.valid))
+                .await;
+        }
+        TaskOutput::Nip05(address, Err(reason)) => {
+            _ = output
+                .send(BackendEvent::Nip05Failed(address, reason))
+                .await;
+        }
     }
     Ok(())
 }
@@ -642,17 +1733,34 @@ pub enum BackendEvent {
         event_hash: EventId,
     },
     ImageDownloaded(ImageDownloaded),
+    /// Event id of the translated message
+    MessageTranslated(i64, DbTranslation),
 
     // ---  ---
     ThemeChanged(style::Theme),
     GotTheme(style::Theme),
     GotKeys(Keys),
+    /// Payload and code to log in on another device
+    PairingCreated(Result<Pairing, String>),
+    /// Latest signed events and how many were signed in total
+    GotSigningLog {
+        entries: Vec<SigningLogEntry>,
+        total: i64,
+    },
     GotChatMessages(DbContact, Vec<ChatMessage>),
+    /// Older messages are requested from this date
+    DmHistoryFetching(XOnlyPublicKey, NaiveDateTime),
+    /// A relay sent all the older messages it had
+    DmHistoryFetched(XOnlyPublicKey),
+    /// Nothing older than what was synced at login
+    DmHistoryComplete(XOnlyPublicKey),
     GotRelayResponses {
         chat_message: ChatMessage,
         responses: Vec<DbRelayResponse>,
         all_relays: Vec<DbRelay>,
     },
+    /// Progress of a deletion request on each relay
+    RelayDeletionUpdated(RelayDeletion),
     GotRelayResponsesUserProfile {
         responses: Vec<DbRelayResponse>,
         all_relays: Vec<DbRelay>,
@@ -666,18 +1774,145 @@ pub enum BackendEvent {
     RelayUpdated(DbRelay),
     RelayDeleted(Url),
     GotRelays(Vec<DbRelay>),
+    /// `active` is the profile switched to last
+    GotRelayProfiles {
+        profiles: Vec<DbRelayProfile>,
+        active: Option<String>,
+    },
     ContactCreated(DbContact),
     ContactUpdated(DbContact),
     ContactDeleted(DbContact),
+    ContactOpFailed(ContactOp, String),
+    /// Blocked users with their profile name when known
+    GotBlocked(Vec<(DbBlocked, Option<String>)>),
+    /// Public keys in the user's mute list
+    GotMutedUsers(HashSet<XOnlyPublicKey>),
+    /// Items in the trash with their profile name when known
+    GotTrash(Vec<TrashItem>),
     OtherKindEventInserted(DbEvent),
     GotUserProfileCache(Option<ProfileCache>),
     FileContactsImported(Vec<DbContact>),
-    UserProfilePictureUpdated(PathBuf),
-    UserBannerPictureUpdated(PathBuf),
+    /// Uploaded address or the failure reason
+    ProfileImageUploaded(ProfileImage, Result<String, String>),
     UpdatedMetadata(XOnlyPublicKey),
+    /// Address typed in the add contact modal, with the cached profile when known
+    Nip05Resolved {
+        address: String,
+        profile: Nip05Profile,
+        cache: Option<ProfileCache>,
+    },
+    /// Address and the reason it could not be resolved
+    Nip05Failed(String, String),
+    /// Lightning address and whether its LNURL endpoint answered
+    LightningChecked(String, bool),
+    /// Zapped sats and the bolt11 invoice or why the wallet refused it
+    ZapInvoice(u64, Result<String, String>),
+    /// Address and whether it answered
+    UrlChecked(String, bool),
+    /// Address and why it doesn't point to the user's public key
+    OwnNip05Verified(String, Result<(), String>),
+    DeveloperConsole(bool),
+    GotMediaProxy(MediaProxyConfig),
+    GotSendKey(SendKey),
+    DimImagesChanged(bool),
+    AutoLockChanged(AutoLockConfig),
+    TimeFormatChanged(TimeFormatConfig),
+    TextDirectionChanged(TextDirection),
+    StartupViewChanged(StartupView),
+    PresenceChanged(PresenceConfig),
+    DownloadsChanged(DownloadConfig),
+    /// The key entered in the lock screen belongs to the user
+    Unlocked,
+    UnlockFailed,
+    ConsoleEvent(ConsoleRow),
+    /// Relay and the time it took to send all stored events
+    ConsoleEose(Url, Duration),
+    ConsoleError(String),
+    GotSimulation(Simulation),
+    /// Newest first
+    GotDryRuns(Vec<DryRun>),
     GotAllMessages(Vec<DbEvent>),
+    /// Stored events matching the export filter
+    FilteredEventsCount(EventFilter, i64),
     GotSingleContact(XOnlyPublicKey, Option<DbContact>),
+    GotConversationRelays(XOnlyPublicKey, Vec<Url>),
+    /// Channels where both the user and this contact are members
+    GotSharedChannels(XOnlyPublicKey, Vec<ChannelCache>),
+    GotChannelPolls(EventId, Vec<(DbPoll, PollTally)>),
+    GotChannelKeywords(EventId, Vec<String>),
+    /// Result of a hashtag feed
+    GotHashtagNote(HashtagNote),
+    /// Relay finished sending the hashtag feed
+    HashtagFeedEose(String),
+    /// Counts so far, sent each time a relay finishes
+    GotAccountPreview(AccountPreview),
+    /// Posted after login with a followed hashtag
+    NewHashtagNote(HashtagNote),
+    GotFollowedHashtags(Vec<String>),
+    ReceivedPoll(DbPoll, PollTally),
+    PollTallyUpdated(EventId, PollTally),
+    GotLiveActivities(Vec<(DbLiveActivity, Option<DbContact>)>),
+    LiveActivityUpdated(DbLiveActivity, Option<DbContact>),
+    GotMessageLink(String),
+    /// Conversation and event id of a resolved link
+    EventLocated(DbContact, i64),
+    /// Channel of a resolved link
+    ChannelLocated(EventId),
+    /// `None` when the relay couldn't be reached
+    RelayLatency(Url, Option<Duration>),
+    /// Linked event not stored, requested from the relays
+    FetchingEvent(EventId),
+    StageContactChanges(bool),
+    DoNotTrack(bool),
+    GotIntegrityReport(IntegrityReport),
+    GotContactListDiff(ContactListDiff),
+    ContactListPublished,
+    GotTemplates(Vec<DbTemplate>),
+    TemplateError(String),
+    GotTranslationConfig(TranslationConfig),
+    GotStorageConfig(StorageConfig),
+    GotRelayTuning {
+        config: RelayTuningConfig,
+        suggestions: Vec<RelaySuggestion>,
+        /// Applied at login by the automatic tuning
+        auto_applied: Vec<RelaySuggestion>,
+    },
+    GotRelayViolations(Vec<DbRelayViolation>),
+    GotRelayDocumentChanges(Vec<DbRelayDocumentChange>),
+    /// Found when a relay's NIP-11 document was refreshed
+    RelayDocumentChanged(Vec<DbRelayDocumentChange>),
+    /// A relay was caught flooding a subscription, its events are being dropped
+    RelayViolation(DbRelayViolation),
+    GotStorageUsage(StorageUsage),
+    StoragePruned(PruneReport),
+    /// `None` when the event isn't stored, with the reactions deleted along with it
+    GotLocalEvent {
+        event_hash: EventId,
+        event: Option<DbEvent>,
+        reactions: i64,
+    },
+    /// Number of events removed, reactions included
+    LocalEventDeleted(EventId, u64),
+    GotAccountStats(AccountStats),
+    GotAutoTranslate(XOnlyPublicKey, bool),
+    GotAutoSaveAttachments(XOnlyPublicKey, bool),
+    GotInbox(Vec<InboxItem>),
+    GotFollowers(Vec<FollowerItem>),
+    /// Someone added the user to their contact list
+    NewFollower(DbFollower),
+    GotLabels(Labels),
+    LabelError(String),
     GotChatInfo(DbContact, ChatInfo),
+    GotStarred(XOnlyPublicKey, Vec<i64>),
+    MessageStarred(i64, bool),
+    /// Reaction counts of the message with this event id
+    ReactionsUpdated(i64, Vec<ReactionCount>),
+    /// Card info from the messages left, `None` when none were kept
+    ChatHistoryCleared(DbContact, Option<ChatInfo>),
+    /// Event ids moved to the trash and the card info from the messages left
+    MessagesDeleted(DbContact, Vec<i64>, Option<ChatInfo>),
+    /// Deleted by its author, the chat's preview comes along
+    DmRetracted(DbContact, i64, Option<ChatInfo>),
     GotRelayStatusList(ns_client::RelayStatusList),
     CacheFileRemoved((ProfileCache, ImageKind)),
     RelayDocument(DbRelay),
@@ -686,14 +1921,26 @@ pub enum BackendEvent {
     GotNipsData(Vec<NipData>),
     GotProfileCache(XOnlyPublicKey, ProfileCache),
 
+    /// Frontend commands are overflowing their lanes
+    Backpressure(bool),
+    /// Metadata requests are waiting for the relays' request budget
+    Throttling(bool),
+    /// Waiting for the relays to confirm the pending events before closing
+    SyncingBeforeExit(usize),
+
     // --- Config ---
     NtpInfo {
         last_ntp_offset: i64,
         ntp_server: Option<String>,
     },
+    /// The clock offset moved noticeably since the last sync, in microseconds
+    NtpDriftChanged(i64),
     Connected(BackEndConnection),
     FinishedPreparing,
     LoginSuccess,
+    /// The backend failed and is being rebuilt, with the reason
+    BackendRestarting(String),
+    BackendRestarted,
     FirstLoginSuccess,
     FailedToStartClient,
     CreateAccountSuccess,
@@ -702,6 +1949,11 @@ pub enum BackendEvent {
 
     PendingChannelMsg(EventId, ChatMessage),
     PendingDM(DbContact, ChatMessage),
+    /// Same content sent to the conversation moments ago, not sent until confirmed
+    DuplicateDM(DbContact, String),
+    DuplicateChannelMsg(EventId, String),
+    /// A relay refused a pending event, another one can still accept it
+    PendingRejected(EventId, String),
     ReceivedDM {
         relay_url: Url,
         db_contact: DbContact,
@@ -711,6 +1963,8 @@ pub enum BackendEvent {
 
     // --- Confirmed Events ---
     ConfirmedDM(EventId, DbMessage, String),
+    /// Channel id, event hash and the stored message
+    ConfirmedChannelMsg(EventId, EventId, ChatMessage),
     ConfirmedContactList(DbEvent),
 
     // --- RFD ---
@@ -718,19 +1972,63 @@ pub enum BackendEvent {
     RFDPickError(String),
     RFDCancelPick,
     RFDSavedFile(PathBuf),
+    PdfExport(PdfExportStatus),
+    /// Attachment from the composer, sent in chunks
+    UploadProgress(UploadStatus),
+    /// Profile and contact list sent again after relays were added, `published` describes them
+    RelaysRepublished {
+        relays: Vec<Url>,
+        published: Vec<String>,
+    },
 
     LoadingChannelDetails(Url, EventId),
     GotChannelMessages(EventId, Vec<ChatMessage>),
     ReceivedChannelMessage(EventId, ChatMessage),
+    /// Deleted by its author
+    ChannelMessageRetracted(EventId, i64),
+    /// Messages of others after the read position of each subscribed channel
+    ChannelUnreadCounts(HashMap<EventId, i64>),
+    /// Unseen DMs and unread channel messages together
+    UnreadTotal(i64),
+    /// Every chat was marked as seen
+    AllChatsRead,
     ChannelSubscribed(EventId),
     ChannelUnsubscribed(EventId),
+    /// Unsubscribed, its history stays browsable
+    ChannelArchived(EventId),
+    /// Relay the channel was first seen on and a link others can join with
+    GotChannelDetails {
+        channel_id: EventId,
+        origin: Option<Url>,
+        invite_link: String,
+    },
+    /// Subscribed channels whose messages aren't counted as unread
+    GotMutedChannels(HashSet<EventId>),
     GotSubscribedChannels(Vec<ChannelCache>),
+    GotArchivedChannels(Vec<ChannelCache>),
+    GotSwitcherItems(Vec<SwitcherItem>),
+    /// Contact or subscribed channel visited last
+    GotLastConversation(SwitcherTarget),
+    /// Conversations with unsent text, newest first
+    GotDrafts(Vec<SwitcherItem>),
     ChannelCacheUpdated(ChannelCache),
+    /// Hide or mute event from the owner or a moderator of the channel
+    ChannelModerated(EventId),
 
     ChannelSearchCacheCreation(Url, ChannelCache),
     EOSESearchChannels(Url),
+    /// Channels the user's contacts are active in, `searching` while relays are asked
+    GotContactChannels {
+        channels: Vec<ContactChannel>,
+        searching: bool,
+    },
+    ContactChannelCache(ChannelCache),
     EOSESearchChannelsDetails(PrefixedId),
     GotChannelCache(ChannelCache),
+
+    // --- Correlated ---
+    Response(RequestId, Box<BackendEvent>),
+    RequestFailed(RequestId, String),
 }
 
 #[derive(Debug, Clone)]
@@ -739,19 +2037,32 @@ pub enum ToBackend {
     Logout,
     FetchLatestVersion,
     QueryFirstLogin,
+    /// How much DM history the first login syncs, sent before `PrepareClient`
+    SetDmSyncDepth(SyncDepth),
     PrepareClient,
     SetTheme(style::Theme),
     GetTheme,
 
     FetchRelayResponsesChatMsg(ChatMessage),
+    /// Publishes a NIP-09 deletion of the user's message and checks
+    /// whether the relays advertising it still return the message
+    RequestRelayDeletion(ChatMessage),
     FetchRelayResponsesUserProfile,
     FetchRelayResponsesContactList,
     FetchRelays,
     FetchRelay(Url),
     AddRelay(Url),
+    MeasureRelayLatency(Vec<Url>),
     DeleteRelay(Url),
     ToggleRelayRead(DbRelay),
+    FetchRelayProfiles,
+    /// Stores the current relays as the named profile
+    SaveRelayProfile(String),
+    DeleteRelayProfile(String),
+    /// Replaces the relays with the named profile's
+    SwitchRelayProfile(String),
     ToggleRelayWrite(DbRelay),
+    ToggleRelayAnonymousAuth(DbRelay),
     GetRelayInformation,
     FetchNipsData,
 
@@ -759,7 +2070,22 @@ pub enum ToBackend {
     AddContact(DbContact),
     UpdateContact(DbContact),
     DeleteContact(DbContact),
+    /// Like `DeleteContact`, the contact list is published once for all of them
+    DeleteContacts(Vec<DbContact>),
+    /// Folds the second contact into the first, the same person under two keys
+    MergeContacts(DbContact, DbContact),
     ImportContacts(Vec<DbContact>, bool),
+    FetchBlocked,
+    /// Drops the user's events from now on, `true` also adds them to the public mute list
+    BlockUser(XOnlyPublicKey, bool),
+    UnblockUser(XOnlyPublicKey),
+    FetchMutedUsers,
+    /// Adds to or removes from the public mute list, `true` mutes
+    MuteUser(XOnlyPublicKey, bool),
+    FetchTrash,
+    RestoreFromTrash(TrashKind, XOnlyPublicKey),
+    PurgeFromTrash(TrashKind, XOnlyPublicKey),
+    EmptyTrash,
 
     FetchMessages(DbContact),
     GetNtpInfo,
@@ -767,18 +2093,180 @@ pub enum ToBackend {
     UpdateUserProfileMeta(Metadata),
     FetchAllMessageEvents,
     ExportMessages(Vec<DbEvent>),
+    CountFilteredEvents(EventFilter),
+    ExportFilteredEvents(EventFilter),
     ExportContacts,
+    /// Spreadsheet of the contacts with their profile and last message dates
+    ExportContactsCsv,
+    /// Contact list event with only these contacts, not published
+    ExportSelectedContacts(Vec<DbContact>),
+    ExportRelays(RelayExportFormat),
+    /// Printable copy of the conversation, `title` heads the first page
+    ExportConversationPdf {
+        title: String,
+        messages: Vec<ChatMessage>,
+        include_images: bool,
+    },
+    /// Zip with the signed DMs, their transcript and a manifest to verify one against the other
+    ExportConversationArchive(DbContact),
+    /// Adds the relays that aren't in the list yet
+    ImportRelays(Vec<ImportedRelay>),
     FetchChatInfo(DbContact),
+    /// Event ids of the starred messages, answered with `GotStarred`
+    FetchStarred(DbContact),
+    StarMessage(i64, bool),
+    /// Reacts to the message with this event id, or takes the user's reaction back
+    SendReaction(i64, String),
+    /// Moves the messages to the trash, only on this device
+    ClearChatHistory {
+        contact: DbContact,
+        keep_starred: bool,
+    },
+    /// Moves the selected messages to the trash, only on this device
+    DeleteMessages(DbContact, Vec<i64>),
+    /// Sends each content to the contact as a new message, in order
+    ForwardMessages(DbContact, Vec<String>),
     FetchContactWithMetadata(XOnlyPublicKey),
+    FetchConversationRelays(DbContact),
+    FetchSharedChannels(XOnlyPublicKey),
+    GetStageContactChanges,
+    SetStageContactChanges(bool),
+    GetDoNotTrack,
+    SetDoNotTrack(bool),
+    GetDeveloperConsole,
+    SetDeveloperConsole(bool),
+    GetMediaProxy,
+    SetMediaProxy(MediaProxyConfig),
+    GetSendKey,
+    SetSendKey(SendKey),
+    GetDimImages,
+    SetDimImages(bool),
+    GetAutoLock,
+    SetAutoLock(AutoLockConfig),
+    GetTimeFormat,
+    SetTimeFormat(TimeFormatConfig),
+    GetTextDirection,
+    GetStartupView,
+    SetStartupView(StartupView),
+    SetTextDirection(TextDirection),
+    GetPresence,
+    SetPresence(PresenceConfig),
+    /// Sent by the app when the status to publish changes
+    PublishPresence(Presence),
+    GetDownloads,
+    SetDownloads(DownloadConfig),
+    ChooseDownloadDirectory,
+    /// Public key of the secret key entered in the lock screen
+    Unlock(XOnlyPublicKey),
+    /// Filter JSON sent as is to the chosen relays, the events are only shown
+    RunConsoleReq(String, Vec<Url>),
+    GetSimulation,
+    /// Ignored outside dev mode
+    SetSimulation(Simulation),
+    /// Events kept from the relays by the dry run
+    FetchDryRuns,
+    /// Drops every relay connection, they reconnect on their own
+    SimulateDisconnect,
+    /// Startup check report, sent only once
+    TakeIntegrityReport,
+    FetchContactListDiff,
+    PublishContactList,
+    FetchTemplates,
+    /// Name and content
+    AddTemplate(String, String),
+    UpdateTemplate(DbTemplate),
+    DeleteTemplate(DbTemplate),
+    GetTranslationConfig,
+    GetStorageConfig,
+    SetStorageConfig(StorageConfig),
+    /// Read and write changes suggested by the relays' activity
+    FetchRelayTuning,
+    SetRelayTuningConfig(RelayTuningConfig),
+    ApplyRelaySuggestions(Vec<RelaySuggestion>),
+    FetchRelayViolations,
+    ClearRelayViolations(Url),
+    FetchRelayDocumentChanges,
+    DismissRelayDocumentChanges(Url),
+    FetchStorageUsage,
+    /// Applies the storage limits right away
+    PruneStorage,
+    FetchLocalEvent(EventId),
+    /// Deletes the stored event with its reactions and derived messages
+    DeleteLocalEvent(EventId),
+    /// Aggregates the account statistics in a background task
+    ComputeStats,
+    SetTranslationConfig(TranslationConfig),
+    TranslateMessage(ChatMessage),
+    FetchAutoTranslate(DbContact),
+    SetAutoTranslate(DbContact, bool),
+    FetchAutoSaveAttachments(DbContact),
+    SetAutoSaveAttachments(DbContact, bool),
+    /// Saves the message's attachments first when they aren't yet
+    ShowInFolder(ChatMessage, DbContact),
+    FetchInbox,
+    FetchFollowers,
+    /// Marks every follower as shown in the activity panel
+    FollowersSeen,
+    FetchLabels,
+    /// Name, color and notifications
+    AddLabel(String, String, LabelNotify),
+    UpdateLabel(DbLabel),
+    DeleteLabel(DbLabel),
+    /// Label and conversation, a contact public key or a channel id
+    ToggleLabel(i64, String),
+    /// Adds the label to every conversation, keeps it where it's already set
+    AssignLabel(i64, Vec<String>),
+    /// Marks the item as seen, removing it from the inbox
+    InboxSeen(InboxItem),
     SendDM(DbContact, String),
     SendChannelMessage(EventId, String),
+    /// Sent again after the user confirmed a duplicate
+    ResendDM(DbContact, String),
+    ResendChannelMessage(EventId, String),
+    /// Channel, question and options
+    CreatePoll(EventId, String, Vec<String>),
+    /// Channel, poll and option
+    VotePoll(EventId, EventId, String),
+    FetchChannelPolls(EventId),
+    FetchChannelKeywords(EventId),
+    /// Replaces the words watched in the channel
+    SetChannelKeywords(EventId, Vec<String>),
+    /// Asks the relays for the latest notes with the hashtag
+    FetchHashtagFeed(String),
+    /// Asks the relays what they have of the user's account before the first sync
+    PreviewAccount,
+    FetchFollowedHashtags,
+    FollowHashtag(String),
+    UnfollowHashtag(String),
+    FetchLiveActivities,
+    FetchMessageLink(ChatMessage),
+    /// Event of a link and its relay hints, used when it isn't stored
+    ResolveMessageLink(EventId, Vec<String>),
     CreateChannel,
     FetchMoreMessages(DbContact, NaiveDateTime),
+    /// Extends the conversation's history further back on the relays
+    FetchOlderDms(DbContact),
     ChooseFile(Option<FileFilter>),
+    /// Picks a file and uploads it for the conversation's composer,
+    /// `keep_metadata` skips stripping an image for this file
+    ChooseAttachment {
+        target: DraftTarget,
+        keep_metadata: bool,
+    },
+    /// Stops an upload or drops a failed one
+    CancelUpload(u64),
+    /// Sends a failed upload again, the file isn't read again
+    ResumeUpload(u64),
     LoginWithSK(Keys),
     CreateAccount(BasicProfile),
     FindChannels(String),
+    /// Ranks the channels the contacts are active in
+    FetchContactChannels,
     FetchKeys,
+    /// Encrypts the keys for another device
+    CreatePairing,
+    /// Events signed with the user's keys
+    FetchSigningLog,
     DownloadImage {
         image_url: String,
         kind: ImageKind,
@@ -790,15 +2278,241 @@ pub enum ToBackend {
     ReconnectRelay(url::Url),
     MessageSeen(i64),
     FetchChannelMessages(EventId),
+    /// Channel and the database id of the newest message scrolled past
+    MarkChannelRead(EventId, i64),
+    /// Read position moved to the newest message of the channel
+    MarkChannelAllRead(EventId),
+    /// Shares the read position of every channel with the user's other devices
+    PublishChannelReadMarkers,
+    /// Every DM is marked as seen
+    MarkAllChatsRead,
+    FetchUnreadTotal,
     FetchMembersInfo(std::collections::HashSet<XOnlyPublicKey>),
     FetchProfileCache(XOnlyPublicKey),
+    /// Looks up the public key of a name@domain address
+    ResolveNip05(String),
+    /// Checks the LNURL-pay endpoint unless a recent check is cached
+    CheckLightningAddress(LightningAddress),
+    /// Same check for an address the user is shown rather than typed,
+    /// dropped in do-not-track mode
+    AutoCheckLightningAddress(LightningAddress),
+    /// Asks the address for an invoice of the given sats
+    RequestZapInvoice(LightningAddress, u64),
+    /// Checks that the address answers
+    CheckUrl(String),
+    /// Checks that the name@domain address points to the user's public key
+    VerifyOwnNip05(String),
+    /// Crops the local image and uploads it to the configured media server
+    UploadProfileImage {
+        path: PathBuf,
+        crop: CropRect,
+        kind: ProfileImage,
+    },
+    /// Asks the relays again for the profiles of these public keys
+    RefreshMetadata(Vec<XOnlyPublicKey>),
 
     SubscribeToChannel(nostr::EventId),
     UnsubscribeToChannel(nostr::EventId),
+    /// Stops the live subscription but keeps the channel listed read-only
+    ArchiveChannel(nostr::EventId),
+    SetChannelMuted(nostr::EventId, bool),
+    FetchChannelDetails(nostr::EventId),
     FetchSubscribedChannels,
+    FetchArchivedChannels,
+    /// Contacts and subscribed channels with their frecency
+    FetchSwitcherItems,
+    FetchLastConversation,
+    FetchDrafts,
+    /// Empty text removes the draft
+    SaveDraft(DraftTarget, String),
     FetchChannelCache(EventId),
     SubscribeToChannelDetails(Url, Vec<EventId>),
     SubscribeChannelMembersMeta(EventId),
+    /// Metadata and moderators of a channel created by the user
+    UpdateChannel(EventId, ChannelMetadata, Vec<XOnlyPublicKey>),
+
+    /// Command whose resulting events are echoed with its id
+    Request(RequestId, Box<ToBackend>),
+}
+impl ToBackend {
+    /// Lane used to send the command to the backend
+    pub fn priority(&self) -> Priority {
+        match self {
+            ToBackend::Shutdown
+            | ToBackend::Logout
+            | ToBackend::LoginWithSK(_)
+            | ToBackend::CreateAccount(_)
+            | ToBackend::SetTheme(_)
+            | ToBackend::SetDmSyncDepth(_)
+            | ToBackend::SendDM(..)
+            | ToBackend::SendChannelMessage(..)
+            | ToBackend::ResendDM(..)
+            | ToBackend::ResendChannelMessage(..)
+            | ToBackend::SaveDraft(..)
+            | ToBackend::CreatePoll(..)
+            | ToBackend::VotePoll(..)
+            | ToBackend::MessageSeen(_)
+            | ToBackend::MarkChannelRead(..)
+            | ToBackend::MarkChannelAllRead(_)
+            | ToBackend::MarkAllChatsRead
+            | ToBackend::AddContact(_)
+            | ToBackend::UpdateContact(_)
+            | ToBackend::DeleteContact(_)
+            | ToBackend::DeleteContacts(_)
+            | ToBackend::MergeContacts(_, _)
+            | ToBackend::BlockUser(..)
+            | ToBackend::UnblockUser(_)
+            | ToBackend::MuteUser(..)
+            | ToBackend::RestoreFromTrash(..)
+            | ToBackend::PurgeFromTrash(..)
+            | ToBackend::EmptyTrash
+            | ToBackend::ImportContacts(..)
+            | ToBackend::PublishContactList
+            | ToBackend::AddTemplate(..)
+            | ToBackend::UpdateTemplate(_)
+            | ToBackend::DeleteTemplate(_)
+            | ToBackend::SetDoNotTrack(_)
+            | ToBackend::SetDeveloperConsole(_)
+            | ToBackend::SetMediaProxy(_)
+            | ToBackend::SetSendKey(_)
+            | ToBackend::SetDimImages(_)
+            | ToBackend::SetAutoLock(_)
+            | ToBackend::SetTimeFormat(_)
+            | ToBackend::SetTextDirection(_)
+            | ToBackend::SetStartupView(_)
+            | ToBackend::SetPresence(_)
+            | ToBackend::SetDownloads(_)
+            | ToBackend::ChooseDownloadDirectory
+            | ToBackend::Unlock(_)
+            | ToBackend::SetSimulation(_)
+            | ToBackend::SetTranslationConfig(_)
+            | ToBackend::SetStorageConfig(_)
+            | ToBackend::SetRelayTuningConfig(_)
+            | ToBackend::ApplyRelaySuggestions(_)
+            | ToBackend::ClearRelayViolations(_)
+            | ToBackend::DismissRelayDocumentChanges(_)
+            | ToBackend::PruneStorage
+            | ToBackend::DeleteLocalEvent(_)
+            | ToBackend::SetAutoTranslate(..)
+            | ToBackend::SetAutoSaveAttachments(..)
+            | ToBackend::ShowInFolder(..)
+            | ToBackend::InboxSeen(_)
+            | ToBackend::FollowersSeen
+            | ToBackend::AddLabel(..)
+            | ToBackend::UpdateLabel(_)
+            | ToBackend::DeleteLabel(_)
+            | ToBackend::ToggleLabel(..)
+            | ToBackend::AssignLabel(..)
+            | ToBackend::AddRelay(_)
+            | ToBackend::DeleteRelay(_)
+            | ToBackend::ToggleRelayRead(_)
+            | ToBackend::ToggleRelayWrite(_)
+            | ToBackend::SaveRelayProfile(_)
+            | ToBackend::DeleteRelayProfile(_)
+            | ToBackend::SwitchRelayProfile(_)
+            | ToBackend::ToggleRelayAnonymousAuth(_)
+            | ToBackend::ReconnectRelay(_)
+            | ToBackend::UpdateUserProfileMeta(_)
+            | ToBackend::CreatePairing
+            | ToBackend::SubscribeToChannel(_)
+            | ToBackend::UnsubscribeToChannel(_)
+            | ToBackend::ArchiveChannel(_)
+            | ToBackend::SetChannelMuted(_, _)
+            | ToBackend::ChooseFile(_)
+            | ToBackend::ChooseAttachment { .. }
+            | ToBackend::CancelUpload(_)
+            | ToBackend::ResumeUpload(_)
+            | ToBackend::UploadProfileImage { .. }
+            | ToBackend::RequestRelayDeletion(_)
+            | ToBackend::ExportMessages(_)
+            | ToBackend::ExportFilteredEvents(_)
+            | ToBackend::ExportContacts
+            | ToBackend::ExportContactsCsv
+            | ToBackend::ExportSelectedContacts(_)
+            | ToBackend::ExportRelays(_)
+            | ToBackend::ExportConversationPdf { .. }
+            | ToBackend::ExportConversationArchive(_)
+            | ToBackend::StarMessage(..)
+            | ToBackend::SendReaction(..)
+            | ToBackend::ClearChatHistory { .. }
+            | ToBackend::DeleteMessages(..)
+            | ToBackend::ForwardMessages(..)
+            | ToBackend::ImportRelays(_)
+            | ToBackend::UpdateChannel(..)
+            | ToBackend::SetChannelKeywords(..)
+            | ToBackend::FollowHashtag(_)
+            | ToBackend::UnfollowHashtag(_) => Priority::High,
+            ToBackend::Request(_, message) => message.priority(),
+            _ => Priority::Low,
+        }
+    }
+
+    /// Outbound traffic the user didn't explicitly ask for, dropped in do-not-track mode.
+    /// Typing indicators, read receipts, user status and NIP-05 refreshes belong here.
+    pub fn is_tracking_signal(&self) -> bool {
+        match self {
+            ToBackend::FetchLatestVersion
+            | ToBackend::AutoCheckLightningAddress(_)
+            | ToBackend::PublishPresence(_)
+            | ToBackend::PublishChannelReadMarkers => true,
+            ToBackend::Request(_, message) => message.is_tracking_signal(),
+            _ => false,
+        }
+    }
+}
+
+/// Processes a correlated command, wrapping every event it produces
+/// in `BackendEvent::Response` so the requesting view can recognize it.
+async fn process_request(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    task_tx: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+    id: RequestId,
+    message: ToBackend,
+) {
+    let result = match message {
+        ToBackend::Shutdown | ToBackend::Logout | ToBackend::Request(..) => {
+            Err(Error::UncorrelatedCommand(format!("{:?}", message)))
+        }
+        other => {
+            let (mut req_output, mut req_events) =
+                futures::channel::mpsc::channel(BACKEND_CHANNEL_SIZE);
+            // forwarded while the command runs, a command producing more events
+            // than the channel holds would otherwise wait on itself
+            let work = async {
+                let result = process_message(&mut req_output, keys, backend, task_tx, other).await;
+                drop(req_output);
+                result
+            };
+            let forward = async {
+                while let Some(event) = req_events.next().await {
+                    _ = output
+                        .send(BackendEvent::Response(id, Box::new(event)))
+                        .await;
+                }
+            };
+            let (result, ()) = futures::join!(work, forward);
+            result
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::error!("{} - {}", id, e);
+        _ = output
+            .send(BackendEvent::RequestFailed(id, e.to_string()))
+            .await;
+    }
+}
+
+/// Tracking signals aren't sent in do-not-track mode, also for the ones a
+/// command sends along with its own work
+fn is_held_back(backend: &BackendState, message: &ToBackend) -> bool {
+    let held_back = backend.do_not_track && message.is_tracking_signal();
+    if held_back {
+        tracing::debug!("Do not track, dropping: {:?}", message);
+    }
+    held_back
 }
 
 pub async fn process_message(
@@ -809,6 +2523,12 @@ pub async fn process_message(
     message: ToBackend,
 ) -> Result<(), Error> {
     tracing::trace!("Processing message: {:?}", message);
+    if is_held_back(backend, &message) {
+        return Ok(());
+    }
+    if let Some(latency) = backend.simulation.latency() {
+        tokio::time::sleep(latency).await;
+    }
     match message {
         // ---- CONFIG ----
         ToBackend::LoginWithSK(_) => {
@@ -823,6 +2543,9 @@ pub async fn process_message(
         ToBackend::Shutdown => {
             unreachable!("Shutdown should be processed outside here")
         }
+        ToBackend::Request(..) => {
+            unreachable!("Request should be processed outside here")
+        }
         // --- RFD ---
         ToBackend::ExportMessages(messages) => {
             let ns_events: Result<Vec<_>, _> = messages.iter().map(|m| m.to_ns_event()).collect();
@@ -837,6 +2560,19 @@ pub async fn process_message(
                 }
             }
         }
+        ToBackend::ExportFilteredEvents(filter) => {
+            let db_events = DbEvent::fetch_filtered(backend.pool(), &filter).await?;
+            let ns_events: Result<Vec<_>, _> = db_events.iter().map(|e| e.to_ns_event()).collect();
+            match save_file(&ns_events?, "json").await {
+                Ok(event) => {
+                    _ = output.send(event).await;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to export events: {}", e);
+                    _ = output.send(BackendEvent::RFDPickError(e.to_string())).await;
+                }
+            }
+        }
         ToBackend::ExportContacts => {
             let pending_event = backend.new_contact_list_event(keys).await?;
             match save_file(pending_event.ns_event(), "json").await {
@@ -849,6 +2585,143 @@ pub async fn process_message(
                 }
             }
         }
+        ToBackend::ExportContactsCsv => {
+            let db_contacts = DbContact::fetch(backend.pool()).await?;
+            let last_messages = DbMessage::fetch_last_dates(backend.pool()).await?;
+            let csv = contacts_to_csv(&db_contacts, &last_messages);
+            match save_bytes(csv.as_bytes(), "csv").await {
+                Ok(event) => {
+                    _ = output.send(event).await;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to export contacts: {}", e);
+                    _ = output.send(BackendEvent::RFDPickError(e.to_string())).await;
+                }
+            }
+        }
+        ToBackend::ExportSelectedContacts(db_contacts) => {
+            let ns_event = backend.contact_list_event(keys, &db_contacts).await?;
+            match save_file(&ns_event, "json").await {
+                Ok(event) => {
+                    _ = output.send(event).await;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to export contacts: {}", e);
+                    _ = output.send(BackendEvent::RFDPickError(e.to_string())).await;
+                }
+            }
+        }
+        ToBackend::ExportRelays(format) => {
+            let relays = DbRelay::fetch(backend.pool()).await?;
+            let saved = match format {
+                RelayExportFormat::Text => {
+                    save_bytes(relays_to_text(&relays).as_bytes(), "txt").await
+                }
+                RelayExportFormat::Nip65 => {
+                    let ns_event = backend.relay_list_event(keys, &relays).await?;
+                    save_file(&ns_event, "json").await
+                }
+            };
+            match saved {
+                Ok(event) => {
+                    _ = output.send(event).await;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to export relays: {}", e);
+                    _ = output.send(BackendEvent::RFDPickError(e.to_string())).await;
+                }
+            }
+        }
+        ToBackend::ExportConversationPdf {
+            title,
+            messages,
+            include_images,
+        } => {
+            let file_handle = AsyncFileDialog::new()
+                .set_directory("/")
+                .set_file_name(&format!("{}.pdf", title))
+                .save_file()
+                .await;
+            match file_handle {
+                Some(file_handle) => {
+                    let mut path = file_handle.path().to_path_buf();
+                    path.set_extension("pdf");
+                    let progress = PdfExportStatus::Progress {
+                        done: 0,
+                        total: messages.len(),
+                    };
+                    _ = output.send(BackendEvent::PdfExport(progress)).await;
+                    spawn_pdf_export(
+                        path,
+                        title,
+                        messages,
+                        include_images,
+                        backend.media_client.clone(),
+                        task_tx.clone(),
+                    );
+                }
+                None => {
+                    _ = output.send(BackendEvent::RFDCancelPick).await;
+                }
+            }
+        }
+        ToBackend::ExportConversationArchive(db_contact) => {
+            let file_handle = AsyncFileDialog::new()
+                .set_directory("/")
+                .set_file_name(&format!("{}.zip", db_contact.select_name()))
+                .save_file()
+                .await;
+            match file_handle {
+                Some(file_handle) => {
+                    let status =
+                        match export_conversation_archive(backend, keys, &db_contact, file_handle)
+                            .await
+                        {
+                            Ok(path) => PdfExportStatus::Saved {
+                                path,
+                                unprintable: 0,
+                            },
+                            Err(e) => {
+                                tracing::error!("Failed to export conversation archive: {}", e);
+                                PdfExportStatus::Failed(e.to_string())
+                            }
+                        };
+                    _ = output.send(BackendEvent::PdfExport(status)).await;
+                }
+                None => {
+                    _ = output.send(BackendEvent::RFDCancelPick).await;
+                }
+            }
+        }
+        ToBackend::ImportRelays(imported) => {
+            let mut added = vec![];
+            for relay in imported {
+                if DbRelay::fetch_by_url(backend.pool(), &relay.url)
+                    .await?
+                    .is_some()
+                {
+                    continue;
+                }
+                backend.nostr.add_relay(relay.url.as_str())?;
+                let mut db_relay = DbRelay::insert(backend.pool(), &relay.url).await?;
+                if db_relay.read != relay.read || db_relay.write != relay.write {
+                    db_relay.read = relay.read;
+                    db_relay.write = relay.write;
+                    backend
+                        .nostr
+                        .toggle_read_for(&db_relay.url, db_relay.read)?;
+                    backend
+                        .nostr
+                        .toggle_write_for(&db_relay.url, db_relay.write)?;
+                    DbRelay::update(backend.pool(), &db_relay).await?;
+                }
+                if db_relay.write {
+                    added.push(db_relay.url.clone());
+                }
+                _ = output.send(BackendEvent::RelayCreated(db_relay)).await;
+            }
+            republish_to_new_relays(output, keys, backend, added).await?;
+        }
         ToBackend::ChooseFile(file_filter_opt) => {
             let mut rfd_instance = AsyncFileDialog::new().set_directory("/");
             if let Some(filter) = &file_filter_opt {
@@ -872,39 +2745,215 @@ pub async fn process_message(
                 }
             }
         }
+        ToBackend::ChooseAttachment {
+            target,
+            keep_metadata,
+        } => {
+            if let Some(handle) = AsyncFileDialog::new().set_directory("/").pick_file().await {
+                let id = backend.next_upload_id;
+                backend.next_upload_id += 1;
+                let mut upload = Upload::new(target, handle.path().to_owned(), keep_metadata);
+                upload.spawn(
+                    id,
+                    backend.req_client.clone(),
+                    backend.pool().clone(),
+                    keys.clone(),
+                    task_tx.clone(),
+                );
+                let status = upload.status(id, UploadState::Sending { sent: 0, total: 0 });
+                backend.uploads.insert(id, upload);
+                _ = output.send(BackendEvent::UploadProgress(status)).await;
+            }
+        }
+        ToBackend::CancelUpload(id) => {
+            if let Some(mut upload) = backend.uploads.remove(&id) {
+                upload.abort();
+                let status = upload.status(id, UploadState::Cancelled);
+                _ = output.send(BackendEvent::UploadProgress(status)).await;
+            }
+        }
+        ToBackend::ResumeUpload(id) => {
+            let req_client = backend.req_client.clone();
+            let pool = backend.pool().clone();
+            if let Some(upload) = backend.uploads.get_mut(&id) {
+                upload.spawn(id, req_client, pool, keys.clone(), task_tx.clone());
+                let status = upload.status(id, UploadState::Sending { sent: 0, total: 0 });
+                _ = output.send(BackendEvent::UploadProgress(status)).await;
+            }
+        }
         // ---- REQWEST ----
-        ToBackend::FetchLatestVersion => {
+        ToBackend::ResolveNip05(address) => {
             let task_tx_1 = task_tx.clone();
             let req_client_1 = backend.req_client.clone();
             tokio::spawn(async move {
-                let result = fetch_latest_version(req_client_1)
+                let result = resolve_nip05(req_client_1, &address)
                     .await
-                    .map(TaskOutput::LatestVersion)
-                    .map_err(|e| e.into());
-                if let Err(e) = task_tx_1.send(result).await {
-                    tracing::error!("Error sending latest version to backend: {}", e);
+                    .map_err(|e| e.to_string());
+                if let Err(e) = task_tx_1.send(Ok(TaskOutput::Nip05(address, result))).await {
+                    tracing::error!("Error sending NIP-05 lookup to backend: {}", e);
                 }
             });
-            _ = output.send(BackendEvent::FetchingLatestVersion).await;
         }
-        ToBackend::DownloadImage {
-            image_url,
-            identifier,
-            kind,
-            event_hash,
-        } => match ImageDownloaded::fetch(backend.cache_pool(), &event_hash, kind).await? {
-            Some(image) => {
-                _ = output.send(BackendEvent::ImageDownloaded(image)).await;
-            }
-            None => {
-                let task_tx_1 = task_tx.clone();
-                let identifier_1 = identifier.clone();
-                let image_url_1 = image_url.to_string();
-                tokio::spawn(async move {
-                    let result = download_image(&image_url_1, &event_hash, &identifier_1, kind)
+        ToBackend::CheckLightningAddress(address)
+        | ToBackend::AutoCheckLightningAddress(address) => {
+            let cached = LightningCheck::fetch(backend.cache_pool(), address.as_str()).await?;
+            match cached {
+                Some(check) if check.is_fresh(chrono::Utc::now().naive_utc()) => {
+                    _ = output
+                        .send(BackendEvent::LightningChecked(check.address, check.valid))
+                        .await;
+                }
+                _ => {
+                    let task_tx_1 = task_tx.clone();
+                    let req_client_1 = backend.req_client.clone();
+                    tokio::spawn(async move {
+                        let valid = match address.endpoint() {
+                            Ok(endpoint) => check_lnurl_pay(req_client_1, endpoint)
+                                .await
+                                .unwrap_or_else(|e| {
+                                    tracing::info!("Lightning address {}: {}", address, e);
+                                    false
+                                }),
+                            Err(e) => {
+                                tracing::info!("{}", e);
+                                false
+                            }
+                        };
+                        let check = LightningCheck::new(address.as_str(), valid);
+                        if let Err(e) = task_tx_1
+                            .send(Ok(TaskOutput::LightningChecked(check)))
+                            .await
+                        {
+                            tracing::error!("Error sending lightning check to backend: {}", e);
+                        }
+                    });
+                }
+            }
+        }
+        ToBackend::RequestZapInvoice(address, sats) => {
+            let task_tx_1 = task_tx.clone();
+            let req_client_1 = backend.req_client.clone();
+            tokio::spawn(async move {
+                let result = match address.endpoint() {
+                    Ok(endpoint) => fetch_lnurl_invoice(req_client_1, endpoint, sats)
                         .await
-                        .map(TaskOutput::ImageDownloaded)
-                        .map_err(|e| e.into());
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                if let Err(e) = task_tx_1
+                    .send(Ok(TaskOutput::ZapInvoice(sats, result)))
+                    .await
+                {
+                    tracing::error!("Error sending zap invoice to backend: {}", e);
+                }
+            });
+        }
+        ToBackend::CheckUrl(url) => {
+            let task_tx_1 = task_tx.clone();
+            let req_client_1 = backend.req_client.clone();
+            tokio::spawn(async move {
+                let reachable = match Url::parse(&url) {
+                    Ok(parsed) => check_url_reachable(req_client_1, parsed)
+                        .await
+                        .unwrap_or_else(|e| {
+                            tracing::info!("{}: {}", url, e);
+                            false
+                        }),
+                    Err(_) => false,
+                };
+                if let Err(e) = task_tx_1
+                    .send(Ok(TaskOutput::UrlChecked(url, reachable)))
+                    .await
+                {
+                    tracing::error!("Error sending url check to backend: {}", e);
+                }
+            });
+        }
+        ToBackend::UploadProfileImage { path, crop, kind } => {
+            let task_tx_1 = task_tx.clone();
+            let req_client_1 = backend.req_client.clone();
+            let pool_1 = backend.pool().clone();
+            let keys_1 = keys.clone();
+            tokio::spawn(async move {
+                let upload = async {
+                    let options = Config::load_file_async().await?.attachments;
+                    let image = crop_profile_image_file(path, crop, kind, options.clone()).await?;
+                    let url = nip96::upload_image(
+                        req_client_1,
+                        &pool_1,
+                        &keys_1,
+                        &options.upload_server,
+                        &image,
+                    )
+                    .await?;
+                    Ok::<_, Error>(url)
+                };
+                let result = upload.await.map_err(|e| e.to_string());
+                if let Err(e) = task_tx_1
+                    .send(Ok(TaskOutput::ProfileImageUploaded(kind, result)))
+                    .await
+                {
+                    tracing::error!("Error sending profile image upload to backend: {}", e);
+                }
+            });
+        }
+        ToBackend::VerifyOwnNip05(address) => {
+            let task_tx_1 = task_tx.clone();
+            let req_client_1 = backend.req_client.clone();
+            let public_key = keys.public_key();
+            tokio::spawn(async move {
+                let result = match resolve_nip05(req_client_1, &address).await {
+                    Ok(profile) if profile.pubkey == public_key => Ok(()),
+                    Ok(_) => Err("Points to another public key".to_owned()),
+                    Err(e) => Err(e.to_string()),
+                };
+                if let Err(e) = task_tx_1
+                    .send(Ok(TaskOutput::OwnNip05(address, result)))
+                    .await
+                {
+                    tracing::error!("Error sending NIP-05 verification to backend: {}", e);
+                }
+            });
+        }
+        ToBackend::FetchLatestVersion => {
+            let task_tx_1 = task_tx.clone();
+            let req_client_1 = backend.req_client.clone();
+            tokio::spawn(async move {
+                let result = fetch_latest_version(req_client_1)
+                    .await
+                    .map(TaskOutput::LatestVersion)
+                    .map_err(|e| e.into());
+                if let Err(e) = task_tx_1.send(result).await {
+                    tracing::error!("Error sending latest version to backend: {}", e);
+                }
+            });
+            _ = output.send(BackendEvent::FetchingLatestVersion).await;
+        }
+        ToBackend::DownloadImage {
+            image_url,
+            identifier,
+            kind,
+            event_hash,
+        } => match ImageDownloaded::fetch(backend.cache_pool(), &event_hash, kind).await? {
+            Some(image) => {
+                _ = output.send(BackendEvent::ImageDownloaded(image)).await;
+            }
+            None => {
+                let task_tx_1 = task_tx.clone();
+                let identifier_1 = identifier.clone();
+                let image_url_1 = image_url.to_string();
+                let media_client = backend.media_client.clone();
+                tokio::spawn(async move {
+                    let result = download_image(
+                        &media_client,
+                        &image_url_1,
+                        &event_hash,
+                        &identifier_1,
+                        kind,
+                    )
+                    .await
+                    .map(TaskOutput::ImageDownloaded)
+                    .map_err(|e| e.into());
                     if let Err(e) = task_tx_1.send(result).await {
                         tracing::error!("Error sending image downloaded event: {}", e);
                     }
@@ -914,7 +2963,565 @@ pub async fn process_message(
                     .await;
             }
         },
+        ToBackend::TranslateMessage(chat_message) => {
+            let Some(event_id) = chat_message.event_id() else {
+                return Ok(());
+            };
+            let Some(db_event) = DbEvent::fetch_id(backend.pool(), event_id).await? else {
+                return Ok(());
+            };
+            let config = Config::load_file_async().await?.translation;
+            let cached = DbTranslation::fetch(
+                backend.cache_pool(),
+                &db_event.event_hash,
+                &config.target_lang,
+            )
+            .await?;
+            if let Some(translation) = cached {
+                _ = output
+                    .send(BackendEvent::MessageTranslated(event_id, translation))
+                    .await;
+            } else if config.is_enabled() {
+                let task_tx_1 = task_tx.clone();
+                let req_client_1 = backend.req_client.clone();
+                let event_hash = db_event.event_hash;
+                let content = chat_message.content().to_owned();
+                tokio::spawn(async move {
+                    let result = translate(req_client_1, &config, &content)
+                        .await
+                        .map(|(content, source_lang)| {
+                            TaskOutput::Translated(
+                                event_id,
+                                DbTranslation {
+                                    event_hash,
+                                    target_lang: config.target_lang.to_owned(),
+                                    source_lang,
+                                    content,
+                                },
+                            )
+                        })
+                        .map_err(|e| e.into());
+                    if let Err(e) = task_tx_1.send(result).await {
+                        tracing::error!("Error sending translation to backend: {}", e);
+                    }
+                });
+            }
+        }
+        // -----------
+        ToBackend::FetchInbox => {
+            send_inbox(output, keys, backend).await?;
+        }
+        ToBackend::InboxSeen(item) => {
+            let Some(event_id) = item.event_id() else {
+                return Ok(());
+            };
+            match item {
+                InboxItem::DM { .. } => DbMessage::mark_seen(backend.pool(), event_id).await?,
+                InboxItem::Mention { .. } => {
+                    ChannelMention::mark_seen(backend.pool(), event_id).await?
+                }
+            }
+            send_inbox(output, keys, backend).await?;
+        }
+        ToBackend::FetchFollowers => {
+            send_followers(output, backend).await?;
+        }
+        ToBackend::FollowersSeen => {
+            DbFollower::mark_all_seen(backend.pool()).await?;
+        }
+        // -----------
+        ToBackend::FetchLabels => {
+            let labels = DbLabel::fetch_labels(backend.pool()).await?;
+            _ = output.send(BackendEvent::GotLabels(labels)).await;
+        }
+        ToBackend::AddLabel(name, color, notify) => {
+            let pool = backend.pool();
+            match DbLabel::insert(pool, &name, &color, notify).await {
+                Ok(_) => {
+                    let labels = DbLabel::fetch_labels(pool).await?;
+                    _ = output.send(BackendEvent::GotLabels(labels)).await;
+                }
+                Err(e) => {
+                    _ = output.send(BackendEvent::LabelError(e.to_string())).await;
+                }
+            }
+        }
+        ToBackend::UpdateLabel(label) => {
+            let pool = backend.pool();
+            match DbLabel::update(pool, &label).await {
+                Ok(_) => {
+                    let labels = DbLabel::fetch_labels(pool).await?;
+                    _ = output.send(BackendEvent::GotLabels(labels)).await;
+                }
+                Err(e) => {
+                    _ = output.send(BackendEvent::LabelError(e.to_string())).await;
+                }
+            }
+        }
+        ToBackend::DeleteLabel(label) => {
+            let pool = backend.pool();
+            DbLabel::delete(pool, &label).await?;
+            let labels = DbLabel::fetch_labels(pool).await?;
+            _ = output.send(BackendEvent::GotLabels(labels)).await;
+        }
+        ToBackend::ToggleLabel(label_id, conversation) => {
+            let pool = backend.pool();
+            DbLabel::toggle(pool, label_id, &conversation).await?;
+            let labels = DbLabel::fetch_labels(pool).await?;
+            _ = output.send(BackendEvent::GotLabels(labels)).await;
+        }
+        ToBackend::AssignLabel(label_id, conversations) => {
+            let pool = backend.pool();
+            for conversation in &conversations {
+                DbLabel::assign(pool, label_id, conversation).await?;
+            }
+            let labels = DbLabel::fetch_labels(pool).await?;
+            _ = output.send(BackendEvent::GotLabels(labels)).await;
+        }
         // -----------
+        ToBackend::FetchTemplates => {
+            let templates = DbTemplate::fetch(backend.pool()).await?;
+            _ = output.send(BackendEvent::GotTemplates(templates)).await;
+        }
+        ToBackend::AddTemplate(name, content) => {
+            let pool = backend.pool();
+            match DbTemplate::insert(pool, &name, &content).await {
+                Ok(_) => {
+                    let templates = DbTemplate::fetch(pool).await?;
+                    _ = output.send(BackendEvent::GotTemplates(templates)).await;
+                }
+                Err(e) => {
+                    _ = output
+                        .send(BackendEvent::TemplateError(e.to_string()))
+                        .await;
+                }
+            }
+        }
+        ToBackend::UpdateTemplate(template) => {
+            let pool = backend.pool();
+            match DbTemplate::update(pool, &template).await {
+                Ok(_) => {
+                    let templates = DbTemplate::fetch(pool).await?;
+                    _ = output.send(BackendEvent::GotTemplates(templates)).await;
+                }
+                Err(e) => {
+                    _ = output
+                        .send(BackendEvent::TemplateError(e.to_string()))
+                        .await;
+                }
+            }
+        }
+        ToBackend::DeleteTemplate(template) => {
+            let pool = backend.pool();
+            DbTemplate::delete(pool, &template).await?;
+            let templates = DbTemplate::fetch(pool).await?;
+            _ = output.send(BackendEvent::GotTemplates(templates)).await;
+        }
+        ToBackend::GetTranslationConfig => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::GotTranslationConfig(config.translation))
+                .await;
+        }
+        ToBackend::SetTranslationConfig(translation) => {
+            Config::set_translation(translation.clone()).await?;
+            _ = output
+                .send(BackendEvent::GotTranslationConfig(translation))
+                .await;
+        }
+        ToBackend::GetStorageConfig => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::GotStorageConfig(config.storage))
+                .await;
+        }
+        ToBackend::SetStorageConfig(storage) => {
+            Config::set_storage(storage.clone()).await?;
+            _ = output.send(BackendEvent::GotStorageConfig(storage)).await;
+        }
+        ToBackend::FetchRelayTuning => {
+            send_relay_tuning(output, keys, backend).await?;
+        }
+        ToBackend::SetRelayTuningConfig(relay_tuning) => {
+            backend
+                .request_budget
+                .set_per_minute(relay_tuning.requests_per_minute);
+            Config::set_relay_tuning(relay_tuning).await?;
+            send_relay_tuning(output, keys, backend).await?;
+        }
+        ToBackend::ApplyRelaySuggestions(suggestions) => {
+            for suggestion in &suggestions {
+                if let Some(db_relay) = apply_relay_suggestion(backend.pool(), suggestion).await? {
+                    match suggestion.change {
+                        TuningChange::DisableRead => {
+                            backend.nostr.toggle_read_for(&db_relay.url, false)?
+                        }
+                        TuningChange::DisableWrite => {
+                            backend.nostr.toggle_write_for(&db_relay.url, false)?
+                        }
+                    }
+                    _ = output.send(BackendEvent::RelayUpdated(db_relay)).await;
+                }
+            }
+            send_relay_tuning(output, keys, backend).await?;
+        }
+        ToBackend::FetchRelayViolations => {
+            let violations = DbRelayViolation::fetch(backend.pool()).await?;
+            _ = output
+                .send(BackendEvent::GotRelayViolations(violations))
+                .await;
+        }
+        ToBackend::ClearRelayViolations(url) => {
+            DbRelayViolation::delete_relay(backend.pool(), &url).await?;
+            let violations = DbRelayViolation::fetch(backend.pool()).await?;
+            _ = output
+                .send(BackendEvent::GotRelayViolations(violations))
+                .await;
+        }
+        ToBackend::FetchRelayDocumentChanges => {
+            let changes = DbRelayDocumentChange::fetch(backend.pool()).await?;
+            _ = output
+                .send(BackendEvent::GotRelayDocumentChanges(changes))
+                .await;
+        }
+        ToBackend::DismissRelayDocumentChanges(url) => {
+            DbRelayDocumentChange::delete_relay(backend.pool(), &url).await?;
+            let changes = DbRelayDocumentChange::fetch(backend.pool()).await?;
+            _ = output
+                .send(BackendEvent::GotRelayDocumentChanges(changes))
+                .await;
+        }
+        ToBackend::FetchStorageUsage => {
+            let usage = fetch_usage(backend.pool(), backend.cache_pool()).await?;
+            _ = output.send(BackendEvent::GotStorageUsage(usage)).await;
+        }
+        ToBackend::PruneStorage => {
+            let storage = Config::load_file_async().await?.storage;
+            let report = prune(backend.pool(), &storage).await?;
+            _ = output.send(BackendEvent::StoragePruned(report)).await;
+            let usage = fetch_usage(backend.pool(), backend.cache_pool()).await?;
+            _ = output.send(BackendEvent::GotStorageUsage(usage)).await;
+        }
+        ToBackend::FetchLocalEvent(event_hash) => {
+            let pool = backend.pool();
+            let event = DbEvent::fetch_hash(pool, &event_hash).await?;
+            let reactions = count_reactions(pool, &event_hash).await?;
+            _ = output
+                .send(BackendEvent::GotLocalEvent {
+                    event_hash,
+                    event,
+                    reactions,
+                })
+                .await;
+        }
+        ToBackend::DeleteLocalEvent(event_hash) => {
+            let removed = delete_event(backend.pool(), &event_hash).await?;
+            _ = output
+                .send(BackendEvent::LocalEventDeleted(event_hash, removed))
+                .await;
+            let usage = fetch_usage(backend.pool(), backend.cache_pool()).await?;
+            _ = output.send(BackendEvent::GotStorageUsage(usage)).await;
+        }
+        ToBackend::ComputeStats => {
+            let task_tx_1 = task_tx.clone();
+            let pool = backend.pool().clone();
+            tokio::spawn(async move {
+                let result = compute_stats(&pool)
+                    .await
+                    .map(TaskOutput::Stats)
+                    .map_err(Error::from);
+                if let Err(e) = task_tx_1.send(result).await {
+                    tracing::error!("Error sending account stats to backend: {}", e);
+                }
+            });
+        }
+        ToBackend::FetchAutoTranslate(db_contact) => {
+            let enabled = AutoTranslate::is_enabled(backend.pool(), db_contact.pubkey()).await?;
+            _ = output
+                .send(BackendEvent::GotAutoTranslate(
+                    *db_contact.pubkey(),
+                    enabled,
+                ))
+                .await;
+        }
+        ToBackend::SetAutoTranslate(db_contact, enabled) => {
+            AutoTranslate::set(backend.pool(), db_contact.pubkey(), enabled).await?;
+            _ = output
+                .send(BackendEvent::GotAutoTranslate(
+                    *db_contact.pubkey(),
+                    enabled,
+                ))
+                .await;
+        }
+        ToBackend::FetchAutoSaveAttachments(db_contact) => {
+            let enabled =
+                AutoSaveAttachments::is_enabled(backend.pool(), db_contact.pubkey()).await?;
+            _ = output
+                .send(BackendEvent::GotAutoSaveAttachments(
+                    *db_contact.pubkey(),
+                    enabled,
+                ))
+                .await;
+        }
+        ToBackend::SetAutoSaveAttachments(db_contact, enabled) => {
+            AutoSaveAttachments::set(backend.pool(), db_contact.pubkey(), enabled).await?;
+            _ = output
+                .send(BackendEvent::GotAutoSaveAttachments(
+                    *db_contact.pubkey(),
+                    enabled,
+                ))
+                .await;
+        }
+        ToBackend::ShowInFolder(chat_message, db_contact) => {
+            let Some(event_id) = chat_message.event_id() else {
+                return Ok(());
+            };
+            let saved = AttachmentDownload::fetch(backend.pool(), event_id)
+                .await?
+                .into_iter()
+                .find(|download| download.path.exists());
+            match saved {
+                Some(download) => show_in_folder(&download.path)?,
+                None => {
+                    let urls = attachment_urls(chat_message.content());
+                    spawn_attachment_save(backend, task_tx, &db_contact, event_id, urls, true)?;
+                }
+            }
+        }
+        ToBackend::GetStageContactChanges => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::StageContactChanges(
+                    config.stage_contact_changes,
+                ))
+                .await;
+        }
+        ToBackend::SetStageContactChanges(stage) => {
+            Config::set_stage_contact_changes(stage).await?;
+            // leaving staging mode publishes what was being held
+            if !stage && !backend.contact_list_diff(keys).await?.is_empty() {
+                backend.new_contact_list_event(keys).await?;
+                _ = output.send(BackendEvent::ContactListPublished).await;
+            }
+            _ = output.send(BackendEvent::StageContactChanges(stage)).await;
+        }
+        ToBackend::TakeIntegrityReport => {
+            if let Some(report) = backend.integrity_report.take() {
+                _ = output.send(BackendEvent::GotIntegrityReport(report)).await;
+            }
+        }
+        ToBackend::GetDoNotTrack => {
+            _ = output
+                .send(BackendEvent::DoNotTrack(backend.do_not_track))
+                .await;
+        }
+        ToBackend::SetDoNotTrack(do_not_track) => {
+            Config::set_do_not_track(do_not_track).await?;
+            backend.do_not_track = do_not_track;
+            _ = output.send(BackendEvent::DoNotTrack(do_not_track)).await;
+        }
+        ToBackend::GetDeveloperConsole => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::DeveloperConsole(config.developer_console))
+                .await;
+        }
+        ToBackend::SetDeveloperConsole(enabled) => {
+            Config::set_developer_console(enabled).await?;
+            _ = output.send(BackendEvent::DeveloperConsole(enabled)).await;
+        }
+        ToBackend::GetMediaProxy => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::GotMediaProxy(config.media_proxy))
+                .await;
+        }
+        ToBackend::SetMediaProxy(media_proxy) => {
+            Config::set_media_proxy(media_proxy.clone()).await?;
+            backend.media_client = MediaClient::new(&media_proxy);
+            _ = output.send(BackendEvent::GotMediaProxy(media_proxy)).await;
+        }
+        ToBackend::GetSendKey => {
+            let config = Config::load_file_async().await?;
+            _ = output.send(BackendEvent::GotSendKey(config.send_key)).await;
+        }
+        ToBackend::SetSendKey(send_key) => {
+            Config::set_send_key(send_key).await?;
+            _ = output.send(BackendEvent::GotSendKey(send_key)).await;
+        }
+        ToBackend::GetDimImages => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::DimImagesChanged(config.dim_images))
+                .await;
+        }
+        ToBackend::SetDimImages(dim_images) => {
+            Config::set_dim_images(dim_images).await?;
+            _ = output
+                .send(BackendEvent::DimImagesChanged(dim_images))
+                .await;
+        }
+        ToBackend::GetAutoLock => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::AutoLockChanged(config.auto_lock))
+                .await;
+        }
+        ToBackend::SetAutoLock(auto_lock) => {
+            Config::set_auto_lock(auto_lock).await?;
+            _ = output.send(BackendEvent::AutoLockChanged(auto_lock)).await;
+        }
+        ToBackend::GetTimeFormat => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::TimeFormatChanged(config.time_format))
+                .await;
+        }
+        ToBackend::SetTimeFormat(time_format) => {
+            Config::set_time_format(time_format).await?;
+            _ = output
+                .send(BackendEvent::TimeFormatChanged(time_format))
+                .await;
+        }
+        ToBackend::GetTextDirection => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::TextDirectionChanged(config.text_direction))
+                .await;
+        }
+        ToBackend::SetTextDirection(text_direction) => {
+            Config::set_text_direction(text_direction).await?;
+            _ = output
+                .send(BackendEvent::TextDirectionChanged(text_direction))
+                .await;
+        }
+        ToBackend::GetStartupView => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::StartupViewChanged(config.startup_view))
+                .await;
+        }
+        ToBackend::SetStartupView(startup_view) => {
+            Config::set_startup_view(startup_view).await?;
+            _ = output
+                .send(BackendEvent::StartupViewChanged(startup_view))
+                .await;
+        }
+        ToBackend::GetPresence => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::PresenceChanged(config.presence))
+                .await;
+        }
+        ToBackend::SetPresence(presence) => {
+            Config::set_presence(presence).await?;
+            _ = output.send(BackendEvent::PresenceChanged(presence)).await;
+        }
+        ToBackend::PublishPresence(presence) => {
+            backend.new_status_event(keys, presence).await?;
+        }
+        ToBackend::GetDownloads => {
+            let config = Config::load_file_async().await?;
+            _ = output
+                .send(BackendEvent::DownloadsChanged(config.downloads))
+                .await;
+        }
+        ToBackend::SetDownloads(downloads) => {
+            Config::set_downloads(downloads.clone()).await?;
+            backend.downloads = downloads.clone();
+            _ = output.send(BackendEvent::DownloadsChanged(downloads)).await;
+        }
+        ToBackend::ChooseDownloadDirectory => match AsyncFileDialog::new().pick_folder().await {
+            Some(handle) => {
+                let downloads = DownloadConfig {
+                    directory: Some(handle.path().to_owned()),
+                };
+                Config::set_downloads(downloads.clone()).await?;
+                backend.downloads = downloads.clone();
+                _ = output.send(BackendEvent::DownloadsChanged(downloads)).await;
+            }
+            None => {
+                _ = output.send(BackendEvent::RFDCancelPick).await;
+            }
+        },
+        ToBackend::Unlock(pubkey) => {
+            let event = if pubkey == keys.public_key() {
+                BackendEvent::Unlocked
+            } else {
+                BackendEvent::UnlockFailed
+            };
+            _ = output.send(event).await;
+        }
+        ToBackend::RunConsoleReq(filter_json, relays) => match parse_filters(&filter_json) {
+            Ok(filters) => {
+                let subscription = Subscription::new(filters)
+                    .with_id(SubName::Console.to_string())
+                    .eose(Some(Duration::from_secs(CONSOLE_EOSE_SECS)));
+                backend.flood_guard.restart(&SubName::Console);
+                backend.console_started = Some(Instant::now());
+                for url in &relays {
+                    backend.nostr.relay_subscribe(url, &subscription)?;
+                }
+            }
+            Err(e) => {
+                _ = output.send(BackendEvent::ConsoleError(e)).await;
+            }
+        },
+        ToBackend::GetSimulation => {
+            let settings = backend.simulation.settings().clone();
+            _ = output.send(BackendEvent::GotSimulation(settings)).await;
+        }
+        ToBackend::SetSimulation(settings) => {
+            if !dev_mode() {
+                tracing::warn!("Simulation is only available in dev mode");
+                return Ok(());
+            }
+            for ns_event in backend.simulation.set(settings) {
+                if !backend.simulation.dry_run(&ns_event) {
+                    backend.nostr.send_event(ns_event)?;
+                }
+            }
+            let settings = backend.simulation.settings().clone();
+            _ = output.send(BackendEvent::GotSimulation(settings)).await;
+        }
+        ToBackend::FetchDryRuns => {
+            let write_relays: Vec<Url> = DbRelay::fetch(backend.pool())
+                .await?
+                .into_iter()
+                .filter(|db_relay| db_relay.write)
+                .map(|db_relay| db_relay.url)
+                .collect();
+            let events: Vec<_> = backend.simulation.dry_runs().cloned().collect();
+            let mut dry_runs = vec![];
+            for ns_event in events {
+                // the relays it was signed for, journaled and republished events
+                // go to the current write relays
+                let relays = SigningLogEntry::fetch_event(backend.pool(), &ns_event.id)
+                    .await?
+                    .map(|entry| entry.relays)
+                    .filter(|relays| !relays.is_empty())
+                    .unwrap_or_else(|| write_relays.clone());
+                dry_runs.push(DryRun { ns_event, relays });
+            }
+            _ = output.send(BackendEvent::GotDryRuns(dry_runs)).await;
+        }
+        ToBackend::SimulateDisconnect => {
+            if !dev_mode() {
+                tracing::warn!("Simulation is only available in dev mode");
+                return Ok(());
+            }
+            for db_relay in DbRelay::fetch(backend.pool()).await? {
+                backend.nostr.reconnect_relay(&db_relay.url)?;
+            }
+        }
+        ToBackend::FetchContactListDiff => {
+            let diff = backend.contact_list_diff(keys).await?;
+            _ = output.send(BackendEvent::GotContactListDiff(diff)).await;
+        }
+        ToBackend::PublishContactList => {
+            backend.new_contact_list_event(keys).await?;
+            _ = output.send(BackendEvent::ContactListPublished).await;
+        }
         ToBackend::GetTheme => {
             let config = Config::load_file_async().await?;
             _ = output.send(BackendEvent::GotTheme(config.theme)).await;
@@ -962,15 +3569,32 @@ pub async fn process_message(
                 let _ = backend.nostr.relay_info(&r.url);
             }
             _ = output.send(BackendEvent::GotRelays(relays)).await;
+            refresh_relay_documents(backend, task_tx, &[]).await?;
+        }
+        ToBackend::MeasureRelayLatency(urls) => {
+            latency::spawn_latency_checks(urls, task_tx.clone());
         }
         ToBackend::AddRelay(url) => {
+            let url = canonical_relay_url(&url);
+            if DbRelay::fetch_by_url(backend.pool(), &url).await?.is_some() {
+                tracing::info!("Relay already added: {}", url);
+                return Ok(());
+            }
             backend.nostr.add_relay(url.as_str())?;
+            // kept when a link lookup connected it first
+            backend.resolve_relays.remove(&url);
             let db_relay = DbRelay::insert(backend.pool(), &url).await?;
+            let new_relays = if db_relay.write { vec![url] } else { vec![] };
             _ = output.send(BackendEvent::RelayCreated(db_relay)).await;
+            republish_to_new_relays(output, keys, backend, new_relays).await?;
         }
         ToBackend::DeleteRelay(url) => {
+            let url = canonical_relay_url(&url);
             backend.nostr.remove_relay(url.as_str())?;
+            backend.flood_guard.forget_relay(&url);
+            backend.request_budget.forget_relay(&url);
             DbRelay::delete(backend.pool(), &url).await?;
+            DbRelayViolation::delete_relay(backend.pool(), &url).await?;
             _ = output.send(BackendEvent::RelayDeleted(url)).await;
         }
         ToBackend::ToggleRelayRead(mut db_relay) => {
@@ -989,6 +3613,31 @@ pub async fn process_message(
             DbRelay::update(backend.pool(), &db_relay).await?;
             _ = output.send(BackendEvent::RelayUpdated(db_relay)).await;
         }
+        ToBackend::FetchRelayProfiles => send_relay_profiles(output, backend.pool()).await?,
+        ToBackend::SaveRelayProfile(name) => {
+            let pool = backend.pool();
+            let relays = DbRelay::fetch(pool).await?;
+            let profile = DbRelayProfile::save(pool, &name, &relays).await?;
+            // the relays are the profile's right now
+            UserConfig::set_active_relay_profile(pool, Some(&profile.name)).await?;
+            send_relay_profiles(output, pool).await?;
+        }
+        ToBackend::DeleteRelayProfile(name) => {
+            let pool = backend.pool();
+            DbRelayProfile::delete(pool, &name).await?;
+            if UserConfig::get_active_relay_profile(pool).await?.as_deref() == Some(name.as_str()) {
+                UserConfig::set_active_relay_profile(pool, None).await?;
+            }
+            send_relay_profiles(output, pool).await?;
+        }
+        ToBackend::SwitchRelayProfile(name) => {
+            switch_relay_profile(output, keys, backend, &name).await?;
+        }
+        ToBackend::ToggleRelayAnonymousAuth(mut db_relay) => {
+            db_relay.anonymous_auth = !db_relay.anonymous_auth;
+            DbRelay::update(backend.pool(), &db_relay).await?;
+            _ = output.send(BackendEvent::RelayUpdated(db_relay)).await;
+        }
         ToBackend::FetchRelayResponsesUserProfile => {
             let pool = backend.pool();
             if let Some(profile_event) =
@@ -1035,10 +3684,58 @@ pub async fn process_message(
                     .await;
             }
         }
+        ToBackend::RequestRelayDeletion(chat_message) => {
+            let pool = backend.pool();
+            let Some(event_id) = chat_message.event_id() else {
+                return Ok(());
+            };
+            let Some(db_event) = DbEvent::fetch_id(pool, event_id).await? else {
+                return Ok(());
+            };
+            if db_event.pubkey != keys.public_key() {
+                tracing::warn!("Only the user's events can be deleted: {}", event_id);
+                return Ok(());
+            }
+            let relays: Vec<_> = DbRelay::fetch(pool)
+                .await?
+                .into_iter()
+                .filter(|relay| relay.write)
+                .map(|relay| relay.url)
+                .collect();
+            let task_tx_1 = task_tx.clone();
+            let req_client_1 = backend.req_client.clone();
+            tokio::spawn(async move {
+                let checks = relays.into_iter().map(|url| {
+                    let req_client = req_client_1.clone();
+                    async move {
+                        let supported = match fetch_supported_nips(req_client, &url).await {
+                            Ok(nips) => nips.contains(&DELETION_NIP),
+                            Err(e) => {
+                                tracing::info!("Relay document of {}: {}", url, e);
+                                false
+                            }
+                        };
+                        (url, supported)
+                    }
+                });
+                let support = futures::future::join_all(checks).await;
+                if let Err(e) = task_tx_1
+                    .send(Ok(TaskOutput::DeletionSupport(
+                        event_id,
+                        db_event.event_hash,
+                        support,
+                    )))
+                    .await
+                {
+                    tracing::error!("Error sending deletion support to backend: {}", e);
+                }
+            });
+        }
         ToBackend::SubscribeToChannel(channel_id) => {
             let pool = backend.pool();
 
             ChannelSubscription::insert(pool, &channel_id).await?;
+            ChannelArchive::delete(pool, &channel_id).await?;
 
             update_channels_subscription(backend).await?;
 
@@ -1050,6 +3747,7 @@ pub async fn process_message(
             let pool = backend.pool();
 
             ChannelSubscription::delete(pool, &channel_id).await?;
+            ChannelArchive::delete(pool, &channel_id).await?;
 
             update_channels_subscription(backend).await?;
 
@@ -1057,6 +3755,41 @@ pub async fn process_message(
                 .send(BackendEvent::ChannelUnsubscribed(channel_id))
                 .await;
         }
+        ToBackend::ArchiveChannel(channel_id) => {
+            let pool = backend.pool();
+
+            ChannelSubscription::delete(pool, &channel_id).await?;
+            ChannelArchive::insert(pool, &channel_id).await?;
+
+            update_channels_subscription(backend).await?;
+
+            _ = output.send(BackendEvent::ChannelArchived(channel_id)).await;
+        }
+        ToBackend::SetChannelMuted(channel_id, muted) => {
+            let pool = backend.pool();
+            if ChannelSubscription::set_muted(pool, &channel_id, muted).await? {
+                let muted = ChannelSubscription::fetch_muted(pool).await?;
+                _ = output.send(BackendEvent::GotMutedChannels(muted)).await;
+                send_unread_counts(output, pool).await?;
+            }
+        }
+        ToBackend::FetchChannelDetails(channel_id) => {
+            let pool = backend.pool();
+            let origin = DbEvent::fetch_hash(pool, &channel_id)
+                .await?
+                .map(|db_event| db_event.relay_url);
+            let relays: Vec<_> = origin.iter().cloned().collect();
+            let invite_link = nevent_link(&channel_id, &relays)?;
+            _ = output
+                .send(BackendEvent::GotChannelDetails {
+                    channel_id,
+                    origin,
+                    invite_link,
+                })
+                .await;
+            let muted = ChannelSubscription::fetch_muted(pool).await?;
+            _ = output.send(BackendEvent::GotMutedChannels(muted)).await;
+        }
 
         ToBackend::FetchChannelCache(channel_id) => {
             if let Some(cache) =
@@ -1066,37 +3799,140 @@ pub async fn process_message(
             }
         }
         ToBackend::FetchSubscribedChannels => {
-            let pool = backend.pool();
-            let cache_pool = backend.cache_pool();
-
-            let channels = ChannelSubscription::fetch(pool).await?;
-            let mut caches = vec![];
-            for ch in channels {
-                if let Ok(Some(cache)) =
-                    ChannelCache::fetch_by_channel_id(cache_pool, &ch.channel_id).await
-                {
-                    caches.push(cache);
-                }
-            }
+            let caches = ChannelCache::fetch_subscribed(backend.pool()).await?;
             _ = output
                 .send(BackendEvent::GotSubscribedChannels(caches))
                 .await;
+            send_unread_counts(output, backend.pool()).await?;
+            let muted = ChannelSubscription::fetch_muted(backend.pool()).await?;
+            _ = output.send(BackendEvent::GotMutedChannels(muted)).await;
         }
-        ToBackend::FetchMembersInfo(members) => {
-            let cache_pool = backend.cache_pool();
+        ToBackend::FetchArchivedChannels => {
+            let caches = ChannelCache::fetch_archived(backend.pool()).await?;
+            _ = output.send(BackendEvent::GotArchivedChannels(caches)).await;
+        }
+        ToBackend::FetchSwitcherItems => {
+            let pool = backend.pool();
+            let scores = ConversationVisit::fetch_scores(pool).await?;
+            let score = |conversation: &str| scores.get(conversation).copied().unwrap_or(0.0);
+            let drafts: HashMap<_, _> = DbDraft::fetch(pool)
+                .await?
+                .into_iter()
+                .map(|draft| (draft.target.conversation(), draft.content))
+                .collect();
 
-            for pubkey in &members {
-                if let Some(profile) = ProfileCache::fetch_by_public_key(cache_pool, pubkey).await?
-                {
-                    _ = output
-                        .send(BackendEvent::GotProfileCache(pubkey.to_owned(), profile))
-                        .await;
+            let mut items: Vec<_> = DbContact::fetch(pool)
+                .await?
+                .into_iter()
+                .map(|contact| {
+                    let conversation = contact.pubkey().to_string();
+                    SwitcherItem {
+                        name: contact.select_name(),
+                        score: score(&conversation),
+                        draft: drafts.get(&conversation).cloned(),
+                        target: SwitcherTarget::Contact(contact),
+                    }
+                })
+                .collect();
+            let channel_ids: Vec<_> = ChannelSubscription::fetch(pool)
+                .await?
+                .into_iter()
+                .map(|ch| ch.channel_id)
+                .collect();
+            let caches = ChannelCache::fetch_by_channel_ids(pool, &channel_ids).await?;
+            for channel_id in channel_ids {
+                let conversation = channel_id.to_string();
+                items.push(SwitcherItem {
+                    name: channel_name(&caches, &channel_id),
+                    score: score(&conversation),
+                    draft: drafts.get(&conversation).cloned(),
+                    target: SwitcherTarget::Channel(channel_id),
+                });
+            }
+            _ = output.send(BackendEvent::GotSwitcherItems(items)).await;
+        }
+        ToBackend::FetchLastConversation => {
+            let pool = backend.pool();
+            let subscribed: HashSet<_> = ChannelSubscription::fetch(pool)
+                .await?
+                .into_iter()
+                .map(|ch| ch.channel_id)
+                .collect();
+            for conversation in ConversationVisit::fetch_recent(pool).await? {
+                // channel ids can also parse as public keys
+                if let Ok(pubkey) = conversation.parse::<XOnlyPublicKey>() {
+                    if let Some(contact) = DbContact::fetch_one(pool, &pubkey).await? {
+                        let target = SwitcherTarget::Contact(contact);
+                        _ = output.send(BackendEvent::GotLastConversation(target)).await;
+                        break;
+                    }
+                }
+                if let Ok(channel_id) = EventId::from_hex(&conversation) {
+                    if subscribed.contains(&channel_id) {
+                        let target = SwitcherTarget::Channel(channel_id);
+                        _ = output.send(BackendEvent::GotLastConversation(target)).await;
+                        break;
+                    }
                 }
             }
+        }
+        ToBackend::FetchDrafts => {
+            let pool = backend.pool();
+            let drafts = DbDraft::fetch(pool).await?;
+            let mut contacts: HashMap<_, _> = DbContact::fetch(pool)
+                .await?
+                .into_iter()
+                .map(|contact| (contact.pubkey().to_owned(), contact))
+                .collect();
+            let channel_ids: Vec<_> = drafts
+                .iter()
+                .filter_map(|draft| match &draft.target {
+                    DraftTarget::Channel(channel_id) => Some(channel_id.to_owned()),
+                    DraftTarget::Contact(_) => None,
+                })
+                .collect();
+            let caches = ChannelCache::fetch_by_channel_ids(pool, &channel_ids).await?;
+
+            let mut items = vec![];
+            for draft in drafts {
+                let (target, name) = match draft.target {
+                    DraftTarget::Contact(pubkey) => {
+                        // the contact may have been deleted since
+                        let Some(contact) = contacts.remove(&pubkey) else {
+                            continue;
+                        };
+                        let name = contact.select_name();
+                        (SwitcherTarget::Contact(contact), name)
+                    }
+                    DraftTarget::Channel(channel_id) => (
+                        SwitcherTarget::Channel(channel_id),
+                        channel_name(&caches, &channel_id),
+                    ),
+                };
+                items.push(SwitcherItem {
+                    target,
+                    name,
+                    score: 0.0,
+                    draft: Some(draft.content),
+                });
+            }
+            _ = output.send(BackendEvent::GotDrafts(items)).await;
+        }
+        ToBackend::SaveDraft(target, content) => {
+            DbDraft::save(backend.pool(), &target, &content).await?;
+        }
+        ToBackend::FetchMembersInfo(members) => {
+            let pubkeys: Vec<_> = members.iter().copied().collect();
+            let profiles = ProfileCache::fetch_by_public_keys(backend.pool(), &pubkeys).await?;
+            for (pubkey, profile) in profiles {
+                _ = output
+                    .send(BackendEvent::GotProfileCache(pubkey, profile))
+                    .await;
+            }
 
             let subscription =
                 Subscription::new(vec![members_metadata_filter(members.iter())]).eose(None);
-            backend.nostr.subscribe(&subscription)?;
+            subscribe_metadata(output, backend, subscription).await?;
         }
         ToBackend::FetchProfileCache(pubkey) => {
             let cache_pool = backend.cache_pool();
@@ -1106,20 +3942,72 @@ pub async fn process_message(
                     .await;
             }
         }
+        ToBackend::RefreshMetadata(pubkeys) => {
+            if !pubkeys.is_empty() {
+                let subscription =
+                    Subscription::new(vec![channel_members_metadata_filter(pubkeys.iter())])
+                        .with_id(SubscriptionId::generate().to_string())
+                        .eose(Some(Duration::from_secs(10)));
+                subscribe_metadata(output, backend, subscription).await?;
+            }
+        }
         ToBackend::FetchKeys => {
             _ = output.send(BackendEvent::GotKeys(keys.to_owned())).await;
         }
+        ToBackend::FetchSigningLog => {
+            let entries = SigningLogEntry::fetch(backend.pool(), SIGNING_LOG_LIMIT).await?;
+            let total = SigningLogEntry::count(backend.pool()).await?;
+            _ = output
+                .send(BackendEvent::GotSigningLog { entries, total })
+                .await;
+        }
+        ToBackend::CreatePairing => {
+            let keys = keys.to_owned();
+            let pairing =
+                tokio::task::spawn_blocking(move || Pairing::new(&keys, Timestamp::now()))
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|pairing| pairing.map_err(|e| e.to_string()));
+            _ = output.send(BackendEvent::PairingCreated(pairing)).await;
+        }
         ToBackend::FindChannels(search_term) => {
             let subscription = Subscription::new(vec![channel_search_filter(&search_term)])
                 .with_id(SubName::SearchChannels.to_string())
                 .eose(Some(Duration::from_secs(10)));
+            backend.flood_guard.restart(&SubName::SearchChannels);
             backend.nostr.subscribe(&subscription)?;
         }
+        ToBackend::FetchContactChannels => {
+            let pool = backend.pool();
+            let contact_list = DbContact::fetch_basic(pool).await?;
+            backend.contact_channel_activity = ContactActivity::fetch(pool).await?;
+
+            let channels = rank_contact_channels(backend).await?;
+            _ = output
+                .send(BackendEvent::GotContactChannels {
+                    channels,
+                    searching: !contact_list.is_empty(),
+                })
+                .await;
+
+            if !contact_list.is_empty() {
+                let subscription = Subscription::new(vec![contact_channels_filter(&contact_list)])
+                    .with_id(SubName::ContactChannels.to_string())
+                    .eose(Some(Duration::from_secs(10)));
+                backend.flood_guard.restart(&SubName::ContactChannels);
+                backend.nostr.subscribe(&subscription)?;
+            }
+        }
         ToBackend::UpdateUserProfileMeta(profile_meta) => {
             backend.new_profile_event(keys, &profile_meta).await?;
         }
         ToBackend::SubscribeToChannelDetails(url, channel_ids) => {
             let actions_id = SubscriptionId::generate().to_string();
+            for channel_id in &channel_ids {
+                backend
+                    .flood_guard
+                    .restart(&SubName::src_channel_details(channel_id));
+            }
             let actions: Vec<_> = channel_ids
                 .iter()
                 .map(|channel_id| {
@@ -1142,6 +4030,10 @@ pub async fn process_message(
                 _ = output.send(BackendEvent::FirstLoginSuccess).await;
             }
         }
+        ToBackend::SetDmSyncDepth(depth) => {
+            let since = depth.since(chrono::Utc::now().naive_utc());
+            UserConfig::set_dm_sync_since(backend.pool(), since).await?;
+        }
         ToBackend::PrepareClient => {
             prepare_client(keys, backend).await?;
             _ = output.send(BackendEvent::FinishedPreparing).await;
@@ -1149,6 +4041,28 @@ pub async fn process_message(
         ToBackend::MessageSeen(msg_id) => {
             DbMessage::mark_seen(backend.pool(), msg_id).await?;
         }
+        ToBackend::MarkChannelRead(channel_id, event_id) => {
+            mark_channel_read(output, keys, backend, &channel_id, event_id).await?;
+        }
+        ToBackend::MarkChannelAllRead(channel_id) => {
+            mark_channel_all_read(output, keys, backend, &channel_id).await?;
+        }
+        ToBackend::PublishChannelReadMarkers => {
+            publish_channel_read_markers(keys, backend).await?;
+        }
+        ToBackend::MarkAllChatsRead => {
+            let pool = backend.pool();
+            let seen = DbMessage::reset_unseen_all(pool).await?;
+            tracing::debug!("Marked {} messages as seen", seen);
+            _ = output.send(BackendEvent::AllChatsRead).await;
+            send_unread_counts(output, pool).await?;
+        }
+        ToBackend::FetchUnreadTotal => {
+            let pool = backend.pool();
+            let counts = ChannelSubscription::unread_counts(pool).await?;
+            let total = unread_total(pool, &counts).await?;
+            _ = output.send(BackendEvent::UnreadTotal(total)).await;
+        }
         ToBackend::SubscribeChannelMembersMeta(channel_id) => {
             if let Some(cache) =
                 ChannelCache::fetch_by_channel_id(backend.cache_pool(), &channel_id).await?
@@ -1157,17 +4071,25 @@ pub async fn process_message(
                     Subscription::new(vec![channel_members_metadata_filter(cache.members.iter())])
                         .with_id(SubName::channel_members_meta(&channel_id).to_string())
                         .eose(None);
-                backend.nostr.subscribe(&subscription)?;
+                backend
+                    .flood_guard
+                    .restart(&SubName::channel_members_meta(&channel_id));
+                subscribe_metadata(output, backend, subscription).await?;
             }
         }
         ToBackend::FetchChannelMessages(channel_id) => {
             let pool = backend.pool();
+            ChannelMention::mark_channel_seen(pool, &channel_id).await?;
+            ConversationVisit::record(pool, &channel_id.to_string()).await?;
 
-            let messages: Vec<_> = DbChannelMessage::fetch(pool, &channel_id)
+            let moderation = channel_moderation(pool, backend.cache_pool(), &channel_id).await?;
+            let mut messages: Vec<_> = DbChannelMessage::fetch(pool, &channel_id)
                 .await?
                 .into_iter()
+                .filter(|ch_msg| !moderation.as_ref().map_or(false, |m| m.hides(ch_msg)))
                 .map(Into::into)
                 .collect();
+            add_reactions(pool, keys, &mut messages).await?;
 
             _ = output
                 .send(BackendEvent::GotChannelMessages(channel_id, messages))
@@ -1175,6 +4097,7 @@ pub async fn process_message(
         }
         ToBackend::FetchMessages(db_contact) => {
             let pool = backend.pool();
+            ConversationVisit::record(pool, &db_contact.pubkey().to_string()).await?;
             let db_messages = DbMessage::fetch_chat(pool, db_contact.pubkey()).await?;
 
             // Maybe the message is only seen when scrolling?
@@ -1203,39 +4126,156 @@ pub async fn process_message(
                 }
             }
         }
+        ToBackend::FetchConversationRelays(db_contact) => {
+            let relays = DbRelayResponse::fetch_conversation_relays(
+                backend.pool(),
+                &db_contact,
+                CONVERSATION_RELAYS_LIMIT,
+            )
+            .await?;
+            _ = output
+                .send(BackendEvent::GotConversationRelays(
+                    db_contact.pubkey().to_owned(),
+                    relays,
+                ))
+                .await;
+        }
+        ToBackend::FetchSharedChannels(pubkey) => {
+            let channels =
+                ChannelCache::fetch_shared(backend.cache_pool(), &keys.public_key(), &pubkey)
+                    .await?;
+            _ = output
+                .send(BackendEvent::GotSharedChannels(pubkey, channels))
+                .await;
+        }
         ToBackend::FetchContactWithMetadata(pubkey) => {
-            let req = DbContact::fetch_one(backend.pool(), backend.cache_pool(), &pubkey).await?;
+            let req = DbContact::fetch_one(backend.pool(), &pubkey).await?;
             _ = output
                 .send(BackendEvent::GotSingleContact(pubkey, req))
                 .await;
         }
+        ToBackend::FetchOlderDms(db_contact) => {
+            let pool = backend.pool();
+            let chat_pubkey = db_contact.pubkey().to_owned();
+            let fetched_since = DmHistory::fetched_since(pool, &chat_pubkey).await?;
+            let login_since = UserConfig::get_dm_sync_since(pool).await?;
+            if let Some((since, until)) = next_history_window(fetched_since, login_since) {
+                DmHistory::extend(pool, &chat_pubkey, since).await?;
+                let sub_name = SubName::dm_history(&chat_pubkey);
+                let subscription = Subscription::new(dm_history_filter(
+                    keys.public_key(),
+                    chat_pubkey,
+                    since,
+                    until,
+                ))
+                .with_id(sub_name.to_string())
+                .eose(Some(Duration::from_secs(10)));
+                backend.flood_guard.restart(&sub_name);
+                backend
+                    .dm_history_fetches
+                    .insert(sub_name.to_string(), chat_pubkey);
+                backend.nostr.subscribe(&subscription)?;
+                _ = output
+                    .send(BackendEvent::DmHistoryFetching(chat_pubkey, since))
+                    .await;
+            } else {
+                _ = output
+                    .send(BackendEvent::DmHistoryComplete(chat_pubkey))
+                    .await;
+            }
+        }
 
         ToBackend::FetchChatInfo(db_contact) => {
+            if let Some(chat_info) =
+                fetch_chat_info(keys, backend.pool(), db_contact.pubkey()).await?
+            {
+                _ = output
+                    .send(BackendEvent::GotChatInfo(db_contact, chat_info))
+                    .await;
+            }
+        }
+        ToBackend::FetchStarred(db_contact) => {
+            let event_ids = DbMessage::fetch_starred(backend.pool(), db_contact.pubkey()).await?;
+            _ = output
+                .send(BackendEvent::GotStarred(*db_contact.pubkey(), event_ids))
+                .await;
+        }
+        ToBackend::StarMessage(event_id, starred) => {
+            DbMessage::set_starred(backend.pool(), event_id, starred).await?;
+            _ = output
+                .send(BackendEvent::MessageStarred(event_id, starred))
+                .await;
+        }
+        ToBackend::SendReaction(event_id, content) => {
             let pool = backend.pool();
-            if let Some(db_message) = DbMessage::fetch_chat_last(pool, db_contact.pubkey()).await? {
-                if let Some(db_event) = DbEvent::fetch_id(pool, db_message.event_id).await? {
-                    let unseen_messages =
-                        DbMessage::fetch_unseen_chat_count(pool, db_contact.pubkey()).await?;
-                    let tag_info = MessageTagInfo::from_event_tags(
-                        &db_event.event_hash,
-                        &db_event.pubkey,
-                        &db_event.tags,
-                    )?;
-
-                    let decrypted_content = db_message.decrypt_message(keys, &tag_info)?;
-
-                    _ = output
-                        .send(BackendEvent::GotChatInfo(
-                            db_contact,
-                            ChatInfo {
-                                unseen_messages,
-                                last_message: decrypted_content,
-                                last_message_time: Some(db_message.created_at),
-                            },
-                        ))
-                        .await;
+            let Some(db_event) = DbEvent::fetch_id(pool, event_id).await? else {
+                return Ok(());
+            };
+            let own =
+                DbReaction::fetch_by(pool, &db_event.event_hash, &keys.public_key(), &content)
+                    .await?;
+            match own {
+                Some(reaction) => {
+                    backend
+                        .new_deletion_event(keys, &reaction.reaction_hash)
+                        .await?;
+                    // a late confirmation would count it again
+                    if let Some(pending) = backend.pending_events.remove(&reaction.reaction_hash) {
+                        backend.remove_from_journal(&pending).await?;
+                    }
+                    DbReaction::delete(pool, &reaction.reaction_hash).await?;
+                }
+                None => {
+                    let pending_event = backend.new_reaction(keys, &db_event, &content).await?;
+                    // counted right away, the relays confirm it later
+                    let reaction = DbReaction::from_ns_event(pending_event.ns_event())?;
+                    DbReaction::insert(backend.pool(), &reaction).await?;
                 }
             }
+            send_reactions(output, keys, backend.pool(), &db_event.event_hash).await?;
+        }
+        ToBackend::ClearChatHistory {
+            contact,
+            keep_starred,
+        } => {
+            let pool = backend.pool();
+            let now = UserConfig::get_corrected_time(pool)
+                .await
+                .unwrap_or(chrono::Utc::now().naive_utc());
+            let cleared = DbMessage::clear_chat(pool, contact.pubkey(), keep_starred, now).await?;
+            tracing::info!("Moved {} messages to the trash", cleared);
+            let chat_info = fetch_chat_info(keys, pool, contact.pubkey()).await?;
+            _ = output
+                .send(BackendEvent::ChatHistoryCleared(contact, chat_info))
+                .await;
+        }
+        ToBackend::DeleteMessages(contact, event_ids) => {
+            let pool = backend.pool();
+            let now = UserConfig::get_corrected_time(pool)
+                .await
+                .unwrap_or(chrono::Utc::now().naive_utc());
+            let trashed =
+                DbMessage::trash_messages(pool, contact.pubkey(), &event_ids, now).await?;
+            tracing::info!("Moved {} messages to the trash", trashed);
+            let chat_info = fetch_chat_info(keys, pool, contact.pubkey()).await?;
+            _ = output
+                .send(BackendEvent::MessagesDeleted(contact, event_ids, chat_info))
+                .await;
+        }
+        ToBackend::ForwardMessages(db_contact, contents) => {
+            for content in contents {
+                let pending_event = backend.new_dm(keys, &db_contact, &content).await?;
+                let chat_message = ChatMessage::pending(pending_event, &content);
+                _ = output
+                    .send(BackendEvent::PendingDM(db_contact.to_owned(), chat_message))
+                    .await;
+            }
+        }
+        ToBackend::CountFilteredEvents(filter) => {
+            let count = DbEvent::count_filtered(backend.pool(), &filter).await?;
+            _ = output
+                .send(BackendEvent::FilteredEventsCount(filter, count))
+                .await;
         }
         ToBackend::FetchAllMessageEvents => {
             let messages =
@@ -1264,85 +4304,786 @@ pub async fn process_message(
                         continue;
                     }
                 } else {
-                    DbContact::insert(pool, db_contact.pubkey()).await?;
+                    // keeps the imported petname and relay
+                    DbContact::upsert_contact(pool, db_contact).await?;
+                }
+            }
+
+            backend.new_contact_list_event(keys).await?;
+
+            _ = output
+                .send(BackendEvent::FileContactsImported(db_contacts))
+                .await;
+        }
+        ToBackend::AddContact(db_contact) => {
+            handle_contact_op(output, keys, backend, ContactOp::Add(db_contact)).await;
+        }
+        ToBackend::UpdateContact(db_contact) => {
+            handle_contact_op(output, keys, backend, ContactOp::Update(db_contact)).await;
+        }
+        ToBackend::DeleteContact(db_contact) => {
+            handle_contact_op(output, keys, backend, ContactOp::Delete(db_contact)).await;
+        }
+        ToBackend::DeleteContacts(db_contacts) => {
+            let ops = db_contacts.into_iter().map(ContactOp::Delete).collect();
+            handle_contact_ops(output, keys, backend, ops).await;
+        }
+        ToBackend::MergeContacts(keep, duplicate) => {
+            let merge = ContactMerge::apply(backend.pool(), &keep, &duplicate).await?;
+            publish_contact_changes(keys, backend).await?;
+            _ = output.send(BackendEvent::ContactDeleted(duplicate)).await;
+            _ = output.send(BackendEvent::ContactUpdated(merge.kept)).await;
+        }
+        ToBackend::FetchContacts => {
+            let contacts = DbContact::fetch(backend.pool()).await?;
+            _ = output.send(BackendEvent::GotContacts(contacts)).await;
+        }
+        ToBackend::FetchBlocked => {
+            send_blocked(output, backend).await?;
+        }
+        ToBackend::BlockUser(pubkey, on_mute_list) => {
+            DbBlocked::insert(backend.pool(), &pubkey, on_mute_list).await?;
+            backend.set_blocked(DbBlocked::fetch_pubkeys(backend.pool()).await?);
+            if on_mute_list {
+                update_mute_list(keys, backend, &pubkey, true).await?;
+            }
+            send_blocked(output, backend).await?;
+        }
+        ToBackend::UnblockUser(pubkey) => {
+            let removed = DbBlocked::delete(backend.pool(), &pubkey).await?;
+            backend.set_blocked(DbBlocked::fetch_pubkeys(backend.pool()).await?);
+            if removed.map_or(false, |blocked| blocked.on_mute_list) {
+                update_mute_list(keys, backend, &pubkey, false).await?;
+            }
+            send_blocked(output, backend).await?;
+        }
+        ToBackend::FetchMutedUsers => {
+            let muted = muted_users(keys, backend).await?;
+            _ = output
+                .send(BackendEvent::GotMutedUsers(muted.into_iter().collect()))
+                .await;
+        }
+        ToBackend::MuteUser(pubkey, mute) => {
+            let muted = update_mute_list(keys, backend, &pubkey, mute).await?;
+            _ = output
+                .send(BackendEvent::GotMutedUsers(muted.into_iter().collect()))
+                .await;
+        }
+        ToBackend::FetchTrash => {
+            send_trash(output, backend).await?;
+        }
+        ToBackend::RestoreFromTrash(kind, pubkey) => {
+            Trash::restore(backend.pool(), kind, &pubkey).await?;
+            match kind {
+                TrashKind::Contact => {
+                    publish_contact_changes(keys, backend).await?;
+                    if let Some(db_contact) = DbContact::fetch_one(backend.pool(), &pubkey).await? {
+                        _ = output.send(BackendEvent::ContactCreated(db_contact)).await;
+                    }
+                }
+                TrashKind::Conversation => {
+                    if let Some(chat_info) = fetch_chat_info(keys, backend.pool(), &pubkey).await? {
+                        _ = output
+                            .send(BackendEvent::GotChatInfo(
+                                DbContact::new(&pubkey),
+                                chat_info,
+                            ))
+                            .await;
+                    }
+                }
+            }
+            send_trash(output, backend).await?;
+        }
+        ToBackend::PurgeFromTrash(kind, pubkey) => {
+            let now = chrono::Utc::now().naive_utc();
+            Trash::purge(backend.pool(), kind, &pubkey, now).await?;
+            send_trash(output, backend).await?;
+        }
+        ToBackend::EmptyTrash => {
+            let now = chrono::Utc::now().naive_utc();
+            for item in Trash::fetch(backend.pool()).await? {
+                Trash::purge(backend.pool(), item.kind, &item.pubkey, now).await?;
+            }
+            send_trash(output, backend).await?;
+        }
+
+        ToBackend::GetRelayInformation => {
+            backend.nostr.relays_info()?;
+        }
+
+        ToBackend::CreateChannel => {
+            todo!()
+        }
+        ToBackend::UpdateChannel(channel_id, metadata, moderators) => {
+            // relays take anyone's kind 41, clients only the creator's
+            let is_owner = ChannelCache::fetch_by_channel_id(backend.cache_pool(), &channel_id)
+                .await?
+                .map_or(false, |cache| cache.creator_pubkey == keys.public_key());
+            if is_owner {
+                let recommended_relay = UserConfig::get_relay(backend.pool()).await?;
+                backend
+                    .new_channel_metadata(
+                        keys,
+                        &channel_id,
+                        recommended_relay.as_ref(),
+                        &metadata,
+                        &moderators,
+                    )
+                    .await?;
+            }
+        }
+
+        ToBackend::SendChannelMessage(channel_id, raw_content) => {
+            let target = DraftTarget::Channel(channel_id);
+            if backend
+                .send_guard
+                .is_duplicate(&target, &raw_content, Instant::now())
+            {
+                _ = output
+                    .send(BackendEvent::DuplicateChannelMsg(channel_id, raw_content))
+                    .await;
+            } else {
+                send_channel_message(output, keys, backend, channel_id, raw_content).await?;
+            }
+        }
+        ToBackend::ResendChannelMessage(channel_id, raw_content) => {
+            send_channel_message(output, keys, backend, channel_id, raw_content).await?;
+        }
+        ToBackend::CreatePoll(channel_id, question, options) => {
+            let pending_event = backend
+                .new_poll(keys, &channel_id, &question, &options)
+                .await?;
+            // show it right away, the relay echo is ignored
+            handle_poll_event(output, keys, backend.pool(), pending_event.ns_event()).await?;
+        }
+        ToBackend::VotePoll(channel_id, poll_hash, option_id) => {
+            let pending_event = backend
+                .new_poll_vote(keys, &channel_id, &poll_hash, &option_id)
+                .await?;
+            handle_poll_event(output, keys, backend.pool(), pending_event.ns_event()).await?;
+        }
+        ToBackend::FetchChannelPolls(channel_id) => {
+            let pool = backend.pool();
+            let mut polls = vec![];
+            for poll in DbPoll::fetch_channel(pool, &channel_id).await? {
+                let tally = PollTally::fetch(pool, &poll, &keys.public_key()).await?;
+                polls.push((poll, tally));
+            }
+            _ = output
+                .send(BackendEvent::GotChannelPolls(channel_id, polls))
+                .await;
+        }
+        ToBackend::FetchChannelKeywords(channel_id) => {
+            let keywords = ChannelKeywords::fetch(backend.pool(), &channel_id).await?;
+            _ = output
+                .send(BackendEvent::GotChannelKeywords(channel_id, keywords))
+                .await;
+        }
+        ToBackend::SetChannelKeywords(channel_id, keywords) => {
+            let pool = backend.pool();
+            ChannelKeywords::set(pool, &channel_id, &keywords).await?;
+            let keywords = ChannelKeywords::fetch(pool, &channel_id).await?;
+            _ = output
+                .send(BackendEvent::GotChannelKeywords(channel_id, keywords))
+                .await;
+        }
+        ToBackend::FetchHashtagFeed(hashtag) => {
+            let sub_name = SubName::HashtagFeed(hashtag.to_owned());
+            let subscription = Subscription::new(vec![hashtag_feed_filter(&hashtag)])
+                .with_id(sub_name.to_string())
+                .eose(Some(Duration::from_secs(10)));
+            backend.flood_guard.restart(&sub_name);
+            backend.nostr.subscribe(&subscription)?;
+        }
+        ToBackend::PreviewAccount => {
+            backend.account_preview = AccountPreview::default();
+            let sub_name = SubName::AccountPreview;
+            let subscription = Subscription::new(account_preview_filter(keys.public_key()))
+                .with_id(sub_name.to_string())
+                .eose(Some(Duration::from_secs(10)));
+            backend.flood_guard.restart(&sub_name);
+            backend.nostr.subscribe(&subscription)?;
+        }
+        ToBackend::FetchFollowedHashtags => {
+            let hashtags = FollowedHashtags::fetch(backend.pool()).await?;
+            _ = output
+                .send(BackendEvent::GotFollowedHashtags(hashtags))
+                .await;
+        }
+        ToBackend::FollowHashtag(hashtag) => {
+            FollowedHashtags::follow(backend.pool(), &hashtag).await?;
+            let hashtags = FollowedHashtags::fetch(backend.pool()).await?;
+            subscribe_followed_hashtags(backend, &hashtags)?;
+            _ = output
+                .send(BackendEvent::GotFollowedHashtags(hashtags))
+                .await;
+        }
+        ToBackend::UnfollowHashtag(hashtag) => {
+            FollowedHashtags::unfollow(backend.pool(), &hashtag).await?;
+            let hashtags = FollowedHashtags::fetch(backend.pool()).await?;
+            if hashtags.is_empty() {
+                // nothing to unsubscribe with, the subscription is replaced
+                // by one that returns nothing and ends at EOSE
+                let subscription =
+                    Subscription::new(vec![followed_hashtags_filter(&[hashtag]).limit(0)])
+                        .with_id(SubName::FollowedHashtags.to_string())
+                        .eose(Some(Duration::from_secs(1)));
+                backend.nostr.subscribe(&subscription)?;
+            } else {
+                subscribe_followed_hashtags(backend, &hashtags)?;
+            }
+            _ = output
+                .send(BackendEvent::GotFollowedHashtags(hashtags))
+                .await;
+        }
+        ToBackend::FetchMessageLink(chat_message) => {
+            let pool = backend.pool();
+            if let Some(event_id) = chat_message.event_id() {
+                if let Some(db_event) = DbEvent::fetch_id(pool, event_id).await? {
+                    let relays: Vec<_> = DbRelayResponse::fetch_by_event(pool, event_id)
+                        .await?
+                        .into_iter()
+                        .map(|r| r.relay_url)
+                        .take(MESSAGE_LINK_RELAYS_LIMIT)
+                        .collect();
+                    let link = nevent_link(&db_event.event_hash, &relays)?;
+                    _ = output.send(BackendEvent::GotMessageLink(link)).await;
+                }
+            }
+        }
+        ToBackend::ResolveMessageLink(event_hash, relays) => {
+            if !locate_event(output, keys, backend, &event_hash).await? {
+                let sub = Subscription::new(vec![Filter::new().id(event_hash.to_hex())])
+                    .with_id(SubName::ResolveEvent.to_string())
+                    .eose(Some(Duration::from_secs(10)));
+                backend.flood_guard.restart(&SubName::ResolveEvent);
+                backend.nostr.subscribe(&sub)?;
+
+                // hints outside the user's relays are only asked for this event
+                let known: HashSet<Url> = DbRelay::fetch(backend.pool())
+                    .await?
+                    .into_iter()
+                    .map(|r| r.url)
+                    .collect();
+                let hints = relays
+                    .iter()
+                    .filter_map(|relay| Url::parse(relay).ok())
+                    .map(|url| canonical_relay_url(&url))
+                    .filter(|url| !known.contains(url))
+                    .take(MESSAGE_LINK_RELAYS_LIMIT);
+                for url in hints {
+                    backend.nostr.add_relay(url.as_str())?;
+                    backend.nostr.relay_subscribe(&url, &sub)?;
+                    backend.resolve_relays.insert(url);
                 }
+                _ = output.send(BackendEvent::FetchingEvent(event_hash)).await;
+            }
+        }
+        ToBackend::FetchLiveActivities => {
+            let mut activities = vec![];
+            for activity in DbLiveActivity::fetch_live(backend.pool()).await? {
+                let host = DbContact::fetch_one(backend.pool(), &activity.host).await?;
+                activities.push((activity, host));
+            }
+            _ = output
+                .send(BackendEvent::GotLiveActivities(activities))
+                .await;
+        }
+        ToBackend::SendDM(db_contact, raw_content) => {
+            let target = DraftTarget::Contact(db_contact.pubkey().to_owned());
+            if backend
+                .send_guard
+                .is_duplicate(&target, &raw_content, Instant::now())
+            {
+                _ = output
+                    .send(BackendEvent::DuplicateDM(db_contact, raw_content))
+                    .await;
+            } else {
+                send_dm(output, keys, backend, db_contact, raw_content).await?;
+            }
+        }
+        ToBackend::ResendDM(db_contact, raw_content) => {
+            send_dm(output, keys, backend, db_contact, raw_content).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies a contact operation, reporting failures to the frontend
+/// so views can roll back their optimistic changes.
+async fn handle_contact_op(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    op: ContactOp,
+) {
+    handle_contact_ops(output, keys, backend, vec![op]).await
+}
+
+/// Stores every operation and publishes the contact list once
+async fn handle_contact_ops(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    ops: Vec<ContactOp>,
+) {
+    let mut applied = vec![];
+    for op in ops {
+        match store_contact_op(keys, backend, &op).await {
+            Ok(snapshot) => applied.push((op, snapshot)),
+            Err(e) => {
+                tracing::error!("Failed to {} contact: {}", op.label(), e);
+                _ = output
+                    .send(BackendEvent::ContactOpFailed(op, e.to_string()))
+                    .await;
+            }
+        }
+    }
+    if applied.is_empty() {
+        return;
+    }
+
+    if let Err(e) = publish_contact_changes(keys, backend).await {
+        tracing::error!("Failed to publish contact list: {}", e);
+        // the views roll back, so the stored contacts must too
+        for (op, snapshot) in applied.into_iter().rev() {
+            if let Err(e) = DbContact::restore_snapshot(backend.pool(), op.pubkey(), snapshot).await
+            {
+                tracing::error!("Failed to undo {} contact: {}", op.label(), e);
+            }
+            _ = output
+                .send(BackendEvent::ContactOpFailed(op, e.to_string()))
+                .await;
+        }
+        return;
+    }
+
+    for (op, _) in applied {
+        let event = match op {
+            ContactOp::Add(db_contact) => BackendEvent::ContactCreated(db_contact),
+            ContactOp::Update(db_contact) => BackendEvent::ContactUpdated(db_contact),
+            ContactOp::Delete(db_contact) => BackendEvent::ContactDeleted(db_contact),
+        };
+        _ = output.send(event).await;
+    }
+}
+
+/// Returns the contact as it was stored before `op`
+async fn store_contact_op(
+    keys: &Keys,
+    backend: &mut BackendState,
+    op: &ContactOp,
+) -> Result<Option<ContactSnapshot>, Error> {
+    let snapshot = DbContact::snapshot(backend.pool(), op.pubkey()).await?;
+    match op {
+        ContactOp::Add(db_contact) => {
+            // Check if the contact is the same as the user
+            if &keys.public_key() == db_contact.pubkey() {
+                return Err(Error::SameContactInsert);
             }
+            DbContact::insert(backend.pool(), db_contact.pubkey()).await?;
+            DbContact::update(backend.pool(), db_contact).await?;
+        }
+        ContactOp::Update(db_contact) => {
+            if &keys.public_key() == db_contact.pubkey() {
+                return Err(Error::SameContactUpdate);
+            }
+            DbContact::update(backend.pool(), db_contact).await?;
+        }
+        ContactOp::Delete(db_contact) => {
+            DbContact::delete(backend.pool(), db_contact).await?;
+        }
+    }
+    Ok(snapshot)
+}
+
+async fn publish_contact_changes(keys: &Keys, backend: &mut BackendState) -> Result<(), Error> {
+    // staged changes are published later, after the user reviews them
+    if !Config::load_file_async().await?.stage_contact_changes {
+        backend.new_contact_list_event(keys).await?;
+    }
+    Ok(())
+}
+
+/// Display name or name of the profile, `None` when both are blank
+fn profile_name(profile: Option<ProfileCache>) -> Option<String> {
+    profile
+        .and_then(|cache| cache.metadata.display_name.or(cache.metadata.name))
+        .filter(|name| !name.trim().is_empty())
+}
+
+async fn send_blocked(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &BackendState,
+) -> Result<(), Error> {
+    let db_blocked = DbBlocked::fetch(backend.pool()).await?;
+    let pubkeys: Vec<_> = db_blocked.iter().map(|b| b.pubkey).collect();
+    let mut profiles = ProfileCache::fetch_by_public_keys(backend.pool(), &pubkeys).await?;
+    let blocked = db_blocked
+        .into_iter()
+        .map(|db_blocked| {
+            let name = profile_name(profiles.remove(&db_blocked.pubkey));
+            (db_blocked, name)
+        })
+        .collect();
+    _ = output.send(BackendEvent::GotBlocked(blocked)).await;
+    Ok(())
+}
+
+/// Preview and unread count of a chat card, `None` without messages
+pub(crate) async fn fetch_chat_info(
+    keys: &Keys,
+    pool: &sqlx::SqlitePool,
+    chat_pubkey: &XOnlyPublicKey,
+) -> Result<Option<ChatInfo>, Error> {
+    let Some(db_message) = DbMessage::fetch_chat_last(pool, chat_pubkey).await? else {
+        return Ok(None);
+    };
+    let Some(db_event) = DbEvent::fetch_id(pool, db_message.event_id).await? else {
+        return Ok(None);
+    };
+    let unseen_messages = DbMessage::fetch_unseen_chat_count(pool, chat_pubkey).await?;
+    let decrypted_content = if db_message.retracted_at.is_some() {
+        RETRACTED_TEXT.to_owned()
+    } else {
+        let tag_info = MessageTagInfo::from_event_tags(
+            &db_event.event_hash,
+            &db_event.pubkey,
+            &db_event.tags,
+        )?;
+        db_message.decrypt_message(keys, &tag_info)?
+    };
 
-            backend.new_contact_list_event(keys).await?;
+    Ok(Some(ChatInfo {
+        unseen_messages,
+        last_message: decrypted_content,
+        last_message_time: Some(db_message.created_at),
+    }))
+}
 
-            _ = output
-                .send(BackendEvent::FileContactsImported(db_contacts))
-                .await;
+async fn send_trash(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &BackendState,
+) -> Result<(), Error> {
+    let mut items = Trash::fetch(backend.pool()).await?;
+    let unnamed = |item: &TrashItem| {
+        item.name
+            .as_deref()
+            .map_or(true, |name| name.trim().is_empty())
+    };
+    let pubkeys: Vec<_> = items
+        .iter()
+        .filter(|item| unnamed(item))
+        .map(|item| item.pubkey)
+        .collect();
+    let profiles = ProfileCache::fetch_by_public_keys(backend.pool(), &pubkeys).await?;
+    for item in &mut items {
+        if unnamed(item) {
+            item.name = profile_name(profiles.get(&item.pubkey).cloned());
         }
-        ToBackend::AddContact(db_contact) => {
-            // Check if the contact is the same as the user
-            if &keys.public_key() == db_contact.pubkey() {
-                return Err(Error::SameContactInsert);
-            }
-
-            DbContact::insert(backend.pool(), db_contact.pubkey()).await?;
-            DbContact::update(backend.pool(), &db_contact).await?;
+    }
+    _ = output.send(BackendEvent::GotTrash(items)).await;
+    Ok(())
+}
 
-            backend.new_contact_list_event(keys).await?;
+/// The user's latest mute list, one still waiting for the relays first
+async fn last_mute_list(
+    keys: &Keys,
+    backend: &BackendState,
+) -> Result<Option<nostr::Event>, Error> {
+    let kind = Kind::Custom(MUTE_LIST_KIND);
+    let pending = backend
+        .pending_events
+        .values()
+        .map(PendingEvent::ns_event)
+        .filter(|ns_event| ns_event.kind == kind)
+        .max_by_key(|ns_event| ns_event.created_at);
+    if let Some(ns_event) = pending {
+        return Ok(Some(ns_event.to_owned()));
+    }
+    let stored = DbEvent::fetch_last_kind_pubkey(backend.pool(), kind, &keys.public_key()).await?;
+    Ok(stored.map(|db_event| db_event.to_ns_event()).transpose()?)
+}
 
-            _ = output.send(BackendEvent::ContactCreated(db_contact)).await;
-        }
-        ToBackend::UpdateContact(db_contact) => {
-            if &keys.public_key() == db_contact.pubkey() {
-                return Err(Error::SameContactUpdate);
-            }
-            DbContact::update(backend.pool(), &db_contact).await?;
+/// Public keys muted in the open, private entries are encrypted in the content
+pub(crate) fn muted_pubkeys(tags: &[Tag]) -> Vec<XOnlyPublicKey> {
+    tags.iter()
+        .filter_map(|tag| match tag {
+            Tag::PubKey(pk, _) => Some(pk.to_owned()),
+            _ => None,
+        })
+        .collect()
+}
 
-            backend.new_contact_list_event(keys).await?;
+async fn muted_users(keys: &Keys, backend: &BackendState) -> Result<Vec<XOnlyPublicKey>, Error> {
+    Ok(last_mute_list(keys, backend)
+        .await?
+        .map(|ns_event| muted_pubkeys(&ns_event.tags))
+        .unwrap_or_default())
+}
 
-            _ = output.send(BackendEvent::ContactUpdated(db_contact)).await;
-        }
-        ToBackend::DeleteContact(db_contact) => {
-            DbContact::delete(backend.pool(), &db_contact).await?;
-            backend.new_contact_list_event(keys).await?;
-            _ = output.send(BackendEvent::ContactDeleted(db_contact)).await;
-        }
-        ToBackend::FetchContacts => {
-            let contacts = DbContact::fetch(backend.pool(), backend.cache_pool()).await?;
-            _ = output.send(BackendEvent::GotContacts(contacts)).await;
-        }
+/// Adds or removes the public key in the user's latest mute list. Its other
+/// tags (hashtags, words, threads) and the encrypted private entries are
+/// published again unchanged. Returns the public keys of the new list
+async fn update_mute_list(
+    keys: &Keys,
+    backend: &mut BackendState,
+    pubkey: &XOnlyPublicKey,
+    add: bool,
+) -> Result<Vec<XOnlyPublicKey>, Error> {
+    let (mut tags, content) = match last_mute_list(keys, backend).await? {
+        Some(ns_event) => (ns_event.tags, ns_event.content),
+        None => (vec![], String::new()),
+    };
+    tags.retain(|tag| !matches!(tag, Tag::PubKey(pk, _) if pk == pubkey));
+    if add {
+        tags.push(Tag::PubKey(pubkey.to_owned(), None));
+    }
+    backend.new_mute_list_event(keys, &tags, &content).await?;
+    Ok(muted_pubkeys(&tags))
+}
 
-        ToBackend::GetRelayInformation => {
-            backend.nostr.relays_info()?;
+/// Sends the latest profile and contact list again so new relays are useful right away,
+/// relays that already have them just answer ok
+async fn republish_to_new_relays(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    relays: Vec<Url>,
+) -> Result<(), Error> {
+    if relays.is_empty() {
+        return Ok(());
+    }
+    let mut published = vec![];
+    for (kind, label) in [
+        (Kind::Metadata, "Profile"),
+        (Kind::ContactList, "Contact list"),
+    ] {
+        let last =
+            DbEvent::fetch_last_kind_pubkey(backend.pool(), kind, &keys.public_key()).await?;
+        if let Some(db_event) = last {
+            let ns_event = db_event.to_ns_event()?;
+            if !backend.simulation.dry_run(&ns_event) {
+                backend.nostr.send_event(ns_event)?;
+            }
+            published.push(format!(
+                "{} from {}",
+                label,
+                from_naive_utc_to_local(db_event.created_at).format(YMD_FORMAT)
+            ));
         }
+    }
+    if !published.is_empty() {
+        _ = output
+            .send(BackendEvent::RelaysRepublished { relays, published })
+            .await;
+    }
+    Ok(())
+}
 
-        ToBackend::CreateChannel => {
-            todo!()
-        }
+/// Sends a metadata REQ to each read relay within its request budget
+async fn subscribe_metadata(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &mut BackendState,
+    subscription: Subscription,
+) -> Result<(), Error> {
+    let relays = DbRelay::fetch(backend.pool()).await?;
+    for db_relay in relays.iter().filter(|db_relay| db_relay.read) {
+        request_metadata(output, backend, &db_relay.url, subscription.clone()).await?;
+    }
+    Ok(())
+}
 
-        ToBackend::SendChannelMessage(channel_id, raw_content) => {
-            // create a pending event and await confirmation of relays
-            let recommended_relay = UserConfig::get_relay(backend.pool()).await?;
-            let pending_event = backend
-                .new_channel_msg(keys, &channel_id, recommended_relay.as_ref(), &raw_content)
-                .await?;
+/// Sends the REQ now or queues it until the relay's budget has room
+async fn request_metadata(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &mut BackendState,
+    url: &Url,
+    subscription: Subscription,
+) -> Result<(), Error> {
+    if let Some(subscription) = backend
+        .request_budget
+        .request(url, subscription, Instant::now())
+    {
+        backend.nostr.relay_subscribe(url, &subscription)?;
+    }
+    send_throttling(output, backend).await;
+    Ok(())
+}
 
-            let chat_message = ChatMessage::pending(pending_event, &raw_content);
+async fn send_queued_requests(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &mut BackendState,
+) -> Result<(), Error> {
+    for (url, subscription) in backend.request_budget.ready(Instant::now()) {
+        backend.nostr.relay_subscribe(&url, &subscription)?;
+    }
+    send_throttling(output, backend).await;
+    Ok(())
+}
 
-            _ = output
-                .send(BackendEvent::PendingChannelMsg(channel_id, chat_message))
-                .await;
-        }
-        ToBackend::SendDM(db_contact, raw_content) => {
-            // create a pending event and await confirmation of relays
-            let pending_event = backend.new_dm(keys, &db_contact, &raw_content).await?;
+async fn send_throttling(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    backend: &mut BackendState,
+) {
+    if let Some(throttling) = backend.request_budget.throttling_changed() {
+        tracing::info!("Throttling metadata requests: {}", throttling);
+        _ = output.send(BackendEvent::Throttling(throttling)).await;
+    }
+}
 
-            let chat_message = ChatMessage::pending(pending_event, &raw_content);
+async fn send_relay_profiles(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    pool: &sqlx::SqlitePool,
+) -> Result<(), Error> {
+    let profiles = DbRelayProfile::fetch(pool).await?;
+    let active = UserConfig::get_active_relay_profile(pool).await?;
+    _ = output
+        .send(BackendEvent::GotRelayProfiles { profiles, active })
+        .await;
+    Ok(())
+}
 
-            _ = output
-                .send(BackendEvent::PendingDM(db_contact, chat_message))
-                .await;
+/// Stores the profile's relays in one transaction, then removes, adds and toggles
+/// relays in the pool to match. The profile stays active for the next startup
+async fn switch_relay_profile(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    name: &str,
+) -> Result<(), Error> {
+    let profile = DbRelayProfile::fetch_by_name(backend.pool(), name)
+        .await?
+        .ok_or_else(|| relay_profile::Error::ProfileNotFound(name.to_owned()))?;
+    let current = DbRelay::fetch(backend.pool()).await?;
+    let switch = RelaySwitch::plan(&current, &profile);
+    let created = switch.apply(backend.pool()).await?;
+
+    for url in &switch.removed {
+        backend.nostr.remove_relay(url.as_str())?;
+        backend.flood_guard.forget_relay(url);
+        backend.request_budget.forget_relay(url);
+        DbRelayViolation::delete_relay(backend.pool(), url).await?;
+    }
+    for db_relay in created.iter().chain(&switch.toggled) {
+        if !current.iter().any(|relay| relay.id == db_relay.id) {
+            backend.nostr.add_relay(db_relay.url.as_str())?;
         }
+        backend
+            .nostr
+            .toggle_read_for(&db_relay.url, db_relay.read)?;
+        backend
+            .nostr
+            .toggle_write_for(&db_relay.url, db_relay.write)?;
     }
+    UserConfig::set_active_relay_profile(backend.pool(), Some(&profile.name)).await?;
+    tracing::info!(
+        "Switched to relay profile {}: {} removed, {} added, {} toggled",
+        profile.name,
+        switch.removed.len(),
+        created.len(),
+        switch.toggled.len()
+    );
+
+    let relays = DbRelay::fetch(backend.pool()).await?;
+    _ = output.send(BackendEvent::GotRelays(relays)).await;
+    send_relay_profiles(output, backend.pool()).await?;
+
+    let new_relays = created
+        .into_iter()
+        .filter(|db_relay| db_relay.write)
+        .map(|db_relay| db_relay.url)
+        .collect();
+    republish_to_new_relays(output, keys, backend, new_relays).await
+}
+
+async fn relay_suggestions(
+    keys: &Keys,
+    pool: &sqlx::SqlitePool,
+    idle_days: u32,
+) -> Result<Vec<RelaySuggestion>, Error> {
+    let now = chrono::Utc::now().naive_utc();
+    let since = now - chrono::Duration::days(idle_days as i64);
+    let relays = DbRelay::fetch(pool).await?;
+    let activity = RelayActivity::fetch(pool, &keys.public_key(), since).await?;
+    Ok(suggest(&relays, &activity, now, idle_days))
+}
 
+async fn send_relay_tuning(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &BackendState,
+) -> Result<(), Error> {
+    let config = Config::load_file_async().await?.relay_tuning;
+    let suggestions = relay_suggestions(keys, backend.pool(), config.idle_days).await?;
+    _ = output
+        .send(BackendEvent::GotRelayTuning {
+            config,
+            suggestions,
+            auto_applied: backend.relay_tuning_applied.clone(),
+        })
+        .await;
     Ok(())
 }
 
+/// Only stored, returns the updated relay
+async fn apply_relay_suggestion(
+    pool: &sqlx::SqlitePool,
+    suggestion: &RelaySuggestion,
+) -> Result<Option<DbRelay>, Error> {
+    let Some(mut db_relay) = DbRelay::fetch_by_url(pool, &suggestion.url).await? else {
+        return Ok(None);
+    };
+    match suggestion.change {
+        TuningChange::DisableRead => db_relay.read = false,
+        TuningChange::DisableWrite => db_relay.write = false,
+    }
+    DbRelay::update(pool, &db_relay).await?;
+    Ok(Some(db_relay))
+}
+
+/// Channel name from the fetched caches, the id when it isn't known
+fn channel_name(caches: &HashMap<EventId, ChannelCache>, channel_id: &EventId) -> String {
+    caches
+        .get(channel_id)
+        .and_then(|cache| cache.metadata.name.clone())
+        .unwrap_or_else(|| channel_id.to_string())
+}
+
+/// Channels from the collected contact activity, with their names and caches
+async fn rank_contact_channels(backend: &BackendState) -> Result<Vec<ContactChannel>, Error> {
+    let pool = backend.pool();
+
+    let subscribed: HashSet<_> = ChannelSubscription::fetch(pool)
+        .await?
+        .into_iter()
+        .map(|c| c.channel_id)
+        .collect();
+    let contacts: HashMap<_, _> = DbContact::fetch(pool)
+        .await?
+        .into_iter()
+        .map(|c| (c.pubkey().to_owned(), c.select_name()))
+        .collect();
+
+    let mut channels = rank(
+        &backend.contact_channel_activity,
+        &subscribed,
+        CONTACT_CHANNELS_RESULTS,
+    );
+    let channel_ids: Vec<_> = channels.iter().map(|c| c.channel_id).collect();
+    let mut caches = ChannelCache::fetch_by_channel_ids(pool, &channel_ids).await?;
+    for channel in &mut channels {
+        channel.contact_names = channel
+            .contacts
+            .iter()
+            .filter_map(|pubkey| contacts.get(pubkey).cloned())
+            .collect();
+        channel.cache = caches.remove(&channel.channel_id);
+    }
+    Ok(channels)
+}
+
 async fn update_channels_subscription(backend: &mut BackendState) -> Result<(), Error> {
     let pool = backend.pool();
 
@@ -1353,6 +5094,7 @@ async fn update_channels_subscription(backend: &mut BackendState) -> Result<(),
 
     let subscription = ns_client::Subscription::new(channel_details_filter(&channels, &last_event))
         .with_id(SubName::Channels.to_string());
+    backend.flood_guard.restart(&SubName::Channels);
     backend.nostr.subscribe(&subscription)?;
 
     Ok(())
@@ -1382,6 +5124,8 @@ async fn send_got_chat_messages(
         }
     }
 
+    add_reactions(pool, keys, &mut chat_messages).await?;
+
     _ = output
         .send(BackendEvent::GotChatMessages(db_contact, chat_messages))
         .await;
@@ -1389,90 +5133,202 @@ async fn send_got_chat_messages(
     Ok(())
 }
 
-fn decrypt_message(
-    db_event: &DbEvent,
-    db_message: &DbMessage,
+/// Fills in the reaction counts of the stored messages
+async fn add_reactions(
+    pool: &sqlx::SqlitePool,
     keys: &Keys,
-    db_contact: &DbContact,
-) -> Result<ChatMessage, Error> {
-    let tag_info =
-        MessageTagInfo::from_event_tags(&db_event.event_hash, &db_event.pubkey, &db_event.tags)?;
-    let decrypted_content = db_message.decrypt_message(keys, &tag_info)?;
+    messages: &mut [ChatMessage],
+) -> Result<(), Error> {
+    let event_ids: Vec<_> = messages.iter().filter_map(ChatMessage::event_id).collect();
+    let mut counts = ReactionCount::fetch(pool, &event_ids, &keys.public_key()).await?;
+    for message in messages {
+        if let Some(reactions) = message.event_id().and_then(|id| counts.remove(&id)) {
+            message.set_reactions(reactions);
+        }
+    }
+    Ok(())
+}
 
-    let chat_message = if db_message.is_users {
-        ChatMessage::confirmed_users(db_message, &decrypted_content)
-    } else {
-        ChatMessage::confirmed_contacts(db_message, db_contact, &decrypted_content)
-    };
+async fn send_dm(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    db_contact: DbContact,
+    raw_content: String,
+) -> Result<(), Error> {
+    let target = DraftTarget::Contact(db_contact.pubkey().to_owned());
+    DbDraft::delete(backend.pool(), &target).await?;
+    // create a pending event and await confirmation of relays
+    let pending_event = backend.new_dm(keys, &db_contact, &raw_content).await?;
+    backend
+        .send_guard
+        .sent(target, &raw_content, Instant::now());
 
-    Ok(chat_message)
+    let chat_message = ChatMessage::pending(pending_event, &raw_content);
+
+    _ = output
+        .send(BackendEvent::PendingDM(db_contact, chat_message))
+        .await;
+    Ok(())
 }
 
-// async fn pending_users_meta_confirmed() -> Result<(),Error>{
-//     Ok(())
-// }
+async fn send_channel_message(
+    output: &mut futures::channel::mpsc::Sender<BackendEvent>,
+    keys: &Keys,
+    backend: &mut BackendState,
+    channel_id: EventId,
+    raw_content: String,
+) -> Result<(), Error> {
+    let target = DraftTarget::Channel(channel_id);
+    DbDraft::delete(backend.pool(), &target).await?;
+    // create a pending event and await confirmation of relays
+    let recommended_relay = UserConfig::get_relay(backend.pool()).await?;
+    // only the owner can mention everyone
+    let broadcast = ChannelMention::has_broadcast_keyword(&raw_content)
+        && ChannelCache::fetch_by_channel_id(backend.cache_pool(), &channel_id)
+            .await?
+            .map_or(false, |cache| cache.creator_pubkey == keys.public_key());
+    let pending_event = backend
+        .new_channel_msg(
+            keys,
+            &channel_id,
+            recommended_relay.as_ref(),
+            &raw_content,
+            broadcast,
+        )
+        .await?;
+    backend
+        .send_guard
+        .sent(target, &raw_content, Instant::now());
+
+    let chat_message = ChatMessage::pending(pending_event, &raw_content);
+
+    _ = output
+        .send(BackendEvent::PendingChannelMsg(channel_id, chat_message))
+        .await;
+    Ok(())
+}
 
-async fn insert_metadata_event(
+/// Unseen DMs and channel mentions, newest first
+async fn send_inbox(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
-    cache_pool: &SqlitePool,
-    relay_url: &Url,
-    ns_event: nostr::Event,
+    keys: &Keys,
+    backend: &mut BackendState,
 ) -> Result<(), Error> {
-    let pubkey = ns_event.pubkey;
-    tracing::debug!("Received metadata event for public key: {}", &pubkey);
+    let pool = backend.pool();
+    let mut items = vec![];
 
-    let rows_changed = ProfileCache::insert(cache_pool, relay_url, ns_event).await?;
+    let contacts: HashMap<_, _> = DbContact::fetch(pool)
+        .await?
+        .into_iter()
+        .map(|contact| (contact.pubkey().to_owned(), contact))
+        .collect();
+    for db_message in DbMessage::fetch_unseen(pool, INBOX_LIMIT).await? {
+        let Some(db_event) = DbEvent::fetch_id(pool, db_message.event_id).await? else {
+            continue;
+        };
+        let contact = contacts
+            .get(&db_message.chat_pubkey)
+            .cloned()
+            .unwrap_or_else(|| DbContact::new(&db_message.chat_pubkey));
+        match decrypt_message(&db_event, &db_message, keys, &contact) {
+            Ok(message) => items.push(InboxItem::DM { contact, message }),
+            Err(e) => tracing::error!("Failed to decrypt message: {}", e),
+        }
+    }
 
-    if rows_changed == 0 {
-        tracing::debug!("Cache already up to date");
+    let mentions = ChannelMention::fetch_unseen(pool).await?;
+    let channel_ids: Vec<_> = mentions.iter().map(|(m, _)| m.channel_id).collect();
+    let caches = ChannelCache::fetch_by_channel_ids(pool, &channel_ids).await?;
+    for (ch_message, keyword) in mentions {
+        let channel_id = ch_message.channel_id;
+        let channel_name = caches
+            .get(&channel_id)
+            .and_then(|cache| cache.metadata.name.clone());
+        items.push(InboxItem::Mention {
+            channel_id,
+            channel_name,
+            message: ch_message.into(),
+            keyword,
+        });
     }
 
-    _ = output.send(BackendEvent::UpdatedMetadata(pubkey)).await;
+    sort_inbox(&mut items);
+    _ = output.send(BackendEvent::GotInbox(items)).await;
 
     Ok(())
 }
 
-async fn handle_channel_message(
+async fn send_followers(
     output: &mut futures::channel::mpsc::Sender<BackendEvent>,
-    keys: &Keys,
-    pool: &SqlitePool,
-    cache_pool: &SqlitePool,
-    relay_url: &Url,
-    ns_event: nostr::Event,
+    backend: &mut BackendState,
 ) -> Result<(), Error> {
-    let Some(channel_id) = channel_id_from_tags(&ns_event.tags) else {
-        return Err(Error::ChannelIdNotFound(ns_event.id));
-    };
-
-    if let Some(db_event) = DbEvent::insert(pool, relay_url, &ns_event).await? {
-        let is_users = db_event.pubkey == keys.public_key();
-        let ch_msg = DbChannelMessage::insert_confirmed(pool, &db_event, is_users).await?;
+    let pool = backend.pool();
+    let mut items = vec![];
 
-        let rows_affected =
-            ChannelCache::insert_member(cache_pool, &channel_id, &db_event.pubkey).await?;
-        if rows_affected != 0 {
-            // TODO: check if this is IO heavy
-            if let Some(cache) = ChannelCache::fetch_by_channel_id(cache_pool, &channel_id).await? {
-                let _ = output.send(BackendEvent::ChannelCacheUpdated(cache)).await;
-            } else {
-                tracing::error!(
-                    "Failed to fetch channel cache for channel_id: {}",
-                    &channel_id
-                );
+    let followers = DbFollower::fetch(pool).await?;
+    let mut contacts: HashMap<_, _> = DbContact::fetch(pool)
+        .await?
+        .into_iter()
+        .map(|contact| (contact.pubkey().to_owned(), contact))
+        .collect();
+    let strangers: Vec<_> = followers
+        .iter()
+        .map(|follower| follower.pubkey)
+        .filter(|pubkey| !contacts.contains_key(pubkey))
+        .collect();
+    let profiles = ProfileCache::fetch_by_public_keys(pool, &strangers).await?;
+
+    for follower in followers {
+        let item = match contacts.remove(&follower.pubkey) {
+            Some(contact) => FollowerItem {
+                follower,
+                contact,
+                followed_back: true,
+            },
+            None => {
+                let mut contact = DbContact::new(&follower.pubkey);
+                if let Some(cache) = profiles.get(&follower.pubkey) {
+                    contact = contact.with_profile_cache(cache);
+                }
+                FollowerItem {
+                    follower,
+                    contact,
+                    followed_back: false,
+                }
             }
-        }
-
-        let _ = output
-            .send(BackendEvent::ReceivedChannelMessage(
-                channel_id,
-                ch_msg.into(),
-            ))
-            .await;
+        };
+        items.push(item);
     }
 
+    _ = output.send(BackendEvent::GotFollowers(items)).await;
+
     Ok(())
 }
 
+fn decrypt_message(
+    db_event: &DbEvent,
+    db_message: &DbMessage,
+    keys: &Keys,
+    db_contact: &DbContact,
+) -> Result<ChatMessage, Error> {
+    let tag_info =
+        MessageTagInfo::from_event_tags(&db_event.event_hash, &db_event.pubkey, &db_event.tags)?;
+    let decrypted_content = db_message.decrypt_message(keys, &tag_info)?;
+
+    let chat_message = if db_message.is_users {
+        ChatMessage::confirmed_users(db_message, &decrypted_content)
+    } else {
+        ChatMessage::confirmed_contacts(db_message, db_contact, &decrypted_content)
+    };
+
+    Ok(chat_message)
+}
+
+// async fn pending_users_meta_confirmed() -> Result<(),Error>{
+//     Ok(())
+// }
+
 // pub async fn handle_recommend_relay(db_event: DbEvent) -> Result<(), Error> {
 //     tracing::debug!("handle_recommend_relay");
 //     dbg!(&db_event);
@@ -1496,16 +5352,46 @@ pub async fn _create_channel(_client: &RelayPool) -> Result<BackendEvent, Error>
 }
 
 async fn prepare_client(keys: &Keys, backend: &mut BackendState) -> Result<(), Error> {
+    let relay_tuning = Config::load_file_async().await?.relay_tuning;
+    if relay_tuning.auto_apply {
+        let pool = backend.pool();
+        let mut applied = vec![];
+        for suggestion in relay_suggestions(keys, pool, relay_tuning.idle_days).await? {
+            if apply_relay_suggestion(pool, &suggestion).await?.is_some() {
+                tracing::info!(
+                    "{} for {}: {}",
+                    suggestion.change,
+                    suggestion.url,
+                    suggestion.reason
+                );
+                applied.push(suggestion);
+            }
+        }
+        backend.relay_tuning_applied = applied;
+    }
+
     let pool = backend.pool();
 
     let relays = DbRelay::fetch(pool).await?;
     let last_event = DbEvent::fetch_last(pool).await?;
+    let dm_sync_since = UserConfig::get_dm_sync_since(pool).await?;
     let contact_list = DbContact::fetch_basic(pool).await?;
+    let visit_scores = ConversationVisit::fetch_scores(pool).await?;
 
     let channels = ChannelSubscription::fetch(pool).await?;
     let channels: Vec<_> = channels.into_iter().map(|c| c.channel_id).collect();
 
     UserConfig::store_first_login(pool).await?;
+    let now = UserConfig::get_corrected_time(pool)
+        .await
+        .unwrap_or(chrono::Utc::now().naive_utc());
+    let purged = Trash::purge_expired(pool, now).await?;
+    if purged > 0 {
+        tracing::info!("Purged {} expired items from the trash", purged);
+    }
+    let blocked = DbBlocked::fetch_pubkeys(pool).await?;
+
+    backend.set_blocked(blocked);
 
     tracing::info!("Adding relays to client: {}", relays.len());
 
@@ -1529,9 +5415,31 @@ async fn prepare_client(keys: &Keys, backend: &mut BackendState) -> Result<(), E
             .eose(Some(Duration::from_secs(30)));
     backend.nostr.subscribe(&user_meta_sub)?;
 
-    let messages_sub = Subscription::new(messages_filter(keys.public_key(), &last_event))
-        .with_id(SubName::Messages.to_string());
-    backend.nostr.subscribe(&messages_sub)?;
+    let messages_sub = Subscription::new(messages_filter(
+        keys.public_key(),
+        &last_event,
+        dm_sync_since,
+    ))
+    .with_id(SubName::Messages.to_string());
+    let contacts: Vec<_> = contact_list.iter().map(|c| c.pubkey().to_owned()).collect();
+    let mut dm_prefetch: Vec<_> = prefetch_waves(&visit_scores, &contacts, &DM_PREFETCH_WAVES)
+        .iter()
+        .enumerate()
+        .map(|(wave, chat_pubkeys)| {
+            Subscription::new(messages_wave_filter(
+                keys.public_key(),
+                chat_pubkeys,
+                &last_event,
+                dm_sync_since,
+            ))
+            .with_id(SubName::MessagesWave(wave).to_string())
+            .eose(Some(Duration::from_secs(10)))
+        })
+        .collect();
+    dm_prefetch.push(messages_sub);
+    tracing::info!("Fetching messages in {} waves", dm_prefetch.len());
+    backend.nostr.subscribe(&dm_prefetch[0])?;
+    backend.dm_prefetch = dm_prefetch;
 
     let filter = contact_list_metadata_filter(&contact_list, &last_event);
     let contact_list_meta_sub =
@@ -1543,27 +5451,104 @@ async fn prepare_client(keys: &Keys, backend: &mut BackendState) -> Result<(), E
     let channels_sub = Subscription::new(filters).with_id(SubName::Channels.to_string());
     backend.nostr.subscribe(&channels_sub)?;
 
+    let hashtags = FollowedHashtags::fetch(backend.pool()).await?;
+    if !hashtags.is_empty() {
+        subscribe_followed_hashtags(backend, &hashtags)?;
+    }
+
     if let Some(profile) = backend.create_account.take() {
         let profile_meta: Metadata = profile.into();
         backend.new_profile_event(keys, &profile_meta).await?;
     }
 
+    let handlers: Vec<_> = backend.kinds().handlers().cloned().collect();
+    for handler in handlers {
+        handler.setup(backend.pool()).await?;
+        let filters = handler.filters(keys.public_key(), &contact_list, &last_event);
+        if !filters.is_empty() {
+            let sub = Subscription::new(filters)
+                .with_id(SubName::Kind(handler.name().into()).to_string());
+            backend.nostr.subscribe(&sub)?;
+        }
+    }
+
+    backend.resend_journal().await?;
+
+    Ok(())
+}
+
+/// Encrypted events of the conversation with their decrypted content,
+/// a message that fails to decrypt is still archived
+async fn export_conversation_archive(
+    backend: &BackendState,
+    keys: &Keys,
+    db_contact: &DbContact,
+    file_handle: rfd::FileHandle,
+) -> Result<PathBuf, Error> {
+    let pool = backend.pool();
+    let mut entries = vec![];
+    for db_message in DbMessage::fetch_chat_all(pool, db_contact.pubkey()).await? {
+        let Some(db_event) = DbEvent::fetch_id(pool, db_message.event_id).await? else {
+            continue;
+        };
+        let event = db_event.to_ns_event()?;
+        let tag_info = MessageTagInfo::from_event_tags(&event.id, &event.pubkey, &event.tags)?;
+        let content = match db_message.decrypt_message(keys, &tag_info) {
+            Ok(content) => Some(content),
+            Err(e) => {
+                tracing::warn!("Archiving {} without transcript: {}", event.id, e);
+                None
+            }
+        };
+        let mut relays = vec![canonical_relay_url(&db_event.relay_url)];
+        for response in DbRelayResponse::fetch_by_event(pool, db_message.event_id).await? {
+            if response.status == ResponseStatus::Ok && !relays.contains(&response.relay_url) {
+                relays.push(response.relay_url);
+            }
+        }
+        entries.push(ArchiveEntry {
+            event,
+            relays,
+            content,
+        });
+    }
+
+    let archive = conversation_archive(
+        &keys.public_key(),
+        db_contact.pubkey(),
+        &db_contact.select_name(),
+        chrono::Utc::now().naive_utc(),
+        &entries,
+    )?;
+    save_with_extension(file_handle, "zip", &archive).await
+}
+
+fn subscribe_followed_hashtags(
+    backend: &mut BackendState,
+    hashtags: &[String],
+) -> Result<(), Error> {
+    let subscription = Subscription::new(vec![followed_hashtags_filter(hashtags)])
+        .with_id(SubName::FollowedHashtags.to_string());
+    backend.nostr.subscribe(&subscription)?;
     Ok(())
 }
 
-async fn save_with_extension<T: Serialize>(
+async fn save_with_extension(
     file_handle: rfd::FileHandle,
     extension: &str,
-    data: &T,
+    data: &[u8],
 ) -> Result<PathBuf, Error> {
     let mut path = file_handle.path().to_path_buf();
     path.set_extension(extension);
-    let json = serde_json::to_vec(data)?;
-    tokio::fs::write(&path, json).await?;
+    tokio::fs::write(&path, data).await?;
     Ok(path)
 }
 
 async fn save_file<T: Serialize>(data: &T, extension: &str) -> Result<BackendEvent, Error> {
+    save_bytes(&serde_json::to_vec(data)?, extension).await
+}
+
+async fn save_bytes(data: &[u8], extension: &str) -> Result<BackendEvent, Error> {
     let rfd_instance = AsyncFileDialog::new().set_directory("/");
     let file_handle = rfd_instance.save_file().await;
     match file_handle {
@@ -1579,3 +5564,15 @@ async fn save_file<T: Serialize>(data: &T, extension: &str) -> Result<BackendEve
 }
 
 const BACKEND_CHANNEL_SIZE: usize = 1024;
+const CLOSING_TIMEOUT_SECS: u64 = 5;
+const PRUNE_INTERVAL_SECS: u64 = 60 * 30;
+const DOCUMENT_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const CONVERSATION_RELAYS_LIMIT: u32 = 3;
+const MESSAGE_LINK_RELAYS_LIMIT: usize = 3;
+const NTP_DRIFT_THRESHOLD_MICROS: i64 = 100_000;
+const CONTACT_CHANNELS_RESULTS: usize = 20;
+const SIGNING_LOG_LIMIT: i64 = 500;
+/// Most visited conversations fetched in each wave before every other message
+const DM_PREFETCH_WAVES: [usize; 2] = [10, 50];
+const CONSOLE_EOSE_SECS: u64 = 10;
+const DELETION_VERIFY_TIMEOUT: Duration = Duration::from_secs(10);
@@ -0,0 +1,250 @@
+//! Media uploads to [NIP-96](https://github.com/nostr-protocol/nips/blob/master/96.md) servers,
+//! authorized with a [NIP-98](https://github.com/nostr-protocol/nips/blob/master/98.md) event.
+
+use base64::engine::general_purpose;
+use base64::Engine;
+use futures::StreamExt;
+use nostr::hashes::Hash;
+use nostr::{EventBuilder, Keys, Kind, Tag, TagKind};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use thiserror::Error;
+use url::Url;
+
+use crate::db::SigningLogEntry;
+use crate::net::image_sanitize::SanitizedImage;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Invalid URL: \"{0}\"")]
+    InvalidUrl(#[from] url::ParseError),
+
+    #[error("Request error: {0}")]
+    FromReqwest(#[from] reqwest::Error),
+
+    #[error("Failed to sign the upload authorization: {0}")]
+    FromEventBuilder(#[from] nostr::prelude::builder::Error),
+
+    #[error("{0}")]
+    FromSigningLog(#[from] crate::db::signing_log::Error),
+
+    #[error("Upload refused: {0}")]
+    Refused(String),
+
+    #[error("The server didn't return the uploaded file's address")]
+    MissingUrl,
+}
+
+#[derive(Deserialize, Debug)]
+struct ServerInfo {
+    api_url: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct UploadResponse {
+    status: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    nip94_event: Option<Nip94Event>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Nip94Event {
+    tags: Vec<Vec<String>>,
+}
+
+/// Request body built once, sending it again after a failure doesn't
+/// read, encode or look up the server again
+#[derive(Debug)]
+pub struct PreparedUpload {
+    api_url: Url,
+    boundary: String,
+    body: Vec<u8>,
+}
+impl PreparedUpload {
+    /// Size of the body, what the progress is counted against
+    pub fn total(&self) -> u64 {
+        self.body.len() as u64
+    }
+}
+
+/// Finds the upload endpoint of `server` and builds the body for the file
+pub async fn prepare_upload(
+    client: &reqwest::Client,
+    server: &str,
+    file_name: &str,
+    mime: &str,
+    bytes: &[u8],
+) -> Result<PreparedUpload, Error> {
+    let api_url = api_url(client, server).await?;
+    let boundary = format!("nostrtalk-{:016x}", rand::random::<u64>());
+    let body = multipart_body(&boundary, file_name, mime, bytes);
+    Ok(PreparedUpload {
+        api_url,
+        boundary,
+        body,
+    })
+}
+
+/// Sends the body in chunks of [`UPLOAD_CHUNK_SIZE`], `on_chunk` gets the bytes
+/// handed to the connection so far and the total. Returns the public address.
+/// The authorization expires quickly, it is signed again for every attempt
+pub async fn send_upload(
+    client: &reqwest::Client,
+    pool: &SqlitePool,
+    keys: &Keys,
+    upload: &PreparedUpload,
+    mut on_chunk: impl FnMut(u64, u64) + Send + Sync + 'static,
+) -> Result<String, Error> {
+    let auth_event = auth_event(keys, &upload.api_url, &upload.body)?;
+    SigningLogEntry::insert(pool, &auth_event, &[]).await?;
+    let authorization = format!(
+        "Nostr {}",
+        general_purpose::STANDARD.encode(auth_event.as_json())
+    );
+
+    let total = upload.total();
+    let chunks: Vec<Vec<u8>> = upload
+        .body
+        .chunks(UPLOAD_CHUNK_SIZE)
+        .map(<[u8]>::to_vec)
+        .collect();
+    let mut sent = 0;
+    let stream = futures::stream::iter(chunks).map(move |chunk| {
+        sent += chunk.len() as u64;
+        on_chunk(sent, total);
+        Ok::<_, std::io::Error>(chunk)
+    });
+
+    let response: UploadResponse = client
+        .post(upload.api_url.clone())
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", upload.boundary),
+        )
+        .header(reqwest::header::CONTENT_LENGTH, total)
+        .timeout(TRANSFER_TIMEOUT)
+        .body(reqwest::Body::wrap_stream(stream))
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    uploaded_url(response)
+}
+
+/// Uploads the image and returns its public address
+pub async fn upload_image(
+    client: reqwest::Client,
+    pool: &SqlitePool,
+    keys: &Keys,
+    server: &str,
+    image: &SanitizedImage,
+) -> Result<String, Error> {
+    let extension = image.mime.rsplit('/').next().unwrap_or("bin");
+    let file_name = format!("image.{}", extension);
+    let upload = prepare_upload(&client, server, &file_name, image.mime, &image.bytes).await?;
+    send_upload(&client, pool, keys, &upload, |_, _| ()).await
+}
+
+/// Upload endpoint listed in the server's well-known document
+async fn api_url(client: &reqwest::Client, server: &str) -> Result<Url, Error> {
+    let server = Url::parse(server.trim())?;
+    let info: ServerInfo = client
+        .get(server.join(NIP96_WELL_KNOWN)?)
+        .timeout(UPLOAD_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(Url::parse(&info.api_url)?)
+}
+
+/// Event bound to the url and the body's hash, sent base64 encoded with the `Nostr` scheme
+fn auth_event(keys: &Keys, api_url: &Url, body: &[u8]) -> Result<nostr::Event, Error> {
+    let payload = nostr::hashes::sha256::Hash::hash(body).to_string();
+    let tags = [
+        Tag::Generic(TagKind::Custom("u".into()), vec![api_url.to_string()]),
+        Tag::Generic(TagKind::Custom("method".into()), vec!["POST".into()]),
+        Tag::Generic(TagKind::Custom("payload".into()), vec![payload]),
+    ];
+    Ok(EventBuilder::new(Kind::Custom(HTTP_AUTH_KIND), "", &tags).to_event(keys)?)
+}
+
+fn multipart_body(boundary: &str, file_name: &str, mime: &str, bytes: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(bytes.len() + 512);
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\n\
+            Content-Disposition: form-data; name=\"content_type\"\r\n\r\n\
+            {mime}\r\n\
+            --{boundary}\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+            Content-Type: {mime}\r\n\r\n",
+            boundary = boundary,
+            mime = mime,
+            file_name = file_name.replace('"', "'"),
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// `url` tag of the returned NIP-94 event
+fn uploaded_url(response: UploadResponse) -> Result<String, Error> {
+    if response.status != "success" {
+        return Err(Error::Refused(response.message.unwrap_or(response.status)));
+    }
+    response
+        .nip94_event
+        .and_then(|event| {
+            event
+                .tags
+                .into_iter()
+                .find(|tag| tag.first().map(String::as_str) == Some("url"))
+                .and_then(|tag| tag.into_iter().nth(1))
+        })
+        .ok_or(Error::MissingUrl)
+}
+
+const NIP96_WELL_KNOWN: &str = "/.well-known/nostr/nip96.json";
+const HTTP_AUTH_KIND: u64 = 27235;
+const UPLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+/// Whole transfer, large attachments on slow connections need a while
+const TRANSFER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+pub const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_has_the_file_part() {
+        let body = multipart_body("b", "image.png", "image/png", &[1, 2, 3]);
+        let text = String::from_utf8_lossy(&body);
+        assert!(text.starts_with("--b\r\n"));
+        assert!(text.contains("name=\"file\"; filename=\"image.png\"\r\nContent-Type: image/png"));
+        assert!(body.ends_with(b"\x01\x02\x03\r\n--b--\r\n"));
+    }
+
+    #[test]
+    fn reads_the_url_tag() {
+        let response: UploadResponse = serde_json::from_str(
+            r#"{"status":"success","nip94_event":{"tags":[["ox","abc"],["url","https://media.example.com/abc.png"]]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            uploaded_url(response).unwrap(),
+            "https://media.example.com/abc.png"
+        );
+
+        let response: UploadResponse =
+            serde_json::from_str(r#"{"status":"error","message":"File too large"}"#).unwrap();
+        assert!(matches!(uploaded_url(response), Err(Error::Refused(m)) if m == "File too large"));
+    }
+}
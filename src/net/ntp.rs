@@ -23,6 +23,15 @@ pub enum NtpError {
 
     #[error("System time before unix epoch")]
     SystemTimeBeforeUnixEpoch,
+
+    #[error("Sntpc Error: {0}")]
+    Query(String),
+
+    #[error("Could not resolve any NTP server")]
+    NoServerResolved,
+
+    #[error("No NTP server answered")]
+    NoServerAnswered,
 }
 
 #[derive(Copy, Clone, Default)]
@@ -81,73 +90,114 @@ fn bind_to_random_port() -> std::io::Result<UdpSocket> {
     ))
 }
 
+/// Offset agreed on by the servers that answered a sync round
+#[derive(Debug, Clone)]
+pub struct NtpSync {
+    /// Median of the offsets reported, in microseconds
+    pub offset: i64,
+    pub servers: Vec<String>,
+}
+
+/// Syncs once on demand, retrying with backoff until some server answers.
 pub fn spawn_ntp_request(sender: tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>) {
+    tokio::spawn(async move {
+        sync_with_retries(&sender).await;
+    });
+}
+
+/// Syncs at login and then every `RESYNC_INTERVAL`, until the backend goes away.
+pub fn spawn_ntp_sync(sender: tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>) {
     tokio::spawn(async move {
         loop {
-            tracing::debug!("Starting NTP request");
-            let ntp_addrs = ntp_addrs();
-
-            for addr in &ntp_addrs {
-                let socket = match bind_to_random_port() {
-                    Ok(socket) => socket,
-                    Err(_e) => {
-                        if let Err(e) = sender.send(Err(NtpError::NtpUnableToBindPort.into())).await
-                        {
-                            tracing::error!("Failed to send time to backend: {}", e);
-                        }
-                        return;
-                    }
-                };
-                if let Err(_e) = socket.set_read_timeout(Some(Duration::from_secs(10))) {
-                    if let Err(e) = sender
-                        .send(Err(NtpError::NtpUnableToSetReadTimeout.into()))
-                        .await
-                    {
-                        tracing::error!("Failed to send time to backend: {}", e);
-                    }
-                }
-                let sock_wrapper = UdpSocketWrapper(socket);
-                let ntp_context = NtpContext::new(StdTimestampGen::default());
-                match sntpc::get_time(addr.1, sock_wrapper, ntp_context) {
-                    Ok(time) => {
-                        if let Err(e) = sender
-                            .send(Ok(TaskOutput::Ntp(
-                                ntp_total_microseconds(time),
-                                addr.0.to_owned(),
-                            )))
-                            .await
-                        {
-                            tracing::error!("Failed to send time to backend: {}", e);
-                        }
-                        return;
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to get time from server: {} - {:?}", addr.0, e);
-                        // Continue trying the next address
-                    }
-                }
+            if !sync_with_retries(&sender).await {
+                break;
             }
-            tracing::info!(
-                "Failed to get NTP request. Trying again in 5 seconds in a different port."
-            );
-            tokio::time::sleep(Duration::from_secs(5)).await;
+            tokio::time::sleep(RESYNC_INTERVAL).await;
         }
+        tracing::debug!("NTP sync loop stopped");
     });
 }
 
-fn ntp_addrs() -> Vec<(String, SocketAddr)> {
-    let ntp_servers = vec![
-        "pool.ntp.org:123",
-        "time.google.com:123",
-        "time.cloudflare.com:123",
-        "time.windows.com:123",
-        "time.apple.com:123",
-        "time.facebook.com:123",
-    ];
+/// Returns false when the backend is no longer listening.
+async fn sync_with_retries(sender: &tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>) -> bool {
+    let mut backoff = RETRY_BACKOFF_START;
+    loop {
+        if sender.is_closed() {
+            return false;
+        }
+        tracing::debug!("Starting NTP request");
+        match query_servers().await {
+            Ok(sync) => return sender.send(Ok(TaskOutput::Ntp(sync))).await.is_ok(),
+            Err(e) => tracing::info!("NTP sync failed: {}. Trying again in {:?}", e, backoff),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+    }
+}
+
+/// Queries every server concurrently and takes the median offset.
+async fn query_servers() -> Result<NtpSync, NtpError> {
+    let ntp_addrs = tokio::task::spawn_blocking(ntp_addrs)
+        .await
+        .unwrap_or_default();
+    if ntp_addrs.is_empty() {
+        return Err(NtpError::NoServerResolved);
+    }
 
+    let queries = ntp_addrs.into_iter().map(|(server, addr)| {
+        tokio::task::spawn_blocking(move || {
+            let result = query_offset(addr);
+            if let Err(e) = &result {
+                tracing::error!("Failed to get time from server: {} - {}", server, e);
+            }
+            result.map(|offset| (server, offset))
+        })
+    });
+
+    let mut servers = vec![];
+    let mut offsets = vec![];
+    for result in futures::future::join_all(queries).await {
+        if let Ok(Ok((server, offset))) = result {
+            servers.push(server);
+            offsets.push(offset);
+        }
+    }
+
+    let offset = median_offset(&mut offsets).ok_or(NtpError::NoServerAnswered)?;
+    Ok(NtpSync { offset, servers })
+}
+
+/// Blocking query to a single server, returning its offset from the system clock.
+fn query_offset(addr: SocketAddr) -> Result<i64, NtpError> {
+    let socket = bind_to_random_port().map_err(|_| NtpError::NtpUnableToBindPort)?;
+    socket
+        .set_read_timeout(Some(QUERY_TIMEOUT))
+        .map_err(|_| NtpError::NtpUnableToSetReadTimeout)?;
+    let ntp_context = NtpContext::new(StdTimestampGen::default());
+    let time = sntpc::get_time(addr, UdpSocketWrapper(socket), ntp_context)
+        .map_err(|e| NtpError::Query(format!("{:?}", e)))?;
+    let system_total_microseconds = system_now_microseconds()?;
+    Ok(ntp_total_microseconds(time) as i64 - system_total_microseconds as i64)
+}
+
+/// Median of the offsets, averaging the middle two for an even count.
+pub fn median_offset(offsets: &mut [i64]) -> Option<i64> {
+    if offsets.is_empty() {
+        return None;
+    }
+    offsets.sort_unstable();
+    let mid = offsets.len() / 2;
+    if offsets.len() % 2 == 0 {
+        Some((offsets[mid - 1] + offsets[mid]) / 2)
+    } else {
+        Some(offsets[mid])
+    }
+}
+
+fn ntp_addrs() -> Vec<(String, SocketAddr)> {
     let mut ntp_addrs: Vec<(String, std::net::SocketAddr)> = Vec::new();
 
-    for server in &ntp_servers {
+    for server in &NTP_SERVERS {
         match server.to_socket_addrs() {
             Ok(mut addrs) => {
                 if let Some(addr) = addrs.next() {
@@ -155,7 +205,7 @@ fn ntp_addrs() -> Vec<(String, SocketAddr)> {
                 }
             }
             Err(e) => {
-                println!("Failed to resolve {}: {}", server, e);
+                tracing::warn!("Failed to resolve {}: {}", server, e);
             }
         }
     }
@@ -208,6 +258,19 @@ pub fn system_time_to_naive_utc(
         .ok_or(NtpError::InvalidTimestampNanos(secs, nanos))
 }
 
+const NTP_SERVERS: [&str; 6] = [
+    "pool.ntp.org:123",
+    "time.google.com:123",
+    "time.cloudflare.com:123",
+    "time.windows.com:123",
+    "time.apple.com:123",
+    "time.facebook.com:123",
+];
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_BACKOFF_START: Duration = Duration::from_secs(5);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[cfg(test)]
 mod tests {
     use chrono::{Datelike, NaiveDate, Timelike};
@@ -282,6 +345,15 @@ mod tests {
         assert!(naive_datetime.second() < 60);
     }
 
+    #[test]
+    fn test_median_offset() {
+        assert_eq!(median_offset(&mut []), None);
+        assert_eq!(median_offset(&mut [42]), Some(42));
+        // an outlier doesn't drag the result
+        assert_eq!(median_offset(&mut [900_000, -10, 20, 5]), Some(12));
+        assert_eq!(median_offset(&mut [3, -5_000_000, 1]), Some(1));
+    }
+
     #[test]
     fn test_corrected_time() {
         // Simulate different offsets
@@ -0,0 +1,663 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use image::{DynamicImage, GenericImageView, ImageOutputFormat};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use thiserror::Error;
+
+use super::reqwest_client::MediaClient;
+use super::TaskOutput;
+use crate::types::chat_message::ChatMessage;
+use crate::utils::from_naive_utc_to_local;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Image error: {0}")]
+    FromImage(#[from] image::error::ImageError),
+
+    #[error("Request error: {0}")]
+    Reqwest(#[from] reqwest::Error),
+
+    #[error("{0}")]
+    FromReqwestClient(#[from] super::reqwest_client::Error),
+
+    #[error("I/O Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Image too large: {0} bytes")]
+    ImageTooLarge(usize),
+
+    #[error("Export task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+/// Where a conversation export is at
+#[derive(Debug, Clone)]
+pub enum PdfExportStatus {
+    /// Messages prepared so far
+    Progress {
+        done: usize,
+        total: usize,
+    },
+    Saved {
+        path: PathBuf,
+        /// Characters the PDF fonts can't show, printed as `?`
+        unprintable: usize,
+    },
+    Failed(String),
+}
+
+/// Message as it is printed, times are local
+#[derive(Debug, Clone)]
+pub struct PdfMessage {
+    pub author: String,
+    pub time: NaiveDateTime,
+    pub content: String,
+    pub images: Vec<PdfImage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PdfImage {
+    pub jpeg: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Prepares the messages, renders and writes the PDF to `path`, reporting
+/// progress to the backend while images are downloaded
+pub fn spawn_pdf_export(
+    path: PathBuf,
+    title: String,
+    messages: Vec<ChatMessage>,
+    include_images: bool,
+    media_client: MediaClient,
+    sender: tokio::sync::mpsc::Sender<Result<TaskOutput, crate::Error>>,
+) {
+    tokio::spawn(async move {
+        let status = match export(
+            &path,
+            &title,
+            &messages,
+            include_images,
+            &media_client,
+            &sender,
+        )
+        .await
+        {
+            Ok(unprintable) => PdfExportStatus::Saved { path, unprintable },
+            Err(e) => {
+                tracing::error!("Failed to export conversation: {}", e);
+                PdfExportStatus::Failed(e.to_string())
+            }
+        };
+        _ = sender.send(Ok(TaskOutput::PdfExport(status))).await;
+    });
+}
+
+async fn export(
+    path: &Path,
+    title: &str,
+    messages: &[ChatMessage],
+    include_images: bool,
+    media_client: &MediaClient,
+    sender: &tokio::sync::mpsc::Sender<Result<TaskOutput, crate::Error>>,
+) -> Result<usize, Error> {
+    let total = messages.len();
+    let mut last_percent = 0;
+    let mut prepared = vec![];
+
+    for (idx, msg) in messages.iter().enumerate() {
+        // pending messages were never sent
+        let Some(time) = msg.display_time() else {
+            continue;
+        };
+        let author = match msg {
+            ChatMessage::UserMessage(_) => "You".to_owned(),
+            ChatMessage::ContactMessage { display_name, .. } => display_name.to_owned(),
+        };
+        let mut images = vec![];
+        if include_images {
+            for url in image_urls(msg.content()) {
+                match fetch_image(media_client, url).await {
+                    Ok(image) => images.push(image),
+                    Err(e) => tracing::info!("Image left out of the export: {} - {}", url, e),
+                }
+            }
+        }
+        prepared.push(PdfMessage {
+            author,
+            time: from_naive_utc_to_local(*time).naive_local(),
+            content: msg.content().to_owned(),
+            images,
+        });
+
+        let percent = (idx + 1) * 100 / total;
+        if percent != last_percent {
+            last_percent = percent;
+            let progress = PdfExportStatus::Progress {
+                done: idx + 1,
+                total,
+            };
+            _ = sender.send(Ok(TaskOutput::PdfExport(progress))).await;
+        }
+    }
+
+    let unprintable = unprintable(title)
+        + prepared
+            .iter()
+            .map(|msg| unprintable(&msg.author) + unprintable(&msg.content))
+            .sum::<usize>();
+    if unprintable > 0 {
+        tracing::warn!("{} characters can't be shown in the PDF", unprintable);
+    }
+    let title = title.to_owned();
+    let exported_at = chrono::Local::now().naive_local();
+    let bytes = tokio::task::spawn_blocking(move || render(&title, exported_at, &prepared)).await?;
+    tokio::fs::write(path, bytes).await?;
+    Ok(unprintable)
+}
+
+fn image_urls(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split_whitespace()
+        .filter(|word| IMAGE_URL_RE.is_match(word))
+}
+
+/// Downloads and shrinks an image, every image is embedded as a JPEG
+async fn fetch_image(media_client: &MediaClient, url: &str) -> Result<PdfImage, Error> {
+    let bytes = media_client
+        .get(url)?
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(Error::ImageTooLarge(bytes.len()));
+    }
+    tokio::task::spawn_blocking(move || {
+        let image = image::load_from_memory(&bytes)?;
+        let image = if image.width() > MAX_IMAGE_DIMENSION || image.height() > MAX_IMAGE_DIMENSION {
+            image.thumbnail(MAX_IMAGE_DIMENSION, MAX_IMAGE_DIMENSION)
+        } else {
+            image
+        };
+        let image = DynamicImage::ImageRgb8(image.to_rgb8());
+        let mut jpeg = Vec::new();
+        image.write_to(
+            &mut Cursor::new(&mut jpeg),
+            ImageOutputFormat::Jpeg(JPEG_QUALITY),
+        )?;
+        let (width, height) = image.dimensions();
+        Ok(PdfImage {
+            jpeg,
+            width,
+            height,
+        })
+    })
+    .await?
+}
+
+/// Lays the conversation out in A4 pages: a title, day headers,
+/// author and time above each message and page numbers at the bottom
+pub fn render(title: &str, exported_at: NaiveDateTime, messages: &[PdfMessage]) -> Vec<u8> {
+    let mut layout = Layout::new();
+    layout.line(title, FontStyle::Bold, TITLE_SIZE, 0.0);
+    layout.line(
+        &format!(
+            "{} messages, exported {}",
+            messages.len(),
+            exported_at.format("%Y-%m-%d %H:%M")
+        ),
+        FontStyle::Regular,
+        META_SIZE,
+        META_GRAY,
+    );
+
+    let mut last_day: Option<NaiveDate> = None;
+    for msg in messages {
+        let day = msg.time.date();
+        if last_day != Some(day) {
+            last_day = Some(day);
+            layout.gap(DAY_GAP);
+            layout.line(
+                &day.format("%A, %B %-d, %Y").to_string(),
+                FontStyle::Bold,
+                DAY_SIZE,
+                0.0,
+            );
+        }
+        layout.gap(MESSAGE_GAP);
+        layout.line(
+            &format!("{}  {}", msg.author, msg.time.format("%H:%M")),
+            FontStyle::Bold,
+            META_SIZE,
+            META_GRAY,
+        );
+        for paragraph in msg.content.lines() {
+            for line in wrap(paragraph, BODY_SIZE, CONTENT_WIDTH) {
+                layout.line(&line, FontStyle::Regular, BODY_SIZE, 0.0);
+            }
+        }
+        for image in &msg.images {
+            layout.image(image);
+        }
+    }
+
+    layout.finish()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FontStyle {
+    Regular,
+    Bold,
+}
+impl FontStyle {
+    fn resource(&self) -> &str {
+        match self {
+            FontStyle::Regular => "F1",
+            FontStyle::Bold => "F2",
+        }
+    }
+}
+
+struct Page {
+    content: Vec<u8>,
+    /// Indexes into the document images
+    images: Vec<usize>,
+}
+
+struct Layout<'a> {
+    pages: Vec<Page>,
+    images: Vec<&'a PdfImage>,
+    /// Baseline of the next line, from the bottom of the page
+    y: f32,
+}
+impl<'a> Layout<'a> {
+    fn new() -> Self {
+        Self {
+            pages: vec![Page {
+                content: vec![],
+                images: vec![],
+            }],
+            images: vec![],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn page(&mut self) -> &mut Page {
+        self.pages.last_mut().expect("there is always a page")
+    }
+
+    fn ensure_room(&mut self, height: f32) {
+        if self.y - height < MARGIN + FOOTER_HEIGHT {
+            self.pages.push(Page {
+                content: vec![],
+                images: vec![],
+            });
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn line(&mut self, text: &str, style: FontStyle, size: f32, gray: f32) {
+        let height = size * LINE_SPACING;
+        self.ensure_room(height);
+        self.y -= height;
+        let y = self.y;
+        write_text(&mut self.page().content, MARGIN, y, text, style, size, gray);
+    }
+
+    fn image(&mut self, image: &'a PdfImage) {
+        let scale = (IMAGE_MAX_WIDTH / image.width as f32)
+            .min(IMAGE_MAX_HEIGHT / image.height as f32)
+            .min(1.0);
+        let (width, height) = (image.width as f32 * scale, image.height as f32 * scale);
+        self.ensure_room(height + IMAGE_GAP);
+        self.y -= height + IMAGE_GAP;
+        let idx = self.images.len();
+        self.images.push(image);
+        let y = self.y;
+        let page = self.page();
+        page.images.push(idx);
+        page.content.extend_from_slice(
+            format!(
+                "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q\n",
+                width, height, MARGIN, y, idx
+            )
+            .as_bytes(),
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let page_count = self.pages.len();
+        for (idx, page) in self.pages.iter_mut().enumerate() {
+            let number = format!("Page {} of {}", idx + 1, page_count);
+            let x = PAGE_WIDTH - MARGIN - text_width(&number, META_SIZE);
+            write_text(
+                &mut page.content,
+                x,
+                MARGIN,
+                &number,
+                FontStyle::Regular,
+                META_SIZE,
+                META_GRAY,
+            );
+        }
+        write_document(&self.pages, &self.images)
+    }
+}
+
+fn write_text(
+    content: &mut Vec<u8>,
+    x: f32,
+    y: f32,
+    text: &str,
+    style: FontStyle,
+    size: f32,
+    gray: f32,
+) {
+    content.extend_from_slice(
+        format!(
+            "{:.2} g BT /{} {} Tf {:.2} {:.2} Td (",
+            gray,
+            style.resource(),
+            size,
+            x,
+            y
+        )
+        .as_bytes(),
+    );
+    for byte in text.chars().map(|ch| win_ansi(ch).unwrap_or(b'?')) {
+        if matches!(byte, b'(' | b')' | b'\\') {
+            content.push(b'\\');
+        }
+        content.push(byte);
+    }
+    content.extend_from_slice(b") Tj ET\n");
+}
+
+/// Numbered objects followed by the cross-reference table
+fn write_document(pages: &[Page], images: &[&PdfImage]) -> Vec<u8> {
+    // 1 catalog, 2 page tree, 3 and 4 fonts, then the images and each page with its content
+    let first_image = 5;
+    let first_page = first_image + images.len();
+    let mut objects: Vec<Vec<u8>> = vec![];
+
+    let kids: Vec<_> = (0..pages.len())
+        .map(|idx| format!("{} 0 R", first_page + idx * 2))
+        .collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+    );
+    for font in ["Helvetica", "Helvetica-Bold"] {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            )
+            .into_bytes(),
+        );
+    }
+    for image in images {
+        let mut object = format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            image.width,
+            image.height,
+            image.jpeg.len()
+        )
+        .into_bytes();
+        object.extend_from_slice(&image.jpeg);
+        object.extend_from_slice(b"\nendstream");
+        objects.push(object);
+    }
+    for (idx, page) in pages.iter().enumerate() {
+        let xobjects: String = page
+            .images
+            .iter()
+            .map(|img| format!("/Im{} {} 0 R ", img, first_image + img))
+            .collect();
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> /XObject << {}>> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                xobjects,
+                first_page + idx * 2 + 1
+            )
+            .into_bytes(),
+        );
+        let mut content = format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+        content.extend_from_slice(&page.content);
+        content.extend_from_slice(b"\nendstream");
+        objects.push(content);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (idx, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", idx + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref_offset = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+/// Splits at spaces to fit `max_width`, words longer than a line are broken
+fn wrap(paragraph: &str, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for word in paragraph.split(' ') {
+        let candidate = if current.is_empty() {
+            word.to_owned()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if text_width(&candidate, size) <= max_width {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        for ch in word.chars() {
+            current.push(ch);
+            if text_width(&current, size) > max_width {
+                current.pop();
+                lines.push(std::mem::take(&mut current));
+                current.push(ch);
+            }
+        }
+    }
+    lines.push(current);
+    lines
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|ch| helvetica_width(win_ansi(ch).unwrap_or(b'?')) as f32)
+        .sum::<f32>()
+        * size
+        / 1000.0
+}
+
+/// Standard fonts only cover WinAnsi, anything else is printed as `?`
+fn win_ansi(ch: char) -> Option<u8> {
+    let byte = match ch {
+        ' '..='~' | '\u{a0}'..='\u{ff}' => ch as u8,
+        '€' => 0x80,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '\t' => b' ',
+        _ => return None,
+    };
+    Some(byte)
+}
+
+/// Characters lost to `?`, line breaks don't count
+fn unprintable(text: &str) -> usize {
+    text.chars()
+        .filter(|ch| !ch.is_control() && win_ansi(*ch).is_none())
+        .count()
+}
+
+/// Helvetica advance widths in thousandths of the font size
+fn helvetica_width(byte: u8) -> u16 {
+    match byte {
+        b' ' | b'!' | b',' | b'.' | b'/' | b':' | b';' | b'I' | b'[' | b'\\' | b']' | b'f'
+        | b't' => 278,
+        b'"' => 355,
+        b'#' | b'$' | b'0'..=b'9' | b'?' | b'L' | b'_' => 556,
+        b'a' | b'b' | b'd' | b'e' | b'g' | b'h' | b'n' | b'o' | b'p' | b'q' | b'u' => 556,
+        b'%' => 889,
+        b'&' | b'A' | b'B' | b'E' | b'K' | b'P' | b'S' | b'V' | b'X' | b'Y' => 667,
+        b'\'' => 191,
+        b'(' | b')' | b'-' | b'`' | b'r' => 333,
+        b'*' => 389,
+        b'+' | b'<' | b'=' | b'>' | b'~' => 584,
+        b'@' => 1015,
+        b'C' | b'D' | b'H' | b'N' | b'R' | b'U' | b'w' => 722,
+        b'F' | b'T' | b'Z' => 611,
+        b'G' | b'O' | b'Q' => 778,
+        b'J' | b'c' | b'k' | b's' | b'v' | b'x' | b'y' | b'z' => 500,
+        b'M' | b'm' => 833,
+        b'W' => 944,
+        b'^' => 469,
+        b'i' | b'j' | b'l' => 222,
+        b'{' | b'}' => 334,
+        b'|' => 260,
+        _ => 556,
+    }
+}
+
+static IMAGE_URL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^https?://\S+\.(?:png|jpe?g|gif|webp)(?:\?\S*)?$")
+        .expect("valid image url regex")
+});
+
+const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+const MAX_IMAGE_DIMENSION: u32 = 1024;
+const JPEG_QUALITY: u8 = 80;
+// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const FOOTER_HEIGHT: f32 = 20.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const IMAGE_MAX_WIDTH: f32 = 300.0;
+const IMAGE_MAX_HEIGHT: f32 = 300.0;
+const IMAGE_GAP: f32 = 6.0;
+const TITLE_SIZE: f32 = 16.0;
+const DAY_SIZE: f32 = 12.0;
+const META_SIZE: f32 = 9.0;
+const BODY_SIZE: f32 = 10.0;
+const META_GRAY: f32 = 0.4;
+const LINE_SPACING: f32 = 1.3;
+const DAY_GAP: f32 = 12.0;
+const MESSAGE_GAP: f32 = 6.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(day: u32, content: &str) -> PdfMessage {
+        PdfMessage {
+            author: "alice".into(),
+            time: NaiveDate::from_ymd_opt(2023, 6, day)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap(),
+            content: content.into(),
+            images: vec![],
+        }
+    }
+
+    fn count(haystack: &[u8], needle: &[u8]) -> usize {
+        haystack
+            .windows(needle.len())
+            .filter(|w| *w == needle)
+            .count()
+    }
+
+    #[test]
+    fn long_conversations_are_paginated() {
+        let messages: Vec<_> = (0..120)
+            .map(|n| message(1 + n / 60, &format!("message (number {})", n)))
+            .collect();
+        let pdf = render("bob", messages[0].time, &messages);
+
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let pages = count(&pdf, b"/Type /Page ");
+        assert!(pages > 1);
+        assert_eq!(
+            count(&pdf, format!("Page {} of {}", pages, pages).as_bytes()),
+            1
+        );
+        // parentheses are escaped inside the string literals
+        assert_eq!(count(&pdf, b"message \\(number 7\\)"), 1);
+        assert_eq!(count(&pdf, b"Friday, June 2, 2023"), 1);
+    }
+
+    #[test]
+    fn lines_fit_the_page() {
+        let text = "word ".repeat(200) + &"x".repeat(300);
+        let lines = wrap(&text, BODY_SIZE, CONTENT_WIDTH);
+        assert!(lines.len() > 2);
+        assert!(lines
+            .iter()
+            .all(|line| text_width(line, BODY_SIZE) <= CONTENT_WIDTH));
+    }
+
+    #[test]
+    fn characters_outside_win_ansi_are_counted() {
+        assert_eq!(unprintable("café – “ok”\n"), 0);
+        assert_eq!(unprintable("привет 👋"), 7);
+        let pdf = render("bob", message(1, "").time, &[message(1, "日本 ok")]);
+        assert_eq!(count(&pdf, b"(?? ok)"), 1);
+    }
+
+    #[test]
+    fn only_image_links_are_embedded() {
+        let content =
+            "look https://a.com/cat.JPG and https://a.com/page.html https://b.com/x.png?s=1";
+        let urls: Vec<_> = image_urls(content).collect();
+        assert_eq!(
+            urls,
+            vec!["https://a.com/cat.JPG", "https://b.com/x.png?s=1"]
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! NIP-11 documents kept in the relay table, fetched again once a day or when
+//! a relay comes back after errors.
+
+use nostr::nips::nip11::RelayInformationDocument;
+use url::Url;
+
+use super::reqwest_client::{fetch_relay_document, RelayDocumentFetch};
+use super::TaskOutput;
+use crate::Error;
+
+/// Fetches every document at the same time, `etag` is the cached one's
+pub fn spawn_document_refresh(
+    client: reqwest::Client,
+    relays: Vec<(Url, Option<String>)>,
+    sender: tokio::sync::mpsc::Sender<Result<TaskOutput, Error>>,
+) {
+    for (url, etag) in relays {
+        let client = client.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            let fetched = fetch_relay_document(client, &url, etag.as_deref())
+                .await
+                .map_err(|e| e.to_string());
+            _ = sender
+                .send(Ok(TaskOutput::RelayDocument(url, fetched)))
+                .await;
+        });
+    }
+}
+
+/// What changed between two versions of a document, one line per change
+pub fn document_changes(
+    old: &RelayInformationDocument,
+    new: &RelayInformationDocument,
+) -> Vec<String> {
+    let mut changes = vec![];
+
+    let old_nips = old.supported_nips.clone().unwrap_or_default();
+    let new_nips = new.supported_nips.clone().unwrap_or_default();
+    let added: Vec<_> = new_nips
+        .iter()
+        .filter(|nip| !old_nips.contains(nip))
+        .collect();
+    if !added.is_empty() {
+        changes.push(format!("Now supports {}", nip_list(&added)));
+    }
+    let removed: Vec<_> = old_nips
+        .iter()
+        .filter(|nip| !new_nips.contains(nip))
+        .collect();
+    if !removed.is_empty() {
+        changes.push(format!("No longer supports {}", nip_list(&removed)));
+    }
+
+    let fields = [
+        ("Name", &old.name, &new.name),
+        ("Description", &old.description, &new.description),
+        ("Public key", &old.pubkey, &new.pubkey),
+        ("Contact", &old.contact, &new.contact),
+        ("Software", &old.software, &new.software),
+        ("Version", &old.version, &new.version),
+    ];
+    for (label, old, new) in fields {
+        if old != new {
+            changes.push(format!(
+                "{} changed from \"{}\" to \"{}\"",
+                label,
+                old.as_deref().unwrap_or_default(),
+                new.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    changes
+}
+
+fn nip_list(nips: &[&u16]) -> String {
+    nips.iter()
+        .map(|nip| format!("NIP-{:02}", nip))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(json: &str) -> RelayInformationDocument {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn lists_nips_and_fields_that_changed() {
+        let old = document(r#"{"name": "relay", "supported_nips": [1, 9, 11], "version": "1.0"}"#);
+        let new = document(r#"{"name": "relay", "supported_nips": [1, 11, 50], "version": "1.1"}"#);
+        assert_eq!(
+            document_changes(&old, &new),
+            vec![
+                "Now supports NIP-50".to_owned(),
+                "No longer supports NIP-09".to_owned(),
+                "Version changed from \"1.0\" to \"1.1\"".to_owned(),
+            ]
+        );
+        assert!(document_changes(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::BackendEvent;
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies a command sent to the backend so views can match
+/// the events produced by it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(u64);
+impl RequestId {
+    pub fn new() -> Self {
+        Self(NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "req-{}", self.0)
+    }
+}
+
+/// Handle returned by `BackEndConnection::request`, kept by the view
+/// while it waits for the correlated response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingRequest {
+    id: RequestId,
+}
+impl PendingRequest {
+    pub(crate) fn new(id: RequestId) -> Self {
+        Self { id }
+    }
+    pub fn id(&self) -> RequestId {
+        self.id
+    }
+    /// Returns the inner event if it belongs to this request.
+    /// `Err` carries the error message when the backend failed to process it.
+    pub fn response<'a>(
+        &self,
+        event: &'a BackendEvent,
+    ) -> Option<Result<&'a BackendEvent, &'a str>> {
+        match event {
+            BackendEvent::Response(id, inner) if id == &self.id => Some(Ok(inner)),
+            BackendEvent::RequestFailed(id, error) if id == &self.id => Some(Err(error)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_ids_are_unique() {
+        let a = RequestId::new();
+        let b = RequestId::new();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn pending_request_matches_only_its_id() {
+        let pending = PendingRequest::new(RequestId::new());
+        let other = RequestId::new();
+
+        let event = BackendEvent::Response(pending.id(), Box::new(BackendEvent::RFDCancelPick));
+        assert!(matches!(
+            pending.response(&event),
+            Some(Ok(BackendEvent::RFDCancelPick))
+        ));
+
+        let event = BackendEvent::Response(other, Box::new(BackendEvent::RFDCancelPick));
+        assert!(pending.response(&event).is_none());
+
+        let event = BackendEvent::RequestFailed(pending.id(), "error".into());
+        assert!(matches!(pending.response(&event), Some(Err("error"))));
+    }
+}
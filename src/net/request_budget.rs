@@ -0,0 +1,155 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use ns_client::Subscription;
+use url::Url;
+
+/// Metadata REQs sent to each relay in the last minute.
+/// Requests over the budget wait in order until the relay has room again,
+/// some relays ban clients that open too many filters.
+#[derive(Debug)]
+pub struct RequestBudget {
+    per_minute: usize,
+    sent: HashMap<Url, VecDeque<Instant>>,
+    queued: VecDeque<(Url, Subscription)>,
+    /// Last state sent to the frontend
+    reported: bool,
+}
+
+impl RequestBudget {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute: per_minute.max(1) as usize,
+            sent: HashMap::new(),
+            queued: VecDeque::new(),
+            reported: false,
+        }
+    }
+
+    pub fn set_per_minute(&mut self, per_minute: u32) {
+        self.per_minute = per_minute.max(1) as usize;
+    }
+
+    /// The subscription when it can be sent right away, otherwise it's queued
+    pub fn request(
+        &mut self,
+        url: &Url,
+        subscription: Subscription,
+        now: Instant,
+    ) -> Option<Subscription> {
+        let is_waiting = self.queued.iter().any(|(queued_url, _)| queued_url == url);
+        if !is_waiting && self.take(url, now) {
+            return Some(subscription);
+        }
+        if self.queued.len() >= MAX_QUEUED {
+            // metadata can be requested again, the oldest is the most likely to be stale
+            if let Some((dropped_url, _)) = self.queued.pop_front() {
+                tracing::warn!("Request budget queue full, dropping REQ to {}", dropped_url);
+            }
+        }
+        self.queued.push_back((url.to_owned(), subscription));
+        None
+    }
+
+    /// Queued requests whose relay has room again, in the order they were made
+    pub fn ready(&mut self, now: Instant) -> Vec<(Url, Subscription)> {
+        let mut ready = vec![];
+        let mut waiting = VecDeque::new();
+        for (url, subscription) in std::mem::take(&mut self.queued) {
+            let is_behind = waiting.iter().any(|(waiting_url, _)| waiting_url == &url);
+            if !is_behind && self.take(&url, now) {
+                ready.push((url, subscription));
+            } else {
+                waiting.push_back((url, subscription));
+            }
+        }
+        self.queued = waiting;
+        ready
+    }
+
+    /// When the first queued request can go, `None` with nothing queued
+    pub fn next_slot(&self) -> Option<Instant> {
+        self.queued
+            .iter()
+            .filter_map(|(url, _)| match self.sent.get(url) {
+                Some(sent) if sent.len() >= self.per_minute => {
+                    sent.front().map(|oldest| *oldest + BUDGET_WINDOW)
+                }
+                // the budget was raised or the relay forgotten
+                _ => Some(Instant::now()),
+            })
+            .min()
+    }
+
+    pub fn is_throttling(&self) -> bool {
+        !self.queued.is_empty()
+    }
+
+    /// The throttling state, only when it changed since the last call
+    pub fn throttling_changed(&mut self) -> Option<bool> {
+        let throttling = self.is_throttling();
+        (throttling != self.reported).then(|| {
+            self.reported = throttling;
+            throttling
+        })
+    }
+
+    pub fn forget_relay(&mut self, url: &Url) {
+        self.sent.remove(url);
+        self.queued.retain(|(queued_url, _)| queued_url != url);
+    }
+
+    /// Counts a request to the relay when the budget allows it
+    fn take(&mut self, url: &Url, now: Instant) -> bool {
+        let sent = self.sent.entry(url.to_owned()).or_default();
+        while let Some(oldest) = sent.front() {
+            if now.saturating_duration_since(*oldest) < BUDGET_WINDOW {
+                break;
+            }
+            sent.pop_front();
+        }
+        if sent.len() >= self.per_minute {
+            return false;
+        }
+        sent.push_back(now);
+        true
+    }
+}
+
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+const MAX_QUEUED: usize = 500;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Filter;
+
+    fn subscription() -> Subscription {
+        Subscription::new(vec![Filter::new()])
+    }
+
+    #[test]
+    fn queues_requests_over_budget() {
+        let mut budget = RequestBudget::new(2);
+        let relay = Url::parse("wss://relay.example.com").unwrap();
+        let other = Url::parse("wss://other.example.com").unwrap();
+        let now = Instant::now();
+
+        assert!(budget.request(&relay, subscription(), now).is_some());
+        assert!(budget.request(&relay, subscription(), now).is_some());
+        assert!(budget.request(&relay, subscription(), now).is_none());
+        // each relay has its own budget
+        assert!(budget.request(&other, subscription(), now).is_some());
+        assert!(budget.is_throttling());
+        assert_eq!(budget.throttling_changed(), Some(true));
+        assert_eq!(budget.throttling_changed(), None);
+        assert_eq!(budget.next_slot(), Some(now + BUDGET_WINDOW));
+
+        assert!(budget.ready(now + Duration::from_secs(30)).is_empty());
+        let ready = budget.ready(now + BUDGET_WINDOW);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].0, relay);
+        assert!(!budget.is_throttling());
+        assert_eq!(budget.throttling_changed(), Some(false));
+    }
+}
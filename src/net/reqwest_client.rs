@@ -5,16 +5,21 @@ use directories::ProjectDirs;
 use futures::TryStreamExt;
 use futures_util::StreamExt;
 use image::io::Reader;
-use image::{DynamicImage, ImageFormat};
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use nostr::nips::nip11::RelayInformationDocument;
+use nostr::secp256k1::XOnlyPublicKey;
 use nostr::{EventId, Url};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::io::Cursor;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use thiserror::Error;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
+use crate::config::{MediaProxyConfig, MEDIA_URL_PLACEHOLDER};
 use crate::consts::{
     APP_PROJECT_DIRS, MEDIUM_PROFILE_IMG_HEIGHT, MEDIUM_PROFILE_IMG_WIDTH,
     SMALL_PROFILE_IMG_HEIGHT, SMALL_PROFILE_IMG_WIDTH,
@@ -70,6 +75,27 @@ pub enum Error {
 
     #[error("Invalid image type: {0}")]
     InvalidImageType(String),
+
+    #[error("Not a name@domain address: {0}")]
+    InvalidNip05(String),
+
+    #[error("{0} is not listed by its domain")]
+    Nip05NotFound(String),
+
+    #[error("Invalid public key for {0}")]
+    Nip05InvalidPubkey(String),
+
+    #[error("Media proxy misconfigured, media is not loaded: {0}")]
+    InvalidMediaProxy(String),
+
+    #[error("Not an LNURL-pay endpoint")]
+    NotLnurlPay,
+
+    #[error("The wallet accepts {min} to {max} sats")]
+    LnurlAmountOutOfRange { min: u64, max: u64 },
+
+    #[error("Invoice refused: {0}")]
+    LnurlRefused(String),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -131,11 +157,85 @@ impl ImageKind {
     }
 }
 
+/// Downloads media directly or through the proxy set in the settings
+#[derive(Debug, Clone)]
+pub struct MediaClient {
+    client: reqwest::Client,
+    route: MediaRoute,
+}
+#[derive(Debug, Clone)]
+enum MediaRoute {
+    /// Directly or through the client's HTTP proxy
+    Client,
+    /// Media address put in this template
+    Rewrite(String),
+    /// Nothing is fetched instead of falling back to a direct connection
+    Invalid(String),
+}
+impl MediaClient {
+    pub fn new(proxy: &MediaProxyConfig) -> Self {
+        match Self::build(proxy) {
+            Ok(media_client) => media_client,
+            Err(e) => Self {
+                client: reqwest::Client::new(),
+                route: MediaRoute::Invalid(e.to_string()),
+            },
+        }
+    }
+    fn build(proxy: &MediaProxyConfig) -> Result<Self, Error> {
+        let address = proxy.address.trim();
+        if !proxy.enabled {
+            return Ok(Self::direct());
+        }
+        if proxy.is_rewrite() {
+            // the template must be a valid url on its own
+            Url::parse(&address.replace(MEDIA_URL_PLACEHOLDER, ""))?;
+            return Ok(Self {
+                client: reqwest::Client::new(),
+                route: MediaRoute::Rewrite(address.to_owned()),
+            });
+        }
+        let client = reqwest::Client::builder()
+            .proxy(reqwest::Proxy::all(Url::parse(address)?)?)
+            .build()?;
+        Ok(Self {
+            client,
+            route: MediaRoute::Client,
+        })
+    }
+    pub fn direct() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            route: MediaRoute::Client,
+        }
+    }
+    pub fn get(&self, media_url: &str) -> Result<reqwest::RequestBuilder, Error> {
+        let media_url = Url::parse(media_url)?;
+        match &self.route {
+            MediaRoute::Client => Ok(self.client.get(media_url)),
+            MediaRoute::Rewrite(template) => {
+                Ok(self.client.get(proxied_url(template, &media_url)?))
+            }
+            MediaRoute::Invalid(reason) => Err(Error::InvalidMediaProxy(reason.to_owned())),
+        }
+    }
+}
+
+/// Template with the percent encoded media address in place of `{url}`
+fn proxied_url(template: &str, media_url: &Url) -> Result<Url, Error> {
+    let encoded: String =
+        url::form_urlencoded::byte_serialize(media_url.as_str().as_bytes()).collect();
+    Ok(Url::parse(
+        &template.replace(MEDIA_URL_PLACEHOLDER, &encoded),
+    )?)
+}
+
 pub fn image_filename(kind: ImageKind, size: ImageSize, image_type: &str) -> String {
     format!("{}_{}.{}", kind.as_str(), size.as_str(), image_type)
 }
 
 pub async fn download_image(
+    media_client: &MediaClient,
     image_url: &str,
     event_hash: &EventId,
     identifier: &str,
@@ -144,7 +244,7 @@ pub async fn download_image(
     if image_url.starts_with("data:image/") {
         parse_base64(image_url, event_hash, identifier, kind).await
     } else {
-        download_image_url(image_url, event_hash, identifier, kind).await
+        download_image_url(media_client, image_url, event_hash, identifier, kind).await
     }
 }
 
@@ -178,24 +278,24 @@ async fn parse_base64(
     let image = reader.decode()?;
 
     image.save_with_format(&original_path, image_format)?;
-    save_dynamic_image(&images_dir, &image, kind, ImageSize::Medium)?;
-    save_dynamic_image(&images_dir, &image, kind, ImageSize::Small)?;
+    save_thumbnails(&images_dir, &image, kind)?;
 
     Ok(ImageDownloaded {
         kind,
         path: original_path,
         event_hash: event_hash.to_owned(),
+        dominant_color: accent_source(&image, kind),
     })
 }
 
 async fn download_image_url(
+    media_client: &MediaClient,
     image_url: &str,
     event_hash: &EventId,
     identifier: &str,
     kind: ImageKind,
 ) -> Result<ImageDownloaded, Error> {
-    let image_url = Url::parse(image_url)?;
-    let response = reqwest::get(image_url.clone()).await?;
+    let response = media_client.get(image_url)?.send().await?;
 
     let content_type = response
         .headers()
@@ -227,48 +327,79 @@ async fn download_image_url(
         dest.write_all(&chunk).await?;
     }
 
-    resize_and_save_image(&original_path, &images_dir, kind, ImageSize::Medium)?;
-    resize_and_save_image(&original_path, &images_dir, kind, ImageSize::Small)?;
+    let image = image::open(&original_path)?;
+    save_thumbnails(&images_dir, &image, kind)?;
 
     Ok(ImageDownloaded {
         kind,
         path: original_path,
         event_hash: event_hash.to_owned(),
+        dominant_color: accent_source(&image, kind),
     })
 }
 
-pub fn save_dynamic_image(
+/// Only profile pictures give their owner an accent color
+fn accent_source(image: &DynamicImage, kind: ImageKind) -> Option<[u8; 3]> {
+    match kind {
+        ImageKind::Profile => dominant_color(image),
+        ImageKind::Banner | ImageKind::Channel => None,
+    }
+}
+
+/// Most common color of the image, grouped in coarse buckets and averaged.
+/// Transparent, grey and almost black or white pixels don't count, pictures
+/// made only of those have no dominant color.
+pub fn dominant_color(image: &DynamicImage) -> Option<[u8; 3]> {
+    let (width, height) = image.dimensions();
+    let sample = if width.max(height) > DOMINANT_COLOR_SAMPLE {
+        image
+            .thumbnail(DOMINANT_COLOR_SAMPLE, DOMINANT_COLOR_SAMPLE)
+            .to_rgba8()
+    } else {
+        image.to_rgba8()
+    };
+    let mut buckets: BTreeMap<(u8, u8, u8), (u32, [u32; 3])> = BTreeMap::new();
+    for pixel in sample.pixels() {
+        let [r, g, b, a] = pixel.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        if a < 128 || max < 40 || min > 215 || max - min < 30 {
+            continue;
+        }
+        let (count, sum) = buckets.entry((r >> 5, g >> 5, b >> 5)).or_default();
+        *count += 1;
+        sum[0] += r as u32;
+        sum[1] += g as u32;
+        sum[2] += b as u32;
+    }
+    buckets
+        .into_values()
+        .max_by_key(|(count, _)| *count)
+        .map(|(count, [r, g, b])| [(r / count) as u8, (g / count) as u8, (b / count) as u8])
+}
+
+/// Medium and small copies of the original, decoded once. The small one is
+/// resized from the medium, lists only ever load that one.
+pub fn save_thumbnails(
     images_dir: &Path,
     image: &DynamicImage,
     kind: ImageKind,
-    size: ImageSize,
 ) -> Result<(), Error> {
-    let output_filename = image_filename(kind, size, "png");
-    tracing::debug!("file: {}", &output_filename);
-    tracing::debug!("resizing: {} - size: {}", kind.as_str(), size.as_str());
-    let output_path = images_dir.join(output_filename);
-    let (width, height) = size
-        .get_width_height()
-        .ok_or(Error::InvalidImageSize(size))?;
-    let resized_image = image.resize(width, height, image::imageops::FilterType::Lanczos3);
-
-    // Save the resized image as a PNG, regardless of the input format
-    resized_image.save_with_format(output_path, ImageFormat::Png)?;
-
+    let medium = save_dynamic_image(images_dir, image, kind, ImageSize::Medium)?;
+    save_dynamic_image(images_dir, &medium, kind, ImageSize::Small)?;
     Ok(())
 }
 
-pub fn resize_and_save_image(
-    original_path: &Path,
+pub fn save_dynamic_image(
     images_dir: &Path,
+    image: &DynamicImage,
     kind: ImageKind,
     size: ImageSize,
-) -> Result<(), Error> {
+) -> Result<DynamicImage, Error> {
     let output_filename = image_filename(kind, size, "png");
     tracing::debug!("file: {}", &output_filename);
     tracing::debug!("resizing: {} - size: {}", kind.as_str(), size.as_str());
     let output_path = images_dir.join(output_filename);
-    let image = image::open(original_path)?;
     let (width, height) = size
         .get_width_height()
         .ok_or(Error::InvalidImageSize(size))?;
@@ -277,7 +408,7 @@ pub fn resize_and_save_image(
     // Save the resized image as a PNG, regardless of the input format
     resized_image.save_with_format(output_path, ImageFormat::Png)?;
 
-    Ok(())
+    Ok(resized_image)
 }
 
 #[derive(Deserialize, Debug)]
@@ -315,7 +446,249 @@ pub async fn fetch_latest_version(client: reqwest::Client) -> Result<String, Err
     Ok(first_release.tag_name.clone())
 }
 
+/// Public key and relays a NIP-05 address points to
+#[derive(Debug, Clone)]
+pub struct Nip05Profile {
+    pub pubkey: XOnlyPublicKey,
+    pub relays: Vec<Url>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Nip05Json {
+    names: HashMap<String, String>,
+    #[serde(default)]
+    relays: HashMap<String, Vec<String>>,
+}
+
+/// Splits `name@domain`, a bare domain means the `_` name
+pub fn split_nip05(address: &str) -> Option<(String, String)> {
+    let address = address.trim().to_lowercase();
+    let (name, domain) = address.split_once('@').unwrap_or(("_", &address));
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    let valid_domain = domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-.:".contains(c));
+    if valid_name && valid_domain {
+        Some((name.to_owned(), domain.to_owned()))
+    } else {
+        None
+    }
+}
+
+pub async fn resolve_nip05(client: reqwest::Client, address: &str) -> Result<Nip05Profile, Error> {
+    let (name, domain) =
+        split_nip05(address).ok_or_else(|| Error::InvalidNip05(address.to_owned()))?;
+    let url = Url::parse(&format!("https://{}/.well-known/nostr.json", domain))?;
+    let response = client
+        .get(url)
+        .query(&[("name", &name)])
+        .timeout(NIP05_TIMEOUT)
+        .send()
+        .await?;
+
+    response.error_for_status_ref()?;
+
+    let json: Nip05Json = response.json().await?;
+    nip05_profile(address, &name, json)
+}
+
+fn nip05_profile(address: &str, name: &str, json: Nip05Json) -> Result<Nip05Profile, Error> {
+    let hex = json
+        .names
+        .get(name)
+        .ok_or_else(|| Error::Nip05NotFound(address.to_owned()))?;
+    let pubkey =
+        XOnlyPublicKey::from_str(hex).map_err(|_| Error::Nip05InvalidPubkey(address.to_owned()))?;
+    // invalid relay urls are skipped, they are only hints
+    let relays = json
+        .relays
+        .get(hex)
+        .map(|urls| urls.iter().filter_map(|url| Url::parse(url).ok()).collect())
+        .unwrap_or_default();
+    Ok(Nip05Profile { pubkey, relays })
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LnurlPayJson {
+    tag: Option<String>,
+    callback: Option<String>,
+    /// millisatoshis
+    min_sendable: Option<u64>,
+    /// millisatoshis
+    max_sendable: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LnurlInvoiceJson {
+    pr: Option<String>,
+    reason: Option<String>,
+}
+
+/// Whether the endpoint answers with an LNURL-pay request
+pub async fn check_lnurl_pay(client: reqwest::Client, endpoint: Url) -> Result<bool, Error> {
+    let response = client.get(endpoint).timeout(LNURL_TIMEOUT).send().await?;
+    if !response.status().is_success() {
+        return Ok(false);
+    }
+    let json: LnurlPayJson = response.json().await?;
+    Ok(json.tag.as_deref() == Some("payRequest") && json.callback.is_some())
+}
+
+/// Asks the LNURL-pay endpoint for a bolt11 invoice of `sats` (LUD-06)
+pub async fn fetch_lnurl_invoice(
+    client: reqwest::Client,
+    endpoint: Url,
+    sats: u64,
+) -> Result<String, Error> {
+    let json: LnurlPayJson = client
+        .get(endpoint)
+        .timeout(LNURL_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let callback = match (json.tag.as_deref(), json.callback) {
+        (Some("payRequest"), Some(callback)) => callback,
+        _ => return Err(Error::NotLnurlPay),
+    };
+    let msats = sats.saturating_mul(1000);
+    let (min, max) = (
+        json.min_sendable.unwrap_or(0),
+        json.max_sendable.unwrap_or(u64::MAX),
+    );
+    if msats < min || msats > max {
+        return Err(Error::LnurlAmountOutOfRange {
+            min: (min + 999) / 1000,
+            max: max / 1000,
+        });
+    }
+
+    let mut callback = Url::parse(&callback)?;
+    callback
+        .query_pairs_mut()
+        .append_pair("amount", &msats.to_string());
+    let json: LnurlInvoiceJson = client
+        .get(callback)
+        .timeout(LNURL_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    match (json.pr, json.reason) {
+        (Some(pr), _) if !pr.is_empty() => Ok(pr),
+        (_, reason) => Err(Error::LnurlRefused(
+            reason.unwrap_or_else(|| "no invoice in the answer".into()),
+        )),
+    }
+}
+
+/// Whether the address answers, servers refusing HEAD requests are asked with GET
+pub async fn check_url_reachable(client: reqwest::Client, url: Url) -> Result<bool, Error> {
+    let response = client
+        .head(url.clone())
+        .timeout(URL_CHECK_TIMEOUT)
+        .send()
+        .await?;
+    if response.status() != reqwest::StatusCode::METHOD_NOT_ALLOWED {
+        return Ok(response.status().is_success());
+    }
+    let response = client.get(url).timeout(URL_CHECK_TIMEOUT).send().await?;
+    Ok(response.status().is_success())
+}
+
+#[derive(Deserialize, Debug)]
+struct RelayDocumentJson {
+    #[serde(default)]
+    supported_nips: Vec<u16>,
+}
+
+/// NIPs listed in the relay's NIP-11 document
+pub async fn fetch_supported_nips(
+    client: reqwest::Client,
+    relay_url: &Url,
+) -> Result<Vec<u16>, Error> {
+    let response = client
+        .get(relay_document_url(relay_url))
+        .header(reqwest::header::ACCEPT, "application/nostr+json")
+        .timeout(RELAY_DOCUMENT_TIMEOUT)
+        .send()
+        .await?;
+
+    response.error_for_status_ref()?;
+
+    let json: RelayDocumentJson = response.json().await?;
+    Ok(json.supported_nips)
+}
+
+/// Answer to a conditional request for the relay's NIP-11 document
+#[derive(Debug, Clone)]
+pub enum RelayDocumentFetch {
+    /// The etag still matches
+    NotModified,
+    Fetched {
+        document: RelayInformationDocument,
+        etag: Option<String>,
+    },
+}
+
+/// Whole NIP-11 document, `etag` of the cached one makes the request conditional
+pub async fn fetch_relay_document(
+    client: reqwest::Client,
+    relay_url: &Url,
+    etag: Option<&str>,
+) -> Result<RelayDocumentFetch, Error> {
+    let mut request = client
+        .get(relay_document_url(relay_url))
+        .header(reqwest::header::ACCEPT, "application/nostr+json")
+        .timeout(RELAY_DOCUMENT_TIMEOUT);
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    let response = request.send().await?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(RelayDocumentFetch::NotModified);
+    }
+    response.error_for_status_ref()?;
+
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .map(|etag| etag.to_str())
+        .transpose()?
+        .map(str::to_owned);
+    let document = response.json().await?;
+    Ok(RelayDocumentFetch::Fetched { document, etag })
+}
+
+/// The document is served over HTTP at the relay's address
+fn relay_document_url(relay_url: &Url) -> Url {
+    let mut url = relay_url.to_owned();
+    let scheme = if relay_url.scheme() == "ws" {
+        "http"
+    } else {
+        "https"
+    };
+    // both are special schemes, the change can't fail
+    _ = url.set_scheme(scheme);
+    url
+}
+
 const IMAGES_FOLDER_NAME: &str = "images";
+/// Side of the thumbnail sampled for the dominant color
+const DOMINANT_COLOR_SAMPLE: u32 = 32;
+const NIP05_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const LNURL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const URL_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const RELAY_DOCUMENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 fn image_type_from_base64(s: &str) -> Option<&str> {
     let parts: Vec<&str> = s.split(';').collect();
@@ -354,4 +727,99 @@ mod tests {
         let base64_image_url = "/9j/4AAQSkZ...";
         assert_eq!(image_type_from_base64(base64_image_url), None);
     }
+
+    #[test]
+    fn test_dominant_color() {
+        let mut image = image::RgbaImage::from_pixel(10, 10, image::Rgba([20, 120, 220, 255]));
+        // a grey border doesn't count
+        for x in 0..10 {
+            image.put_pixel(x, 0, image::Rgba([128, 128, 128, 255]));
+            image.put_pixel(x, 9, image::Rgba([128, 128, 128, 255]));
+        }
+        let image = DynamicImage::ImageRgba8(image);
+        assert_eq!(dominant_color(&image), Some([20, 120, 220]));
+
+        let grey = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            10,
+            10,
+            image::Rgba([128, 128, 128, 255]),
+        ));
+        assert_eq!(dominant_color(&grey), None);
+    }
+
+    #[test]
+    fn test_media_proxy_routes() {
+        let media_url = "https://cdn.example.com/a b.png?size=1";
+
+        let direct = MediaClient::new(&MediaProxyConfig::default());
+        let request = direct.get(media_url).unwrap().build().unwrap();
+        assert_eq!(request.url().host_str(), Some("cdn.example.com"));
+
+        let rewrite = MediaClient::new(&MediaProxyConfig {
+            enabled: true,
+            address: "https://images.example.org/?url={url}".into(),
+        });
+        let request = rewrite.get(media_url).unwrap().build().unwrap();
+        assert_eq!(request.url().host_str(), Some("images.example.org"));
+        assert_eq!(
+            request.url().query(),
+            Some("url=https%3A%2F%2Fcdn.example.com%2Fa%2520b.png%3Fsize%3D1")
+        );
+
+        let invalid = MediaClient::new(&MediaProxyConfig {
+            enabled: true,
+            address: "not a proxy".into(),
+        });
+        assert!(matches!(
+            invalid.get(media_url),
+            Err(Error::InvalidMediaProxy(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_nip05() {
+        assert_eq!(
+            split_nip05(" Bob@Example.com "),
+            Some(("bob".into(), "example.com".into()))
+        );
+        assert_eq!(
+            split_nip05("example.com"),
+            Some(("_".into(), "example.com".into()))
+        );
+        assert_eq!(split_nip05("bob@localhost"), None);
+        assert_eq!(split_nip05("bob smith@example.com"), None);
+        assert_eq!(split_nip05("npub1xyz"), None);
+    }
+
+    #[test]
+    fn test_nip05_profile() {
+        let hex = "b0635d6a9851d3aed0cd6c495b282167acf761729078d975fc341b22650b07b9";
+        let json: Nip05Json = serde_json::from_str(&format!(
+            r#"{{"names":{{"bob":"{hex}"}},"relays":{{"{hex}":["wss://relay.example.com","not a url"]}}}}"#
+        ))
+        .unwrap();
+        let profile = nip05_profile("bob@example.com", "bob", json).unwrap();
+        assert_eq!(profile.pubkey.to_string(), hex);
+        assert_eq!(
+            profile.relays,
+            vec![Url::parse("wss://relay.example.com").unwrap()]
+        );
+
+        let json: Nip05Json = serde_json::from_str(r#"{"names":{}}"#).unwrap();
+        assert!(matches!(
+            nip05_profile("alice@example.com", "alice", json),
+            Err(Error::Nip05NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_relay_document_url() {
+        let url = Url::parse("wss://relay.example.com/path").unwrap();
+        assert_eq!(
+            relay_document_url(&url).as_str(),
+            "https://relay.example.com/path"
+        );
+        let url = Url::parse("ws://localhost:7000").unwrap();
+        assert_eq!(relay_document_url(&url).as_str(), "http://localhost:7000/");
+    }
 }
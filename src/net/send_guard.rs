@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::db::DraftTarget;
+
+/// Last message sent to each conversation, catches the same message sent twice in a row
+#[derive(Debug, Default)]
+pub struct SendGuard {
+    last_sent: HashMap<DraftTarget, (String, Instant)>,
+}
+
+impl SendGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The same content went to the same conversation moments ago
+    pub fn is_duplicate(&self, target: &DraftTarget, content: &str, now: Instant) -> bool {
+        self.last_sent.get(target).map_or(false, |(last, sent_at)| {
+            last == content && now.saturating_duration_since(*sent_at) < DUPLICATE_WINDOW
+        })
+    }
+
+    pub fn sent(&mut self, target: DraftTarget, content: &str, now: Instant) {
+        self.last_sent
+            .retain(|_, (_, sent_at)| now.saturating_duration_since(*sent_at) < DUPLICATE_WINDOW);
+        self.last_sent.insert(target, (content.to_owned(), now));
+    }
+}
+
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Keys;
+
+    #[test]
+    fn flags_same_content_to_same_conversation() {
+        let mut guard = SendGuard::new();
+        let contact = DraftTarget::Contact(Keys::generate().public_key());
+        let other = DraftTarget::Contact(Keys::generate().public_key());
+        let now = Instant::now();
+
+        assert!(!guard.is_duplicate(&contact, "hello", now));
+        guard.sent(contact, "hello", now);
+
+        let soon = now + Duration::from_millis(500);
+        assert!(guard.is_duplicate(&contact, "hello", soon));
+        assert!(!guard.is_duplicate(&contact, "hello again", soon));
+        assert!(!guard.is_duplicate(&other, "hello", soon));
+        assert!(!guard.is_duplicate(&contact, "hello", now + DUPLICATE_WINDOW));
+    }
+}
@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use nostr::EventId;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use url::Url;
+
+/// Artificial network conditions, only applied in dev mode
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Simulation {
+    /// Delay before each frontend command is processed
+    pub latency_ms: u64,
+    /// Percentage of outgoing events rejected by every relay
+    pub failure_rate: u8,
+    /// The same seed fails the same sends
+    pub seed: u64,
+    /// Outgoing events are held as pending until this is turned off
+    pub offline: bool,
+    /// Outgoing events are signed and logged but never sent
+    pub dry_run: bool,
+}
+impl Simulation {
+    pub fn latency(&self) -> Option<Duration> {
+        (self.latency_ms > 0).then(|| Duration::from_millis(self.latency_ms))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outgoing {
+    Send,
+    Hold,
+    Fail,
+    /// Kept from the relays, see `Simulation::dry_run`
+    DryRun,
+}
+
+/// Event kept from the relays by the dry run
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    pub ns_event: nostr::Event,
+    /// Where it would have been sent
+    pub relays: Vec<Url>,
+}
+
+pub struct SimulatedNetwork {
+    settings: Simulation,
+    rng: StdRng,
+    held: Vec<nostr::Event>,
+    failed: Vec<EventId>,
+    /// Oldest first, the last `MAX_DRY_RUNS`
+    dry_runs: Vec<nostr::Event>,
+}
+impl SimulatedNetwork {
+    pub fn new() -> Self {
+        Self {
+            settings: Simulation::default(),
+            rng: StdRng::seed_from_u64(0),
+            held: vec![],
+            failed: vec![],
+            dry_runs: vec![],
+        }
+    }
+    pub fn settings(&self) -> &Simulation {
+        &self.settings
+    }
+    pub fn latency(&self) -> Option<Duration> {
+        self.settings.latency()
+    }
+    /// Restarts the failure draws, returns the held events when going back online
+    pub fn set(&mut self, settings: Simulation) -> Vec<nostr::Event> {
+        self.rng = StdRng::seed_from_u64(settings.seed);
+        self.settings = settings;
+        if self.settings.offline {
+            vec![]
+        } else {
+            std::mem::take(&mut self.held)
+        }
+    }
+    /// Decides what happens to an event about to be sent
+    pub fn outgoing(&mut self, ns_event: &nostr::Event) -> Outgoing {
+        let failure_rate = self.settings.failure_rate.min(100) as u32;
+        if self.dry_run(ns_event) {
+            Outgoing::DryRun
+        } else if self.settings.offline {
+            self.held.push(ns_event.clone());
+            Outgoing::Hold
+        } else if failure_rate > 0 && self.rng.gen_range(0..100) < failure_rate {
+            self.failed.push(ns_event.id);
+            Outgoing::Fail
+        } else {
+            Outgoing::Send
+        }
+    }
+    /// Keeps the event when dry running, for sends that skip the other conditions
+    pub fn dry_run(&mut self, ns_event: &nostr::Event) -> bool {
+        if !self.settings.dry_run {
+            return false;
+        }
+        tracing::info!("Dry run, not sent: {}", ns_event.as_json());
+        if self.dry_runs.len() >= MAX_DRY_RUNS {
+            self.dry_runs.remove(0);
+        }
+        self.dry_runs.push(ns_event.clone());
+        true
+    }
+    /// Newest first
+    pub fn dry_runs(&self) -> impl Iterator<Item = &nostr::Event> {
+        self.dry_runs.iter().rev()
+    }
+    /// Events that must be answered with a rejection
+    pub fn take_failed(&mut self) -> Vec<EventId> {
+        std::mem::take(&mut self.failed)
+    }
+}
+
+/// Set with `debug_assertions` or the `NOSTRTALK_DEV` variable
+pub fn dev_mode() -> bool {
+    cfg!(debug_assertions) || std::env::var(DEV_MODE_VAR).is_ok()
+}
+
+pub const SIMULATED_FAILURE: &str = "simulated failure";
+const DEV_MODE_VAR: &str = "NOSTRTALK_DEV";
+const MAX_DRY_RUNS: usize = 50;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys};
+
+    fn events(count: usize) -> Vec<nostr::Event> {
+        let keys = Keys::generate();
+        (0..count)
+            .map(|n| {
+                EventBuilder::new_text_note(n.to_string(), &[])
+                    .to_event(&keys)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_fails_same_sends() {
+        let events = events(50);
+        let settings = Simulation {
+            failure_rate: 30,
+            seed: 7,
+            ..Default::default()
+        };
+        let run = |network: &mut SimulatedNetwork| {
+            network.set(settings.clone());
+            events
+                .iter()
+                .map(|e| network.outgoing(e))
+                .collect::<Vec<_>>()
+        };
+        let first = run(&mut SimulatedNetwork::new());
+        let second = run(&mut SimulatedNetwork::new());
+        assert_eq!(first, second);
+        assert!(first.contains(&Outgoing::Fail));
+        assert!(first.contains(&Outgoing::Send));
+    }
+
+    #[test]
+    fn offline_holds_until_back_online() {
+        let events = events(3);
+        let mut network = SimulatedNetwork::new();
+        network.set(Simulation {
+            offline: true,
+            ..Default::default()
+        });
+        for e in &events {
+            assert_eq!(network.outgoing(e), Outgoing::Hold);
+        }
+        let released = network.set(Simulation::default());
+        assert_eq!(released.len(), 3);
+        assert_eq!(network.outgoing(&events[0]), Outgoing::Send);
+        assert!(network.take_failed().is_empty());
+    }
+
+    #[test]
+    fn dry_run_keeps_everything_from_relays() {
+        let events = events(MAX_DRY_RUNS + 2);
+        let mut network = SimulatedNetwork::new();
+        network.set(Simulation {
+            dry_run: true,
+            offline: true,
+            failure_rate: 100,
+            ..Default::default()
+        });
+        for e in &events {
+            assert_eq!(network.outgoing(e), Outgoing::DryRun);
+        }
+        assert_eq!(network.dry_runs().count(), MAX_DRY_RUNS);
+        assert_eq!(
+            network.dry_runs().next().map(|e| e.id),
+            events.last().map(|e| e.id)
+        );
+        assert!(network.take_failed().is_empty());
+        assert!(network.set(Simulation::default()).is_empty());
+        assert_eq!(network.outgoing(&events[0]), Outgoing::Send);
+    }
+}
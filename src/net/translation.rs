@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config::TranslationConfig;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Request error: {0}")]
+    FromReqwest(#[from] reqwest::Error),
+
+    #[error("Translation endpoint not configured")]
+    NotConfigured,
+
+    #[error("Translation provider error: {0}")]
+    Provider(String),
+}
+
+#[derive(Serialize)]
+struct TranslateRequest<'a> {
+    q: &'a str,
+    source: &'a str,
+    target: &'a str,
+    format: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    translated_text: Option<String>,
+    detected_language: Option<DetectedLanguage>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DetectedLanguage {
+    language: String,
+}
+
+/// Translates `text` with a LibreTranslate compatible provider.
+/// Returns the translated text and the detected source language.
+pub async fn translate(
+    client: reqwest::Client,
+    config: &TranslationConfig,
+    text: &str,
+) -> Result<(String, Option<String>), Error> {
+    let endpoint = config
+        .endpoint
+        .as_deref()
+        .filter(|e| !e.trim().is_empty())
+        .ok_or(Error::NotConfigured)?;
+
+    let body = TranslateRequest {
+        q: text,
+        source: AUTO_DETECT,
+        target: &config.target_lang,
+        format: "text",
+        api_key: config.api_key.as_deref().filter(|k| !k.is_empty()),
+    };
+
+    let response: TranslateResponse = client
+        .post(endpoint)
+        .json(&body)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    parse_response(response)
+}
+
+fn parse_response(response: TranslateResponse) -> Result<(String, Option<String>), Error> {
+    match (response.translated_text, response.error) {
+        (Some(translated), _) => Ok((translated, response.detected_language.map(|d| d.language))),
+        (None, Some(error)) => Err(Error::Provider(error)),
+        (None, None) => Err(Error::Provider("empty response".into())),
+    }
+}
+
+const AUTO_DETECT: &str = "auto";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_translation_and_detected_language() {
+        let json =
+            r#"{"translatedText":"Hello","detectedLanguage":{"confidence":90,"language":"pt"}}"#;
+        let response: TranslateResponse = serde_json::from_str(json).unwrap();
+        let (content, source) = parse_response(response).unwrap();
+        assert_eq!(content, "Hello");
+        assert_eq!(source.as_deref(), Some("pt"));
+    }
+
+    #[test]
+    fn provider_error_is_reported() {
+        let json = r#"{"error":"Invalid API key"}"#;
+        let response: TranslateResponse = serde_json::from_str(json).unwrap();
+        assert!(matches!(parse_response(response), Err(Error::Provider(_))));
+    }
+
+    #[tokio::test]
+    async fn requires_endpoint() {
+        let config = TranslationConfig::default();
+        let result = translate(reqwest::Client::new(), &config, "Olá").await;
+        assert!(matches!(result, Err(Error::NotConfigured)));
+    }
+}
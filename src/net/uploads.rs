@@ -0,0 +1,284 @@
+//! Files attached from the composer, uploaded to the NIP-96 media server.
+//!
+//! The file is read, checked against the size limit and turned into a request
+//! body once. A failed transfer keeps that body so resuming skips straight to
+//! sending it, NIP-96 servers have no way to take the rest of a partial body.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use nostr::Keys;
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio::sync::mpsc::Sender;
+use tokio::task::AbortHandle;
+
+use super::image_sanitize::sanitize_image_file;
+use super::nip96::{self, prepare_upload, send_upload, PreparedUpload};
+use super::TaskOutput;
+use crate::config::{AttachmentConfig, Config};
+use crate::db::DraftTarget;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O Error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    FromNip96(#[from] nip96::Error),
+
+    #[error("{0}")]
+    FromConfig(#[from] crate::config::Error),
+
+    #[error("{0}")]
+    FromImageSanitize(#[from] super::image_sanitize::Error),
+
+    #[error("The file is {size} MB, attachments are limited to {limit} MB")]
+    TooLarge { size: u64, limit: u64 },
+}
+impl Error {
+    /// Trying again can't help a file over the limit
+    fn is_resumable(&self) -> bool {
+        !matches!(self, Error::TooLarge { .. })
+    }
+}
+
+/// Where an upload started from the composer is at
+#[derive(Debug, Clone)]
+pub struct UploadStatus {
+    pub id: u64,
+    /// Conversation whose composer shows it
+    pub target: DraftTarget,
+    pub name: String,
+    pub state: UploadState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadState {
+    /// Body bytes handed to the connection
+    Sending {
+        sent: u64,
+        total: u64,
+    },
+    /// `resumable` when the upload was kept to be tried again
+    Failed {
+        error: String,
+        resumable: bool,
+    },
+    /// Public address of the file
+    Uploaded(String),
+    Cancelled,
+}
+
+/// Upload the backend holds until it's done or cancelled
+pub struct Upload {
+    pub target: DraftTarget,
+    pub name: String,
+    path: PathBuf,
+    /// Images are sent as they are for this file, whatever the settings say
+    keep_metadata: bool,
+    /// Body ready to be sent, once the file was read
+    prepared: Option<Arc<PreparedUpload>>,
+    task: Option<AbortHandle>,
+}
+impl Upload {
+    pub fn new(target: DraftTarget, path: PathBuf, keep_metadata: bool) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| DEFAULT_FILE_NAME.to_owned());
+        Self {
+            target,
+            name,
+            path,
+            keep_metadata,
+            prepared: None,
+            task: None,
+        }
+    }
+    pub fn status(&self, id: u64, state: UploadState) -> UploadStatus {
+        UploadStatus {
+            id,
+            target: self.target,
+            name: self.name.clone(),
+            state,
+        }
+    }
+    pub fn set_prepared(&mut self, prepared: Arc<PreparedUpload>) {
+        self.prepared = Some(prepared);
+    }
+    /// Stops the transfer, what the server got so far is dropped
+    pub fn abort(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// Prepares the body unless a previous attempt did, then sends it.
+    /// Progress and the result come back as [`TaskOutput::Upload`]
+    pub fn spawn(
+        &mut self,
+        id: u64,
+        req_client: reqwest::Client,
+        pool: SqlitePool,
+        keys: Keys,
+        sender: Sender<Result<TaskOutput, crate::Error>>,
+    ) {
+        self.abort();
+        let path = self.path.clone();
+        let name = self.name.clone();
+        let keep_metadata = self.keep_metadata;
+        let prepared = self.prepared.clone();
+        let handle = tokio::spawn(async move {
+            let result = upload(
+                id,
+                &path,
+                &name,
+                keep_metadata,
+                prepared,
+                &req_client,
+                &pool,
+                &keys,
+                &sender,
+            )
+            .await;
+            let state = match result {
+                Ok(url) => UploadState::Uploaded(url),
+                Err(e) => {
+                    tracing::error!("Failed to upload {}: {}", path.display(), e);
+                    UploadState::Failed {
+                        error: e.to_string(),
+                        resumable: e.is_resumable(),
+                    }
+                }
+            };
+            _ = sender.send(Ok(TaskOutput::Upload(id, state))).await;
+        });
+        self.task = Some(handle.abort_handle());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn upload(
+    id: u64,
+    path: &Path,
+    name: &str,
+    keep_metadata: bool,
+    prepared: Option<Arc<PreparedUpload>>,
+    req_client: &reqwest::Client,
+    pool: &SqlitePool,
+    keys: &Keys,
+    sender: &Sender<Result<TaskOutput, crate::Error>>,
+) -> Result<String, Error> {
+    let prepared = match prepared {
+        Some(prepared) => prepared,
+        None => {
+            let mut options = Config::load_file_async().await?.attachments;
+            if keep_metadata {
+                options.strip_metadata = false;
+            }
+            let prepared = Arc::new(prepare(req_client, path, name, &options).await?);
+            _ = sender
+                .send(Ok(TaskOutput::UploadPrepared(id, prepared.clone())))
+                .await;
+            prepared
+        }
+    };
+
+    let progress_tx = sender.clone();
+    let mut last_percent = 0;
+    let url = send_upload(req_client, pool, keys, &prepared, move |sent, total| {
+        let percent = sent * 100 / total.max(1);
+        if percent != last_percent {
+            last_percent = percent;
+            // dropped when the backend is busy, the next chunk reports again
+            _ = progress_tx.try_send(Ok(TaskOutput::Upload(
+                id,
+                UploadState::Sending { sent, total },
+            )));
+        }
+    })
+    .await?;
+    Ok(url)
+}
+
+/// Reads the file once it's known to be under the limit, images are
+/// sanitized like the profile pictures
+async fn prepare(
+    req_client: &reqwest::Client,
+    path: &Path,
+    name: &str,
+    options: &AttachmentConfig,
+) -> Result<PreparedUpload, Error> {
+    let size = tokio::fs::metadata(path).await?.len();
+    check_size(size, options)?;
+
+    let (bytes, mime) = if is_image(path) {
+        let image = sanitize_image_file(path.to_owned(), options.clone()).await?;
+        (image.bytes, image.mime)
+    } else {
+        (tokio::fs::read(path).await?, mime_of(path))
+    };
+    Ok(prepare_upload(req_client, &options.upload_server, name, mime, &bytes).await?)
+}
+
+fn check_size(size: u64, options: &AttachmentConfig) -> Result<(), Error> {
+    if size > options.max_upload_bytes() {
+        return Err(Error::TooLarge {
+            size: size.div_ceil(BYTES_PER_MB),
+            limit: options.max_upload_mb,
+        });
+    }
+    Ok(())
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn is_image(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "png" | "jpg" | "jpeg" | "webp")
+}
+
+/// Type sent with the file, servers guess from the content when it's generic
+fn mime_of(path: &Path) -> &'static str {
+    match extension(path).as_str() {
+        "gif" => "image/gif",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+const DEFAULT_FILE_NAME: &str = "attachment";
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_files_over_the_limit() {
+        let options = AttachmentConfig {
+            max_upload_mb: 2,
+            ..AttachmentConfig::default()
+        };
+        assert!(check_size(2 * BYTES_PER_MB, &options).is_ok());
+        let error = check_size(2 * BYTES_PER_MB + 1, &options).unwrap_err();
+        assert!(matches!(error, Error::TooLarge { size: 3, limit: 2 }));
+        assert!(!error.is_resumable());
+
+        assert_eq!(mime_of(Path::new("clip.MP4")), "video/mp4");
+        assert_eq!(mime_of(Path::new("notes")), "application/octet-stream");
+        assert!(is_image(Path::new("/tmp/photo.JPG")));
+        assert!(!is_image(Path::new("/tmp/anim.gif")));
+    }
+}
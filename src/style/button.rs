@@ -14,6 +14,8 @@ pub enum Button {
     Invisible,
     ContactCard,
     ActiveContactCard,
+    /// Contact card picked with the keyboard in the search results
+    SelectedContactCard,
     ActiveMenuBtn,
     MenuBtn,
     StatusBarButton,
@@ -86,6 +88,11 @@ impl button::StyleSheet for Theme {
                     .into(),
                 ..contact_card
             },
+            Button::SelectedContactCard => button::Appearance {
+                border_color: self.palette().normal.primary,
+                border_width: 1.0,
+                ..contact_card
+            },
 
             Button::MenuBtn => menu,
             Button::ActiveMenuBtn => button::Appearance {
@@ -167,6 +174,7 @@ impl button::StyleSheet for Theme {
             },
             Button::ContactCard => changed,
             Button::ActiveContactCard => changed,
+            Button::SelectedContactCard => changed,
             Button::MenuBtn => changed,
             Button::ActiveMenuBtn => changed,
             Button::StatusBarButton => changed,
@@ -203,6 +211,7 @@ impl button::StyleSheet for Theme {
             Button::Bordered => self.active(style),
             Button::ContactCard => changed,
             Button::ActiveContactCard => changed,
+            Button::SelectedContactCard => changed,
             Button::MenuBtn => changed,
             Button::HighlightButton => changed,
             Button::ActiveMenuBtn => changed,
@@ -1,4 +1,4 @@
-use crate::utils::{change_color_by_type, darken_color, lighten_color};
+use crate::utils::{change_color_by_type, darken_color, lighten_color, readable_accent};
 
 use super::Theme;
 use iced::widget::container;
@@ -15,7 +15,11 @@ pub enum Container {
     CardBody,
     Frame,
     SentMessage,
+    /// Sent message not accepted by a relay yet
+    PendingMessage,
     ReceivedMessage,
+    /// Received message bordered with the author's accent
+    AccentMessage(Color),
     ChatDateDivider,
     StatusBar,
     TooltipBg,
@@ -91,11 +95,32 @@ impl container::StyleSheet for Theme {
                 border_radius: 10.0,
                 ..def
             },
+            Container::PendingMessage => container::Appearance {
+                background: Color {
+                    a: PENDING_ALPHA,
+                    ..self.palette().normal.primary_variant
+                }
+                .into(),
+                text_color: Color {
+                    a: PENDING_ALPHA,
+                    ..self.palette().base.text
+                }
+                .into(),
+                border_radius: 10.0,
+                ..def
+            },
             Container::ReceivedMessage => container::Appearance {
                 background: self.palette().base.foreground.into(),
                 border_radius: 10.0,
                 ..def
             },
+            Container::AccentMessage(accent) => container::Appearance {
+                background: self.palette().base.foreground.into(),
+                border_color: readable_accent(accent, self.palette().base.foreground, theme_type),
+                border_width: 1.5,
+                border_radius: 10.0,
+                ..def
+            },
             Container::ChatDateDivider => container::Appearance {
                 background: self.palette().base.foreground.into(),
                 text_color: self.palette().base.text.into(),
@@ -143,3 +168,6 @@ impl container::StyleSheet for Theme {
         }
     }
 }
+
+/// Opacity of a sent message until a relay accepts it
+const PENDING_ALPHA: f32 = 0.6;
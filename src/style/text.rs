@@ -1,4 +1,5 @@
 use super::{ColorPalette, Theme};
+use crate::utils::readable_accent;
 use iced::widget::text;
 use iced::Color;
 use ns_client::RelayStatus;
@@ -13,6 +14,8 @@ pub enum Text {
     Danger,
     Placeholder,
     Color(Color),
+    /// Author's accent, adjusted to be readable over the messages background
+    Accent(Color),
     Alpha(f32),
     RelayStatus(Option<RelayStatus>),
 }
@@ -63,6 +66,14 @@ impl text::StyleSheet for Theme {
                 color: self.palette().base.comment.into(),
             },
             Text::Color(c) => text::Appearance { color: Some(c) },
+            Text::Accent(c) => text::Appearance {
+                color: readable_accent(
+                    c,
+                    self.palette().base.foreground,
+                    self.theme_meta().theme_type,
+                )
+                .into(),
+            },
             Text::Alpha(a) => {
                 let mut color = self.palette().base.text;
                 color.a = a;
@@ -0,0 +1,335 @@
+//! Synthetic accounts written straight into the databases, for the
+//! integration tests and the benchmarks.
+//!
+//! The events are real: DMs are encrypted between the user and each contact
+//! and every event is signed, so the code reading them back is the same as for
+//! an account synced from relays. Messages are one `interval` apart, oldest
+//! first, and the newest is a little before the time of the generation.
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventBuilder, EventId, Keys};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use thiserror::Error;
+
+use crate::db::{ChannelCache, ChannelSubscription, MessageStatus};
+use crate::types::ChannelMetadata;
+use crate::utils::{
+    channel_creation_builder, channel_msg_builder, dm_builder, naive_to_event_tt,
+    ns_event_to_millis,
+};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Sqlx error: {0}")]
+    Sqlx(#[from] sqlx::Error),
+
+    #[error("Serde JSON error: {0}")]
+    SerdeJson(#[from] serde_json::Error),
+
+    #[error("Signing error: {0}")]
+    SigningEvent(String),
+
+    #[error("{0}")]
+    FromUtils(#[from] crate::utils::Error),
+
+    #[error("{0}")]
+    FromChannelCache(#[from] crate::db::channel_cache::Error),
+
+    #[error("{0}")]
+    FromChannelSubscription(#[from] crate::db::channel_subscription::Error),
+}
+
+/// Size of the generated account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountSpec {
+    pub contacts: usize,
+    /// In each conversation, sent and received in turns
+    pub messages_per_contact: usize,
+    /// Subscribed channels
+    pub channels: usize,
+    /// Members of each channel besides the user
+    pub members_per_channel: usize,
+    /// In each channel, the user writes one of every `members_per_channel + 1`
+    pub messages_per_channel: usize,
+    /// Between two messages of a conversation or channel
+    pub interval: Duration,
+}
+impl Default for AccountSpec {
+    fn default() -> Self {
+        Self {
+            contacts: 20,
+            messages_per_contact: 50,
+            channels: 3,
+            members_per_channel: 10,
+            messages_per_channel: 100,
+            interval: Duration::minutes(1),
+        }
+    }
+}
+impl AccountSpec {
+    pub fn dm_count(&self) -> usize {
+        self.contacts * self.messages_per_contact
+    }
+    pub fn channel_message_count(&self) -> usize {
+        self.channels * self.messages_per_channel
+    }
+}
+
+/// What was generated, to check the results against
+pub struct SyntheticAccount {
+    pub keys: Keys,
+    pub contacts: Vec<XOnlyPublicKey>,
+    pub channels: Vec<EventId>,
+    /// Time of the newest message
+    pub newest: NaiveDateTime,
+}
+
+/// Writes an account of `spec` into the migrated databases. Each conversation
+/// and channel is written in one transaction
+pub async fn generate(
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+    spec: &AccountSpec,
+) -> Result<SyntheticAccount, Error> {
+    let keys = Keys::generate();
+    // events only keep whole seconds
+    let newest = NaiveDateTime::from_timestamp_opt(Utc::now().timestamp() - 1, 0)
+        .expect("current time is in range");
+
+    let mut contacts = vec![];
+    for _ in 0..spec.contacts {
+        let contact_keys = Keys::generate();
+        insert_conversation(pool, &keys, &contact_keys, spec, newest).await?;
+        contacts.push(contact_keys.public_key());
+    }
+
+    let mut channels = vec![];
+    for number in 0..spec.channels {
+        let channel_id = insert_channel(pool, cache_pool, &keys, spec, newest, number).await?;
+        channels.push(channel_id);
+    }
+
+    Ok(SyntheticAccount {
+        keys,
+        contacts,
+        channels,
+        newest,
+    })
+}
+
+/// Content of the `n`th message, every topic comes up as often
+pub fn message_content(n: usize) -> String {
+    format!("synthetic message {} about {}", n, TOPICS[n % TOPICS.len()])
+}
+
+async fn insert_conversation(
+    pool: &SqlitePool,
+    keys: &Keys,
+    contact_keys: &Keys,
+    spec: &AccountSpec,
+    newest: NaiveDateTime,
+) -> Result<(), Error> {
+    let contact = contact_keys.public_key();
+    let mut tx = pool.begin().await?;
+    for n in 0..spec.messages_per_contact {
+        let content = message_content(n);
+        let is_users = n % 2 == 0;
+        let (sender, receiver) = if is_users {
+            (keys, contact)
+        } else {
+            (contact_keys, keys.public_key())
+        };
+        let created_at = message_time(newest, spec, n, spec.messages_per_contact);
+        let builder = dm_builder(sender, &receiver, None, &content)?;
+        let ns_event = sign_at(sender, builder, created_at)?;
+        let event_id = insert_event(&mut tx, &ns_event).await?;
+
+        // only the newest received message is left unseen
+        let is_last = n + 1 == spec.messages_per_contact;
+        let status = if is_last && !is_users {
+            MessageStatus::Delivered
+        } else {
+            MessageStatus::Seen
+        };
+        let sql = r#"
+            INSERT INTO message
+            (event_id, content, chat_pubkey, is_users, created_at, status, relay_url)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);
+        "#;
+        sqlx::query(sql)
+            .bind(event_id)
+            .bind(&ns_event.content)
+            .bind(contact.to_string())
+            .bind(is_users)
+            .bind(created_at.timestamp_millis())
+            .bind(status.to_i32())
+            .bind(FIXTURE_RELAY)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    let created_at = newest - spec.interval * spec.messages_per_contact as i32;
+    let sql = "INSERT INTO contact (pubkey, created_at, updated_at) VALUES (?1, ?2, ?2);";
+    sqlx::query(sql)
+        .bind(contact.to_string())
+        .bind(created_at.timestamp_millis())
+        .execute(&mut tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn insert_channel(
+    pool: &SqlitePool,
+    cache_pool: &SqlitePool,
+    keys: &Keys,
+    spec: &AccountSpec,
+    newest: NaiveDateTime,
+    number: usize,
+) -> Result<EventId, Error> {
+    let members: Vec<Keys> = (0..spec.members_per_channel)
+        .map(|_| Keys::generate())
+        .collect();
+    let creator = members.first().unwrap_or(keys);
+    let metadata = ChannelMetadata::new()
+        .name(format!("Synthetic channel {}", number))
+        .about("Generated for tests and benchmarks");
+    let created_at = newest - spec.interval * (spec.messages_per_channel as i32 + 1);
+    let creation = sign_at(creator, channel_creation_builder(&metadata), created_at)?;
+    let channel_id = creation.id;
+
+    let mut tx = pool.begin().await?;
+    insert_event(&mut tx, &creation).await?;
+    for n in 0..spec.messages_per_channel {
+        // the user is the last of the authors
+        let author = members.get(n % (members.len() + 1)).unwrap_or(keys);
+        let is_users = author.public_key() == keys.public_key();
+        let content = message_content(n);
+        let created_at = message_time(newest, spec, n, spec.messages_per_channel);
+        let builder = channel_msg_builder(&channel_id, None, &content, false);
+        let ns_event = sign_at(author, builder, created_at)?;
+        let event_id = insert_event(&mut tx, &ns_event).await?;
+
+        let sql = r#"
+            INSERT INTO channel_message (
+                event_id, channel_id, author, is_users, created_at, relay_url, content
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);
+        "#;
+        sqlx::query(sql)
+            .bind(event_id)
+            .bind(channel_id.to_string())
+            .bind(author.public_key().to_string())
+            .bind(is_users)
+            .bind(created_at.timestamp_millis())
+            .bind(FIXTURE_RELAY)
+            .bind(&content)
+            .execute(&mut tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    ChannelCache::fetch_insert(cache_pool, &creation).await?;
+    ChannelCache::insert_member(cache_pool, &channel_id, &keys.public_key()).await?;
+    for member in &members {
+        ChannelCache::insert_member(cache_pool, &channel_id, &member.public_key()).await?;
+    }
+    ChannelSubscription::insert(pool, &channel_id).await?;
+    Ok(channel_id)
+}
+
+/// Time of the `n`th of `count` messages, the last one is `newest`
+fn message_time(
+    newest: NaiveDateTime,
+    spec: &AccountSpec,
+    n: usize,
+    count: usize,
+) -> NaiveDateTime {
+    newest - spec.interval * (count - 1 - n) as i32
+}
+
+/// Signed with `created_at` instead of the current time
+fn sign_at(
+    keys: &Keys,
+    builder: EventBuilder,
+    created_at: NaiveDateTime,
+) -> Result<nostr::Event, Error> {
+    let mut ns_event = builder.to_unsigned_event(keys.public_key());
+    ns_event.created_at = naive_to_event_tt(created_at);
+    ns_event.id = EventId::new(
+        &keys.public_key(),
+        ns_event.created_at,
+        &ns_event.kind,
+        &ns_event.tags,
+        &ns_event.content,
+    );
+    ns_event
+        .sign(keys)
+        .map_err(|e| Error::SigningEvent(e.to_string()))
+}
+
+async fn insert_event(
+    tx: &mut Transaction<'_, Sqlite>,
+    ns_event: &nostr::Event,
+) -> Result<i64, Error> {
+    let sql = r#"
+        INSERT INTO event
+            (event_hash, pubkey, kind, content, sig, tags, relay_url, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+    "#;
+    let inserted = sqlx::query(sql)
+        .bind(ns_event.id.to_string())
+        .bind(ns_event.pubkey.to_string())
+        .bind(ns_event.kind.as_u32())
+        .bind(&ns_event.content)
+        .bind(ns_event.sig.to_string())
+        .bind(serde_json::to_string(&ns_event.tags)?)
+        .bind(FIXTURE_RELAY)
+        .bind(ns_event_to_millis(ns_event.created_at))
+        .execute(&mut *tx)
+        .await?;
+    Ok(inserted.last_insert_rowid())
+}
+
+/// Relay every generated event came from
+pub const FIXTURE_RELAY: &str = "wss://fixtures.relay";
+pub const TOPICS: [&str; 5] = ["bitcoin", "relays", "gardening", "music", "weather"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DbChannelMessage, DbContact, DbMessage, TestDatabase};
+
+    #[tokio::test]
+    async fn generated_account_reads_back() {
+        let db = TestDatabase::new().await.unwrap();
+        let (pool, cache_pool) = (db.pool(), db.cache_pool());
+
+        let spec = AccountSpec {
+            contacts: 3,
+            messages_per_contact: 4,
+            channels: 2,
+            members_per_channel: 2,
+            messages_per_channel: 6,
+            interval: Duration::seconds(30),
+        };
+        let account = generate(pool, cache_pool, &spec).await.unwrap();
+
+        assert_eq!(DbContact::fetch(pool).await.unwrap().len(), 3);
+        let chat = DbMessage::fetch_chat(pool, &account.contacts[0])
+            .await
+            .unwrap();
+        assert_eq!(chat.len(), 4);
+        assert_eq!(chat[0].created_at, account.newest);
+        assert_eq!(chat.iter().filter(|m| m.is_users).count(), 2);
+
+        assert_eq!(ChannelSubscription::fetch(pool).await.unwrap().len(), 2);
+        let channel_messages = DbChannelMessage::fetch(pool, &account.channels[1])
+            .await
+            .unwrap();
+        assert_eq!(channel_messages.len(), 6);
+        assert_eq!(channel_messages.iter().filter(|m| m.is_users).count(), 2);
+        assert_eq!(channel_messages[0].content, message_content(0));
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use nostr::{EventId, Kind, Tag, Timestamp};
+
+/// What the relays keep of an existing account, asked before the first sync.
+/// Direct messages are sampled, so past the sample the count is a lower bound.
+#[derive(Debug, Clone, Default)]
+pub struct AccountPreview {
+    /// Name of the newest profile, `None` without profile or name
+    pub profile_name: Option<String>,
+    pub has_profile: bool,
+    profile_at: Option<Timestamp>,
+    /// Contacts of the newest contact list
+    pub contacts: Option<usize>,
+    contacts_at: Option<Timestamp>,
+    sent: HashSet<EventId>,
+    received: HashSet<EventId>,
+    /// Relays that finished sending their stored events
+    pub relays_done: usize,
+}
+
+impl AccountPreview {
+    /// Every relay sends its copies, each event counts once
+    pub fn add_event(&mut self, ns_event: &nostr::Event, user: &nostr::secp256k1::XOnlyPublicKey) {
+        match ns_event.kind {
+            Kind::Metadata if is_newer(self.profile_at, ns_event.created_at) => {
+                self.profile_at = Some(ns_event.created_at);
+                self.has_profile = true;
+                self.profile_name = nostr::Metadata::from_json(&ns_event.content)
+                    .ok()
+                    .and_then(|metadata| metadata.display_name.or(metadata.name))
+                    .filter(|name| !name.trim().is_empty());
+            }
+            Kind::ContactList if is_newer(self.contacts_at, ns_event.created_at) => {
+                self.contacts_at = Some(ns_event.created_at);
+                let contacts = ns_event
+                    .tags
+                    .iter()
+                    .filter(|tag| matches!(tag, Tag::PubKey(..) | Tag::ContactList { .. }))
+                    .count();
+                self.contacts = Some(contacts);
+            }
+            Kind::EncryptedDirectMessage => {
+                if &ns_event.pubkey == user {
+                    self.sent.insert(ns_event.id);
+                } else {
+                    self.received.insert(ns_event.id);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn messages(&self) -> usize {
+        self.sent.len() + self.received.len()
+    }
+
+    /// Some relay had more messages than the sample asked for
+    pub fn messages_sampled(&self) -> bool {
+        self.sent.len() >= DM_SAMPLE || self.received.len() >= DM_SAMPLE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.has_profile && self.contacts.is_none() && self.messages() == 0
+    }
+
+    /// e.g. "Found profile "Alice", 124 contacts, ~3k messages"
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "Nothing found for this account on the selected relays".into();
+        }
+        let profile = match (&self.profile_name, self.has_profile) {
+            (Some(name), _) => format!("Found profile \"{}\"", name),
+            (None, true) => "Found profile".to_owned(),
+            (None, false) => "No profile".to_owned(),
+        };
+        let contacts = match self.contacts {
+            Some(1) => "1 contact".to_owned(),
+            Some(contacts) => format!("{} contacts", contacts),
+            None => "no contact list".to_owned(),
+        };
+        let messages = match self.messages() {
+            1 => "1 message".to_owned(),
+            count => format!(
+                "{}{} messages",
+                approximate(count),
+                if self.messages_sampled() { "+" } else { "" }
+            ),
+        };
+        format!("{}, {}, {}", profile, contacts, messages)
+    }
+}
+
+fn is_newer(current: Option<Timestamp>, created_at: Timestamp) -> bool {
+    current.map_or(true, |current| created_at > current)
+}
+
+/// Exact below a thousand, "~3k" above
+fn approximate(count: usize) -> String {
+    if count < 1000 {
+        count.to_string()
+    } else {
+        format!("~{}k", (count + 500) / 1000)
+    }
+}
+
+/// Direct messages asked to each relay, in each direction
+pub const DM_SAMPLE: usize = 2000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{EventBuilder, Keys, Metadata};
+
+    #[test]
+    fn summarizes_newest_profile_and_contact_list() {
+        let user = Keys::generate();
+        let contact = Keys::generate();
+        let mut preview = AccountPreview::default();
+        assert!(preview.is_empty());
+
+        let metadata = Metadata::new().name("Alice");
+        let profile = EventBuilder::set_metadata(metadata)
+            .to_event(&user)
+            .unwrap();
+        let contacts = EventBuilder::set_contact_list(vec![nostr::Contact::new::<String>(
+            contact.public_key(),
+            None,
+            None,
+        )])
+        .to_event(&user)
+        .unwrap();
+        let dm = EventBuilder::new_encrypted_direct_msg(&contact, user.public_key(), "hi")
+            .unwrap()
+            .to_event(&contact)
+            .unwrap();
+        for ns_event in [&profile, &contacts, &dm, &dm] {
+            preview.add_event(ns_event, &user.public_key());
+        }
+
+        assert_eq!(preview.messages(), 1);
+        assert_eq!(
+            preview.summary(),
+            "Found profile \"Alice\", 1 contact, 1 message"
+        );
+        assert_eq!(approximate(2_600), "~3k");
+    }
+}
@@ -1,23 +1,39 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
-use chrono::NaiveDateTime;
-use nostr::{Contact, EventBuilder, EventId, Keys, Metadata, Timestamp};
-use ns_client::RelayPool;
+use chrono::{NaiveDateTime, Utc};
+use nostr::{
+    secp256k1::XOnlyPublicKey, Contact, EventBuilder, EventId, Keys, Kind, Metadata, Tag, Timestamp,
+};
+use ns_client::{RelayPool, Subscription};
 use sqlx::SqlitePool;
 use thiserror::Error;
 use url::Url;
 
 use crate::{
-    db::{Database, DbContact, UserConfig},
-    net::ntp::system_now_microseconds,
+    config::{DownloadConfig, Presence},
+    db::{
+        ContactActivity, Database, DbContact, DbEvent, DbPendingEvent, DbRelay, DbRelayResponse,
+        IntegrityReport, ReadMarkers, RelaySuggestion, SigningLogEntry, UserConfig,
+    },
+    net::flood_guard::FloodGuard,
+    net::kind::{KindHandler, KindRegistry},
+    net::ntp::NtpSync,
+    net::request_budget::RequestBudget,
+    net::reqwest_client::MediaClient,
+    net::send_guard::SendGuard,
+    net::simulation::{Outgoing, SimulatedNetwork},
+    net::uploads::Upload,
     utils::{
-        channel_creation_builder, channel_metadata_builder, channel_msg_builder, naive_to_event_tt,
-        ns_event_to_naive, NipData,
+        channel_creation_builder, channel_metadata_builder, channel_msg_builder,
+        channel_read_builder, deletion_builder, dm_builder, mute_list_builder, naive_to_event_tt,
+        ns_event_to_naive, poll_builder, poll_vote_builder, reaction_builder, status_builder,
+        NipData,
     },
     views::login::BasicProfile,
 };
 
-use super::ChannelMetadata;
+use super::{relay_list_builder, AccountPreview, ChannelMetadata, ContactListDiff, RelayDeletion};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -33,37 +49,129 @@ pub enum Error {
     #[error("{0}")]
     FromDbContact(#[from] crate::db::contact::Error),
 
+    #[error("{0}")]
+    FromDbEvent(#[from] crate::db::event::Error),
+
+    #[error("{0}")]
+    FromDbPendingEvent(#[from] crate::db::pending_event::Error),
+
+    #[error("{0}")]
+    FromDbRelay(#[from] crate::db::relay::Error),
+
+    #[error("{0}")]
+    FromDbRelayResponse(#[from] crate::db::relay_response::Error),
+
+    #[error("{0}")]
+    FromSigningLog(#[from] crate::db::signing_log::Error),
+
+    #[error("{0}")]
+    FromUtils(#[from] crate::utils::Error),
+
     #[error("Invalid timestamp: {0}")]
     InvalidTimestamp(Timestamp),
 }
 
 #[derive(Debug, Clone)]
-pub struct PendingEvent(nostr::Event);
+pub struct PendingEvent {
+    ns_event: nostr::Event,
+    /// When it was handed to the relays
+    sent_at: NaiveDateTime,
+    /// Refusals that arrived before any relay accepted it, with their arrival time
+    rejections: Vec<(Url, String, NaiveDateTime)>,
+    /// Sent again from the journal, its row is kept until a relay accepts it
+    journaled: bool,
+}
 impl PendingEvent {
     fn new(ns_event: nostr::Event) -> Self {
-        Self(ns_event)
+        Self {
+            ns_event,
+            sent_at: Utc::now().naive_utc(),
+            rejections: vec![],
+            journaled: false,
+        }
     }
     pub fn id(&self) -> &EventId {
-        &self.0.id
+        &self.ns_event.id
     }
     pub fn ns_event(&self) -> &nostr::Event {
-        &self.0
+        &self.ns_event
     }
     pub fn event_hash(&self) -> EventId {
-        self.0.id.to_owned()
+        self.ns_event.id.to_owned()
     }
     pub fn display_time(&self) -> Result<NaiveDateTime, Error> {
-        ns_event_to_naive(self.0.created_at).map_err(|_| Error::InvalidTimestamp(self.0.created_at))
+        ns_event_to_naive(self.ns_event.created_at)
+            .map_err(|_| Error::InvalidTimestamp(self.ns_event.created_at))
+    }
+    pub fn sent_at(&self) -> NaiveDateTime {
+        self.sent_at
+    }
+    pub fn rejections(&self) -> &[(Url, String, NaiveDateTime)] {
+        &self.rejections
+    }
+    pub fn add_rejection(&mut self, relay_url: &Url, error_msg: &str) {
+        self.rejections.push((
+            relay_url.to_owned(),
+            error_msg.to_owned(),
+            Utc::now().naive_utc(),
+        ));
     }
 }
 
 pub struct BackendState {
     pub req_client: reqwest::Client,
+    /// Image downloads, through the media proxy when it's enabled
+    pub media_client: MediaClient,
     pub nostr: RelayPool,
     pub nips_data: Vec<NipData>,
     pub create_account: Option<BasicProfile>,
     pub pending_events: HashMap<EventId, PendingEvent>,
+    /// Drops outbound signals, see `ToBackend::is_tracking_signal`
+    pub do_not_track: bool,
+    /// Startup check results, until the frontend takes them
+    pub integrity_report: Option<IntegrityReport>,
+    /// Relay changes made by the automatic tuning at login
+    pub relay_tuning_applied: Vec<RelaySuggestion>,
+    /// Drops events from relays flooding a subscription
+    pub flood_guard: FloodGuard,
+    /// Holds a message sent twice in a row until the user confirms it
+    pub send_guard: SendGuard,
+    /// Queues metadata REQs over each relay's budget
+    pub request_budget: RequestBudget,
+    /// Message subscriptions sent to each relay after the EOSE of the one before,
+    /// top conversations first and every message last
+    pub dm_prefetch: Vec<Subscription>,
+    /// Conversations waiting for older messages, by subscription id
+    pub dm_history_fetches: HashMap<String, XOnlyPublicKey>,
+    /// Channel messages of contacts seen by the discovery subscription
+    pub contact_channel_activity: Vec<ContactActivity>,
+    /// When the last developer console REQ was sent
+    pub console_started: Option<Instant>,
+    /// What the relays have of the account, asked from the welcome screen
+    pub account_preview: AccountPreview,
+    /// Latency, failures and offline mode for UI development
+    pub simulation: SimulatedNetwork,
+    /// Deletion requests still waiting for some relays
+    pub relay_deletions: Vec<RelayDeletion>,
+    /// Relay hints of a linked event, connected until they answered the lookup
+    pub resolve_relays: HashSet<Url>,
+    /// Where attachments are saved
+    pub downloads: DownloadConfig,
+    /// Messages whose attachments are being downloaded
+    pub attachment_saves: HashSet<i64>,
+    /// Composer attachments being sent or waiting to be resumed
+    pub uploads: HashMap<u64, Upload>,
+    pub next_upload_id: u64,
+    /// When the relays' NIP-11 documents are checked for staleness next
+    pub next_document_check: Instant,
+    /// Relays whose document is being fetched
+    pub documents_refreshing: HashSet<Url>,
+    /// Relays last seen failing, their document is fetched once they connect again
+    pub relays_with_errors: HashSet<Url>,
+    /// Events from these authors are dropped before being stored
+    blocked: HashSet<XOnlyPublicKey>,
     db_client: Database,
+    kinds: KindRegistry,
     ntp_offset: Option<i64>,
     ntp_server: Option<String>,
 }
@@ -74,31 +182,90 @@ impl BackendState {
         nostr: RelayPool,
         nips_data: Vec<NipData>,
         create_account: Option<BasicProfile>,
+        do_not_track: bool,
+        requests_per_minute: u32,
     ) -> Self {
         Self {
             db_client,
             req_client,
+            media_client: MediaClient::direct(),
             nostr,
             nips_data,
             create_account,
             pending_events: HashMap::new(),
+            do_not_track,
+            integrity_report: None,
+            relay_tuning_applied: vec![],
+            flood_guard: FloodGuard::new(),
+            send_guard: SendGuard::new(),
+            request_budget: RequestBudget::new(requests_per_minute),
+            dm_prefetch: vec![],
+            dm_history_fetches: HashMap::new(),
+            contact_channel_activity: vec![],
+            console_started: None,
+            account_preview: AccountPreview::default(),
+            simulation: SimulatedNetwork::new(),
+            relay_deletions: vec![],
+            resolve_relays: HashSet::new(),
+            downloads: DownloadConfig::default(),
+            attachment_saves: HashSet::new(),
+            uploads: HashMap::new(),
+            next_upload_id: 0,
+            next_document_check: Instant::now(),
+            documents_refreshing: HashSet::new(),
+            relays_with_errors: HashSet::new(),
+            blocked: HashSet::new(),
+            kinds: KindRegistry::with_defaults(),
             ntp_offset: None,
             ntp_server: None,
         }
     }
 
+    pub fn kinds(&self) -> &KindRegistry {
+        &self.kinds
+    }
+    /// Adds support for new event kinds
+    pub fn register_kind(&mut self, handler: impl KindHandler + 'static) {
+        self.kinds.register(handler);
+    }
+    pub fn is_blocked(&self, pubkey: &XOnlyPublicKey) -> bool {
+        self.blocked.contains(pubkey)
+    }
+    pub fn set_blocked(&mut self, blocked: HashSet<XOnlyPublicKey>) {
+        self.blocked = blocked;
+    }
     fn insert_pending(&mut self, event: PendingEvent) {
         self.pending_events.insert(*event.id(), event);
     }
+    /// Sends to the relays unless the simulation holds or fails it
+    fn publish(&mut self, ns_event: &nostr::Event) -> Result<(), Error> {
+        if let Outgoing::Send = self.simulation.outgoing(ns_event) {
+            self.nostr.send_event(ns_event.clone())?;
+        }
+        Ok(())
+    }
+    /// Signed for the write relays
+    async fn event_to_publish(
+        &self,
+        keys: &Keys,
+        builder: EventBuilder,
+    ) -> Result<nostr::Event, Error> {
+        let pool = &self.db_client.pool;
+        let relays: Vec<Url> = DbRelay::fetch(pool)
+            .await?
+            .into_iter()
+            .filter(|db_relay| db_relay.write)
+            .map(|db_relay| db_relay.url)
+            .collect();
+        event_with_time(pool, keys, builder, &relays).await
+    }
     pub fn synced_ntp(&self) -> (Option<i64>, Option<String>) {
         (self.ntp_offset, self.ntp_server.clone())
     }
-    pub fn update_ntp(&mut self, ntp_time: u64, server: &str) {
-        let system_microseconds = system_now_microseconds().expect("System time before unix epoch");
-        let offset = ntp_time as i64 - system_microseconds as i64;
-
-        self.ntp_offset = Some(offset);
-        self.ntp_server = Some(server.to_owned());
+    /// Returns the previous offset
+    pub fn update_ntp(&mut self, sync: &NtpSync) -> Option<i64> {
+        self.ntp_server = Some(sync.servers.join(", "));
+        self.ntp_offset.replace(sync.offset)
     }
     pub async fn new_auth_event<S>(
         &mut self,
@@ -113,37 +280,113 @@ impl BackendState {
         let pool = &self.db_client.pool;
 
         let builder = EventBuilder::auth(challenge, relay_url.to_owned());
-        let ns_event = event_with_time(pool, keys, builder).await?;
+        let ns_event = event_with_time(pool, keys, builder, &[relay_url.to_owned()]).await?;
         self.nostr.send_auth(relay_url, ns_event)?;
         Ok(())
     }
 
+    /// Signed NIP-65 relay list, not published
+    pub async fn relay_list_event(
+        &self,
+        keys: &Keys,
+        relays: &[DbRelay],
+    ) -> Result<nostr::Event, Error> {
+        let builder = relay_list_builder(relays);
+        event_with_time(&self.db_client.pool, keys, builder, &[]).await
+    }
+
+    /// Signed contact list with only these contacts, not published
+    pub async fn contact_list_event(
+        &self,
+        keys: &Keys,
+        contacts: &[DbContact],
+    ) -> Result<nostr::Event, Error> {
+        let c_list: Vec<Contact> = contacts.iter().map(|c| c.into()).collect();
+        let builder = EventBuilder::set_contact_list(c_list);
+        event_with_time(&self.db_client.pool, keys, builder, &[]).await
+    }
+
     pub async fn new_profile_event(
         &mut self,
         keys: &Keys,
         metadata: &Metadata,
     ) -> Result<(), Error> {
         tracing::debug!("send_profile");
-        let pool = &self.db_client.pool;
 
         let builder = EventBuilder::set_metadata(metadata.clone());
-        let ns_event = event_with_time(pool, keys, builder).await?;
-        self.nostr.send_event(ns_event.clone())?;
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
+
+        self.insert_pending(PendingEvent::new(ns_event));
+
+        Ok(())
+    }
+
+    /// Publishes the NIP-51 mute list with these tags and private entries
+    pub async fn new_mute_list_event(
+        &mut self,
+        keys: &Keys,
+        tags: &[Tag],
+        content: &str,
+    ) -> Result<(), Error> {
+        tracing::debug!("send_mute_list");
+
+        let builder = mute_list_builder(tags, content);
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
 
         self.insert_pending(PendingEvent::new(ns_event));
 
         Ok(())
     }
 
+    /// Publishes the NIP-38 status. It is replaced often,
+    /// so it doesn't wait for the relays before exiting
+    pub async fn new_status_event(&mut self, keys: &Keys, presence: Presence) -> Result<(), Error> {
+        tracing::debug!("send_status: {:?}", presence);
+        let builder = status_builder(presence);
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
+        Ok(())
+    }
+
+    /// Publishes the channel read positions. Like the status it is
+    /// replaced often, so it doesn't wait for the relays
+    pub async fn new_channel_read_event(
+        &mut self,
+        keys: &Keys,
+        markers: &ReadMarkers,
+    ) -> Result<(), Error> {
+        tracing::debug!("send_channel_read: {} channels", markers.0.len());
+        let builder = channel_read_builder(keys, markers)?;
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
+        Ok(())
+    }
+
+    /// Publishes a NIP-09 deletion of the user's event, not stored locally
+    pub async fn new_deletion_event(
+        &mut self,
+        keys: &Keys,
+        event_hash: &EventId,
+    ) -> Result<EventId, Error> {
+        tracing::debug!("send_deletion");
+
+        let builder = deletion_builder(event_hash);
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
+
+        Ok(ns_event.id)
+    }
+
     pub async fn new_contact_list_event(&mut self, keys: &Keys) -> Result<PendingEvent, Error> {
         tracing::debug!("build_contact_list_event");
-        let pool = &self.db_client.pool;
         let list = DbContact::fetch_basic(&self.db_client.pool).await?;
         let c_list: Vec<Contact> = list.iter().map(|c| c.into()).collect();
 
         let builder = EventBuilder::set_contact_list(c_list);
-        let ns_event = event_with_time(pool, keys, builder).await?;
-        self.nostr.send_event(ns_event.clone())?;
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
 
         let pending_event = PendingEvent::new(ns_event);
         self.insert_pending(pending_event.clone());
@@ -151,6 +394,24 @@ impl BackendState {
         Ok(pending_event)
     }
 
+    /// Changes to the contact list not yet published by the user
+    pub async fn contact_list_diff(&self, keys: &Keys) -> Result<ContactListDiff, Error> {
+        let pool = &self.db_client.pool;
+        let published: Vec<_> =
+            DbEvent::fetch_last_kind_pubkey(pool, Kind::ContactList, &keys.public_key())
+                .await?
+                .map(|db_event| {
+                    db_event
+                        .tags
+                        .iter()
+                        .filter_map(|t| DbContact::from_tag(t).ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+        let local = DbContact::fetch_basic(pool).await?;
+        Ok(ContactListDiff::new(&published, &local))
+    }
+
     pub async fn new_dm(
         &mut self,
         keys: &Keys,
@@ -160,10 +421,10 @@ impl BackendState {
         tracing::debug!("build_dm");
         let pool = &self.db_client.pool;
 
-        let builder =
-            EventBuilder::new_encrypted_direct_msg(keys, db_contact.pubkey().to_owned(), content)?;
-        let ns_event = event_with_time(pool, keys, builder).await?;
-        self.nostr.send_event(ns_event.clone())?;
+        let relays = DbRelayResponse::fetch_conversation_relays(pool, db_contact, 1).await?;
+        let builder = dm_builder(keys, db_contact.pubkey(), relays.first(), content)?;
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
 
         let pending_event = PendingEvent::new(ns_event);
         self.insert_pending(pending_event.clone());
@@ -177,12 +438,65 @@ impl BackendState {
         channel_id: &EventId,
         recommended_relay: Option<&Url>,
         content: &str,
+        broadcast: bool,
     ) -> Result<PendingEvent, Error> {
-        let pool = &self.db_client.pool;
-        let builder = channel_msg_builder(channel_id, recommended_relay, content);
+        let builder = channel_msg_builder(channel_id, recommended_relay, content, broadcast);
 
-        let ns_event = event_with_time(pool, keys, builder).await?;
-        self.nostr.send_event(ns_event.clone())?;
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
+
+        let pending_event = PendingEvent::new(ns_event);
+        self.insert_pending(pending_event.clone());
+
+        Ok(pending_event)
+    }
+
+    pub(crate) async fn new_poll(
+        &mut self,
+        keys: &Keys,
+        channel_id: &EventId,
+        question: &str,
+        options: &[String],
+    ) -> Result<PendingEvent, Error> {
+        let builder = poll_builder(channel_id, question, options);
+
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
+
+        let pending_event = PendingEvent::new(ns_event);
+        self.insert_pending(pending_event.clone());
+
+        Ok(pending_event)
+    }
+
+    pub(crate) async fn new_poll_vote(
+        &mut self,
+        keys: &Keys,
+        channel_id: &EventId,
+        poll_hash: &EventId,
+        option_id: &str,
+    ) -> Result<PendingEvent, Error> {
+        let builder = poll_vote_builder(channel_id, poll_hash, option_id);
+
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
+
+        let pending_event = PendingEvent::new(ns_event);
+        self.insert_pending(pending_event.clone());
+
+        Ok(pending_event)
+    }
+
+    pub(crate) async fn new_reaction(
+        &mut self,
+        keys: &Keys,
+        db_event: &DbEvent,
+        content: &str,
+    ) -> Result<PendingEvent, Error> {
+        let builder = reaction_builder(&db_event.event_hash, &db_event.pubkey, content);
+
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
 
         let pending_event = PendingEvent::new(ns_event);
         self.insert_pending(pending_event.clone());
@@ -195,11 +509,10 @@ impl BackendState {
         keys: &Keys,
         metadata: &ChannelMetadata,
     ) -> Result<PendingEvent, Error> {
-        let pool = &self.db_client.pool;
         let builder = channel_creation_builder(metadata);
 
-        let ns_event = event_with_time(pool, keys, builder).await?;
-        self.nostr.send_event(ns_event.clone())?;
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
 
         let pending_event = PendingEvent::new(ns_event);
         self.insert_pending(pending_event.clone());
@@ -213,12 +526,12 @@ impl BackendState {
         channel_id: &EventId,
         recommended_relay: Option<&Url>,
         metadata: &ChannelMetadata,
+        moderators: &[XOnlyPublicKey],
     ) -> Result<PendingEvent, Error> {
-        let pool = &self.db_client.pool;
-        let builder = channel_metadata_builder(channel_id, recommended_relay, metadata);
+        let builder = channel_metadata_builder(channel_id, recommended_relay, metadata, moderators);
 
-        let ns_event = event_with_time(pool, keys, builder).await?;
-        self.nostr.send_event(ns_event.clone())?;
+        let ns_event = self.event_to_publish(keys, builder).await?;
+        self.publish(&ns_event)?;
 
         let pending_event = PendingEvent::new(ns_event);
         self.insert_pending(pending_event.clone());
@@ -226,6 +539,40 @@ impl BackendState {
         Ok(pending_event)
     }
 
+    /// Saves the events still waiting for relay confirmation,
+    /// they are sent again at the next login
+    pub async fn persist_pending(&mut self) -> Result<usize, Error> {
+        let pool = &self.db_client.pool;
+        let count = self.pending_events.len();
+        for (_, pending) in self.pending_events.drain() {
+            DbPendingEvent::insert(pool, pending.ns_event()).await?;
+        }
+        Ok(count)
+    }
+
+    /// Sends again the events saved by the last `persist_pending`,
+    /// each one stays in the journal until a relay accepts it
+    pub async fn resend_journal(&mut self) -> Result<(), Error> {
+        let journal = DbPendingEvent::fetch(&self.db_client.pool).await?;
+        for db_pending in journal {
+            tracing::info!("Resending journaled event: {}", db_pending.ns_event.id);
+            self.publish(&db_pending.ns_event)?;
+            self.insert_pending(PendingEvent {
+                journaled: true,
+                ..PendingEvent::new(db_pending.ns_event)
+            });
+        }
+        Ok(())
+    }
+
+    /// A relay accepted the event, it's no longer sent again at login
+    pub async fn remove_from_journal(&self, pending: &PendingEvent) -> Result<(), Error> {
+        if pending.journaled {
+            DbPendingEvent::delete(&self.db_client.pool, pending.id()).await?;
+        }
+        Ok(())
+    }
+
     pub async fn logout(&self) -> Result<(), Error> {
         tracing::info!("Database Logging out");
         self.db_client.pool.close().await;
@@ -241,12 +588,18 @@ impl BackendState {
     pub fn cache_pool(&self) -> &SqlitePool {
         &self.db_client.cache_pool
     }
+
+    pub fn migrated(&self) -> Option<(usize, usize)> {
+        self.db_client.migrated
+    }
 }
 
+/// Signs with the corrected time and records it in the signing log
 async fn event_with_time(
     pool: &SqlitePool,
     keys: &Keys,
     builder: EventBuilder,
+    relays: &[Url],
 ) -> Result<nostr::Event, Error> {
     let mut ns_event = builder.to_unsigned_event(keys.public_key());
     if let Ok(utc_now) = UserConfig::get_corrected_time(pool).await {
@@ -263,5 +616,6 @@ async fn event_with_time(
     let ns_event = ns_event
         .sign(keys)
         .map_err(|e| Error::SigningEvent(e.to_string()))?;
+    SigningLogEntry::insert(pool, &ns_event, relays).await?;
     Ok(ns_event)
 }
@@ -43,6 +43,10 @@ pub struct ChannelMetadata {
     /// Picture url
     #[serde(skip_serializing_if = "Option::is_none")]
     pub picture: Option<String>,
+    /// Pinned by the owner, shown as a banner to the members.
+    /// Not part of NIP-28, other clients ignore it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub announcement: Option<String>,
 }
 
 impl Default for ChannelMetadata {
@@ -58,6 +62,7 @@ impl ChannelMetadata {
             name: None,
             about: None,
             picture: None,
+            announcement: None,
         }
     }
 
@@ -103,6 +108,17 @@ impl ChannelMetadata {
             ..self
         }
     }
+
+    /// Set announcement
+    pub fn announcement<S>(self, announcement: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            announcement: Some(announcement.into()),
+            ..self
+        }
+    }
 }
 
 #[cfg(test)]
@@ -127,5 +143,13 @@ mod tests {
                 .about("Description")
                 .picture(Url::parse("https://some-picture.com/200/300").unwrap())
         );
+
+        let metadata = ChannelMetadata::new()
+            .name("myname")
+            .announcement("Meetup on friday");
+        assert_eq!(
+            ChannelMetadata::from_json(metadata.as_json()).unwrap(),
+            metadata
+        );
     }
 }
@@ -1,23 +1,31 @@
 use chrono::NaiveDateTime;
 use iced::widget::{button, column, container, row, text};
-use iced::Point;
 use iced::{alignment, Length};
+use iced::{Color, Point};
 use nostr::secp256k1::XOnlyPublicKey;
 use nostr::EventId;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::components::hover_card::{self, HoverCards};
+use crate::components::text::rich_text;
 use crate::components::MouseArea;
-use crate::db::{DbChannelMessage, MessageStatus};
-use crate::icon::{check_icon, double_check_icon, xmark_icon};
-use crate::utils::{from_naive_utc_to_local, hide_string};
+use crate::config::{Appearance, TextDirection, TimeFormatConfig};
+use crate::db::reaction::LIKE;
+use crate::db::{DbChannelMessage, DbTranslation, MessageStatus, ReactionCount};
+use crate::icon::{
+    check_icon, clock_icon, double_check_icon, regular_circle_icon, triangle_warn_icon, xmark_icon,
+};
+use crate::net::RequestId;
+use crate::utils::text_direction::{visual_line, Direction};
+use crate::utils::{from_naive_utc_to_local, hide_string, time_format};
 use crate::widget::{Element, Text};
 use crate::{
     db::{DbContact, DbMessage},
     style,
 };
 
-use super::PendingEvent;
+use super::{NostrLink, PendingEvent};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -28,21 +36,45 @@ pub enum Error {
 #[derive(Debug, Clone)]
 pub enum Message {
     ChatRightClick(ChatMessage, Point),
+    ChatPress(ChatMessage),
+    ChatRelease(ChatMessage),
     UserNameClick(XOnlyPublicKey),
+    NostrLinkPressed(NostrLink),
+    HashtagPressed(String),
+    /// Event id of the message and the reaction added or taken back
+    ReactionPressed(i64, String),
+    HoverCard(hover_card::Message),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserMessage {
+    /// Shown as soon as the user presses send, before the backend creates the event
+    Local {
+        #[serde(skip)]
+        request_id: RequestId,
+        content: String,
+        display_time: NaiveDateTime,
+        failed: bool,
+    },
+    /// Handed to the relays, until one of them accepts it
     Pending {
         event_hash: EventId,
         content: String,
         display_time: Option<NaiveDateTime>,
+        /// A relay refused it
+        #[serde(default)]
+        rejected: bool,
     },
     Confirmed {
         content: String,
         display_time: NaiveDateTime,
         event_id: i64,
         status: MessageStatus,
+        #[serde(default)]
+        reactions: Vec<ReactionCount>,
+        /// Deleted by the author, the content is gone
+        #[serde(default)]
+        retracted: bool,
     },
 }
 
@@ -56,13 +88,18 @@ pub enum ChatMessage {
         display_time: NaiveDateTime,
         event_id: i64,
         status: MessageStatus,
+        #[serde(default)]
+        reactions: Vec<ReactionCount>,
+        /// Deleted by the author, the content is gone
+        #[serde(default)]
+        retracted: bool,
     },
 }
 
 impl ChatMessage {
-    pub fn is_pending(&self) -> bool {
-        if let Self::UserMessage(UserMessage::Pending { .. }) = self {
-            return true;
+    pub fn match_local_request(&self, id: &RequestId) -> bool {
+        if let Self::UserMessage(UserMessage::Local { request_id, .. }) = self {
+            return request_id == id;
         }
         false
     }
@@ -76,41 +113,138 @@ impl ChatMessage {
         }
         false
     }
+    pub fn author(&self) -> Option<&XOnlyPublicKey> {
+        match self {
+            Self::UserMessage(_) => None,
+            Self::ContactMessage { author, .. } => Some(author),
+        }
+    }
     pub fn event_id(&self) -> Option<i64> {
         match self {
             Self::UserMessage(user) => match user {
-                UserMessage::Pending { .. } => None,
+                UserMessage::Local { .. } | UserMessage::Pending { .. } => None,
                 UserMessage::Confirmed { event_id, .. } => Some(*event_id),
             },
             Self::ContactMessage { event_id, .. } => Some(*event_id),
         }
     }
+    /// Counts of the reactions, local and pending messages have none
+    pub fn reactions(&self) -> &[ReactionCount] {
+        match self {
+            Self::UserMessage(UserMessage::Confirmed { reactions, .. })
+            | Self::ContactMessage { reactions, .. } => reactions,
+            Self::UserMessage(_) => &[],
+        }
+    }
+    pub fn is_retracted(&self) -> bool {
+        match self {
+            Self::UserMessage(UserMessage::Confirmed { retracted, .. })
+            | Self::ContactMessage { retracted, .. } => *retracted,
+            Self::UserMessage(_) => false,
+        }
+    }
+    /// The author deleted the message, its content and reactions go with it
+    pub fn retract(&mut self) {
+        if let Self::UserMessage(UserMessage::Confirmed {
+            content,
+            reactions,
+            retracted,
+            ..
+        })
+        | Self::ContactMessage {
+            content,
+            reactions,
+            retracted,
+            ..
+        } = self
+        {
+            content.clear();
+            reactions.clear();
+            *retracted = true;
+        }
+    }
+    pub fn set_reactions(&mut self, counts: Vec<ReactionCount>) {
+        if let Self::UserMessage(UserMessage::Confirmed { reactions, .. })
+        | Self::ContactMessage { reactions, .. } = self
+        {
+            *reactions = counts;
+        }
+    }
+    pub fn local(request_id: RequestId, content: &str, display_time: NaiveDateTime) -> Self {
+        Self::UserMessage(UserMessage::Local {
+            request_id,
+            content: content.to_owned(),
+            display_time,
+            failed: false,
+        })
+    }
+    /// The backend failed to create the event for a local message
+    pub fn set_local_failed(&mut self) {
+        if let Self::UserMessage(UserMessage::Local { failed, .. }) = self {
+            *failed = true;
+        }
+    }
+    /// A relay refused the pending event
+    pub fn set_pending_rejected(&mut self) {
+        if let Self::UserMessage(UserMessage::Pending { rejected, .. }) = self {
+            *rejected = true;
+        }
+    }
     pub fn pending(pending: PendingEvent, content: &str) -> Self {
         let user_msg = UserMessage::Pending {
             event_hash: pending.event_hash().to_owned(),
             content: content.to_owned(),
             display_time: pending.display_time().ok(),
+            rejected: false,
         };
         Self::UserMessage(user_msg)
     }
 
     pub fn confirmed_users(db_message: &DbMessage, content: &str) -> Self {
+        let retracted = db_message.retracted_at.is_some();
         let user_msg = UserMessage::Confirmed {
-            content: content.to_owned(),
+            content: if retracted { "" } else { content }.to_owned(),
             display_time: db_message.created_at.to_owned(),
             event_id: db_message.event_id,
             status: db_message.status,
+            reactions: vec![],
+            retracted,
         };
         Self::UserMessage(user_msg)
     }
     pub fn confirmed_contacts(db_message: &DbMessage, contact: &DbContact, content: &str) -> Self {
+        let retracted = db_message.retracted_at.is_some();
         Self::ContactMessage {
-            content: content.to_owned(),
+            content: if retracted { "" } else { content }.to_owned(),
             author: contact.pubkey().to_owned(),
             display_time: db_message.created_at.to_owned(),
             display_name: contact.select_name(),
             event_id: db_message.event_id,
             status: db_message.status,
+            reactions: vec![],
+            retracted,
+        }
+    }
+
+    /// Sent by the user right after the same content, like with a double click
+    pub fn is_duplicate_of(&self, previous_msg: &Self) -> bool {
+        match (self, previous_msg) {
+            (
+                Self::UserMessage(UserMessage::Confirmed {
+                    content,
+                    display_time,
+                    ..
+                }),
+                Self::UserMessage(UserMessage::Confirmed {
+                    content: prev_content,
+                    display_time: prev_time,
+                    ..
+                }),
+            ) => {
+                content == prev_content
+                    && (*display_time - *prev_time).num_seconds().abs() <= DUPLICATE_SECS
+            }
+            _ => false,
         }
     }
 
@@ -135,10 +269,16 @@ impl ChatMessage {
         }
     }
 
-    fn style(&self) -> style::Container {
+    fn style(&self, accent: Option<Color>) -> style::Container {
         match self {
-            ChatMessage::ContactMessage { .. } => style::Container::ReceivedMessage,
-            ChatMessage::UserMessage(_) => style::Container::SentMessage,
+            ChatMessage::ContactMessage { .. } => match accent {
+                Some(accent) => style::Container::AccentMessage(accent),
+                None => style::Container::ReceivedMessage,
+            },
+            ChatMessage::UserMessage(UserMessage::Confirmed { .. }) => {
+                style::Container::SentMessage
+            }
+            ChatMessage::UserMessage(_) => style::Container::PendingMessage,
         }
     }
 
@@ -146,7 +286,15 @@ impl ChatMessage {
         let style = match self {
             ChatMessage::ContactMessage { .. } => check_icon().size(14),
             ChatMessage::UserMessage(user) => match user {
-                UserMessage::Pending { .. } => xmark_icon().size(14),
+                UserMessage::Local { failed: true, .. }
+                | UserMessage::Pending { rejected: true, .. } => {
+                    return triangle_warn_icon()
+                        .size(14)
+                        .style(style::Text::Danger)
+                        .into();
+                }
+                UserMessage::Local { .. } => regular_circle_icon().size(14),
+                UserMessage::Pending { .. } => clock_icon().size(14),
                 UserMessage::Confirmed { status, .. } => match status {
                     MessageStatus::Pending => xmark_icon().size(14),
                     MessageStatus::Delivered => check_icon().size(14),
@@ -160,6 +308,7 @@ impl ChatMessage {
     pub fn display_time(&self) -> Option<&NaiveDateTime> {
         match self {
             ChatMessage::UserMessage(user) => match user {
+                UserMessage::Local { display_time, .. } => Some(display_time),
                 UserMessage::Pending { display_time, .. } => display_time.as_ref(),
                 UserMessage::Confirmed { display_time, .. } => Some(display_time),
             },
@@ -167,11 +316,27 @@ impl ChatMessage {
         }
     }
 
-    fn local_time(&self) -> Text<'_> {
-        make_local_time(self.display_time())
+    fn set_display_time(&mut self, time: NaiveDateTime) {
+        match self {
+            ChatMessage::UserMessage(user) => match user {
+                UserMessage::Local { display_time, .. }
+                | UserMessage::Confirmed { display_time, .. } => *display_time = time,
+                UserMessage::Pending { display_time, .. } => *display_time = Some(time),
+            },
+            ChatMessage::ContactMessage { display_time, .. } => *display_time = time,
+        }
+    }
+
+    fn local_time(&self, format: &TimeFormatConfig) -> Text<'_> {
+        make_local_time(self.display_time(), format)
     }
 
-    fn name(&self, show_name: bool) -> Element<'_, Message> {
+    fn name<'a>(
+        &'a self,
+        show_name: bool,
+        accent: Option<Color>,
+        hover_cards: &'a HoverCards,
+    ) -> Element<'a, Message> {
         if !show_name {
             return text("").into();
         }
@@ -183,18 +348,20 @@ impl ChatMessage {
                 author,
                 ..
             } => {
+                let name_style = accent.map_or(style::Text::Default, style::Text::Accent);
                 // only shows name if is in channel view and
                 // previous chat message is a different user
-                button(text(display_name))
+                let name_btn = button(text(display_name).style(name_style))
                     .on_press(Message::UserNameClick(*author))
-                    .style(style::Button::Invisible)
-                    .into()
+                    .style(style::Button::Invisible);
+                hover_cards.wrap(author, name_btn, Message::HoverCard)
             }
         }
     }
     pub fn content(&self) -> &str {
         match self {
             ChatMessage::UserMessage(user) => match user {
+                UserMessage::Local { content, .. } => content,
                 UserMessage::Pending { content, .. } => content,
                 UserMessage::Confirmed { content, .. } => content,
             },
@@ -202,18 +369,65 @@ impl ChatMessage {
         }
     }
 
-    pub fn view(&self, show_name: bool) -> Element<'_, Message> {
+    pub fn view<'a>(
+        &'a self,
+        show_name: bool,
+        translation: Option<&'a DbTranslation>,
+        accent: Option<Color>,
+        hover_cards: &'a HoverCards,
+        appearance: Appearance,
+    ) -> Element<'a, Message> {
         make_chat_view(
             self.alignment(),
-            self.style(),
-            self.name(show_name),
+            self.style(accent),
+            self.name(show_name, accent, hover_cards),
             self.status(),
-            self.local_time(),
-            self.content(),
+            self.local_time(&appearance.time_format),
+            (!self.is_retracted()).then(|| self.content()),
+            translation,
+            hover_cards,
+            self.reactions_row(),
+            Message::ChatPress(self.clone()),
+            Message::ChatRelease(self.clone()),
             |p| Message::ChatRightClick(self.clone(), p),
+            appearance.text_direction,
         )
     }
 
+    /// Reactions under the message, pressing one adds the user's or takes it back.
+    /// The like button is there until the user likes it
+    fn reactions_row(&self) -> Option<Element<'_, Message>> {
+        if self.is_retracted() {
+            return None;
+        }
+        let event_id = self.event_id()?;
+        let reactions = self.reactions();
+        let chips = reactions.iter().map(|reaction| {
+            let style = if reaction.by_user {
+                style::Button::Primary
+            } else {
+                style::Button::Bordered
+            };
+            button(text(format!("{} {}", reaction.label(), reaction.count)).size(14))
+                .padding([2, 8])
+                .style(style)
+                .on_press(Message::ReactionPressed(
+                    event_id,
+                    reaction.content.to_owned(),
+                ))
+                .into()
+        });
+        let mut chips_row = row(chips.collect()).spacing(5);
+        if !reactions.iter().any(|r| r.by_user && r.content == LIKE) {
+            let like_btn = button(text(LIKE_BUTTON).size(14).style(style::Text::Alpha(0.5)))
+                .padding([2, 4])
+                .style(style::Button::Invisible)
+                .on_press(Message::ReactionPressed(event_id, LIKE.to_owned()));
+            chips_row = chips_row.push(like_btn);
+        }
+        Some(chips_row.into())
+    }
+
     pub(crate) fn update_display_name(&mut self, pubkey: &XOnlyPublicKey, name: String) {
         match self {
             ChatMessage::UserMessage(_) => (),
@@ -230,44 +444,157 @@ impl ChatMessage {
     }
 }
 
-fn make_local_time<'a>(display_time: Option<&NaiveDateTime>) -> Text<'a> {
+/// One "[date time] name: content" line per message, in local time
+pub fn transcript(messages: &[&ChatMessage]) -> String {
+    messages
+        .iter()
+        .map(|msg| {
+            let author = match msg {
+                ChatMessage::UserMessage(_) => "You",
+                ChatMessage::ContactMessage { display_name, .. } => display_name.as_str(),
+            };
+            match msg.display_time() {
+                Some(time) => format!(
+                    "[{}] {}: {}",
+                    from_naive_utc_to_local(*time).format(TRANSCRIPT_TIME_FORMAT),
+                    author,
+                    msg.content()
+                ),
+                None => format!("{}: {}", author, msg.content()),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replaces the message at `idx` without moving it.
+/// The time of the event created by the backend can differ slightly from the one shown
+/// when the message was sent, so it's kept between its neighbours and later sorts don't reorder it.
+pub fn reconcile_at(messages: &mut [ChatMessage], idx: usize, mut new_message: ChatMessage) {
+    if idx >= messages.len() {
+        return;
+    }
+    if let Some(mut time) = new_message.display_time().cloned() {
+        let previous = idx
+            .checked_sub(1)
+            .and_then(|p| messages[p].display_time().cloned());
+        let next = messages
+            .get(idx + 1)
+            .and_then(|n| n.display_time().cloned());
+        if let Some(previous) = previous {
+            time = time.max(previous);
+        }
+        if let Some(next) = next {
+            time = time.min(next);
+        }
+        new_message.set_display_time(time);
+    }
+    messages[idx] = new_message;
+}
+
+fn make_local_time<'a>(
+    display_time: Option<&NaiveDateTime>,
+    format: &TimeFormatConfig,
+) -> Text<'a> {
     if let Some(display_time) = display_time {
-        let local_time = from_naive_utc_to_local(*display_time);
-        let local_time = local_time.time().format("%H:%M").to_string();
+        let local_time = time_format::message_time(*display_time, format);
         text(&local_time).style(style::Text::Alpha(0.5)).size(16)
     } else {
         text("")
     }
 }
 
+fn make_translation<'a>(
+    translation: &'a DbTranslation,
+    text_direction: TextDirection,
+) -> Element<'a, Message> {
+    let lang = match &translation.source_lang {
+        Some(source) => format!("{} → {}", source, translation.target_lang),
+        None => translation.target_lang.to_owned(),
+    };
+    let badge = container(text(lang.to_uppercase()).size(12))
+        .padding([1, 5])
+        .style(style::Container::ChatDateDivider);
+    let direction = Direction::of(&translation.content, text_direction);
+    column![
+        badge,
+        text(visual_line(&translation.content, direction))
+            .size(16)
+            .style(style::Text::Alpha(0.8))
+            .horizontal_alignment(direction.alignment())
+    ]
+    .spacing(2)
+    .align_items(if direction.is_rtl() {
+        alignment::Alignment::End
+    } else {
+        alignment::Alignment::Start
+    })
+    .into()
+}
+
+#[allow(clippy::too_many_arguments)]
 fn make_chat_view<'a, F>(
     alignment: alignment::Horizontal,
     container_style: style::Container,
     name: impl Into<Element<'a, Message>>,
     status: impl Into<Element<'a, Message>>,
     local_time: impl Into<Element<'a, Message>>,
-    content: &'a str,
+    content: Option<&'a str>,
+    translation: Option<&'a DbTranslation>,
+    hover_cards: &'a HoverCards,
+    reactions: Option<Element<'a, Message>>,
+    on_press: Message,
+    on_release: Message,
     on_right_press: F,
+    text_direction: TextDirection,
 ) -> Element<'a, Message>
 where
     F: 'a + Fn(Point) -> Message,
 {
-    let content = text(content).size(18);
+    let content_text = content.unwrap_or_default();
+    let content: Element<_> = match content {
+        Some(content) => rich_text(content)
+            .size(18)
+            .on_nostr_link(Message::NostrLinkPressed)
+            .on_hashtag(Message::HashtagPressed)
+            .mention_cards(hover_cards, Message::HoverCard)
+            .text_direction(text_direction)
+            .into(),
+        None => text(RETRACTED_TEXT)
+            .size(18)
+            .style(style::Text::Placeholder)
+            .into(),
+    };
     let status_row = row![local_time.into(), status.into()].spacing(5);
-    let message_container = column![name.into(), content, status_row]
-        // this works but all the items are aligned to the right
-        // and I cant realign them to the left after this
-        // .align_items(alignment::Alignment::End)
-        .spacing(5);
+    let mut message_container = column![name.into(), content].spacing(5);
+    // every item goes to the right, name and time included
+    if Direction::of(content_text, text_direction).is_rtl() {
+        message_container = message_container.align_items(alignment::Alignment::End);
+    }
+    if let Some(translation) = translation {
+        message_container = message_container.push(make_translation(translation, text_direction));
+    }
+    let message_container = message_container.push(status_row);
 
     let message_container = container(message_container)
         .max_width(CHAT_MESSAGE_MAX_WIDTH)
         .padding([5, 10])
         .style(container_style);
 
-    let mouse_area = MouseArea::new(message_container).on_right_release(on_right_press);
+    let mouse_area = MouseArea::new(message_container)
+        .on_press(on_press)
+        .on_release(on_release)
+        .on_right_release(on_right_press);
+
+    let mut bubble = column![mouse_area].spacing(2);
+    if let Some(reactions) = reactions {
+        bubble = bubble.push(reactions).align_items(match alignment {
+            alignment::Horizontal::Right => alignment::Alignment::End,
+            _ => alignment::Alignment::Start,
+        });
+    }
 
-    container(mouse_area)
+    container(bubble)
         .width(Length::Fill)
         .center_y()
         .align_x(alignment)
@@ -277,25 +604,180 @@ where
 
 impl From<DbChannelMessage> for ChatMessage {
     fn from(ch_msg: DbChannelMessage) -> Self {
+        let retracted = ch_msg.retracted_at.is_some();
         if ch_msg.is_users {
             Self::UserMessage(UserMessage::Confirmed {
-                content: ch_msg.content,
+                content: if retracted {
+                    String::new()
+                } else {
+                    ch_msg.content
+                },
                 display_time: ch_msg.created_at,
                 event_id: ch_msg.event_id,
                 status: MessageStatus::Delivered,
+                reactions: vec![],
+                retracted,
             })
         } else {
             let display_name = hide_string(&ch_msg.display_name(), 6);
             Self::ContactMessage {
                 author: ch_msg.author,
                 display_time: ch_msg.created_at,
-                content: ch_msg.content,
+                content: if retracted {
+                    String::new()
+                } else {
+                    ch_msg.content
+                },
                 display_name,
                 event_id: ch_msg.event_id,
                 status: MessageStatus::Delivered,
+                reactions: vec![],
+                retracted,
             }
         }
     }
 }
 
 const CHAT_MESSAGE_MAX_WIDTH: f32 = 450.0;
+const LIKE_BUTTON: &str = "♡";
+/// Shown instead of a message deleted by its author
+pub const RETRACTED_TEXT: &str = "Message deleted";
+/// Messages with the same content sent this close are taken as accidental
+const DUPLICATE_SECS: i64 = 10;
+const TRANSCRIPT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use nostr::Keys;
+
+    fn contact_message(event_id: i64, display_time: NaiveDateTime) -> ChatMessage {
+        ChatMessage::ContactMessage {
+            content: "hi".into(),
+            author: Keys::generate().public_key(),
+            display_name: "contact".into(),
+            display_time,
+            event_id,
+            status: MessageStatus::Delivered,
+            reactions: vec![],
+            retracted: false,
+        }
+    }
+
+    fn confirmed(display_time: NaiveDateTime) -> ChatMessage {
+        ChatMessage::UserMessage(UserMessage::Confirmed {
+            content: "hello".into(),
+            display_time,
+            event_id: 2,
+            status: MessageStatus::Delivered,
+            reactions: vec![],
+            retracted: false,
+        })
+    }
+
+    #[test]
+    fn reconcile_keeps_position_when_time_differs() {
+        let now = chrono::Utc::now().naive_utc();
+        let id = RequestId::new();
+        let mut messages = vec![
+            contact_message(1, now - Duration::seconds(10)),
+            ChatMessage::local(id, "hello", now),
+            contact_message(3, now + Duration::seconds(1)),
+        ];
+
+        // relay created_at is a few seconds after the contact's answer
+        reconcile_at(&mut messages, 1, confirmed(now + Duration::seconds(3)));
+        assert_eq!(messages[1].event_id(), Some(2));
+        assert_eq!(
+            messages[1].display_time(),
+            Some(&(now + Duration::seconds(1)))
+        );
+
+        let before = messages.iter().map(|m| m.event_id()).collect::<Vec<_>>();
+        messages.sort_by(|a, b| a.display_time().cmp(&b.display_time()));
+        let after = messages.iter().map(|m| m.event_id()).collect::<Vec<_>>();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn flags_messages_sent_twice() {
+        let now = chrono::Utc::now().naive_utc();
+        let first = confirmed(now);
+        assert!(confirmed(now + Duration::seconds(1)).is_duplicate_of(&first));
+        assert!(!confirmed(now + Duration::seconds(DUPLICATE_SECS + 1)).is_duplicate_of(&first));
+        assert!(!contact_message(3, now).is_duplicate_of(&first));
+    }
+
+    #[test]
+    fn local_message_matches_its_request() {
+        let id = RequestId::new();
+        let mut message = ChatMessage::local(id, "hello", chrono::Utc::now().naive_utc());
+        assert!(message.match_local_request(&id));
+        assert!(!message.match_local_request(&RequestId::new()));
+
+        message.set_local_failed();
+        assert!(matches!(
+            message,
+            ChatMessage::UserMessage(UserMessage::Local { failed: true, .. })
+        ));
+    }
+
+    #[test]
+    fn pending_message_is_flagged_when_rejected() {
+        let event_hash = EventId::from_slice(&[2; 32]).unwrap();
+        let mut message = ChatMessage::UserMessage(UserMessage::Pending {
+            event_hash,
+            content: "hello".into(),
+            display_time: None,
+            rejected: false,
+        });
+        assert!(message.match_pending_hash(&event_hash));
+
+        message.set_pending_rejected();
+        assert!(matches!(
+            message,
+            ChatMessage::UserMessage(UserMessage::Pending { rejected: true, .. })
+        ));
+    }
+
+    #[test]
+    fn only_stored_messages_have_reactions() {
+        let now = chrono::Utc::now().naive_utc();
+        let like = ReactionCount {
+            content: LIKE.into(),
+            count: 2,
+            by_user: true,
+        };
+        let mut received = contact_message(1, now);
+        received.set_reactions(vec![like.clone()]);
+        assert_eq!(received.reactions(), &[like.clone()]);
+
+        let mut local = ChatMessage::local(RequestId::new(), "hello", now);
+        local.set_reactions(vec![like]);
+        assert!(local.reactions().is_empty());
+        assert!(local.reactions_row().is_none());
+    }
+
+    #[test]
+    fn transcript_names_each_author() {
+        let now = chrono::Utc::now().naive_utc();
+        let received = contact_message(1, now);
+        let sent = confirmed(now + Duration::seconds(5));
+        let local = ChatMessage::local(RequestId::new(), "draft", now);
+        let pending = ChatMessage::UserMessage(UserMessage::Pending {
+            event_hash: EventId::from_slice(&[1; 32]).unwrap(),
+            content: "on its way".into(),
+            display_time: None,
+            rejected: false,
+        });
+
+        let transcript = transcript(&[&received, &sent, &local, &pending]);
+        let lines: Vec<_> = transcript.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with('[') && lines[0].ends_with("] contact: hi"));
+        assert!(lines[1].ends_with("] You: hello"));
+        assert!(lines[2].ends_with("] You: draft"));
+        assert_eq!(lines[3], "You: on its way");
+    }
+}
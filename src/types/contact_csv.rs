@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use nostr::prelude::ToBech32;
+use nostr::secp256k1::XOnlyPublicKey;
+
+use crate::db::DbContact;
+use crate::types::parse_relay_url;
+
+/// Contact field a column of an imported file is read into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    PublicKey,
+    Petname,
+    RelayUrl,
+    Skip,
+}
+impl CsvColumn {
+    pub const ALL: [CsvColumn; 4] = [
+        CsvColumn::PublicKey,
+        CsvColumn::Petname,
+        CsvColumn::RelayUrl,
+        CsvColumn::Skip,
+    ];
+
+    /// Guessed from the header, e.g. "npub" or "Petname"
+    fn from_header(header: &str) -> Self {
+        let header = header.trim().to_lowercase().replace([' ', '-'], "_");
+        match header.as_str() {
+            "npub" | "pubkey" | "public_key" | "hex" => Self::PublicKey,
+            "petname" | "alias" | "name" => Self::Petname,
+            "relay" | "relay_url" => Self::RelayUrl,
+            _ => Self::Skip,
+        }
+    }
+}
+impl std::fmt::Display for CsvColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsvColumn::PublicKey => write!(f, "Public key"),
+            CsvColumn::Petname => write!(f, "Petname"),
+            CsvColumn::RelayUrl => write!(f, "Relay URL"),
+            CsvColumn::Skip => write!(f, "Skip"),
+        }
+    }
+}
+
+/// Header and records of a CSV file, the first line is always the header
+#[derive(Debug, Clone, Default)]
+pub struct ContactCsv {
+    pub headers: Vec<String>,
+    pub records: Vec<Vec<String>>,
+}
+impl ContactCsv {
+    pub fn parse(content: &str) -> Self {
+        let mut records = parse_records(content.trim_start_matches('\u{feff}'));
+        if records.is_empty() {
+            return Self::default();
+        }
+        let headers = records.remove(0);
+        Self { headers, records }
+    }
+
+    /// One column for each header, only the first match of a field is kept
+    pub fn guess_mapping(&self) -> Vec<CsvColumn> {
+        let mut mapping: Vec<CsvColumn> = vec![];
+        for header in &self.headers {
+            let column = CsvColumn::from_header(header);
+            if column != CsvColumn::Skip && mapping.contains(&column) {
+                mapping.push(CsvColumn::Skip);
+            } else {
+                mapping.push(column);
+            }
+        }
+        mapping
+    }
+
+    /// Valid contacts without duplicates and the records that were skipped
+    pub fn contacts(&self, mapping: &[CsvColumn]) -> (Vec<DbContact>, Vec<String>) {
+        let mut contacts: Vec<DbContact> = vec![];
+        let mut invalid = vec![];
+        let field = |record: &[String], wanted: CsvColumn| {
+            mapping
+                .iter()
+                .position(|column| *column == wanted)
+                .and_then(|idx| record.get(idx))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(str::to_owned)
+        };
+        for record in &self.records {
+            if record.iter().all(|value| value.trim().is_empty()) {
+                continue;
+            }
+            let Some(pubkey) = field(record, CsvColumn::PublicKey) else {
+                invalid.push(record.join(","));
+                continue;
+            };
+            let Ok(mut db_contact) = DbContact::from_pubkey(&pubkey) else {
+                invalid.push(pubkey);
+                continue;
+            };
+            if contacts.iter().any(|c| c.pubkey() == db_contact.pubkey()) {
+                continue;
+            }
+            if let Some(petname) = field(record, CsvColumn::Petname) {
+                db_contact = db_contact.with_petname(&petname);
+            }
+            if let Some(relay_url) = field(record, CsvColumn::RelayUrl) {
+                match parse_relay_url(&relay_url) {
+                    Some(url) => db_contact = db_contact.with_relay_url(url.as_str()),
+                    None => invalid.push(relay_url),
+                }
+            }
+            contacts.push(db_contact);
+        }
+        (contacts, invalid)
+    }
+}
+
+/// Header and one line per contact, dates in UTC
+pub fn contacts_to_csv(
+    contacts: &[DbContact],
+    last_messages: &HashMap<XOnlyPublicKey, NaiveDateTime>,
+) -> String {
+    let mut csv = format!("{}\r\n", EXPORT_HEADERS.join(","));
+    for db_contact in contacts {
+        let pubkey = db_contact.pubkey();
+        let fields = [
+            pubkey.to_bech32().unwrap_or_else(|_| pubkey.to_string()),
+            db_contact.get_petname().unwrap_or_default(),
+            db_contact.get_display_name().unwrap_or_default(),
+            db_contact.get_nip05().unwrap_or_default(),
+            format_date(db_contact.get_created_at()),
+            last_messages
+                .get(pubkey)
+                .map(|date| format_date(*date))
+                .unwrap_or_default(),
+        ];
+        let line: Vec<_> = fields.iter().map(|field| escape_field(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+fn format_date(utc: NaiveDateTime) -> String {
+    utc.format(DATE_FORMAT).to_string()
+}
+
+/// Quoted when it holds a separator, quote or line break
+fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// RFC 4180 records, quoted fields may hold commas, quotes and line breaks
+fn parse_records(content: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => (),
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+const EXPORT_HEADERS: [&str; 6] = [
+    "npub",
+    "petname",
+    "display_name",
+    "nip05",
+    "added",
+    "last_message",
+];
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Keys;
+
+    #[test]
+    fn exported_file_imports_back() {
+        let pubkey = Keys::generate().public_key();
+        let db_contact = DbContact::new(&pubkey).with_petname("Smith, \"J\"");
+        let csv = contacts_to_csv(&[db_contact], &HashMap::new());
+
+        let parsed = ContactCsv::parse(&csv);
+        assert_eq!(parsed.headers, EXPORT_HEADERS);
+        assert_eq!(parsed.records.len(), 1);
+        assert_eq!(parsed.records[0][1], "Smith, \"J\"");
+
+        let mapping = parsed.guess_mapping();
+        assert_eq!(mapping[..2], [CsvColumn::PublicKey, CsvColumn::Petname]);
+        let (contacts, invalid) = parsed.contacts(&mapping);
+        assert!(invalid.is_empty());
+        assert_eq!(contacts[0].pubkey(), &pubkey);
+        assert_eq!(contacts[0].get_petname().as_deref(), Some("Smith, \"J\""));
+    }
+
+    #[test]
+    fn mapped_columns_are_read_and_bad_keys_skipped() {
+        let pubkey = Keys::generate().public_key();
+        let content = format!(
+            "Friend,Key,Where\nAlice,{},wss://relay.example.com\nBob,not-a-key,\n\n",
+            pubkey
+        );
+        let parsed = ContactCsv::parse(&content);
+        let mapping = [
+            CsvColumn::Petname,
+            CsvColumn::PublicKey,
+            CsvColumn::RelayUrl,
+        ];
+        let (contacts, invalid) = parsed.contacts(&mapping);
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].get_petname().as_deref(), Some("Alice"));
+        assert_eq!(
+            contacts[0].get_relay_url().map(|url| url.to_string()),
+            Some("wss://relay.example.com/".to_owned())
+        );
+        assert_eq!(invalid, vec!["not-a-key".to_owned()]);
+    }
+}
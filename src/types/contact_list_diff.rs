@@ -0,0 +1,69 @@
+use crate::db::DbContact;
+
+/// Difference between the last published contact list and the local one
+#[derive(Debug, Clone, Default)]
+pub struct ContactListDiff {
+    pub added: Vec<DbContact>,
+    pub removed: Vec<DbContact>,
+    /// (published, local) pairs with a different petname
+    pub renamed: Vec<(DbContact, DbContact)>,
+}
+
+impl ContactListDiff {
+    pub fn new(published: &[DbContact], local: &[DbContact]) -> Self {
+        let mut diff = Self::default();
+        for contact in local {
+            match published.iter().find(|p| p.pubkey() == contact.pubkey()) {
+                Some(old) => {
+                    if old.get_petname() != contact.get_petname() {
+                        diff.renamed.push((old.to_owned(), contact.to_owned()));
+                    }
+                }
+                None => diff.added.push(contact.to_owned()),
+            }
+        }
+        diff.removed = published
+            .iter()
+            .filter(|p| !local.iter().any(|c| c.pubkey() == p.pubkey()))
+            .cloned()
+            .collect();
+        diff
+    }
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.renamed.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(petname: &str) -> DbContact {
+        DbContact::new(&nostr::Keys::generate().public_key()).with_petname(petname)
+    }
+
+    #[test]
+    fn same_lists_are_empty() {
+        let list = vec![contact("alice"), contact("bob")];
+        assert!(ContactListDiff::new(&list, &list).is_empty());
+    }
+
+    #[test]
+    fn finds_added_removed_and_renamed() {
+        let alice = contact("alice");
+        let bob = contact("bob");
+        let carol = contact("carol");
+        let published = vec![alice.clone(), bob.clone()];
+        let local = vec![alice.clone().with_petname("ally"), carol.clone()];
+
+        let diff = ContactListDiff::new(&published, &local);
+
+        assert_eq!(diff.len(), 3);
+        assert_eq!(diff.added[0].pubkey(), carol.pubkey());
+        assert_eq!(diff.removed[0].pubkey(), bob.pubkey());
+        assert_eq!(diff.renamed[0].1.get_petname(), Some("ally".into()));
+    }
+}
@@ -0,0 +1,29 @@
+use nostr::secp256k1::XOnlyPublicKey;
+
+use crate::db::DbContact;
+
+/// Contact change requested by the user.
+/// Views apply it right away and wait for the backend to confirm or reject it.
+#[derive(Debug, Clone)]
+pub enum ContactOp {
+    Add(DbContact),
+    Update(DbContact),
+    Delete(DbContact),
+}
+impl ContactOp {
+    pub fn contact(&self) -> &DbContact {
+        match self {
+            ContactOp::Add(c) | ContactOp::Update(c) | ContactOp::Delete(c) => c,
+        }
+    }
+    pub fn pubkey(&self) -> &XOnlyPublicKey {
+        self.contact().pubkey()
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            ContactOp::Add(_) => "add",
+            ContactOp::Update(_) => "update",
+            ContactOp::Delete(_) => "delete",
+        }
+    }
+}
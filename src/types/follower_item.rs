@@ -0,0 +1,11 @@
+use crate::db::{DbContact, DbFollower};
+
+/// "X added you" entry of the activity panel
+#[derive(Debug, Clone)]
+pub struct FollowerItem {
+    pub follower: DbFollower,
+    /// Contact with the cached profile, a bare one when unknown
+    pub contact: DbContact,
+    /// Already in the user's contact list
+    pub followed_back: bool,
+}
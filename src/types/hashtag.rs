@@ -0,0 +1,117 @@
+use chrono::NaiveDateTime;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Kind, Tag};
+
+use crate::utils::{channel_id_from_tags, ns_event_to_naive};
+
+/// Hashtag as it's stored and compared: without the `#`, in lowercase
+pub fn normalize_hashtag(hashtag: &str) -> Option<String> {
+    let hashtag = hashtag.trim().trim_start_matches('#');
+    is_hashtag(hashtag).then(|| hashtag.to_lowercase())
+}
+
+/// Letters, digits and underscores, not only digits so "#1" is not a hashtag
+fn is_hashtag(word: &str) -> bool {
+    !word.is_empty()
+        && word.chars().count() <= MAX_HASHTAG_CHARS
+        && word.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !word.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Hashtag written at the start of `word` without the `#` and the rest of the word,
+/// e.g. "#rust," gives "rust" and ","
+pub fn split_inline_hashtag(word: &str) -> Option<(&str, &str)> {
+    let tail = word.strip_prefix('#')?;
+    let end = tail
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(tail.len());
+    let (hashtag, rest) = tail.split_at(end);
+    is_hashtag(hashtag).then_some((hashtag, rest))
+}
+
+/// Normalized hashtags of the `t` tags and the content, without repeats
+pub fn hashtags(content: &str, tags: &[Tag]) -> Vec<String> {
+    let tagged = tags.iter().filter_map(|tag| match tag {
+        Tag::Hashtag(hashtag) => normalize_hashtag(hashtag),
+        _ => None,
+    });
+    let inline = content
+        .split_whitespace()
+        .filter_map(|word| split_inline_hashtag(word).map(|(hashtag, _)| hashtag.to_lowercase()));
+    let mut hashtags: Vec<String> = vec![];
+    for hashtag in tagged.chain(inline) {
+        if !hashtags.contains(&hashtag) {
+            hashtags.push(hashtag);
+        }
+    }
+    hashtags
+}
+
+/// `t` tags for the hashtags written in the content, so other clients can find it
+pub fn hashtag_tags(content: &str) -> Vec<Tag> {
+    hashtags(content, &[])
+        .into_iter()
+        .map(Tag::Hashtag)
+        .collect()
+}
+
+/// Note or channel message found through a hashtag, shown without being stored
+#[derive(Debug, Clone)]
+pub struct HashtagNote {
+    pub event_id: EventId,
+    pub author: XOnlyPublicKey,
+    /// Name from the author's cached profile
+    pub author_name: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub content: String,
+    /// Channel of a channel message, `None` for notes
+    pub channel_id: Option<EventId>,
+    pub hashtags: Vec<String>,
+}
+impl HashtagNote {
+    pub fn from_ns_event(
+        ns_event: &nostr::Event,
+        author_name: Option<String>,
+    ) -> Result<Self, crate::utils::Error> {
+        let channel_id = match ns_event.kind {
+            Kind::ChannelMessage => channel_id_from_tags(&ns_event.tags),
+            _ => None,
+        };
+        Ok(Self {
+            event_id: ns_event.id,
+            author: ns_event.pubkey,
+            author_name,
+            created_at: ns_event_to_naive(ns_event.created_at)?,
+            content: ns_event.content.to_owned(),
+            channel_id,
+            hashtags: hashtags(&ns_event.content, &ns_event.tags),
+        })
+    }
+
+    pub fn has_hashtag(&self, hashtag: &str) -> bool {
+        self.hashtags.iter().any(|h| h == hashtag)
+    }
+}
+
+const MAX_HASHTAG_CHARS: usize = 64;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tagged_and_inline_hashtags() {
+        assert_eq!(split_inline_hashtag("#Rust,"), Some(("Rust", ",")));
+        assert_eq!(split_inline_hashtag("#1"), None);
+        assert_eq!(split_inline_hashtag("#"), None);
+        assert_eq!(split_inline_hashtag("a#b"), None);
+        assert_eq!(normalize_hashtag(" #Nostr "), Some("nostr".into()));
+        assert_eq!(normalize_hashtag("two words"), None);
+
+        let tags = vec![Tag::Hashtag("Bitcoin".into()), Tag::Hashtag("rust".into())];
+        assert_eq!(
+            hashtags("learning #rust and #café #1 today", &tags),
+            vec!["bitcoin", "rust", "café"]
+        );
+    }
+}
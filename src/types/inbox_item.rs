@@ -0,0 +1,56 @@
+use chrono::NaiveDateTime;
+use nostr::EventId;
+
+use crate::db::DbContact;
+
+use super::ChatMessage;
+
+/// Unread DM or channel mention shown in the inbox
+#[derive(Debug, Clone)]
+pub enum InboxItem {
+    DM {
+        contact: DbContact,
+        message: ChatMessage,
+    },
+    Mention {
+        channel_id: EventId,
+        channel_name: Option<String>,
+        message: ChatMessage,
+        /// Watched word found in the message, `None` when the user was mentioned
+        keyword: Option<String>,
+    },
+}
+impl InboxItem {
+    pub fn message(&self) -> &ChatMessage {
+        match self {
+            InboxItem::DM { message, .. } | InboxItem::Mention { message, .. } => message,
+        }
+    }
+    pub fn display_time(&self) -> Option<&NaiveDateTime> {
+        self.message().display_time()
+    }
+    pub fn event_id(&self) -> Option<i64> {
+        self.message().event_id()
+    }
+    /// Where the item came from, contact or channel name
+    pub fn source_name(&self) -> String {
+        match self {
+            InboxItem::DM { contact, .. } => contact.select_name(),
+            InboxItem::Mention {
+                channel_id,
+                channel_name,
+                ..
+            } => match channel_name {
+                Some(name) => format!("#{}", name),
+                None => format!("#{}", &channel_id.to_hex()[..CHANNEL_ID_PREVIEW]),
+            },
+        }
+    }
+}
+
+/// Newest first
+pub fn sort_inbox(items: &mut [InboxItem]) {
+    items.sort_by(|a, b| b.display_time().cmp(&a.display_time()));
+}
+
+const CHANNEL_ID_PREVIEW: usize = 8;
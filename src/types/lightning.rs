@@ -0,0 +1,172 @@
+use nostr::Metadata;
+use thiserror::Error;
+use url::Url;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Invalid lightning address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Invalid LNURL: {0}")]
+    InvalidLnurl(String),
+
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+}
+
+/// Lightning address of a profile, lud16 is preferred over lud06
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LightningAddress {
+    /// `name@domain`
+    Lud16(String),
+    /// bech32 encoded LNURL
+    Lud06(String),
+}
+
+impl LightningAddress {
+    pub fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        let non_empty = |s: &Option<String>| {
+            s.as_ref()
+                .map(|s| s.trim().to_owned())
+                .filter(|s| !s.is_empty())
+        };
+        non_empty(&metadata.lud16)
+            .map(Self::Lud16)
+            .or_else(|| non_empty(&metadata.lud06).map(Self::Lud06))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Lud16(address) | Self::Lud06(address) => address,
+        }
+    }
+
+    /// LNURL-pay endpoint the wallet will call
+    pub fn endpoint(&self) -> Result<Url, Error> {
+        match self {
+            Self::Lud16(address) => {
+                let (name, domain) = address
+                    .split_once('@')
+                    .filter(|(name, domain)| !name.is_empty() && domain.contains('.'))
+                    .ok_or_else(|| Error::InvalidAddress(address.to_owned()))?;
+                Ok(Url::parse(&format!(
+                    "https://{}/.well-known/lnurlp/{}",
+                    domain.to_lowercase(),
+                    name.to_lowercase()
+                ))?)
+            }
+            Self::Lud06(lnurl) => Ok(Url::parse(&decode_lnurl(lnurl)?)?),
+        }
+    }
+
+    /// Opens the zap/invoice flow in the user's wallet
+    pub fn wallet_uri(&self) -> String {
+        format!("lightning:{}", self.as_str())
+    }
+}
+
+impl std::fmt::Display for LightningAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Decodes a bech32 `lnurl1...` string into the URL it wraps
+pub fn decode_lnurl(lnurl: &str) -> Result<String, Error> {
+    let invalid = || Error::InvalidLnurl(lnurl.to_owned());
+    let lower = lnurl.trim().to_lowercase();
+    let (hrp, data) = lower.rsplit_once('1').ok_or_else(invalid)?;
+    if hrp != "lnurl" || data.len() < 6 {
+        return Err(invalid());
+    }
+    let values = data
+        .chars()
+        .map(|c| BECH32_CHARSET.find(c).map(|i| i as u8))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+
+    let mut checked: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    checked.push(0);
+    checked.extend(hrp.bytes().map(|b| b & 31));
+    checked.extend(&values);
+    if bech32_polymod(&checked) != 1 {
+        return Err(invalid());
+    }
+
+    // 5 bit groups to bytes, leftover padding bits are dropped
+    let mut bytes = vec![];
+    let (mut acc, mut bits) = (0u32, 0);
+    for value in &values[..values.len() - 6] {
+        acc = (acc << 5) | *value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    let mut chk = 1u32;
+    for value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ff_ffff) << 5) ^ *value as u32;
+        for (i, gen) in BECH32_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= gen;
+            }
+        }
+    }
+    chk
+}
+
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [
+    0x3b6a_57b2,
+    0x2650_8e6d,
+    0x1ea1_19fa,
+    0x3d42_33dd,
+    0x2a14_62b3,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // LUD-01 example
+    const LNURL: &str = "LNURL1DP68GURN8GHJ7UM9WFMXJCM99E3K7MF0V9CXJ0M385EKVCENXC6R2C35XVUKXEFCV5MKVV34X5EKZD3EV56NYD3HXQURZEPEXEJXXEPNXSCRVWFNV9NXZCN9XQ6XYEFHVGCXXCMYXYMNSERXFQ5FNS";
+
+    #[test]
+    fn decodes_lnurl() {
+        assert_eq!(
+            decode_lnurl(LNURL).unwrap(),
+            "https://service.com/api?q=3fc3645b439ce8e7f2553a69e5267081d96dcd340693afabe04be7b0ccd178df"
+        );
+        let mut corrupted = LNURL.to_owned();
+        corrupted.replace_range(10..11, "Q");
+        assert!(decode_lnurl(&corrupted).is_err());
+        assert!(decode_lnurl("npub1xyz").is_err());
+    }
+
+    #[test]
+    fn prefers_lud16() {
+        let metadata = Metadata::new().lud06(LNURL).lud16("Satoshi@Example.com");
+        let address = LightningAddress::from_metadata(&metadata).unwrap();
+        assert_eq!(
+            address,
+            LightningAddress::Lud16("Satoshi@Example.com".into())
+        );
+        assert_eq!(
+            address.endpoint().unwrap().as_str(),
+            "https://example.com/.well-known/lnurlp/satoshi"
+        );
+
+        let metadata = Metadata::new().lud06(LNURL).lud16(" ");
+        let address = LightningAddress::from_metadata(&metadata).unwrap();
+        assert_eq!(address.endpoint().unwrap().host_str(), Some("service.com"));
+
+        assert!(LightningAddress::Lud16("nobody".into()).endpoint().is_err());
+        assert_eq!(LightningAddress::from_metadata(&Metadata::new()), None);
+    }
+}
@@ -0,0 +1,111 @@
+/// Block of a message written with a markdown subset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Level from 1 to 6
+    Heading(u8, String),
+    Bullet(String),
+    Quote(String),
+    /// Fenced with ```, kept as typed
+    Code(String),
+    Paragraph(String),
+}
+
+/// Splits the content in blocks, inline emphasis markers are removed
+pub fn parse(content: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut paragraph: Vec<String> = vec![];
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in content.lines() {
+        if let Some(code_lines) = &mut code {
+            if line.trim_start().starts_with("```") {
+                blocks.push(Block::Code(code_lines.join("\n")));
+                code = None;
+            } else {
+                code_lines.push(line);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+        let block = if trimmed.starts_with("```") {
+            code = Some(vec![]);
+            None
+        } else if trimmed.is_empty() {
+            None
+        } else if let Some((level, title)) = heading(trimmed) {
+            Some(Block::Heading(level, strip_inline(title)))
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            Some(Block::Bullet(strip_inline(item)))
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            Some(Block::Quote(strip_inline(quote.trim_start())))
+        } else {
+            paragraph.push(strip_inline(trimmed));
+            continue;
+        };
+
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(paragraph.join("\n")));
+            paragraph.clear();
+        }
+        blocks.extend(block);
+    }
+
+    if !paragraph.is_empty() {
+        blocks.push(Block::Paragraph(paragraph.join("\n")));
+    }
+    // an unclosed fence still shows what was typed
+    if let Some(code_lines) = code {
+        blocks.push(Block::Code(code_lines.join("\n")));
+    }
+    blocks
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ')?;
+    Some((level as u8, title.trim()))
+}
+
+fn strip_inline(line: &str) -> String {
+    line.replace("**", "").replace("__", "").replace('`', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_blocks() {
+        let content = "## Plan\nfirst **bold** line\nsecond line\n\n- one\n* two\n> quoted\n```\nlet x = 1;\n```\n#hashtag";
+        assert_eq!(
+            parse(content),
+            vec![
+                Block::Heading(2, "Plan".into()),
+                Block::Paragraph("first bold line\nsecond line".into()),
+                Block::Bullet("one".into()),
+                Block::Bullet("two".into()),
+                Block::Quote("quoted".into()),
+                Block::Code("let x = 1;".into()),
+                Block::Paragraph("#hashtag".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_fence_is_code() {
+        assert_eq!(
+            parse("text\n```\nfn main() {}"),
+            vec![
+                Block::Paragraph("text".into()),
+                Block::Code("fn main() {}".into())
+            ]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use nostr::Metadata;
+
+/// Standard profile metadata fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileField {
+    Name,
+    DisplayName,
+    About,
+    Picture,
+    Banner,
+    Website,
+    Nip05,
+    Lud06,
+    Lud16,
+}
+impl ProfileField {
+    pub const ALL: [ProfileField; 9] = [
+        ProfileField::Name,
+        ProfileField::DisplayName,
+        ProfileField::About,
+        ProfileField::Picture,
+        ProfileField::Banner,
+        ProfileField::Website,
+        ProfileField::Nip05,
+        ProfileField::Lud06,
+        ProfileField::Lud16,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProfileField::Name => "Name",
+            ProfileField::DisplayName => "Display name",
+            ProfileField::About => "About",
+            ProfileField::Picture => "Picture",
+            ProfileField::Banner => "Banner",
+            ProfileField::Website => "Website",
+            ProfileField::Nip05 => "Nostr address",
+            ProfileField::Lud06 => "LNURL",
+            ProfileField::Lud16 => "Lightning address",
+        }
+    }
+
+    /// Blank values count as unset
+    pub fn value(&self, meta: &Metadata) -> Option<String> {
+        let value = match self {
+            ProfileField::Name => &meta.name,
+            ProfileField::DisplayName => &meta.display_name,
+            ProfileField::About => &meta.about,
+            ProfileField::Picture => &meta.picture,
+            ProfileField::Banner => &meta.banner,
+            ProfileField::Website => &meta.website,
+            ProfileField::Nip05 => &meta.nip05,
+            ProfileField::Lud06 => &meta.lud06,
+            ProfileField::Lud16 => &meta.lud16,
+        };
+        value
+            .as_ref()
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: ProfileField,
+    pub published: Option<String>,
+    pub edited: Option<String>,
+}
+
+/// Fields that differ between the published profile and the edited one
+#[derive(Debug, Clone, Default)]
+pub struct MetadataDiff {
+    pub changes: Vec<FieldChange>,
+}
+impl MetadataDiff {
+    /// Every set field is a change when nothing was published
+    pub fn new(published: Option<&Metadata>, edited: &Metadata) -> Self {
+        let changes = ProfileField::ALL
+            .iter()
+            .filter_map(|field| {
+                let published = published.and_then(|meta| field.value(meta));
+                let edited = field.value(edited);
+                (published != edited).then_some(FieldChange {
+                    field: *field,
+                    published,
+                    edited,
+                })
+            })
+            .collect();
+        Self { changes }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+    pub fn has_change(&self, field: ProfileField) -> bool {
+        self.changes.iter().any(|change| change.field == field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_fields_are_unset() {
+        let published = Metadata::new().name("alice");
+        let edited = Metadata::new().name("alice ").about("").display_name("");
+        assert!(MetadataDiff::new(Some(&published), &edited).is_empty());
+    }
+
+    #[test]
+    fn lists_changed_fields() {
+        let published = Metadata::new().name("alice").about("hi");
+        let edited = Metadata::new().name("alice").nip05("alice@example.com");
+
+        let diff = MetadataDiff::new(Some(&published), &edited);
+
+        assert_eq!(
+            diff.changes,
+            vec![
+                FieldChange {
+                    field: ProfileField::About,
+                    published: Some("hi".into()),
+                    edited: None,
+                },
+                FieldChange {
+                    field: ProfileField::Nip05,
+                    published: None,
+                    edited: Some("alice@example.com".into()),
+                },
+            ]
+        );
+        assert_eq!(MetadataDiff::new(None, &edited).changes.len(), 2);
+    }
+}
@@ -1,13 +1,47 @@
+pub(crate) mod account_preview;
 pub(crate) mod backend_state;
 pub(crate) mod channel_metadata;
 mod channel_result;
 pub(crate) mod chat_message;
+mod contact_csv;
+mod contact_list_diff;
+mod contact_op;
 mod event;
+mod follower_item;
+pub(crate) mod hashtag;
+mod inbox_item;
+pub(crate) mod lightning;
+pub(crate) mod markdown;
+mod metadata_diff;
+mod nostr_link;
+mod pairing;
+mod profile_image;
+mod relay_deletion;
+mod relay_list;
+pub(crate) mod slash_command;
 mod subscription_type;
 
+pub use account_preview::AccountPreview;
 pub use backend_state::{BackendState, PendingEvent};
 pub use channel_metadata::ChannelMetadata;
 pub(crate) use channel_result::ChannelResult;
 pub use chat_message::{ChatMessage, UserMessage};
+pub use contact_csv::{contacts_to_csv, ContactCsv, CsvColumn};
+pub use contact_list_diff::ContactListDiff;
+pub use contact_op::ContactOp;
 pub(crate) use event::UncheckedEvent;
+pub use follower_item::FollowerItem;
+pub use hashtag::HashtagNote;
+pub use inbox_item::{sort_inbox, InboxItem};
+pub use lightning::LightningAddress;
+pub use metadata_diff::{FieldChange, MetadataDiff, ProfileField};
+pub use nostr_link::NostrLink;
+pub use pairing::{is_pairing_payload, open_pairing, Pairing};
+pub use profile_image::{CropRect, ProfileImage};
+pub use relay_deletion::{DeletionStatus, RelayDeletion, DELETION_NIP};
+pub use relay_list::{
+    parse_relay_url, relay_list_builder, relays_to_text, ImportedRelay, RelayExportFormat,
+    RelayList,
+};
+pub use slash_command::{CommandAction, SlashCommand};
 pub use subscription_type::{PrefixedId, SubName};
@@ -0,0 +1,67 @@
+use nostr::prelude::{FromBech32, Profile};
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::EventId;
+
+use crate::consts::NOSTR_URI_PREFIX;
+use crate::utils::parse_event_link;
+
+/// What a `nostr:` URI points to, with the relay hints it carried
+#[derive(Debug, Clone, PartialEq)]
+pub enum NostrLink {
+    /// `npub` or `nprofile`
+    Profile(XOnlyPublicKey, Vec<String>),
+    /// `note` or `nevent`, a channel or a message
+    Event(EventId, Vec<String>),
+}
+impl NostrLink {
+    /// Accepts the bech32 entity with or without the `nostr:` prefix
+    pub fn parse(uri: &str) -> Option<Self> {
+        let entity = uri.trim();
+        let entity = entity.strip_prefix(NOSTR_URI_PREFIX).unwrap_or(entity);
+        let entity = entity.trim_start_matches('/');
+        if entity.starts_with("npub") {
+            XOnlyPublicKey::from_bech32(entity)
+                .ok()
+                .map(|pubkey| Self::Profile(pubkey, vec![]))
+        } else if entity.starts_with("nprofile") {
+            Profile::from_bech32(entity)
+                .ok()
+                .map(|profile| Self::Profile(profile.public_key, profile.relays))
+        } else {
+            parse_event_link(entity).map(|(event_id, relays)| Self::Event(event_id, relays))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::prelude::ToBech32;
+    use nostr::Keys;
+
+    #[test]
+    fn parses_profiles_and_events() {
+        let pubkey = Keys::generate().public_key();
+        let npub = pubkey.to_bech32().unwrap();
+        assert_eq!(
+            NostrLink::parse(&format!("nostr:{}", npub)),
+            Some(NostrLink::Profile(pubkey, vec![]))
+        );
+
+        let relays = vec!["wss://relay.damus.io".to_string()];
+        let nprofile = Profile::new(pubkey, relays.clone()).to_bech32().unwrap();
+        assert_eq!(
+            NostrLink::parse(&format!("nostr://{}", nprofile)),
+            Some(NostrLink::Profile(pubkey, relays))
+        );
+
+        let event_id = EventId::from_slice(&[1; 32]).unwrap();
+        let note = event_id.to_bech32().unwrap();
+        assert_eq!(
+            NostrLink::parse(&note),
+            Some(NostrLink::Event(event_id, vec![]))
+        );
+
+        assert!(NostrLink::parse("nostr:hello").is_none());
+    }
+}
@@ -0,0 +1,212 @@
+//! Pairing payload to log in on another device without copying the nsec.
+//! The secret key is sealed with ChaCha20-Poly1305 under a key stretched with
+//! scrypt from a 16 character code (about 79 bits), shown next to the QR but
+//! never put in it. The expiry is authenticated along with the key, so it
+//! can't be pushed back without the code, and the payload is refused after it.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use nostr::secp256k1::SecretKey;
+use nostr::{Keys, Timestamp};
+use rand::Rng;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Not a pairing payload")]
+    InvalidPayload,
+
+    #[error("The pairing payload expired, create a new one on the other device")]
+    Expired,
+
+    #[error("Wrong pairing code")]
+    WrongCode,
+
+    #[error("Key derivation failed")]
+    KeyDerivation,
+
+    #[error("Encryption failed")]
+    Encryption,
+
+    #[error("{0}")]
+    Key(#[from] nostr::key::Error),
+}
+
+/// Shown on the device that has the account
+#[derive(Debug, Clone)]
+pub struct Pairing {
+    /// Goes in the QR
+    pub payload: String,
+    /// Typed on the new device, e.g. "K7QM-3XPA-WN4H-T8RC"
+    pub code: String,
+    pub expires_at: Timestamp,
+}
+
+impl Pairing {
+    /// Slow on purpose, run it off the async runtime
+    pub fn new(keys: &Keys, now: Timestamp) -> Result<Self, Error> {
+        let code = new_code();
+        let expires_at = Timestamp::from(now.as_u64() + PAIRING_TTL_SECS);
+        let mut rng = rand::thread_rng();
+        let salt: [u8; SALT_LEN] = rng.gen();
+        let nonce: [u8; NONCE_LEN] = rng.gen();
+
+        let cipher = code_cipher(&code, &salt)?;
+        let sealed = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &keys.secret_key()?.secret_bytes(),
+                    aad: &associated_data(expires_at.as_u64()),
+                },
+            )
+            .map_err(|_| Error::Encryption)?;
+        let payload = format!(
+            "{}{}:{}:{}:{}",
+            PAIRING_PREFIX,
+            URL_SAFE_NO_PAD.encode(salt),
+            URL_SAFE_NO_PAD.encode(nonce),
+            expires_at.as_u64(),
+            URL_SAFE_NO_PAD.encode(sealed)
+        );
+
+        Ok(Self {
+            payload,
+            code,
+            expires_at,
+        })
+    }
+}
+
+/// Keys of the account paired with `payload`, slow like [`Pairing::new`]
+pub fn open_pairing(payload: &str, code: &str, now: Timestamp) -> Result<Keys, Error> {
+    let parts: Vec<_> = payload
+        .trim()
+        .strip_prefix(PAIRING_PREFIX)
+        .ok_or(Error::InvalidPayload)?
+        .split(':')
+        .collect();
+    let [salt, nonce, expires_at, sealed] = parts[..] else {
+        return Err(Error::InvalidPayload);
+    };
+    let decode = |part: &str| {
+        URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|_| Error::InvalidPayload)
+    };
+    let (salt, nonce, sealed) = (decode(salt)?, decode(nonce)?, decode(sealed)?);
+    if nonce.len() != NONCE_LEN {
+        return Err(Error::InvalidPayload);
+    }
+    let expires_at: u64 = expires_at.parse().map_err(|_| Error::InvalidPayload)?;
+    if now.as_u64() > expires_at {
+        return Err(Error::Expired);
+    }
+
+    // also fails when the expiry was edited
+    let secret_key = code_cipher(code, &salt)?
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &sealed,
+                aad: &associated_data(expires_at),
+            },
+        )
+        .map_err(|_| Error::WrongCode)?;
+    let secret_key = SecretKey::from_slice(&secret_key).map_err(|_| Error::InvalidPayload)?;
+    Ok(Keys::new(secret_key))
+}
+
+pub fn is_pairing_payload(text: &str) -> bool {
+    text.trim().starts_with(PAIRING_PREFIX)
+}
+
+/// Random code without look-alike characters, in groups of four
+fn new_code() -> String {
+    let mut rng = rand::thread_rng();
+    let chars: Vec<char> = (0..CODE_LEN)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect();
+    chars
+        .chunks(CODE_GROUP_LEN)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Cipher keyed by the code, as typed in any case and with or without the dashes
+fn code_cipher(code: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, Error> {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, KEY_LEN)
+        .map_err(|_| Error::KeyDerivation)?;
+    let mut key = [0u8; KEY_LEN];
+    scrypt::scrypt(normalized.as_bytes(), salt, &params, &mut key)
+        .map_err(|_| Error::KeyDerivation)?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn associated_data(expires_at: u64) -> Vec<u8> {
+    format!("{}{}", PAIRING_PREFIX, expires_at).into_bytes()
+}
+
+const PAIRING_PREFIX: &str = "nostrtalk-pair:";
+const PAIRING_TTL_SECS: u64 = 5 * 60;
+const CODE_LEN: usize = 16;
+const CODE_GROUP_LEN: usize = 4;
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+/// 32 MiB and a few hundred milliseconds per guess
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_with_the_code_until_it_expires() {
+        let keys = Keys::generate();
+        let now = Timestamp::from(1_700_000_000);
+        let pairing = Pairing::new(&keys, now).unwrap();
+        assert!(is_pairing_payload(&pairing.payload));
+        assert!(!pairing
+            .payload
+            .contains(&keys.secret_key().unwrap().display_secret().to_string()));
+
+        let typed = pairing.code.to_lowercase().replace('-', "");
+        let opened = open_pairing(&pairing.payload, &typed, now).unwrap();
+        assert_eq!(opened.public_key(), keys.public_key());
+
+        assert_eq!(pairing.code.len(), CODE_LEN + 3);
+        assert!(matches!(
+            open_pairing(&pairing.payload, "AAAA-AAAA-AAAA-AAAA", now),
+            Err(Error::WrongCode)
+        ));
+        let expiry = pairing.expires_at.as_u64().to_string();
+        let extended = pairing
+            .payload
+            .replace(&expiry, &(pairing.expires_at.as_u64() + 3600).to_string());
+        assert!(matches!(
+            open_pairing(&extended, &pairing.code, now),
+            Err(Error::WrongCode)
+        ));
+        let later = Timestamp::from(now.as_u64() + PAIRING_TTL_SECS + 1);
+        assert!(matches!(
+            open_pairing(&pairing.payload, &pairing.code, later),
+            Err(Error::Expired)
+        ));
+        assert!(matches!(
+            open_pairing("nsec1abc", &pairing.code, now),
+            Err(Error::InvalidPayload)
+        ));
+    }
+}
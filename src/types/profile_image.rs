@@ -0,0 +1,118 @@
+/// Image of the user's profile that can be uploaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileImage {
+    Picture,
+    Banner,
+}
+impl ProfileImage {
+    /// Width over height of the crop
+    pub fn aspect_ratio(&self) -> f32 {
+        match self {
+            ProfileImage::Picture => 1.0,
+            ProfileImage::Banner => 3.0,
+        }
+    }
+    /// Size the crop is resized to before uploading, in pixels
+    pub fn output_size(&self) -> (u32, u32) {
+        match self {
+            ProfileImage::Picture => (PICTURE_SIZE, PICTURE_SIZE),
+            ProfileImage::Banner => (BANNER_WIDTH, BANNER_WIDTH / 3),
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProfileImage::Picture => "picture",
+            ProfileImage::Banner => "banner",
+        }
+    }
+}
+
+/// Area of the image kept, in image pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+impl CropRect {
+    /// Largest centered area with the aspect ratio
+    pub fn centered(image_width: u32, image_height: u32, aspect_ratio: f32) -> Self {
+        let (image_width, image_height) = (image_width as f32, image_height as f32);
+        let width = image_width.min(image_height * aspect_ratio);
+        let height = width / aspect_ratio;
+        Self {
+            x: (image_width - width) / 2.0,
+            y: (image_height - height) / 2.0,
+            width,
+            height,
+        }
+    }
+
+    /// Moved by the offset, staying inside the image
+    pub fn moved(&self, dx: f32, dy: f32, image_width: u32, image_height: u32) -> Self {
+        Self {
+            x: (self.x + dx).clamp(0.0, (image_width as f32 - self.width).max(0.0)),
+            y: (self.y + dy).clamp(0.0, (image_height as f32 - self.height).max(0.0)),
+            ..*self
+        }
+    }
+
+    /// Resized around its center by `factor`, between `MIN_CROP_SIDE` and the image size
+    pub fn zoomed(&self, factor: f32, image_width: u32, image_height: u32) -> Self {
+        let aspect_ratio = self.width / self.height;
+        let max = Self::centered(image_width, image_height, aspect_ratio);
+        let min_width = MIN_CROP_SIDE
+            .max(MIN_CROP_SIDE * aspect_ratio)
+            .min(max.width);
+        let width = (self.width * factor).clamp(min_width, max.width);
+        let height = width / aspect_ratio;
+        let resized = Self {
+            x: self.x + (self.width - width) / 2.0,
+            y: self.y + (self.height - height) / 2.0,
+            width,
+            height,
+        };
+        // clamps the position
+        resized.moved(0.0, 0.0, image_width, image_height)
+    }
+
+    /// Whole pixels inside the image as (x, y, width, height)
+    pub fn pixels(&self, image_width: u32, image_height: u32) -> (u32, u32, u32, u32) {
+        let x = (self.x.max(0.0) as u32).min(image_width.saturating_sub(1));
+        let y = (self.y.max(0.0) as u32).min(image_height.saturating_sub(1));
+        let width = (self.width as u32).clamp(1, (image_width - x).max(1));
+        let height = (self.height as u32).clamp(1, (image_height - y).max(1));
+        (x, y, width, height)
+    }
+}
+
+const PICTURE_SIZE: u32 = 400;
+const BANNER_WIDTH: u32 = 1500;
+const MIN_CROP_SIDE: f32 = 32.0;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centered_crop_fits_the_image() {
+        let crop = CropRect::centered(800, 400, 1.0);
+        assert_eq!(crop.pixels(800, 400), (200, 0, 400, 400));
+
+        let crop = CropRect::centered(600, 600, ProfileImage::Banner.aspect_ratio());
+        assert_eq!(crop.pixels(600, 600), (0, 200, 600, 200));
+    }
+
+    #[test]
+    fn stays_inside_the_image() {
+        let crop = CropRect::centered(800, 400, 1.0);
+        assert_eq!(crop.moved(1000.0, -50.0, 800, 400).pixels(800, 400).0, 400);
+
+        let zoomed_out = crop.zoomed(2.0, 800, 400);
+        assert_eq!(zoomed_out.width, 400.0);
+
+        let zoomed_in = crop.zoomed(0.5, 800, 400);
+        assert_eq!(zoomed_in.pixels(800, 400), (300, 100, 200, 200));
+    }
+}
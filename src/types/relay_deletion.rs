@@ -0,0 +1,199 @@
+use nostr::EventId;
+use url::Url;
+
+use super::PrefixedId;
+
+/// NIP-09, event deletion
+pub const DELETION_NIP: u16 = 9;
+
+/// Progress of a deletion request on one relay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeletionStatus {
+    /// The relay doesn't advertise NIP-09, nothing is verified
+    NotSupported,
+    /// Waiting for the relay to accept the deletion
+    Sent,
+    /// The relay refused the deletion with this reason
+    Rejected(String),
+    /// Asking the relay for the event again
+    Verifying,
+    /// The relay no longer returns the event
+    Deleted,
+    /// The relay still returns the event
+    StillServed,
+    /// The verification request timed out
+    NoAnswer,
+}
+impl DeletionStatus {
+    pub fn is_final(&self) -> bool {
+        !matches!(self, DeletionStatus::Sent | DeletionStatus::Verifying)
+    }
+}
+impl std::fmt::Display for DeletionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeletionStatus::NotSupported => write!(f, "Deletion not supported"),
+            DeletionStatus::Sent => write!(f, "Sent"),
+            DeletionStatus::Rejected(reason) => write!(f, "Refused: {}", reason),
+            DeletionStatus::Verifying => write!(f, "Verifying..."),
+            DeletionStatus::Deleted => write!(f, "Deleted"),
+            DeletionStatus::StillServed => write!(f, "Still returns the event"),
+            DeletionStatus::NoAnswer => write!(f, "No answer"),
+        }
+    }
+}
+
+/// Deletion of one of the user's events, followed relay by relay
+#[derive(Debug, Clone)]
+pub struct RelayDeletion {
+    /// Database id of the deleted event
+    pub event_id: i64,
+    pub event_hash: EventId,
+    /// Hash of the published kind 5 event
+    pub deletion_hash: Option<EventId>,
+    pub relays: Vec<(Url, DeletionStatus)>,
+}
+impl RelayDeletion {
+    /// `support` tells for each relay whether it advertises NIP-09
+    pub fn new(event_id: i64, event_hash: EventId, support: Vec<(Url, bool)>) -> Self {
+        let relays = support
+            .into_iter()
+            .map(|(url, supported)| {
+                let status = if supported {
+                    DeletionStatus::Sent
+                } else {
+                    DeletionStatus::NotSupported
+                };
+                (url, status)
+            })
+            .collect();
+        Self {
+            event_id,
+            event_hash,
+            deletion_hash: None,
+            relays,
+        }
+    }
+
+    /// Whether any relay can be asked to delete the event
+    pub fn is_supported(&self) -> bool {
+        self.relays
+            .iter()
+            .any(|(_, status)| status != &DeletionStatus::NotSupported)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.relays.iter().all(|(_, status)| status.is_final())
+    }
+
+    pub fn matches(&self, prefixed: &PrefixedId) -> bool {
+        &PrefixedId::new(&self.event_hash.to_hex()) == prefixed
+    }
+
+    /// The relay answered the deletion, `Ok` starts the verification
+    pub fn answered(&mut self, url: &Url, result: Result<(), String>) {
+        let status = match result {
+            Ok(()) => DeletionStatus::Verifying,
+            Err(reason) => DeletionStatus::Rejected(reason),
+        };
+        self.advance(url, DeletionStatus::Sent, status);
+    }
+
+    /// The relay returned the event to the verification request
+    pub fn served(&mut self, url: &Url) {
+        self.advance(url, DeletionStatus::Verifying, DeletionStatus::StillServed);
+    }
+
+    /// The verification request ended
+    pub fn end_of_stored(&mut self, url: &Url) {
+        self.advance(url, DeletionStatus::Verifying, DeletionStatus::Deleted);
+    }
+
+    /// The verification request timed out before ending
+    pub fn timed_out(&mut self, url: &Url) {
+        self.advance(url, DeletionStatus::Verifying, DeletionStatus::NoAnswer);
+    }
+
+    fn advance(&mut self, url: &Url, from: DeletionStatus, to: DeletionStatus) {
+        if let Some((_, status)) = self
+            .relays
+            .iter_mut()
+            .find(|(relay_url, status)| relay_url == url && status == &from)
+        {
+            *status = to;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn hash() -> EventId {
+        EventId::from_slice(&[1; 32]).unwrap()
+    }
+
+    fn deletion() -> RelayDeletion {
+        RelayDeletion::new(
+            1,
+            hash(),
+            vec![(url("wss://a.com"), true), (url("wss://b.com"), false)],
+        )
+    }
+
+    #[test]
+    fn verifies_after_the_relay_accepts() {
+        let mut deletion = deletion();
+        assert!(deletion.is_supported());
+        assert!(!deletion.is_done());
+
+        // nothing to verify before the relay accepted it
+        deletion.end_of_stored(&url("wss://a.com"));
+        assert_eq!(deletion.relays[0].1, DeletionStatus::Sent);
+
+        deletion.answered(&url("wss://a.com"), Ok(()));
+        deletion.served(&url("wss://a.com"));
+        // the end of the request keeps the event served
+        deletion.end_of_stored(&url("wss://a.com"));
+
+        assert_eq!(deletion.relays[0].1, DeletionStatus::StillServed);
+        assert_eq!(deletion.relays[1].1, DeletionStatus::NotSupported);
+        assert!(deletion.is_done());
+    }
+
+    #[test]
+    fn reports_each_relay() {
+        let mut deletion = RelayDeletion::new(
+            1,
+            hash(),
+            vec![
+                (url("wss://a.com"), true),
+                (url("wss://b.com"), true),
+                (url("wss://c.com"), true),
+            ],
+        );
+        deletion.answered(&url("wss://a.com"), Ok(()));
+        deletion.answered(&url("wss://b.com"), Err("blocked".into()));
+        deletion.answered(&url("wss://c.com"), Ok(()));
+        deletion.end_of_stored(&url("wss://a.com"));
+        deletion.timed_out(&url("wss://c.com"));
+
+        assert_eq!(
+            deletion
+                .relays
+                .iter()
+                .map(|(_, status)| status.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                DeletionStatus::Deleted,
+                DeletionStatus::Rejected("blocked".into()),
+                DeletionStatus::NoAnswer,
+            ]
+        );
+        assert!(!RelayDeletion::new(1, hash(), vec![]).is_supported());
+    }
+}
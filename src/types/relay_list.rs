@@ -0,0 +1,157 @@
+use nostr::{EventBuilder, Kind, Tag, TagKind};
+use url::Url;
+
+use crate::db::DbRelay;
+use crate::types::UncheckedEvent;
+use crate::utils::parse_canonical_relay_url;
+
+/// Relay read from an imported list
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedRelay {
+    pub url: Url,
+    pub read: bool,
+    pub write: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RelayExportFormat {
+    /// One URL per line
+    Text,
+    /// Signed relay list event
+    Nip65,
+}
+
+/// Valid relays without duplicates and the entries that were skipped
+#[derive(Debug, Clone, Default)]
+pub struct RelayList {
+    pub relays: Vec<ImportedRelay>,
+    pub invalid: Vec<String>,
+}
+impl RelayList {
+    /// A NIP-65 event when the content is JSON, one URL per line otherwise
+    pub fn parse(content: &str) -> Self {
+        let content = content.trim();
+        if content.starts_with('{') {
+            match serde_json::from_str::<UncheckedEvent>(content) {
+                Ok(event) => Self::from_event(&event),
+                Err(e) => Self {
+                    relays: vec![],
+                    invalid: vec![e.to_string()],
+                },
+            }
+        } else {
+            let mut list = Self::default();
+            for line in content.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                list.push(line, true, true);
+            }
+            list
+        }
+    }
+
+    fn from_event(event: &UncheckedEvent) -> Self {
+        let mut list = Self::default();
+        if event.kind.as_u32() != RELAY_LIST_KIND {
+            list.invalid.push(format!(
+                "Not a relay list event: kind {}",
+                event.kind.as_u32()
+            ));
+            return list;
+        }
+        for tag in event.tags.iter().map(Tag::as_vec) {
+            let (Some(name), Some(url)) = (tag.get(0), tag.get(1)) else {
+                continue;
+            };
+            if name != RELAY_TAG {
+                continue;
+            }
+            match tag.get(2).map(String::as_str) {
+                Some(READ_MARKER) => list.push(url, true, false),
+                Some(WRITE_MARKER) => list.push(url, false, true),
+                _ => list.push(url, true, true),
+            }
+        }
+        list
+    }
+
+    fn push(&mut self, entry: &str, read: bool, write: bool) {
+        match parse_relay_url(entry) {
+            Some(url) => {
+                if let Some(relay) = self.relays.iter_mut().find(|r| r.url == url) {
+                    relay.read |= read;
+                    relay.write |= write;
+                } else {
+                    self.relays.push(ImportedRelay { url, read, write });
+                }
+            }
+            None => self.invalid.push(entry.to_owned()),
+        }
+    }
+}
+
+/// Only websocket URLs with a host are relays
+pub fn parse_relay_url(entry: &str) -> Option<Url> {
+    let url = parse_canonical_relay_url(entry).ok()?;
+    let is_websocket = matches!(url.scheme(), "ws" | "wss");
+    (is_websocket && url.has_host()).then_some(url)
+}
+
+/// One relay URL per line
+pub fn relays_to_text(relays: &[DbRelay]) -> String {
+    relays
+        .iter()
+        .map(|relay| format!("{}\n", relay.url))
+        .collect()
+}
+
+/// NIP-65 relay list, relays that neither read nor write are left out
+pub fn relay_list_builder(relays: &[DbRelay]) -> EventBuilder {
+    let tags: Vec<_> = relays
+        .iter()
+        .filter_map(|relay| {
+            let mut values = vec![relay.url.to_string()];
+            match (relay.read, relay.write) {
+                (true, true) => (),
+                (true, false) => values.push(READ_MARKER.into()),
+                (false, true) => values.push(WRITE_MARKER.into()),
+                (false, false) => return None,
+            }
+            Some(Tag::Generic(TagKind::Custom(RELAY_TAG.into()), values))
+        })
+        .collect();
+    EventBuilder::new(Kind::Custom(RELAY_LIST_KIND.into()), "", &tags)
+}
+
+const RELAY_LIST_KIND: u32 = 10002;
+const RELAY_TAG: &str = "r";
+const READ_MARKER: &str = "read";
+const WRITE_MARKER: &str = "write";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_list_is_validated_and_deduped() {
+        let list = RelayList::parse(
+            "wss://relay.damus.io\n\n# comment\nwss://Relay.Damus.io:443/\nhttps://example.com\nnot a url\nws://localhost:8080\n",
+        );
+        let urls: Vec<_> = list.relays.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, vec!["wss://relay.damus.io/", "ws://localhost:8080/"]);
+        assert_eq!(list.invalid, vec!["https://example.com", "not a url"]);
+    }
+
+    #[test]
+    fn nip65_markers() {
+        let json = r#"{
+            "id": "", "pubkey": "", "sig": "", "created_at": 0, "kind": 10002, "content": "",
+            "tags": [["r", "wss://a.com", "read"], ["r", "wss://b.com", "write"], ["r", "wss://c.com"], ["t", "nostr"]]
+        }"#;
+        let list = RelayList::parse(json);
+        assert!(list.invalid.is_empty());
+        let flags: Vec<_> = list.relays.iter().map(|r| (r.read, r.write)).collect();
+        assert_eq!(flags, vec![(true, false), (false, true), (true, true)]);
+    }
+}
@@ -0,0 +1,208 @@
+use nostr::prelude::ToBech32;
+use nostr::EventId;
+use thiserror::Error;
+
+use crate::consts::NOSTR_URI_PREFIX;
+use crate::utils::parse_event_link;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("Usage: /{0} {1}")]
+    Usage(&'static str, &'static str),
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("Invalid channel: {0}")]
+    InvalidChannel(String),
+}
+
+/// What the composer should do with a parsed command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandAction {
+    /// Send the text as a message
+    Send(String),
+    /// Zap the conversation's contact, in sats
+    Zap(u64),
+    /// Invite the contact to a channel
+    Invite(EventId),
+    /// Add the contact to the user's mute list or take it out,
+    /// in a channel its notifications are toggled
+    Mute,
+    /// Clear the messages on screen, nothing is deleted
+    Clear,
+}
+
+/// Entry in the command registry
+pub struct SlashCommand {
+    pub name: &'static str,
+    /// Argument hint shown in the autocomplete popup
+    pub args: &'static str,
+    pub description: &'static str,
+    parse: fn(&'static SlashCommand, &str) -> Result<CommandAction, Error>,
+}
+impl SlashCommand {
+    fn usage(&'static self) -> Error {
+        Error::Usage(self.name, self.args)
+    }
+}
+
+/// Available commands, add new ones here
+pub static COMMANDS: &[SlashCommand] = &[
+    SlashCommand {
+        name: "shrug",
+        args: "[message]",
+        description: "Appends ¯\\_(ツ)_/¯ to the message",
+        parse: |_, args| {
+            Ok(CommandAction::Send(
+                format!("{} {}", args, SHRUG).trim().into(),
+            ))
+        },
+    },
+    SlashCommand {
+        name: "me",
+        args: "<action>",
+        description: "Sends an action, like *waves*",
+        parse: |cmd, args| match args {
+            "" => Err(cmd.usage()),
+            action => Ok(CommandAction::Send(format!("*{}*", action))),
+        },
+    },
+    SlashCommand {
+        name: "zap",
+        args: "<sats>",
+        description: "Zaps the contact with their lightning address",
+        parse: |cmd, args| match args {
+            "" => Err(cmd.usage()),
+            amount => match amount.parse::<u64>() {
+                Ok(sats) if sats > 0 => Ok(CommandAction::Zap(sats)),
+                _ => Err(Error::InvalidAmount(amount.into())),
+            },
+        },
+    },
+    SlashCommand {
+        name: "invite",
+        args: "<channel id>",
+        description: "Sends an invite to a public channel",
+        parse: |cmd, args| match args {
+            "" => Err(cmd.usage()),
+            channel => parse_event_link(channel)
+                .map(|(id, _)| id)
+                .or_else(|| EventId::from_hex(channel).ok())
+                .map(CommandAction::Invite)
+                .ok_or_else(|| Error::InvalidChannel(channel.into())),
+        },
+    },
+    SlashCommand {
+        name: "mute",
+        args: "",
+        description: "Mutes or unmutes the contact or channel",
+        parse: |_, _| Ok(CommandAction::Mute),
+    },
+    SlashCommand {
+        name: "clear",
+        args: "",
+        description: "Clears the messages on screen",
+        parse: |_, _| Ok(CommandAction::Clear),
+    },
+];
+
+/// Splits `/name args` into the command name and its arguments
+fn split_command(input: &str) -> Option<(&str, &str)> {
+    let input = input.strip_prefix('/')?;
+    match input.split_once(char::is_whitespace) {
+        Some((name, args)) => Some((name, args.trim())),
+        None => Some((input, "")),
+    }
+}
+
+/// Finds a registered command by name
+pub fn find_command(name: &str) -> Option<&'static SlashCommand> {
+    COMMANDS.iter().find(|c| c.name == name)
+}
+
+/// `None` when the input is not a registered command
+pub fn parse_command(input: &str) -> Option<Result<CommandAction, Error>> {
+    let (name, args) = split_command(input)?;
+    let command = find_command(name)?;
+    Some((command.parse)(command, args))
+}
+
+/// Commands matching what is being typed.
+/// Once the name is complete only that command is returned, for its hint.
+pub fn suggestions(input: &str) -> Vec<&'static SlashCommand> {
+    let Some((name, _)) = split_command(input) else {
+        return vec![];
+    };
+    let typing_args = input.contains(char::is_whitespace);
+    COMMANDS
+        .iter()
+        .filter(|c| {
+            if typing_args {
+                c.name == name
+            } else {
+                c.name.starts_with(name)
+            }
+        })
+        .collect()
+}
+
+/// Message sent by `/invite`
+pub fn invite_message(channel_id: &EventId) -> String {
+    let link = channel_id
+        .to_bech32()
+        .unwrap_or_else(|_| channel_id.to_hex());
+    format!("Join me on this channel: {}{}", NOSTR_URI_PREFIX, link)
+}
+
+const SHRUG: &str = r"¯\_(ツ)_/¯";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_text_commands() {
+        assert_eq!(
+            parse_command("/shrug ok"),
+            Some(Ok(CommandAction::Send(r"ok ¯\_(ツ)_/¯".into())))
+        );
+        assert_eq!(
+            parse_command("/me waves"),
+            Some(Ok(CommandAction::Send("*waves*".into())))
+        );
+        assert_eq!(
+            parse_command("/me"),
+            Some(Err(Error::Usage("me", "<action>")))
+        );
+    }
+
+    #[test]
+    fn validates_arguments() {
+        assert_eq!(parse_command("/zap 100"), Some(Ok(CommandAction::Zap(100))));
+        assert!(matches!(
+            parse_command("/zap lots"),
+            Some(Err(Error::InvalidAmount(_)))
+        ));
+        let channel_id = EventId::from_slice(&[1; 32]).unwrap();
+        assert_eq!(
+            parse_command(&format!("/invite {}", channel_id.to_hex())),
+            Some(Ok(CommandAction::Invite(channel_id)))
+        );
+    }
+
+    #[test]
+    fn unknown_inputs_are_not_commands() {
+        assert_eq!(parse_command("/thanks"), None);
+        assert_eq!(parse_command("hello /me"), None);
+    }
+
+    #[test]
+    fn suggests_by_prefix_then_hint() {
+        let names: Vec<_> = suggestions("/m").iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["me", "mute"]);
+        let names: Vec<_> = suggestions("/zap 1").iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["zap"]);
+        assert!(suggestions("hello").is_empty());
+    }
+}
@@ -22,10 +22,32 @@ pub enum SubName {
     ContactListMetadata,
     UserMetadata,
     Messages,
+    /// Messages of the most visited conversations, fetched before `Messages`
+    MessagesWave(usize),
     SearchChannels,
     SearchChannelsDetails(PrefixedId),
     ChannelMembersMetadata(PrefixedId),
     Channels,
+    /// Subscription of a kind handler
+    Kind(String),
+    /// Single event requested through a link
+    ResolveEvent,
+    /// Older messages of a conversation
+    DmHistory(PrefixedId),
+    /// Channel messages written by the user's contacts
+    ContactChannels,
+    /// Creation events of channels found through contacts
+    ContactChannelsMeta,
+    /// REQ typed in the developer console
+    Console,
+    /// Checks whether a relay still returns an event the user asked to delete
+    VerifyDeletion(PrefixedId),
+    /// Notes and channel messages of a hashtag opened by the user
+    HashtagFeed(String),
+    /// New notes of the followed hashtags
+    FollowedHashtags,
+    /// Profile, contact list and a sample of the messages of an existing account
+    AccountPreview,
 }
 impl SubName {
     pub fn src_channel_details(channel_id: &nostr::EventId) -> Self {
@@ -34,6 +56,12 @@ impl SubName {
     pub fn channel_members_meta(channel_id: &nostr::EventId) -> Self {
         Self::ChannelMembersMetadata(PrefixedId::new(&channel_id.to_hex()))
     }
+    pub fn dm_history(chat_pubkey: &nostr::secp256k1::XOnlyPublicKey) -> Self {
+        Self::DmHistory(PrefixedId::new(&chat_pubkey.to_string()))
+    }
+    pub fn verify_deletion(event_hash: &nostr::EventId) -> Self {
+        Self::VerifyDeletion(PrefixedId::new(&event_hash.to_hex()))
+    }
     pub fn from_id(id: &SubscriptionId) -> Option<Self> {
         let str = id.to_string();
         match str.as_str() {
@@ -43,13 +71,34 @@ impl SubName {
             "Messages" => Some(SubName::Messages),
             "Channels" => Some(SubName::Channels),
             "SearchChannels" => Some(SubName::SearchChannels),
+            "ResolveEvent" => Some(SubName::ResolveEvent),
+            "ContactChannels" => Some(SubName::ContactChannels),
+            "ContactChannelsMeta" => Some(SubName::ContactChannelsMeta),
+            "Console" => Some(SubName::Console),
+            "FollowedHashtags" => Some(SubName::FollowedHashtags),
+            "AccountPreview" => Some(SubName::AccountPreview),
             _ => {
                 if str.starts_with("SrcChannelDts_") {
                     let (_, hex) = str.split_at("SrcChannelDts_".len());
                     Some(SubName::SearchChannelsDetails(PrefixedId(hex.to_owned())))
+                } else if str.starts_with("Kind_") {
+                    let (_, name) = str.split_at("Kind_".len());
+                    Some(SubName::Kind(name.to_owned()))
+                } else if str.starts_with("MessagesWave_") {
+                    let (_, wave) = str.split_at("MessagesWave_".len());
+                    wave.parse().ok().map(SubName::MessagesWave)
+                } else if str.starts_with("DmHistory_") {
+                    let (_, hex) = str.split_at("DmHistory_".len());
+                    Some(SubName::DmHistory(PrefixedId(hex.to_owned())))
                 } else if str.starts_with("ChannelMembersMeta_") {
                     let (_, hex) = str.split_at("ChannelMembersMeta_".len());
                     Some(SubName::ChannelMembersMetadata(PrefixedId(hex.to_owned())))
+                } else if str.starts_with("VerifyDeletion_") {
+                    let (_, hex) = str.split_at("VerifyDeletion_".len());
+                    Some(SubName::VerifyDeletion(PrefixedId(hex.to_owned())))
+                } else if str.starts_with("HashtagFeed_") {
+                    let (_, hashtag) = str.split_at("HashtagFeed_".len());
+                    Some(SubName::HashtagFeed(hashtag.to_owned()))
                 } else {
                     None
                 }
@@ -65,6 +114,7 @@ impl std::fmt::Display for SubName {
             SubName::ContactListMetadata => write!(f, "ContactListMetadata"),
             SubName::UserMetadata => write!(f, "UserMetadata"),
             SubName::Messages => write!(f, "Messages"),
+            SubName::MessagesWave(wave) => write!(f, "MessagesWave_{}", wave),
             SubName::Channels => write!(f, "Channels"),
             SubName::SearchChannels => write!(f, "SearchChannels"),
             SubName::ChannelMembersMetadata(prefixed) => {
@@ -73,6 +123,16 @@ impl std::fmt::Display for SubName {
             SubName::SearchChannelsDetails(prefixed) => {
                 write!(f, "SrcChannelDts_{}", &prefixed)
             }
+            SubName::Kind(name) => write!(f, "Kind_{}", name),
+            SubName::ResolveEvent => write!(f, "ResolveEvent"),
+            SubName::DmHistory(prefixed) => write!(f, "DmHistory_{}", &prefixed),
+            SubName::ContactChannels => write!(f, "ContactChannels"),
+            SubName::ContactChannelsMeta => write!(f, "ContactChannelsMeta"),
+            SubName::Console => write!(f, "Console"),
+            SubName::VerifyDeletion(prefixed) => write!(f, "VerifyDeletion_{}", &prefixed),
+            SubName::HashtagFeed(hashtag) => write!(f, "HashtagFeed_{}", hashtag),
+            SubName::FollowedHashtags => write!(f, "FollowedHashtags"),
+            SubName::AccountPreview => write!(f, "AccountPreview"),
         }
     }
 }
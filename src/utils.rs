@@ -1,14 +1,22 @@
 #![allow(dead_code)]
 use crate::{
-    components::chat_contact::ChatContact,
-    db::{DbContact, MessageStatus},
+    config::Presence,
+    consts::NOSTR_URI_PREFIX,
+    db::blocked::MUTE_LIST_KIND,
+    db::channel_cache::moderator_tag,
+    db::channel_subscription::{CHANNEL_READ_ID, CHANNEL_READ_KIND},
+    db::poll::{OPTION_TAG, POLL_KIND, POLL_RESPONSE_KIND, RESPONSE_TAG},
+    db::{ChannelMention, DbContact, MessageStatus, ReadMarkers},
     net::ImageKind,
     style::{Theme, ThemeType},
+    types::hashtag::hashtag_tags,
     types::ChannelMetadata,
 };
 use chrono::{DateTime, Local, NaiveDateTime, Offset};
 use iced::widget::image::Handle;
 use image::{ImageBuffer, Luma, Rgba};
+use nostr::hashes::Hash;
+use nostr::nips::nip19::Nip19Event;
 use nostr::prelude::*;
 use qrcode::QrCode;
 use regex::Regex;
@@ -22,6 +30,10 @@ use std::{
 
 use thiserror::Error;
 
+pub mod fuzzy;
+pub mod text_direction;
+pub mod time_format;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Nostr Nip 19 Error: {0}")]
@@ -47,6 +59,15 @@ pub enum Error {
 
     #[error("{0}")]
     FromParseError(#[from] std::num::ParseIntError),
+
+    #[error("Nostr Nip 04 Error: {0}")]
+    FromNip04(#[from] nostr::nips::nip04::Error),
+
+    #[error("Nostr Key Error: {0}")]
+    FromKey(#[from] nostr::key::Error),
+
+    #[error("Secp256k1 Error: {0}")]
+    FromSecp256k1(#[from] nostr::secp256k1::Error),
 }
 
 // Accepts both hex and bech32 keys and returns the hex encoded key
@@ -67,6 +88,38 @@ pub fn parse_key(key: String) -> Result<String, Error> {
     Ok(parsed_key)
 }
 
+/// Event id and relay hints of a `nevent` or `note`, with or without the `nostr:` prefix
+pub fn parse_event_link(link: &str) -> Option<(EventId, Vec<String>)> {
+    let link = link.trim();
+    let link = link.strip_prefix(NOSTR_URI_PREFIX).unwrap_or(link);
+    if link.starts_with("nevent") {
+        Nip19Event::from_bech32(link)
+            .ok()
+            .map(|nevent| (nevent.event_id, nevent.relays))
+    } else if link.starts_with("note") {
+        EventId::from_bech32(link).ok().map(|id| (id, vec![]))
+    } else {
+        None
+    }
+}
+
+/// `nevent` pointing to the event and the relays that have it
+pub fn nevent_link(event_hash: &EventId, relays: &[Url]) -> Result<String, Error> {
+    let relays: Vec<_> = relays.iter().map(|url| url.to_string()).collect();
+    Ok(Nip19Event::new(event_hash.to_owned(), relays).to_bech32()?)
+}
+
+/// Throwaway keys to answer a relay's AUTH without revealing the main identity.
+/// Always the same for the same relay, so the relay still sees a stable user.
+pub fn relay_auth_keys(keys: &Keys, relay_url: &Url) -> Result<Keys, Error> {
+    let mut data = keys.secret_key()?.secret_bytes().to_vec();
+    data.extend_from_slice(RELAY_AUTH_DOMAIN.as_bytes());
+    data.extend_from_slice(relay_url.as_str().as_bytes());
+    let hash = nostr::hashes::sha256::Hash::hash(&data);
+    let secret_key = nostr::secp256k1::SecretKey::from_slice(hash.as_ref())?;
+    Ok(Keys::new(secret_key))
+}
+
 pub fn json_reader<P, T: DeserializeOwned>(path: P) -> Result<T, Error>
 where
     P: AsRef<Path>,
@@ -164,11 +217,20 @@ pub fn theme_or_err(theme: u8, index: &str) -> Result<Theme, sqlx::Error> {
     u8::try_into(theme).map_err(|e| handle_decode_error(e, index))
 }
 
-pub fn chat_matches_search(chat: &ChatContact, search: &str) -> bool {
-    let selected_name = chat.contact.select_name();
-    selected_name
-        .to_lowercase()
-        .contains(&search.to_lowercase())
+/// Single spelling for a relay URL. The parser already lowercases the host and drops
+/// default ports, this also removes trailing slashes from paths and the fragment.
+pub fn canonical_relay_url(url: &Url) -> Url {
+    let mut url = url.to_owned();
+    url.set_fragment(None);
+    let path = url.path().trim_end_matches('/').to_owned();
+    if !path.is_empty() {
+        url.set_path(&path);
+    }
+    url
+}
+
+pub fn parse_canonical_relay_url(url: &str) -> Result<Url, url::ParseError> {
+    Url::parse(url.trim()).map(|url| canonical_relay_url(&url))
 }
 
 pub fn url_matches_search(url: &Url, search: &str) -> bool {
@@ -193,34 +255,139 @@ pub fn channel_msg_builder(
     channel_id: &EventId,
     recommended_relay: Option<&Url>,
     content: &str,
+    broadcast: bool,
 ) -> EventBuilder {
-    let tags = &[nostr::Tag::Event(
+    let mut tags = vec![nostr::Tag::Event(
         channel_id.to_owned(),
         recommended_relay
             .as_ref()
             .map(|url| nostr::UncheckedUrl::new(url.to_string())),
         Some(Marker::Root),
     )];
-    EventBuilder::new(nostr::Kind::ChannelMessage, content, tags)
+    if broadcast {
+        tags.push(ChannelMention::broadcast_tag());
+    }
+    tags.extend(hashtag_tags(content));
+    EventBuilder::new(nostr::Kind::ChannelMessage, content, &tags)
+}
+
+/// Poll in a channel, options are identified by their position
+pub fn poll_builder(channel_id: &EventId, question: &str, options: &[String]) -> EventBuilder {
+    let mut tags = vec![nostr::Tag::Event(
+        channel_id.to_owned(),
+        None,
+        Some(Marker::Root),
+    )];
+    tags.extend(options.iter().enumerate().map(|(idx, label)| {
+        nostr::Tag::Generic(
+            nostr::TagKind::Custom(OPTION_TAG.into()),
+            vec![idx.to_string(), label.to_owned()],
+        )
+    }));
+    EventBuilder::new(nostr::Kind::Custom(POLL_KIND), question, &tags)
+}
+
+pub fn poll_vote_builder(
+    channel_id: &EventId,
+    poll_hash: &EventId,
+    option_id: &str,
+) -> EventBuilder {
+    let tags = &[
+        nostr::Tag::Event(channel_id.to_owned(), None, Some(Marker::Root)),
+        nostr::Tag::Event(poll_hash.to_owned(), None, Some(Marker::Reply)),
+        nostr::Tag::Generic(
+            nostr::TagKind::Custom(RESPONSE_TAG.into()),
+            vec![option_id.to_owned()],
+        ),
+    ];
+    EventBuilder::new(nostr::Kind::Custom(POLL_RESPONSE_KIND), "", tags)
+}
+
+/// NIP-51 mute list, `content` holds the encrypted private entries
+pub fn mute_list_builder(tags: &[nostr::Tag], content: &str) -> EventBuilder {
+    EventBuilder::new(nostr::Kind::Custom(MUTE_LIST_KIND), content, tags)
+}
+
+/// NIP-25 reaction to the event of `author`
+pub fn reaction_builder(
+    event_hash: &EventId,
+    author: &XOnlyPublicKey,
+    content: &str,
+) -> EventBuilder {
+    let tags = &[
+        nostr::Tag::Event(event_hash.to_owned(), None, None),
+        nostr::Tag::PubKey(author.to_owned(), None),
+    ];
+    EventBuilder::new(nostr::Kind::Reaction, content, tags)
+}
+
+/// NIP-09 request to delete the user's event
+pub fn deletion_builder(event_hash: &EventId) -> EventBuilder {
+    let tags = &[nostr::Tag::Event(event_hash.to_owned(), None, None)];
+    EventBuilder::new(nostr::Kind::EventDeletion, "", tags)
+}
+
+/// NIP-38 general status, an empty content clears it
+pub fn status_builder(presence: Presence) -> EventBuilder {
+    let content = match presence {
+        Presence::Available => "",
+        Presence::Away => AWAY_STATUS,
+    };
+    let tags = &[nostr::Tag::Identifier(GENERAL_STATUS.into())];
+    EventBuilder::new(nostr::Kind::Custom(USER_STATUS_KIND), content, tags)
+}
+
+/// Channel read positions encrypted to the user, for their other devices
+pub fn channel_read_builder(keys: &Keys, markers: &ReadMarkers) -> Result<EventBuilder, Error> {
+    let content = serde_json::to_string(markers)?;
+    let encrypted = nostr::nips::nip04::encrypt(&keys.secret_key()?, &keys.public_key(), content)?;
+    let tags = &[nostr::Tag::Identifier(CHANNEL_READ_ID.into())];
+    Ok(EventBuilder::new(
+        nostr::Kind::Custom(CHANNEL_READ_KIND),
+        encrypted,
+        tags,
+    ))
+}
+
+/// Encrypted direct message with a relay hint in the `p` tag
+pub fn dm_builder(
+    keys: &Keys,
+    receiver: &XOnlyPublicKey,
+    relay_hint: Option<&Url>,
+    content: &str,
+) -> Result<EventBuilder, Error> {
+    let encrypted = nostr::nips::nip04::encrypt(&keys.secret_key()?, receiver, content)?;
+    let tags = &[nostr::Tag::PubKey(
+        receiver.to_owned(),
+        relay_hint.map(|url| nostr::UncheckedUrl::new(url.to_string())),
+    )];
+    Ok(EventBuilder::new(
+        nostr::Kind::EncryptedDirectMessage,
+        encrypted,
+        tags,
+    ))
 }
 
 pub fn channel_creation_builder(metadata: &ChannelMetadata) -> EventBuilder {
     EventBuilder::new(nostr::Kind::ChannelCreation, metadata.as_json(), &[])
 }
 
+/// Kind 41, the moderators are tagged with the `moderator` role
 pub fn channel_metadata_builder(
     channel_id: &EventId,
     recommended_relay: Option<&Url>,
     metadata: &ChannelMetadata,
+    moderators: &[XOnlyPublicKey],
 ) -> EventBuilder {
-    let tags = &[nostr::Tag::Event(
+    let mut tags = vec![nostr::Tag::Event(
         channel_id.to_owned(),
         recommended_relay
             .as_ref()
             .map(|url| nostr::UncheckedUrl::new(url.to_string())),
         None,
     )];
-    EventBuilder::new(nostr::Kind::ChannelMetadata, metadata.as_json(), tags)
+    tags.extend(moderators.iter().map(moderator_tag));
+    EventBuilder::new(nostr::Kind::ChannelMetadata, metadata.as_json(), &tags)
 }
 
 pub fn contact_matches_search_full(contact: &DbContact, search: &str) -> bool {
@@ -276,6 +443,40 @@ pub fn darken_color(mut color: iced::Color, amount: f32) -> iced::Color {
     color
 }
 
+/// WCAG contrast ratio between two colors, from 1 to 21
+pub fn contrast_ratio(a: iced::Color, b: iced::Color) -> f32 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+fn relative_luminance(color: iced::Color) -> f32 {
+    let linear = |c: f32| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(color.r) + 0.7152 * linear(color.g) + 0.0722 * linear(color.b)
+}
+
+/// Accent lightened in dark themes and darkened in light ones
+/// until it can be read over `background`
+pub fn readable_accent(
+    accent: iced::Color,
+    background: iced::Color,
+    theme_type: ThemeType,
+) -> iced::Color {
+    let mut color = accent;
+    for _ in 0..ACCENT_CONTRAST_STEPS {
+        if contrast_ratio(color, background) >= MIN_ACCENT_CONTRAST {
+            break;
+        }
+        color = change_color_by_type(theme_type, color, ACCENT_CONTRAST_STEP);
+    }
+    color
+}
+
 pub fn qr_code_handle(code: &str) -> Result<Handle, Error> {
     // Encode some data into bits.
     let code = match QrCode::new(code.as_bytes()) {
@@ -349,6 +550,29 @@ pub fn hide_string(string: &str, open: usize) -> String {
     format!("{}...{}", prefix, suffix.chars().rev().collect::<String>())
 }
 
+/// "512 B", "1.5 KB", "20.0 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+const RELAY_AUTH_DOMAIN: &str = "nostrtalk-relay-auth";
+const USER_STATUS_KIND: u64 = 30315;
+const GENERAL_STATUS: &str = "general";
+const AWAY_STATUS: &str = "Away";
+const MIN_ACCENT_CONTRAST: f32 = 3.0;
+const ACCENT_CONTRAST_STEP: f32 = 0.05;
+const ACCENT_CONTRAST_STEPS: usize = 20;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -394,6 +618,89 @@ mod tests {
         // 8 from each side turns into 16 chars, open the entire string
         assert_eq!(hide_string("Hello, world!", 8), "Hello, world!");
     }
+
+    #[test]
+    fn test_event_link_round_trip() {
+        let event_hash =
+            EventId::from_hex("b9f5441e45ca39179320e0031cfb18e34078673dcc3d3e3a3b3a981760aa5696")
+                .unwrap();
+        let relay = Url::parse("wss://relay.damus.io").unwrap();
+
+        let link = nevent_link(&event_hash, &[relay.clone()]).unwrap();
+        let (parsed_hash, relays) = parse_event_link(&format!("nostr:{}", link)).unwrap();
+        assert_eq!(parsed_hash, event_hash);
+        assert_eq!(relays, vec![relay.to_string()]);
+
+        let note = event_hash.to_bech32().unwrap();
+        assert_eq!(parse_event_link(&note).unwrap().0, event_hash);
+        assert!(parse_event_link("hello").is_none());
+    }
+
+    #[test]
+    fn test_canonical_relay_url() {
+        let canonical = |url: &str| parse_canonical_relay_url(url).unwrap().to_string();
+        assert_eq!(canonical("wss://Relay.Damus.io"), "wss://relay.damus.io/");
+        assert_eq!(
+            canonical(" wss://relay.damus.io:443/ "),
+            "wss://relay.damus.io/"
+        );
+        assert_eq!(
+            canonical("wss://nostr.x.com/inbox/"),
+            "wss://nostr.x.com/inbox"
+        );
+        assert_eq!(
+            canonical("wss://nostr.x.com/inbox#a"),
+            "wss://nostr.x.com/inbox"
+        );
+        assert_ne!(
+            canonical("wss://relay.damus.io"),
+            canonical("wss://relay.damus.io:8080")
+        );
+    }
+
+    #[test]
+    fn test_relay_auth_keys() {
+        let keys = Keys::generate();
+        let relay = Url::parse("wss://relay.damus.io").unwrap();
+        let other = Url::parse("wss://nos.lol").unwrap();
+
+        let auth_keys = relay_auth_keys(&keys, &relay).unwrap();
+        assert_ne!(auth_keys.public_key(), keys.public_key());
+        assert_eq!(
+            auth_keys.public_key(),
+            relay_auth_keys(&keys, &relay).unwrap().public_key()
+        );
+        assert_ne!(
+            auth_keys.public_key(),
+            relay_auth_keys(&keys, &other).unwrap().public_key()
+        );
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(20 * 1024 * 1024), "20.0 MB");
+    }
+
+    #[test]
+    fn test_readable_accent() {
+        let dark_bg = iced::Color::from_rgb8(30, 30, 30);
+        let light_bg = iced::Color::from_rgb8(245, 245, 245);
+        let navy = iced::Color::from_rgb8(10, 20, 90);
+        let yellow = iced::Color::from_rgb8(250, 240, 120);
+
+        assert!((contrast_ratio(iced::Color::BLACK, iced::Color::WHITE) - 21.0).abs() < 0.01);
+
+        let accent = readable_accent(navy, dark_bg, ThemeType::Dark);
+        assert!(contrast_ratio(accent, dark_bg) >= MIN_ACCENT_CONTRAST);
+
+        let accent = readable_accent(yellow, light_bg, ThemeType::Light);
+        assert!(contrast_ratio(accent, light_bg) >= MIN_ACCENT_CONTRAST);
+
+        // already readable, unchanged
+        assert_eq!(readable_accent(yellow, dark_bg, ThemeType::Dark), yellow);
+    }
 }
 
 // pub fn round_image(image: &mut ColorImage) {
@@ -0,0 +1,89 @@
+//! Fuzzy matching for the quick switcher and the contact search.
+
+/// Characters of a query found in order in a candidate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Indexes of the matched characters of the candidate
+    pub positions: Vec<usize>,
+}
+
+/// Characters of `query` in order, consecutive and word start matches score higher.
+/// An empty query matches everything with a score of 0
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let query: Vec<char> = query.trim().chars().map(fold_case).collect();
+    let mut positions = vec![];
+    let mut score = 0;
+    let mut previous_matched = false;
+    let mut previous_char = ' ';
+    for (idx, c) in candidate.chars().map(fold_case).enumerate() {
+        if positions.len() < query.len() && c == query[positions.len()] {
+            score += 1;
+            if previous_matched {
+                score += CONSECUTIVE_BONUS;
+            }
+            if !previous_char.is_alphanumeric() {
+                score += WORD_START_BONUS;
+            }
+            positions.push(idx);
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+        previous_char = c;
+    }
+    (positions.len() == query.len()).then_some(FuzzyMatch { score, positions })
+}
+
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    fuzzy_match(query, candidate).map(|m| m.score)
+}
+
+/// Candidate split in runs of matched and unmatched characters, for highlighting
+pub fn highlight_runs(candidate: &str, positions: &[usize]) -> Vec<(String, bool)> {
+    let mut runs: Vec<(String, bool)> = vec![];
+    for (idx, c) in candidate.chars().enumerate() {
+        let matched = positions.contains(&idx);
+        match runs.last_mut() {
+            Some((run, run_matched)) if *run_matched == matched => run.push(c),
+            _ => runs.push((c.to_string(), matched)),
+        }
+    }
+    runs
+}
+
+/// One character per character, so positions stay valid in the original text
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+const CONSECUTIVE_BONUS: i64 = 5;
+const WORD_START_BONUS: i64 = 10;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches_in_order() {
+        assert!(fuzzy_score("ndm", "nostr dome").is_some());
+        assert!(fuzzy_score("mdn", "nostr dome").is_none());
+        assert!(fuzzy_score("nos", "nostr").unwrap() > fuzzy_score("nos", "canoes").unwrap());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn highlights_matched_runs() {
+        let matched = fuzzy_match("ÉDo", "Élan dome").unwrap();
+        assert_eq!(matched.positions, vec![0, 5, 6]);
+        assert_eq!(
+            highlight_runs("Élan dome", &matched.positions),
+            vec![
+                ("É".to_owned(), true),
+                ("lan ".to_owned(), false),
+                ("do".to_owned(), true),
+                ("me".to_owned(), false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Direction of text written by users, Arabic and Hebrew read right to left.
+//!
+//! Text is drawn left to right in the order it's stored, so lines with
+//! right-to-left letters are reordered for display with the Unicode
+//! bidirectional algorithm and aligned to the right when they start with one.
+
+use std::borrow::Cow;
+
+use iced::alignment;
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level};
+
+use crate::config::TextDirection;
+
+/// Base direction of a paragraph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+}
+impl Direction {
+    /// From the first letter of `text`, unless `setting` forces one
+    pub fn of(text: &str, setting: TextDirection) -> Self {
+        match setting {
+            TextDirection::Auto => detect(text),
+            TextDirection::LeftToRight => Direction::LeftToRight,
+            TextDirection::RightToLeft => Direction::RightToLeft,
+        }
+    }
+    /// Lines without letters follow the text `around` them
+    pub fn of_line(line: &str, around: Direction, setting: TextDirection) -> Self {
+        match setting {
+            TextDirection::Auto => first_strong(line).unwrap_or(around),
+            _ => around,
+        }
+    }
+    pub fn is_rtl(self) -> bool {
+        self == Direction::RightToLeft
+    }
+    pub fn alignment(self) -> alignment::Horizontal {
+        match self {
+            Direction::LeftToRight => alignment::Horizontal::Left,
+            Direction::RightToLeft => alignment::Horizontal::Right,
+        }
+    }
+}
+
+/// Direction of the first strong letter, left to right when there is none
+pub fn detect(text: &str) -> Direction {
+    first_strong(text).unwrap_or(Direction::LeftToRight)
+}
+
+fn first_strong(text: &str) -> Option<Direction> {
+    text.chars().find_map(|c| match bidi_class(c) {
+        BidiClass::L => Some(Direction::LeftToRight),
+        BidiClass::R | BidiClass::AL => Some(Direction::RightToLeft),
+        _ => None,
+    })
+}
+
+fn has_rtl(text: &str) -> bool {
+    text.chars()
+        .any(|c| matches!(bidi_class(c), BidiClass::R | BidiClass::AL))
+}
+
+/// One line in display order. Left to right text is returned as is
+pub fn visual_line(line: &str, base: Direction) -> Cow<str> {
+    if base == Direction::LeftToRight && !has_rtl(line) {
+        return Cow::Borrowed(line);
+    }
+    let level = match base {
+        Direction::LeftToRight => Level::ltr(),
+        Direction::RightToLeft => Level::rtl(),
+    };
+    let info = BidiInfo::new(line, Some(level));
+    match info.paragraphs.first() {
+        Some(para) => Cow::Owned(info.reorder_line(para, para.range.clone()).into_owned()),
+        None => Cow::Borrowed(line),
+    }
+}
+
+/// Single line labels such as names, in their own direction
+pub fn visual_label(label: &str, setting: TextDirection) -> Cow<str> {
+    visual_line(label, Direction::of(label, setting))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_and_reorders_mixed_lines() {
+        assert_eq!(detect("שלום world"), Direction::RightToLeft);
+        assert_eq!(detect("123 hello שלום"), Direction::LeftToRight);
+        assert_eq!(detect("مرحبا"), Direction::RightToLeft);
+        assert_eq!(detect("1, 2, 3"), Direction::LeftToRight);
+
+        assert!(matches!(
+            visual_line("plain text", Direction::LeftToRight),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(visual_line("אבג", Direction::RightToLeft), "גבא");
+        // latin runs and numbers keep their order inside right to left lines
+        assert_eq!(
+            visual_line("אבג nostr 21", Direction::RightToLeft),
+            "21 nostr גבא"
+        );
+        assert_eq!(
+            visual_line("hello אבג דה", Direction::LeftToRight),
+            "hello הד גבא"
+        );
+    }
+}
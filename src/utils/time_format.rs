@@ -0,0 +1,206 @@
+//! Times and dates as chosen in the appearance settings.
+//! Takes UTC times and writes them in the local timezone.
+
+use chrono::{NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+
+use crate::config::{ClockFormat, DateOrder, TimeFormatConfig};
+use crate::utils::from_naive_utc_to_local;
+
+/// Language and region from the environment, e.g. "en_US"
+static SYSTEM_LOCALE: Lazy<Option<String>> = Lazy::new(|| {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+});
+
+/// Time of a message bubble: relative when recent and the option is on,
+/// the clock time otherwise since the day is in the divider above
+pub fn message_time(utc: NaiveDateTime, config: &TimeFormatConfig) -> String {
+    let formats = Formats::system(config);
+    let now = Utc::now().naive_utc();
+    formats
+        .relative(utc, now, RELATIVE_MESSAGE_SECS)
+        .unwrap_or_else(|| formats.clock(from_naive_utc_to_local(utc).naive_local()))
+}
+
+/// Time of a chat card: relative when recent and the option is on,
+/// the clock time for today and the date before that
+pub fn short(utc: NaiveDateTime, config: &TimeFormatConfig) -> String {
+    let now = Utc::now().naive_utc();
+    Formats::system(config).short(utc, now)
+}
+
+/// Day of a date divider
+pub fn date(utc: NaiveDateTime, config: &TimeFormatConfig) -> String {
+    Formats::system(config).date(from_naive_utc_to_local(utc).naive_local())
+}
+
+/// Date and time with seconds, followed by the relative time when the option is on
+pub fn precise(utc: NaiveDateTime, config: &TimeFormatConfig) -> String {
+    let formats = Formats::system(config);
+    let local = from_naive_utc_to_local(utc).naive_local();
+    let absolute = format!("{} {}", formats.date(local), formats.clock_secs(local));
+    let now = Utc::now().naive_utc();
+    match formats.relative(utc, now, RELATIVE_SHORT_SECS) {
+        Some(relative) => format!("{} ({})", absolute, relative),
+        None => absolute,
+    }
+}
+
+/// Settings resolved against the system locale
+struct Formats {
+    twelve_hour: bool,
+    date: &'static str,
+    relative: bool,
+}
+
+impl Formats {
+    fn system(config: &TimeFormatConfig) -> Self {
+        Self::new(config, SYSTEM_LOCALE.as_deref())
+    }
+
+    fn new(config: &TimeFormatConfig, locale: Option<&str>) -> Self {
+        let (language, region) = split_locale(locale.unwrap_or_default());
+        let twelve_hour = match config.clock {
+            ClockFormat::Locale => TWELVE_HOUR_REGIONS.contains(&region),
+            ClockFormat::H24 => false,
+            ClockFormat::H12 => true,
+        };
+        let date = match config.date_order {
+            DateOrder::Locale if locale.is_none() => "%Y-%m-%d",
+            DateOrder::Locale if MONTH_FIRST_REGIONS.contains(&region) => "%m/%d/%Y",
+            DateOrder::Locale if YEAR_FIRST_REGIONS.contains(&region) => "%Y-%m-%d",
+            DateOrder::Locale if DOTTED_LANGUAGES.contains(&language) => "%d.%m.%Y",
+            DateOrder::Locale => "%d/%m/%Y",
+            DateOrder::YearMonthDay => "%Y-%m-%d",
+            DateOrder::DayMonthYear => "%d/%m/%Y",
+            DateOrder::MonthDayYear => "%m/%d/%Y",
+        };
+        Self {
+            twelve_hour,
+            date,
+            relative: config.relative,
+        }
+    }
+
+    fn clock(&self, local: NaiveDateTime) -> String {
+        let format = if self.twelve_hour {
+            "%-I:%M %p"
+        } else {
+            "%H:%M"
+        };
+        local.format(format).to_string()
+    }
+
+    fn clock_secs(&self, local: NaiveDateTime) -> String {
+        let format = if self.twelve_hour {
+            "%-I:%M:%S %p"
+        } else {
+            "%H:%M:%S"
+        };
+        local.format(format).to_string()
+    }
+
+    fn date(&self, local: NaiveDateTime) -> String {
+        local.format(self.date).to_string()
+    }
+
+    fn short(&self, utc: NaiveDateTime, now: NaiveDateTime) -> String {
+        if let Some(relative) = self.relative(utc, now, RELATIVE_SHORT_SECS) {
+            return relative;
+        }
+        let local = from_naive_utc_to_local(utc).naive_local();
+        let local_now = from_naive_utc_to_local(now).naive_local();
+        if local.date() == local_now.date() {
+            self.clock(local)
+        } else {
+            self.date(local)
+        }
+    }
+
+    /// `None` when the option is off or the time is older than `max_secs`
+    fn relative(&self, utc: NaiveDateTime, now: NaiveDateTime, max_secs: i64) -> Option<String> {
+        if !self.relative {
+            return None;
+        }
+        relative_time(utc, now, max_secs)
+    }
+}
+
+/// "just now", "5 min ago", "3 h ago" or "2 d ago", `None` past `max_secs`
+fn relative_time(utc: NaiveDateTime, now: NaiveDateTime, max_secs: i64) -> Option<String> {
+    // times a bit ahead of the clock are still "just now"
+    let secs = (now - utc).num_seconds().max(0);
+    if secs > max_secs {
+        return None;
+    }
+    let relative = match secs {
+        0..=59 => "just now".to_owned(),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} d ago", secs / 86400),
+    };
+    Some(relative)
+}
+
+/// "en_US.UTF-8" gives "en" and "US"
+fn split_locale(locale: &str) -> (&str, &str) {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    match locale.split_once(['_', '-']) {
+        Some((language, region)) => (language, region),
+        None => (locale, ""),
+    }
+}
+
+const RELATIVE_MESSAGE_SECS: i64 = 24 * 3600;
+const RELATIVE_SHORT_SECS: i64 = 7 * 24 * 3600;
+const TWELVE_HOUR_REGIONS: [&str; 10] =
+    ["US", "CA", "AU", "NZ", "IN", "PH", "PK", "EG", "SA", "MY"];
+const MONTH_FIRST_REGIONS: [&str; 2] = ["US", "PH"];
+const YEAR_FIRST_REGIONS: [&str; 8] = ["CN", "JP", "KR", "TW", "HU", "LT", "SE", "CA"];
+const DOTTED_LANGUAGES: [&str; 12] = [
+    "de", "ru", "pl", "cs", "sk", "fi", "nb", "no", "da", "tr", "uk", "ro",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_follow_settings_and_locale() {
+        let config = TimeFormatConfig::default();
+        let us = Formats::new(&config, Some("en_US.UTF-8"));
+        let de = Formats::new(&config, Some("de_DE.UTF-8"));
+        let unknown = Formats::new(&config, None);
+        let time = NaiveDateTime::from_timestamp_opt(1_700_000_000, 0).unwrap();
+
+        assert_eq!(us.clock(time), "10:13 PM");
+        assert_eq!(us.date(time), "11/14/2023");
+        assert_eq!(de.clock(time), "22:13");
+        assert_eq!(de.date(time), "14.11.2023");
+        assert_eq!(unknown.date(time), "2023-11-14");
+
+        let config = TimeFormatConfig {
+            clock: ClockFormat::H24,
+            relative: true,
+            date_order: DateOrder::DayMonthYear,
+        };
+        let forced = Formats::new(&config, Some("en_US"));
+        assert_eq!(forced.clock_secs(time), "22:13:20");
+        assert_eq!(forced.date(time), "14/11/2023");
+
+        let later = |secs| time + chrono::Duration::seconds(secs);
+        assert_eq!(
+            forced.relative(time, later(-5), 60).as_deref(),
+            Some("just now")
+        );
+        assert_eq!(
+            forced.relative(time, later(300), 3600).as_deref(),
+            Some("5 min ago")
+        );
+        assert_eq!(forced.relative(time, later(7200), 3600), None);
+        assert_eq!(us.relative(time, later(300), 3600), None);
+    }
+}
@@ -0,0 +1,167 @@
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Length};
+
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::config::Appearance;
+use crate::error::BackendClosed;
+use crate::icon::add_friend_icon;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::types::FollowerItem;
+use crate::utils::from_naive_utc_to_local;
+use crate::views::RouterCommand;
+use crate::widget::Element;
+
+use super::home::HomeGoTo;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    OpenPressed(FollowerItem),
+    FollowBackPressed(FollowerItem),
+}
+
+pub struct State {
+    items: Vec<FollowerItem>,
+    loading: bool,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchFollowers)?;
+        Ok(Self {
+            items: vec![],
+            loading: true,
+        })
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<Option<HomeGoTo>, BackendClosed> {
+        match message {
+            Message::OpenPressed(item) => return Ok(Some(HomeGoTo::Chat(item.contact))),
+            Message::FollowBackPressed(item) => {
+                if let Some(listed) = self
+                    .items
+                    .iter_mut()
+                    .find(|i| i.follower.pubkey == item.follower.pubkey)
+                {
+                    listed.followed_back = true;
+                }
+                conn.send(ToBackend::AddContact(item.contact))?;
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn backend_event(
+        &mut self,
+        event: BackendEvent,
+        conn: &mut BackEndConnection,
+    ) -> Result<RouterCommand<Message>, BackendClosed> {
+        let commands = RouterCommand::new();
+
+        match event {
+            BackendEvent::GotFollowers(items) => {
+                self.loading = false;
+                // new ones stay highlighted until the panel is opened again
+                if items.iter().any(|i| !i.follower.seen) {
+                    conn.send(ToBackend::FollowersSeen)?;
+                }
+                self.items = items;
+            }
+            BackendEvent::NewFollower(_) => {
+                conn.send(ToBackend::FetchFollowers)?;
+            }
+            BackendEvent::ContactCreated(db_contact) => {
+                if let Some(item) = self
+                    .items
+                    .iter_mut()
+                    .find(|i| &i.follower.pubkey == db_contact.pubkey())
+                {
+                    item.followed_back = true;
+                    item.contact = db_contact;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(commands)
+    }
+
+    pub fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        _appearance: Appearance,
+    ) -> Element<Message> {
+        let title = title("Activity");
+
+        let content: Element<_> = if self.items.is_empty() {
+            let empty = if self.loading {
+                "Loading..."
+            } else {
+                "Nobody added you to their contacts yet"
+            };
+            text(empty).style(style::Text::Placeholder).into()
+        } else {
+            self.items
+                .iter()
+                .fold(column![].spacing(10), |col, item| col.push(item_view(item)))
+                .into()
+        };
+
+        common_scrollable(
+            container(column![title, content].spacing(10))
+                .width(Length::Fill)
+                .padding([20, 20, 0, 20]),
+        )
+        .into()
+    }
+}
+
+fn item_view(item: &FollowerItem) -> Element<'_, Message> {
+    let time = from_naive_utc_to_local(item.follower.followed_at)
+        .format(TIME_FORMAT)
+        .to_string();
+
+    let name_btn = button(text(item.contact.select_name()))
+        .style(style::Button::Invisible)
+        .on_press(Message::OpenPressed(item.to_owned()));
+    let new_label: Element<_> = if item.follower.seen {
+        Space::with_width(0).into()
+    } else {
+        text("New").size(14).style(style::Text::Primary).into()
+    };
+    let follow_btn: Element<_> = if item.followed_back {
+        text("Following").style(style::Text::Placeholder).into()
+    } else {
+        button(
+            row![add_friend_icon().size(16), text("Follow back")]
+                .align_items(Alignment::Center)
+                .spacing(5),
+        )
+        .style(style::Button::Primary)
+        .on_press(Message::FollowBackPressed(item.to_owned()))
+        .into()
+    };
+
+    let content = row![
+        name_btn,
+        text("added you").style(style::Text::Placeholder),
+        new_label,
+        Space::with_width(Length::Fill),
+        text(time).size(14).style(style::Text::Alpha(0.5)),
+        follow_btn
+    ]
+    .align_items(Alignment::Center)
+    .spacing(10);
+
+    container(content)
+        .padding(10)
+        .width(Length::Fill)
+        .style(style::Container::Foreground)
+        .into()
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
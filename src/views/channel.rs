@@ -1,29 +1,42 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
+use chrono::Utc;
 use iced::widget::{
-    button, column, container,
-    image::{Handle, Image},
-    row, scrollable, text, text_input, Space,
+    button, column, container, image::Handle, row, scrollable, text, text_input, Space,
 };
-use iced::{alignment, Color, Length};
-use nostr::{secp256k1::XOnlyPublicKey, EventId};
+use iced::{alignment, clipboard, Color, Command, Length, Subscription};
+use nostr::{secp256k1::XOnlyPublicKey, EventId, Url};
 use once_cell::sync::Lazy;
 
 use crate::{
     components::{
         chat_view::{self, ChatView},
-        common_scrollable, inform_card,
+        common_scrollable,
+        hover_card::{self, HoverCards},
+        inform_card,
+        poll::{self, poll_card, ComposerAction, PollComposer},
+        quick_switcher::SwitcherTarget,
+        text::rich_text,
+        DimmedImage,
     },
-    consts::default_profile_image,
-    db::{ChannelCache, ProfileCache},
+    config::Appearance,
+    consts::{default_channel_image, default_profile_image},
+    db::{ChannelCache, ChannelMention, DbPoll, DraftTarget, Labels, PollTally, ProfileCache},
     error::BackendClosed,
-    net::{BackEndConnection, BackendEvent, ImageSize, ToBackend},
+    icon::{bell_slash_icon, pin_icon, xmark_icon},
+    net::{BackEndConnection, BackendEvent, ImageKind, ImageSize, ToBackend},
     style::{self, Theme},
-    types::ChatMessage,
+    types::{
+        chat_message::reconcile_at,
+        slash_command::{invite_message, parse_command},
+        ChannelMetadata, ChatMessage, CommandAction, NostrLink, UserMessage,
+    },
     utils::hide_string,
     widget::Element,
 };
 
+use super::modal::{basic_contact, edit_channel, ContactDetails, EditChannel, ModalView};
 use super::{route::Route, RouterCommand};
 
 static CHAT_SCROLLABLE_ID: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
@@ -35,10 +48,37 @@ pub enum Message {
     ChatView(chat_view::Message),
     BackPressed,
     EnterChannelPressed,
+    TogglePollComposer,
+    PollComposer(poll::Message),
+    VotePoll(EventId, String),
+    ConfirmBroadcast,
+    CancelBroadcast,
+    ConfirmDeletion,
+    CloseDeletion,
+    ModalEditChannel(Box<edit_channel::CMessage<Message>>),
+    ModalBasicContact(Box<basic_contact::CMessage<Message>>),
+    HoverCard(hover_card::Message),
+    KeywordInputChange(String),
+    AddKeyword,
+    RemoveKeyword(String),
+    EditChannelPressed,
+    ComposeAnnouncementPressed,
+    AnnouncementInputChange(String),
+    PinAnnouncement,
+    UnpinAnnouncement,
+    CloseAnnouncement,
+    ArchivePressed,
+    UnsubscribePressed,
+    ToggleHeader,
+    InvitePressed,
+    ToggleMutePressed,
+    MarkAllReadPressed,
 }
 pub struct Member {
     pub pubkey: XOnlyPublicKey,
     pub profile: Option<ProfileCache>,
+    /// Thumbnail, the list can be long
+    image: Handle,
 }
 impl Member {
     pub fn name(&self) -> String {
@@ -57,15 +97,50 @@ impl Member {
         Self {
             pubkey: public_key.to_owned(),
             profile: None,
+            image: Handle::from_memory(default_profile_image(MEMBER_IMAGE_SIZE)),
         }
     }
 
     fn with_profile(profile: ProfileCache) -> Self {
+        let image = match &profile.profile_pic_cache {
+            Some(cache) => Handle::from_path(cache.sized_image(MEMBER_IMAGE_SIZE)),
+            None => Handle::from_memory(default_profile_image(MEMBER_IMAGE_SIZE)),
+        };
         Self {
             pubkey: profile.public_key.to_owned(),
             profile: Some(profile),
+            image,
         }
     }
+
+    fn accent(&self) -> Option<Color> {
+        self.profile
+            .as_ref()
+            .and_then(|profile| profile.profile_pic_cache.as_ref())
+            .and_then(|image| image.accent())
+    }
+
+    /// Picture in the metadata that wasn't downloaded yet
+    fn missing_image(&self) -> Option<ToBackend> {
+        let profile = self.profile.as_ref()?;
+        if profile.profile_pic_cache.is_some() {
+            return None;
+        }
+        Some(ToBackend::DownloadImage {
+            image_url: profile.metadata.picture.clone()?,
+            kind: ImageKind::Profile,
+            identifier: self.pubkey.to_string(),
+            event_hash: profile.event_hash,
+        })
+    }
+}
+/// Deletion of one of the user's messages, asked from a bar over the chat
+enum DeletionPrompt {
+    Confirm(ChatMessage),
+    /// Waiting to know which relays accept deletions
+    Sent(i64),
+    /// None of the write relays accept deletions
+    NotSupported,
 }
 pub enum State {
     Loading,
@@ -74,12 +149,46 @@ pub enum State {
         chat_view: ChatView,
         messages: Vec<ChatMessage>,
         members: HashMap<XOnlyPublicKey, Member>,
+        /// Members' profile picture colors, used for their names
+        accents: HashMap<XOnlyPublicKey, Color>,
+        polls: Vec<(DbPoll, PollTally)>,
+        poll_composer: Option<PollComposer>,
     },
 }
 pub struct Channel {
     msgs_scroll_offset: scrollable::RelativeOffset,
     is_subscribed: bool,
+    /// Unsubscribed but kept, the history is read-only
+    is_archived: bool,
     channel_id: EventId,
+    labels: Labels,
+    user_pubkey: Option<XOnlyPublicKey>,
+    /// Members in the user's contact list get a badge
+    contacts: HashSet<XOnlyPublicKey>,
+    /// `@here` message waiting for the owner's confirmation
+    pending_broadcast: Option<String>,
+    deletion: Option<DeletionPrompt>,
+    /// Open by the owner from the channel header
+    edit_modal: Option<EditChannel<Message>>,
+    /// Actions under the header's menu button
+    menu_open: bool,
+    /// Announcement being written by the owner
+    announcement_input: Option<String>,
+    /// Messages with these words go to the inbox
+    keywords: Vec<String>,
+    keyword_input: String,
+    /// Database id of the last message reported as read
+    read_event_id: Option<i64>,
+    /// About text and origin relay shown under the channel name
+    header_expanded: bool,
+    /// Relay the channel was first seen on
+    origin: Option<Url>,
+    /// `nostr:nevent` link copied by the invite button
+    invite_link: Option<String>,
+    is_muted: bool,
+    hover_cards: HoverCards,
+    /// Profile opened from a hover card
+    profile_modal: Option<ContactDetails<Message>>,
     state: State,
 }
 impl Channel {
@@ -96,7 +205,25 @@ impl Channel {
         Ok(Self {
             msgs_scroll_offset: scrollable::RelativeOffset::default(),
             is_subscribed,
+            is_archived: false,
             channel_id,
+            labels: Labels::default(),
+            user_pubkey: None,
+            contacts: HashSet::new(),
+            pending_broadcast: None,
+            deletion: None,
+            edit_modal: None,
+            menu_open: false,
+            announcement_input: None,
+            keywords: vec![],
+            keyword_input: "".into(),
+            read_event_id: None,
+            header_expanded: true,
+            origin: None,
+            invite_link: None,
+            is_muted: false,
+            hover_cards: HoverCards::new(conn)?,
+            profile_modal: None,
             state: State::Loading,
         })
     }
@@ -107,6 +234,23 @@ impl Channel {
     ) -> Result<Self, BackendClosed> {
         conn.send(ToBackend::FetchChannelMessages(cache.channel_id))?;
         conn.send(ToBackend::SubscribeChannelMembersMeta(cache.channel_id))?;
+        conn.send(ToBackend::FetchChannelPolls(cache.channel_id))?;
+        conn.send(ToBackend::FetchChannelKeywords(cache.channel_id))?;
+        conn.send(ToBackend::FetchLabels)?;
+        conn.send(ToBackend::FetchKeys)?;
+        conn.send(ToBackend::FetchContacts)?;
+        conn.send(ToBackend::FetchDrafts)?;
+        conn.send(ToBackend::GetSendKey)?;
+        conn.send(ToBackend::FetchArchivedChannels)?;
+        conn.send(ToBackend::FetchChannelDetails(cache.channel_id))?;
+        if let (None, Some(image_url)) = (&cache.image_cache, &cache.metadata.picture) {
+            conn.send(ToBackend::DownloadImage {
+                image_url: image_url.to_owned(),
+                kind: ImageKind::Channel,
+                identifier: cache.channel_id.to_string(),
+                event_hash: cache.last_event_hash().to_owned(),
+            })?;
+        }
 
         let members = cache
             .members
@@ -118,27 +262,204 @@ impl Channel {
             msgs_scroll_offset: scrollable::RelativeOffset::default(),
             channel_id: cache.channel_id,
             is_subscribed,
+            is_archived: false,
+            labels: Labels::default(),
+            user_pubkey: None,
+            contacts: HashSet::new(),
+            pending_broadcast: None,
+            deletion: None,
+            edit_modal: None,
+            menu_open: false,
+            announcement_input: None,
+            keywords: vec![],
+            keyword_input: "".into(),
+            read_event_id: None,
+            header_expanded: true,
+            origin: None,
+            invite_link: None,
+            is_muted: false,
+            hover_cards: HoverCards::new(conn)?,
+            profile_modal: None,
             state: State::Loaded {
                 cache,
                 chat_view: ChatView::new(),
                 messages: vec![],
                 members,
+                accents: HashMap::new(),
+                polls: vec![],
+                poll_composer: None,
             },
         })
     }
+    /// Reports the newest message scrolled past, estimated from the scroll position
+    fn mark_read(&mut self, conn: &mut BackEndConnection) -> Result<(), BackendClosed> {
+        let State::Loaded { messages, .. } = &self.state else {
+            return Ok(());
+        };
+        let Some(last_idx) = messages.len().checked_sub(1) else {
+            return Ok(());
+        };
+        if !self.is_subscribed {
+            return Ok(());
+        }
+        let idx = ((last_idx as f32 * self.msgs_scroll_offset.y).round() as usize).min(last_idx);
+        // local and pending messages have no id yet
+        let read = messages[..=idx]
+            .iter()
+            .rev()
+            .find_map(ChatMessage::event_id);
+        if let Some(event_id) = read.filter(|id| self.read_event_id != Some(*id)) {
+            self.read_event_id = Some(event_id);
+            conn.send(ToBackend::MarkChannelRead(self.channel_id, event_id))?;
+        }
+        Ok(())
+    }
+
     fn update_cache(&mut self, new_cache: ChannelCache) {
         match &mut self.state {
             State::Loading { .. } => (),
-            State::Loaded { cache, members, .. } => {
+            State::Loaded {
+                cache,
+                members,
+                accents,
+                ..
+            } => {
                 *members = new_cache
                     .members
                     .iter()
                     .map(|public_key| (public_key.to_owned(), Member::new(public_key)))
                     .collect();
+                accents.retain(|pubkey, _| members.contains_key(pubkey));
                 *cache = new_cache;
             }
         }
     }
+    fn is_owner(&self) -> bool {
+        match (&self.state, &self.user_pubkey) {
+            (State::Loaded { cache, .. }, Some(pubkey)) => &cache.creator_pubkey == pubkey,
+            _ => false,
+        }
+    }
+    /// Publishes the metadata with the new announcement, `None` unpins it
+    fn set_announcement(
+        &mut self,
+        announcement: Option<String>,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if let (true, State::Loaded { cache, .. }) = (self.is_owner(), &self.state) {
+            let metadata = ChannelMetadata {
+                announcement,
+                ..cache.metadata.clone()
+            };
+            conn.send(ToBackend::UpdateChannel(
+                self.channel_id,
+                metadata,
+                cache.moderators.clone(),
+            ))?;
+        }
+        self.announcement_input = None;
+        Ok(())
+    }
+    fn open_edit_modal(&mut self) {
+        if let (true, State::Loaded { cache, members, .. }) = (self.is_owner(), &self.state) {
+            let names = members
+                .values()
+                .map(|member| (member.pubkey, member.name()))
+                .collect();
+            self.edit_modal = Some(EditChannel::new(cache, names));
+        }
+    }
+    /// `resend` skips the duplicate check, the user already confirmed it
+    fn send_message(
+        &mut self,
+        content: String,
+        resend: bool,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        let request = conn.request(if resend {
+            ToBackend::ResendChannelMessage(self.channel_id, content.to_owned())
+        } else {
+            ToBackend::SendChannelMessage(self.channel_id, content.to_owned())
+        })?;
+        if let State::Loaded {
+            chat_view,
+            messages,
+            ..
+        } = &mut self.state
+        {
+            // shown right away, replaced when the backend creates the event
+            messages.push(ChatMessage::local(
+                request.id(),
+                &content,
+                Utc::now().naive_utc(),
+            ));
+            chat_view.update_dm_msg("".into());
+        }
+        self.msgs_scroll_offset = scrollable::RelativeOffset::END;
+        Ok(())
+    }
+
+    /// Commands that don't send anything, `Send` and `Invite` go through [`Self::send_message`]
+    fn run_command(
+        &mut self,
+        action: CommandAction,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        let State::Loaded {
+            chat_view,
+            messages,
+            ..
+        } = &mut self.state
+        else {
+            return Ok(());
+        };
+        match action {
+            CommandAction::Send(_) | CommandAction::Invite(_) => return Ok(()),
+            CommandAction::Zap(_) => {
+                chat_view.set_command_error("Zaps are sent in direct messages".into());
+                return Ok(());
+            }
+            CommandAction::Mute => {
+                conn.send(ToBackend::SetChannelMuted(self.channel_id, !self.is_muted))?
+            }
+            CommandAction::Clear => messages.clear(),
+        }
+        conn.send(ToBackend::SaveDraft(
+            DraftTarget::Channel(self.channel_id),
+            "".into(),
+        ))?;
+        chat_view.update_dm_msg("".into());
+        Ok(())
+    }
+
+    /// Matches the events of a send request with the local message shown when it was sent
+    fn reconcile_local_message(&mut self, event: &BackendEvent) {
+        let State::Loaded { messages, .. } = &mut self.state else {
+            return;
+        };
+        match event {
+            BackendEvent::Response(id, inner) => {
+                let idx = messages.iter().position(|m| m.match_local_request(id));
+                match (inner.as_ref(), idx) {
+                    (BackendEvent::PendingChannelMsg(_, chat_message), Some(idx)) => {
+                        reconcile_at(messages, idx, chat_message.to_owned());
+                    }
+                    // the bar asking to send it again comes with the event itself
+                    (BackendEvent::DuplicateChannelMsg(..), Some(idx)) => {
+                        messages.remove(idx);
+                    }
+                    _ => (),
+                }
+            }
+            BackendEvent::RequestFailed(id, e) => {
+                if let Some(message) = messages.iter_mut().find(|m| m.match_local_request(id)) {
+                    tracing::error!("Failed to send channel message: {}", e);
+                    message.set_local_failed();
+                }
+            }
+            _ => (),
+        }
+    }
     fn name(&self) -> String {
         match &self.state {
             State::Loading { .. } => "Loading...".into(),
@@ -153,6 +474,10 @@ impl Channel {
 impl Route for Channel {
     type Message = Message;
 
+    fn subscription(&self) -> Subscription<Self::Message> {
+        ChatView::subscription().map(Message::ChatView)
+    }
+
     fn backend_event(
         &mut self,
         event: crate::net::BackendEvent,
@@ -160,25 +485,202 @@ impl Route for Channel {
     ) -> Result<super::RouterCommand<Self::Message>, BackendClosed> {
         let mut command = RouterCommand::new();
 
+        self.reconcile_local_message(&event);
+        self.hover_cards.backend_event(&event, conn)?;
+        if let Some(modal) = &mut self.profile_modal {
+            modal.backend_event(event.clone(), conn)?;
+        }
+
         match event {
             BackendEvent::GotChannelCache(cache) => {
                 if self.matches_id(&cache.channel_id) {
                     *self = Self::loaded(cache, self.is_subscribed, conn)?;
                 }
             }
+            BackendEvent::GotLabels(labels) => {
+                self.labels = labels;
+            }
+            BackendEvent::PdfExport(status) => {
+                if let State::Loaded { chat_view, .. } = &mut self.state {
+                    chat_view.pdf_export_status(status);
+                }
+            }
+            BackendEvent::RFDCancelPick => {
+                if let State::Loaded { chat_view, .. } = &mut self.state {
+                    chat_view.pdf_export_cancelled();
+                }
+            }
+            BackendEvent::UploadProgress(status) => {
+                if let (DraftTarget::Channel(channel_id), State::Loaded { chat_view, .. }) =
+                    (status.target, &mut self.state)
+                {
+                    if self.channel_id == channel_id && chat_view.upload_status(status) {
+                        let text = chat_view.dm_msg_input().to_owned();
+                        conn.send(ToBackend::SaveDraft(DraftTarget::Channel(channel_id), text))?;
+                    }
+                }
+            }
+            BackendEvent::GotDrafts(drafts) => {
+                let draft = drafts.into_iter().find_map(|item| match item.target {
+                    SwitcherTarget::Channel(channel_id) if channel_id == self.channel_id => {
+                        item.draft
+                    }
+                    _ => None,
+                });
+                if let (Some(draft), State::Loaded { chat_view, .. }) = (draft, &mut self.state) {
+                    chat_view.update_dm_msg(draft);
+                }
+            }
+            BackendEvent::GotSendKey(send_key) => {
+                if let State::Loaded { chat_view, .. } = &mut self.state {
+                    chat_view.set_send_key(send_key);
+                }
+            }
+            BackendEvent::GotKeys(keys) => {
+                self.user_pubkey = Some(keys.public_key());
+            }
+            BackendEvent::GotContacts(contacts) => {
+                self.contacts = contacts.iter().map(|c| c.pubkey().to_owned()).collect();
+            }
+            BackendEvent::ContactCreated(db_contact) => {
+                self.contacts.insert(db_contact.pubkey().to_owned());
+            }
+            BackendEvent::ContactDeleted(db_contact) => {
+                self.contacts.remove(db_contact.pubkey());
+            }
+            BackendEvent::PendingChannelMsg(channel_id, new_message) => {
+                if self.matches_id(&channel_id) {
+                    if let State::Loaded { messages, .. } = &mut self.state {
+                        // unless it's the pending event of a local message
+                        let already_shown = match &new_message {
+                            ChatMessage::UserMessage(UserMessage::Pending {
+                                event_hash, ..
+                            }) => messages.iter().any(|m| m.match_pending_hash(event_hash)),
+                            _ => false,
+                        };
+                        if !already_shown {
+                            messages.push(new_message);
+                        }
+                    }
+                    self.msgs_scroll_offset = scrollable::RelativeOffset::END;
+                    command.push(scrollable::snap_to(
+                        CHAT_SCROLLABLE_ID.clone(),
+                        self.msgs_scroll_offset,
+                    ));
+                }
+            }
+            BackendEvent::ConfirmedChannelMsg(channel_id, event_hash, confirmed) => {
+                if self.matches_id(&channel_id) {
+                    if let State::Loaded { messages, .. } = &mut self.state {
+                        if let Some(idx) = messages
+                            .iter()
+                            .position(|m| m.match_pending_hash(&event_hash))
+                        {
+                            reconcile_at(messages, idx, confirmed);
+                        } else if !messages
+                            .iter()
+                            .any(|m| m.event_id() == confirmed.event_id())
+                        {
+                            messages.push(confirmed);
+                            messages.sort_by(|a, b| a.display_time().cmp(&b.display_time()));
+                        }
+                    }
+                }
+            }
+            BackendEvent::PendingRejected(event_hash, _) => {
+                if let State::Loaded { messages, .. } = &mut self.state {
+                    if let Some(message) = messages
+                        .iter_mut()
+                        .find(|m| m.match_pending_hash(&event_hash))
+                    {
+                        message.set_pending_rejected();
+                    }
+                }
+            }
+            BackendEvent::DuplicateChannelMsg(channel_id, content) => {
+                if self.matches_id(&channel_id) {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        chat_view.confirm_duplicate(content);
+                    }
+                }
+            }
             BackendEvent::ChannelCacheUpdated(cache) => {
                 if self.matches_id(&cache.channel_id) {
-                    self.update_cache(cache)
+                    let moderators_changed = match &self.state {
+                        State::Loaded { cache: old, .. } => old.moderators != cache.moderators,
+                        State::Loading => false,
+                    };
+                    self.update_cache(cache);
+                    if moderators_changed {
+                        conn.send(ToBackend::FetchChannelMessages(self.channel_id))?;
+                    }
+                }
+            }
+            BackendEvent::ChannelModerated(channel_id) => {
+                if self.matches_id(&channel_id) {
+                    conn.send(ToBackend::FetchChannelMessages(channel_id))?;
                 }
             }
             BackendEvent::ChannelSubscribed(channel_id) => {
                 if self.matches_id(&channel_id) {
                     self.is_subscribed = true;
+                    self.is_archived = false;
                 }
             }
             BackendEvent::ChannelUnsubscribed(channel_id) => {
                 if self.matches_id(&channel_id) {
                     self.is_subscribed = false;
+                    self.is_archived = false;
+                }
+            }
+            BackendEvent::ChannelArchived(channel_id) => {
+                if self.matches_id(&channel_id) {
+                    self.is_subscribed = false;
+                    self.is_archived = true;
+                }
+            }
+            BackendEvent::GotChannelDetails {
+                channel_id,
+                origin,
+                invite_link,
+            } => {
+                if self.matches_id(&channel_id) {
+                    self.origin = origin;
+                    self.invite_link = Some(invite_link);
+                }
+            }
+            BackendEvent::GotMutedChannels(muted) => {
+                self.is_muted = muted.contains(&self.channel_id);
+            }
+            BackendEvent::GotArchivedChannels(caches) => {
+                self.is_archived = caches
+                    .iter()
+                    .any(|cache| self.matches_id(&cache.channel_id));
+            }
+            BackendEvent::ChannelMessageRetracted(channel_id, event_id)
+                if self.matches_id(&channel_id) =>
+            {
+                if let State::Loaded { messages, .. } = &mut self.state {
+                    if let Some(msg) = messages.iter_mut().find(|m| m.event_id() == Some(event_id))
+                    {
+                        msg.retract();
+                    }
+                }
+            }
+            BackendEvent::RelayDeletionUpdated(deletion) => {
+                if let Some(DeletionPrompt::Sent(event_id)) = self.deletion {
+                    if deletion.event_id == event_id {
+                        self.deletion =
+                            (!deletion.is_supported()).then_some(DeletionPrompt::NotSupported);
+                    }
+                }
+            }
+            BackendEvent::ReactionsUpdated(event_id, reactions) => {
+                if let State::Loaded { messages, .. } = &mut self.state {
+                    if let Some(msg) = messages.iter_mut().find(|m| m.event_id() == Some(event_id))
+                    {
+                        msg.set_reactions(reactions);
+                    }
                 }
             }
             BackendEvent::GotChannelMessages(channel_id, new_messages) => {
@@ -201,6 +703,7 @@ impl Route for Channel {
                     CHAT_SCROLLABLE_ID.clone(),
                     self.msgs_scroll_offset,
                 ));
+                self.mark_read(conn)?;
             }
             BackendEvent::ReceivedChannelMessage(channel_id, new_message) => {
                 // match &mut message {
@@ -219,6 +722,39 @@ impl Route for Channel {
                             messages.sort_by(|a, b| a.display_time().cmp(&b.display_time()))
                         }
                     }
+                    self.mark_read(conn)?;
+                }
+            }
+            BackendEvent::GotChannelKeywords(channel_id, keywords) => {
+                if self.matches_id(&channel_id) {
+                    self.keywords = keywords;
+                }
+            }
+            BackendEvent::GotChannelPolls(channel_id, new_polls) => {
+                if self.matches_id(&channel_id) {
+                    if let State::Loaded { polls, .. } = &mut self.state {
+                        *polls = new_polls;
+                    }
+                }
+            }
+            BackendEvent::ReceivedPoll(poll, tally) => {
+                if self.matches_id(&poll.channel_id) {
+                    if let State::Loaded { polls, .. } = &mut self.state {
+                        polls.push((poll, tally));
+                        polls.sort_by(|(a, _), (b, _)| a.created_at.cmp(&b.created_at));
+                    }
+                }
+            }
+            BackendEvent::PollTallyUpdated(channel_id, tally) => {
+                if self.matches_id(&channel_id) {
+                    if let State::Loaded { polls, .. } = &mut self.state {
+                        if let Some((_, old)) = polls
+                            .iter_mut()
+                            .find(|(poll, _)| poll.poll_hash == tally.poll_hash)
+                        {
+                            *old = tally;
+                        }
+                    }
                 }
             }
 
@@ -230,13 +766,42 @@ impl Route for Channel {
                     }
                 }
             },
+            BackendEvent::ImageDownloaded(image) => {
+                if let State::Loaded { cache, .. } = &mut self.state {
+                    if matches!(image.kind, ImageKind::Channel)
+                        && &image.event_hash == cache.last_event_hash()
+                    {
+                        cache.image_cache = Some(image.clone());
+                    }
+                }
+                if let State::Loaded { members, .. } = &self.state {
+                    let owner = members.values().find(|m| {
+                        m.profile
+                            .as_ref()
+                            .map_or(false, |p| p.event_hash == image.event_hash)
+                    });
+                    if let Some(member) = owner {
+                        conn.send(ToBackend::FetchProfileCache(member.pubkey))?;
+                    }
+                }
+            }
             BackendEvent::GotProfileCache(pubkey, profile) => match &mut self.state {
                 State::Loading => (),
                 State::Loaded {
-                    members, messages, ..
+                    members,
+                    messages,
+                    accents,
+                    ..
                 } => {
                     if let Some(member) = members.get_mut(&pubkey) {
                         *member = Member::with_profile(profile);
+                        if let Some(download) = member.missing_image() {
+                            conn.send(download)?;
+                        }
+                        match member.accent() {
+                            Some(accent) => accents.insert(pubkey, accent),
+                            None => accents.remove(&pubkey),
+                        };
 
                         messages.iter_mut().for_each(|m| {
                             m.update_display_name(&member.pubkey, member.name());
@@ -269,56 +834,383 @@ impl Route for Channel {
             Message::EnterChannelPressed => {
                 conn.send(ToBackend::SubscribeToChannel(self.channel_id.to_owned()))?;
             }
+            Message::TogglePollComposer => {
+                if let State::Loaded { poll_composer, .. } = &mut self.state {
+                    *poll_composer = match poll_composer {
+                        Some(_) => None,
+                        None => Some(PollComposer::new()),
+                    };
+                }
+            }
+            Message::PollComposer(poll_msg) => {
+                if let State::Loaded { poll_composer, .. } = &mut self.state {
+                    let action = poll_composer
+                        .as_mut()
+                        .and_then(|composer| composer.update(poll_msg));
+                    match action {
+                        Some(ComposerAction::Publish { question, options }) => {
+                            conn.send(ToBackend::CreatePoll(self.channel_id, question, options))?;
+                            *poll_composer = None;
+                        }
+                        Some(ComposerAction::Close) => *poll_composer = None,
+                        None => (),
+                    }
+                }
+            }
+            Message::ConfirmBroadcast => {
+                if let Some(content) = self.pending_broadcast.take() {
+                    self.send_message(content, false, conn)?;
+                }
+            }
+            Message::CancelBroadcast => self.pending_broadcast = None,
+            Message::ConfirmDeletion => {
+                if let Some(DeletionPrompt::Confirm(chat_msg)) = self.deletion.take() {
+                    if let Some(event_id) = chat_msg.event_id() {
+                        conn.send(ToBackend::RequestRelayDeletion(chat_msg))?;
+                        self.deletion = Some(DeletionPrompt::Sent(event_id));
+                    }
+                }
+            }
+            Message::CloseDeletion => self.deletion = None,
+            Message::KeywordInputChange(text) => self.keyword_input = text,
+            Message::AddKeyword => {
+                let keyword = self.keyword_input.trim().to_owned();
+                let is_new = !self
+                    .keywords
+                    .iter()
+                    .any(|k| k.to_lowercase() == keyword.to_lowercase());
+                if !keyword.is_empty() && is_new {
+                    let mut keywords = self.keywords.clone();
+                    keywords.push(keyword);
+                    conn.send(ToBackend::SetChannelKeywords(self.channel_id, keywords))?;
+                }
+                self.keyword_input.clear();
+            }
+            Message::RemoveKeyword(keyword) => {
+                let keywords = self
+                    .keywords
+                    .iter()
+                    .filter(|k| *k != &keyword)
+                    .cloned()
+                    .collect();
+                conn.send(ToBackend::SetChannelKeywords(self.channel_id, keywords))?;
+            }
+            Message::EditChannelPressed => {
+                self.menu_open = false;
+                self.open_edit_modal();
+            }
+            Message::ComposeAnnouncementPressed => {
+                self.menu_open = false;
+                if let State::Loaded { cache, .. } = &self.state {
+                    self.announcement_input =
+                        Some(cache.metadata.announcement.clone().unwrap_or_default());
+                }
+            }
+            Message::AnnouncementInputChange(text) => self.announcement_input = Some(text),
+            Message::PinAnnouncement => {
+                let announcement = self
+                    .announcement_input
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty())
+                    .map(str::to_owned);
+                if announcement.is_some() {
+                    self.set_announcement(announcement, conn)?;
+                }
+            }
+            Message::UnpinAnnouncement => self.set_announcement(None, conn)?,
+            Message::ArchivePressed => {
+                self.menu_open = false;
+                conn.send(ToBackend::ArchiveChannel(self.channel_id))?;
+            }
+            Message::UnsubscribePressed => {
+                self.menu_open = false;
+                conn.send(ToBackend::UnsubscribeToChannel(self.channel_id))?;
+            }
+            Message::ToggleHeader => self.header_expanded = !self.header_expanded,
+            Message::InvitePressed => {
+                if let Some(link) = &self.invite_link {
+                    command.push(clipboard::write(link.to_owned()));
+                }
+            }
+            Message::MarkAllReadPressed => {
+                if self.is_subscribed {
+                    conn.send(ToBackend::MarkChannelAllRead(self.channel_id))?;
+                }
+            }
+            Message::ToggleMutePressed => {
+                if self.is_subscribed {
+                    conn.send(ToBackend::SetChannelMuted(self.channel_id, !self.is_muted))?;
+                }
+            }
+            Message::CloseAnnouncement => self.announcement_input = None,
+            Message::ModalEditChannel(modal_msg) => {
+                if let Some(modal) = &mut self.edit_modal {
+                    match *modal_msg {
+                        edit_channel::CMessage::UnderlayMessage(message) => {
+                            return self.update(message, conn);
+                        }
+                        other => {
+                            let (_, close_modal) = modal.update(other, conn)?;
+                            if close_modal {
+                                self.edit_modal = None;
+                            }
+                        }
+                    }
+                }
+            }
+            Message::ModalBasicContact(modal_msg) => {
+                if let Some(modal) = &mut self.profile_modal {
+                    match *modal_msg {
+                        basic_contact::CMessage::UnderlayMessage(message) => {
+                            return self.update(message, conn);
+                        }
+                        basic_contact::CMessage::OpenChannel(channel_id) => {
+                            self.profile_modal = None;
+                            command.change_route(super::GoToView::Channel(channel_id));
+                        }
+                        other => {
+                            let (cmd, close_modal) = modal.update(other, conn)?;
+                            if close_modal {
+                                self.profile_modal = None;
+                            }
+                            command.push(cmd.map(|m| Message::ModalBasicContact(Box::new(m))));
+                        }
+                    }
+                }
+            }
+            Message::HoverCard(card_msg) => self.hover_card_update(card_msg, &mut command, conn)?,
+            Message::VotePoll(poll_hash, option_id) => {
+                if self.is_subscribed {
+                    conn.send(ToBackend::VotePoll(self.channel_id, poll_hash, option_id))?;
+                }
+            }
             Message::ChatView(ch_msg) => match ch_msg {
-                chat_view::Message::DMSentPress(_) => tracing::info!("DM sent!"),
-                chat_view::Message::DMNMessageChange(_) => {
-                    tracing::info!("DMNMessageChange")
+                chat_view::Message::DMSentPress(content) => {
+                    let content = content.trim().to_owned();
+                    if !self.is_subscribed || content.is_empty() {
+                        return Ok(command);
+                    }
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        if !chat_view.debounce_send(Instant::now()) {
+                            return Ok(command);
+                        }
+                    }
+                    let content = match parse_command(&content) {
+                        None => content,
+                        Some(Ok(CommandAction::Send(text))) => text,
+                        Some(Ok(CommandAction::Invite(channel_id))) => invite_message(&channel_id),
+                        Some(Ok(action)) => {
+                            self.run_command(action, conn)?;
+                            return Ok(command);
+                        }
+                        Some(Err(e)) => {
+                            if let State::Loaded { chat_view, .. } = &mut self.state {
+                                chat_view.set_command_error(e.to_string());
+                            }
+                            return Ok(command);
+                        }
+                    };
+                    if self.is_owner() && ChannelMention::has_broadcast_keyword(&content) {
+                        self.pending_broadcast = Some(content);
+                    } else {
+                        self.send_message(content, false, conn)?;
+                    }
+                }
+                chat_view::Message::DMNMessageChange(text) => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        conn.send(ToBackend::SaveDraft(
+                            DraftTarget::Channel(self.channel_id),
+                            text.clone(),
+                        ))?;
+                        chat_view.update_dm_msg(text);
+                    }
                 }
                 chat_view::Message::GotChatSize(_, _) => tracing::info!("GotChatSize"),
-                chat_view::Message::Scrolled(_) => tracing::info!("Scrolled"),
+                chat_view::Message::Scrolled(offset) => {
+                    self.msgs_scroll_offset = offset;
+                    self.mark_read(conn)?;
+                }
                 chat_view::Message::OpenContactProfile => {
                     tracing::info!("OpenContactProfile")
                 }
-                chat_view::Message::ChatRightClick(_, _) => {
-                    tracing::info!("ChatRightClick")
-                }
-                chat_view::Message::ChannelOpenModalPressed => {
-                    tracing::info!("ChannelOpenModalPressed")
+                chat_view::Message::ChatRightClick(chat_msg, _) => {
+                    // only the user's stored messages can be deleted
+                    if matches!(
+                        chat_msg,
+                        ChatMessage::UserMessage(UserMessage::Confirmed {
+                            retracted: false,
+                            ..
+                        })
+                    ) {
+                        self.deletion = Some(DeletionPrompt::Confirm(chat_msg));
+                    }
                 }
+                chat_view::Message::ChannelOpenModalPressed => self.open_edit_modal(),
                 chat_view::Message::ChannelSearchPressed => {
                     tracing::info!("ChannelSearchPressed")
                 }
                 chat_view::Message::ChannelMenuPressed => {
-                    tracing::info!("ChannelMenuPressed")
+                    self.menu_open = (self.is_owner() || self.is_subscribed) && !self.menu_open;
+                }
+                chat_view::Message::PdfExportPressed => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        chat_view.toggle_pdf_export();
+                    }
+                }
+                chat_view::Message::PdfIncludeImagesToggled(include_images) => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        chat_view.set_pdf_include_images(include_images);
+                    }
+                }
+                chat_view::Message::ModifiersChanged(modifiers) => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        chat_view.set_modifiers(modifiers);
+                    }
+                }
+                chat_view::Message::TogglePreview => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        chat_view.toggle_preview();
+                    }
+                }
+                chat_view::Message::PdfExportConfirm(include_images) => {
+                    if let State::Loaded { messages, .. } = &self.state {
+                        conn.send(ToBackend::ExportConversationPdf {
+                            title: self.name(),
+                            messages: messages.clone(),
+                            include_images,
+                        })?;
+                    }
+                }
+                chat_view::Message::ToggleAutoTranslate
+                | chat_view::Message::ToggleAutoSaveAttachments
+                | chat_view::Message::FetchOlderHistory
+                | chat_view::Message::TipPressed
+                | chat_view::Message::TemplatesPressed
+                | chat_view::Message::TemplateSelected(_)
+                | chat_view::Message::DeleteDuplicatePressed(_)
+                | chat_view::Message::ArchiveExportPressed
+                | chat_view::Message::ClearHistoryPressed
+                | chat_view::Message::ClearKeepStarredToggled(_)
+                | chat_view::Message::ClearHistoryConfirm(_)
+                | chat_view::Message::ChatPress(_)
+                | chat_view::Message::ChatRelease(_)
+                | chat_view::Message::SelectionCopyPressed
+                | chat_view::Message::SelectionForwardPressed
+                | chat_view::Message::SelectionDeletePressed
+                | chat_view::Message::SelectionCancelPressed => (),
+                chat_view::Message::CommandSelected(name) => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        let cursor = chat_view.select_command(name);
+                        command.push(Command::batch(vec![
+                            text_input::focus(CHAT_INPUT_ID.clone()),
+                            text_input::move_cursor_to(CHAT_INPUT_ID.clone(), cursor),
+                        ]));
+                    }
+                }
+                chat_view::Message::ToggleLabel(label_id) => {
+                    conn.send(ToBackend::ToggleLabel(
+                        label_id,
+                        self.channel_id.to_string(),
+                    ))?;
                 }
                 chat_view::Message::ChannelUserNamePressed(author) => {
                     tracing::info!("ChannelUserNamePressed: {}", author)
                 }
+                chat_view::Message::NostrLinkPressed(link) => {
+                    command.change_route(super::GoToView::Link(link));
+                }
+                chat_view::Message::HashtagPressed(hashtag) => {
+                    command.change_route(super::GoToView::Hashtag(hashtag));
+                }
+                chat_view::Message::HoverCard(card_msg) => {
+                    self.hover_card_update(card_msg, &mut command, conn)?;
+                }
+                chat_view::Message::ReactionPressed(event_id, content) => {
+                    conn.send(ToBackend::SendReaction(event_id, content))?;
+                }
+                chat_view::Message::ResendDuplicatePressed => {
+                    let duplicate = match &mut self.state {
+                        State::Loaded { chat_view, .. } => chat_view.take_duplicate(),
+                        State::Loading { .. } => None,
+                    };
+                    if let Some(content) = duplicate {
+                        self.send_message(content, true, conn)?;
+                    }
+                }
+                chat_view::Message::DiscardDuplicatePressed => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        chat_view.take_duplicate();
+                    }
+                }
+                chat_view::Message::AttachPressed(keep_metadata) => {
+                    if self.is_subscribed {
+                        let target = DraftTarget::Channel(self.channel_id);
+                        conn.send(ToBackend::ChooseAttachment {
+                            target,
+                            keep_metadata,
+                        })?;
+                    }
+                }
+                chat_view::Message::CancelUploadPressed(id) => {
+                    conn.send(ToBackend::CancelUpload(id))?;
+                }
+                chat_view::Message::ResumeUploadPressed(id) => {
+                    conn.send(ToBackend::ResumeUpload(id))?;
+                }
+                chat_view::Message::DismissUploadPressed => {
+                    if let State::Loaded { chat_view, .. } = &mut self.state {
+                        chat_view.dismiss_upload();
+                    }
+                }
             },
         }
 
         Ok(command)
     }
 
-    fn view(&self, _selected_theme: Option<Theme>) -> Element<'_, Self::Message> {
+    fn view(
+        &self,
+        _selected_theme: Option<Theme>,
+        appearance: Appearance,
+    ) -> Element<'_, Self::Message> {
         match &self.state {
             State::Loading { .. } => inform_card("Loading Channel", "Please wait"),
             State::Loaded {
+                cache,
                 chat_view,
                 messages,
                 members,
-                ..
+                accents,
+                polls,
+                poll_composer,
             } => {
                 // let members_list = make_member_list(self.channel.members.iter(), Message::MemberPressed);
 
                 let members_list = members
                     .iter()
                     .fold(column![].spacing(5), |col, (_, member)| {
-                        col.push(member_btn(member))
+                        let btn = member_btn(
+                            member,
+                            self.contacts.contains(&member.pubkey),
+                            cache.moderators.contains(&member.pubkey),
+                            appearance.dim_images,
+                        );
+                        col.push(
+                            self.hover_cards
+                                .wrap(&member.pubkey, btn, Message::HoverCard),
+                        )
                     });
-                let members_list = container(common_scrollable(
-                    column![text("Members").size(24), members_list].spacing(10),
-                ))
+                let members_list = container(
+                    column![
+                        container(common_scrollable(
+                            column![text("Members").size(24), members_list].spacing(10),
+                        ))
+                        .height(Length::Fill),
+                        self.keywords_view()
+                    ]
+                    .spacing(10),
+                )
                 .padding(10)
                 .height(Length::Fill)
                 .width(MEMBERS_LIST_WIDTH)
@@ -329,13 +1221,51 @@ impl Route for Channel {
                         &CHAT_SCROLLABLE_ID,
                         &CHAT_INPUT_ID,
                         messages,
+                        accents,
                         &self.name(),
                         members.len() as i32,
                         !self.is_subscribed,
+                        &self.labels,
+                        &self.channel_id.to_string(),
+                        &self.hover_cards,
+                        appearance,
                     )
                     .map(Message::ChatView);
 
-                let content = row![members_list, chat_view];
+                let on_vote = if self.is_subscribed {
+                    Some(Message::VotePoll as fn(EventId, String) -> Message)
+                } else {
+                    None
+                };
+                let polls_list = polls
+                    .iter()
+                    .fold(column![].spacing(5), |col, (poll, tally)| {
+                        col.push(poll_card(poll, tally, on_vote))
+                    });
+                let composer: Element<_> = match poll_composer {
+                    Some(composer) => composer.view().map(Message::PollComposer),
+                    None => text("").into(),
+                };
+                let mut poll_btn = button("Poll").style(style::Button::MenuBtn);
+                if self.is_subscribed {
+                    poll_btn = poll_btn.on_press(Message::TogglePollComposer);
+                }
+                let polls_panel = container(
+                    column![
+                        row![
+                            text("Polls").size(18),
+                            Space::with_width(Length::Fill),
+                            poll_btn
+                        ]
+                        .align_items(alignment::Alignment::Center),
+                        composer,
+                        container(common_scrollable(polls_list)).max_height(POLLS_MAX_HEIGHT)
+                    ]
+                    .spacing(5),
+                )
+                .padding(10);
+
+                let content = row![members_list, column![polls_panel, chat_view]];
 
                 let show_join: Element<_> = if self.is_subscribed {
                     text("").into()
@@ -343,15 +1273,25 @@ impl Route for Channel {
                     let back_btn = button("Back")
                         .on_press(Message::BackPressed)
                         .style(style::Button::HighlightButton);
-                    let subscribe_btn = button(text(&format!("Enter {}", self.name())))
+                    let (hint, enter_label) = if self.is_archived {
+                        (
+                            "Archived, the history is read-only",
+                            "Re-subscribe".to_owned(),
+                        )
+                    } else {
+                        (
+                            "Subscribe to this channel",
+                            format!("Enter {}", self.name()),
+                        )
+                    };
+                    let subscribe_btn = button(text(enter_label))
                         .on_press(Message::EnterChannelPressed)
                         .style(style::Button::HighlightButton);
                     container(
                         row![
                             back_btn,
                             Space::with_width(Length::Fill),
-                            text("Subscribe to this channel")
-                                .style(style::Text::Color(Color::WHITE)),
+                            text(hint).style(style::Text::Color(Color::WHITE)),
                             Space::with_width(10),
                             subscribe_btn,
                             Space::with_width(Length::Fill)
@@ -363,22 +1303,357 @@ impl Route for Channel {
                     .into()
                 };
 
-                column![show_join, content].into()
+                let broadcast_bar: Element<_> = if self.pending_broadcast.is_some() {
+                    container(
+                        row![
+                            text("@here notifies every member of the channel. Send it?")
+                                .style(style::Text::Color(Color::WHITE)),
+                            Space::with_width(Length::Fill),
+                            button("Cancel")
+                                .on_press(Message::CancelBroadcast)
+                                .style(style::Button::HighlightButton),
+                            button("Send")
+                                .on_press(Message::ConfirmBroadcast)
+                                .style(style::Button::HighlightButton),
+                        ]
+                        .spacing(10)
+                        .align_items(alignment::Alignment::Center),
+                    )
+                    .style(style::Container::Highlight)
+                    .padding(10)
+                    .into()
+                } else {
+                    text("").into()
+                };
+
+                let underlay = column![
+                    self.channel_header(cache, members.len(), appearance),
+                    show_join,
+                    broadcast_bar,
+                    self.deletion_bar(),
+                    self.channel_menu(),
+                    self.announcement_view(cache),
+                    content
+                ];
+                let underlay: Element<_> = match &self.profile_modal {
+                    Some(modal) => modal
+                        .view(underlay, appearance)
+                        .map(|m| Message::ModalBasicContact(Box::new(m))),
+                    None => underlay.into(),
+                };
+                match &self.edit_modal {
+                    Some(modal) => modal
+                        .view(underlay, appearance)
+                        .map(|m| Message::ModalEditChannel(Box::new(m))),
+                    None => underlay.into(),
+                }
             }
         }
     }
 }
 
-fn member_btn(member: &Member) -> Element<'_, Message> {
-    let content = row![
-        container(Image::new(Handle::from_memory(default_profile_image(
-            ImageSize::Small
-        ))))
-        .width(30)
-        .height(30),
+impl Channel {
+    fn hover_card_update(
+        &mut self,
+        message: hover_card::Message,
+        command: &mut RouterCommand<Message>,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match self.hover_cards.update(message, conn)? {
+            Some(hover_card::Action::OpenChat(pubkey)) => {
+                command.change_route(super::GoToView::Link(NostrLink::Profile(pubkey, vec![])));
+            }
+            Some(hover_card::Action::ShowProfile(contact)) => {
+                self.profile_modal = Some(ContactDetails::viewer(&contact, conn)?);
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    /// Picture, name and member count, with the about text and origin relay when expanded
+    fn channel_header<'a>(
+        &'a self,
+        cache: &'a ChannelCache,
+        members: usize,
+        appearance: Appearance,
+    ) -> Element<'a, Message> {
+        let image_handle = match &cache.image_cache {
+            Some(image) => Handle::from_path(image.sized_image(HEADER_IMAGE_SIZE)),
+            None => Handle::from_memory(default_channel_image(HEADER_IMAGE_SIZE)),
+        };
+        let image_size = if self.header_expanded { 64 } else { 32 };
+        let member_count = match members {
+            1 => "1 member".to_owned(),
+            n => format!("{} members", n),
+        };
+
+        let mut invite_btn = button("Invite").style(style::Button::MenuBtn);
+        if self.invite_link.is_some() {
+            invite_btn = invite_btn.on_press(Message::InvitePressed);
+        }
+        let mut actions = row![invite_btn]
+            .spacing(5)
+            .align_items(alignment::Alignment::Center);
+        if self.is_subscribed {
+            let mute_label = if self.is_muted { "Unmute" } else { "Mute" };
+            actions = actions
+                .push(
+                    button("Mark all as read")
+                        .on_press(Message::MarkAllReadPressed)
+                        .style(style::Button::MenuBtn),
+                )
+                .push(
+                    button(mute_label)
+                        .on_press(Message::ToggleMutePressed)
+                        .style(style::Button::MenuBtn),
+                )
+                .push(
+                    button("Unsubscribe")
+                        .on_press(Message::UnsubscribePressed)
+                        .style(style::Button::MenuBtn),
+                );
+        }
+        let toggle_label = if self.header_expanded { "Less" } else { "More" };
+        actions = actions.push(
+            button(text(toggle_label).size(14))
+                .on_press(Message::ToggleHeader)
+                .style(style::Button::Link),
+        );
+
+        let mut title = row![text(self.name()).size(20)]
+            .spacing(10)
+            .align_items(alignment::Alignment::Center);
+        if self.is_muted {
+            title = title.push(bell_slash_icon().size(14).style(style::Text::Placeholder));
+        }
+        let mut details = column![
+            title,
+            text(member_count).size(14).style(style::Text::Placeholder)
+        ]
+        .spacing(2);
+        if self.header_expanded {
+            if let Some(about) = cache.metadata.about.as_deref().map(str::trim) {
+                if !about.is_empty() {
+                    details = details.push(
+                        rich_text(about)
+                            .size(14)
+                            .text_direction(appearance.text_direction)
+                            .on_nostr_link(|link| {
+                                Message::ChatView(chat_view::Message::NostrLinkPressed(link))
+                            })
+                            .on_hashtag(|hashtag| {
+                                Message::ChatView(chat_view::Message::HashtagPressed(hashtag))
+                            }),
+                    );
+                }
+            }
+            if let Some(origin) = &self.origin {
+                details = details.push(
+                    text(format!("Created on {}", origin))
+                        .size(12)
+                        .style(style::Text::Placeholder),
+                );
+            }
+        }
+
+        container(
+            row![
+                container(DimmedImage::new(image_handle, appearance.dim_images))
+                    .width(image_size)
+                    .height(image_size),
+                container(details).width(Length::Fill),
+                actions
+            ]
+            .spacing(10)
+            .align_items(alignment::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .padding(10)
+        .style(style::Container::Foreground)
+        .into()
+    }
+
+    /// Actions of the header's menu button, editing is left to the owner
+    fn channel_menu(&self) -> Element<'_, Message> {
+        if !self.menu_open {
+            return text("").into();
+        }
+        let mut actions = row![Space::with_width(Length::Fill)]
+            .spacing(5)
+            .align_items(alignment::Alignment::Center);
+        if self.is_owner() {
+            actions = actions
+                .push(
+                    button("Edit channel")
+                        .on_press(Message::EditChannelPressed)
+                        .style(style::Button::MenuBtn),
+                )
+                .push(
+                    button("Compose announcement")
+                        .on_press(Message::ComposeAnnouncementPressed)
+                        .style(style::Button::MenuBtn),
+                );
+        }
+        if self.is_subscribed {
+            actions = actions
+                .push(
+                    button("Archive")
+                        .on_press(Message::ArchivePressed)
+                        .style(style::Button::MenuBtn),
+                )
+                .push(
+                    button("Unsubscribe")
+                        .on_press(Message::UnsubscribePressed)
+                        .style(style::Button::MenuBtn),
+                );
+        }
+        container(actions)
+            .padding(5)
+            .style(style::Container::ContextMenu)
+            .into()
+    }
+
+    /// Confirmation, then the answer when no relay takes the deletion
+    fn deletion_bar(&self) -> Element<'_, Message> {
+        let (hint, buttons) = match &self.deletion {
+            None => return text("").into(),
+            Some(DeletionPrompt::Confirm(_)) => (
+                "Delete this message for everyone?",
+                row![
+                    button("Cancel")
+                        .on_press(Message::CloseDeletion)
+                        .style(style::Button::HighlightButton),
+                    button("Delete")
+                        .on_press(Message::ConfirmDeletion)
+                        .style(style::Button::HighlightButton),
+                ],
+            ),
+            Some(DeletionPrompt::Sent(_)) => ("Deleting…", row![]),
+            Some(DeletionPrompt::NotSupported) => (
+                "None of the write relays accept deletions, the message was kept",
+                row![button("Close")
+                    .on_press(Message::CloseDeletion)
+                    .style(style::Button::HighlightButton)],
+            ),
+        };
+        container(
+            row![
+                text(hint).style(style::Text::Color(Color::WHITE)),
+                Space::with_width(Length::Fill),
+                buttons.spacing(10),
+            ]
+            .spacing(10)
+            .align_items(alignment::Alignment::Center),
+        )
+        .style(style::Container::Highlight)
+        .padding(10)
+        .into()
+    }
+
+    /// The owner's composer while writing, the pinned banner otherwise
+    fn announcement_view<'a>(&'a self, cache: &'a ChannelCache) -> Element<'a, Message> {
+        if let Some(input) = &self.announcement_input {
+            let mut unpin_btn = button("Unpin").style(style::Button::HighlightButton);
+            if cache.metadata.announcement.is_some() {
+                unpin_btn = unpin_btn.on_press(Message::UnpinAnnouncement);
+            }
+            return container(
+                row![
+                    text_input("Announcement for every member", input)
+                        .on_input(Message::AnnouncementInputChange)
+                        .on_submit(Message::PinAnnouncement),
+                    button("Cancel")
+                        .on_press(Message::CloseAnnouncement)
+                        .style(style::Button::HighlightButton),
+                    unpin_btn,
+                    button("Pin")
+                        .on_press(Message::PinAnnouncement)
+                        .style(style::Button::HighlightButton),
+                ]
+                .spacing(10)
+                .align_items(alignment::Alignment::Center),
+            )
+            .style(style::Container::Highlight)
+            .padding(10)
+            .into();
+        }
+        match &cache.metadata.announcement {
+            Some(announcement) => container(
+                row![
+                    pin_icon().size(14).style(style::Text::Primary),
+                    text(announcement).size(16)
+                ]
+                .spacing(10)
+                .align_items(alignment::Alignment::Center),
+            )
+            .width(Length::Fill)
+            .padding(10)
+            .style(style::Container::ForegroundBordered)
+            .into(),
+            None => text("").into(),
+        }
+    }
+
+    /// Watched words, messages containing them show up in the inbox
+    fn keywords_view(&self) -> Element<'_, Message> {
+        let keywords = self
+            .keywords
+            .iter()
+            .fold(column![].spacing(2), |col, keyword| {
+                col.push(
+                    row![
+                        text(keyword).size(14),
+                        Space::with_width(Length::Fill),
+                        button(xmark_icon().size(12))
+                            .padding(2)
+                            .style(style::Button::Invisible)
+                            .on_press(Message::RemoveKeyword(keyword.to_owned()))
+                    ]
+                    .align_items(alignment::Alignment::Center),
+                )
+            });
+        let keyword_input = text_input("Add a word", &self.keyword_input)
+            .on_input(Message::KeywordInputChange)
+            .on_submit(Message::AddKeyword)
+            .size(14)
+            .style(style::TextInput::ChatSearch);
+        column![
+            text("Watched words").size(18),
+            text("Messages with them go to the inbox")
+                .size(12)
+                .style(style::Text::Placeholder),
+            keywords,
+            keyword_input
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+fn member_btn(
+    member: &Member,
+    is_contact: bool,
+    is_moderator: bool,
+    dim_images: bool,
+) -> Element<'_, Message> {
+    let mut content = row![
+        container(DimmedImage::new(member.image.to_owned(), dim_images))
+            .width(30)
+            .height(30),
         text(member.name()).size(15)
     ]
-    .spacing(5);
+    .spacing(5)
+    .align_items(alignment::Alignment::Center);
+    if is_contact || is_moderator {
+        content = content.push(Space::with_width(Length::Fill));
+    }
+    if is_moderator {
+        content = content.push(text("mod").size(12).style(style::Text::Placeholder));
+    }
+    if is_contact {
+        content = content.push(text("contact").size(12).style(style::Text::Primary));
+    }
 
     button(content)
         .on_press(Message::MemberPressed(member.pubkey.to_owned()))
@@ -388,3 +1663,6 @@ fn member_btn(member: &Member) -> Element<'_, Message> {
 }
 
 const MEMBERS_LIST_WIDTH: u16 = 200;
+const MEMBER_IMAGE_SIZE: ImageSize = ImageSize::Small;
+const POLLS_MAX_HEIGHT: u16 = 300;
+const HEADER_IMAGE_SIZE: ImageSize = ImageSize::Medium;
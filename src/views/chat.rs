@@ -1,20 +1,36 @@
+use chrono::Utc;
 use iced::clipboard;
 use iced::subscription::Subscription;
-use iced::widget::{button, column, container, row, scrollable, text, text_input, Space};
+use iced::widget::{button, column, container, row, scrollable, text, text_input, tooltip, Space};
 use iced::Size;
 use iced::{Alignment, Command, Length};
 use iced_native::widget::scrollable::RelativeOffset;
 use nostr::secp256k1::XOnlyPublicKey;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 use crate::components::chat_contact::{ChatContact, CARD_HEIGHT};
 use crate::components::floating_element::{Anchor, FloatingElement, Offset};
-use crate::components::{chat_contact, chat_view, contact_list};
-use crate::db::{DbContact, DbRelay, DbRelayResponse};
+use crate::components::hover_card::{self, HoverCards};
+use crate::components::live_card::live_card;
+use crate::components::quick_switcher::{SwitcherItem, SwitcherTarget};
+use crate::components::{chat_view, contact_list};
+use crate::config::Appearance;
+use crate::db::{
+    DbContact, DbLiveActivity, DbRelay, DbRelayResponse, DbTemplate, DbTranslation, DraftTarget,
+    Labels,
+};
 use crate::error::BackendClosed;
-use crate::icon::{copy_icon, reply_icon, satellite_icon};
-use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::icon::{copy_icon, delete_icon, folder_open_icon, reply_icon, satellite_icon};
+use crate::net::downloads::attachment_urls;
+use crate::net::{BackEndConnection, BackendEvent, PendingRequest, ToBackend};
 use crate::style;
-use crate::types::ChatMessage;
+use crate::types::chat_message::{reconcile_at, transcript};
+use crate::types::slash_command::{invite_message, parse_command};
+use crate::types::{
+    ChatMessage, CommandAction, InboxItem, LightningAddress, NostrLink, UserMessage,
+};
+use crate::utils::parse_event_link;
 use crate::widget::Element;
 use once_cell::sync::Lazy;
 
@@ -22,7 +38,8 @@ use self::chat_view::ChatView;
 use self::contact_list::ContactList;
 
 use super::modal::{
-    basic_contact, relays_confirmation, ContactDetails, ModalView, RelaysConfirmation,
+    basic_contact, forward_messages, relay_deletion, relays_confirmation, ContactDetails,
+    ForwardMessages, ModalView, RelayDeletionModal, RelaysConfirmation,
 };
 use super::route::Route;
 use super::{GoToView, RouterCommand};
@@ -38,6 +55,8 @@ pub enum ModalState {
     Off,
     BasicProfile(ContactDetails<Message>),
     RelaysConfirmation(RelaysConfirmation<Message>),
+    RelayDeletion(RelayDeletionModal<Message>),
+    ForwardMessages(ForwardMessages<Message>),
 }
 impl ModalState {
     pub fn basic_profile(
@@ -46,15 +65,25 @@ impl ModalState {
     ) -> Result<Self, BackendClosed> {
         Ok(Self::BasicProfile(ContactDetails::viewer(contact, conn)?))
     }
-    pub fn view<'a>(&'a self, underlay: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    pub fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Message>>,
+        appearance: Appearance,
+    ) -> Element<'a, Message> {
         match self {
             ModalState::Off => underlay.into(),
             ModalState::RelaysConfirmation(state) => state
-                .view(underlay)
+                .view(underlay, appearance)
                 .map(|m| Message::ModalRelaysConfirmation(Box::new(m))),
             ModalState::BasicProfile(state) => state
-                .view(underlay)
+                .view(underlay, appearance)
                 .map(|m| Message::ModalBasicContact(Box::new(m))),
+            ModalState::RelayDeletion(state) => state
+                .view(underlay, appearance)
+                .map(|m| Message::ModalRelayDeletion(Box::new(m))),
+            ModalState::ForwardMessages(state) => state
+                .view(underlay, appearance)
+                .map(|m| Message::ModalForwardMessages(Box::new(m))),
         }
     }
     fn backend_event(
@@ -62,8 +91,12 @@ impl ModalState {
         event: BackendEvent,
         conn: &mut BackEndConnection,
     ) -> Result<(), BackendClosed> {
-        if let ModalState::BasicProfile(state) = self {
-            state.backend_event(event, conn)?
+        match self {
+            ModalState::BasicProfile(state) => state.backend_event(event, conn)?,
+            ModalState::RelayDeletion(state) => state.backend_event(event, conn)?,
+            ModalState::Off
+            | ModalState::RelaysConfirmation(_)
+            | ModalState::ForwardMessages(_) => (),
         }
         Ok(())
     }
@@ -72,16 +105,24 @@ impl ModalState {
 #[derive(Debug, Clone)]
 pub enum Message {
     CopyPressed,
+    CopyLinkPressed,
+    TranslatePressed,
     ReplyPressed,
+    StarPressed,
+    ShowInFolderPressed,
     RelaysConfirmationPress,
+    DeleteFromRelaysPressed,
     ModalBasicContact(Box<basic_contact::CMessage<Message>>),
     ModalRelaysConfirmation(Box<relays_confirmation::CMessage<Message>>),
+    ModalRelayDeletion(Box<relay_deletion::CMessage<Message>>),
+    ModalForwardMessages(Box<forward_messages::CMessage<Message>>),
     OnVerResize(u16),
     CloseModal,
     CloseCtxMenu,
     DebugPressed,
     ContactList(contact_list::Message),
     ChatView(chat_view::Message),
+    JoinLivePressed(String),
 }
 
 pub struct State {
@@ -100,13 +141,46 @@ pub struct State {
     chat_total_size: Size,
     hide_context_menu: bool,
     chat_message_pressed: Option<ChatMessage>,
+    /// Event ids of the selected messages, `None` outside selection mode
+    selection: Option<HashSet<i64>>,
+    /// Message under the left button and when it went down, for long presses
+    left_pressed: Option<(i64, Instant)>,
     last_relays_response: Option<RelaysResponse>,
+    relays_request: Option<PendingRequest>,
     focus_pubkey: Option<XOnlyPublicKey>,
+    live_activities: Vec<DbLiveActivity>,
+    /// Message to scroll to once the conversation loads
+    scroll_to_event: Option<i64>,
+    /// Translations of the active conversation by event id
+    translations: HashMap<i64, DbTranslation>,
+    /// Starred messages of the active conversation by event id
+    starred: HashSet<i64>,
+    auto_translate: bool,
+    /// Received attachments of the active conversation are saved as they arrive
+    auto_save_attachments: bool,
+    templates: Vec<DbTemplate>,
+    labels: Labels,
+    /// Conversations with unsent text, newest first
+    drafts: Vec<SwitcherItem>,
+    /// Their conversations are hidden
+    blocked: HashSet<XOnlyPublicKey>,
+    /// Contacts that mentioned the user in a channel, not seen in the inbox yet
+    mention_authors: HashSet<XOnlyPublicKey>,
+    /// The user's mute list
+    muted_users: HashSet<XOnlyPublicKey>,
+    hover_cards: HoverCards,
 }
 
 impl State {
     pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchBlocked)?;
         conn.send(ToBackend::FetchContacts)?;
+        conn.send(ToBackend::FetchLiveActivities)?;
+        conn.send(ToBackend::FetchTemplates)?;
+        conn.send(ToBackend::FetchLabels)?;
+        conn.send(ToBackend::FetchDrafts)?;
+        conn.send(ToBackend::GetSendKey)?;
+        conn.send(ToBackend::FetchInbox)?;
         Ok(Self {
             contact_list: ContactList::new(),
             chat_view: ChatView::new(),
@@ -123,8 +197,24 @@ impl State {
             chat_total_size: Size::ZERO,
             hide_context_menu: true,
             chat_message_pressed: None,
+            selection: None,
+            left_pressed: None,
             last_relays_response: None,
+            relays_request: None,
             focus_pubkey: None,
+            live_activities: vec![],
+            scroll_to_event: None,
+            translations: HashMap::new(),
+            starred: HashSet::new(),
+            auto_translate: false,
+            auto_save_attachments: false,
+            templates: vec![],
+            labels: Labels::default(),
+            drafts: vec![],
+            blocked: HashSet::new(),
+            mention_authors: HashSet::new(),
+            muted_users: HashSet::new(),
+            hover_cards: HoverCards::new(conn)?,
         })
     }
     pub(crate) fn chat_to(
@@ -135,9 +225,22 @@ impl State {
         state.focus_pubkey = Some(db_contact.pubkey().to_owned());
         Ok(state)
     }
+    /// Conversation of a linked message, scrolled to it
+    pub(crate) fn chat_to_event(
+        db_contact: DbContact,
+        event_id: i64,
+        conn: &mut BackEndConnection,
+    ) -> Result<Self, BackendClosed> {
+        let mut state = Self::chat_to(db_contact, conn)?;
+        state.scroll_to_event = Some(event_id);
+        Ok(state)
+    }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        Subscription::none()
+        Subscription::batch(vec![
+            ChatView::subscription().map(Message::ChatView),
+            ContactList::subscription().map(Message::ContactList),
+        ])
     }
 
     fn active_chat(&self) -> Option<&ChatContact> {
@@ -155,8 +258,12 @@ impl State {
         }
     }
     fn active_matches(&self, db_contact: &DbContact) -> bool {
+        self.active_pubkey_matches(db_contact.pubkey())
+    }
+
+    fn active_pubkey_matches(&self, pubkey: &XOnlyPublicKey) -> bool {
         if let Some(active_chat) = self.active_chat() {
-            active_chat.contact.pubkey() == db_contact.pubkey()
+            active_chat.contact.pubkey() == pubkey
         } else {
             false
         }
@@ -184,13 +291,245 @@ impl State {
                     commands.push(scrollable::snap_to(CONTACTS_SCROLLABLE_ID.clone(), offset));
                     Ok(Some(commands))
                 }
-                _ => Ok(None),
+                _ => {
+                    // not a conversation yet, like an opened nostr: profile link
+                    self.modal_state = ModalState::basic_profile(&DbContact::new(&pubkey), conn)?;
+                    Ok(None)
+                }
             }
         } else {
             Ok(None)
         }
     }
 
+    /// `resend` skips the duplicate check, the user already confirmed it
+    fn send_dm(
+        &mut self,
+        dm_msg: String,
+        resend: bool,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if !resend && !self.chat_view.debounce_send(Instant::now()) {
+            return Ok(());
+        }
+        if let (Some(chat_contact), false) = (self.active_chat_mut(), dm_msg.trim().is_empty()) {
+            let contact = chat_contact.contact.to_owned();
+            let request = conn.request(if resend {
+                ToBackend::ResendDM(contact, dm_msg.to_owned())
+            } else {
+                ToBackend::SendDM(contact, dm_msg.to_owned())
+            })?;
+            // shown right away, replaced when the backend creates the event
+            let local_message = ChatMessage::local(request.id(), &dm_msg, Utc::now().naive_utc());
+            chat_contact.update_headers(local_message.clone());
+            self.messages.push(local_message);
+            self.sort_contacts_name_date();
+            self.msgs_scroll_offset = scrollable::RelativeOffset::END;
+            self.chat_view.update_dm_msg("".into());
+            self.update_local_draft(String::new());
+        }
+        Ok(())
+    }
+
+    /// Matches the events of a `SendDM` request with the local message shown when it was sent
+    fn reconcile_local_message(&mut self, event: &BackendEvent) {
+        match event {
+            BackendEvent::Response(id, inner) => {
+                let idx = self.messages.iter().position(|m| m.match_local_request(id));
+                match (inner.as_ref(), idx) {
+                    (BackendEvent::PendingDM(_, chat_message), Some(idx)) => {
+                        reconcile_at(&mut self.messages, idx, chat_message.to_owned());
+                    }
+                    (BackendEvent::DuplicateDM(_, content), Some(idx)) => {
+                        self.messages.remove(idx);
+                        self.chat_view.confirm_duplicate(content.to_owned());
+                    }
+                    _ => (),
+                }
+            }
+            BackendEvent::RequestFailed(id, e) => {
+                if let Some(message) = self.messages.iter_mut().find(|m| m.match_local_request(id))
+                {
+                    tracing::error!("Failed to send message: {}", e);
+                    message.set_local_failed();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn run_command(
+        &mut self,
+        action: CommandAction,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match action {
+            CommandAction::Send(text) => self.send_dm(text, false, conn)?,
+            CommandAction::Invite(channel_id) => {
+                self.send_dm(invite_message(&channel_id), false, conn)?;
+            }
+            CommandAction::Zap(sats) => match self.active_lightning_address() {
+                // the wallet opens once the address answers with an invoice
+                Some(address) => {
+                    conn.send(ToBackend::RequestZapInvoice(address, sats))?;
+                    self.update_composer("".into(), conn)?;
+                }
+                None => self
+                    .chat_view
+                    .set_command_error("Contact has no lightning address".into()),
+            },
+            CommandAction::Mute => {
+                if let Some(pubkey) = self.active_chat().map(|c| c.contact.pubkey().to_owned()) {
+                    let mute = !self.muted_users.contains(&pubkey);
+                    conn.send(ToBackend::MuteUser(pubkey, mute))?;
+                }
+                self.update_composer("".into(), conn)?;
+            }
+            CommandAction::Clear => {
+                self.messages.clear();
+                self.translations.clear();
+                self.update_composer("".into(), conn)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Conversations already in the list open in place, the profile
+    /// of a contact shows its petname and notes
+    fn hover_card_update(
+        &mut self,
+        message: hover_card::Message,
+        commands: &mut RouterCommand<Message>,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match self.hover_cards.update(message, conn)? {
+            Some(hover_card::Action::OpenChat(pubkey)) => {
+                let idx = self
+                    .chats
+                    .iter()
+                    .find(|c| c.contact.pubkey() == &pubkey)
+                    .map(|c| c.id);
+                match idx {
+                    Some(idx) => commands.push(self.set_active_contact(idx, conn)?),
+                    None => {
+                        commands.change_route(GoToView::Link(NostrLink::Profile(pubkey, vec![])))
+                    }
+                }
+            }
+            Some(hover_card::Action::ShowProfile(contact)) => {
+                let contact = self
+                    .chats
+                    .iter()
+                    .find(|c| c.contact.pubkey() == contact.pubkey())
+                    .map_or(contact, |c| c.contact.to_owned());
+                self.modal_state = ModalState::basic_profile(&contact, conn)?;
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    /// Composer text is kept as the active conversation's draft
+    fn update_composer(
+        &mut self,
+        text: String,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if let Some(chat_contact) = self.active_chat() {
+            let target = DraftTarget::Contact(chat_contact.contact.pubkey().to_owned());
+            conn.send(ToBackend::SaveDraft(target, text.clone()))?;
+            self.update_local_draft(text.clone());
+        }
+        self.chat_view.update_dm_msg(text);
+        Ok(())
+    }
+
+    /// Keeps the drafts section in sync without asking the backend
+    fn update_local_draft(&mut self, text: String) {
+        let Some(contact) = self.active_chat().map(|c| c.contact.to_owned()) else {
+            return;
+        };
+        let position = self.drafts.iter().position(|item| {
+            matches!(&item.target, SwitcherTarget::Contact(c) if c.pubkey() == contact.pubkey())
+        });
+        if let Some(position) = position {
+            self.drafts.remove(position);
+        }
+        if !text.trim().is_empty() {
+            self.drafts.insert(
+                0,
+                SwitcherItem {
+                    name: contact.select_name(),
+                    score: 0.0,
+                    draft: Some(text),
+                    target: SwitcherTarget::Contact(contact),
+                },
+            );
+        }
+    }
+
+    fn draft_of(&self, contact: &DbContact) -> String {
+        self.drafts
+            .iter()
+            .find_map(|item| match &item.target {
+                SwitcherTarget::Contact(c) if c.pubkey() == contact.pubkey() => {
+                    item.draft.to_owned()
+                }
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    fn new_chat(
+        &self,
+        id: i32,
+        db_contact: &DbContact,
+        conn: &mut BackEndConnection,
+    ) -> Result<ChatContact, BackendClosed> {
+        let mut chat = ChatContact::new(id, db_contact, conn)?;
+        chat.update_labels(&self.labels);
+        chat.set_mentioned(self.mention_authors.contains(db_contact.pubkey()));
+        chat.set_muted(self.muted_users.contains(db_contact.pubkey()));
+        Ok(chat)
+    }
+
+    fn insert_template(&mut self, template: &DbTemplate) -> Command<Message> {
+        let contact_name = self
+            .active_chat()
+            .map(|c| c.contact.select_name())
+            .unwrap_or_default();
+        let cursor = self.chat_view.insert_template(template, &contact_name);
+        Command::batch(vec![
+            text_input::focus(CHAT_INPUT_ID.clone()),
+            text_input::move_cursor_to(CHAT_INPUT_ID.clone(), cursor),
+        ])
+    }
+
+    fn translate_contact_messages(
+        &self,
+        messages: &[ChatMessage],
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        for msg in messages {
+            let is_contacts = matches!(msg, ChatMessage::ContactMessage { .. });
+            let translated = msg
+                .event_id()
+                .map_or(false, |id| self.translations.contains_key(&id));
+            if is_contacts && !translated {
+                conn.send(ToBackend::TranslateMessage(msg.to_owned()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Live activity hosted by the active contact
+    fn active_live_activity(&self) -> Option<&DbLiveActivity> {
+        let active_chat = self.active_chat()?;
+        self.live_activities
+            .iter()
+            .find(|a| &a.host == active_chat.contact.pubkey() && a.is_live())
+    }
+
     fn sort_contacts_name_date(&mut self) {
         self.chats
             .sort_by(|a, b| b.contact.select_name().cmp(&a.contact.select_name()));
@@ -198,11 +537,48 @@ impl State {
             .sort_by_key(|b| std::cmp::Reverse(b.last_message_date()));
     }
 
+    /// Asks the relays to delete the message and shows their answers
+    fn request_deletion(
+        &mut self,
+        chat_msg: ChatMessage,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if let Some(event_id) = chat_msg.event_id() {
+            conn.send(ToBackend::RequestRelayDeletion(chat_msg))?;
+            self.modal_state = ModalState::RelayDeletion(RelayDeletionModal::new(event_id));
+        }
+        Ok(())
+    }
+
+    /// Selected messages in the order they are shown
+    fn selected_messages(&self) -> Vec<&ChatMessage> {
+        let Some(selection) = &self.selection else {
+            return vec![];
+        };
+        self.messages
+            .iter()
+            .filter(|m| m.event_id().map_or(false, |id| selection.contains(&id)))
+            .collect()
+    }
+
+    fn toggle_selected(&mut self, event_id: i64) {
+        let selection = self.selection.get_or_insert_with(HashSet::new);
+        if !selection.remove(&event_id) {
+            selection.insert(event_id);
+        }
+    }
+
     fn close_modal(&mut self) -> Command<Message> {
         self.modal_state = ModalState::Off;
         scrollable::snap_to(CHAT_SCROLLABLE_ID.clone(), self.msgs_scroll_offset)
     }
 
+    fn active_lightning_address(&self) -> Option<LightningAddress> {
+        self.active_chat()
+            .and_then(|c| c.contact.get_profile_cache())
+            .and_then(|cache| LightningAddress::from_metadata(&cache.metadata))
+    }
+
     /// If it finds a contact, focus the text input chat.
     fn set_active_contact(
         &mut self,
@@ -211,15 +587,39 @@ impl State {
     ) -> Result<Command<Message>, BackendClosed> {
         if let Some(chat) = self.chats.iter().find(|c| c.id == idx) {
             conn.send(ToBackend::FetchMessages(chat.contact.to_owned()))?;
+            conn.send(ToBackend::FetchAutoTranslate(chat.contact.to_owned()))?;
+            conn.send(ToBackend::FetchAutoSaveAttachments(chat.contact.to_owned()))?;
+            conn.send(ToBackend::FetchStarred(chat.contact.to_owned()))?;
+            let draft = self.draft_of(&chat.contact);
             self.messages = vec![];
-            self.chat_view.update_dm_msg("".into());
+            self.selection = None;
+            self.chat_view.reset_history();
+            let lightning = chat
+                .contact
+                .get_profile_cache()
+                .and_then(|cache| LightningAddress::from_metadata(&cache.metadata));
+            if let Some(address) = &lightning {
+                conn.send(ToBackend::AutoCheckLightningAddress(address.to_owned()))?;
+            }
+            self.chat_view.set_lightning(lightning);
+            self.translations.clear();
+            self.starred.clear();
+            self.auto_translate = false;
+            self.auto_save_attachments = false;
+            self.chat_view.update_dm_msg(draft);
             self.active_idx = Some(idx);
             return Ok(text_input::focus(CHAT_INPUT_ID.clone()));
         }
         Ok(Command::none())
     }
 
-    fn calculate_ctx_menu_pos(&mut self, point: iced_native::Point) {
+    fn calculate_ctx_menu_pos(
+        &mut self,
+        point: iced_native::Point,
+        deletable: bool,
+        starrable: bool,
+        has_attachment: bool,
+    ) {
         let total_h = self.chat_total_size.height;
         let window_h = self.chat_window_size.height;
         let offset_h = self.msgs_scroll_offset.y;
@@ -239,8 +639,9 @@ impl State {
         }
 
         // check height for collision
-        if window_h - (self.context_menu_position.y + ctx_menu_height()) < 0.0 {
-            self.context_menu_position.y -= ctx_menu_height();
+        let menu_height = ctx_menu_height(deletable, starrable, has_attachment);
+        if window_h - (self.context_menu_position.y + menu_height) < 0.0 {
+            self.context_menu_position.y -= menu_height;
         }
 
         if let Some(div_pos) = self.ver_divider_position {
@@ -262,8 +663,23 @@ impl State {
     ) -> Result<Command<Message>, BackendClosed> {
         let active_chatting = self.active_matches(&db_contact);
 
-        // push into chat messages
-        self.messages.push(chat_message.clone());
+        if active_chatting && self.auto_translate {
+            if let ChatMessage::ContactMessage { .. } = &chat_message {
+                conn.send(ToBackend::TranslateMessage(chat_message.clone()))?;
+            }
+        }
+
+        // push into chat messages, unless it's the pending event of a local message
+        let already_shown = match &chat_message {
+            ChatMessage::UserMessage(UserMessage::Pending { event_hash, .. }) => self
+                .messages
+                .iter()
+                .any(|m| m.match_pending_hash(event_hash)),
+            _ => false,
+        };
+        if !already_shown {
+            self.messages.push(chat_message.clone());
+        }
 
         // update chat card headers
         if let Some(contact_card) = self
@@ -281,7 +697,7 @@ impl State {
                 "New message for a contact not in the list?? {:?}",
                 &db_contact
             );
-            let new_chat = ChatContact::new(self.chats.len() as i32, &db_contact, conn)?;
+            let new_chat = self.new_chat(self.chats.len() as i32, &db_contact, conn)?;
             self.chats.push(new_chat);
         }
 
@@ -298,7 +714,11 @@ impl State {
 
 impl Route for State {
     type Message = Message;
-    fn view(&self, _selected_theme: Option<style::Theme>) -> Element<Self::Message> {
+    fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Self::Message> {
         // --- FIRST SPLIT ---
         let first_split = self
             .contact_list
@@ -307,6 +727,10 @@ impl Route for State {
                 &self.chats,
                 self.show_only_profile,
                 self.active_idx,
+                &self.labels,
+                &self.drafts,
+                &self.hover_cards,
+                appearance,
             )
             .map(Message::ContactList);
 
@@ -319,8 +743,30 @@ impl Route for State {
                 &CHAT_INPUT_ID,
                 &self.messages,
                 self.active_chat(),
+                &self.translations,
+                &self.starred,
+                self.auto_translate,
+                self.auto_save_attachments,
+                &self.templates,
+                &self.labels,
+                self.selection.as_ref(),
+                &self.hover_cards,
+                appearance,
             )
             .map(Message::ChatView);
+        let second_split: Element<_> = match (self.active_live_activity(), self.active_chat()) {
+            (Some(activity), Some(chat)) => column![
+                container(live_card(
+                    activity,
+                    Some(&chat.contact),
+                    Message::JoinLivePressed
+                ))
+                .padding(5),
+                second_split
+            ]
+            .into(),
+            _ => second_split,
+        };
 
         let main_content = iced_aw::split::Split::new(
             first_split,
@@ -333,7 +779,25 @@ impl Route for State {
         .min_size_second(300);
 
         let float = FloatingElement::new(main_content, || {
-            make_context_menu(&self.last_relays_response)
+            let deletable = self
+                .chat_message_pressed
+                .as_ref()
+                .map_or(false, is_deletable);
+            let starred = self
+                .chat_message_pressed
+                .as_ref()
+                .and_then(ChatMessage::event_id)
+                .map(|id| self.starred.contains(&id));
+            let has_attachment = self
+                .chat_message_pressed
+                .as_ref()
+                .map_or(false, has_attachment);
+            make_context_menu(
+                &self.last_relays_response,
+                deletable,
+                starred,
+                has_attachment,
+            )
         })
         .on_esc(Message::CloseCtxMenu)
         .backdrop(Message::CloseCtxMenu)
@@ -341,7 +805,7 @@ impl Route for State {
         .offset(self.context_menu_position)
         .hide(self.hide_context_menu);
 
-        self.modal_state.view(float)
+        self.modal_state.view(float, appearance)
     }
 
     fn backend_event(
@@ -352,8 +816,211 @@ impl Route for State {
         let mut commands = RouterCommand::new();
 
         self.modal_state.backend_event(event.clone(), conn)?;
+        self.hover_cards.backend_event(&event, conn)?;
+
+        // instead of fetching relay responses
+        // each message already got the responses?
+        if let Some(request) = &self.relays_request {
+            match request.response(&event) {
+                Some(Ok(BackendEvent::GotRelayResponses {
+                    chat_message,
+                    responses,
+                    all_relays,
+                })) => {
+                    self.last_relays_response = Some(RelaysResponse::new(
+                        chat_message.to_owned(),
+                        responses.to_owned(),
+                        all_relays.to_owned(),
+                    ));
+                    self.relays_request = None;
+                }
+                Some(Err(e)) => {
+                    tracing::error!("Failed to fetch relay responses: {}", e);
+                    self.relays_request = None;
+                }
+                _ => (),
+            }
+        }
+        self.reconcile_local_message(&event);
 
         match event {
+            BackendEvent::GotMessageLink(link) => {
+                commands.push(clipboard::write(link));
+            }
+            BackendEvent::EventLocated(db_contact, event_id) => {
+                let idx = self
+                    .chats
+                    .iter()
+                    .find(|c| c.contact.pubkey() == db_contact.pubkey())
+                    .map(|c| c.id);
+                if let Some(idx) = idx {
+                    self.scroll_to_event = Some(event_id);
+                    commands.push(self.set_active_contact(idx, conn)?);
+                }
+            }
+            BackendEvent::GotTemplates(templates) => {
+                self.templates = templates;
+            }
+            BackendEvent::GotSendKey(send_key) => self.chat_view.set_send_key(send_key),
+            BackendEvent::PdfExport(status) => self.chat_view.pdf_export_status(status),
+            BackendEvent::UploadProgress(status) => {
+                let is_active = matches!(
+                    status.target,
+                    DraftTarget::Contact(pubkey) if self.active_pubkey_matches(&pubkey)
+                );
+                if is_active && self.chat_view.upload_status(status) {
+                    self.update_composer(self.chat_view.dm_msg_input().to_owned(), conn)?;
+                }
+            }
+            BackendEvent::RFDCancelPick => self.chat_view.pdf_export_cancelled(),
+            BackendEvent::GotDrafts(drafts) => {
+                self.drafts = drafts;
+            }
+            BackendEvent::GotBlocked(blocked) => {
+                let blocked: HashSet<_> = blocked.into_iter().map(|(b, _)| b.pubkey).collect();
+                if blocked != self.blocked {
+                    // ids are positions, the list is rebuilt and the active chat focused again
+                    if let Some(pubkey) = self.active_chat().map(|c| c.contact.pubkey().to_owned())
+                    {
+                        if blocked.contains(&pubkey) {
+                            self.messages.clear();
+                            self.chat_view.update_dm_msg("".into());
+                        } else {
+                            self.focus_pubkey = Some(pubkey);
+                        }
+                    }
+                    self.active_idx = None;
+                    self.blocked = blocked;
+                    conn.send(ToBackend::FetchContacts)?;
+                }
+            }
+            BackendEvent::GotLabels(labels) => {
+                for chat in &mut self.chats {
+                    chat.update_labels(&labels);
+                }
+                self.contact_list.update_labels(&labels);
+                self.labels = labels;
+            }
+            BackendEvent::MessageTranslated(event_id, translation) => {
+                if self.messages.iter().any(|m| m.event_id() == Some(event_id)) {
+                    self.translations.insert(event_id, translation);
+                }
+            }
+            BackendEvent::GotStarred(pubkey, event_ids) => {
+                let is_active = self
+                    .active_chat()
+                    .map_or(false, |c| c.contact.pubkey() == &pubkey);
+                if is_active {
+                    self.starred = event_ids.into_iter().collect();
+                }
+            }
+            BackendEvent::MessageStarred(event_id, starred) => {
+                if starred {
+                    if self.messages.iter().any(|m| m.event_id() == Some(event_id)) {
+                        self.starred.insert(event_id);
+                    }
+                } else {
+                    self.starred.remove(&event_id);
+                }
+            }
+            BackendEvent::ReactionsUpdated(event_id, reactions) => {
+                if let Some(msg) = self
+                    .messages
+                    .iter_mut()
+                    .find(|m| m.event_id() == Some(event_id))
+                {
+                    msg.set_reactions(reactions);
+                }
+            }
+            BackendEvent::AllChatsRead => {
+                for contact_card in &mut self.chats {
+                    contact_card.reset_unseen();
+                }
+            }
+            BackendEvent::ChatHistoryCleared(db_contact, chat_info) => {
+                if let Some(contact_card) = self
+                    .chats
+                    .iter_mut()
+                    .find(|c| c.contact.pubkey() == db_contact.pubkey())
+                {
+                    contact_card.set_chat_info(chat_info.unwrap_or_default());
+                }
+                self.sort_contacts_name_date();
+                if self.active_matches(&db_contact) {
+                    self.messages.clear();
+                    self.translations.clear();
+                    self.starred.clear();
+                    conn.send(ToBackend::FetchMessages(db_contact.to_owned()))?;
+                    conn.send(ToBackend::FetchStarred(db_contact))?;
+                }
+            }
+            BackendEvent::MessagesDeleted(db_contact, event_ids, chat_info) => {
+                if let Some(contact_card) = self
+                    .chats
+                    .iter_mut()
+                    .find(|c| c.contact.pubkey() == db_contact.pubkey())
+                {
+                    contact_card.set_chat_info(chat_info.unwrap_or_default());
+                }
+                self.sort_contacts_name_date();
+                if self.active_matches(&db_contact) {
+                    let deleted: HashSet<_> = event_ids.into_iter().collect();
+                    self.messages
+                        .retain(|m| m.event_id().map_or(true, |id| !deleted.contains(&id)));
+                    self.translations.retain(|id, _| !deleted.contains(id));
+                    self.starred.retain(|id| !deleted.contains(id));
+                }
+            }
+            BackendEvent::DmRetracted(db_contact, event_id, chat_info) => {
+                if let Some(contact_card) = self
+                    .chats
+                    .iter_mut()
+                    .find(|c| c.contact.pubkey() == db_contact.pubkey())
+                {
+                    contact_card.set_chat_info(chat_info.unwrap_or_default());
+                }
+                if self.active_matches(&db_contact) {
+                    if let Some(msg) = self
+                        .messages
+                        .iter_mut()
+                        .find(|m| m.event_id() == Some(event_id))
+                    {
+                        msg.retract();
+                    }
+                    self.translations.remove(&event_id);
+                }
+            }
+            BackendEvent::GotAutoSaveAttachments(pubkey, enabled) => {
+                let is_active = self
+                    .active_chat()
+                    .map_or(false, |c| c.contact.pubkey() == &pubkey);
+                if is_active {
+                    self.auto_save_attachments = enabled;
+                }
+            }
+            BackendEvent::GotAutoTranslate(pubkey, enabled) => {
+                let is_active = self
+                    .active_chat()
+                    .map_or(false, |c| c.contact.pubkey() == &pubkey);
+                if is_active {
+                    self.auto_translate = enabled;
+                    if enabled {
+                        self.translate_contact_messages(&self.messages, conn)?;
+                    }
+                }
+            }
+            BackendEvent::FetchingEvent(event_hash) => {
+                tracing::info!("Linked message not stored, fetching: {}", event_hash);
+            }
+            BackendEvent::GotLiveActivities(activities) => {
+                self.live_activities = activities.into_iter().map(|(a, _)| a).collect();
+            }
+            BackendEvent::LiveActivityUpdated(activity, _) => {
+                self.live_activities.retain(|a| {
+                    !(a.author == activity.author && a.identifier == activity.identifier)
+                });
+                self.live_activities.push(activity);
+            }
             BackendEvent::ImageDownloaded(image) => {
                 if let Some(chat) = self
                     .chats
@@ -364,8 +1031,11 @@ impl Route for State {
                 }
             }
             BackendEvent::ContactCreated(db_contact) => {
+                if self.blocked.contains(db_contact.pubkey()) {
+                    return Ok(commands);
+                }
                 let id = self.chats.len() as i32;
-                let new_chat = chat_contact::ChatContact::new(id, &db_contact, conn)?;
+                let new_chat = self.new_chat(id, &db_contact, conn)?;
                 self.chats.push(new_chat);
                 conn.send(ToBackend::FetchContactWithMetadata(
                     db_contact.pubkey().to_owned(),
@@ -378,8 +1048,8 @@ impl Route for State {
                     .find(|c| c.contact.pubkey() == db_contact.pubkey())
                 {
                     contact_card.update_contact(db_contact, conn)?;
-                } else {
-                    let new_chat = ChatContact::new(self.chats.len() as i32, &db_contact, conn)?;
+                } else if !self.blocked.contains(db_contact.pubkey()) {
+                    let new_chat = self.new_chat(self.chats.len() as i32, &db_contact, conn)?;
                     self.chats.push(new_chat);
                 }
             }
@@ -413,34 +1083,49 @@ impl Route for State {
             }
             BackendEvent::GotContacts(db_contacts) => {
                 self.chats = vec![];
-                for (idx, c) in db_contacts.iter().enumerate() {
-                    self.chats
-                        .push(chat_contact::ChatContact::new(idx as i32, c, conn)?);
+                for c in db_contacts
+                    .iter()
+                    .filter(|c| !self.blocked.contains(c.pubkey()))
+                {
+                    let new_chat = self.new_chat(self.chats.len() as i32, c, conn)?;
+                    self.chats.push(new_chat);
                 }
 
                 if let Some(cmds) = self.handle_focus_contact(conn)? {
                     cmds.into_iter().for_each(|c| commands.push(c));
                 }
             }
-            // instead of fetching relay responses
-            // each message already got the responses?
-            BackendEvent::GotRelayResponses {
-                chat_message,
-                responses,
-                all_relays,
-            } => {
-                self.last_relays_response =
-                    Some(RelaysResponse::new(chat_message, responses, all_relays));
+            BackendEvent::DmHistoryFetching(pubkey, since) => {
+                if self.active_pubkey_matches(&pubkey) {
+                    self.chat_view.history_fetching(since);
+                }
+            }
+            BackendEvent::LightningChecked(address, valid) => {
+                self.chat_view.lightning_checked(&address, valid);
+            }
+            BackendEvent::DmHistoryComplete(pubkey) => {
+                if self.active_pubkey_matches(&pubkey) {
+                    self.chat_view.history_complete();
+                }
+            }
+            BackendEvent::DmHistoryFetched(pubkey) => {
+                if self.active_pubkey_matches(&pubkey) {
+                    self.chat_view.history_fetched();
+                    // older messages came in at the end, reload them in order
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::FetchMessages(chat_contact.contact.to_owned()))?;
+                        self.messages = vec![];
+                    }
+                }
             }
             BackendEvent::GotChatMessages(db_contact, chat_msgs) => {
                 if self.active_matches(&db_contact) {
+                    if self.auto_translate {
+                        self.translate_contact_messages(&chat_msgs, conn)?;
+                    }
                     if self.messages.is_empty() {
                         self.messages = chat_msgs;
                         self.msgs_scroll_offset = scrollable::RelativeOffset::END;
-                        commands.push(scrollable::snap_to(
-                            CHAT_SCROLLABLE_ID.clone(),
-                            self.msgs_scroll_offset,
-                        ));
                     } else {
                         // TODO: scrollable doesnt stay still when new messages are added at the top
                         self.messages.extend(chat_msgs);
@@ -448,6 +1133,24 @@ impl Route for State {
 
                     self.messages
                         .sort_by(|a, b| a.display_time().cmp(&b.display_time()));
+
+                    if let Some(event_id) = self.scroll_to_event.take() {
+                        if let Some(position) = self
+                            .messages
+                            .iter()
+                            .position(|m| m.event_id() == Some(event_id))
+                        {
+                            let last = (self.messages.len() - 1).max(1) as f32;
+                            self.msgs_scroll_offset = RelativeOffset {
+                                x: 0.0,
+                                y: position as f32 / last,
+                            };
+                        }
+                    }
+                    commands.push(scrollable::snap_to(
+                        CHAT_SCROLLABLE_ID.clone(),
+                        self.msgs_scroll_offset,
+                    ));
                     if let Some(c) = self.active_chat_mut() {
                         c.reset_unseen()
                     }
@@ -460,14 +1163,23 @@ impl Route for State {
                 }
             }
             BackendEvent::ConfirmedDM(event_hash, db_message, content) => {
+                if let Some(idx) = self
+                    .messages
+                    .iter()
+                    .position(|message| message.match_pending_hash(&event_hash))
+                {
+                    let confirmed = ChatMessage::confirmed_users(&db_message, &content);
+                    reconcile_at(&mut self.messages, idx, confirmed);
+                    // conn.send(ToBackend::MessageSeen(message.msg_id))?;
+                }
+            }
+            BackendEvent::PendingRejected(event_hash, _) => {
                 if let Some(message) = self
                     .messages
                     .iter_mut()
-                    .filter(|m| m.is_pending())
-                    .find(|message| message.match_pending_hash(&event_hash))
+                    .find(|m| m.match_pending_hash(&event_hash))
                 {
-                    *message = ChatMessage::confirmed_users(&db_message, &content);
-                    // conn.send(ToBackend::MessageSeen(message.msg_id))?;
+                    message.set_pending_rejected();
                 }
             }
             BackendEvent::PendingDM(db_contact, chat_message)
@@ -526,6 +1238,40 @@ impl Route for State {
                     );
                 }
             }
+            BackendEvent::GotInbox(items) => {
+                self.mention_authors = items
+                    .iter()
+                    .filter_map(|item| match item {
+                        InboxItem::Mention {
+                            message,
+                            keyword: None,
+                            ..
+                        } => message.author().copied(),
+                        _ => None,
+                    })
+                    .collect();
+                for chat in &mut self.chats {
+                    chat.set_mentioned(self.mention_authors.contains(chat.contact.pubkey()));
+                }
+            }
+            BackendEvent::ReceivedChannelMessage(..) => {
+                conn.send(ToBackend::FetchInbox)?;
+            }
+            BackendEvent::GotMutedUsers(muted) => {
+                for chat in &mut self.chats {
+                    chat.set_muted(muted.contains(chat.contact.pubkey()));
+                }
+                self.muted_users = muted;
+            }
+            BackendEvent::ZapInvoice(sats, result) => match result {
+                Ok(invoice) => {
+                    tracing::info!("Zapping {} sats", sats);
+                    open_wallet(&format!("lightning:{}", invoice));
+                }
+                Err(e) => self
+                    .chat_view
+                    .set_command_error(format!("Zap of {} sats failed: {}", sats, e)),
+            },
 
             _ => (),
         };
@@ -541,12 +1287,29 @@ impl Route for State {
         let mut commands = RouterCommand::new();
 
         match message {
+            Message::JoinLivePressed(url) => {
+                if let Err(e) = webbrowser::open(&url) {
+                    tracing::error!("Failed to open stream: {}", e);
+                }
+            }
             Message::CopyPressed => {
                 if let Some(chat_msg) = &self.chat_message_pressed {
                     commands.push(clipboard::write(chat_msg.content().to_owned()));
                 }
                 self.hide_context_menu = true;
             }
+            Message::TranslatePressed => {
+                if let Some(chat_msg) = &self.chat_message_pressed {
+                    conn.send(ToBackend::TranslateMessage(chat_msg.to_owned()))?;
+                }
+                self.hide_context_menu = true;
+            }
+            Message::CopyLinkPressed => {
+                if let Some(chat_msg) = &self.chat_message_pressed {
+                    conn.send(ToBackend::FetchMessageLink(chat_msg.to_owned()))?;
+                }
+                self.hide_context_menu = true;
+            }
             Message::DebugPressed => {
                 if let Some(chat_msg) = &self.chat_message_pressed {
                     tracing::info!("{:?}", chat_msg);
@@ -557,6 +1320,28 @@ impl Route for State {
                 tracing::info!("Reply Pressed");
                 self.hide_context_menu = true;
             }
+            Message::StarPressed => {
+                if let Some(event_id) = self
+                    .chat_message_pressed
+                    .as_ref()
+                    .and_then(ChatMessage::event_id)
+                {
+                    let starred = !self.starred.contains(&event_id);
+                    conn.send(ToBackend::StarMessage(event_id, starred))?;
+                }
+                self.hide_context_menu = true;
+            }
+            Message::ShowInFolderPressed => {
+                if let (Some(chat_msg), Some(chat_contact)) =
+                    (&self.chat_message_pressed, self.active_chat())
+                {
+                    conn.send(ToBackend::ShowInFolder(
+                        chat_msg.to_owned(),
+                        chat_contact.contact.to_owned(),
+                    ))?;
+                }
+                self.hide_context_menu = true;
+            }
             Message::CloseCtxMenu => {
                 self.hide_context_menu = true;
             }
@@ -564,15 +1349,63 @@ impl Route for State {
                 // already have the relays responses
                 self.hide_context_menu = true;
                 if let Some(resp) = &self.last_relays_response {
-                    self.modal_state = ModalState::RelaysConfirmation(RelaysConfirmation::new(
-                        &resp.confirmed_relays,
-                        &resp.all_relays,
-                    ));
+                    self.modal_state = ModalState::RelaysConfirmation(
+                        RelaysConfirmation::new(&resp.confirmed_relays, &resp.all_relays)
+                            .created_at(resp.chat_message.display_time().copied()),
+                    );
+                }
+            }
+            Message::DeleteFromRelaysPressed => {
+                self.hide_context_menu = true;
+                if let Some(chat_msg) = self.chat_message_pressed.to_owned() {
+                    self.request_deletion(chat_msg, conn)?;
                 }
             }
             Message::CloseModal => {
                 commands.push(self.close_modal());
             }
+            Message::ModalRelayDeletion(modal_msg) => {
+                if let ModalState::RelayDeletion(state) = &mut self.modal_state {
+                    match *modal_msg {
+                        relay_deletion::CMessage::UnderlayMessage(message) => {
+                            return self.update(message, conn);
+                        }
+                        other => {
+                            let (cmd, close_modal) = state.update(other, conn)?;
+                            if close_modal {
+                                commands.push(self.close_modal())
+                            }
+                            commands.push(cmd.map(|m| Message::ModalRelayDeletion(Box::new(m))));
+                        }
+                    }
+                }
+            }
+            Message::ModalForwardMessages(modal_msg) => {
+                if let ModalState::ForwardMessages(state) = &mut self.modal_state {
+                    match *modal_msg {
+                        forward_messages::CMessage::UnderlayMessage(message) => {
+                            return self.update(message, conn);
+                        }
+                        forward_messages::CMessage::ForwardTo(db_contact) => {
+                            let contents = self
+                                .selected_messages()
+                                .iter()
+                                .map(|m| m.content().to_owned())
+                                .collect();
+                            conn.send(ToBackend::ForwardMessages(db_contact, contents))?;
+                            self.selection = None;
+                            commands.push(self.close_modal());
+                        }
+                        other => {
+                            let (cmd, close_modal) = state.update(other, conn)?;
+                            if close_modal {
+                                commands.push(self.close_modal())
+                            }
+                            commands.push(cmd.map(|m| Message::ModalForwardMessages(Box::new(m))));
+                        }
+                    }
+                }
+            }
             Message::ModalRelaysConfirmation(modal_msg) => {
                 if let ModalState::RelaysConfirmation(state) = &mut self.modal_state {
                     match *modal_msg {
@@ -596,6 +1429,10 @@ impl Route for State {
                         basic_contact::CMessage::UnderlayMessage(message) => {
                             return self.update(message, conn);
                         }
+                        basic_contact::CMessage::OpenChannel(channel_id) => {
+                            commands.push(self.close_modal());
+                            commands.change_route(GoToView::Channel(channel_id));
+                        }
                         other => {
                             let (cmd, close_modal) = state.update(other, conn)?;
                             if close_modal {
@@ -626,14 +1463,24 @@ impl Route for State {
             }
 
             Message::ChatView(chat_msg) => match chat_msg {
-                chat_view::Message::DMSentPress(dm_msg) => {
-                    if let (Some(chat_contact), false) = (self.active_chat(), dm_msg.is_empty()) {
-                        conn.send(ToBackend::SendDM(chat_contact.contact.to_owned(), dm_msg))?;
-                        self.chat_view.update_dm_msg("".into());
+                chat_view::Message::DMSentPress(dm_msg) => match parse_command(&dm_msg) {
+                    Some(Ok(action)) => self.run_command(action, conn)?,
+                    Some(Err(e)) => self.chat_view.set_command_error(e.to_string()),
+                    None => {
+                        if let Some(template) = self.chat_view.typed_template(&self.templates) {
+                            let template = template.to_owned();
+                            commands.push(self.insert_template(&template));
+                        } else {
+                            self.send_dm(dm_msg, false, conn)?;
+                            commands.push(scrollable::snap_to(
+                                CHAT_SCROLLABLE_ID.clone(),
+                                self.msgs_scroll_offset,
+                            ));
+                        }
                     }
-                }
+                },
                 chat_view::Message::DMNMessageChange(text) => {
-                    self.chat_view.update_dm_msg(text);
+                    self.update_composer(text, conn)?;
                 }
                 chat_view::Message::GotChatSize(size, child_size) => {
                     self.chat_window_size = size;
@@ -664,15 +1511,201 @@ impl Route for State {
                     }
                 }
                 chat_view::Message::ChatRightClick(msg, point) => {
-                    conn.send(ToBackend::FetchRelayResponsesChatMsg(msg.clone()))?;
-                    self.calculate_ctx_menu_pos(point);
+                    // an older response must not overwrite this one
+                    self.last_relays_response = None;
+                    self.relays_request =
+                        Some(conn.request(ToBackend::FetchRelayResponsesChatMsg(msg.clone()))?);
+                    self.calculate_ctx_menu_pos(
+                        point,
+                        is_deletable(&msg),
+                        msg.event_id().is_some(),
+                        has_attachment(&msg),
+                    );
                     self.hide_context_menu = false;
                     self.chat_message_pressed = Some(msg);
                 }
+                chat_view::Message::ChatPress(msg) => {
+                    if let Some(event_id) = msg.event_id() {
+                        if self.selection.is_some() || self.chat_view.ctrl_held() {
+                            self.toggle_selected(event_id);
+                        } else {
+                            self.left_pressed = Some((event_id, Instant::now()));
+                        }
+                    }
+                }
+                chat_view::Message::ChatRelease(msg) => {
+                    if let Some((event_id, pressed_at)) = self.left_pressed.take() {
+                        let is_long_press =
+                            msg.event_id() == Some(event_id) && pressed_at.elapsed() >= LONG_PRESS;
+                        if is_long_press && self.selection.is_none() {
+                            self.toggle_selected(event_id);
+                        }
+                    }
+                }
+                chat_view::Message::SelectionCopyPressed => {
+                    commands.push(clipboard::write(transcript(&self.selected_messages())));
+                    self.selection = None;
+                }
+                chat_view::Message::SelectionForwardPressed => {
+                    let contacts = self
+                        .chats
+                        .iter()
+                        .filter(|c| Some(c.id) != self.active_idx)
+                        .map(|c| c.contact.to_owned())
+                        .collect();
+                    let count = self.selected_messages().len();
+                    self.modal_state =
+                        ModalState::ForwardMessages(ForwardMessages::new(contacts, count));
+                }
+                chat_view::Message::SelectionDeletePressed => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        let event_ids = self
+                            .selected_messages()
+                            .iter()
+                            .filter_map(|m| m.event_id())
+                            .collect();
+                        conn.send(ToBackend::DeleteMessages(
+                            chat_contact.contact.to_owned(),
+                            event_ids,
+                        ))?;
+                    }
+                    self.selection = None;
+                }
+                chat_view::Message::SelectionCancelPressed => self.selection = None,
                 chat_view::Message::ChannelMenuPressed => {}
                 chat_view::Message::ChannelOpenModalPressed => {}
                 chat_view::Message::ChannelSearchPressed => {}
                 chat_view::Message::ChannelUserNamePressed(_) => {}
+                chat_view::Message::CommandSelected(name) => {
+                    let cursor = self.chat_view.select_command(name);
+                    commands.push(Command::batch(vec![
+                        text_input::focus(CHAT_INPUT_ID.clone()),
+                        text_input::move_cursor_to(CHAT_INPUT_ID.clone(), cursor),
+                    ]));
+                }
+                chat_view::Message::TemplatesPressed => {
+                    self.chat_view.toggle_templates();
+                }
+                chat_view::Message::TemplateSelected(template) => {
+                    commands.push(self.insert_template(&template));
+                }
+                chat_view::Message::ToggleLabel(label_id) => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::ToggleLabel(label_id, chat_contact.label_key()))?;
+                    }
+                }
+                chat_view::Message::PdfExportPressed => self.chat_view.toggle_pdf_export(),
+                chat_view::Message::PdfIncludeImagesToggled(include_images) => {
+                    self.chat_view.set_pdf_include_images(include_images);
+                }
+                chat_view::Message::PdfExportConfirm(include_images) => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::ExportConversationPdf {
+                            title: chat_contact.contact.select_name(),
+                            messages: self.messages.clone(),
+                            include_images,
+                        })?;
+                    }
+                }
+                chat_view::Message::ClearHistoryPressed => self.chat_view.toggle_clear_history(),
+                chat_view::Message::ClearKeepStarredToggled(keep_starred) => {
+                    self.chat_view.set_clear_keep_starred(keep_starred);
+                }
+                chat_view::Message::ClearHistoryConfirm(keep_starred) => {
+                    self.chat_view.close_clear_history();
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::ClearChatHistory {
+                            contact: chat_contact.contact.to_owned(),
+                            keep_starred,
+                        })?;
+                    }
+                }
+                chat_view::Message::ArchiveExportPressed => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::ExportConversationArchive(
+                            chat_contact.contact.to_owned(),
+                        ))?;
+                    }
+                }
+                chat_view::Message::TipPressed => {
+                    if let Some(address) = self.active_lightning_address() {
+                        open_wallet(&address.wallet_uri());
+                    }
+                }
+                chat_view::Message::ModifiersChanged(modifiers) => {
+                    self.chat_view.set_modifiers(modifiers)
+                }
+                chat_view::Message::TogglePreview => self.chat_view.toggle_preview(),
+                chat_view::Message::ResendDuplicatePressed => {
+                    if let Some(content) = self.chat_view.take_duplicate() {
+                        self.send_dm(content, true, conn)?;
+                        commands.push(scrollable::snap_to(
+                            CHAT_SCROLLABLE_ID.clone(),
+                            self.msgs_scroll_offset,
+                        ));
+                    }
+                }
+                chat_view::Message::DiscardDuplicatePressed => {
+                    self.chat_view.take_duplicate();
+                }
+                chat_view::Message::DeleteDuplicatePressed(chat_msg) => {
+                    self.request_deletion(chat_msg, conn)?;
+                }
+                chat_view::Message::AttachPressed(keep_metadata) => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        let target = DraftTarget::Contact(chat_contact.contact.pubkey().to_owned());
+                        conn.send(ToBackend::ChooseAttachment {
+                            target,
+                            keep_metadata,
+                        })?;
+                    }
+                }
+                chat_view::Message::CancelUploadPressed(id) => {
+                    conn.send(ToBackend::CancelUpload(id))?;
+                }
+                chat_view::Message::ResumeUploadPressed(id) => {
+                    conn.send(ToBackend::ResumeUpload(id))?;
+                }
+                chat_view::Message::DismissUploadPressed => self.chat_view.dismiss_upload(),
+                chat_view::Message::NostrLinkPressed(link) => match link {
+                    NostrLink::Profile(pubkey, _relays) => {
+                        self.modal_state =
+                            ModalState::basic_profile(&DbContact::new(&pubkey), conn)?;
+                    }
+                    NostrLink::Event(event_hash, relays) => {
+                        conn.send(ToBackend::ResolveMessageLink(event_hash, relays))?;
+                    }
+                },
+                chat_view::Message::HashtagPressed(hashtag) => {
+                    commands.change_route(GoToView::Hashtag(hashtag));
+                }
+                chat_view::Message::HoverCard(card_msg) => {
+                    self.hover_card_update(card_msg, &mut commands, conn)?;
+                }
+                chat_view::Message::ReactionPressed(event_id, content) => {
+                    conn.send(ToBackend::SendReaction(event_id, content))?;
+                }
+                chat_view::Message::FetchOlderHistory => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::FetchOlderDms(chat_contact.contact.to_owned()))?;
+                    }
+                }
+                chat_view::Message::ToggleAutoSaveAttachments => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::SetAutoSaveAttachments(
+                            chat_contact.contact.to_owned(),
+                            !self.auto_save_attachments,
+                        ))?;
+                    }
+                }
+                chat_view::Message::ToggleAutoTranslate => {
+                    if let Some(chat_contact) = self.active_chat() {
+                        conn.send(ToBackend::SetAutoTranslate(
+                            chat_contact.contact.to_owned(),
+                            !self.auto_translate,
+                        ))?;
+                    }
+                }
             },
 
             Message::ContactList(ct_msg) => match ct_msg {
@@ -680,11 +1713,54 @@ impl Route for State {
                     commands.change_route(GoToView::SettingsContacts);
                 }
                 contact_list::Message::SearchContactInputChange(text) => {
-                    self.contact_list.search_input_change(text);
+                    // pasted message links jump to the message
+                    if let Some((event_hash, relays)) = parse_event_link(&text) {
+                        conn.send(ToBackend::ResolveMessageLink(event_hash, relays))?;
+                        self.contact_list.search_input_change("".into());
+                    } else {
+                        self.contact_list.search_input_change(text);
+                    }
                 }
+                contact_list::Message::SearchSubmit => {
+                    if let Some(idx) = self.contact_list.selected_chat(&self.chats, &self.labels) {
+                        self.contact_list.search_input_change("".into());
+                        commands.push(self.set_active_contact(idx, conn)?);
+                    }
+                }
+                contact_list::Message::SearchNext => {
+                    self.contact_list.select_next(&self.chats, &self.labels);
+                }
+                contact_list::Message::SearchPrevious => self.contact_list.select_previous(),
                 contact_list::Message::ContactPress(idx) => {
                     commands.push(self.set_active_contact(idx, conn)?);
                 }
+                contact_list::Message::LabelFilterPress(label_id) => {
+                    self.contact_list.toggle_label_filter(label_id);
+                }
+                contact_list::Message::MarkAllReadPress => {
+                    conn.send(ToBackend::MarkAllChatsRead)?;
+                }
+                contact_list::Message::HoverCard(card_msg) => {
+                    self.hover_card_update(card_msg, &mut commands, conn)?;
+                }
+                contact_list::Message::DraftPress(idx) => {
+                    match self.drafts.get(idx).map(|item| item.target.to_owned()) {
+                        Some(SwitcherTarget::Contact(contact)) => {
+                            let idx = self
+                                .chats
+                                .iter()
+                                .find(|c| c.contact.pubkey() == contact.pubkey())
+                                .map(|c| c.id);
+                            if let Some(idx) = idx {
+                                commands.push(self.set_active_contact(idx, conn)?);
+                            }
+                        }
+                        Some(SwitcherTarget::Channel(channel_id)) => {
+                            commands.change_route(GoToView::Channel(channel_id));
+                        }
+                        None => (),
+                    }
+                }
             },
         }
 
@@ -692,7 +1768,36 @@ impl Route for State {
     }
 }
 
-fn make_context_menu<'a>(response: &Option<RelaysResponse>) -> Element<'a, Message> {
+/// Hands a `lightning:` URI of an address or an invoice to the wallet
+fn open_wallet(uri: &str) {
+    if let Err(e) = webbrowser::open(uri) {
+        tracing::error!("Failed to open wallet: {}", e);
+    }
+}
+
+/// Only the user's messages stored by the relays can be deleted from them
+fn is_deletable(chat_msg: &ChatMessage) -> bool {
+    matches!(
+        chat_msg,
+        ChatMessage::UserMessage(UserMessage::Confirmed {
+            retracted: false,
+            ..
+        })
+    )
+}
+
+/// Stored messages linking to files
+fn has_attachment(chat_msg: &ChatMessage) -> bool {
+    chat_msg.event_id().is_some() && !attachment_urls(chat_msg.content()).is_empty()
+}
+
+/// `starred` is `None` for messages not stored yet, they can't be starred
+fn make_context_menu<'a>(
+    response: &Option<RelaysResponse>,
+    deletable: bool,
+    starred: Option<bool>,
+    has_attachment: bool,
+) -> Element<'a, Message> {
     let copy_btn = button(
         row![
             text("Copy").size(18),
@@ -706,6 +1811,32 @@ fn make_context_menu<'a>(response: &Option<RelaysResponse>) -> Element<'a, Messa
     .on_press(Message::CopyPressed)
     .style(style::Button::ContextMenuButton);
 
+    let copy_link_btn = button(
+        row![
+            text("Copy link").size(18),
+            Space::with_width(Length::Fill),
+            copy_icon().size(16)
+        ]
+        .align_items(Alignment::Center),
+    )
+    .width(Length::Fill)
+    .height(CTX_BUTTON_HEIGHT)
+    .on_press(Message::CopyLinkPressed)
+    .style(style::Button::ContextMenuButton);
+
+    let translate_btn = button(
+        row![
+            text("Translate").size(18),
+            Space::with_width(Length::Fill),
+            text("Aa").size(16)
+        ]
+        .align_items(Alignment::Center),
+    )
+    .width(Length::Fill)
+    .height(CTX_BUTTON_HEIGHT)
+    .on_press(Message::TranslatePressed)
+    .style(style::Button::ContextMenuButton);
+
     // let reply_btn = button(
     //     row![
     //         text("Reply").size(18),
@@ -760,10 +1891,73 @@ fn make_context_menu<'a>(response: &Option<RelaysResponse>) -> Element<'a, Messa
             .into()
     };
 
-    let buttons = column![debug_btn, copy_btn, relays_btn].spacing(5);
+    let mut buttons = column![
+        debug_btn,
+        copy_btn,
+        copy_link_btn,
+        translate_btn,
+        relays_btn
+    ]
+    .spacing(5);
+    if let Some(starred) = starred {
+        let star_btn = button(
+            row![
+                text(if starred { "Unstar" } else { "Star" }).size(18),
+                Space::with_width(Length::Fill),
+                text(if starred { "☆" } else { "★" }).size(16)
+            ]
+            .align_items(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(CTX_BUTTON_HEIGHT)
+        .on_press(Message::StarPressed)
+        .style(style::Button::ContextMenuButton);
+        buttons = buttons.push(star_btn);
+    }
+    if has_attachment {
+        let folder_btn = button(
+            row![
+                text("Show in folder").size(18),
+                Space::with_width(Length::Fill),
+                folder_open_icon().size(16)
+            ]
+            .align_items(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(CTX_BUTTON_HEIGHT)
+        .on_press(Message::ShowInFolderPressed)
+        .style(style::Button::ContextMenuButton);
+        buttons = buttons.push(folder_btn);
+    }
+    if deletable {
+        let delete_btn = button(
+            row![
+                text("Delete message").size(18),
+                Space::with_width(Length::Fill),
+                delete_icon().size(16)
+            ]
+            .align_items(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(CTX_BUTTON_HEIGHT)
+        .on_press(Message::DeleteFromRelaysPressed)
+        .style(style::Button::ContextMenuButton);
+        buttons = buttons.push(
+            tooltip(
+                delete_btn,
+                "Delete for everyone, the relays are asked to remove it",
+                tooltip::Position::Right,
+            )
+            .style(style::Container::TooltipBg),
+        );
+    }
 
     container(buttons)
-        .height(ctx_menu_height())
+        .height(ctx_menu_height(
+            deletable,
+            starred.is_some(),
+            has_attachment,
+        ))
         .width(CONTEXT_MENU_WIDTH)
         .style(style::Container::ContextMenu)
         .padding(5)
@@ -777,8 +1971,11 @@ fn calculate_scroll_offset(position: usize, total_height: f32, card_height: f32)
     RelativeOffset { x: 0.0, y }
 }
 
-fn ctx_menu_height() -> f32 {
-    let n = 3.0;
+fn ctx_menu_height(deletable: bool, starrable: bool, has_attachment: bool) -> f32 {
+    let n = 5.0
+        + if deletable { 1.0 } else { 0.0 }
+        + if starrable { 1.0 } else { 0.0 }
+        + if has_attachment { 1.0 } else { 0.0 };
     let padding = 0.0;
     let ctx_elements_h = (CTX_BUTTON_HEIGHT + padding * 2.0) * n;
 
@@ -813,3 +2010,5 @@ impl RelaysResponse {
 const PIC_WIDTH: u16 = 50;
 const CONTEXT_MENU_WIDTH: f32 = 130.0;
 const CTX_BUTTON_HEIGHT: f32 = 30.0;
+/// Holding the left button this long on a message starts selecting
+const LONG_PRESS: Duration = Duration::from_millis(500);
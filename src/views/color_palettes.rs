@@ -1,4 +1,4 @@
-use crate::{components::text::title, style, widget::Element};
+use crate::{components::text::title, config::Appearance, style, widget::Element};
 use iced::widget::{column, container, row, text};
 use iced::Color;
 
@@ -15,7 +15,11 @@ impl State {
 }
 impl Route for State {
     type Message = Message;
-    fn view(&self, _selected_theme: Option<style::Theme>) -> Element<'_, Self::Message> {
+    fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        _appearance: Appearance,
+    ) -> Element<'_, Self::Message> {
         let page_title = title("Color Palettes");
 
         let color_grid = style::Theme::ALL
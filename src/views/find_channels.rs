@@ -1,13 +1,16 @@
 use std::collections::HashMap;
 
-use iced::widget::{button, column, container, image, row, text, Space};
+use iced::widget::{button, column, container, row, text, Space};
 use iced::Length;
 use iced_native::widget::text_input;
 use nostr::EventId;
 
-use crate::components::common_scrollable;
-use crate::components::text::title;
+use crate::components::live_card::live_card;
+use crate::components::text::{rich_text, title};
+use crate::components::{common_scrollable, DimmedImage};
+use crate::config::{Appearance, TextDirection};
 use crate::consts::{MEDIUM_CHANNEL_IMG_HEIGHT, MEDIUM_CHANNEL_IMG_WIDTH, YMD_FORMAT};
+use crate::db::{ChannelCache, ContactChannel, DbContact, DbLiveActivity};
 use crate::error::BackendClosed;
 use crate::net::{BackEndConnection, BackendEvent, ToBackend};
 use crate::types::ChannelResult;
@@ -22,18 +25,58 @@ pub enum Message {
     SearchInputChanged(String),
     SubmitPress,
     ChannelPressed(ChannelResult),
+    JoinLivePressed(String),
+    TabPressed(Tab),
+    PreviewPressed(EventId),
+    SubscribePressed(EventId),
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Search,
+    Contacts,
+    Archived,
 }
 pub struct State {
     search_results: HashMap<EventId, ChannelResult>,
     search_input_value: String,
     searching: bool,
+    live_activities: Vec<(DbLiveActivity, Option<DbContact>)>,
+    tab: Tab,
+    contact_channels: Vec<ContactChannel>,
+    /// None until the contacts tab is first opened
+    contacts_searching: Option<bool>,
+    /// Unsubscribed channels kept read-only, latest archived first
+    archived_channels: Vec<ChannelCache>,
 }
 impl State {
-    pub fn new(_conn: &mut BackEndConnection) -> Self {
-        Self {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchLiveActivities)?;
+        conn.send(ToBackend::FetchArchivedChannels)?;
+        Ok(Self {
             search_results: HashMap::new(),
             search_input_value: String::new(),
             searching: false,
+            live_activities: vec![],
+            tab: Tab::Search,
+            contact_channels: vec![],
+            contacts_searching: None,
+            archived_channels: vec![],
+        })
+    }
+    fn update_contact_channel_cache(&mut self, cache: ChannelCache) {
+        if let Some(channel) = self
+            .contact_channels
+            .iter_mut()
+            .find(|c| c.channel_id == cache.channel_id)
+        {
+            channel.cache = Some(cache);
+        }
+    }
+    fn update_live_activity(&mut self, activity: DbLiveActivity, host: Option<DbContact>) {
+        self.live_activities
+            .retain(|(a, _)| !(a.author == activity.author && a.identifier == activity.identifier));
+        if activity.is_live() {
+            self.live_activities.insert(0, (activity, host));
         }
     }
     pub fn update(
@@ -53,6 +96,24 @@ impl State {
                 self.search_results = HashMap::new();
                 conn.send(ToBackend::FindChannels(self.search_input_value.clone()))?;
             }
+            Message::JoinLivePressed(url) => {
+                if let Err(e) = webbrowser::open(&url) {
+                    tracing::error!("Failed to open stream: {}", e);
+                }
+            }
+            Message::TabPressed(tab) => {
+                self.tab = tab;
+                if tab == Tab::Contacts && self.contacts_searching.is_none() {
+                    self.contacts_searching = Some(true);
+                    conn.send(ToBackend::FetchContactChannels)?;
+                }
+            }
+            Message::PreviewPressed(channel_id) => {
+                return Ok(Some(HomeGoTo::ChannelId(channel_id)));
+            }
+            Message::SubscribePressed(channel_id) => {
+                conn.send(ToBackend::SubscribeToChannel(channel_id))?;
+            }
         }
 
         Ok(None)
@@ -107,14 +168,63 @@ impl State {
                     result.update_image(&image);
                 }
             }
+            BackendEvent::GotLiveActivities(activities) => {
+                self.live_activities = activities;
+            }
+            BackendEvent::LiveActivityUpdated(activity, host) => {
+                self.update_live_activity(activity, host);
+            }
+            BackendEvent::GotContactChannels {
+                channels,
+                searching,
+            } => {
+                self.contact_channels = channels;
+                self.contacts_searching = Some(searching);
+            }
+            BackendEvent::ContactChannelCache(cache) => {
+                self.update_contact_channel_cache(cache);
+            }
+            BackendEvent::GotArchivedChannels(caches) => {
+                self.archived_channels = caches;
+            }
+            BackendEvent::ChannelArchived(_) => {
+                conn.send(ToBackend::FetchArchivedChannels)?;
+            }
+            BackendEvent::ChannelSubscribed(channel_id) => {
+                self.contact_channels.retain(|c| c.channel_id != channel_id);
+                self.archived_channels
+                    .retain(|cache| cache.channel_id != channel_id);
+            }
+            BackendEvent::ChannelUnsubscribed(channel_id) => {
+                self.archived_channels
+                    .retain(|cache| cache.channel_id != channel_id);
+            }
             _ => (),
         }
 
         Ok(commands)
     }
-    pub fn view(&self, _selected_theme: Option<style::Theme>) -> Element<Message> {
-        let title = title("Find Channels");
-
+    fn tabs_row(&self) -> Element<Message> {
+        let tab_btn = |label: &'static str, tab: Tab| {
+            button(text(label).size(18))
+                .padding([5, 10])
+                .style(if self.tab == tab {
+                    style::Button::ActiveMenuBtn
+                } else {
+                    style::Button::MenuBtn
+                })
+                .on_press(Message::TabPressed(tab))
+        };
+        row![
+            tab_btn("Search", Tab::Search),
+            tab_btn("Channels your contacts use", Tab::Contacts),
+            tab_btn("Archived", Tab::Archived)
+        ]
+        .spacing(5)
+        .padding([0, 0, 10, 0])
+        .into()
+    }
+    fn search_view(&self, appearance: Appearance) -> Element<Message> {
         let searching_text = if self.searching {
             text("Searching for channels...").size(18)
         } else {
@@ -145,34 +255,202 @@ impl State {
                 acc.push(channel_card(
                     result,
                     Message::ChannelPressed(result.to_owned()),
+                    appearance,
                 ))
             });
 
+        column![search_input, searching_text, results_container].into()
+    }
+    fn contacts_view(&self, text_direction: TextDirection) -> Element<Message> {
+        let searching = self.contacts_searching.unwrap_or_default();
+        let status: Element<_> = if searching {
+            text("Looking for your contacts' channels...")
+                .size(18)
+                .into()
+        } else if self.contact_channels.is_empty() {
+            text("None of your contacts were seen in a channel you are not subscribed to")
+                .size(14)
+                .style(style::Text::Placeholder)
+                .into()
+        } else {
+            text("").into()
+        };
+
+        self.contact_channels
+            .iter()
+            .fold(column![status].spacing(5), |col, channel| {
+                col.push(contact_channel_row(channel, text_direction))
+            })
+            .max_width(MAX_WIDTH_RESULT)
+            .into()
+    }
+    fn archived_view(&self, text_direction: TextDirection) -> Element<Message> {
+        let status: Element<_> = if self.archived_channels.is_empty() {
+            text("Archive a channel from its menu to keep reading it without a subscription")
+                .size(14)
+                .style(style::Text::Placeholder)
+                .into()
+        } else {
+            text("").into()
+        };
+
+        self.archived_channels
+            .iter()
+            .fold(column![status].spacing(5), |col, cache| {
+                col.push(archived_channel_row(cache, text_direction))
+            })
+            .max_width(MAX_WIDTH_RESULT)
+            .into()
+    }
+    pub fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Message> {
+        let title = title("Find Channels");
+
+        let live_section: Element<_> = if self.live_activities.is_empty() {
+            text("").into()
+        } else {
+            self.live_activities
+                .iter()
+                .fold(
+                    column![text("Live").size(24)].spacing(5),
+                    |col, (activity, host)| {
+                        col.push(live_card(activity, host.as_ref(), Message::JoinLivePressed))
+                    },
+                )
+                .padding([0, 0, 20, 0])
+                .into()
+        };
+
+        let content = match self.tab {
+            Tab::Search => self.search_view(appearance),
+            Tab::Contacts => self.contacts_view(appearance.text_direction),
+            Tab::Archived => self.archived_view(appearance.text_direction),
+        };
+
         common_scrollable(
-            container(column![
-                title,
-                search_input,
-                searching_text,
-                results_container
-            ])
-            .width(Length::Fill)
-            .padding([20, 20, 0, 20]),
+            container(column![title, live_section, self.tabs_row(), content])
+                .width(Length::Fill)
+                .padding([20, 20, 0, 20]),
         )
         .into()
     }
 }
 
-fn channel_card<'a, M: 'a + Clone>(channel: &ChannelResult, on_channel_press: M) -> Element<'a, M> {
-    let image_container = container(image(channel.image_handle.to_owned()))
-        .width(MEDIUM_CHANNEL_IMG_WIDTH)
-        .height(MEDIUM_CHANNEL_IMG_HEIGHT)
-        .center_x()
-        .center_y();
+fn contact_channel_row(
+    channel: &ContactChannel,
+    text_direction: TextDirection,
+) -> Element<'_, Message> {
+    let name = channel
+        .cache
+        .as_ref()
+        .and_then(|c| c.metadata.name.clone())
+        .unwrap_or_else(|| channel.channel_id.to_hex()[..16].to_owned());
+    let about = channel
+        .cache
+        .as_ref()
+        .and_then(|c| c.metadata.about.clone())
+        .unwrap_or_default();
+    let contacts = format!(
+        "{} {}: {}",
+        channel.contacts.len(),
+        if channel.contacts.len() == 1 {
+            "contact"
+        } else {
+            "contacts"
+        },
+        channel.contact_names.join(", ")
+    );
+
+    let info = column![
+        text(name).size(20),
+        rich_text(&about).size(14).text_direction(text_direction),
+        text(contacts).size(14),
+        text(format!(
+            "Last active: {}",
+            channel.last_active.format(YMD_FORMAT)
+        ))
+        .size(14)
+        .style(style::Text::Placeholder),
+    ]
+    .spacing(2)
+    .width(Length::Fill);
+
+    let buttons = row![
+        button("Preview")
+            .style(style::Button::MenuBtn)
+            .on_press(Message::PreviewPressed(channel.channel_id)),
+        button("Subscribe")
+            .style(style::Button::Primary)
+            .on_press(Message::SubscribePressed(channel.channel_id)),
+    ]
+    .spacing(5);
+
+    container(row![info, buttons].spacing(10))
+        .padding(5)
+        .style(style::Container::Foreground)
+        .into()
+}
+
+fn archived_channel_row(
+    cache: &ChannelCache,
+    text_direction: TextDirection,
+) -> Element<'_, Message> {
+    let name = cache
+        .metadata
+        .name
+        .clone()
+        .unwrap_or_else(|| cache.channel_id.to_hex()[..16].to_owned());
+    let about = cache.metadata.about.clone().unwrap_or_default();
+
+    let info = column![
+        text(name).size(20),
+        rich_text(&about).size(14).text_direction(text_direction),
+        text(format!("Members: {}", cache.members.len()))
+            .size(14)
+            .style(style::Text::Placeholder),
+    ]
+    .spacing(2)
+    .width(Length::Fill);
+
+    let buttons = row![
+        button("Open")
+            .style(style::Button::MenuBtn)
+            .on_press(Message::PreviewPressed(cache.channel_id)),
+        button("Re-subscribe")
+            .style(style::Button::Primary)
+            .on_press(Message::SubscribePressed(cache.channel_id)),
+    ]
+    .spacing(5);
+
+    container(row![info, buttons].spacing(10))
+        .padding(5)
+        .style(style::Container::Foreground)
+        .into()
+}
+
+fn channel_card<'a, M: 'a + Clone>(
+    channel: &ChannelResult,
+    on_channel_press: M,
+    appearance: Appearance,
+) -> Element<'a, M> {
+    let image_container = container(DimmedImage::new(
+        channel.image_handle.to_owned(),
+        appearance.dim_images,
+    ))
+    .width(MEDIUM_CHANNEL_IMG_WIDTH)
+    .height(MEDIUM_CHANNEL_IMG_HEIGHT)
+    .center_x()
+    .center_y();
 
     let name_about_ct = container(common_scrollable(
         column![
             text(channel.name()).size(22),
-            text(channel.about()).size(18),
+            rich_text(&channel.about())
+                .size(18)
+                .text_direction(appearance.text_direction),
             text(&channel.relay_url.to_string()).size(14),
         ]
         .spacing(5),
@@ -0,0 +1,209 @@
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Length};
+use nostr::prelude::ToBech32;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::EventId;
+
+use crate::components::common_scrollable;
+use crate::components::text::{rich_text, title};
+use crate::config::{Appearance, TextDirection};
+use crate::db::DbContact;
+use crate::error::BackendClosed;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::types::HashtagNote;
+use crate::utils::{from_naive_utc_to_local, hide_string};
+use crate::views::RouterCommand;
+use crate::widget::Element;
+
+use super::home::HomeGoTo;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FollowPressed,
+    UnfollowPressed,
+    HashtagPressed(String),
+    ChannelPressed(EventId),
+    AuthorPressed(XOnlyPublicKey),
+}
+
+/// Latest notes and channel messages with a hashtag, asked to the relays when opened
+pub struct State {
+    hashtag: String,
+    /// Newest first
+    notes: Vec<HashtagNote>,
+    followed: bool,
+    loading: bool,
+}
+impl State {
+    pub fn new(
+        hashtag: String,
+        followed: bool,
+        conn: &mut BackEndConnection,
+    ) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchHashtagFeed(hashtag.clone()))?;
+        Ok(Self {
+            hashtag,
+            notes: vec![],
+            followed,
+            loading: true,
+        })
+    }
+
+    pub fn hashtag(&self) -> &str {
+        &self.hashtag
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<Option<HomeGoTo>, BackendClosed> {
+        match message {
+            Message::FollowPressed => {
+                conn.send(ToBackend::FollowHashtag(self.hashtag.clone()))?;
+            }
+            Message::UnfollowPressed => {
+                conn.send(ToBackend::UnfollowHashtag(self.hashtag.clone()))?;
+            }
+            Message::HashtagPressed(hashtag) => {
+                if hashtag != self.hashtag {
+                    return Ok(Some(HomeGoTo::Hashtag(hashtag)));
+                }
+            }
+            Message::ChannelPressed(channel_id) => {
+                return Ok(Some(HomeGoTo::ChannelId(channel_id)));
+            }
+            Message::AuthorPressed(pubkey) => {
+                return Ok(Some(HomeGoTo::Chat(DbContact::new(&pubkey))));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn backend_event(
+        &mut self,
+        event: BackendEvent,
+        _conn: &mut BackEndConnection,
+    ) -> Result<RouterCommand<Message>, BackendClosed> {
+        let commands = RouterCommand::new();
+
+        match event {
+            BackendEvent::GotHashtagNote(note) | BackendEvent::NewHashtagNote(note) => {
+                if note.has_hashtag(&self.hashtag) {
+                    self.insert_note(note);
+                }
+            }
+            BackendEvent::HashtagFeedEose(hashtag) => {
+                if hashtag == self.hashtag {
+                    self.loading = false;
+                }
+            }
+            BackendEvent::GotFollowedHashtags(hashtags) => {
+                self.followed = hashtags.contains(&self.hashtag);
+            }
+            _ => (),
+        }
+
+        Ok(commands)
+    }
+
+    /// Every relay sends its copy, each note is kept once
+    fn insert_note(&mut self, note: HashtagNote) {
+        if self.notes.iter().any(|n| n.event_id == note.event_id) {
+            return;
+        }
+        let idx = self
+            .notes
+            .iter()
+            .position(|n| n.created_at < note.created_at)
+            .unwrap_or(self.notes.len());
+        self.notes.insert(idx, note);
+        self.notes.truncate(MAX_NOTES);
+    }
+
+    pub fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Message> {
+        let follow_btn = if self.followed {
+            button(text("Following"))
+                .style(style::Button::Bordered)
+                .on_press(Message::UnfollowPressed)
+        } else {
+            button(text("Follow hashtag"))
+                .style(style::Button::Primary)
+                .on_press(Message::FollowPressed)
+        };
+        let header = row![title(format!("#{}", self.hashtag)), follow_btn]
+            .align_items(Alignment::Center)
+            .spacing(10);
+
+        let content: Element<_> = if self.notes.is_empty() {
+            let empty = if self.loading {
+                "Loading...".to_owned()
+            } else {
+                format!("No notes with #{} on your relays", self.hashtag)
+            };
+            text(empty).style(style::Text::Placeholder).into()
+        } else {
+            self.notes
+                .iter()
+                .fold(column![].spacing(10), |col, note| {
+                    col.push(note_view(note, appearance.text_direction))
+                })
+                .into()
+        };
+
+        common_scrollable(
+            container(column![header, content].spacing(10))
+                .width(Length::Fill)
+                .padding([20, 20, 0, 20]),
+        )
+        .into()
+    }
+}
+
+fn note_view(note: &HashtagNote, text_direction: TextDirection) -> Element<'_, Message> {
+    let author = note.author_name.clone().unwrap_or_else(|| {
+        let npub = note.author.to_bech32().unwrap_or(note.author.to_string());
+        hide_string(&npub, 12)
+    });
+    let time = from_naive_utc_to_local(note.created_at)
+        .format(TIME_FORMAT)
+        .to_string();
+
+    let author_btn = button(text(author))
+        .style(style::Button::Invisible)
+        .on_press(Message::AuthorPressed(note.author));
+    let channel_btn: Element<_> = match note.channel_id {
+        Some(channel_id) => button(text("in channel").size(14))
+            .style(style::Button::Link)
+            .on_press(Message::ChannelPressed(channel_id))
+            .into(),
+        None => Space::with_width(0).into(),
+    };
+    let header = row![
+        author_btn,
+        channel_btn,
+        Space::with_width(Length::Fill),
+        text(time).size(14).style(style::Text::Alpha(0.5))
+    ]
+    .align_items(Alignment::Center)
+    .spacing(10);
+
+    let content = rich_text(&note.content)
+        .size(16)
+        .on_hashtag(Message::HashtagPressed)
+        .text_direction(text_direction);
+
+    container(column![header, content].spacing(5))
+        .padding(10)
+        .width(Length::Fill)
+        .style(style::Container::Foreground)
+        .into()
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const MAX_NOTES: usize = 200;
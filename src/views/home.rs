@@ -1,16 +1,20 @@
-use iced::widget::{button, column, container, image, image::Handle, row, Rule};
+use iced::widget::{button, column, container, image, image::Handle, row, text, tooltip, Rule};
 use iced::{alignment, Length, Subscription};
+use iced_aw::Modal;
 use nostr::EventId;
 use status_bar::StatusBar;
 
-use crate::components::{invisible_scrollable, status_bar};
+use crate::components::badge_overlay::Corner;
+use crate::components::quick_switcher::{self, QuickSwitcher, SwitcherTarget};
+use crate::components::{invisible_scrollable, status_bar, BadgeOverlay, DimmedImage};
+use crate::config::Appearance;
 use crate::consts::default_channel_image;
 use crate::db::{ChannelCache, DbContact};
 use crate::error::BackendClosed;
-use crate::icon::{settings_icon, wand_icon};
+use crate::icon::{add_friend_icon, regular_bell_icon, settings_icon, wand_icon};
 use crate::net::{BackEndConnection, BackendEvent, ImageSize, ToBackend};
 
-use crate::types::ChannelResult;
+use crate::types::{ChannelResult, ChatMessage, NostrLink};
 use crate::widget::Text;
 use crate::{
     icon::{home_icon, search_icon},
@@ -18,41 +22,63 @@ use crate::{
     widget::Element,
 };
 
+use super::modal::{integrity_report, IntegrityReportModal, ModalView};
 use super::route::Route;
-use super::{channel, chat, color_palettes, find_channels, GoToView, RouterCommand};
+use super::{
+    activity, channel, chat, color_palettes, find_channels, hashtag, inbox, GoToView, RouterCommand,
+};
 
 pub enum HomeGoTo {
     Channel(ChannelResult),
+    ChannelId(EventId),
+    Chat(DbContact),
+    Hashtag(String),
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     DMsPressed,
+    InboxPressed,
+    ActivityPressed,
     FindChannelsPressed,
     SettingsPressed,
     ColorPalettePressed,
     MenuChannelBtnPressed(EventId),
+    MenuHashtagBtnPressed(String),
     Dms(chat::Message),
+    Inbox(inbox::Message),
+    Activity(activity::Message),
+    Hashtag(hashtag::Message),
     FindChannels(find_channels::Message),
     StatusBar(status_bar::Message),
     ColorPalette(color_palettes::Message),
     Channel(channel::Message),
+    IntegrityReport(Box<integrity_report::CMessage<Message>>),
+    QuickSwitcher(quick_switcher::Message),
 }
 pub struct State {
     active_view: ViewState,
     channels_subscribed: Vec<ChannelMenuBtn>,
+    followed_hashtags: Vec<HashtagMenuBtn>,
     status_bar: StatusBar,
+    integrity_report: Option<IntegrityReportModal<Message>>,
+    quick_switcher: Option<QuickSwitcher>,
 }
 
 impl State {
     pub(crate) fn chat(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
         conn.send(ToBackend::FetchSubscribedChannels)?;
+        conn.send(ToBackend::TakeIntegrityReport)?;
+        conn.send(ToBackend::FetchFollowedHashtags)?;
         Ok(Self {
-            status_bar: StatusBar::new(),
+            status_bar: StatusBar::new(conn)?,
+            integrity_report: None,
+            quick_switcher: None,
             active_view: ViewState::DMs {
                 state: chat::State::new(conn)?,
             },
             channels_subscribed: Vec::new(),
+            followed_hashtags: Vec::new(),
         })
     }
     pub(crate) fn chat_to(
@@ -60,24 +86,137 @@ impl State {
         conn: &mut BackEndConnection,
     ) -> Result<Self, BackendClosed> {
         conn.send(ToBackend::FetchSubscribedChannels)?;
+        conn.send(ToBackend::TakeIntegrityReport)?;
+        conn.send(ToBackend::FetchFollowedHashtags)?;
         Ok(Self {
-            status_bar: StatusBar::new(),
+            status_bar: StatusBar::new(conn)?,
+            integrity_report: None,
+            quick_switcher: None,
             active_view: ViewState::DMs {
                 state: chat::State::chat_to(db_contact, conn)?,
             },
             channels_subscribed: Vec::new(),
+            followed_hashtags: Vec::new(),
+        })
+    }
+    /// Chat list that opens the conversation visited last once the backend finds it
+    pub(crate) fn last_chat(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchLastConversation)?;
+        Self::chat(conn)
+    }
+    pub(crate) fn inbox(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchSubscribedChannels)?;
+        conn.send(ToBackend::TakeIntegrityReport)?;
+        conn.send(ToBackend::FetchFollowedHashtags)?;
+        Ok(Self {
+            status_bar: StatusBar::new(conn)?,
+            integrity_report: None,
+            quick_switcher: None,
+            active_view: ViewState::Inbox {
+                state: inbox::State::new(conn)?,
+            },
+            channels_subscribed: Vec::new(),
+            followed_hashtags: Vec::new(),
         })
     }
     pub(crate) fn find_channels(conn: &mut BackEndConnection) -> Result<State, BackendClosed> {
         conn.send(ToBackend::FetchSubscribedChannels)?;
+        conn.send(ToBackend::TakeIntegrityReport)?;
+        conn.send(ToBackend::FetchFollowedHashtags)?;
         Ok(Self {
-            status_bar: StatusBar::new(),
+            status_bar: StatusBar::new(conn)?,
+            integrity_report: None,
+            quick_switcher: None,
             active_view: ViewState::FindChannel {
-                state: find_channels::State::new(conn),
+                state: find_channels::State::new(conn)?,
             },
             channels_subscribed: Vec::new(),
+            followed_hashtags: Vec::new(),
         })
     }
+
+    fn is_following(&self, hashtag: &str) -> bool {
+        self.followed_hashtags
+            .iter()
+            .any(|btn| btn.hashtag == hashtag)
+    }
+
+    fn is_subscribed(&self, channel_id: &EventId) -> bool {
+        self.channels_subscribed
+            .iter()
+            .any(|btn| &btn.channel_id == channel_id)
+    }
+
+    /// Opens a `nostr:` link, events are located by the backend first
+    pub(crate) fn open_link(
+        &mut self,
+        link: NostrLink,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match link {
+            NostrLink::Profile(pubkey, _relays) => {
+                self.go_to(HomeGoTo::Chat(DbContact::new(&pubkey)), conn)
+            }
+            NostrLink::Event(event_id, relays) => {
+                conn.send(ToBackend::ResolveMessageLink(event_id, relays))
+            }
+        }
+    }
+
+    pub(crate) fn open_hashtag(
+        &mut self,
+        hashtag: String,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        self.go_to(HomeGoTo::Hashtag(hashtag), conn)
+    }
+
+    pub(crate) fn open_channel(
+        &mut self,
+        channel_id: EventId,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        self.go_to(HomeGoTo::ChannelId(channel_id), conn)
+    }
+
+    fn go_to(
+        &mut self,
+        go_to: HomeGoTo,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        self.active_view = match go_to {
+            HomeGoTo::Channel(result) => {
+                let channel_id = result.cache.channel_id;
+                ViewState::Channel {
+                    state: channel::Channel::load(
+                        channel_id,
+                        self.is_subscribed(&channel_id),
+                        conn,
+                    )?,
+                }
+            }
+            HomeGoTo::ChannelId(channel_id) => ViewState::Channel {
+                state: channel::Channel::load(channel_id, self.is_subscribed(&channel_id), conn)?,
+            },
+            HomeGoTo::Chat(db_contact) => ViewState::DMs {
+                state: chat::State::chat_to(db_contact, conn)?,
+            },
+            HomeGoTo::Hashtag(hashtag) => {
+                if let Some(btn) = self
+                    .followed_hashtags
+                    .iter_mut()
+                    .find(|btn| btn.hashtag == hashtag)
+                {
+                    btn.unseen = 0;
+                }
+                let followed = self.is_following(&hashtag);
+                ViewState::Hashtag {
+                    state: hashtag::State::new(hashtag, followed, conn)?,
+                }
+            }
+        };
+        Ok(())
+    }
 }
 
 impl Route for State {
@@ -86,6 +225,7 @@ impl Route for State {
         Subscription::batch(vec![
             self.active_view.subscription(),
             self.status_bar.subscription().map(Message::StatusBar),
+            QuickSwitcher::subscription().map(Message::QuickSwitcher),
         ])
     }
     fn backend_event(
@@ -100,12 +240,77 @@ impl Route for State {
                     .map(ChannelMenuBtn::with_cache)
                     .collect()
             }
+            BackendEvent::ChannelUnreadCounts(counts) => {
+                for btn in &mut self.channels_subscribed {
+                    btn.unread = counts.get(&btn.channel_id).copied().unwrap_or(0);
+                }
+            }
+            BackendEvent::ReceivedChannelMessage(
+                channel_id,
+                ChatMessage::ContactMessage { .. },
+            ) if !self.active_view.is_channel_selected(&channel_id) => {
+                if let Some(btn) = self
+                    .channels_subscribed
+                    .iter_mut()
+                    .find(|btn| btn.channel_id == channel_id && !btn.muted)
+                {
+                    btn.unread += 1;
+                }
+            }
+            BackendEvent::GotMutedChannels(muted) => {
+                for btn in &mut self.channels_subscribed {
+                    btn.muted = muted.contains(&btn.channel_id);
+                }
+            }
             BackendEvent::ChannelSubscribed(channel_id) => {
                 self.channels_subscribed
                     .push(ChannelMenuBtn::new(channel_id));
                 conn.send(ToBackend::FetchChannelCache(channel_id))?;
             }
-            BackendEvent::ChannelUnsubscribed(channel_id) => {
+            BackendEvent::ChannelLocated(channel_id) => {
+                self.go_to(HomeGoTo::ChannelId(channel_id), conn)?;
+            }
+            BackendEvent::GotLastConversation(target) => {
+                let go_to = match target {
+                    SwitcherTarget::Contact(db_contact) => HomeGoTo::Chat(db_contact),
+                    SwitcherTarget::Channel(channel_id) => HomeGoTo::ChannelId(channel_id),
+                };
+                self.go_to(go_to, conn)?;
+            }
+            BackendEvent::EventLocated(db_contact, event_id)
+                if !matches!(self.active_view, ViewState::DMs { .. }) =>
+            {
+                self.active_view = ViewState::DMs {
+                    state: chat::State::chat_to_event(db_contact, event_id, conn)?,
+                };
+            }
+            BackendEvent::GotIntegrityReport(report) => {
+                self.integrity_report = Some(IntegrityReportModal::new(report));
+            }
+            BackendEvent::GotFollowedHashtags(hashtags) => {
+                self.followed_hashtags = hashtags
+                    .into_iter()
+                    .map(|hashtag| {
+                        let unseen = self
+                            .followed_hashtags
+                            .iter()
+                            .find(|btn| btn.hashtag == hashtag)
+                            .map_or(0, |btn| btn.unseen);
+                        HashtagMenuBtn { hashtag, unseen }
+                    })
+                    .collect();
+            }
+            BackendEvent::NewHashtagNote(note) => {
+                for btn in &mut self.followed_hashtags {
+                    if note.has_hashtag(&btn.hashtag)
+                        && !self.active_view.is_hashtag_selected(&btn.hashtag)
+                    {
+                        btn.unseen += 1;
+                    }
+                }
+            }
+            BackendEvent::ChannelUnsubscribed(channel_id)
+            | BackendEvent::ChannelArchived(channel_id) => {
                 self.channels_subscribed
                     .retain(|btn| btn.channel_id != channel_id);
             }
@@ -121,6 +326,10 @@ impl Route for State {
             _ => (),
         }
 
+        if let Some(switcher) = &mut self.quick_switcher {
+            switcher.backend_event(event.clone());
+        }
+
         let mut commands = self.active_view.backend_event(event.clone(), conn)?;
 
         let cmd = self.status_bar.backend_event(event, conn);
@@ -152,14 +361,35 @@ impl Route for State {
                     }
                 }
             },
+            Message::InboxPressed => match self.active_view {
+                ViewState::Inbox { .. } => (),
+                _ => {
+                    self.active_view = ViewState::Inbox {
+                        state: inbox::State::new(conn)?,
+                    }
+                }
+            },
+            Message::ActivityPressed => match self.active_view {
+                ViewState::Activity { .. } => (),
+                _ => {
+                    self.active_view = ViewState::Activity {
+                        state: activity::State::new(conn)?,
+                    }
+                }
+            },
             Message::FindChannelsPressed => match self.active_view {
                 ViewState::FindChannel { .. } => (),
                 _ => {
                     self.active_view = ViewState::FindChannel {
-                        state: find_channels::State::new(conn),
+                        state: find_channels::State::new(conn)?,
                     }
                 }
             },
+            Message::MenuHashtagBtnPressed(hashtag) => {
+                if !self.active_view.is_hashtag_selected(&hashtag) {
+                    self.go_to(HomeGoTo::Hashtag(hashtag), conn)?;
+                }
+            }
             Message::MenuChannelBtnPressed(channel_id) => match &mut self.active_view {
                 ViewState::Channel { state } if state.matches_id(&channel_id) => (),
                 _ => {
@@ -176,27 +406,68 @@ impl Route for State {
             }
             Message::FindChannels(msg) => {
                 if let ViewState::FindChannel { state } = &mut self.active_view {
-                    if let Some(router_message) = state.update(msg, conn)? {
-                        match router_message {
-                            HomeGoTo::Channel(result) => {
-                                let is_subscribed = self
-                                    .channels_subscribed
-                                    .iter()
-                                    .any(|btn| btn.channel_id == result.cache.channel_id);
-
-                                self.active_view = ViewState::Channel {
-                                    state: channel::Channel::load(
-                                        result.cache.channel_id,
-                                        is_subscribed,
-                                        conn,
-                                    )?,
-                                }
+                    if let Some(go_to) = state.update(msg, conn)? {
+                        self.go_to(go_to, conn)?;
+                    }
+                }
+            }
+            Message::Inbox(msg) => {
+                if let ViewState::Inbox { state } = &mut self.active_view {
+                    if let Some(go_to) = state.update(msg, conn)? {
+                        self.go_to(go_to, conn)?;
+                    }
+                }
+            }
+            Message::Activity(msg) => {
+                if let ViewState::Activity { state } = &mut self.active_view {
+                    if let Some(go_to) = state.update(msg, conn)? {
+                        self.go_to(go_to, conn)?;
+                    }
+                }
+            }
+            Message::Hashtag(msg) => {
+                if let ViewState::Hashtag { state } = &mut self.active_view {
+                    if let Some(go_to) = state.update(msg, conn)? {
+                        self.go_to(go_to, conn)?;
+                    }
+                }
+            }
+            Message::QuickSwitcher(msg) => match &mut self.quick_switcher {
+                None => {
+                    if let quick_switcher::Message::Open = msg {
+                        self.quick_switcher = Some(QuickSwitcher::new(conn)?);
+                        commands.push(QuickSwitcher::focus());
+                    }
+                }
+                Some(switcher) => match switcher.update(msg) {
+                    Some(quick_switcher::Action::Open(target)) => {
+                        self.quick_switcher = None;
+                        let go_to = match target {
+                            SwitcherTarget::Contact(db_contact) => HomeGoTo::Chat(db_contact),
+                            SwitcherTarget::Channel(channel_id) => HomeGoTo::ChannelId(channel_id),
+                        };
+                        self.go_to(go_to, conn)?;
+                    }
+                    Some(quick_switcher::Action::Close) => self.quick_switcher = None,
+                    None => (),
+                },
+            },
+            Message::SettingsPressed => commands.change_route(GoToView::Settings),
+            Message::IntegrityReport(modal_msg) => {
+                if let Some(state) = &mut self.integrity_report {
+                    match *modal_msg {
+                        integrity_report::CMessage::UnderlayMessage(message) => {
+                            return self.update(message, conn);
+                        }
+                        other => {
+                            let (_, close_modal) = state.update(other, conn)?;
+                            if close_modal {
+                                self.integrity_report = None;
                             }
                         }
                     }
                 }
             }
-            Message::SettingsPressed => commands.change_route(GoToView::Settings),
             Message::ColorPalette(msg) => {
                 if let ViewState::ColorPalettes { state } = &mut self.active_view {
                     return Ok(state.update(msg, conn)?.map(Message::ColorPalette));
@@ -216,13 +487,27 @@ impl Route for State {
 
         Ok(commands)
     }
-    fn view(&self, selected_theme: Option<style::Theme>) -> Element<Self::Message> {
+    fn view(
+        &self,
+        selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Self::Message> {
         let color_palette_btn = make_menu_btn(
             self.active_view.is_color_palette_view(),
             wand_icon,
             Message::ColorPalettePressed,
         );
         let dm_btn = make_menu_btn(self.active_view.is_dms(), home_icon, Message::DMsPressed);
+        let inbox_btn = make_menu_btn(
+            self.active_view.is_inbox(),
+            regular_bell_icon,
+            Message::InboxPressed,
+        );
+        let activity_btn = make_menu_btn(
+            self.active_view.is_activity(),
+            add_friend_icon,
+            Message::ActivityPressed,
+        );
         let find_ch_btn = make_menu_btn(
             self.active_view.is_find_channel(),
             search_icon,
@@ -242,19 +527,31 @@ impl Route for State {
                     col.push(make_channel_menu_btn(
                         is_active,
                         btn.image_handle.to_owned(),
+                        btn.unread,
                         message,
+                        appearance.dim_images,
                     ))
                 });
+        let hashtag_buttons =
+            self.followed_hashtags
+                .iter()
+                .fold(column![].spacing(2), |col, btn| {
+                    let is_active = self.active_view.is_hashtag_selected(&btn.hashtag);
+                    col.push(make_hashtag_menu_btn(is_active, btn))
+                });
 
         let nav_bar = container(
             column![
                 container(invisible_scrollable(
                     column![
                         dm_btn,
+                        inbox_btn,
+                        activity_btn,
                         spacer,
                         find_ch_btn,
                         color_palette_btn,
-                        channel_buttons
+                        channel_buttons,
+                        hashtag_buttons
                     ]
                     .spacing(2)
                 ),)
@@ -271,13 +568,27 @@ impl Route for State {
         let status_bar = self.status_bar.view().map(Message::StatusBar);
 
         let active_view = column![
-            container(self.active_view.view(selected_theme))
+            container(self.active_view.view(selected_theme, appearance))
                 .width(Length::Fill)
                 .height(Length::Fill),
             status_bar
         ];
 
-        row![nav_bar, active_view].into()
+        let content: Element<_> = match &self.quick_switcher {
+            Some(switcher) => Modal::new(true, row![nav_bar, active_view], move || {
+                switcher.view().map(Message::QuickSwitcher)
+            })
+            .backdrop(Message::QuickSwitcher(quick_switcher::Message::Close))
+            .on_esc(Message::QuickSwitcher(quick_switcher::Message::Close))
+            .into(),
+            None => row![nav_bar, active_view].into(),
+        };
+        match &self.integrity_report {
+            Some(state) => state
+                .view(content, appearance)
+                .map(|m| Message::IntegrityReport(Box::new(m))),
+            None => content.into(),
+        }
     }
 }
 
@@ -312,23 +623,84 @@ fn make_menu_btn<'a, M: 'a + Clone>(
     .into()
 }
 
+/// Subscribed channel with the count of unread messages
 fn make_channel_menu_btn<'a, M: 'a + Clone>(
     is_active: bool,
     image_handle: Handle,
+    unread: i64,
     message: M,
+    dim_images: bool,
 ) -> Element<'a, M> {
     let style = if is_active {
         style::Button::ActiveMenuBtn
     } else {
         style::Button::MenuBtn
     };
+    let unread_badge = (unread > 0).then(|| {
+        let count_txt = if unread > 99 {
+            "99+".to_owned()
+        } else {
+            unread.to_string()
+        };
+        button(text(count_txt).size(UNREAD_TEXT_SIZE))
+            .padding([0, 3])
+            .style(style::Button::Notification)
+    });
 
     container(
-        button(image(image_handle).width(Length::Fill).height(Length::Fill))
+        BadgeOverlay::new(
+            button(DimmedImage::with_content(
+                image(image_handle).width(Length::Fill).height(Length::Fill),
+                dim_images,
+            ))
             .style(style)
             .width(Length::Fill)
             .height(Length::Fill)
             .on_press(message),
+        )
+        .badge_maybe(Corner::TopRight, unread_badge),
+    )
+    .padding([PADDING_V, PADDING_H])
+    .width(NAVBAR_WIDTH)
+    .height(NAVBAR_WIDTH)
+    .into()
+}
+
+/// Followed hashtag, shows whether new notes arrived since it was opened
+fn make_hashtag_menu_btn<'a>(is_active: bool, btn: &HashtagMenuBtn) -> Element<'a, Message> {
+    let style = if is_active {
+        style::Button::ActiveMenuBtn
+    } else {
+        style::Button::MenuBtn
+    };
+    let (text_style, tip) = if btn.unseen > 0 {
+        (
+            style::Text::Primary,
+            format!("#{}, {} new", btn.hashtag, btn.unseen),
+        )
+    } else {
+        (style::Text::Default, format!("#{}", btn.hashtag))
+    };
+
+    container(
+        tooltip(
+            button(
+                text("#")
+                    .size(ICON_SIZE)
+                    .style(text_style)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .vertical_alignment(alignment::Vertical::Center)
+                    .horizontal_alignment(alignment::Horizontal::Center),
+            )
+            .style(style)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .on_press(Message::MenuHashtagBtnPressed(btn.hashtag.to_owned())),
+            tip,
+            tooltip::Position::Right,
+        )
+        .style(style::Container::TooltipBg),
     )
     .padding([PADDING_V, PADDING_H])
     .width(NAVBAR_WIDTH)
@@ -341,17 +713,32 @@ pub enum ViewState {
     ColorPalettes { state: color_palettes::State },
     DMs { state: chat::State },
     FindChannel { state: find_channels::State },
+    Inbox { state: inbox::State },
+    Activity { state: activity::State },
+    Hashtag { state: hashtag::State },
 }
 impl ViewState {
     pub fn is_dms(&self) -> bool {
         matches!(self, ViewState::DMs { .. })
     }
+    pub fn is_inbox(&self) -> bool {
+        matches!(self, ViewState::Inbox { .. })
+    }
+    pub fn is_activity(&self) -> bool {
+        matches!(self, ViewState::Activity { .. })
+    }
     pub fn is_find_channel(&self) -> bool {
         matches!(self, ViewState::FindChannel { .. })
     }
     fn is_color_palette_view(&self) -> bool {
         matches!(self, ViewState::ColorPalettes { .. })
     }
+    fn is_hashtag_selected(&self, hashtag: &str) -> bool {
+        match self {
+            ViewState::Hashtag { state } => state.hashtag() == hashtag,
+            _ => false,
+        }
+    }
     fn is_channel_selected(&self, channel_id: &EventId) -> bool {
         match self {
             ViewState::Channel { state } => state.matches_id(channel_id),
@@ -376,6 +763,11 @@ impl Route for ViewState {
             ViewState::FindChannel { state } => {
                 state.backend_event(event, conn)?.map(Message::FindChannels)
             }
+            ViewState::Inbox { state } => state.backend_event(event, conn)?.map(Message::Inbox),
+            ViewState::Activity { state } => {
+                state.backend_event(event, conn)?.map(Message::Activity)
+            }
+            ViewState::Hashtag { state } => state.backend_event(event, conn)?.map(Message::Hashtag),
         };
 
         Ok(command)
@@ -386,17 +778,35 @@ impl Route for ViewState {
             ViewState::Channel { state } => state.subscription().map(Message::Channel),
             ViewState::DMs { state } => state.subscription().map(Message::Dms),
             ViewState::FindChannel { state: _ } => Subscription::none(),
+            ViewState::Inbox { state: _ } => Subscription::none(),
+            ViewState::Activity { state: _ } => Subscription::none(),
+            ViewState::Hashtag { state: _ } => Subscription::none(),
         }
     }
-    fn view(&self, selected_theme: Option<style::Theme>) -> Element<Self::Message> {
+    fn view(
+        &self,
+        selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Self::Message> {
         match self {
-            ViewState::ColorPalettes { state } => {
-                state.view(selected_theme).map(Message::ColorPalette)
+            ViewState::ColorPalettes { state } => state
+                .view(selected_theme, appearance)
+                .map(Message::ColorPalette),
+            ViewState::Channel { state } => {
+                state.view(selected_theme, appearance).map(Message::Channel)
             }
-            ViewState::Channel { state } => state.view(selected_theme).map(Message::Channel),
-            ViewState::DMs { state } => state.view(selected_theme).map(Message::Dms),
-            ViewState::FindChannel { state } => {
-                state.view(selected_theme).map(Message::FindChannels)
+            ViewState::DMs { state } => state.view(selected_theme, appearance).map(Message::Dms),
+            ViewState::FindChannel { state } => state
+                .view(selected_theme, appearance)
+                .map(Message::FindChannels),
+            ViewState::Inbox { state } => {
+                state.view(selected_theme, appearance).map(Message::Inbox)
+            }
+            ViewState::Activity { state } => state
+                .view(selected_theme, appearance)
+                .map(Message::Activity),
+            ViewState::Hashtag { state } => {
+                state.view(selected_theme, appearance).map(Message::Hashtag)
             }
         }
     }
@@ -406,6 +816,10 @@ pub struct ChannelMenuBtn {
     channel_id: EventId,
     cache: Option<ChannelCache>,
     image_handle: Handle,
+    /// Messages of others after the read position
+    unread: i64,
+    /// New messages don't count as unread
+    muted: bool,
 }
 impl ChannelMenuBtn {
     pub fn new(channel_id: EventId) -> Self {
@@ -413,6 +827,8 @@ impl ChannelMenuBtn {
             channel_id,
             cache: None,
             image_handle: Handle::from_memory(default_channel_image(IMAGE_SIZE)),
+            unread: 0,
+            muted: false,
         }
     }
     pub fn with_cache(cache: ChannelCache) -> Self {
@@ -428,6 +844,8 @@ impl ChannelMenuBtn {
             channel_id: cache.channel_id,
             cache: Some(cache),
             image_handle,
+            unread: 0,
+            muted: false,
         }
     }
 
@@ -445,9 +863,16 @@ impl ChannelMenuBtn {
     }
 }
 
+pub struct HashtagMenuBtn {
+    hashtag: String,
+    /// Notes received while the feed wasn't open
+    unseen: usize,
+}
+
 const NAVBAR_WIDTH: u16 = 55;
 const PADDING_V: u16 = 6;
 const PADDING_H: u16 = 6;
 const ICON_SIZE: u16 = 26;
 
 const IMAGE_SIZE: ImageSize = ImageSize::Small;
+const UNREAD_TEXT_SIZE: u16 = 12;
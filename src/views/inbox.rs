@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+
+use iced::widget::{button, column, container, row, text, text_input, Space};
+use iced::{Alignment, Length};
+
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::config::Appearance;
+use crate::db::channel_keyword::find_keyword;
+use crate::error::BackendClosed;
+use crate::icon::{check_icon, reply_icon, send_icon};
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::types::{ChatMessage, InboxItem};
+use crate::utils::from_naive_utc_to_local;
+use crate::views::RouterCommand;
+use crate::widget::Element;
+
+use super::home::HomeGoTo;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ReplyChange(i64, String),
+    ReplySubmit(InboxItem),
+    OpenPressed(InboxItem),
+    SeenPressed(InboxItem),
+}
+
+pub struct State {
+    items: Vec<InboxItem>,
+    /// Quick reply inputs by event id
+    replies: HashMap<i64, String>,
+    loading: bool,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchInbox)?;
+        Ok(Self {
+            items: vec![],
+            replies: HashMap::new(),
+            loading: true,
+        })
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<Option<HomeGoTo>, BackendClosed> {
+        match message {
+            Message::ReplyChange(event_id, text) => {
+                self.replies.insert(event_id, text);
+            }
+            Message::ReplySubmit(item) => {
+                let Some(event_id) = item.event_id() else {
+                    return Ok(None);
+                };
+                let Some(reply) = self.replies.remove(&event_id) else {
+                    return Ok(None);
+                };
+                if reply.trim().is_empty() {
+                    return Ok(None);
+                }
+                match &item {
+                    InboxItem::DM { contact, .. } => {
+                        conn.send(ToBackend::SendDM(contact.to_owned(), reply))?
+                    }
+                    InboxItem::Mention { channel_id, .. } => {
+                        conn.send(ToBackend::SendChannelMessage(*channel_id, reply))?
+                    }
+                }
+                self.remove_item(event_id);
+                conn.send(ToBackend::InboxSeen(item))?;
+            }
+            Message::OpenPressed(item) => {
+                return Ok(Some(match item {
+                    InboxItem::DM { contact, .. } => HomeGoTo::Chat(contact),
+                    InboxItem::Mention { channel_id, .. } => HomeGoTo::ChannelId(channel_id),
+                }));
+            }
+            Message::SeenPressed(item) => {
+                if let Some(event_id) = item.event_id() {
+                    self.remove_item(event_id);
+                }
+                conn.send(ToBackend::InboxSeen(item))?;
+            }
+        }
+        Ok(None)
+    }
+
+    fn remove_item(&mut self, event_id: i64) {
+        self.items.retain(|i| i.event_id() != Some(event_id));
+        self.replies.remove(&event_id);
+    }
+
+    pub fn backend_event(
+        &mut self,
+        event: BackendEvent,
+        conn: &mut BackEndConnection,
+    ) -> Result<RouterCommand<Message>, BackendClosed> {
+        let commands = RouterCommand::new();
+
+        match event {
+            BackendEvent::GotInbox(items) => {
+                self.loading = false;
+                self.items = items;
+            }
+            BackendEvent::ReceivedDM { .. } | BackendEvent::ReceivedChannelMessage(..) => {
+                conn.send(ToBackend::FetchInbox)?;
+            }
+            _ => (),
+        }
+
+        Ok(commands)
+    }
+
+    pub fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        _appearance: Appearance,
+    ) -> Element<Message> {
+        let title = title("Inbox");
+
+        let content: Element<_> = if self.items.is_empty() {
+            let empty = if self.loading {
+                "Loading..."
+            } else {
+                "Nothing unread"
+            };
+            text(empty).style(style::Text::Placeholder).into()
+        } else {
+            self.items
+                .iter()
+                .fold(column![].spacing(10), |col, item| {
+                    col.push(self.item_view(item))
+                })
+                .into()
+        };
+
+        common_scrollable(
+            container(column![title, content].spacing(10))
+                .width(Length::Fill)
+                .padding([20, 20, 0, 20]),
+        )
+        .into()
+    }
+
+    fn item_view<'a>(&'a self, item: &'a InboxItem) -> Element<'a, Message> {
+        let message = item.message();
+        let time = item
+            .display_time()
+            .map(|t| from_naive_utc_to_local(*t).format(TIME_FORMAT).to_string())
+            .unwrap_or_default();
+        let author = match message {
+            ChatMessage::ContactMessage { display_name, .. } => display_name.to_owned(),
+            ChatMessage::UserMessage(_) => "You".into(),
+        };
+
+        let open_btn = button(text(item.source_name()))
+            .style(style::Button::Invisible)
+            .on_press(Message::OpenPressed(item.to_owned()));
+        let seen_btn = button(check_icon().size(16))
+            .style(style::Button::Invisible)
+            .on_press(Message::SeenPressed(item.to_owned()));
+        let header = row![
+            open_btn,
+            text(author).style(style::Text::Placeholder),
+            Space::with_width(Length::Fill),
+            text(time).size(14).style(style::Text::Alpha(0.5)),
+            seen_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10);
+
+        let reply = item
+            .event_id()
+            .and_then(|id| self.replies.get(&id))
+            .map(String::as_str)
+            .unwrap_or("");
+        let event_id = item.event_id().unwrap_or_default();
+        let reply_input = text_input("Reply", reply)
+            .on_input(move |text| Message::ReplyChange(event_id, text))
+            .on_submit(Message::ReplySubmit(item.to_owned()))
+            .width(Length::Fill);
+        let send_btn = button(send_icon().size(16))
+            .style(style::Button::MenuBtn)
+            .on_press(Message::ReplySubmit(item.to_owned()));
+        let reply_row = row![reply_icon().size(16), reply_input, send_btn]
+            .align_items(Alignment::Center)
+            .spacing(5);
+
+        container(column![header, content_view(item), reply_row].spacing(5))
+            .padding(10)
+            .width(Length::Fill)
+            .style(style::Container::Foreground)
+            .into()
+    }
+}
+
+/// Mentions found by a watched word show the text around it, word highlighted
+fn content_view(item: &InboxItem) -> Element<'_, Message> {
+    let content = item.message().content();
+    let (keyword, range) = match item {
+        InboxItem::Mention {
+            keyword: Some(keyword),
+            ..
+        } => match find_keyword(content, keyword) {
+            Some(range) => (keyword, range),
+            None => return text(content).into(),
+        },
+        _ => return text(content).into(),
+    };
+
+    let before = &content[..range.start];
+    let before = match before.char_indices().rev().nth(EXCERPT_CHARS) {
+        Some((idx, _)) => format!("…{}", &before[idx..]),
+        None => before.to_owned(),
+    };
+    let after = &content[range.end..];
+    let after = match after.char_indices().nth(EXCERPT_CHARS) {
+        Some((idx, _)) => format!("{}…", &after[..idx]),
+        None => after.to_owned(),
+    };
+
+    column![
+        text(format!("Watching \u{201c}{}\u{201d}", keyword))
+            .size(12)
+            .style(style::Text::Placeholder),
+        row![
+            text(before),
+            text(&content[range]).style(style::Text::Primary),
+            text(after)
+        ]
+    ]
+    .spacing(2)
+    .into()
+}
+
+const EXCERPT_CHARS: usize = 40;
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
@@ -0,0 +1,102 @@
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Length};
+use nostr::{prelude::FromSkStr, Keys};
+
+use crate::components::text::title;
+use crate::components::text_input_group::TextInputGroup;
+use crate::error::BackendClosed;
+use crate::net::{BackEndConnection, ToBackend};
+use crate::style;
+use crate::widget::Element;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SecretKeyInputChange(String),
+    UnlockPress,
+}
+
+/// Shown instead of the app after it was idle for too long
+pub struct State {
+    secret_key_input: String,
+    is_invalid: bool,
+    checking: bool,
+}
+impl State {
+    pub fn new() -> Self {
+        Self {
+            secret_key_input: "".into(),
+            is_invalid: false,
+            checking: false,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::SecretKeyInputChange(secret_key) => {
+                self.secret_key_input = secret_key;
+                self.is_invalid = false;
+            }
+            Message::UnlockPress => match Keys::from_sk_str(self.secret_key_input.trim()) {
+                // only the public key leaves the lock screen
+                Ok(keys) => {
+                    self.checking = true;
+                    conn.send(ToBackend::Unlock(keys.public_key()))?;
+                }
+                Err(_) => self.is_invalid = true,
+            },
+        }
+        Ok(())
+    }
+
+    pub fn unlock_failed(&mut self) {
+        self.checking = false;
+        self.is_invalid = true;
+        self.secret_key_input.clear();
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let info =
+            text("Locked after a while without activity. Enter your secret key to continue.")
+                .size(16)
+                .style(style::Text::Placeholder);
+
+        let mut secret_input = TextInputGroup::new(
+            "Secret Key",
+            &self.secret_key_input,
+            Message::SecretKeyInputChange,
+        )
+        .password();
+        if !self.checking {
+            secret_input = secret_input.on_submit(Message::UnlockPress);
+        }
+        if self.is_invalid {
+            secret_input = secret_input.invalid("Wrong Secret Key");
+        }
+
+        let mut unlock_btn = button("Unlock").padding(10).style(style::Button::Primary);
+        if !self.checking {
+            unlock_btn = unlock_btn.on_press(Message::UnlockPress);
+        }
+        let buttons = row![Space::with_width(Length::Fill), unlock_btn]
+            .align_items(Alignment::Center)
+            .spacing(10);
+
+        let form =
+            container(column![title("Locked"), info, secret_input.build(), buttons].spacing(20))
+                .width(400.0)
+                .padding(30)
+                .style(style::Container::Frame);
+
+        container(form)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .style(style::Container::Background)
+            .into()
+    }
+}
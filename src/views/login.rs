@@ -1,15 +1,17 @@
 use iced::{
     alignment,
     widget::{button, column, container, row, text, Space},
-    Alignment, Length,
+    Alignment, Command, Length,
 };
-use nostr::{prelude::FromSkStr, Keys};
+use nostr::{prelude::FromSkStr, Keys, Timestamp};
 
 use crate::{
     components::{text::title, text_input_group::TextInputGroup},
+    config::Appearance,
     error::BackendClosed,
     net::{BackEndConnection, BackendEvent, ToBackend},
     style,
+    types::{is_pairing_payload, open_pairing},
     widget::Element,
 };
 
@@ -47,11 +49,16 @@ pub enum Message {
     SubmitPress(String),
     ToCreateAccount,
     ToImportAccount,
+    ToPairDevice,
     ToChooseAccount,
     CreateAccountSubmit(BasicProfile),
     NameInputChange(String),
     AboutInputChange(String),
     ProfilePictureInputChange(String),
+    PairingPayloadChange(String),
+    PairingCodeChange(String),
+    PairSubmit,
+    PairOpened(Result<Keys, String>),
 }
 
 #[allow(dead_code)]
@@ -67,6 +74,12 @@ pub enum State {
         secret_key_input: String,
         is_invalid: bool,
     },
+    /// Payload and code shown by a device already logged in
+    Pair {
+        payload_input: String,
+        code_input: String,
+        error: Option<String>,
+    },
 }
 impl State {
     pub fn new() -> Self {
@@ -80,6 +93,13 @@ impl State {
             is_invalid: false,
         }
     }
+    pub fn pair_device() -> Self {
+        Self::Pair {
+            payload_input: "".into(),
+            code_input: "".into(),
+            error: None,
+        }
+    }
     pub fn create_account() -> Self {
         Self::Create {
             name: "".into(),
@@ -104,7 +124,7 @@ impl Route for State {
                 conn.send(ToBackend::QueryFirstLogin)?;
             }
             BackendEvent::FinishedPreparing => {
-                command.change_route(GoToView::Chat);
+                command.change_route(GoToView::Startup);
             }
             BackendEvent::FirstLoginSuccess => {
                 command.change_route(GoToView::Welcome);
@@ -123,12 +143,13 @@ impl Route for State {
         message: Message,
         conn: &mut BackEndConnection,
     ) -> Result<RouterCommand<Self::Message>, BackendClosed> {
-        let command = RouterCommand::new();
+        let mut command = RouterCommand::new();
 
         match self {
             State::Choose => match message {
                 Message::ToCreateAccount => *self = Self::create_account(),
                 Message::ToImportAccount => *self = Self::import_account(),
+                Message::ToPairDevice => *self = Self::pair_device(),
                 _ => (),
             },
             State::Create {
@@ -169,19 +190,57 @@ impl Route for State {
                 Message::ToChooseAccount => *self = Self::new(),
                 _ => (),
             },
+            State::Pair {
+                payload_input,
+                code_input,
+                error,
+            } => match message {
+                Message::PairingPayloadChange(payload) => {
+                    *payload_input = payload;
+                    *error = None;
+                }
+                Message::PairingCodeChange(code) => {
+                    *code_input = code;
+                    *error = None;
+                }
+                Message::PairSubmit => {
+                    // the key derivation takes a moment
+                    let (payload, code) = (payload_input.to_owned(), code_input.to_owned());
+                    command.push(Command::perform(
+                        async move {
+                            tokio::task::spawn_blocking(move || {
+                                open_pairing(&payload, &code, Timestamp::now())
+                                    .map_err(|e| e.to_string())
+                            })
+                            .await
+                            .unwrap_or_else(|e| Err(e.to_string()))
+                        },
+                        Message::PairOpened,
+                    ));
+                }
+                Message::PairOpened(Ok(keys)) => conn.send(ToBackend::LoginWithSK(keys))?,
+                Message::PairOpened(Err(e)) => *error = Some(e),
+                Message::ToChooseAccount => *self = Self::new(),
+                _ => (),
+            },
         }
 
         Ok(command)
     }
 
-    fn view(&self, _selected_theme: Option<style::Theme>) -> Element<Self::Message> {
+    fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        _appearance: Appearance,
+    ) -> Element<Self::Message> {
         let content: Element<_> = match self {
             State::Choose => {
                 let page_title = title("Sign In").center_x();
                 let create_acc_btn = big_button("Create Nostr Account", Message::ToCreateAccount);
                 let import_acc_btn = big_button("Import With Keys", Message::ToImportAccount);
+                let pair_btn = big_button("Pair With Another Device", Message::ToPairDevice);
 
-                let buttons = row![create_acc_btn, import_acc_btn]
+                let buttons = row![create_acc_btn, import_acc_btn, pair_btn]
                     .height(100.0)
                     .spacing(20)
                     .width(Length::Fill);
@@ -262,6 +321,55 @@ impl Route for State {
                     .spacing(20)
                     .into()
             }
+            State::Pair {
+                payload_input,
+                code_input,
+                error,
+            } => {
+                let info = text(
+                    "In Settings > Account on the other device, press \"Pair new device\" \
+                    and paste the payload of its QR here.",
+                )
+                .size(14)
+                .style(style::Text::Placeholder);
+                let mut payload_input = TextInputGroup::new(
+                    "Pairing payload",
+                    payload_input,
+                    Message::PairingPayloadChange,
+                )
+                .placeholder("nostrtalk-pair:...");
+                if !self.payload_looks_valid() {
+                    payload_input = payload_input.invalid("Not a pairing payload");
+                }
+                let mut code_input =
+                    TextInputGroup::new("Code", code_input, Message::PairingCodeChange)
+                        .placeholder("XXXX-XXXX")
+                        .on_submit(Message::PairSubmit);
+                if let Some(error) = error {
+                    code_input = code_input.invalid(error);
+                }
+
+                let back_btn = button("Back")
+                    .style(style::Button::Invisible)
+                    .padding(10)
+                    .on_press(Message::ToChooseAccount);
+                let submit_btn = button("Submit")
+                    .padding(10)
+                    .style(style::Button::Primary)
+                    .on_press(Message::PairSubmit);
+                let buttons = row![back_btn, Space::with_width(Length::Fill), submit_btn]
+                    .align_items(Alignment::Center)
+                    .spacing(10);
+                column![
+                    title("Pair Device"),
+                    info,
+                    payload_input.build(),
+                    code_input.build(),
+                    buttons
+                ]
+                .spacing(20)
+                .into()
+            }
         };
 
         let form = container(content)
@@ -279,6 +387,18 @@ impl Route for State {
     }
 }
 
+impl State {
+    /// An empty payload isn't flagged yet
+    fn payload_looks_valid(&self) -> bool {
+        match self {
+            State::Pair { payload_input, .. } => {
+                payload_input.trim().is_empty() || is_pairing_payload(payload_input)
+            }
+            _ => true,
+        }
+    }
+}
+
 fn big_button(title: &str, message: Message) -> Element<'static, Message> {
     button(
         container(
@@ -1,4 +1,5 @@
 use crate::components::inform_card;
+use crate::config::Appearance;
 use crate::error::BackendClosed;
 use crate::net::{BackEndConnection, BackendEvent};
 use crate::style;
@@ -32,7 +33,11 @@ impl Route for State {
         Ok(command)
     }
 
-    fn view(&self, _selected_theme: Option<style::Theme>) -> Element<'_, Self::Message> {
+    fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        _appearance: Appearance,
+    ) -> Element<'_, Self::Message> {
         inform_card("Logging out", "Please wait...")
     }
 }
@@ -1,20 +1,27 @@
 use iced::{Command, Subscription};
+use nostr::EventId;
 
 use crate::{
+    config::{Appearance, StartupView},
     db::DbContact,
     error::BackendClosed,
     net::{BackEndConnection, BackendEvent},
     style,
+    types::NostrLink,
     widget::Element,
 };
 
 use self::route::Route;
 
+mod activity;
 mod channel;
 mod chat;
 mod color_palettes;
 mod find_channels;
+mod hashtag;
 pub(crate) mod home;
+mod inbox;
+pub(crate) mod lock;
 pub(crate) mod login;
 mod logout;
 pub(crate) mod modal;
@@ -63,12 +70,19 @@ impl<M> RouterCommand<M> {
 
 pub enum GoToView {
     SettingsContacts,
+    /// View chosen in the settings, after the login
+    Startup,
     Chat,
     Channels,
     About,
     Network,
     Settings,
     ChatTo(DbContact),
+    Channel(EventId),
+    /// `nostr:` link pressed in some content
+    Link(NostrLink),
+    /// Feed of a hashtag, normalized
+    Hashtag(String),
     Welcome,
     Login,
     Logout,
@@ -86,15 +100,24 @@ pub enum Message {
 pub struct Router {
     previous_state: Option<ViewState>,
     state: ViewState,
+    /// `nostr:` link opened before the user logged in
+    pending_link: Option<NostrLink>,
+    /// Where the login leads
+    startup_view: StartupView,
 }
 impl Router {
-    pub fn new(conn: &mut BackEndConnection) -> Self {
+    pub fn new(conn: &mut BackEndConnection, startup_view: StartupView) -> Self {
         let (state, _command) = ViewState::login(conn);
         Self {
             previous_state: None,
             state,
+            pending_link: None,
+            startup_view,
         }
     }
+    pub fn set_startup_view(&mut self, startup_view: StartupView) {
+        self.startup_view = startup_view;
+    }
     fn next_state(&mut self, next: ViewState) {
         let old_state = std::mem::replace(&mut self.state, next);
         self.previous_state = Some(old_state);
@@ -106,15 +129,37 @@ impl Router {
         if let Some(s) = self.previous_state.take() {
             self.state = s;
         } else {
-            self.state = Self::new(conn).state;
+            self.state = Self::new(conn, self.startup_view).state;
         }
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
         self.state.subscription()
     }
-    pub fn view(&self, selected_theme: Option<style::Theme>) -> Element<Message> {
-        self.state.view(selected_theme)
+    pub fn view(
+        &self,
+        selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Message> {
+        self.state.view(selected_theme, appearance)
+    }
+
+    /// Opens the link in home, waiting for the login when needed
+    pub fn open_link(
+        &mut self,
+        link: NostrLink,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if let ViewState::Settings { .. } = self.state {
+            self.next_state(ViewState::chat(conn)?);
+        }
+        match &mut self.state {
+            ViewState::Home { state } => state.open_link(link, conn),
+            _ => {
+                self.pending_link = Some(link);
+                Ok(())
+            }
+        }
     }
 
     pub fn change_route(
@@ -128,15 +173,52 @@ impl Router {
             }),
             GoToView::Back => self.back(conn),
             GoToView::SettingsContacts => self.next_state(ViewState::settings_contacts(conn)?),
-            GoToView::Chat => self.next_state(ViewState::chat(conn)?),
+            GoToView::Startup => {
+                let state = match self.startup_view {
+                    // a link opened with the app goes first
+                    StartupView::LastChat if self.pending_link.is_none() => {
+                        ViewState::last_chat(conn)?
+                    }
+                    StartupView::LastChat | StartupView::ChatList => ViewState::chat(conn)?,
+                    StartupView::Channels => ViewState::channels(conn)?,
+                    StartupView::Inbox => ViewState::inbox(conn)?,
+                };
+                self.next_state(state);
+                if let Some(link) = self.pending_link.take() {
+                    self.open_link(link, conn)?;
+                }
+            }
+            GoToView::Chat => {
+                self.next_state(ViewState::chat(conn)?);
+                if let Some(link) = self.pending_link.take() {
+                    self.open_link(link, conn)?;
+                }
+            }
             GoToView::Channels => self.next_state(ViewState::channels(conn)?),
-            GoToView::About => self.next_state(ViewState::settings_about(conn)),
+            GoToView::About => self.next_state(ViewState::settings_about(conn)?),
             GoToView::Network => self.next_state(ViewState::settings_network(conn)?),
             GoToView::Settings => self.next_state(ViewState::settings(conn)?),
             GoToView::ChatTo(db_contact) => {
                 let state = ViewState::chat_contact(db_contact, conn)?;
                 self.next_state(state);
             }
+            GoToView::Link(link) => self.open_link(link, conn)?,
+            GoToView::Channel(channel_id) => {
+                if !matches!(self.state, ViewState::Home { .. }) {
+                    self.next_state(ViewState::chat(conn)?);
+                }
+                if let ViewState::Home { state } = &mut self.state {
+                    state.open_channel(channel_id, conn)?;
+                }
+            }
+            GoToView::Hashtag(hashtag) => {
+                if !matches!(self.state, ViewState::Home { .. }) {
+                    self.next_state(ViewState::chat(conn)?);
+                }
+                if let ViewState::Home { state } = &mut self.state {
+                    state.open_hashtag(hashtag, conn)?;
+                }
+            }
             GoToView::Login => {
                 let (state, command) = ViewState::login(conn);
                 self.next_state(state);
@@ -213,6 +295,16 @@ impl ViewState {
             state: home::State::chat(conn)?,
         })
     }
+    pub fn last_chat(conn: &mut BackEndConnection) -> Result<ViewState, BackendClosed> {
+        Ok(Self::Home {
+            state: home::State::last_chat(conn)?,
+        })
+    }
+    pub fn inbox(conn: &mut BackEndConnection) -> Result<ViewState, BackendClosed> {
+        Ok(Self::Home {
+            state: home::State::inbox(conn)?,
+        })
+    }
     pub fn channels(conn: &mut BackEndConnection) -> Result<ViewState, BackendClosed> {
         Ok(Self::Home {
             state: home::State::find_channels(conn)?,
@@ -228,10 +320,10 @@ impl ViewState {
             state: settings::Settings::network(conn)?,
         })
     }
-    pub fn settings_about(conn: &mut BackEndConnection) -> ViewState {
-        Self::Settings {
-            state: settings::Settings::about(conn),
-        }
+    pub fn settings_about(conn: &mut BackEndConnection) -> Result<ViewState, BackendClosed> {
+        Ok(Self::Settings {
+            state: settings::Settings::about(conn)?,
+        })
     }
     pub fn settings_contacts(conn: &mut BackEndConnection) -> Result<ViewState, BackendClosed> {
         Ok(Self::Settings {
@@ -250,13 +342,19 @@ impl Route for ViewState {
             _ => Subscription::none(),
         }
     }
-    fn view(&self, selected_theme: Option<style::Theme>) -> Element<'_, Self::Message> {
+    fn view(
+        &self,
+        selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<'_, Self::Message> {
         match self {
-            Self::Welcome { state } => state.view(selected_theme).map(map_welcome_msg),
-            Self::Home { state } => state.view(selected_theme).map(map_home_msg),
-            Self::Login { state } => state.view(selected_theme).map(map_login_msg),
-            Self::Logout { state } => state.view(selected_theme).map(map_logout_msg),
-            Self::Settings { state } => state.view(selected_theme).map(map_settings_msg),
+            Self::Welcome { state } => state.view(selected_theme, appearance).map(map_welcome_msg),
+            Self::Home { state } => state.view(selected_theme, appearance).map(map_home_msg),
+            Self::Login { state } => state.view(selected_theme, appearance).map(map_login_msg),
+            Self::Logout { state } => state.view(selected_theme, appearance).map(map_logout_msg),
+            Self::Settings { state } => {
+                state.view(selected_theme, appearance).map(map_settings_msg)
+            }
         }
     }
     fn backend_event(
@@ -1,18 +1,25 @@
 use std::fmt::Debug;
 
+use crate::components::text::rich_text;
 use crate::components::text_input_group::TextInputGroup;
-use crate::components::{card, common_scrollable};
+use crate::components::{card, common_scrollable, DimmedImage};
+use crate::config::{Appearance, TextDirection};
 use crate::consts::{MEDIUM_PROFILE_IMG_HEIGHT, MEDIUM_PROFILE_IMG_WIDTH, YMD_FORMAT};
-use crate::db::DbContact;
+use crate::db::{ChannelCache, DbContact, ProfileCache};
 use crate::error::BackendClosed;
 use crate::icon::{copy_icon, edit_icon};
-use crate::net::{self, BackEndConnection, BackendEvent, ImageSize};
+use crate::net::reqwest_client::{split_nip05, Nip05Profile};
+use crate::net::{self, BackEndConnection, BackendEvent, ImageSize, RequestId};
+use crate::types::{ContactOp, LightningAddress};
+use crate::utils::text_direction::visual_label;
 use crate::utils::{from_naive_utc_to_local, hide_string};
-use iced::widget::{button, column, container, image, row, text, tooltip, Space};
+use iced::widget::{button, checkbox, column, container, image, row, text, tooltip, Space};
 use iced::{alignment, clipboard};
 use iced::{Alignment, Command, Length};
 use iced_aw::Modal;
+use nostr::nips::nip19::Profile;
 use nostr::prelude::ToBech32;
+use nostr::{EventId, Url};
 
 use crate::style;
 use crate::widget::{Element, Rule};
@@ -25,28 +32,57 @@ pub enum Mode {
     Add,
 }
 
+/// Lookup of a name@domain typed in the public key input
+enum Nip05Lookup {
+    Idle,
+    Resolving(String),
+    Resolved {
+        address: String,
+        profile: Nip05Profile,
+        cache: Option<ProfileCache>,
+    },
+    Failed(String),
+}
+
 #[derive(Debug, Clone)]
 pub enum CMessage<M: Clone + Debug> {
     PetNameInputChange(String),
     PubKeyInputChange(String),
     RecRelayInputChange(String),
+    NotesInputChange(String),
     SubmitContact,
     CloseModal,
     EditMode,
     UnderlayMessage(M),
     CopyPubkey,
+    CopyNprofile,
     DeleteContact,
+    OpenChannel(EventId),
+    ToggleBlock,
+    MuteListToggled(bool),
+    TipPressed,
 }
 pub struct ContactDetails<M: Clone + Debug> {
     db_contact: Option<DbContact>,
     petname_input: String,
     pubkey_input: String,
     rec_relay_input: String,
+    notes_input: String,
     mode: Mode,
     is_pub_invalid: bool,
     is_relay_invalid: bool,
     profile_img_handle: Option<image::Handle>,
     pubkey_hidden: String,
+    /// With the request that sent it
+    submitted_op: Option<(RequestId, ContactOp)>,
+    relay_hints: Vec<Url>,
+    shared_channels: Vec<ChannelCache>,
+    is_blocked: bool,
+    /// Blocking also adds the contact to the public mute list
+    block_on_mute_list: bool,
+    nip05: Nip05Lookup,
+    /// Lightning address with the result of its check
+    lightning: Option<(LightningAddress, Option<bool>)>,
     phantom: std::marker::PhantomData<M>,
 }
 impl<M: Clone + Debug> ContactDetails<M> {
@@ -56,11 +92,19 @@ impl<M: Clone + Debug> ContactDetails<M> {
             petname_input: "".into(),
             pubkey_input: "".into(),
             rec_relay_input: "".into(),
+            notes_input: "".into(),
             mode: Mode::Add,
             is_pub_invalid: false,
             is_relay_invalid: false,
             profile_img_handle: None,
             pubkey_hidden: "".into(),
+            submitted_op: None,
+            relay_hints: vec![],
+            shared_channels: vec![],
+            is_blocked: false,
+            block_on_mute_list: false,
+            nip05: Nip05Lookup::Idle,
+            lightning: None,
             phantom: std::marker::PhantomData,
         }
     }
@@ -81,10 +125,18 @@ impl<M: Clone + Debug> ContactDetails<M> {
                 .get_relay_url()
                 .map(|url| url.to_string())
                 .unwrap_or("".into()),
+            notes_input: db_contact.get_notes().unwrap_or_default().to_owned(),
             mode: Mode::Edit,
             is_pub_invalid: false,
             is_relay_invalid: false,
-            profile_img_handle: Some(db_contact.profile_image(ImageSize::Medium, conn)?),
+            profile_img_handle: Some(db_contact.profile_image(PROFILE_IMAGE_SIZE, conn)?),
+            submitted_op: None,
+            relay_hints: vec![],
+            shared_channels: vec![],
+            is_blocked: false,
+            block_on_mute_list: false,
+            nip05: Nip05Lookup::Idle,
+            lightning: None,
             phantom: std::marker::PhantomData,
         })
     }
@@ -94,13 +146,80 @@ impl<M: Clone + Debug> ContactDetails<M> {
     ) -> Result<Self, BackendClosed> {
         let mut details = Self::edit(db_contact, conn)?;
         details.mode = Mode::View;
+        conn.send(net::ToBackend::FetchConversationRelays(
+            db_contact.to_owned(),
+        ))?;
+        conn.send(net::ToBackend::FetchSharedChannels(
+            db_contact.pubkey().to_owned(),
+        ))?;
+        conn.send(net::ToBackend::FetchBlocked)?;
+        if let Some(address) = db_contact
+            .get_profile_cache()
+            .and_then(|cache| LightningAddress::from_metadata(&cache.metadata))
+        {
+            conn.send(net::ToBackend::AutoCheckLightningAddress(
+                address.to_owned(),
+            ))?;
+            details.lightning = Some((address, None));
+        }
         Ok(details)
     }
 
+    /// Operation sent to the backend by the last update, if any
+    pub(crate) fn take_submitted_op(&mut self) -> Option<(RequestId, ContactOp)> {
+        self.submitted_op.take()
+    }
+
+    /// Public key of the resolved address while the input still holds it
+    fn resolved_pubkey(&self) -> Option<String> {
+        match &self.nip05 {
+            Nip05Lookup::Resolved {
+                address, profile, ..
+            } if address == &self.pubkey_input => Some(profile.pubkey.to_string()),
+            _ => None,
+        }
+    }
+
+    fn resolved(&mut self, address: String, profile: Nip05Profile, cache: Option<ProfileCache>) {
+        if !matches!(&self.nip05, Nip05Lookup::Resolving(a) if a == &address) {
+            return;
+        }
+        if self.rec_relay_input.is_empty() {
+            if let Some(relay) = profile.relays.first() {
+                self.rec_relay_input = relay.to_string();
+            }
+        }
+        self.fill_petname(cache.as_ref());
+        self.nip05 = Nip05Lookup::Resolved {
+            address,
+            profile,
+            cache,
+        };
+    }
+
+    fn fill_petname(&mut self, cache: Option<&ProfileCache>) {
+        if self.petname_input.is_empty() {
+            if let Some(name) = cache.and_then(|c| c.metadata.name.clone()) {
+                self.petname_input = name;
+            }
+        }
+    }
+
     pub(crate) fn handle_submit_contact(
         &mut self,
         conn: &mut BackEndConnection,
     ) -> Result<bool, BackendClosed> {
+        let is_address = self.db_contact.is_none() && split_nip05(&self.pubkey_input).is_some();
+        let resolved_pubkey = self.resolved_pubkey();
+        if is_address && resolved_pubkey.is_none() {
+            // the resolved profile is shown for confirmation before adding
+            if !matches!(self.nip05, Nip05Lookup::Resolving(_)) {
+                self.nip05 = Nip05Lookup::Resolving(self.pubkey_input.to_owned());
+                conn.send(net::ToBackend::ResolveNip05(self.pubkey_input.to_owned()))?;
+            }
+            return Ok(false);
+        }
+
         let submit_result = match &self.db_contact {
             Some(db_contact) => DbContact::edit_contact(
                 db_contact.to_owned(),
@@ -108,7 +227,7 @@ impl<M: Clone + Debug> ContactDetails<M> {
                 &self.rec_relay_input,
             ),
             None => DbContact::new_from_submit(
-                &self.pubkey_input,
+                resolved_pubkey.as_ref().unwrap_or(&self.pubkey_input),
                 &self.petname_input,
                 &self.rec_relay_input,
             ),
@@ -116,10 +235,19 @@ impl<M: Clone + Debug> ContactDetails<M> {
 
         match submit_result {
             Ok(db_contact) => {
+                let db_contact = db_contact.with_notes(&self.notes_input);
                 match self.mode {
-                    Mode::Edit => conn.send(net::ToBackend::UpdateContact(db_contact))?,
-                    Mode::Add => conn.send(net::ToBackend::AddContact(db_contact))?,
-                    Mode::View => Result::Ok(())?,
+                    Mode::Edit => {
+                        let request =
+                            conn.request(net::ToBackend::UpdateContact(db_contact.clone()))?;
+                        self.submitted_op = Some((request.id(), ContactOp::Update(db_contact)));
+                    }
+                    Mode::Add => {
+                        let request =
+                            conn.request(net::ToBackend::AddContact(db_contact.clone()))?;
+                        self.submitted_op = Some((request.id(), ContactOp::Add(db_contact)));
+                    }
+                    Mode::View => (),
                 }
 
                 // *self = Self::Off;
@@ -150,6 +278,7 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
     fn view<'a>(
         &'a self,
         underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
     ) -> Element<'a, Self::Message> {
         let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
 
@@ -168,10 +297,12 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
                         &self.pubkey_input,
                         CMessage::PubKeyInputChange,
                     )
-                    .placeholder("");
+                    .placeholder("npub, hex or name@domain");
 
                     if self.is_pub_invalid {
                         pubkey_input = pubkey_input.invalid("Invalid Public Key");
+                    } else if let Nip05Lookup::Failed(reason) = &self.nip05 {
+                        pubkey_input = pubkey_input.invalid(reason);
                     }
 
                     if let Mode::Edit = self.mode {
@@ -189,10 +320,16 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
                         rec_relay_input = rec_relay_input.invalid("Invalid Relay URL");
                     }
 
+                    let notes_input =
+                        TextInputGroup::new("Notes", &self.notes_input, CMessage::NotesInputChange)
+                            .placeholder("Only visible to you");
+
                     column![
                         pubkey_input.build(),
+                        nip05_preview(&self.nip05, appearance.text_direction),
                         petname_input.build(),
-                        rec_relay_input.build()
+                        rec_relay_input.build(),
+                        notes_input.build()
                     ]
                     .spacing(4)
                     .into()
@@ -224,13 +361,22 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
                         tooltip::Position::Top,
                     )
                     .style(style::Container::TooltipBg);
+                    let copy_nprofile_btn = tooltip(
+                        button(text("nprofile").size(14))
+                            .on_press(CMessage::CopyNprofile)
+                            .style(style::Button::MenuBtn),
+                        "Copy with relay hints",
+                        tooltip::Position::Top,
+                    )
+                    .style(style::Container::TooltipBg);
 
                     let pubkey_group = column![
                         text("Contact PubKey"),
                         container(
                             row![
                                 container(text(&self.pubkey_hidden)).width(Length::Fill),
-                                copy_btn
+                                copy_btn,
+                                copy_nprofile_btn
                             ]
                             .align_items(Alignment::Center)
                             .spacing(5)
@@ -246,11 +392,88 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
                             .style(style::Container::Frame),
                     ]
                     .spacing(2);
-                    let middle = column![pubkey_group, petname_group, relay_group].spacing(4);
+                    let notes_group: Element<_> = if self.notes_input.is_empty() {
+                        text("").into()
+                    } else {
+                        column![
+                            text("Notes"),
+                            container(text(&self.notes_input))
+                                .padding([2, 8])
+                                .style(style::Container::Frame),
+                        ]
+                        .spacing(2)
+                        .into()
+                    };
+                    let shared_channels =
+                        self.shared_channels
+                            .iter()
+                            .fold(column![].spacing(2), |col, channel| {
+                                let name = channel.metadata.name.clone().unwrap_or_else(|| {
+                                    hide_string(&channel.channel_id.to_string(), 8)
+                                });
+                                col.push(
+                                    button(text(format!("# {}", name)).size(14))
+                                        .on_press(CMessage::OpenChannel(channel.channel_id))
+                                        .style(style::Button::MenuBtn)
+                                        .width(Length::Fill),
+                                )
+                            });
+                    let shared_channels: Element<_> = if self.shared_channels.is_empty() {
+                        text("No channels in common")
+                            .size(14)
+                            .style(style::Text::Placeholder)
+                            .into()
+                    } else {
+                        shared_channels.into()
+                    };
+                    let channels_group =
+                        column![text("Channels in Common"), shared_channels].spacing(2);
+                    let lightning_group = lightning_row(self.lightning.as_ref());
+                    let block_group: Element<_> = if self.is_blocked {
+                        row![
+                            text("Blocked, new events are dropped")
+                                .size(14)
+                                .style(style::Text::Placeholder)
+                                .width(Length::Fill),
+                            button(text("Unblock").size(14))
+                                .style(style::Button::Bordered)
+                                .on_press(CMessage::ToggleBlock)
+                        ]
+                        .align_items(Alignment::Center)
+                        .spacing(5)
+                        .into()
+                    } else {
+                        row![
+                            checkbox(
+                                "Add to my public mute list",
+                                self.block_on_mute_list,
+                                CMessage::MuteListToggled
+                            )
+                            .size(16)
+                            .width(Length::Fill),
+                            button(text("Block").size(14))
+                                .style(style::Button::Danger)
+                                .on_press(CMessage::ToggleBlock)
+                        ]
+                        .align_items(Alignment::Center)
+                        .spacing(5)
+                        .into()
+                    };
+                    let middle = column![
+                        pubkey_group,
+                        petname_group,
+                        relay_group,
+                        notes_group,
+                        lightning_group,
+                        channels_group,
+                        block_group
+                    ]
+                    .spacing(4);
                     let profile_top = make_profile_top_row(
                         self.db_contact.as_ref(),
                         self.profile_img_handle.as_ref(),
                         CMessage::EditMode,
+                        appearance,
                     );
                     column![profile_top, middle,].spacing(4).into()
                 }
@@ -298,13 +521,67 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
         event: BackendEvent,
         conn: &mut BackEndConnection,
     ) -> Result<(), BackendClosed> {
-        if let BackendEvent::ImageDownloaded(image) = event {
-            if let Some(db_contact) = &self.db_contact {
-                if db_contact.get_profile_event_hash() == Some(image.event_hash) {
-                    self.profile_img_handle =
-                        Some(db_contact.profile_image(ImageSize::Medium, conn)?)
+        match event {
+            BackendEvent::ImageDownloaded(image) => {
+                if let Some(db_contact) = &self.db_contact {
+                    if db_contact.get_profile_event_hash() == Some(image.event_hash) {
+                        self.profile_img_handle =
+                            Some(db_contact.profile_image(PROFILE_IMAGE_SIZE, conn)?)
+                    }
+                }
+            }
+            BackendEvent::GotConversationRelays(pubkey, relays) => {
+                if self.db_contact.as_ref().map(|c| c.pubkey()) == Some(&pubkey) {
+                    self.relay_hints = relays;
+                }
+            }
+            BackendEvent::GotSharedChannels(pubkey, channels) => {
+                if self.db_contact.as_ref().map(|c| c.pubkey()) == Some(&pubkey) {
+                    self.shared_channels = channels;
+                }
+            }
+            BackendEvent::Nip05Resolved {
+                address,
+                profile,
+                cache,
+            } => {
+                self.resolved(address, profile, cache);
+            }
+            BackendEvent::Nip05Failed(address, reason) => {
+                if matches!(&self.nip05, Nip05Lookup::Resolving(a) if a == &address) {
+                    self.nip05 = Nip05Lookup::Failed(reason);
+                }
+            }
+            BackendEvent::UpdatedMetadata(pubkey) => {
+                if let Nip05Lookup::Resolved { profile, .. } = &self.nip05 {
+                    if profile.pubkey == pubkey {
+                        conn.send(net::ToBackend::FetchProfileCache(pubkey))?;
+                    }
                 }
             }
+            BackendEvent::GotProfileCache(pubkey, profile_cache) => {
+                if let Nip05Lookup::Resolved { profile, cache, .. } = &mut self.nip05 {
+                    if profile.pubkey == pubkey {
+                        *cache = Some(profile_cache.clone());
+                        self.fill_petname(Some(&profile_cache));
+                    }
+                }
+            }
+            BackendEvent::LightningChecked(address, valid) => {
+                if let Some((lightning, checked)) = &mut self.lightning {
+                    if lightning.as_str() == address {
+                        *checked = Some(valid);
+                    }
+                }
+            }
+            BackendEvent::GotBlocked(blocked) => {
+                if let Some(db_contact) = &self.db_contact {
+                    self.is_blocked = blocked
+                        .iter()
+                        .any(|(db_blocked, _)| &db_blocked.pubkey == db_contact.pubkey());
+                }
+            }
+            _ => (),
         }
         Ok(())
     }
@@ -318,13 +595,46 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
         match message {
             CMessage::DeleteContact => {
                 if let Some(contact) = &self.db_contact {
-                    conn.send(net::ToBackend::DeleteContact(contact.to_owned()))?;
+                    let request =
+                        conn.request(net::ToBackend::DeleteContact(contact.to_owned()))?;
+                    self.submitted_op = Some((request.id(), ContactOp::Delete(contact.to_owned())));
                 }
                 return Ok((command, true));
             }
+            CMessage::ToggleBlock => {
+                if let Some(contact) = &self.db_contact {
+                    let pubkey = contact.pubkey().to_owned();
+                    if self.is_blocked {
+                        conn.send(net::ToBackend::UnblockUser(pubkey))?;
+                    } else {
+                        conn.send(net::ToBackend::BlockUser(pubkey, self.block_on_mute_list))?;
+                        // the conversation is hidden once blocked
+                        return Ok((command, true));
+                    }
+                }
+            }
+            CMessage::TipPressed => {
+                if let Some((address, _)) = &self.lightning {
+                    if let Err(e) = webbrowser::open(&address.wallet_uri()) {
+                        tracing::error!("Failed to open wallet: {}", e);
+                    }
+                }
+            }
+            CMessage::MuteListToggled(on_mute_list) => {
+                self.block_on_mute_list = on_mute_list;
+            }
             CMessage::CopyPubkey => {
                 command = clipboard::write(self.pubkey_input.to_owned());
             }
+            CMessage::CopyNprofile => {
+                if let Some(db_contact) = &self.db_contact {
+                    let relays = self.relay_hints.iter().map(|url| url.to_string()).collect();
+                    match Profile::new(db_contact.pubkey().to_owned(), relays).to_bech32() {
+                        Ok(nprofile) => command = clipboard::write(nprofile),
+                        Err(e) => tracing::error!("{}", e),
+                    }
+                }
+            }
             CMessage::EditMode => {
                 if let Mode::View = self.mode {
                     self.mode = Mode::Edit;
@@ -336,16 +646,22 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
             CMessage::PubKeyInputChange(text) => {
                 self.pubkey_input = text;
                 self.is_pub_invalid = false;
+                if let Nip05Lookup::Failed(_) = self.nip05 {
+                    self.nip05 = Nip05Lookup::Idle;
+                }
             }
             CMessage::RecRelayInputChange(text) => {
                 self.rec_relay_input = text;
                 self.is_relay_invalid = false;
             }
+            CMessage::NotesInputChange(text) => {
+                self.notes_input = text;
+            }
             CMessage::SubmitContact => {
                 let is_close = self.handle_submit_contact(conn)?;
                 return Ok((command, is_close));
             }
-            CMessage::CloseModal => {
+            CMessage::CloseModal | CMessage::OpenChannel(_) => {
                 return Ok((command, true));
             }
             CMessage::UnderlayMessage(_) => (),
@@ -355,15 +671,107 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ContactDetails<M> {
     }
 }
 
+fn lightning_row<'a, M: 'a + Clone + Debug>(
+    lightning: Option<&(LightningAddress, Option<bool>)>,
+) -> Element<'a, CMessage<M>> {
+    let Some((address, valid)) = lightning else {
+        return column![
+            text("Lightning"),
+            text("No lightning address")
+                .size(14)
+                .style(style::Text::Placeholder)
+        ]
+        .spacing(2)
+        .into();
+    };
+    let (status, tip_btn) = match valid {
+        Some(false) => (
+            "Not reachable",
+            button(text("⚡ tip").size(14)).style(style::Button::Bordered),
+        ),
+        Some(true) => (
+            "",
+            button(text("⚡ tip").size(14))
+                .style(style::Button::Primary)
+                .on_press(CMessage::TipPressed),
+        ),
+        None => (
+            "Checking...",
+            button(text("⚡ tip").size(14))
+                .style(style::Button::Primary)
+                .on_press(CMessage::TipPressed),
+        ),
+    };
+    column![
+        text("Lightning"),
+        row![
+            text(address.to_string()).size(14).width(Length::Fill),
+            text(status).size(14).style(style::Text::Placeholder),
+            tip_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(5)
+    ]
+    .spacing(2)
+    .into()
+}
+
+fn nip05_preview<'a, M: 'a + Clone>(
+    lookup: &Nip05Lookup,
+    text_direction: TextDirection,
+) -> Element<'a, M> {
+    match lookup {
+        Nip05Lookup::Idle | Nip05Lookup::Failed(_) => text("").into(),
+        Nip05Lookup::Resolving(address) => text(format!("Looking up {}...", address))
+            .size(14)
+            .style(style::Text::Placeholder)
+            .into(),
+        Nip05Lookup::Resolved {
+            address,
+            profile,
+            cache,
+        } => {
+            let metadata = cache.as_ref().map(|c| &c.metadata);
+            let name = metadata
+                .and_then(|m| m.display_name.clone().or(m.name.clone()))
+                .unwrap_or_else(|| "Loading profile...".into());
+            let about = metadata.and_then(|m| m.about.clone()).unwrap_or_default();
+            let pubkey = profile
+                .pubkey
+                .to_bech32()
+                .unwrap_or(profile.pubkey.to_string());
+            container(
+                column![
+                    text(name).size(18),
+                    text(format!("{} is {}", address, hide_string(&pubkey, 16))).size(14),
+                    rich_text(&about).size(14).text_direction(text_direction),
+                    text(format!(
+                        "{} relay hints, press Ok to add",
+                        profile.relays.len()
+                    ))
+                    .size(14)
+                    .style(style::Text::Placeholder),
+                ]
+                .spacing(2),
+            )
+            .padding(5)
+            .width(Length::Fill)
+            .style(style::Container::Foreground)
+            .into()
+        }
+    }
+}
+
 fn make_profile_top_row<'a, M: 'a + Clone>(
     db_contact: Option<&'a DbContact>,
     img_handle: Option<&image::Handle>,
     edit_press: M,
+    appearance: Appearance,
 ) -> Element<'a, M> {
     if let Some(contact) = db_contact {
         if let Some(profile) = contact.get_profile_cache() {
             let image_container: Element<_> = if let Some(handle) = img_handle {
-                image(handle.to_owned()).into()
+                DimmedImage::new(handle.to_owned(), appearance.dim_images).into()
             } else {
                 text("No image").into()
             };
@@ -374,16 +782,28 @@ fn make_profile_top_row<'a, M: 'a + Clone>(
 
             let profile_name_group = column![
                 text("Profile Name"),
-                container(text(profile.metadata.name.unwrap_or("".into())))
-                    .padding([2, 8])
-                    .style(style::Container::Frame),
+                container(text(
+                    visual_label(
+                        &profile.metadata.name.unwrap_or_default(),
+                        appearance.text_direction,
+                    )
+                    .into_owned()
+                ))
+                .padding([2, 8])
+                .style(style::Container::Frame),
             ]
             .spacing(2);
             let profile_username_group = column![
                 text("Profile Username"),
-                container(text(profile.metadata.display_name.unwrap_or("".into())))
-                    .padding([2, 8])
-                    .style(style::Container::Frame),
+                container(text(
+                    visual_label(
+                        &profile.metadata.display_name.unwrap_or_default(),
+                        appearance.text_direction,
+                    )
+                    .into_owned()
+                ))
+                .padding([2, 8])
+                .style(style::Container::Frame),
             ]
             .spacing(2);
 
@@ -432,3 +852,5 @@ fn make_profile_top_row<'a, M: 'a + Clone>(
 
 const MODAL_WIDTH: f32 = 500.0;
 const COPY_BTN_WIDTH: f32 = 30.0;
+/// The modal is the only place showing the full profile picture
+const PROFILE_IMAGE_SIZE: ImageSize = ImageSize::Original;
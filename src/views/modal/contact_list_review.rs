@@ -0,0 +1,165 @@
+use crate::components::{card, common_scrollable};
+use crate::config::Appearance;
+use crate::db::DbContact;
+use crate::error::BackendClosed;
+use crate::net::{self, BackEndConnection, BackendEvent};
+use crate::style;
+use crate::types::ContactListDiff;
+use crate::utils::hide_string;
+use crate::widget::Element;
+use iced::alignment;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Command, Length};
+use iced_aw::Modal;
+use nostr::prelude::ToBech32;
+use std::fmt::Debug;
+
+use super::ModalView;
+
+#[derive(Debug, Clone)]
+pub enum CMessage<M: Clone + Debug> {
+    CloseModal,
+    Publish,
+    UnderlayMessage(M),
+}
+
+pub struct ContactListReview<M: Clone + Debug> {
+    diff: ContactListDiff,
+    phantom: std::marker::PhantomData<M>,
+}
+impl<M: Clone + Debug> ContactListReview<M> {
+    pub fn new(diff: ContactListDiff) -> Self {
+        Self {
+            diff,
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<M: Clone + Debug + 'static + Send> ModalView for ContactListReview<M> {
+    type UnderlayMessage = M;
+    type Message = CMessage<M>;
+
+    fn backend_event(
+        &mut self,
+        event: BackendEvent,
+        _conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if let BackendEvent::GotContactListDiff(diff) = event {
+            self.diff = diff;
+        }
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(Command<Self::Message>, bool), BackendClosed> {
+        let command = Command::none();
+        match message {
+            CMessage::UnderlayMessage(_) => (),
+            CMessage::CloseModal => return Ok((command, true)),
+            CMessage::Publish => {
+                conn.send(net::ToBackend::PublishContactList)?;
+                return Ok((command, true));
+            }
+        }
+        Ok((command, false))
+    }
+
+    fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
+    ) -> Element<'a, Self::Message> {
+        let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
+        Modal::new(true, underlay_component, move || {
+            let title = container(text("Pending contact list changes").size(22)).center_x();
+
+            let added = self
+                .diff
+                .added
+                .iter()
+                .fold(column![].spacing(5), |col, contact| {
+                    col.push(make_change_row("+", contact, contact_name(contact)))
+                });
+            let removed = self
+                .diff
+                .removed
+                .iter()
+                .fold(column![].spacing(5), |col, contact| {
+                    col.push(make_change_row("-", contact, contact_name(contact)))
+                });
+            let renamed =
+                self.diff
+                    .renamed
+                    .iter()
+                    .fold(column![].spacing(5), |col, (published, local)| {
+                        let change = format!(
+                            "{} → {}",
+                            published.get_petname().unwrap_or_default(),
+                            local.get_petname().unwrap_or_default()
+                        );
+                        col.push(make_change_row("~", local, change))
+                    });
+
+            let content: Element<_> = if self.diff.is_empty() {
+                text("Nothing to publish")
+                    .style(style::Text::Placeholder)
+                    .into()
+            } else {
+                column![added, removed, renamed].spacing(5).into()
+            };
+
+            let card_body = common_scrollable(
+                container(column![title, content].spacing(15))
+                    .center_x()
+                    .padding(20),
+            );
+
+            let mut publish_btn =
+                button(text("Publish").horizontal_alignment(alignment::Horizontal::Center))
+                    .width(Length::Fill);
+            if !self.diff.is_empty() {
+                publish_btn = publish_btn.on_press(CMessage::Publish);
+            }
+            let card_footer = row![
+                button(text("Cancel").horizontal_alignment(alignment::Horizontal::Center))
+                    .width(Length::Fill)
+                    .style(style::Button::Bordered)
+                    .on_press(CMessage::CloseModal),
+                publish_btn
+            ]
+            .spacing(10);
+
+            card(card_body, card_footer).max_width(MODAL_WIDTH).into()
+        })
+        .backdrop(CMessage::CloseModal)
+        .on_esc(CMessage::CloseModal)
+        .into()
+    }
+}
+
+fn contact_name(contact: &DbContact) -> String {
+    contact.get_petname().unwrap_or_default()
+}
+
+fn make_change_row<'a, M: 'a>(sign: &str, contact: &DbContact, change: String) -> Element<'a, M> {
+    let pubkey = contact
+        .pubkey()
+        .to_bech32()
+        .unwrap_or(contact.pubkey().to_string());
+    row![
+        text(sign).width(Length::Fixed(SIGN_WIDTH)),
+        text(hide_string(&pubkey, 6)),
+        Space::with_width(Length::Fill),
+        text(change).style(style::Text::Placeholder),
+    ]
+    .spacing(5)
+    .padding(5)
+    .into()
+}
+
+const MODAL_WIDTH: f32 = 400.0;
+const SIGN_WIDTH: f32 = 15.0;
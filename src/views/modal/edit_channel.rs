@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use iced::alignment;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Command, Length};
+use iced_aw::Modal;
+use nostr::prelude::ToBech32;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::EventId;
+
+use crate::components::text_input_group::TextInputGroup;
+use crate::components::{card, common_scrollable};
+use crate::config::Appearance;
+use crate::db::ChannelCache;
+use crate::error::BackendClosed;
+use crate::icon::delete_icon;
+use crate::net::{BackEndConnection, ToBackend};
+use crate::style;
+use crate::types::ChannelMetadata;
+use crate::utils::{hide_string, parse_key};
+use crate::widget::{Element, Rule};
+
+use super::ModalView;
+
+#[derive(Debug, Clone)]
+pub enum CMessage<M: Clone + Debug> {
+    NameInputChange(String),
+    AboutInputChange(String),
+    PictureInputChange(String),
+    ModeratorInputChange(String),
+    AddModerator,
+    RemoveModerator(XOnlyPublicKey),
+    Save,
+    CloseModal,
+    UnderlayMessage(M),
+}
+
+/// Metadata and co-moderators of a channel created by the user,
+/// saved together as a new kind 41
+pub struct EditChannel<M: Clone + Debug> {
+    channel_id: EventId,
+    name_input: String,
+    about_input: String,
+    picture_input: String,
+    /// Kept as is, the channel menu changes it
+    announcement: Option<String>,
+    moderators: Vec<XOnlyPublicKey>,
+    moderator_input: String,
+    is_moderator_invalid: bool,
+    /// Members' names, other moderators show their key
+    names: HashMap<XOnlyPublicKey, String>,
+    phantom: std::marker::PhantomData<M>,
+}
+impl<M: Clone + Debug> EditChannel<M> {
+    pub fn new(cache: &ChannelCache, names: HashMap<XOnlyPublicKey, String>) -> Self {
+        let metadata = &cache.metadata;
+        Self {
+            channel_id: cache.channel_id,
+            name_input: metadata.name.clone().unwrap_or_default(),
+            about_input: metadata.about.clone().unwrap_or_default(),
+            picture_input: metadata.picture.clone().unwrap_or_default(),
+            announcement: metadata.announcement.clone(),
+            moderators: cache.moderators.clone(),
+            moderator_input: "".into(),
+            is_moderator_invalid: false,
+            names,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn add_moderator(&mut self) {
+        let pubkey = parse_key(self.moderator_input.trim().to_owned())
+            .ok()
+            .and_then(|hex| XOnlyPublicKey::from_str(&hex).ok());
+        match pubkey {
+            Some(pubkey) => {
+                if !self.moderators.contains(&pubkey) {
+                    self.moderators.push(pubkey);
+                }
+                self.moderator_input.clear();
+            }
+            None => self.is_moderator_invalid = true,
+        }
+    }
+
+    fn metadata(&self) -> ChannelMetadata {
+        let non_empty = |input: &str| {
+            let input = input.trim();
+            (!input.is_empty()).then(|| input.to_owned())
+        };
+        ChannelMetadata {
+            name: non_empty(&self.name_input),
+            about: non_empty(&self.about_input),
+            picture: non_empty(&self.picture_input),
+            announcement: self.announcement.clone(),
+        }
+    }
+
+    fn moderator_name(&self, pubkey: &XOnlyPublicKey) -> String {
+        self.names.get(pubkey).cloned().unwrap_or_else(|| {
+            let npub = pubkey.to_bech32().unwrap_or(pubkey.to_string());
+            hide_string(&npub, 12)
+        })
+    }
+
+    fn moderators_section(&self) -> Element<'_, CMessage<M>> {
+        let info = text("Messages hidden and users muted by a moderator are removed like yours")
+            .size(14)
+            .style(style::Text::Placeholder);
+
+        let list: Element<_> = if self.moderators.is_empty() {
+            text("No moderators").style(style::Text::Placeholder).into()
+        } else {
+            self.moderators
+                .iter()
+                .fold(column![].spacing(5), |col, pubkey| {
+                    col.push(
+                        row![
+                            text(self.moderator_name(pubkey)),
+                            Space::with_width(Length::Fill),
+                            button(delete_icon().size(16))
+                                .style(style::Button::Invisible)
+                                .on_press(CMessage::RemoveModerator(pubkey.to_owned()))
+                        ]
+                        .align_items(Alignment::Center)
+                        .spacing(5),
+                    )
+                })
+                .into()
+        };
+
+        let mut moderator_input = TextInputGroup::new(
+            "Add moderator",
+            &self.moderator_input,
+            CMessage::ModeratorInputChange,
+        )
+        .placeholder("npub or hex public key")
+        .on_submit(CMessage::AddModerator);
+        if self.is_moderator_invalid {
+            moderator_input = moderator_input.invalid("Invalid Public Key");
+        }
+
+        column![
+            text("Moderators").size(18),
+            info,
+            list,
+            moderator_input.build()
+        ]
+        .spacing(10)
+        .into()
+    }
+}
+
+impl<M: Clone + Debug + 'static + Send> ModalView for EditChannel<M> {
+    type UnderlayMessage = M;
+    type Message = CMessage<M>;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(Command<Self::Message>, bool), BackendClosed> {
+        let command = Command::none();
+        match message {
+            CMessage::NameInputChange(text) => self.name_input = text,
+            CMessage::AboutInputChange(text) => self.about_input = text,
+            CMessage::PictureInputChange(text) => self.picture_input = text,
+            CMessage::ModeratorInputChange(text) => {
+                self.moderator_input = text;
+                self.is_moderator_invalid = false;
+            }
+            CMessage::AddModerator => self.add_moderator(),
+            CMessage::RemoveModerator(pubkey) => self.moderators.retain(|m| m != &pubkey),
+            CMessage::Save => {
+                conn.send(ToBackend::UpdateChannel(
+                    self.channel_id,
+                    self.metadata(),
+                    self.moderators.clone(),
+                ))?;
+                return Ok((command, true));
+            }
+            CMessage::UnderlayMessage(_) => (),
+            CMessage::CloseModal => return Ok((command, true)),
+        }
+        Ok((command, false))
+    }
+
+    fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
+    ) -> Element<'a, Self::Message> {
+        let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
+        Modal::new(true, underlay_component, move || {
+            let title = container(text("Edit channel").size(22)).center_x();
+            let name_input =
+                TextInputGroup::new("Name", &self.name_input, CMessage::NameInputChange);
+            let about_input =
+                TextInputGroup::new("About", &self.about_input, CMessage::AboutInputChange);
+            let picture_input = TextInputGroup::new(
+                "Picture URL",
+                &self.picture_input,
+                CMessage::PictureInputChange,
+            );
+
+            let card_body = common_scrollable(
+                container(
+                    column![
+                        title,
+                        name_input.build(),
+                        about_input.build(),
+                        picture_input.build(),
+                        Rule::horizontal(2),
+                        self.moderators_section()
+                    ]
+                    .spacing(15),
+                )
+                .padding(20),
+            );
+
+            let card_footer = row![
+                button(text("Cancel").horizontal_alignment(alignment::Horizontal::Center))
+                    .style(style::Button::Bordered)
+                    .width(Length::Fill)
+                    .on_press(CMessage::CloseModal),
+                button(text("Save").horizontal_alignment(alignment::Horizontal::Center))
+                    .style(style::Button::Primary)
+                    .width(Length::Fill)
+                    .on_press(CMessage::Save)
+            ]
+            .spacing(10)
+            .width(Length::Fill);
+
+            card(card_body, card_footer).max_width(MODAL_WIDTH).into()
+        })
+        .backdrop(CMessage::CloseModal)
+        .on_esc(CMessage::CloseModal)
+        .into()
+    }
+}
+
+const MODAL_WIDTH: f32 = 420.0;
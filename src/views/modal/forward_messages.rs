@@ -0,0 +1,130 @@
+use crate::components::{card, common_scrollable};
+use crate::config::Appearance;
+use crate::db::DbContact;
+use crate::net::BackEndConnection;
+use crate::style;
+use crate::widget::Element;
+use iced::alignment;
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{Command, Length};
+use iced_aw::Modal;
+use std::fmt::Debug;
+
+use super::ModalView;
+
+#[derive(Debug, Clone)]
+pub enum CMessage<M: Clone + Debug> {
+    CloseModal,
+    UnderlayMessage(M),
+    SearchChange(String),
+    /// Handled by the view that opened the modal
+    ForwardTo(DbContact),
+}
+
+/// Picks the contact the selected messages are forwarded to
+pub struct ForwardMessages<M: Clone + Debug> {
+    contacts: Vec<DbContact>,
+    /// Number of selected messages
+    count: usize,
+    search: String,
+    phantom: std::marker::PhantomData<M>,
+}
+impl<M: Clone + Debug> ForwardMessages<M> {
+    pub fn new(contacts: Vec<DbContact>, count: usize) -> Self {
+        Self {
+            contacts,
+            count,
+            search: String::new(),
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn contact_list(&self) -> Element<'_, CMessage<M>> {
+        let search = self.search.to_lowercase();
+        let matches: Vec<_> = self
+            .contacts
+            .iter()
+            .filter(|contact| contact.select_name().to_lowercase().contains(&search))
+            .collect();
+        if matches.is_empty() {
+            return text("No contacts found")
+                .style(style::Text::Placeholder)
+                .into();
+        }
+        matches
+            .into_iter()
+            .fold(column![].spacing(2), |col, contact| {
+                col.push(
+                    button(text(contact.select_name()))
+                        .width(Length::Fill)
+                        .style(style::Button::ContactCard)
+                        .on_press(CMessage::ForwardTo(contact.to_owned())),
+                )
+            })
+            .into()
+    }
+}
+
+impl<M: Clone + Debug + 'static + Send> ModalView for ForwardMessages<M> {
+    type UnderlayMessage = M;
+    type Message = CMessage<M>;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(Command<Self::Message>, bool), crate::error::BackendClosed> {
+        let command = Command::none();
+        match message {
+            CMessage::UnderlayMessage(_) => (),
+            CMessage::SearchChange(search) => self.search = search,
+            CMessage::CloseModal | CMessage::ForwardTo(_) => return Ok((command, true)),
+        }
+        Ok((command, false))
+    }
+
+    fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
+    ) -> Element<'a, Self::Message> {
+        let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
+        Modal::new(true, underlay_component, move || {
+            let title = container(
+                text(format!(
+                    "Forward {} message{}",
+                    self.count,
+                    if self.count == 1 { "" } else { "s" }
+                ))
+                .size(22),
+            )
+            .center_x();
+            let search_input =
+                text_input("Search contacts", &self.search).on_input(CMessage::SearchChange);
+            let contacts = container(common_scrollable(self.contact_list()))
+                .height(LIST_HEIGHT)
+                .width(Length::Fill);
+
+            let card_body = container(column![title, search_input, contacts].spacing(15))
+                .center_x()
+                .padding(20);
+
+            let card_footer =
+                row![
+                    button(text("Cancel").horizontal_alignment(alignment::Horizontal::Center))
+                        .width(Length::Fill)
+                        .style(style::Button::Bordered)
+                        .on_press(CMessage::CloseModal)
+                ]
+                .spacing(10);
+
+            card(card_body, card_footer).max_width(MODAL_WIDTH).into()
+        })
+        .backdrop(CMessage::CloseModal)
+        .on_esc(CMessage::CloseModal)
+        .into()
+    }
+}
+
+const MODAL_WIDTH: f32 = 420.0;
+const LIST_HEIGHT: f32 = 300.0;
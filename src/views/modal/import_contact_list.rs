@@ -1,12 +1,14 @@
-use crate::components::{async_file_importer, card, AsyncFileImporter};
+use crate::components::{async_file_importer, card, common_scrollable, AsyncFileImporter};
+use crate::config::Appearance;
 use crate::db::DbContact;
 use crate::error::BackendClosed;
 use crate::net::{self, BackEndConnection, BackendEvent};
 use crate::style;
+use crate::types::{ContactCsv, CsvColumn};
 use crate::widget::Element;
 use crate::{types::UncheckedEvent, utils::json_reader};
 use iced::alignment;
-use iced::widget::{button, column, row, text};
+use iced::widget::{button, column, container, row, text};
 use iced::Command;
 use iced::Length;
 use iced_aw::Modal;
@@ -22,11 +24,21 @@ pub enum CMessage<M: Clone + Debug> {
     UnderlayMessage(M),
     FileImporterMessage(async_file_importer::Message),
     SaveImportedContacts(Vec<DbContact>),
+    MapColumn(usize, CsvColumn),
+}
+
+/// Spreadsheet being read, each column is mapped to a contact field
+struct CsvImport {
+    csv: ContactCsv,
+    mapping: Vec<CsvColumn>,
+    /// Public keys and relays that couldn't be read
+    invalid: Vec<String>,
 }
 
 pub struct ImportContactList<M: Clone + Debug> {
     pub imported_contacts: Vec<DbContact>,
     pub file_importer: AsyncFileImporter,
+    csv_import: Option<CsvImport>,
     phantom: std::marker::PhantomData<M>,
 }
 impl<M: Clone + Debug> ImportContactList<M> {
@@ -34,7 +46,8 @@ impl<M: Clone + Debug> ImportContactList<M> {
         Self {
             imported_contacts: vec![],
             file_importer: AsyncFileImporter::new("/path/to/contacts.json")
-                .file_filter("JSON File", &["json"]),
+                .file_filter("Contact list", &["json", "csv"]),
+            csv_import: None,
             phantom: std::marker::PhantomData,
         }
     }
@@ -43,6 +56,28 @@ impl<M: Clone + Debug> ImportContactList<M> {
     where
         P: AsRef<Path>,
     {
+        self.imported_contacts.clear();
+        self.csv_import = None;
+        let is_csv = path
+            .as_ref()
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("csv"));
+        if is_csv {
+            match std::fs::read_to_string(path) {
+                Ok(content) => {
+                    let csv = ContactCsv::parse(&content);
+                    let mapping = csv.guess_mapping();
+                    self.csv_import = Some(CsvImport {
+                        csv,
+                        mapping,
+                        invalid: vec![],
+                    });
+                    self.update_csv_contacts();
+                }
+                Err(e) => tracing::error!("{}", e),
+            }
+            return;
+        }
         match json_reader::<P, UncheckedEvent>(path) {
             Ok(contact_event) => {
                 if let nostr::event::Kind::ContactList = contact_event.kind {
@@ -66,6 +101,69 @@ impl<M: Clone + Debug> ImportContactList<M> {
 
         self.imported_contacts = oks.into_iter().map(Result::unwrap).collect();
     }
+
+    fn update_csv_contacts(&mut self) {
+        if let Some(csv_import) = &mut self.csv_import {
+            let (contacts, invalid) = csv_import.csv.contacts(&csv_import.mapping);
+            self.imported_contacts = contacts;
+            csv_import.invalid = invalid;
+        }
+    }
+}
+
+impl CsvImport {
+    fn view<'a, M: Clone + Debug + 'a>(&'a self) -> Element<'a, CMessage<M>> {
+        let sample = self.csv.records.first();
+        let columns =
+            self.csv
+                .headers
+                .iter()
+                .enumerate()
+                .fold(column![].spacing(8), |col, (idx, header)| {
+                    let selected = self.mapping.get(idx).copied().unwrap_or(CsvColumn::Skip);
+                    let choices =
+                        CsvColumn::ALL
+                            .iter()
+                            .fold(row![].spacing(5), |choices, column| {
+                                let style = if *column == selected {
+                                    style::Button::Primary
+                                } else {
+                                    style::Button::Bordered
+                                };
+                                choices.push(
+                                    button(text(column.to_string()).size(14))
+                                        .padding([2, 8])
+                                        .style(style)
+                                        .on_press(CMessage::MapColumn(idx, *column)),
+                                )
+                            });
+                    let example = sample
+                        .and_then(|record| record.get(idx))
+                        .map(|value| value.as_str())
+                        .unwrap_or_default();
+                    col.push(column![
+                        row![
+                            text(header),
+                            text(example).size(14).style(style::Text::Placeholder)
+                        ]
+                        .spacing(10),
+                        choices
+                    ])
+                });
+        let invalid = self
+            .invalid
+            .iter()
+            .fold(column![].spacing(2), |col, entry| {
+                col.push(
+                    text(format!("Skipped: {}", entry))
+                        .size(14)
+                        .style(style::Text::Danger),
+                )
+            });
+        container(common_scrollable(column![columns, invalid].spacing(10)))
+            .max_height(MAPPING_HEIGHT)
+            .into()
+    }
 }
 
 impl<M: Clone + Debug + 'static + Send> ModalView for ImportContactList<M> {
@@ -104,6 +202,20 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ImportContactList<M> {
                 return Ok((command, true));
             }
             CMessage::FileImporterMessage(msg) => self.file_importer.update(msg, conn)?,
+            CMessage::MapColumn(idx, column) => {
+                if let Some(csv_import) = &mut self.csv_import {
+                    // a field is read from one column only
+                    for mapped in csv_import.mapping.iter_mut() {
+                        if column != CsvColumn::Skip && *mapped == column {
+                            *mapped = CsvColumn::Skip;
+                        }
+                    }
+                    if let Some(mapped) = csv_import.mapping.get_mut(idx) {
+                        *mapped = column;
+                    }
+                }
+                self.update_csv_contacts();
+            }
         }
 
         Ok((command, false))
@@ -112,6 +224,7 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ImportContactList<M> {
     fn view<'a>(
         &'a self,
         underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
     ) -> Element<'a, Self::Message> {
         let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
 
@@ -123,7 +236,11 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ImportContactList<M> {
             };
             let stats_row = row![found_contacts_txt];
 
-            let card_body = column![importer_cp, stats_row].spacing(4).padding(20);
+            let mut card_body = column![importer_cp].spacing(4).padding(20);
+            if let Some(csv_import) = &self.csv_import {
+                card_body = card_body.push(csv_import.view());
+            }
+            let card_body = card_body.push(stats_row);
             let card_footer = row![
                 button(text("Cancel").horizontal_alignment(alignment::Horizontal::Center),)
                     .style(style::Button::Bordered)
@@ -138,7 +255,12 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ImportContactList<M> {
             .spacing(10)
             .width(Length::Fill);
 
-            card(card_body, card_footer).max_width(MODAL_WIDTH).into()
+            let width = if self.csv_import.is_some() {
+                CSV_MODAL_WIDTH
+            } else {
+                MODAL_WIDTH
+            };
+            card(card_body, card_footer).max_width(width).into()
         })
         .backdrop(CMessage::CloseModal)
         .on_esc(CMessage::CloseModal)
@@ -147,3 +269,5 @@ impl<M: Clone + Debug + 'static + Send> ModalView for ImportContactList<M> {
 }
 
 const MODAL_WIDTH: f32 = 400.0;
+const CSV_MODAL_WIDTH: f32 = 600.0;
+const MAPPING_HEIGHT: f32 = 300.0;
@@ -0,0 +1,210 @@
+use crate::components::{async_file_importer, card, common_scrollable, AsyncFileImporter};
+use crate::config::Appearance;
+use crate::error::BackendClosed;
+use crate::net::{self, BackEndConnection, BackendEvent};
+use crate::style;
+use crate::types::{ImportedRelay, RelayList};
+use crate::widget::Element;
+use iced::alignment;
+use iced::widget::{button, checkbox, column, container, row, text};
+use iced::Command;
+use iced::Length;
+use iced_aw::Modal;
+use std::fmt::Debug;
+use std::path::Path;
+use url::Url;
+
+use super::ModalView;
+
+#[derive(Debug, Clone)]
+pub enum CMessage<M: Clone + Debug> {
+    CloseModal,
+    UnderlayMessage(M),
+    FileImporterMessage(async_file_importer::Message),
+    ToggleRelay(usize, bool),
+    AddSelected,
+}
+
+struct PreviewRow {
+    relay: ImportedRelay,
+    selected: bool,
+    /// Already in the relay list, can't be selected
+    existing: bool,
+}
+
+pub struct ImportRelays<M: Clone + Debug> {
+    file_importer: AsyncFileImporter,
+    existing: Vec<Url>,
+    rows: Vec<PreviewRow>,
+    invalid: Vec<String>,
+    phantom: std::marker::PhantomData<M>,
+}
+impl<M: Clone + Debug> ImportRelays<M> {
+    pub fn new(existing: Vec<Url>) -> Self {
+        Self {
+            file_importer: AsyncFileImporter::new("/path/to/relays.txt")
+                .file_filter("Relay list", &["txt", "json"]),
+            existing,
+            rows: vec![],
+            invalid: vec![],
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn load_file<P: AsRef<Path>>(&mut self, path: P) {
+        match std::fs::read_to_string(path) {
+            Ok(content) => self.set_list(RelayList::parse(&content)),
+            Err(e) => {
+                tracing::error!("{}", e);
+                self.rows.clear();
+                self.invalid = vec![e.to_string()];
+            }
+        }
+    }
+
+    fn set_list(&mut self, list: RelayList) {
+        self.rows = list
+            .relays
+            .into_iter()
+            .map(|relay| {
+                let existing = self.existing.contains(&relay.url);
+                PreviewRow {
+                    relay,
+                    selected: !existing,
+                    existing,
+                }
+            })
+            .collect();
+        self.invalid = list.invalid;
+    }
+
+    fn selected(&self) -> Vec<ImportedRelay> {
+        self.rows
+            .iter()
+            .filter(|row| row.selected && !row.existing)
+            .map(|row| row.relay.to_owned())
+            .collect()
+    }
+}
+
+impl<M: Clone + Debug + 'static + Send> ModalView for ImportRelays<M> {
+    type UnderlayMessage = M;
+    type Message = CMessage<M>;
+
+    fn backend_event(
+        &mut self,
+        event: BackendEvent,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if let BackendEvent::RFDPickedFile(path) = event {
+            self.load_file(&path);
+            self.file_importer
+                .update(async_file_importer::Message::UpdateFilePath(path), conn)?;
+        }
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(Command<Self::Message>, bool), BackendClosed> {
+        let command = Command::none();
+        match message {
+            CMessage::UnderlayMessage(_) => (),
+            CMessage::CloseModal => return Ok((command, true)),
+            CMessage::FileImporterMessage(msg) => self.file_importer.update(msg, conn)?,
+            CMessage::ToggleRelay(idx, selected) => {
+                if let Some(row) = self.rows.get_mut(idx) {
+                    row.selected = selected;
+                }
+            }
+            CMessage::AddSelected => {
+                conn.send(net::ToBackend::ImportRelays(self.selected()))?;
+                return Ok((command, true));
+            }
+        }
+
+        Ok((command, false))
+    }
+
+    fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
+    ) -> Element<'a, Self::Message> {
+        let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
+
+        Modal::new(true, underlay_component, || {
+            let importer_cp = self.file_importer.view().map(CMessage::FileImporterMessage);
+
+            let preview =
+                self.rows
+                    .iter()
+                    .enumerate()
+                    .fold(column![].spacing(4), |col, (idx, row)| {
+                        let mut label = row.relay.url.to_string();
+                        match (row.relay.read, row.relay.write) {
+                            (true, false) => label.push_str(" (read)"),
+                            (false, true) => label.push_str(" (write)"),
+                            _ => (),
+                        }
+                        if row.existing {
+                            col.push(
+                                text(format!("{} - already added", label))
+                                    .style(style::Text::Placeholder),
+                            )
+                        } else {
+                            col.push(checkbox(label, row.selected, move |selected| {
+                                CMessage::ToggleRelay(idx, selected)
+                            }))
+                        }
+                    });
+            let invalid = self
+                .invalid
+                .iter()
+                .fold(column![].spacing(2), |col, entry| {
+                    col.push(
+                        text(format!("Skipped: {}", entry))
+                            .size(14)
+                            .style(style::Text::Danger),
+                    )
+                });
+
+            let card_body = column![
+                importer_cp,
+                container(common_scrollable(column![preview, invalid].spacing(10)))
+                    .max_height(PREVIEW_HEIGHT)
+            ]
+            .spacing(10)
+            .padding(20);
+
+            let selected = self.selected().len();
+            let mut add_btn = button(
+                text(format!("Add {} relays", selected))
+                    .horizontal_alignment(alignment::Horizontal::Center),
+            )
+            .width(Length::Fill);
+            if selected > 0 {
+                add_btn = add_btn.on_press(CMessage::AddSelected);
+            }
+            let card_footer = row![
+                button(text("Cancel").horizontal_alignment(alignment::Horizontal::Center),)
+                    .style(style::Button::Bordered)
+                    .width(Length::Fill)
+                    .on_press(CMessage::CloseModal),
+                add_btn
+            ]
+            .spacing(10)
+            .width(Length::Fill);
+
+            card(card_body, card_footer).max_width(MODAL_WIDTH).into()
+        })
+        .backdrop(CMessage::CloseModal)
+        .on_esc(CMessage::CloseModal)
+        .into()
+    }
+}
+
+const MODAL_WIDTH: f32 = 450.0;
+const PREVIEW_HEIGHT: f32 = 300.0;
@@ -0,0 +1,129 @@
+use crate::components::{card, common_scrollable};
+use crate::config::Appearance;
+use crate::db::IntegrityReport;
+use crate::net::BackEndConnection;
+use crate::style;
+use crate::widget::Element;
+use iced::alignment;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Command, Length};
+use iced_aw::Modal;
+use std::fmt::Debug;
+
+use super::ModalView;
+
+#[derive(Debug, Clone)]
+pub enum CMessage<M: Clone + Debug> {
+    CloseModal,
+    UnderlayMessage(M),
+}
+
+pub struct IntegrityReportModal<M: Clone + Debug> {
+    report: IntegrityReport,
+    phantom: std::marker::PhantomData<M>,
+}
+impl<M: Clone + Debug> IntegrityReportModal<M> {
+    pub fn new(report: IntegrityReport) -> Self {
+        Self {
+            report,
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<M: Clone + Debug + 'static + Send> ModalView for IntegrityReportModal<M> {
+    type UnderlayMessage = M;
+    type Message = CMessage<M>;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(Command<Self::Message>, bool), crate::error::BackendClosed> {
+        let command = Command::none();
+        match message {
+            CMessage::UnderlayMessage(_) => (),
+            CMessage::CloseModal => return Ok((command, true)),
+        }
+        Ok((command, false))
+    }
+
+    fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
+    ) -> Element<'a, Self::Message> {
+        let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
+        Modal::new(true, underlay_component, move || {
+            let report = &self.report;
+            let title = container(text("Database check").size(22)).center_x();
+
+            let mut content = column![].spacing(10);
+            if let Some((from, to)) = report.migrated {
+                content = content.push(report_row(
+                    "Schema upgraded",
+                    format!("v{} → v{}", from, to),
+                ));
+            }
+            if report.orphan_messages_removed > 0 {
+                content = content.push(report_row(
+                    "Messages without event removed",
+                    report.orphan_messages_removed.to_string(),
+                ));
+            }
+            if report.messages_restored > 0 {
+                content = content.push(report_row(
+                    "Direct messages restored",
+                    report.messages_restored.to_string(),
+                ));
+            }
+            if report.missing_images_removed > 0 {
+                content = content.push(report_row(
+                    "Missing images to download again",
+                    report.missing_images_removed.to_string(),
+                ));
+            }
+            if report.needs_action() {
+                content = content.push(
+                    text("The database is damaged, back it up and restore it from a backup:")
+                        .style(style::Text::Danger),
+                );
+                content = report
+                    .corruption
+                    .iter()
+                    .take(MAX_PROBLEMS_SHOWN)
+                    .fold(content, |col, problem| {
+                        col.push(text(problem).size(14).style(style::Text::Placeholder))
+                    });
+            }
+
+            let card_body = common_scrollable(
+                container(column![title, content].spacing(15))
+                    .center_x()
+                    .padding(20),
+            );
+
+            let card_footer =
+                row![
+                    button(text("Ok").horizontal_alignment(alignment::Horizontal::Center),)
+                        .width(Length::Fill)
+                        .on_press(CMessage::CloseModal),
+                ]
+                .spacing(10);
+
+            card(card_body, card_footer).max_width(MODAL_WIDTH).into()
+        })
+        .backdrop(CMessage::CloseModal)
+        .on_esc(CMessage::CloseModal)
+        .into()
+    }
+}
+
+fn report_row<'a, M: 'a>(label: &str, value: String) -> Element<'a, M> {
+    row![text(label), Space::with_width(Length::Fill), text(value)]
+        .spacing(5)
+        .into()
+}
+
+const MODAL_WIDTH: f32 = 400.0;
+const MAX_PROBLEMS_SHOWN: usize = 20;
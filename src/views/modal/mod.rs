@@ -1,18 +1,31 @@
 #![allow(unused_variables)]
 
 pub(crate) mod basic_contact;
+pub(crate) mod contact_list_review;
+pub(crate) mod edit_channel;
+pub(crate) mod forward_messages;
 pub(crate) mod import_contact_list;
+pub(crate) mod import_relays;
+pub(crate) mod integrity_report;
 pub(crate) mod relay_basic;
+pub(crate) mod relay_deletion;
 pub(crate) mod relay_document;
 pub(crate) mod relays_confirmation;
 
 pub(crate) use basic_contact::ContactDetails;
+pub(crate) use contact_list_review::ContactListReview;
+pub(crate) use edit_channel::EditChannel;
+pub(crate) use forward_messages::ForwardMessages;
 pub(crate) use import_contact_list::ImportContactList;
+pub(crate) use import_relays::ImportRelays;
+pub(crate) use integrity_report::IntegrityReportModal;
 pub(crate) use relay_basic::RelayBasic;
+pub(crate) use relay_deletion::RelayDeletionModal;
 pub(crate) use relay_document::RelayDocState;
 pub(crate) use relays_confirmation::RelaysConfirmation;
 
 use crate::{
+    config::Appearance,
     error::BackendClosed,
     net::{BackEndConnection, BackendEvent},
     widget::Element,
@@ -42,5 +55,6 @@ pub trait ModalView {
     fn view<'a>(
         &'a self,
         underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
     ) -> Element<'a, Self::Message>;
 }
@@ -1,6 +1,8 @@
-use crate::components::text::title;
+use crate::components::text::{rich_text, title};
+use crate::config::Appearance;
 use crate::db::DbContact;
 use crate::net::BackEndConnection;
+use crate::utils::text_direction::visual_label;
 use crate::widget::Element;
 use iced::alignment;
 use iced::widget::{button, column, container, row, text};
@@ -35,49 +37,65 @@ impl ProfileView {
     pub fn view<'a, M: 'a + Clone + Debug>(
         &'a self,
         underlay: impl Into<Element<'a, M>>,
+        appearance: Appearance,
     ) -> Element<'a, CMessage<M>> {
         let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
         Modal::new(true, underlay_component, move || {
             let title = title("Profile");
             let header = container(title).width(Length::Fill).center_y();
-            let card_body: Element<_> =
-                if let Some(profile_cache) = self.contact.get_profile_cache() {
-                    let profile_meta = profile_cache.metadata;
-                    let mut content = column![].spacing(5);
-                    if let Some(name) = profile_meta.name {
-                        content = content.push(column![text("name"), text(name)].spacing(5));
-                    }
-                    if let Some(display_name) = profile_meta.display_name {
-                        content = content
-                            .push(column![text("display_name"), text(display_name)].spacing(5));
-                    }
-                    if let Some(picture_url) = profile_meta.picture {
-                        content = content
-                            .push(column![text("picture_url"), text(picture_url)].spacing(5));
-                    }
-                    if let Some(about) = profile_meta.about {
-                        content = content.push(column![text("about"), text(about)].spacing(5));
-                    }
-                    if let Some(website) = profile_meta.website {
-                        content = content.push(column![text("website"), text(website)].spacing(5));
-                    }
-                    if let Some(banner_url) = profile_meta.banner {
-                        content =
-                            content.push(column![text("banner_url"), text(banner_url)].spacing(5));
-                    }
-                    if let Some(nip05) = profile_meta.nip05 {
-                        content = content.push(column![text("nip05"), text(nip05)].spacing(5));
-                    }
-                    if let Some(lud06) = profile_meta.lud06 {
-                        content = content.push(column![text("lud06"), text(lud06)].spacing(5));
-                    }
-                    if let Some(lud16) = profile_meta.lud16 {
-                        content = content.push(column![text("lud16"), text(lud16)].spacing(5));
-                    }
-                    content.into()
-                } else {
-                    text("No profile data found").into()
-                };
+            let card_body: Element<_> = if let Some(profile_cache) =
+                self.contact.get_profile_cache()
+            {
+                let profile_meta = profile_cache.metadata;
+                let mut content = column![].spacing(5);
+                if let Some(name) = profile_meta.name {
+                    content = content.push(
+                        column![
+                            text("name"),
+                            text(visual_label(&name, appearance.text_direction).into_owned())
+                        ]
+                        .spacing(5),
+                    );
+                }
+                if let Some(display_name) = profile_meta.display_name {
+                    content = content.push(
+                        column![
+                            text("display_name"),
+                            text(
+                                visual_label(&display_name, appearance.text_direction).into_owned()
+                            )
+                        ]
+                        .spacing(5),
+                    );
+                }
+                if let Some(picture_url) = profile_meta.picture {
+                    content =
+                        content.push(column![text("picture_url"), text(picture_url)].spacing(5));
+                }
+                if let Some(about) = profile_meta.about {
+                    let about = rich_text(&about).text_direction(appearance.text_direction);
+                    content = content.push(column![text("about"), about].spacing(5));
+                }
+                if let Some(website) = profile_meta.website {
+                    content = content.push(column![text("website"), text(website)].spacing(5));
+                }
+                if let Some(banner_url) = profile_meta.banner {
+                    content =
+                        content.push(column![text("banner_url"), text(banner_url)].spacing(5));
+                }
+                if let Some(nip05) = profile_meta.nip05 {
+                    content = content.push(column![text("nip05"), text(nip05)].spacing(5));
+                }
+                if let Some(lud06) = profile_meta.lud06 {
+                    content = content.push(column![text("lud06"), text(lud06)].spacing(5));
+                }
+                if let Some(lud16) = profile_meta.lud16 {
+                    content = content.push(column![text("lud16"), text(lud16)].spacing(5));
+                }
+                content.into()
+            } else {
+                text("No profile data found").into()
+            };
             let card_body: Element<_> = container(card_body)
                 .width(Length::Fill)
                 .center_y()
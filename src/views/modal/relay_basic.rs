@@ -1,6 +1,7 @@
 use super::ModalView;
 use crate::components::card;
 use crate::components::text_input_group::TextInputGroup;
+use crate::config::Appearance;
 use crate::net::{BackEndConnection, ToBackend};
 use crate::style;
 use crate::widget::Element;
@@ -70,6 +71,7 @@ impl<M: Clone + Debug + 'static + Send> ModalView for RelayBasic<M> {
     fn view<'a>(
         &'a self,
         underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
     ) -> Element<'a, Self::Message> {
         let underlay_component: Element<_> = underlay.into().map(CMessage::UnderlayMessage);
 
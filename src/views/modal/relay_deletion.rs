@@ -0,0 +1,144 @@
+use crate::components::{card, common_scrollable};
+use crate::config::Appearance;
+use crate::net::{BackEndConnection, BackendEvent};
+use crate::style;
+use crate::types::{DeletionStatus, RelayDeletion};
+use crate::widget::Element;
+use iced::alignment;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Command, Length};
+use iced_aw::Modal;
+use std::fmt::Debug;
+
+use super::ModalView;
+
+#[derive(Debug, Clone)]
+pub enum CMessage<M: Clone + Debug> {
+    CloseModal,
+    UnderlayMessage(M),
+}
+
+/// Results of a deletion request, updated as the relays answer
+pub struct RelayDeletionModal<M: Clone + Debug> {
+    /// Database id of the message being deleted
+    event_id: i64,
+    deletion: Option<RelayDeletion>,
+    phantom: std::marker::PhantomData<M>,
+}
+impl<M: Clone + Debug> RelayDeletionModal<M> {
+    pub fn new(event_id: i64) -> Self {
+        Self {
+            event_id,
+            deletion: None,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn content<'a>(&self) -> Element<'a, CMessage<M>> {
+        let Some(deletion) = &self.deletion else {
+            return text("Checking which relays support deletion...")
+                .style(style::Text::Placeholder)
+                .into();
+        };
+        if !deletion.is_supported() {
+            return text("None of your relays advertise support for deletion requests")
+                .style(style::Text::Placeholder)
+                .into();
+        }
+        deletion
+            .relays
+            .iter()
+            .fold(column![].spacing(10), |col, (url, status)| {
+                col.push(make_status_row(url, status))
+            })
+            .into()
+    }
+}
+
+impl<M: Clone + Debug + 'static + Send> ModalView for RelayDeletionModal<M> {
+    type UnderlayMessage = M;
+    type Message = CMessage<M>;
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(Command<Self::Message>, bool), crate::error::BackendClosed> {
+        let command = Command::none();
+        match message {
+            CMessage::UnderlayMessage(_) => (),
+            CMessage::CloseModal => return Ok((command, true)),
+        }
+        Ok((command, false))
+    }
+
+    fn backend_event(
+        &mut self,
+        event: BackendEvent,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), crate::error::BackendClosed> {
+        if let BackendEvent::RelayDeletionUpdated(deletion) = event {
+            if deletion.event_id == self.event_id {
+                self.deletion = Some(deletion);
+            }
+        }
+        Ok(())
+    }
+
+    fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
+    ) -> Element<'a, Self::Message> {
+        let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
+        Modal::new(true, underlay_component, move || {
+            let title = container(text("Deletion from relays").size(22)).center_x();
+            let info = text(
+                "Relays that support it were asked to delete the message, \
+                then asked for it again. Other clients may have kept a copy.",
+            )
+            .size(14)
+            .style(style::Text::Placeholder);
+
+            let card_body = common_scrollable(
+                container(column![title, info, self.content()].spacing(15))
+                    .center_x()
+                    .padding(20),
+            );
+
+            let card_footer =
+                row![
+                    button(text("Ok").horizontal_alignment(alignment::Horizontal::Center),)
+                        .width(Length::Fill)
+                        .on_press(CMessage::CloseModal),
+                ]
+                .spacing(10);
+
+            card(card_body, card_footer).max_width(MODAL_WIDTH).into()
+        })
+        .backdrop(CMessage::CloseModal)
+        .on_esc(CMessage::CloseModal)
+        .into()
+    }
+}
+
+fn make_status_row<'a, M: 'a>(url: &url::Url, status: &DeletionStatus) -> Element<'a, M> {
+    let status_style = match status {
+        DeletionStatus::Deleted => style::Text::Primary,
+        DeletionStatus::Rejected(_) | DeletionStatus::StillServed => style::Text::Danger,
+        DeletionStatus::NotSupported
+        | DeletionStatus::Sent
+        | DeletionStatus::Verifying
+        | DeletionStatus::NoAnswer => style::Text::Placeholder,
+    };
+    row![
+        text(url),
+        Space::with_width(Length::Fill),
+        text(status.to_string()).style(status_style)
+    ]
+    .spacing(5)
+    .padding(5)
+    .into()
+}
+
+const MODAL_WIDTH: f32 = 420.0;
@@ -3,6 +3,7 @@ use std::fmt::Debug;
 
 use crate::components::text::title;
 use crate::components::{card, common_scrollable, copy_btn};
+use crate::config::Appearance;
 use crate::db::DbRelay;
 use crate::error::BackendClosed;
 use crate::net::{BackEndConnection, BackendEvent, ToBackend};
@@ -90,98 +91,95 @@ impl<M: Clone + Debug + 'static + Send> ModalView for RelayDocState<M> {
     fn view<'a>(
         &'a self,
         underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
     ) -> Element<'a, Self::Message> {
         let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
 
         Modal::new(true, underlay_component, move || {
-            let card_body: Element<_> = if let Some(information) = &self.db_relay.information {
-                if let Some(document) = &information.document {
-                    let name_gp = column![
-                        text("Name").size(24),
-                        text(document.name.as_ref().unwrap_or(&"".into())),
-                        Rule::horizontal(5),
-                    ]
-                    .spacing(5);
-
-                    let description_gp = column![
-                        text("Description").size(24),
-                        text(document.description.as_ref().unwrap_or(&"".into())),
-                        Rule::horizontal(5),
-                    ]
-                    .spacing(5);
-
-                    let pubkey_text: Element<_> = if let Some(pubkey) = &document.pubkey {
-                        text_and_copy_btn(pubkey, CMessage::Copy(pubkey.to_string()))
-                    } else {
-                        text("").into()
-                    };
-                    let pubkey_gp = column![
-                        text("Public Key").size(24),
-                        pubkey_text,
-                        Rule::horizontal(5),
-                    ]
-                    .spacing(5);
-
-                    let contact_text = if let Some(contact) = &document.contact {
-                        text_and_copy_btn(contact, CMessage::Copy(contact.to_string()))
-                    } else {
-                        text("").into()
-                    };
-                    let contact_gp =
-                        column![text("Contact").size(24), contact_text, Rule::horizontal(5),]
-                            .spacing(5);
-
-                    let supported_nips_col: Element<_> =
-                        if let Some(nips) = document.supported_nips.as_ref() {
-                            nips.iter()
-                                .filter_map(|nip| self.nips_data.get(nip))
-                                .fold(column![].spacing(5), |col, nip| col.push(nip_row(nip)))
-                                .into()
-                        } else {
-                            text("Not informed").into()
-                        };
-                    let nips_gp = column![
-                        text("Supported Nips").size(24),
-                        supported_nips_col,
-                        Rule::horizontal(5),
-                    ]
-                    .spacing(5);
-
-                    let software_text = if let Some(software) = &document.software {
-                        text_and_copy_btn(software, CMessage::Copy(software.to_string()))
+            let card_body: Element<_> = if let Some(document) = self.db_relay.document() {
+                let name_gp = column![
+                    text("Name").size(24),
+                    text(document.name.as_ref().unwrap_or(&"".into())),
+                    Rule::horizontal(5),
+                ]
+                .spacing(5);
+
+                let description_gp = column![
+                    text("Description").size(24),
+                    text(document.description.as_ref().unwrap_or(&"".into())),
+                    Rule::horizontal(5),
+                ]
+                .spacing(5);
+
+                let pubkey_text: Element<_> = if let Some(pubkey) = &document.pubkey {
+                    text_and_copy_btn(pubkey, CMessage::Copy(pubkey.to_string()))
+                } else {
+                    text("").into()
+                };
+                let pubkey_gp = column![
+                    text("Public Key").size(24),
+                    pubkey_text,
+                    Rule::horizontal(5),
+                ]
+                .spacing(5);
+
+                let contact_text = if let Some(contact) = &document.contact {
+                    text_and_copy_btn(contact, CMessage::Copy(contact.to_string()))
+                } else {
+                    text("").into()
+                };
+                let contact_gp =
+                    column![text("Contact").size(24), contact_text, Rule::horizontal(5),]
+                        .spacing(5);
+
+                let supported_nips_col: Element<_> =
+                    if let Some(nips) = document.supported_nips.as_ref() {
+                        nips.iter()
+                            .filter_map(|nip| self.nips_data.get(nip))
+                            .fold(column![].spacing(5), |col, nip| col.push(nip_row(nip)))
+                            .into()
                     } else {
-                        text("").into()
+                        text("Not informed").into()
                     };
-                    let software_gp = column![
-                        text("Software").size(24),
-                        software_text,
-                        Rule::horizontal(5),
-                    ]
-                    .spacing(5);
-
-                    let version_gp = column![
-                        text("Version").size(24),
-                        text(document.version.as_ref().unwrap_or(&"".into())),
-                        Rule::horizontal(5),
-                    ]
-                    .spacing(5);
-
-                    column![
-                        name_gp,
-                        description_gp,
-                        pubkey_gp,
-                        contact_gp,
-                        nips_gp,
-                        software_gp,
-                        version_gp
-                    ]
-                    .spacing(10)
-                    .into()
+                let nips_gp = column![
+                    text("Supported Nips").size(24),
+                    supported_nips_col,
+                    Rule::horizontal(5),
+                ]
+                .spacing(5);
+
+                let software_text = if let Some(software) = &document.software {
+                    text_and_copy_btn(software, CMessage::Copy(software.to_string()))
                 } else {
-                    text("Relay has no document").into()
-                }
+                    text("").into()
+                };
+                let software_gp = column![
+                    text("Software").size(24),
+                    software_text,
+                    Rule::horizontal(5),
+                ]
+                .spacing(5);
+
+                let version_gp = column![
+                    text("Version").size(24),
+                    text(document.version.as_ref().unwrap_or(&"".into())),
+                    Rule::horizontal(5),
+                ]
+                .spacing(5);
+
+                column![
+                    name_gp,
+                    description_gp,
+                    pubkey_gp,
+                    contact_gp,
+                    nips_gp,
+                    software_gp,
+                    version_gp
+                ]
+                .spacing(10)
+                .into()
             } else {
-                text("Relay has no information").into()
+                text("Relay has no document").into()
             };
 
             let card_footer =
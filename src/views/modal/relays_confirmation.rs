@@ -1,8 +1,11 @@
 use crate::components::{card, common_scrollable};
+use crate::config::{Appearance, TimeFormatConfig};
 use crate::db::{DbRelay, DbRelayResponse};
 use crate::net::BackEndConnection;
 use crate::style;
+use crate::utils::time_format;
 use crate::widget::Element;
+use chrono::{Duration, NaiveDateTime};
 use iced::alignment;
 use iced::widget::{button, column, container, row, text, tooltip, Space};
 use iced::{Command, Length};
@@ -20,16 +23,48 @@ pub enum CMessage<M: Clone + Debug> {
 pub struct RelaysConfirmation<M: Clone + Debug> {
     responses: Vec<DbRelayResponse>,
     all_relays: Vec<DbRelay>,
+    /// When the event was signed
+    created_at: Option<NaiveDateTime>,
     phantom: std::marker::PhantomData<M>,
 }
 impl<M: Clone + Debug> RelaysConfirmation<M> {
     pub fn new(responses: &[DbRelayResponse], all_relays: &[DbRelay]) -> Self {
+        let mut responses = responses.to_vec();
+        // answers in the order they arrived, untimed ones last
+        responses.sort_by_key(|r| (r.responded_at.is_none(), r.responded_at));
         Self {
-            responses: responses.to_vec(),
+            responses,
             all_relays: all_relays.to_vec(),
+            created_at: None,
             phantom: std::marker::PhantomData,
         }
     }
+    pub fn created_at(mut self, created_at: Option<NaiveDateTime>) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    fn timeline<'a>(&self, format: &TimeFormatConfig) -> Element<'a, CMessage<M>> {
+        let sent_at = self.responses.iter().filter_map(|r| r.sent_at).min();
+        let mut col = column![].spacing(2);
+        if let Some(created_at) = self.created_at {
+            col = col.push(timeline_row(
+                "Created",
+                time_format::precise(created_at, format),
+            ));
+        }
+        if let Some(sent_at) = sent_at {
+            col = col.push(timeline_row("Sent", time_format::precise(sent_at, format)));
+        }
+        let first_confirmation = match DbRelayResponse::time_to_first_confirmation(&self.responses)
+        {
+            Some(delay) => format_delay(delay),
+            None if sent_at.is_some() => "Not confirmed".into(),
+            None => "Not recorded".into(),
+        };
+        col.push(timeline_row("First confirmation", first_confirmation))
+            .into()
+    }
 }
 
 impl<M: Clone + Debug + 'static + Send> ModalView for RelaysConfirmation<M> {
@@ -52,6 +87,7 @@ impl<M: Clone + Debug + 'static + Send> ModalView for RelaysConfirmation<M> {
     fn view<'a>(
         &'a self,
         underlay: impl Into<Element<'a, Self::UnderlayMessage>>,
+        appearance: Appearance,
     ) -> Element<'a, Self::Message> {
         let underlay_component = underlay.into().map(CMessage::UnderlayMessage);
         Modal::new(true, underlay_component, move || {
@@ -67,11 +103,19 @@ impl<M: Clone + Debug + 'static + Send> ModalView for RelaysConfirmation<M> {
                 .responses
                 .iter()
                 .fold(col, |col, response| col.push(make_response_row(response)));
+            let content = self
+                .all_relays
+                .iter()
+                .filter(|relay| relay.write)
+                .filter(|relay| !self.responses.iter().any(|r| r.relay_url == relay.url))
+                .fold(content, |col, relay| col.push(make_silent_row(relay)));
 
             let card_body = common_scrollable(
-                container(column![title, content].spacing(15))
-                    .center_x()
-                    .padding(20),
+                container(
+                    column![title, self.timeline(&appearance.time_format), content].spacing(15),
+                )
+                .center_x()
+                .padding(20),
             );
 
             let card_footer =
@@ -102,10 +146,49 @@ fn make_response_row<'a, M: 'a>(response: &DbRelayResponse) -> Element<'a, M> {
         text("Ok").into()
     };
 
-    row![url_txt, Space::with_width(Length::Fill), status_txt,]
-        .spacing(5)
-        .padding(5)
-        .into()
+    let delay_txt = text(response.delay().map(format_delay).unwrap_or_default())
+        .size(14)
+        .style(style::Text::Placeholder);
+
+    row![
+        url_txt,
+        Space::with_width(Length::Fill),
+        delay_txt,
+        status_txt,
+    ]
+    .spacing(5)
+    .padding(5)
+    .into()
+}
+
+/// Relay that got the event but never answered
+fn make_silent_row<'a, M: 'a>(relay: &DbRelay) -> Element<'a, M> {
+    row![
+        text(&relay.url),
+        Space::with_width(Length::Fill),
+        text("No answer").style(style::Text::Placeholder)
+    ]
+    .spacing(5)
+    .padding(5)
+    .into()
+}
+
+fn timeline_row<'a, M: 'a>(label: &str, value: String) -> Element<'a, M> {
+    row![
+        text(label).size(14).width(Length::Fill),
+        text(value).size(14)
+    ]
+    .spacing(5)
+    .into()
+}
+
+fn format_delay(delay: Duration) -> String {
+    let millis = delay.num_milliseconds();
+    if millis < 1000 {
+        format!("+{} ms", millis)
+    } else {
+        format!("+{:.1} s", millis as f64 / 1000.0)
+    }
 }
 
-const MODAL_WIDTH: f32 = 300.0;
+const MODAL_WIDTH: f32 = 420.0;
@@ -2,6 +2,7 @@
 use iced::Subscription;
 
 use crate::{
+    config::Appearance,
     error::BackendClosed,
     net::{BackEndConnection, BackendEvent},
     style,
@@ -33,5 +34,9 @@ pub trait Route: Sized {
         Ok(RouterCommand::new())
     }
 
-    fn view(&self, selected_theme: Option<style::Theme>) -> Element<'_, Self::Message>;
+    fn view(
+        &self,
+        selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<'_, Self::Message>;
 }
@@ -1,6 +1,7 @@
 use crate::{
     components::{common_scrollable, copy_btn, text::title},
     consts::{BITCOIN_ADDRESS, GITHUB_REPO, LIGHTNING_ADDRESS, NOSTRTALK_VERSION, TT_LINK},
+    ipc::uri_handler,
     net::{BackEndConnection, BackendEvent},
     style,
     utils::{hide_string, qr_code_handle},
@@ -15,17 +16,21 @@ pub enum Message {
     OpenTTLink,
     OpenGHLink,
     CopyQrCode(String),
+    RegisterUriHandler,
 }
 
 pub struct State {
     btc_qrcode_handle: Option<Handle>,
     lnd_qrcode_handle: Option<Handle>,
+    /// Result of the last `nostr:` handler registration
+    uri_handler_result: Option<Result<(), String>>,
 }
 impl State {
     pub fn new() -> Self {
         Self {
             btc_qrcode_handle: qr_code_handle(BITCOIN_ADDRESS).ok(),
             lnd_qrcode_handle: qr_code_handle(LIGHTNING_ADDRESS).ok(),
+            uri_handler_result: None,
         }
     }
 
@@ -46,6 +51,13 @@ impl State {
             Message::CopyQrCode(content) => {
                 return clipboard::write(content);
             }
+            Message::RegisterUriHandler => {
+                let result = uri_handler::register().map_err(|e| e.to_string());
+                if let Err(e) = &result {
+                    tracing::error!("Failed to register nostr: links: {}", e);
+                }
+                self.uri_handler_result = Some(result);
+            }
         }
         Command::none()
     }
@@ -77,6 +89,19 @@ impl State {
             .padding(10)
             .width(Length::Fill);
 
+        let uri_handler_btn = button("Open nostr: links with NostrTalk")
+            .padding(10)
+            .style(style::Button::Bordered)
+            .on_press(Message::RegisterUriHandler);
+        let uri_handler_status = match &self.uri_handler_result {
+            None => text(""),
+            Some(Ok(())) => text("Registered").style(style::Text::Placeholder),
+            Some(Err(e)) => text(format!("Failed: {}", e)).style(style::Text::Danger),
+        };
+        let uri_handler_group = row![uri_handler_btn, uri_handler_status.size(14)]
+            .align_items(Alignment::Center)
+            .spacing(10);
+
         let donation_1 =
             text("If you like this software, please consider donating to the following addresses:");
         let donation_btc =
@@ -95,6 +120,7 @@ impl State {
             about_2,
             about_3_group,
             github_group,
+            uri_handler_group,
             h_divider,
             donation_1,
             row![donation_btc, donation_lnd]
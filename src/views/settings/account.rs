@@ -1,15 +1,26 @@
-use iced::widget::{button, column, container, row, text, tooltip, Space};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use iced::widget::image::Handle;
+use iced::widget::{button, column, container, image as iced_image, row, text, tooltip, Space};
 use iced::{Alignment, Length};
-use nostr::Metadata;
+use nostr::{Kind, Metadata};
 
+use crate::components::async_file_importer::FileFilter;
 use crate::components::common_scrollable;
-use crate::components::text::title;
+use crate::components::text::{rich_text, title};
 use crate::components::text_input_group::TextInputGroup;
-use crate::db::{DbRelay, DbRelayResponse};
+use crate::components::CropArea;
+use crate::config::{Appearance, TextDirection, TimeFormatConfig};
+use crate::db::{DbRelay, DbRelayResponse, SigningLogEntry};
 use crate::error::BackendClosed;
-use crate::icon::satellite_icon;
-use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::icon::{check_icon, satellite_icon};
+use crate::net::{BackEndConnection, BackendEvent, PendingRequest, ToBackend};
 use crate::style;
+use crate::types::{
+    CropRect, FieldChange, LightningAddress, MetadataDiff, Pairing, ProfileField, ProfileImage,
+};
+use crate::utils::{hide_string, ns_event_to_naive, qr_code_handle, time_format};
 use crate::widget::Element;
 
 #[derive(Debug, Clone)]
@@ -24,7 +35,66 @@ pub enum Message {
     LNChange(String),
     NIP05Change(String),
     SavePress,
+    BackPress,
+    PublishPress,
     RelaysConfirmationPress(Option<AccountRelaysResponse>),
+    UploadPress(ProfileImage),
+    CropChange(CropRect),
+    CancelCropPress,
+    ConfirmCropPress,
+    PairDevicePress,
+    NewPairingPress,
+    CopyPairingPress(String),
+    ClosePairingPress,
+    SigningLogPress,
+    CloseSigningLogPress,
+}
+
+/// Result of checking a URL or address before publishing it
+#[derive(Debug, Clone)]
+enum CheckStatus {
+    Checking,
+    Valid,
+    Invalid(String),
+}
+
+/// Local image being cropped before its upload
+struct ImageCrop {
+    kind: ProfileImage,
+    path: PathBuf,
+    handle: Handle,
+    image_size: (u32, u32),
+    rect: CropRect,
+    uploading: bool,
+    error: Option<String>,
+}
+impl ImageCrop {
+    fn new(kind: ProfileImage, path: PathBuf) -> Result<Self, String> {
+        let image_size = image::image_dimensions(&path).map_err(|e| e.to_string())?;
+        let rect = CropRect::centered(image_size.0, image_size.1, kind.aspect_ratio());
+        Ok(Self {
+            kind,
+            handle: Handle::from_path(&path),
+            path,
+            image_size,
+            rect,
+            uploading: false,
+            error: None,
+        })
+    }
+}
+
+/// QR and code shown to log in on another device
+struct PairingView {
+    pairing: Option<Pairing>,
+    qr_code: Option<Handle>,
+    error: Option<String>,
+}
+
+/// Events signed with the user's keys, most recent first
+struct SigningLogView {
+    entries: Option<Vec<SigningLogEntry>>,
+    total: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -58,6 +128,19 @@ pub struct State {
     website_url_is_invalid: bool,
     banner_url_is_invalid: bool,
     relays_response: Option<AccountRelaysResponse>,
+    /// Last profile seen on the relays
+    published: Option<Metadata>,
+    /// Edited profile waiting for confirmation
+    review: Option<(Metadata, MetadataDiff)>,
+    /// Keyed by the checked URL or address
+    checks: HashMap<String, CheckStatus>,
+    /// File dialog opened for this image
+    picking: Option<(ProfileImage, PendingRequest)>,
+    image_crop: Option<ImageCrop>,
+    /// Why the picked image couldn't be opened
+    pick_error: Option<String>,
+    pairing: Option<PairingView>,
+    signing_log: Option<SigningLogView>,
 }
 impl State {
     pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
@@ -77,6 +160,14 @@ impl State {
             website_url_is_invalid: false,
             banner_url_is_invalid: false,
             relays_response: None,
+            published: None,
+            review: None,
+            checks: HashMap::new(),
+            picking: None,
+            image_crop: None,
+            pick_error: None,
+            pairing: None,
+            signing_log: None,
         })
     }
 
@@ -85,6 +176,23 @@ impl State {
         event: BackendEvent,
         _conn: &mut BackEndConnection,
     ) -> Result<(), BackendClosed> {
+        if let Some((kind, request)) = self.picking {
+            if let Some(response) = request.response(&event) {
+                self.picking = None;
+                match response {
+                    Ok(BackendEvent::RFDPickedFile(path)) => {
+                        match ImageCrop::new(kind, path.to_owned()) {
+                            Ok(image_crop) => self.image_crop = Some(image_crop),
+                            Err(e) => self.pick_error = Some(e),
+                        }
+                    }
+                    Ok(_) => (),
+                    Err(e) => self.pick_error = Some(e.to_owned()),
+                }
+                return Ok(());
+            }
+        }
+
         match event {
             BackendEvent::GotRelayResponsesUserProfile {
                 responses,
@@ -94,6 +202,7 @@ impl State {
             }
             BackendEvent::GotUserProfileCache(Some(profile_cache)) => {
                 let meta = profile_cache.metadata;
+                self.published = Some(meta.clone());
                 self.name = meta.name.unwrap_or("".into());
                 self.user_name = meta.display_name.unwrap_or("".into());
                 self.picture_url = meta.picture.unwrap_or("".into());
@@ -104,6 +213,74 @@ impl State {
                 self.ln_addrs = meta.lud16.unwrap_or("".into());
                 self.nostr_addrs = meta.nip05.unwrap_or("".into());
             }
+            BackendEvent::UrlChecked(url, reachable) => {
+                let status = if reachable {
+                    CheckStatus::Valid
+                } else {
+                    CheckStatus::Invalid("Not reachable".into())
+                };
+                self.set_check(url, status);
+            }
+            BackendEvent::OwnNip05Verified(address, result) => {
+                let status = match result {
+                    Ok(()) => CheckStatus::Valid,
+                    Err(reason) => CheckStatus::Invalid(reason),
+                };
+                self.set_check(address, status);
+            }
+            BackendEvent::LightningChecked(address, valid) => {
+                let status = if valid {
+                    CheckStatus::Valid
+                } else {
+                    CheckStatus::Invalid("The LNURL endpoint doesn't answer".into())
+                };
+                self.set_check(address, status);
+            }
+            BackendEvent::PairingCreated(result) => {
+                if let Some(pairing_view) = &mut self.pairing {
+                    match result {
+                        Ok(pairing) => {
+                            pairing_view.qr_code = qr_code_handle(&pairing.payload).ok();
+                            pairing_view.pairing = Some(pairing);
+                            pairing_view.error = None;
+                        }
+                        Err(e) => pairing_view.error = Some(e),
+                    }
+                }
+            }
+            BackendEvent::GotSigningLog { entries, total } => {
+                if let Some(signing_log) = &mut self.signing_log {
+                    signing_log.entries = Some(entries);
+                    signing_log.total = total;
+                }
+            }
+            BackendEvent::ProfileImageUploaded(kind, result) => {
+                let Some(image_crop) = &mut self.image_crop else {
+                    return Ok(());
+                };
+                if image_crop.kind != kind || !image_crop.uploading {
+                    return Ok(());
+                }
+                match result {
+                    Ok(url) => {
+                        match kind {
+                            ProfileImage::Picture => {
+                                self.picture_url = url;
+                                self.picture_url_is_invalid = false;
+                            }
+                            ProfileImage::Banner => {
+                                self.banner = url;
+                                self.banner_url_is_invalid = false;
+                            }
+                        }
+                        self.image_crop = None;
+                    }
+                    Err(e) => {
+                        image_crop.uploading = false;
+                        image_crop.error = Some(e);
+                    }
+                }
+            }
             _ => (),
         }
         Ok(())
@@ -137,13 +314,113 @@ impl State {
             Message::SavePress => {
                 let meta = self.make_meta();
                 if self.all_valid() {
-                    conn.send(ToBackend::UpdateUserProfileMeta(meta))?;
+                    let diff = MetadataDiff::new(self.published.as_ref(), &meta);
+                    self.send_checks(&diff, conn)?;
+                    self.review = Some((meta, diff));
+                }
+            }
+            Message::UploadPress(kind) => {
+                self.pick_error = None;
+                let request = conn.request(ToBackend::ChooseFile(Some(FileFilter {
+                    name: "Image".into(),
+                    extensions: IMAGE_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+                })))?;
+                self.picking = Some((kind, request));
+            }
+            Message::CropChange(rect) => {
+                if let Some(image_crop) = &mut self.image_crop {
+                    if !image_crop.uploading {
+                        image_crop.rect = rect;
+                    }
+                }
+            }
+            Message::CancelCropPress => self.image_crop = None,
+            Message::ConfirmCropPress => {
+                if let Some(image_crop) = &mut self.image_crop {
+                    image_crop.uploading = true;
+                    image_crop.error = None;
+                    conn.send(ToBackend::UploadProfileImage {
+                        path: image_crop.path.clone(),
+                        crop: image_crop.rect,
+                        kind: image_crop.kind,
+                    })?;
                 }
             }
+            Message::PairDevicePress | Message::NewPairingPress => {
+                self.pairing = Some(PairingView {
+                    pairing: None,
+                    qr_code: None,
+                    error: None,
+                });
+                conn.send(ToBackend::CreatePairing)?;
+            }
+            // the settings router writes it to the clipboard
+            Message::CopyPairingPress(_) => (),
+            Message::ClosePairingPress => self.pairing = None,
+            Message::SigningLogPress => {
+                self.signing_log = Some(SigningLogView {
+                    entries: None,
+                    total: 0,
+                });
+                conn.send(ToBackend::FetchSigningLog)?;
+            }
+            Message::CloseSigningLogPress => self.signing_log = None,
+            Message::BackPress => self.review = None,
+            Message::PublishPress => {
+                if let Some((meta, diff)) = self.review.take() {
+                    if !diff.is_empty() {
+                        conn.send(ToBackend::UpdateUserProfileMeta(meta.clone()))?;
+                        self.published = Some(meta);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Only answers for values still waiting are kept
+    fn set_check(&mut self, value: String, status: CheckStatus) {
+        if let Some(check) = self.checks.get_mut(&value) {
+            *check = status;
+        }
+    }
+
+    /// Checks the changed addresses that weren't checked yet
+    fn send_checks(
+        &mut self,
+        diff: &MetadataDiff,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        for change in &diff.changes {
+            let Some(value) = &change.edited else {
+                continue;
+            };
+            if self.checks.contains_key(value) {
+                continue;
+            }
+            let message = match change.field {
+                ProfileField::Picture | ProfileField::Banner | ProfileField::Website => {
+                    ToBackend::CheckUrl(value.to_owned())
+                }
+                ProfileField::Nip05 => ToBackend::VerifyOwnNip05(value.to_owned()),
+                ProfileField::Lud06 => {
+                    ToBackend::CheckLightningAddress(LightningAddress::Lud06(value.to_owned()))
+                }
+                ProfileField::Lud16 => {
+                    ToBackend::CheckLightningAddress(LightningAddress::Lud16(value.to_owned()))
+                }
+                ProfileField::Name | ProfileField::DisplayName | ProfileField::About => continue,
+            };
+            self.checks.insert(value.to_owned(), CheckStatus::Checking);
+            conn.send(message)?;
         }
         Ok(())
     }
 
+    fn check_of(&self, value: Option<&String>) -> Option<&CheckStatus> {
+        value.and_then(|value| self.checks.get(value))
+    }
+
     fn make_meta(&mut self) -> Metadata {
         let mut meta = Metadata::new()
             .name(self.name.clone())
@@ -210,12 +487,32 @@ impl State {
                 .into()
         }
     }
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self, appearance: Appearance) -> Element<Message> {
+        if let Some((_, diff)) = &self.review {
+            return self.review_view(diff);
+        }
+        if let Some(image_crop) = &self.image_crop {
+            return crop_view(image_crop);
+        }
+        if let Some(pairing_view) = &self.pairing {
+            return pairing_view.view(&appearance.time_format);
+        }
+        if let Some(signing_log) = &self.signing_log {
+            return signing_log.view(&appearance.time_format);
+        }
         let title = title("Account");
         let title_group = container(
             row![
                 title,
                 Space::with_width(Length::Fill),
+                button("Signing log")
+                    .padding(5)
+                    .style(style::Button::Bordered)
+                    .on_press(Message::SigningLogPress),
+                button("Pair new device")
+                    .padding(5)
+                    .style(style::Button::Bordered)
+                    .on_press(Message::PairDevicePress),
                 self.make_relays_response()
             ]
             .align_items(Alignment::Center)
@@ -282,30 +579,49 @@ impl State {
         .tooltip("Easily find and confirm users using their email-like identifiers on NOSTR")
         .build();
 
-        let form = container(common_scrollable(
-            column![
-                profile_name_input,
-                user_name_input,
-                about_input,
-                picture_url_input.build(),
-                banner_input.build(),
-                website_input.build(),
-                ln_url_input,
-                ln_input,
-                nostr_addrs_input,
+        let form = container(
+            row![
+                common_scrollable(
+                    column![
+                        profile_name_input,
+                        user_name_input,
+                        about_input,
+                        with_upload_btn(picture_url_input.build(), ProfileImage::Picture),
+                        with_upload_btn(banner_input.build(), ProfileImage::Banner),
+                        website_input.build(),
+                        ln_url_input,
+                        ln_input,
+                        nostr_addrs_input,
+                    ]
+                    .spacing(10),
+                )
+                .width(Length::FillPortion(3)),
+                container(self.preview_card(appearance.text_direction))
+                    .width(Length::FillPortion(2))
             ]
-            .spacing(10),
-        ))
+            .spacing(20),
+        )
         .width(Length::Fill)
         .height(Length::Fill);
 
-        let mut save_btn = button("Save").padding(10);
+        let mut save_btn = button("Review changes").padding(10);
         if self.all_valid() {
             save_btn = save_btn.on_press(Message::SavePress);
         }
-        let footer_row = container(row![Space::with_width(Length::Fill), save_btn].spacing(10))
-            .width(Length::Fill)
-            .height(FOOTER_HEIGHT);
+        let pick_error = self.pick_error.as_deref().unwrap_or("");
+        let footer_row = container(
+            row![
+                text(pick_error)
+                    .size(14)
+                    .style(style::Text::Danger)
+                    .width(Length::Fill),
+                save_btn
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .height(FOOTER_HEIGHT);
 
         container(
             column![title_group, form, footer_row]
@@ -314,7 +630,448 @@ impl State {
         )
         .into()
     }
+
+    /// How the profile looks with the current inputs
+    fn preview_card(&self, text_direction: TextDirection) -> Element<Message> {
+        let display_name = [&self.user_name, &self.name]
+            .into_iter()
+            .map(|name| name.trim())
+            .find(|name| !name.is_empty())
+            .unwrap_or("No name");
+        let mut content = column![text(display_name).size(22)].spacing(5);
+        if !self.user_name.trim().is_empty() && !self.name.trim().is_empty() {
+            content = content.push(
+                text(format!("@{}", self.name.trim()))
+                    .size(14)
+                    .style(style::Text::Placeholder),
+            );
+        }
+        let nip05 = self.nostr_addrs.trim();
+        if !nip05.is_empty() {
+            content = content.push(
+                row![text(nip05).size(14), check_badge(self.checks.get(nip05))]
+                    .align_items(Alignment::Center)
+                    .spacing(5),
+            );
+        }
+        if !self.about.trim().is_empty() {
+            content = content.push(
+                rich_text(self.about.trim())
+                    .size(14)
+                    .text_direction(text_direction),
+            );
+        }
+        if !self.website.trim().is_empty() {
+            content = content.push(
+                text(self.website.trim())
+                    .size(14)
+                    .style(style::Text::Primary),
+            );
+        }
+        let lightning = [&self.ln_addrs, &self.ln_url]
+            .into_iter()
+            .map(|address| address.trim())
+            .find(|address| !address.is_empty());
+        if let Some(address) = lightning {
+            content = content.push(text(format!("⚡ {}", address)).size(14));
+        }
+
+        column![
+            text("Preview").size(14).style(style::Text::Placeholder),
+            container(content)
+                .padding(15)
+                .width(Length::Fill)
+                .style(style::Container::Frame)
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    /// Changes against the published profile, confirmed before broadcasting
+    fn review_view<'a>(&'a self, diff: &'a MetadataDiff) -> Element<'a, Message> {
+        let title_group = container(title("Review profile changes"))
+            .width(Length::Fill)
+            .height(HEADER_HEIGHT);
+
+        let changes: Element<_> = if diff.is_empty() {
+            text("Nothing changed since the last published profile")
+                .style(style::Text::Placeholder)
+                .into()
+        } else {
+            diff.changes
+                .iter()
+                .fold(column![].spacing(15), |col, change| {
+                    col.push(self.change_row(change))
+                })
+                .into()
+        };
+        let has_failed = diff.changes.iter().any(|change| {
+            matches!(
+                self.check_of(change.edited.as_ref()),
+                Some(CheckStatus::Invalid(_))
+            )
+        });
+        let warning = if has_failed {
+            "Some checks failed, the profile can still be published"
+        } else {
+            ""
+        };
+
+        let back_btn = button("Back")
+            .padding(10)
+            .style(style::Button::Bordered)
+            .on_press(Message::BackPress);
+        let mut publish_btn = button("Publish").padding(10);
+        if !diff.is_empty() {
+            publish_btn = publish_btn.on_press(Message::PublishPress);
+        }
+        let footer_row = container(
+            row![
+                text(warning)
+                    .size(14)
+                    .style(style::Text::Danger)
+                    .width(Length::Fill),
+                back_btn,
+                publish_btn
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .height(FOOTER_HEIGHT);
+
+        container(
+            column![
+                title_group,
+                container(common_scrollable(changes)).height(Length::Fill),
+                footer_row
+            ]
+            .padding([20, 20, 0, 0])
+            .spacing(10),
+        )
+        .into()
+    }
+
+    fn change_row<'a>(&self, change: &FieldChange) -> Element<'a, Message> {
+        let published = change.published.as_deref().unwrap_or("Not set");
+        let edited = change.edited.as_deref().unwrap_or("Removed");
+        column![
+            row![
+                text(change.field.label()),
+                check_badge(self.check_of(change.edited.as_ref()))
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+            text(format!("Published: {}", published))
+                .size(14)
+                .style(style::Text::Placeholder),
+            text(format!("New: {}", edited)).size(14),
+        ]
+        .spacing(2)
+        .into()
+    }
+}
+
+impl PairingView {
+    fn view(&self, format: &TimeFormatConfig) -> Element<Message> {
+        let title_group = container(title("Pair new device"))
+            .width(Length::Fill)
+            .height(HEADER_HEIGHT);
+        let hint = text(
+            "On the other device choose \"Pair With Another Device\" at the login, \
+            scan or paste the payload and type the code. The code is never in the QR, \
+            keep it off screenshots.",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let content: Element<_> = match (&self.pairing, &self.error) {
+            (_, Some(error)) => text(error).style(style::Text::Danger).into(),
+            (None, None) => text("Encrypting your keys...")
+                .style(style::Text::Placeholder)
+                .into(),
+            (Some(pairing), None) => {
+                let qr_code: Element<_> = match &self.qr_code {
+                    Some(handle) => iced_image(handle.to_owned())
+                        .width(PAIRING_QR_SIZE)
+                        .height(PAIRING_QR_SIZE)
+                        .into(),
+                    None => text("The payload is too long for a QR, copy it instead")
+                        .style(style::Text::Placeholder)
+                        .into(),
+                };
+                let expires = ns_event_to_naive(pairing.expires_at)
+                    .map(|expires_at| {
+                        format!("Expires at {}", time_format::short(expires_at, format))
+                    })
+                    .unwrap_or_default();
+                column![
+                    qr_code,
+                    text("Code").size(14).style(style::Text::Placeholder),
+                    text(&pairing.code).size(32),
+                    text(expires).size(14).style(style::Text::Placeholder),
+                    button("Copy payload")
+                        .padding(5)
+                        .style(style::Button::Bordered)
+                        .on_press(Message::CopyPairingPress(pairing.payload.clone())),
+                ]
+                .align_items(Alignment::Center)
+                .spacing(10)
+                .into()
+            }
+        };
+
+        let footer_row = container(
+            row![
+                Space::with_width(Length::Fill),
+                button("New code")
+                    .padding(10)
+                    .style(style::Button::Bordered)
+                    .on_press(Message::NewPairingPress),
+                button("Done")
+                    .padding(10)
+                    .on_press(Message::ClosePairingPress)
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .height(FOOTER_HEIGHT);
+
+        container(
+            column![
+                title_group,
+                hint,
+                container(content)
+                    .center_x()
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+                footer_row
+            ]
+            .padding([20, 20, 0, 0])
+            .spacing(10),
+        )
+        .into()
+    }
+}
+
+impl SigningLogView {
+    fn view(&self, format: &TimeFormatConfig) -> Element<Message> {
+        let title_group = container(title("Signing log"))
+            .width(Length::Fill)
+            .height(HEADER_HEIGHT);
+        let hint = text(
+            "Every event signed with your keys, where it was meant to go and when. \
+            Entries can't be edited or removed.",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let content: Element<_> = match &self.entries {
+            None => text("Loading...").style(style::Text::Placeholder).into(),
+            Some(entries) if entries.is_empty() => text("Nothing signed yet")
+                .style(style::Text::Placeholder)
+                .into(),
+            Some(entries) => {
+                common_scrollable(entries.iter().fold(column![].spacing(5), |col, entry| {
+                    col.push(signing_log_row(entry, format))
+                }))
+                .into()
+            }
+        };
+        let shown = self.entries.as_ref().map(Vec::len).unwrap_or_default() as i64;
+        let count = if self.total > shown {
+            format!("Latest {} of {} signed events", shown, self.total)
+        } else {
+            format!("{} signed events", self.total)
+        };
+
+        let footer_row = container(
+            row![
+                text(count)
+                    .size(14)
+                    .style(style::Text::Placeholder)
+                    .width(Length::Fill),
+                button("Done")
+                    .padding(10)
+                    .on_press(Message::CloseSigningLogPress)
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        )
+        .width(Length::Fill)
+        .height(FOOTER_HEIGHT);
+
+        container(
+            column![
+                title_group,
+                hint,
+                container(content).width(Length::Fill).height(Length::Fill),
+                footer_row
+            ]
+            .padding([20, 20, 0, 0])
+            .spacing(10),
+        )
+        .into()
+    }
+}
+
+fn signing_log_row(
+    entry: &SigningLogEntry,
+    format: &TimeFormatConfig,
+) -> Element<'static, Message> {
+    let target = match &entry.target {
+        Some(target) if target.contains("://") => target.to_owned(),
+        Some(target) => hide_string(target, TARGET_OPEN_CHARS),
+        None => "-".into(),
+    };
+    let relays = match entry.relays.as_slice() {
+        [] => "Not sent to relays".to_owned(),
+        [relay] => relay.to_string(),
+        relays => format!("{} relays", relays.len()),
+    };
+    let relays: Element<_> = if entry.relays.len() > 1 {
+        let list = entry
+            .relays
+            .iter()
+            .map(|relay| relay.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        tooltip(text(relays).size(14), list, tooltip::Position::Left)
+            .style(style::Container::TooltipBg)
+            .into()
+    } else {
+        text(relays).size(14).into()
+    };
+
+    container(
+        row![
+            text(time_format::precise(entry.signed_at, format))
+                .size(14)
+                .width(Length::Fixed(SIGNED_AT_WIDTH)),
+            text(kind_name(entry.kind))
+                .size(14)
+                .width(Length::Fixed(KIND_WIDTH)),
+            text(target)
+                .size(14)
+                .style(style::Text::Placeholder)
+                .width(Length::Fill),
+            relays,
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
+}
+
+fn kind_name(kind: Kind) -> String {
+    match kind.as_u32() {
+        0 => "Profile".into(),
+        1 => "Note".into(),
+        3 => "Contact list".into(),
+        4 => "Direct message".into(),
+        5 => "Deletion".into(),
+        7 => "Reaction".into(),
+        40 => "Channel creation".into(),
+        41 => "Channel metadata".into(),
+        42 => "Channel message".into(),
+        10000 => "Mute list".into(),
+        10002 => "Relay list".into(),
+        22242 => "Relay login".into(),
+        27235 => "Upload authorization".into(),
+        30315 => "Status".into(),
+        other => format!("Kind {}", other),
+    }
+}
+
+fn with_upload_btn<'a>(
+    input: impl Into<Element<'a, Message>>,
+    kind: ProfileImage,
+) -> Element<'a, Message> {
+    let upload_btn = button("Upload")
+        .padding(5)
+        .style(style::Button::Bordered)
+        .on_press(Message::UploadPress(kind));
+    row![container(input).width(Length::Fill), upload_btn]
+        .align_items(Alignment::End)
+        .spacing(10)
+        .into()
+}
+
+/// Picks the part of the image that is uploaded
+fn crop_view(image_crop: &ImageCrop) -> Element<Message> {
+    let (width, height) = image_crop.kind.output_size();
+    let title_group = container(title(format!("Crop your {}", image_crop.kind.label())))
+        .width(Length::Fill)
+        .height(HEADER_HEIGHT);
+    let hint = text(format!(
+        "Drag to move, scroll to zoom. Uploaded as {}x{} to your media server",
+        width, height
+    ))
+    .size(14)
+    .style(style::Text::Placeholder);
+    let crop_area = CropArea::new(
+        image_crop.handle.clone(),
+        image_crop.image_size,
+        image_crop.rect,
+        Message::CropChange,
+    )
+    .height(Length::Fill);
+
+    let status: Element<_> = match (&image_crop.error, image_crop.uploading) {
+        (_, true) => text("Uploading...")
+            .size(14)
+            .style(style::Text::Placeholder)
+            .into(),
+        (Some(error), false) => text(error).size(14).style(style::Text::Danger).into(),
+        (None, false) => text("").into(),
+    };
+    let mut cancel_btn = button("Cancel").padding(10).style(style::Button::Bordered);
+    let mut upload_btn = button("Upload").padding(10);
+    if !image_crop.uploading {
+        cancel_btn = cancel_btn.on_press(Message::CancelCropPress);
+        upload_btn = upload_btn.on_press(Message::ConfirmCropPress);
+    }
+    let footer_row = container(
+        row![
+            container(status).width(Length::Fill),
+            cancel_btn,
+            upload_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .width(Length::Fill)
+    .height(FOOTER_HEIGHT);
+
+    container(
+        column![title_group, hint, crop_area, footer_row]
+            .padding([20, 20, 0, 0])
+            .spacing(10),
+    )
+    .into()
+}
+
+fn check_badge<'a>(status: Option<&CheckStatus>) -> Element<'a, Message> {
+    match status {
+        None => Space::with_width(0).into(),
+        Some(CheckStatus::Checking) => text("Checking...")
+            .size(14)
+            .style(style::Text::Placeholder)
+            .into(),
+        Some(CheckStatus::Valid) => check_icon().size(14).style(style::Text::Primary).into(),
+        Some(CheckStatus::Invalid(reason)) => {
+            text(reason).size(14).style(style::Text::Danger).into()
+        }
+    }
 }
 
 const HEADER_HEIGHT: f32 = 50.0;
 const FOOTER_HEIGHT: f32 = 50.0;
+const PAIRING_QR_SIZE: f32 = 240.0;
+const SIGNED_AT_WIDTH: f32 = 200.0;
+const KIND_WIDTH: f32 = 160.0;
+const TARGET_OPEN_CHARS: usize = 10;
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
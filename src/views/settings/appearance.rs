@@ -1,17 +1,48 @@
-use iced::widget::{column, container, radio, row, scrollable, scrollable::Properties, text};
+use chrono::Utc;
+use iced::widget::{
+    checkbox, column, container, radio, row, scrollable, scrollable::Properties, text,
+};
 use iced::Alignment;
 
 use crate::{
     components::text::title,
+    config::{
+        AutoLockConfig, ClockFormat, DateOrder, PresenceConfig, SendKey, StartupView, StatusChoice,
+        TextDirection, TimeFormatConfig,
+    },
     style::{self},
+    utils::time_format,
     widget::Element,
 };
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ChangeTheme(style::Theme),
+    ChangeSendKey(SendKey),
+    ToggleDimImages(bool),
+    ToggleAutoLock(bool),
+    ChangeLockMinutes(u32),
+    ChangeClockFormat(ClockFormat),
+    ToggleRelativeTimes(bool),
+    ChangeDateOrder(DateOrder),
+    ChangeTextDirection(TextDirection),
+    ChangeStartupView(StartupView),
+    TogglePublishStatus(bool),
+    ToggleAutoAway(bool),
+    ChangeAwayMinutes(u32),
+    ChangeStatus(StatusChoice),
 }
-pub fn view(selected_theme: Option<style::Theme>) -> Element<'static, Message> {
+#[allow(clippy::too_many_arguments)]
+pub fn view(
+    selected_theme: Option<style::Theme>,
+    send_key: SendKey,
+    dim_images: bool,
+    auto_lock: AutoLockConfig,
+    time: TimeFormatConfig,
+    direction: TextDirection,
+    startup_view: StartupView,
+    presence: PresenceConfig,
+) -> Element<'static, Message> {
     let title = title("Appearance");
     let light_themes =
         style::Theme::LIGHT
@@ -39,12 +70,170 @@ pub fn view(selected_theme: Option<style::Theme>) -> Element<'static, Message> {
                 ))
             });
     let dark_themes = scrollable(dark_themes).horizontal_scroll(Properties::default());
-    let dark_themes = column![text("Dark Themes").size(24), dark_themes].spacing(10);
+    let dim_images = checkbox(
+        "Dim images in dark themes",
+        dim_images,
+        Message::ToggleDimImages,
+    );
+    let dark_themes = column![text("Dark Themes").size(24), dark_themes, dim_images].spacing(10);
+
+    let send_keys = SendKey::ALL
+        .into_iter()
+        .fold(column![].spacing(10), |column, key| {
+            column.push(radio(
+                key.to_string(),
+                key,
+                Some(send_key),
+                Message::ChangeSendKey,
+            ))
+        });
+    let composer = column![
+        text("Composer").size(24),
+        send_keys,
+        text(send_key.hint()).size(14)
+    ]
+    .spacing(10);
+
+    let lock_minutes =
+        AutoLockConfig::MINUTES
+            .into_iter()
+            .fold(row![].spacing(20), |row, minutes| {
+                row.push(radio(
+                    format!("{} min", minutes),
+                    minutes,
+                    Some(auto_lock.idle_minutes),
+                    Message::ChangeLockMinutes,
+                ))
+            });
+    let lock = column![
+        text("Lock").size(24),
+        checkbox(
+            "Lock when idle, the secret key unlocks",
+            auto_lock.enabled,
+            Message::ToggleAutoLock,
+        ),
+        lock_minutes,
+    ]
+    .spacing(10);
+
+    let clock_formats = ClockFormat::ALL
+        .into_iter()
+        .fold(row![].spacing(20), |row, clock| {
+            row.push(radio(
+                clock.to_string(),
+                clock,
+                Some(time.clock),
+                Message::ChangeClockFormat,
+            ))
+        });
+    let date_orders = DateOrder::ALL
+        .into_iter()
+        .fold(row![].spacing(20), |row, order| {
+            row.push(radio(
+                order.to_string(),
+                order,
+                Some(time.date_order),
+                Message::ChangeDateOrder,
+            ))
+        });
+    let time_example = format!(
+        "Example: {}",
+        time_format::precise(Utc::now().naive_utc(), &time)
+    );
+    let time = column![
+        text("Time").size(24),
+        clock_formats,
+        date_orders,
+        checkbox(
+            "Relative times for recent messages, like \"5 min ago\"",
+            time.relative,
+            Message::ToggleRelativeTimes,
+        ),
+        text(time_example).size(14),
+    ]
+    .spacing(10);
 
-    column![title, light_themes, dark_themes]
-        .spacing(20)
-        .padding([20, 0, 0, 0])
-        .into()
+    let directions = TextDirection::ALL
+        .into_iter()
+        .fold(row![].spacing(20), |row, choice| {
+            row.push(radio(
+                choice.to_string(),
+                choice,
+                Some(direction),
+                Message::ChangeTextDirection,
+            ))
+        });
+    let direction = column![
+        text("Text direction").size(24),
+        directions,
+        text("Forcing a direction is meant for checking layouts").size(14),
+    ]
+    .spacing(10);
+
+    let startup_views = StartupView::ALL
+        .into_iter()
+        .fold(row![].spacing(20), |row, choice| {
+            row.push(radio(
+                choice.to_string(),
+                choice,
+                Some(startup_view),
+                Message::ChangeStartupView,
+            ))
+        });
+    let startup = column![text("Open after login").size(24), startup_views].spacing(10);
+
+    let statuses = StatusChoice::ALL
+        .into_iter()
+        .fold(row![].spacing(20), |row, status| {
+            row.push(radio(
+                status.to_string(),
+                status,
+                Some(presence.status),
+                Message::ChangeStatus,
+            ))
+        });
+    let away_minutes =
+        PresenceConfig::MINUTES
+            .into_iter()
+            .fold(row![].spacing(20), |row, minutes| {
+                row.push(radio(
+                    format!("{} min", minutes),
+                    minutes,
+                    Some(presence.away_minutes),
+                    Message::ChangeAwayMinutes,
+                ))
+            });
+    let status = column![
+        text("Status").size(24),
+        checkbox(
+            "Publish my status to my contacts",
+            presence.publish,
+            Message::TogglePublishStatus,
+        ),
+        statuses,
+        checkbox(
+            "Away when idle, available again on activity",
+            presence.auto_away,
+            Message::ToggleAutoAway,
+        ),
+        away_minutes,
+    ]
+    .spacing(10);
+
+    column![
+        title,
+        light_themes,
+        dark_themes,
+        composer,
+        time,
+        direction,
+        startup,
+        lock,
+        status
+    ]
+    .spacing(20)
+    .padding([20, 0, 0, 0])
+    .into()
 }
 
 fn vertical_radio<V, Message: 'static>(
@@ -1,24 +1,40 @@
-use crate::components::copy_btn;
+use std::str::FromStr;
+
 use crate::components::text::title;
-use crate::db::DbEvent;
+use crate::components::text_input_group::TextInputGroup;
+use crate::components::{common_scrollable, copy_btn};
+use crate::consts::YMD_FORMAT;
+use crate::db::{DbEvent, EventFilter};
 use crate::error::BackendClosed;
 use crate::net::{self, BackEndConnection, BackendEvent};
-use crate::utils::hide_string;
+use crate::style;
+use crate::utils::{hide_string, parse_key};
 use crate::{db::DbContact, widget::Element};
-use iced::widget::{button, column, row, text};
-use iced::{clipboard, Alignment, Command};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use iced::widget::{button, checkbox, column, row, text};
+use iced::{clipboard, Alignment, Command, Length};
 use nostr::prelude::ToBech32;
-use nostr::Keys;
+use nostr::secp256k1::XOnlyPublicKey;
+use nostr::{EventId, Keys, Kind};
 
 pub enum Listener {
     Contacts,
+    ContactsCsv,
     Messages,
+    FilteredEvents,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ExportContacts,
+    ExportContactsCsv,
     ExportMessages,
+    ToggleExportKind(Kind, bool),
+    ExportAuthorsChange(String),
+    ExportSinceChange(String),
+    ExportUntilChange(String),
+    ExportChannelsChange(String),
+    ExportFilteredEvents,
     HidePublicKey,
     ShowPublicKey,
     CopyPublicKey,
@@ -36,7 +52,12 @@ pub struct State {
     contacts: Vec<DbContact>,
     messages: Vec<DbEvent>,
     contacts_state: LoadingState,
+    contacts_csv_state: LoadingState,
     messages_state: LoadingState,
+    export_form: ExportForm,
+    /// Events matching the form, `None` while counting or when it's invalid
+    export_count: Option<i64>,
+    filtered_state: LoadingState,
     listening_to: Option<Listener>,
     public_key_visible: bool,
     secret_key_visible: bool,
@@ -47,12 +68,17 @@ impl State {
         conn.send(net::ToBackend::FetchContacts)?;
         conn.send(net::ToBackend::FetchAllMessageEvents)?;
         conn.send(net::ToBackend::FetchKeys)?;
+        conn.send(net::ToBackend::CountFilteredEvents(EventFilter::default()))?;
 
         Ok(Self {
             contacts: Vec::new(),
             messages: Vec::new(),
             contacts_state: LoadingState::Idle,
+            contacts_csv_state: LoadingState::Idle,
             messages_state: LoadingState::Idle,
+            export_form: ExportForm::default(),
+            export_count: None,
+            filtered_state: LoadingState::Idle,
             listening_to: None,
             public_key_visible: false,
             secret_key_visible: false,
@@ -68,14 +94,24 @@ impl State {
             BackendEvent::GotAllMessages(all_messages) => {
                 self.messages = all_messages;
             }
+            BackendEvent::FilteredEventsCount(filter, count) => {
+                // an older count must not overwrite this one
+                if self.export_form.to_filter().as_ref() == Some(&filter) {
+                    self.export_count = Some(count);
+                }
+            }
             BackendEvent::RFDSavedFile(_path) => match self.listening_to {
                 Some(Listener::Contacts) => self.contacts_state = LoadingState::Success,
+                Some(Listener::ContactsCsv) => self.contacts_csv_state = LoadingState::Success,
                 Some(Listener::Messages) => self.messages_state = LoadingState::Success,
+                Some(Listener::FilteredEvents) => self.filtered_state = LoadingState::Success,
                 None => (),
             },
             BackendEvent::RFDCancelPick => match self.listening_to {
                 Some(Listener::Contacts) => self.contacts_state = LoadingState::Idle,
+                Some(Listener::ContactsCsv) => self.contacts_csv_state = LoadingState::Idle,
                 Some(Listener::Messages) => self.messages_state = LoadingState::Idle,
+                Some(Listener::FilteredEvents) => self.filtered_state = LoadingState::Idle,
                 None => (),
             },
             BackendEvent::GotKeys(keys) => self.keys = Some(keys),
@@ -95,11 +131,46 @@ impl State {
                 self.listening_to = Some(Listener::Contacts);
                 conn.send(net::ToBackend::ExportContacts)?;
             }
+            Message::ExportContactsCsv => {
+                self.contacts_csv_state = LoadingState::Loading;
+                self.listening_to = Some(Listener::ContactsCsv);
+                conn.send(net::ToBackend::ExportContactsCsv)?;
+            }
             Message::ExportMessages => {
                 self.messages_state = LoadingState::Loading;
                 self.listening_to = Some(Listener::Messages);
                 conn.send(net::ToBackend::ExportMessages(self.messages.clone()))?;
             }
+            Message::ToggleExportKind(kind, selected) => {
+                self.export_form.kinds.retain(|k| k != &kind);
+                if selected {
+                    self.export_form.kinds.push(kind);
+                }
+                self.export_form_changed(conn)?;
+            }
+            Message::ExportAuthorsChange(authors) => {
+                self.export_form.authors = authors;
+                self.export_form_changed(conn)?;
+            }
+            Message::ExportSinceChange(since) => {
+                self.export_form.since = since;
+                self.export_form_changed(conn)?;
+            }
+            Message::ExportUntilChange(until) => {
+                self.export_form.until = until;
+                self.export_form_changed(conn)?;
+            }
+            Message::ExportChannelsChange(channels) => {
+                self.export_form.channels = channels;
+                self.export_form_changed(conn)?;
+            }
+            Message::ExportFilteredEvents => {
+                if let Some(filter) = self.export_form.to_filter() {
+                    self.filtered_state = LoadingState::Loading;
+                    self.listening_to = Some(Listener::FilteredEvents);
+                    conn.send(net::ToBackend::ExportFilteredEvents(filter))?;
+                }
+            }
             Message::ShowPublicKey => {
                 self.public_key_visible = true;
             }
@@ -139,6 +210,93 @@ impl State {
         Ok(Command::batch(commands))
     }
 
+    /// Counts again what the new filter matches
+    fn export_form_changed(&mut self, conn: &mut BackEndConnection) -> Result<(), BackendClosed> {
+        self.export_count = None;
+        self.filtered_state = LoadingState::Idle;
+        if let Some(filter) = self.export_form.to_filter() {
+            conn.send(net::ToBackend::CountFilteredEvents(filter))?;
+        }
+        Ok(())
+    }
+
+    fn export_filter_group(&self) -> Element<Message> {
+        let form = &self.export_form;
+        let kinds = EXPORT_KINDS
+            .chunks(KINDS_PER_ROW)
+            .fold(column![].spacing(5), |col, chunk| {
+                col.push(chunk.iter().fold(row![].spacing(10), |row, (kind, label)| {
+                    let kind = *kind;
+                    row.push(
+                        checkbox(*label, form.kinds.contains(&kind), move |selected| {
+                            Message::ToggleExportKind(kind, selected)
+                        })
+                        .width(Length::Fixed(KIND_CHECKBOX_WIDTH)),
+                    )
+                }))
+            });
+
+        let mut authors_input =
+            TextInputGroup::new("Authors", &form.authors, Message::ExportAuthorsChange)
+                .placeholder("Anyone, npub or hex separated by commas");
+        if parse_authors(&form.authors).is_none() {
+            authors_input = authors_input.invalid("Not a public key");
+        }
+        let mut since_input = TextInputGroup::new("From", &form.since, Message::ExportSinceChange)
+            .placeholder("YYYY-MM-DD");
+        if parse_date(&form.since).is_none() {
+            since_input = since_input.invalid("Not a YYYY-MM-DD date");
+        }
+        let mut until_input = TextInputGroup::new("To", &form.until, Message::ExportUntilChange)
+            .placeholder("YYYY-MM-DD");
+        if parse_date(&form.until).is_none() {
+            until_input = until_input.invalid("Not a YYYY-MM-DD date");
+        }
+        let mut channels_input =
+            TextInputGroup::new("Channels", &form.channels, Message::ExportChannelsChange)
+                .placeholder("Any, channel ids separated by commas");
+        if parse_channels(&form.channels).is_none() {
+            channels_input = channels_input.invalid("Not a channel id");
+        }
+
+        let filter = form.to_filter();
+        let estimate = match (&filter, self.export_count) {
+            (None, _) => text("Fix the filter to see how many events match"),
+            (Some(_), None) => text("Counting..."),
+            (Some(filter), Some(count)) if filter.is_empty() => {
+                text(format!("All {} stored events", count))
+            }
+            (Some(_), Some(1)) => text("1 event matches"),
+            (Some(_), Some(count)) => text(format!("{} events match", count)),
+        };
+
+        let mut export_btn = button("Export events");
+        match self.filtered_state {
+            LoadingState::Idle => {
+                if self.export_count.map_or(false, |count| count > 0) {
+                    export_btn = export_btn.on_press(Message::ExportFilteredEvents)
+                }
+            }
+            LoadingState::Loading => export_btn = button("Loading..."),
+            LoadingState::Success => export_btn = button("Saved!"),
+        }
+
+        column![
+            text("Export events").size(24),
+            text("Kinds").size(18),
+            kinds,
+            text("Every kind is exported when none is checked")
+                .size(14)
+                .style(style::Text::Placeholder),
+            authors_input.build(),
+            row![since_input.build(), until_input.build()].spacing(10),
+            channels_input.build(),
+            row![estimate.width(Length::Fill), export_btn].align_items(Alignment::Center),
+        ]
+        .spacing(10)
+        .into()
+    }
+
     pub fn view(&self) -> Element<Message> {
         let page_title = title("Backup");
 
@@ -150,9 +308,17 @@ impl State {
             LoadingState::Loading => export_contacts_btn = button("Loading..."),
             LoadingState::Success => export_contacts_btn = button("Saved!"),
         }
+        let mut export_csv_btn = button("Export as CSV");
+        match self.contacts_csv_state {
+            LoadingState::Idle => {
+                export_csv_btn = export_csv_btn.on_press(Message::ExportContactsCsv)
+            }
+            LoadingState::Loading => export_csv_btn = button("Loading..."),
+            LoadingState::Success => export_csv_btn = button("Saved!"),
+        }
         let contacts_group = column![
             row![text(format!("Number of contacts: {}", self.contacts.len())),].spacing(4),
-            export_contacts_btn,
+            row![export_contacts_btn, export_csv_btn].spacing(10),
         ]
         .spacing(5);
 
@@ -179,10 +345,18 @@ impl State {
             keys_group = keys_group.push(text("Loading keys..."));
         };
 
-        column![page_title, contacts_group, messages_group, keys_group]
+        common_scrollable(
+            column![
+                page_title,
+                contacts_group,
+                messages_group,
+                self.export_filter_group(),
+                keys_group
+            ]
             .padding([20, 20, 0, 0])
-            .spacing(10)
-            .into()
+            .spacing(10),
+        )
+        .into()
     }
 
     fn make_public_key(&self, keys: &Keys) -> Element<Message> {
@@ -249,4 +423,87 @@ impl State {
     }
 }
 
+/// Export filter as typed, see `to_filter`
+#[derive(Default)]
+struct ExportForm {
+    kinds: Vec<Kind>,
+    authors: String,
+    since: String,
+    until: String,
+    channels: String,
+}
+impl ExportForm {
+    /// `None` while some field is invalid. Dates are local days, both included
+    fn to_filter(&self) -> Option<EventFilter> {
+        Some(EventFilter {
+            kinds: self.kinds.clone(),
+            authors: parse_authors(&self.authors)?,
+            since: parse_date(&self.since)?.map(local_midnight_utc),
+            until: parse_date(&self.until)?
+                .map(|date| local_midnight_utc(date + Duration::days(1))),
+            channels: parse_channels(&self.channels)?,
+        })
+    }
+}
+
+/// Outer `None` when invalid, inner `None` when empty
+fn parse_date(input: &str) -> Option<Option<NaiveDate>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Some(None);
+    }
+    NaiveDate::parse_from_str(input, YMD_FORMAT).ok().map(Some)
+}
+
+fn local_midnight_utc(date: NaiveDate) -> NaiveDateTime {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map_or(midnight, |local| local.naive_utc())
+}
+
+fn split_list(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+}
+
+/// `None` when some key is invalid
+fn parse_authors(input: &str) -> Option<Vec<XOnlyPublicKey>> {
+    split_list(input)
+        .map(|key| {
+            // a secret key would parse as hex
+            if key.starts_with("nsec") {
+                return None;
+            }
+            let hex = parse_key(key.to_owned()).ok()?;
+            XOnlyPublicKey::from_str(&hex).ok()
+        })
+        .collect()
+}
+
+/// `None` when some id is invalid
+fn parse_channels(input: &str) -> Option<Vec<EventId>> {
+    split_list(input)
+        .map(|id| {
+            let hex = parse_key(id.to_owned()).ok()?;
+            EventId::from_hex(hex).ok()
+        })
+        .collect()
+}
+
+const EXPORT_KINDS: [(Kind, &str); 9] = [
+    (Kind::Metadata, "Profiles"),
+    (Kind::TextNote, "Notes"),
+    (Kind::ContactList, "Contact lists"),
+    (Kind::EncryptedDirectMessage, "Direct messages"),
+    (Kind::EventDeletion, "Deletions"),
+    (Kind::Reaction, "Reactions"),
+    (Kind::ChannelCreation, "Channel creations"),
+    (Kind::ChannelMetadata, "Channel metadata"),
+    (Kind::ChannelMessage, "Channel messages"),
+];
+const KINDS_PER_ROW: usize = 3;
+const KIND_CHECKBOX_WIDTH: f32 = 180.0;
 const OPEN_VALUE: usize = 16;
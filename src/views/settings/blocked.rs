@@ -0,0 +1,110 @@
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::consts::YMD_FORMAT;
+use crate::db::DbBlocked;
+use crate::error::BackendClosed;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::utils::{from_naive_utc_to_local, hide_string};
+use crate::widget::Element;
+use iced::widget::{button, column, container, row, text};
+use iced::{Alignment, Length};
+use nostr::prelude::ToBech32;
+use nostr::secp256k1::XOnlyPublicKey;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    UnblockPress(XOnlyPublicKey),
+}
+
+pub struct State {
+    blocked: Vec<(DbBlocked, Option<String>)>,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchBlocked)?;
+        Ok(Self { blocked: vec![] })
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        if let BackendEvent::GotBlocked(blocked) = event {
+            self.blocked = blocked;
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::UnblockPress(pubkey) => conn.send(ToBackend::UnblockUser(pubkey))?,
+        }
+        Ok(())
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Blocked");
+        let hint = text(
+            "Events from blocked users are dropped when received and their conversations are hidden",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let list: Element<_> = if self.blocked.is_empty() {
+            text("Nobody is blocked")
+                .style(style::Text::Placeholder)
+                .into()
+        } else {
+            self.blocked
+                .iter()
+                .fold(column![].spacing(5), |col, (blocked, name)| {
+                    col.push(blocked_row(blocked, name.as_deref()))
+                })
+                .into()
+        };
+
+        container(
+            column![title, hint, common_scrollable(list)]
+                .padding([20, 20, 0, 0])
+                .spacing(10),
+        )
+        .into()
+    }
+}
+
+fn blocked_row<'a>(blocked: &'a DbBlocked, name: Option<&'a str>) -> Element<'a, Message> {
+    let npub = blocked
+        .pubkey
+        .to_bech32()
+        .unwrap_or_else(|_| blocked.pubkey.to_string());
+    let name = name.map_or_else(|| hide_string(&npub, 12), |name| name.to_owned());
+    let details = format!(
+        "Blocked {}{}",
+        from_naive_utc_to_local(blocked.created_at).format(YMD_FORMAT),
+        if blocked.on_mute_list {
+            ", on the public mute list"
+        } else {
+            ""
+        }
+    );
+    let unblock_btn = button(text("Unblock").size(14))
+        .style(style::Button::Bordered)
+        .on_press(Message::UnblockPress(blocked.pubkey));
+
+    container(
+        row![
+            column![
+                text(name),
+                text(details).size(14).style(style::Text::Placeholder)
+            ]
+            .width(Length::Fill),
+            unblock_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
+}
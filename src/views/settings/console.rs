@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use iced::widget::{button, checkbox, column, container, row, text, text_input, Space};
+use iced::{Alignment, Length};
+use nostr::EventId;
+use url::Url;
+
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::error::BackendClosed;
+use crate::net::console::ConsoleRow;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::utils::{from_naive_utc_to_local, hide_string};
+use crate::widget::Element;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FilterChange(String),
+    ToggleRelay(Url, bool),
+    RunPress,
+    ClearPress,
+    RowPress(EventId),
+}
+
+pub struct State {
+    filter_input: String,
+    /// Relays and whether the REQ is sent to them
+    relays: Vec<(Url, bool)>,
+    rows: Vec<ConsoleRow>,
+    eose: Vec<(Url, Duration)>,
+    error: Option<String>,
+    /// Row showing its raw JSON
+    expanded: Option<EventId>,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchRelays)?;
+        Ok(Self {
+            filter_input: DEFAULT_FILTER.into(),
+            relays: vec![],
+            rows: vec![],
+            eose: vec![],
+            error: None,
+            expanded: None,
+        })
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        match event {
+            BackendEvent::GotRelays(db_relays) => {
+                self.relays = db_relays
+                    .into_iter()
+                    .map(|db_relay| (db_relay.url, db_relay.read))
+                    .collect();
+            }
+            BackendEvent::ConsoleEvent(row) => {
+                if self.rows.len() < MAX_ROWS {
+                    self.rows.push(row);
+                }
+            }
+            BackendEvent::ConsoleEose(url, elapsed) => {
+                self.eose.push((url, elapsed));
+            }
+            BackendEvent::ConsoleError(e) => {
+                self.error = Some(e);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::FilterChange(text) => {
+                self.filter_input = text;
+                self.error = None;
+            }
+            Message::ToggleRelay(url, selected) => {
+                if let Some((_, is_selected)) = self.relays.iter_mut().find(|(u, _)| u == &url) {
+                    *is_selected = selected;
+                }
+            }
+            Message::RunPress => {
+                let relays: Vec<_> = self
+                    .relays
+                    .iter()
+                    .filter(|(_, selected)| *selected)
+                    .map(|(url, _)| url.to_owned())
+                    .collect();
+                if relays.is_empty() {
+                    self.error = Some("Pick at least one relay".into());
+                    return Ok(());
+                }
+                self.clear();
+                conn.send(ToBackend::RunConsoleReq(
+                    self.filter_input.to_owned(),
+                    relays,
+                ))?;
+            }
+            Message::ClearPress => self.clear(),
+            Message::RowPress(event_id) => {
+                self.expanded = if self.expanded == Some(event_id) {
+                    None
+                } else {
+                    Some(event_id)
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.rows.clear();
+        self.eose.clear();
+        self.error = None;
+        self.expanded = None;
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Console");
+
+        let filter_input = text_input("Filter JSON", &self.filter_input)
+            .on_input(Message::FilterChange)
+            .on_submit(Message::RunPress)
+            .style(style::TextInput::ChatSearch);
+        let error: Element<_> = match &self.error {
+            Some(e) => text(e).size(14).style(style::Text::Danger).into(),
+            None => text("A filter object or an array of them, events are not stored")
+                .size(14)
+                .style(style::Text::Placeholder)
+                .into(),
+        };
+
+        let relays = self
+            .relays
+            .iter()
+            .fold(column![].spacing(2), |col, (url, selected)| {
+                let url_1 = url.to_owned();
+                col.push(
+                    checkbox(url.as_str(), *selected, move |selected| {
+                        Message::ToggleRelay(url_1.to_owned(), selected)
+                    })
+                    .size(14),
+                )
+            });
+
+        let buttons = row![
+            button("Run REQ")
+                .style(style::Button::Primary)
+                .on_press(Message::RunPress),
+            button("Clear")
+                .style(style::Button::Bordered)
+                .on_press(Message::ClearPress),
+            Space::with_width(Length::Fill),
+            text(format!("{} events", self.rows.len())).size(14),
+        ]
+        .align_items(Alignment::Center)
+        .spacing(5);
+
+        let eose = self
+            .eose
+            .iter()
+            .fold(column![].spacing(2), |col, (url, elapsed)| {
+                col.push(
+                    text(format!("EOSE {} in {} ms", url, elapsed.as_millis()))
+                        .size(14)
+                        .style(style::Text::Placeholder),
+                )
+            });
+
+        let header = row![
+            text("Relay").width(RELAY_WIDTH),
+            text("Kind").width(KIND_WIDTH),
+            text("Author").width(Length::Fill),
+            text("Created").width(CREATED_WIDTH),
+            text("ms").width(MS_WIDTH),
+        ]
+        .spacing(5);
+        let table = self
+            .rows
+            .iter()
+            .fold(column![header].spacing(2), |col, row| {
+                col.push(self.row_view(row))
+            });
+
+        container(common_scrollable(
+            column![title, filter_input, error, relays, buttons, eose, table]
+                .spacing(10)
+                .padding([20, 20, 0, 0]),
+        ))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn row_view<'a>(&self, console_row: &'a ConsoleRow) -> Element<'a, Message> {
+        let created = console_row
+            .created_at
+            .map(|date| {
+                from_naive_utc_to_local(date)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let cells = row![
+            text(console_row.relay.host_str().unwrap_or_default())
+                .size(14)
+                .width(RELAY_WIDTH),
+            text(console_row.kind).size(14).width(KIND_WIDTH),
+            text(hide_string(&console_row.author.to_string(), 8))
+                .size(14)
+                .width(Length::Fill),
+            text(created).size(14).width(CREATED_WIDTH),
+            text(console_row.elapsed.as_millis())
+                .size(14)
+                .width(MS_WIDTH),
+        ]
+        .spacing(5);
+        let row_btn = button(cells)
+            .padding([2, 0])
+            .width(Length::Fill)
+            .style(style::Button::MenuBtn)
+            .on_press(Message::RowPress(console_row.event_id));
+
+        if self.expanded == Some(console_row.event_id) {
+            column![
+                row_btn,
+                container(text(&console_row.json).size(12))
+                    .padding(5)
+                    .width(Length::Fill)
+                    .style(style::Container::Foreground)
+            ]
+            .into()
+        } else {
+            row_btn.into()
+        }
+    }
+}
+
+const DEFAULT_FILTER: &str = r#"{"kinds":[1],"limit":20}"#;
+const MAX_ROWS: usize = 500;
+const RELAY_WIDTH: f32 = 180.0;
+const KIND_WIDTH: f32 = 50.0;
+const CREATED_WIDTH: f32 = 160.0;
+const MS_WIDTH: f32 = 60.0;
@@ -1,12 +1,17 @@
-use iced::widget::{button, column, container, row, text, text_input, tooltip, Space};
+use std::collections::HashSet;
+
+use iced::widget::{button, checkbox, column, container, row, text, text_input, tooltip, Space};
 use iced::{Alignment, Length};
+use nostr::secp256k1::XOnlyPublicKey;
 
+use crate::components::label_chip::label_chip;
 use crate::components::{common_scrollable, contact_row, ContactRow};
-use crate::db::{DbRelay, DbRelayResponse};
+use crate::db::{DbRelay, DbRelayResponse, Labels};
 use crate::error::BackendClosed;
-use crate::icon::{import_icon, plus_icon, satellite_icon};
-use crate::net::{self, BackEndConnection, BackendEvent};
+use crate::icon::{import_icon, plus_icon, satellite_icon, xmark_icon};
+use crate::net::{self, BackEndConnection, BackendEvent, RequestId};
 use crate::style;
+use crate::types::{ContactListDiff, ContactOp};
 use crate::utils::contact_matches_search_full;
 use crate::views::GoToView;
 use crate::widget::Element;
@@ -24,6 +29,18 @@ pub enum Message {
     SearchContactInputChange(String),
     RelaysConfirmationPress(Option<ContactsRelaysResponse>),
     SendDMTo(DbContact),
+    DismissError,
+    ToggleStageChanges(bool),
+    ReviewChanges,
+    ToggleSelected(XOnlyPublicKey, bool),
+    SelectAll(bool),
+    ClearSelection,
+    DeleteSelected,
+    /// Keeps the first, see `ToBackend::MergeContacts`
+    MergeSelected(DbContact, DbContact),
+    AssignLabelToSelected(i64),
+    ExportSelected,
+    RefreshSelected,
 }
 
 #[derive(Debug, Clone)]
@@ -43,22 +60,115 @@ impl ContactsRelaysResponse {
     }
 }
 
+/// Operation applied to the list but not yet confirmed by the backend
+struct PendingContact {
+    /// Request that sent it, a batch sends several operations in one request
+    request: RequestId,
+    op: ContactOp,
+    /// Contact as it was before the operation, `None` when it was being added
+    previous: Option<DbContact>,
+}
+
 pub struct State {
     contacts: Vec<DbContact>,
+    /// In the order they were sent, a contact can have more than one
+    pending: Vec<PendingContact>,
     search_contact_input: String,
     relays_response: Option<ContactsRelaysResponse>,
+    error_msg: Option<String>,
+    stage_changes: bool,
+    staged: ContactListDiff,
+    selected: HashSet<XOnlyPublicKey>,
+    labels: Labels,
 }
 impl State {
     pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
         conn.send(net::ToBackend::FetchContacts)?;
         conn.send(net::ToBackend::FetchRelayResponsesContactList)?;
+        conn.send(net::ToBackend::GetStageContactChanges)?;
+        conn.send(net::ToBackend::FetchLabels)?;
         Ok(Self {
             contacts: vec![],
+            pending: vec![],
             search_contact_input: "".into(),
             relays_response: None,
+            error_msg: None,
+            stage_changes: false,
+            staged: ContactListDiff::default(),
+            selected: HashSet::new(),
+            labels: Labels::default(),
         })
     }
 
+    fn visible_contacts(&self) -> impl Iterator<Item = &DbContact> {
+        self.contacts
+            .iter()
+            .filter(|c| contact_matches_search_full(c, &self.search_contact_input))
+    }
+
+    fn selected_contacts(&self) -> Vec<DbContact> {
+        self.contacts
+            .iter()
+            .filter(|c| self.selected.contains(c.pubkey()))
+            .cloned()
+            .collect()
+    }
+
+    /// Applies the operation sent by `request` to the list before the backend confirms it
+    pub fn apply_optimistic(&mut self, request: RequestId, op: ContactOp) {
+        let previous = self
+            .contacts
+            .iter()
+            .find(|c| c.pubkey() == op.pubkey())
+            .cloned();
+        apply_op(&mut self.contacts, &op);
+        self.pending.push(PendingContact {
+            request,
+            op,
+            previous,
+        });
+    }
+
+    fn pending_position(&self, request: RequestId, pubkey: &XOnlyPublicKey) -> Option<usize> {
+        self.pending
+            .iter()
+            .position(|p| p.request == request && p.op.pubkey() == pubkey)
+    }
+
+    fn confirm_op(&mut self, request: RequestId, db_contact: &DbContact) {
+        if let Some(idx) = self.pending_position(request, db_contact.pubkey()) {
+            self.pending.remove(idx);
+        }
+    }
+
+    fn rollback_op(
+        &mut self,
+        request: RequestId,
+        op: &ContactOp,
+        error: &str,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        if let Some(idx) = self.pending_position(request, op.pubkey()) {
+            let pending = self.pending.remove(idx);
+            let later = self.pending[idx..]
+                .iter_mut()
+                .find(|p| p.op.pubkey() == op.pubkey());
+            match (later, pending.previous) {
+                // the later change stays on screen, failing too goes back to before this one
+                (Some(later), previous) => later.previous = previous,
+                (None, Some(previous)) => {
+                    apply_op(&mut self.contacts, &ContactOp::Update(previous))
+                }
+                (None, None) => apply_op(
+                    &mut self.contacts,
+                    &ContactOp::Delete(op.contact().to_owned()),
+                ),
+            }
+        }
+        self.error_msg = Some(format!("Failed to {} contact: {}", op.label(), error));
+        conn.send(net::ToBackend::FetchContacts)
+    }
+
     pub fn backend_event(
         &mut self,
         event: BackendEvent,
@@ -67,6 +177,23 @@ impl State {
         match event {
             BackendEvent::ConfirmedContactList(_) => {
                 conn.send(net::ToBackend::FetchRelayResponsesContactList)?;
+                if self.stage_changes {
+                    conn.send(net::ToBackend::FetchContactListDiff)?;
+                }
+            }
+            BackendEvent::StageContactChanges(stage) => {
+                self.stage_changes = stage;
+                if stage {
+                    conn.send(net::ToBackend::FetchContactListDiff)?;
+                } else {
+                    self.staged = ContactListDiff::default();
+                }
+            }
+            BackendEvent::GotContactListDiff(diff) => {
+                self.staged = diff;
+            }
+            BackendEvent::ContactListPublished => {
+                self.staged = ContactListDiff::default();
             }
             BackendEvent::GotRelayResponsesContactList {
                 responses,
@@ -76,6 +203,45 @@ impl State {
             }
             BackendEvent::GotContacts(db_contacts) => {
                 self.contacts = db_contacts;
+                // the fetched list may not include changes still in flight
+                for pending in &self.pending {
+                    apply_op(&mut self.contacts, &pending.op);
+                }
+                let contacts = &self.contacts;
+                self.selected
+                    .retain(|pubkey| contacts.iter().any(|c| c.pubkey() == pubkey));
+            }
+            BackendEvent::GotLabels(labels) => {
+                self.labels = labels;
+            }
+            // the plain event follows the response, it updates the list
+            BackendEvent::Response(id, inner) => match *inner {
+                BackendEvent::ContactCreated(db_contact)
+                | BackendEvent::ContactUpdated(db_contact)
+                | BackendEvent::ContactDeleted(db_contact) => self.confirm_op(id, &db_contact),
+                BackendEvent::ContactOpFailed(op, error) => {
+                    self.rollback_op(id, &op, &error, conn)?;
+                }
+                _ => (),
+            },
+            BackendEvent::RequestFailed(id, error) => {
+                let ops: Vec<_> = self
+                    .pending
+                    .iter()
+                    .filter(|p| p.request == id)
+                    .map(|p| p.op.clone())
+                    .collect();
+                for op in ops {
+                    self.rollback_op(id, &op, &error, conn)?;
+                }
+            }
+            BackendEvent::ContactCreated(_)
+            | BackendEvent::ContactUpdated(_)
+            | BackendEvent::ContactDeleted(_) => {
+                if self.stage_changes {
+                    conn.send(net::ToBackend::FetchContactListDiff)?;
+                }
+                conn.send(net::ToBackend::FetchContacts)?;
             }
             BackendEvent::UpdatedMetadata(pubkey) => {
                 if self.contacts.iter().any(|c| c.pubkey() == &pubkey) {
@@ -96,12 +262,11 @@ impl State {
                     );
                 }
             }
-            BackendEvent::ReceivedContactList
-            | BackendEvent::FileContactsImported(_)
-            | BackendEvent::ContactCreated(_)
-            | BackendEvent::ContactUpdated(_)
-            | BackendEvent::ContactDeleted(_) => {
+            BackendEvent::ReceivedContactList | BackendEvent::FileContactsImported(_) => {
                 conn.send(net::ToBackend::FetchContacts)?;
+                if self.stage_changes {
+                    conn.send(net::ToBackend::FetchContactListDiff)?;
+                }
             }
             _ => (),
         }
@@ -115,6 +280,15 @@ impl State {
     ) -> Result<Option<SettingsRouterMessage>, BackendClosed> {
         match message {
             Message::SendDMTo(_) => (),
+            Message::DismissError => self.error_msg = None,
+            Message::ToggleStageChanges(stage) => {
+                conn.send(net::ToBackend::SetStageContactChanges(stage))?;
+            }
+            Message::ReviewChanges => {
+                return Ok(Some(SettingsRouterMessage::OpenContactListReview(
+                    self.staged.to_owned(),
+                )));
+            }
             Message::RelaysConfirmationPress(_) => (),
             Message::OpenProfileModal(db_contact) => {
                 return Ok(Some(SettingsRouterMessage::OpenProfileModal(db_contact)));
@@ -134,14 +308,60 @@ impl State {
                     )));
                 }
                 contact_row::Message::DeleteContact(contact) => {
-                    conn.send(net::ToBackend::DeleteContact(contact))?;
+                    let request = conn.request(net::ToBackend::DeleteContact(contact.clone()))?;
+                    self.apply_optimistic(request.id(), ContactOp::Delete(contact));
                 }
                 contact_row::Message::EditContact(contact) => {
                     return Ok(Some(SettingsRouterMessage::OpenEditContactModal(contact)));
                 }
             },
             Message::DeleteContact(contact) => {
-                conn.send(net::ToBackend::DeleteContact(contact))?;
+                let request = conn.request(net::ToBackend::DeleteContact(contact.clone()))?;
+                self.apply_optimistic(request.id(), ContactOp::Delete(contact));
+            }
+            Message::ToggleSelected(pubkey, selected) => {
+                if selected {
+                    self.selected.insert(pubkey);
+                } else {
+                    self.selected.remove(&pubkey);
+                }
+            }
+            Message::SelectAll(selected) => {
+                let visible: Vec<_> = self.visible_contacts().map(|c| *c.pubkey()).collect();
+                for pubkey in visible {
+                    if selected {
+                        self.selected.insert(pubkey);
+                    } else {
+                        self.selected.remove(&pubkey);
+                    }
+                }
+            }
+            Message::ClearSelection => self.selected.clear(),
+            Message::DeleteSelected => {
+                let contacts = self.selected_contacts();
+                let request = conn.request(net::ToBackend::DeleteContacts(contacts.clone()))?;
+                for contact in contacts {
+                    self.apply_optimistic(request.id(), ContactOp::Delete(contact));
+                }
+                self.selected.clear();
+            }
+            Message::MergeSelected(keep, duplicate) => {
+                conn.send(net::ToBackend::MergeContacts(keep, duplicate))?;
+                self.selected.clear();
+            }
+            Message::AssignLabelToSelected(label_id) => {
+                let conversations = self.selected.iter().map(|p| p.to_string()).collect();
+                conn.send(net::ToBackend::AssignLabel(label_id, conversations))?;
+            }
+            Message::ExportSelected => {
+                conn.send(net::ToBackend::ExportSelectedContacts(
+                    self.selected_contacts(),
+                ))?;
+            }
+            Message::RefreshSelected => {
+                conn.send(net::ToBackend::RefreshMetadata(
+                    self.selected.iter().copied().collect(),
+                ))?;
             }
         }
 
@@ -181,6 +401,68 @@ impl State {
         }
     }
 
+    fn make_bulk_actions(&self) -> Element<Message> {
+        if self.selected.is_empty() {
+            return Space::with_height(Length::Shrink).into();
+        }
+        let labels = self
+            .labels
+            .list
+            .iter()
+            .fold(row![].spacing(5), |row, label| {
+                row.push(label_chip(
+                    label,
+                    true,
+                    Message::AssignLabelToSelected(label.id),
+                ))
+            });
+        let labels: Element<_> = if self.labels.is_empty() {
+            Space::with_width(Length::Shrink).into()
+        } else {
+            row![text("Label:").size(14), labels]
+                .align_items(Alignment::Center)
+                .spacing(5)
+                .into()
+        };
+        // two entries for the same person, either one can be kept
+        let merge: Element<_> = match self.selected_contacts().as_slice() {
+            [first, second] => row![
+                text("Merge into:").size(14),
+                merge_button(first, second),
+                merge_button(second, first),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(5)
+            .into(),
+            _ => Space::with_width(Length::Shrink).into(),
+        };
+        row![
+            text(format!("{} selected", self.selected.len())),
+            Space::with_width(Length::Fill),
+            labels,
+            merge,
+            button(text("Refresh profiles").size(14))
+                .padding(5)
+                .style(style::Button::Bordered)
+                .on_press(Message::RefreshSelected),
+            button(text("Export").size(14))
+                .padding(5)
+                .style(style::Button::Bordered)
+                .on_press(Message::ExportSelected),
+            button(text("Delete").size(14))
+                .padding(5)
+                .style(style::Button::Danger)
+                .on_press(Message::DeleteSelected),
+            button(xmark_icon().size(14))
+                .on_press(Message::ClearSelection)
+                .style(style::Button::MenuBtn)
+        ]
+        .align_items(Alignment::Center)
+        .padding([0, 20, 0, 0])
+        .spacing(5)
+        .into()
+    }
+
     pub fn view(&self) -> Element<Message> {
         let title = title("Contacts");
         let title_group = row![
@@ -213,43 +495,133 @@ impl State {
             button(import_icon().size(18))
                 .padding(5)
                 .on_press(Message::OpenImportContactModal),
-            "Import from file",
+            "Import from a contact list or CSV file",
             tooltip::Position::Top,
         )
         .style(style::Container::TooltipBg);
 
+        let stage_checkbox = checkbox(
+            "Review before publishing",
+            self.stage_changes,
+            Message::ToggleStageChanges,
+        );
+        let review_btn: Element<_> = if self.stage_changes && !self.staged.is_empty() {
+            button(text(format!("Review {} changes", self.staged.len())).size(18))
+                .padding(5)
+                .on_press(Message::ReviewChanges)
+                .into()
+        } else {
+            Space::with_width(Length::Shrink).into()
+        };
+
         let utils_row = row![
             search_contact,
             Space::with_width(Length::Fill),
+            stage_checkbox,
+            review_btn,
             add_contact_btn,
             import_btn,
         ]
+        .align_items(Alignment::Center)
         .padding([0, 20, 0, 0])
         .spacing(5)
         .width(Length::Fill);
 
         let contact_list: Element<_> = self
-            .contacts
-            .iter()
-            .filter(|c| contact_matches_search_full(c, &self.search_contact_input))
-            .map(ContactRow::from_db_contact)
-            .fold(
-                column![].padding([0, 20, 0, 0]).spacing(5),
-                |col, contact| col.push(contact.view().map(Message::ContactRow)),
-            )
+            .visible_contacts()
+            .fold(column![].padding([0, 20, 0, 0]).spacing(5), |col, c| {
+                let pubkey = *c.pubkey();
+                let contact_row =
+                    ContactRow::from_db_contact(c).saving(self.pending.contains_key(c.pubkey()));
+                col.push(
+                    row![
+                        checkbox("", self.selected.contains(&pubkey), move |selected| {
+                            Message::ToggleSelected(pubkey, selected)
+                        })
+                        .width(SELECT_WIDTH),
+                        contact_row.view().map(Message::ContactRow)
+                    ]
+                    .align_items(Alignment::Center),
+                )
+            })
             .into();
+        let all_selected = self.visible_contacts().next().is_some()
+            && self
+                .visible_contacts()
+                .all(|c| self.selected.contains(c.pubkey()));
         let contact_list_scroller = column![
-            container(ContactRow::header()).padding([0, 20, 0, 0]),
+            container(
+                row![
+                    checkbox("", all_selected, Message::SelectAll).width(SELECT_WIDTH),
+                    ContactRow::header()
+                ]
+                .align_items(Alignment::Center)
+            )
+            .padding([0, 20, 0, 0]),
             common_scrollable(contact_list)
         ];
-        let content: Element<_> = column![title_group, utils_row, contact_list_scroller]
-            .spacing(10)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into();
+        let error_row: Element<_> = match &self.error_msg {
+            Some(error_msg) => row![
+                text(error_msg).style(style::Text::Danger),
+                Space::with_width(Length::Fill),
+                button(xmark_icon().size(14))
+                    .on_press(Message::DismissError)
+                    .style(style::Button::MenuBtn)
+            ]
+            .align_items(Alignment::Center)
+            .padding([0, 20, 0, 0])
+            .into(),
+            None => Space::with_height(Length::Shrink).into(),
+        };
+        let content: Element<_> = column![
+            title_group,
+            error_row,
+            utils_row,
+            self.make_bulk_actions(),
+            contact_list_scroller
+        ]
+        .spacing(10)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into();
 
         container(content).center_x().center_y().into()
     }
 }
 
+fn merge_button<'a>(keep: &DbContact, duplicate: &DbContact) -> Element<'a, Message> {
+    tooltip(
+        button(text(keep.select_name()).size(14))
+            .padding(5)
+            .style(style::Button::Bordered)
+            .on_press(Message::MergeSelected(
+                keep.to_owned(),
+                duplicate.to_owned(),
+            )),
+        "Moves the other conversation here and deletes the other contact",
+        tooltip::Position::Top,
+    )
+    .style(style::Container::TooltipBg)
+    .into()
+}
+
+fn apply_op(contacts: &mut Vec<DbContact>, op: &ContactOp) {
+    match op {
+        ContactOp::Add(db_contact) | ContactOp::Update(db_contact) => {
+            if let Some(contact) = contacts
+                .iter_mut()
+                .find(|c| c.pubkey() == db_contact.pubkey())
+            {
+                *contact = db_contact.to_owned();
+            } else {
+                contacts.push(db_contact.to_owned());
+            }
+        }
+        ContactOp::Delete(db_contact) => {
+            contacts.retain(|c| c.pubkey() != db_contact.pubkey());
+        }
+    }
+}
+
 const SEARCH_CONTACT_WIDTH: f32 = 200.0;
+const SELECT_WIDTH: f32 = 30.0;
@@ -0,0 +1,245 @@
+use iced::widget::{button, checkbox, column, container, row, text, Space};
+use iced::Length;
+
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::components::text_input_group::TextInputGroup;
+use crate::error::BackendClosed;
+use crate::net::simulation::{DryRun, Simulation};
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::utils::hide_string;
+use crate::widget::Element;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    LatencyChange(String),
+    FailureRateChange(String),
+    SeedChange(String),
+    ToggleOffline(bool),
+    ToggleDryRun(bool),
+    RefreshDryRuns,
+    ApplyPress,
+    ResetPress,
+    DisconnectPress,
+}
+
+pub struct State {
+    latency_ms: String,
+    failure_rate: String,
+    seed: String,
+    offline: bool,
+    dry_run: bool,
+    is_saved: bool,
+    dry_runs: Vec<DryRun>,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::GetSimulation)?;
+        conn.send(ToBackend::FetchDryRuns)?;
+        Ok(Self::from_simulation(&Simulation::default()))
+    }
+    fn from_simulation(simulation: &Simulation) -> Self {
+        Self {
+            latency_ms: simulation.latency_ms.to_string(),
+            failure_rate: simulation.failure_rate.to_string(),
+            seed: simulation.seed.to_string(),
+            offline: simulation.offline,
+            dry_run: simulation.dry_run,
+            is_saved: true,
+            dry_runs: vec![],
+        }
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        match event {
+            BackendEvent::GotSimulation(simulation) => {
+                let dry_runs = std::mem::take(&mut self.dry_runs);
+                *self = Self {
+                    dry_runs,
+                    ..Self::from_simulation(&simulation)
+                };
+            }
+            BackendEvent::GotDryRuns(dry_runs) => self.dry_runs = dry_runs,
+            _ => (),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::LatencyChange(text) => self.latency_ms = text,
+            Message::FailureRateChange(text) => self.failure_rate = text,
+            Message::SeedChange(text) => self.seed = text,
+            Message::ToggleOffline(offline) => self.offline = offline,
+            Message::ToggleDryRun(dry_run) => self.dry_run = dry_run,
+            Message::RefreshDryRuns => {
+                conn.send(ToBackend::FetchDryRuns)?;
+                return Ok(());
+            }
+            Message::ApplyPress => {
+                if let Some(simulation) = self.to_simulation() {
+                    conn.send(ToBackend::SetSimulation(simulation))?;
+                }
+                return Ok(());
+            }
+            Message::ResetPress => {
+                conn.send(ToBackend::SetSimulation(Simulation::default()))?;
+                return Ok(());
+            }
+            Message::DisconnectPress => {
+                conn.send(ToBackend::SimulateDisconnect)?;
+                return Ok(());
+            }
+        }
+        self.is_saved = false;
+        Ok(())
+    }
+
+    fn dry_runs_view(&self) -> Element<Message> {
+        let header = row![
+            text("Dry run events").size(20),
+            Space::with_width(Length::Fill),
+            button(text("Refresh").size(14))
+                .padding(5)
+                .style(style::Button::Bordered)
+                .on_press(Message::RefreshDryRuns),
+        ];
+        if self.dry_runs.is_empty() {
+            let empty = text("Nothing kept from the relays yet")
+                .size(14)
+                .style(style::Text::Placeholder);
+            return column![header, empty].spacing(5).into();
+        }
+        self.dry_runs
+            .iter()
+            .fold(column![header].spacing(5), |col, dry_run| {
+                let ns_event = &dry_run.ns_event;
+                let relays: Vec<&str> = dry_run.relays.iter().map(|url| url.as_str()).collect();
+                let relays = if relays.is_empty() {
+                    "no write relays".to_owned()
+                } else {
+                    relays.join(", ")
+                };
+                col.push(
+                    container(
+                        column![
+                            text(format!(
+                                "kind {} · {}",
+                                ns_event.kind.as_u32(),
+                                hide_string(&ns_event.id.to_hex(), 8)
+                            ))
+                            .size(14),
+                            text(format!("To {}", relays))
+                                .size(14)
+                                .style(style::Text::Placeholder),
+                            text(hide_string(&ns_event.content, DRY_RUN_PREVIEW_CHARS)).size(14),
+                        ]
+                        .spacing(2),
+                    )
+                    .padding(5)
+                    .width(Length::Fill)
+                    .style(style::Container::Frame),
+                )
+            })
+            .into()
+    }
+
+    /// `None` when some field isn't a valid number
+    fn to_simulation(&self) -> Option<Simulation> {
+        Some(Simulation {
+            latency_ms: self.latency_ms.trim().parse().ok()?,
+            failure_rate: self
+                .failure_rate
+                .trim()
+                .parse()
+                .ok()
+                .filter(|rate| *rate <= 100)?,
+            seed: self.seed.trim().parse().ok()?,
+            offline: self.offline,
+            dry_run: self.dry_run,
+        })
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Debug");
+        let hint = text("Simulated network conditions, reset when the app restarts")
+            .size(14)
+            .style(style::Text::Placeholder);
+
+        let mut latency_input =
+            TextInputGroup::new("Latency (ms)", &self.latency_ms, Message::LatencyChange)
+                .tooltip("Delay before each command reaches the backend");
+        if self.latency_ms.trim().parse::<u64>().is_err() {
+            latency_input = latency_input.invalid("Must be a number");
+        }
+        let mut failure_input = TextInputGroup::new(
+            "Send failures (%)",
+            &self.failure_rate,
+            Message::FailureRateChange,
+        )
+        .tooltip("Outgoing events rejected by every relay");
+        if !matches!(self.failure_rate.trim().parse::<u8>(), Ok(rate) if rate <= 100) {
+            failure_input = failure_input.invalid("Must be between 0 and 100");
+        }
+        let mut seed_input = TextInputGroup::new("Seed", &self.seed, Message::SeedChange)
+            .tooltip("The same seed fails the same sends");
+        if self.seed.trim().parse::<u64>().is_err() {
+            seed_input = seed_input.invalid("Must be a number");
+        }
+        let offline = checkbox(
+            "Offline, keep outgoing events pending",
+            self.offline,
+            Message::ToggleOffline,
+        );
+
+        let dry_run = checkbox(
+            "Dry run, sign and log outgoing events without sending them",
+            self.dry_run,
+            Message::ToggleDryRun,
+        );
+
+        let mut apply_btn = button("Apply").padding(10);
+        if !self.is_saved && self.to_simulation().is_some() {
+            apply_btn = apply_btn.on_press(Message::ApplyPress);
+        }
+        let reset_btn = button("Reset")
+            .padding(10)
+            .style(style::Button::Bordered)
+            .on_press(Message::ResetPress);
+        let disconnect_btn = button("Drop relay connections")
+            .padding(10)
+            .style(style::Button::Danger)
+            .on_press(Message::DisconnectPress);
+
+        let content = column![
+            title,
+            hint,
+            latency_input.build(),
+            failure_input.build(),
+            seed_input.build(),
+            offline,
+            dry_run,
+            row![
+                disconnect_btn,
+                Space::with_width(Length::Fill),
+                reset_btn,
+                apply_btn
+            ]
+            .spacing(10),
+        ]
+        .spacing(10)
+        .push(self.dry_runs_view());
+
+        container(common_scrollable(content.padding([20, 20, 0, 0])))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+/// Characters kept at each end of long contents
+const DRY_RUN_PREVIEW_CHARS: usize = 60;
@@ -0,0 +1,270 @@
+use chrono::{DateTime, Local};
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Color, Length};
+use nostr::Kind;
+
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::db::AccountStats;
+use crate::error::BackendClosed;
+use crate::icon::solid_circle_icon;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::utils::format_bytes;
+use crate::widget::Element;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    RefreshPress,
+}
+
+pub struct State {
+    stats: Option<AccountStats>,
+    computing: bool,
+    computed_at: Option<DateTime<Local>>,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::ComputeStats)?;
+        Ok(Self {
+            stats: None,
+            computing: true,
+            computed_at: None,
+        })
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        if let BackendEvent::GotAccountStats(stats) = event {
+            self.stats = Some(stats);
+            self.computing = false;
+            self.computed_at = Some(Local::now());
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::RefreshPress => {
+                if !self.computing {
+                    self.computing = true;
+                    conn.send(ToBackend::ComputeStats)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn header(&self) -> Element<Message> {
+        let status = match (&self.computed_at, self.computing) {
+            (_, true) => "Computing...".to_owned(),
+            (Some(at), false) => format!("Updated at {}", at.format("%H:%M")),
+            (None, false) => "".to_owned(),
+        };
+        let mut refresh_btn = button("Refresh").padding(10).style(style::Button::Bordered);
+        if !self.computing {
+            refresh_btn = refresh_btn.on_press(Message::RefreshPress);
+        }
+        row![
+            text(status)
+                .size(14)
+                .style(style::Text::Placeholder)
+                .width(Length::Fill),
+            refresh_btn
+        ]
+        .align_items(Alignment::Center)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Insights");
+        let mut content = column![title, self.header()].spacing(10);
+
+        if let Some(stats) = &self.stats {
+            content = content
+                .push(text(format!("Events stored ({})", stats.total_events())).size(24))
+                .push(events_by_kind(stats))
+                .push(text("Top relays").size(24))
+                .push(top_relays(stats))
+                .push(text("Direct messages per month").size(24))
+                .push(dm_volume(stats))
+                .push(text("Storage growth").size(24))
+                .push(storage_growth(stats));
+        }
+
+        container(common_scrollable(content.padding([20, 20, 0, 0])))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+fn events_by_kind<'a>(stats: &AccountStats) -> Element<'a, Message> {
+    let max = stats.events_by_kind.first().map_or(0, |(_, count)| *count);
+    let rows = stats
+        .events_by_kind
+        .iter()
+        .map(|(kind, count)| {
+            bar_row(
+                kind_label(kind),
+                &[(*count, KIND_COLOR)],
+                max,
+                count.to_string(),
+            )
+        })
+        .collect();
+    list_or_empty(rows, "No events stored")
+}
+
+fn top_relays<'a>(stats: &AccountStats) -> Element<'a, Message> {
+    let max = stats.top_relays.first().map_or(0, |(_, count)| *count);
+    let rows = stats
+        .top_relays
+        .iter()
+        .map(|(url, count)| {
+            bar_row(
+                url.to_owned(),
+                &[(*count, RELAY_COLOR)],
+                max,
+                count.to_string(),
+            )
+        })
+        .collect();
+    list_or_empty(rows, "No events stored")
+}
+
+fn dm_volume<'a>(stats: &AccountStats) -> Element<'a, Message> {
+    let max = stats
+        .dm_volume
+        .iter()
+        .map(|month| month.total())
+        .max()
+        .unwrap_or(0);
+    let rows: Vec<_> = stats
+        .dm_volume
+        .iter()
+        .map(|month| {
+            bar_row(
+                month.month.to_owned(),
+                &[(month.sent, SENT_COLOR), (month.received, RECEIVED_COLOR)],
+                max,
+                month.total().to_string(),
+            )
+        })
+        .collect();
+    if rows.is_empty() {
+        return list_or_empty(rows, "No direct messages");
+    }
+    let legend = row![
+        legend_item("Sent", SENT_COLOR),
+        legend_item("Received", RECEIVED_COLOR)
+    ]
+    .spacing(20);
+    column![list_or_empty(rows, ""), legend].spacing(10).into()
+}
+
+fn storage_growth<'a>(stats: &AccountStats) -> Element<'a, Message> {
+    let max = stats.storage_growth.last().map_or(0, |(_, bytes)| *bytes);
+    let rows = stats
+        .storage_growth
+        .iter()
+        .map(|(month, bytes)| {
+            bar_row(
+                month.to_owned(),
+                &[(*bytes, STORAGE_COLOR)],
+                max,
+                format_bytes(*bytes),
+            )
+        })
+        .collect();
+    list_or_empty(rows, "No events stored")
+}
+
+fn list_or_empty<'a>(rows: Vec<Element<'a, Message>>, empty: &str) -> Element<'a, Message> {
+    if rows.is_empty() {
+        return text(empty).size(14).style(style::Text::Placeholder).into();
+    }
+    rows.into_iter()
+        .fold(column![].spacing(5), |col, row| col.push(row))
+        .into()
+}
+
+/// Label, bar with `parts` stacked relative to `max` and the value
+fn bar_row<'a>(
+    label: String,
+    parts: &[(u64, (u8, u8, u8))],
+    max: u64,
+    value: String,
+) -> Element<'a, Message> {
+    let max = max.max(1);
+    let mut used = 0;
+    let mut bar = row![].height(BAR_HEIGHT).width(Length::Fill);
+    for (count, color) in parts.iter().filter(|(count, _)| *count > 0) {
+        let portion = (count * BAR_PORTIONS / max).max(1) as u16;
+        used += portion;
+        bar = bar.push(
+            container(Space::with_width(Length::Fill))
+                .width(Length::FillPortion(portion))
+                .height(Length::Fill)
+                .style(style::Container::WithColor(rgb(*color))),
+        );
+    }
+    if let Some(rest) = (BAR_PORTIONS as u16)
+        .checked_sub(used)
+        .filter(|rest| *rest > 0)
+    {
+        bar = bar.push(Space::with_width(Length::FillPortion(rest)));
+    }
+
+    row![
+        text(label).size(14).width(Length::Fixed(LABEL_WIDTH)),
+        bar,
+        text(value).size(14).width(Length::Fixed(VALUE_WIDTH))
+    ]
+    .align_items(Alignment::Center)
+    .spacing(10)
+    .into()
+}
+
+fn legend_item<'a>(name: &str, color: (u8, u8, u8)) -> Element<'a, Message> {
+    row![
+        solid_circle_icon()
+            .size(LEGEND_DOT_SIZE)
+            .style(style::Text::Color(rgb(color))),
+        text(name).size(14)
+    ]
+    .align_items(Alignment::Center)
+    .spacing(5)
+    .into()
+}
+
+fn kind_label(kind: &Kind) -> String {
+    match kind {
+        Kind::Metadata => "Profiles".into(),
+        Kind::TextNote => "Notes".into(),
+        Kind::ContactList => "Contact lists".into(),
+        Kind::EncryptedDirectMessage => "Direct messages".into(),
+        Kind::Reaction => "Reactions".into(),
+        Kind::ChannelCreation => "Channel creations".into(),
+        Kind::ChannelMetadata => "Channel metadata".into(),
+        Kind::ChannelMessage => "Channel messages".into(),
+        other => format!("Kind {}", other.as_u32()),
+    }
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::from_rgb8(r, g, b)
+}
+
+const BAR_HEIGHT: f32 = 16.0;
+const BAR_PORTIONS: u64 = 1000;
+const LABEL_WIDTH: f32 = 200.0;
+const VALUE_WIDTH: f32 = 80.0;
+const LEGEND_DOT_SIZE: u16 = 14;
+const KIND_COLOR: (u8, u8, u8) = (0x1e, 0x88, 0xe5);
+const RELAY_COLOR: (u8, u8, u8) = (0x8e, 0x24, 0xaa);
+const SENT_COLOR: (u8, u8, u8) = (0x43, 0xa0, 0x47);
+const RECEIVED_COLOR: (u8, u8, u8) = (0xfb, 0x8c, 0x00);
+const STORAGE_COLOR: (u8, u8, u8) = (0x75, 0x75, 0x75);
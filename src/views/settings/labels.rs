@@ -0,0 +1,261 @@
+use crate::components::common_scrollable;
+use crate::components::label_chip::{label_chip, label_color};
+use crate::components::text::title;
+use crate::components::text_input_group::TextInputGroup;
+use crate::db::{DbLabel, LabelNotify, Labels};
+use crate::error::BackendClosed;
+use crate::icon::{delete_icon, edit_icon, solid_circle_icon};
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::widget::Element;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Length};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    NameChange(String),
+    ColorPress(&'static str),
+    NotifyPress(LabelNotify),
+    EditPress(DbLabel),
+    DeletePress(DbLabel),
+    CancelPress,
+    SavePress,
+    None,
+}
+
+pub struct State {
+    labels: Labels,
+    editing: Option<DbLabel>,
+    name: String,
+    color: String,
+    notify: LabelNotify,
+    error: Option<String>,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchLabels)?;
+        Ok(Self {
+            labels: Labels::default(),
+            editing: None,
+            name: "".into(),
+            color: LABEL_COLORS[0].into(),
+            notify: LabelNotify::Default,
+            error: None,
+        })
+    }
+
+    fn clear_form(&mut self) {
+        self.editing = None;
+        self.name = "".into();
+        self.color = LABEL_COLORS[0].into();
+        self.notify = LabelNotify::Default;
+        self.error = None;
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        match event {
+            BackendEvent::GotLabels(labels) => {
+                self.labels = labels;
+            }
+            BackendEvent::LabelError(e) => {
+                self.error = Some(e);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::None => (),
+            Message::NameChange(text) => self.name = text,
+            Message::ColorPress(color) => self.color = color.into(),
+            Message::NotifyPress(notify) => self.notify = notify,
+            Message::EditPress(label) => {
+                self.name = label.name.to_owned();
+                self.color = label.color.to_owned();
+                self.notify = label.notify;
+                self.editing = Some(label);
+                self.error = None;
+            }
+            Message::DeletePress(label) => {
+                if self.editing.as_ref() == Some(&label) {
+                    self.clear_form();
+                }
+                conn.send(ToBackend::DeleteLabel(label))?;
+            }
+            Message::CancelPress => self.clear_form(),
+            Message::SavePress => {
+                match self.editing.take() {
+                    Some(label) => conn.send(ToBackend::UpdateLabel(DbLabel {
+                        name: self.name.to_owned(),
+                        color: self.color.to_owned(),
+                        notify: self.notify,
+                        ..label
+                    }))?,
+                    None => conn.send(ToBackend::AddLabel(
+                        self.name.to_owned(),
+                        self.color.to_owned(),
+                        self.notify,
+                    ))?,
+                }
+                self.clear_form();
+            }
+        }
+        Ok(())
+    }
+
+    fn form(&self) -> Element<Message> {
+        let name_input = TextInputGroup::new("Name", &self.name, Message::NameChange)
+            .placeholder("Work")
+            .on_submit(Message::SavePress)
+            .build();
+
+        let colors = LABEL_COLORS.iter().fold(row![].spacing(5), |row, color| {
+            let preview = DbLabel {
+                id: 0,
+                name: "".into(),
+                color: (*color).into(),
+                notify: LabelNotify::Default,
+            };
+            let btn_style = if self.color == *color {
+                style::Button::ActiveMenuBtn
+            } else {
+                style::Button::Invisible
+            };
+            row.push(
+                button(
+                    solid_circle_icon()
+                        .size(COLOR_SIZE)
+                        .style(style::Text::Color(label_color(&preview))),
+                )
+                .style(btn_style)
+                .on_press(Message::ColorPress(color)),
+            )
+        });
+
+        let notify_buttons = LabelNotify::ALL
+            .iter()
+            .fold(row![].spacing(5), |row, notify| {
+                let btn_style = if &self.notify == notify {
+                    style::Button::ActiveMenuBtn
+                } else {
+                    style::Button::Bordered
+                };
+                row.push(
+                    button(text(notify.to_string()).size(14))
+                        .padding([5, 10])
+                        .style(btn_style)
+                        .on_press(Message::NotifyPress(*notify)),
+                )
+            });
+
+        let preview = DbLabel {
+            id: 0,
+            name: if self.name.trim().is_empty() {
+                "Preview".into()
+            } else {
+                self.name.to_owned()
+            },
+            color: self.color.to_owned(),
+            notify: self.notify,
+        };
+
+        let mut save_btn = button(if self.editing.is_some() {
+            "Save"
+        } else {
+            "Add"
+        })
+        .padding(10);
+        if !self.name.trim().is_empty() {
+            save_btn = save_btn.on_press(Message::SavePress);
+        }
+        let mut buttons = row![Space::with_width(Length::Fill)].spacing(10);
+        if self.editing.is_some() {
+            buttons = buttons.push(
+                button("Cancel")
+                    .padding(10)
+                    .style(style::Button::Bordered)
+                    .on_press(Message::CancelPress),
+            );
+        }
+        buttons = buttons.push(save_btn);
+
+        let mut form = column![
+            name_input,
+            text("Color"),
+            colors,
+            text("Notifications"),
+            notify_buttons,
+            row![text("Preview"), label_chip(&preview, true, Message::None)]
+                .align_items(Alignment::Center)
+                .spacing(10),
+        ]
+        .spacing(10);
+        if let Some(error) = &self.error {
+            form = form.push(text(error).style(style::Text::Danger));
+        }
+        form.push(buttons).into()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Labels");
+        let hint =
+            text("Assign labels from the conversation header and filter by them in the chat list")
+                .size(14)
+                .style(style::Text::Placeholder);
+
+        let list: Element<_> = if self.labels.is_empty() {
+            text("No labels yet").style(style::Text::Placeholder).into()
+        } else {
+            self.labels
+                .list
+                .iter()
+                .fold(column![].spacing(5), |col, label| {
+                    col.push(label_row(label))
+                })
+                .into()
+        };
+
+        container(
+            column![title, hint, self.form(), common_scrollable(list)]
+                .padding([20, 20, 0, 0])
+                .spacing(10),
+        )
+        .into()
+    }
+}
+
+fn label_row(label: &DbLabel) -> Element<Message> {
+    let edit_btn = button(edit_icon().size(16))
+        .style(style::Button::Invisible)
+        .on_press(Message::EditPress(label.to_owned()));
+    let delete_btn = button(delete_icon().size(16))
+        .style(style::Button::Invisible)
+        .on_press(Message::DeletePress(label.to_owned()));
+
+    container(
+        row![
+            solid_circle_icon()
+                .size(COLOR_SIZE)
+                .style(style::Text::Color(label_color(label))),
+            text(&label.name).width(Length::Fill),
+            text(label.notify.to_string()).style(style::Text::Placeholder),
+            edit_btn,
+            delete_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
+}
+
+const COLOR_SIZE: u16 = 18;
+const LABEL_COLORS: [&str; 8] = [
+    "#e53935", "#fb8c00", "#fdd835", "#43a047", "#00acc1", "#1e88e5", "#8e24aa", "#757575",
+];
@@ -1,16 +1,24 @@
 use iced::widget::{button, column, container, row, Space};
-use iced::{Command, Length, Subscription};
+use iced::{clipboard, Command, Length, Subscription};
 
+use crate::config::{
+    Appearance, AutoLockConfig, ClockFormat, DateOrder, PresenceConfig, SendKey, StartupView,
+    StatusChoice, TextDirection, TimeFormatConfig,
+};
 use crate::db::{DbContact, DbRelay};
 use crate::error::BackendClosed;
-use crate::net::{self, BackEndConnection, BackendEvent};
+use crate::net::simulation::dev_mode;
+use crate::net::{self, BackEndConnection, BackendEvent, RequestId};
 use crate::style;
+use crate::types::{ContactListDiff, ContactOp};
+use url::Url;
 
 use crate::widget::{Button, Element};
 
 use super::modal::{
-    basic_contact, import_contact_list, relay_basic, relay_document, relays_confirmation,
-    ContactDetails, ImportContactList, ModalView, RelayBasic, RelayDocState, RelaysConfirmation,
+    basic_contact, contact_list_review, import_contact_list, import_relays, relay_basic,
+    relay_document, relays_confirmation, ContactDetails, ContactListReview, ImportContactList,
+    ImportRelays, ModalView, RelayBasic, RelayDocState, RelaysConfirmation,
 };
 use super::route::Route;
 use super::{GoToView, RouterCommand};
@@ -19,8 +27,17 @@ mod about;
 mod account;
 pub mod appearance;
 mod backup;
+mod blocked;
+mod console;
 mod contacts;
+mod debug;
+mod insights;
+mod labels;
 mod network;
+mod storage;
+mod templates;
+mod translation;
+mod trash;
 
 pub enum SettingsRouterMessage {
     OpenRelayBasicModal,
@@ -30,6 +47,9 @@ pub enum SettingsRouterMessage {
     OpenImportContactModal,
     OpenAddContactModal,
     OpenRelayDocument(DbRelay),
+    OpenContactListReview(ContactListDiff),
+    /// Relays already in the list are shown but can't be added again
+    OpenImportRelaysModal(Vec<Url>),
 }
 
 #[derive(Debug, Clone)]
@@ -38,10 +58,21 @@ pub enum Message {
     Network(network::Message),
     Backup(backup::Message),
     Contacts(contacts::Message),
+    Blocked(blocked::Message),
+    Trash(trash::Message),
     About(about::Message),
+    Templates(templates::Message),
+    Labels(labels::Message),
+    Storage(storage::Message),
+    Insights(insights::Message),
+    Translation(translation::Message),
+    Console(console::Message),
+    Debug(debug::Message),
 
     ModalContactDetails(Box<basic_contact::CMessage<Message>>),
+    ModalContactListReview(Box<contact_list_review::CMessage<Message>>),
     ModalImportContactList(Box<import_contact_list::CMessage<Message>>),
+    ModalImportRelays(Box<import_relays::CMessage<Message>>),
     ModalRelaysConfirmation(Box<relays_confirmation::CMessage<Message>>),
     ModalRelayDocument(Box<relay_document::CMessage<Message>>),
     ModalRelayBasic(Box<relay_basic::CMessage<Message>>),
@@ -52,6 +83,15 @@ pub enum Message {
     MenuNetworkPress,
     MenuBackupPress,
     MenuContactsPress,
+    MenuBlockedPress,
+    MenuTrashPress,
+    MenuTemplatesPress,
+    MenuLabelsPress,
+    MenuStoragePress,
+    MenuInsightsPress,
+    MenuTranslationPress,
+    MenuConsolePress,
+    MenuDebugPress,
     MenuAboutPress,
     LogoutPress,
     NavEscPress,
@@ -63,16 +103,74 @@ pub enum Message {
     // Other
     None,
     ChangeTheme(style::Theme),
+    ChangeSendKey(SendKey),
+    ToggleDimImages(bool),
+    ToggleAutoLock(bool),
+    ChangeLockMinutes(u32),
+    ChangeClockFormat(ClockFormat),
+    ToggleRelativeTimes(bool),
+    ChangeDateOrder(DateOrder),
+    ChangeTextDirection(TextDirection),
+    ChangeStartupView(StartupView),
+    TogglePublishStatus(bool),
+    ToggleAutoAway(bool),
+    ChangeAwayMinutes(u32),
+    ChangeStatus(StatusChoice),
 }
 
 #[repr(u8)]
 pub enum MenuState {
-    Account { state: account::State } = 0,
-    Appearance = 1,
-    Network { state: network::State } = 2,
-    Backup { state: backup::State } = 3,
-    Contacts { state: contacts::State } = 4,
-    About { state: about::State } = 10,
+    Account {
+        state: account::State,
+    } = 0,
+    Appearance {
+        send_key: SendKey,
+        dim_images: bool,
+        auto_lock: AutoLockConfig,
+        time_format: TimeFormatConfig,
+        text_direction: TextDirection,
+        startup_view: StartupView,
+        presence: PresenceConfig,
+    } = 1,
+    Network {
+        state: network::State,
+    } = 2,
+    Backup {
+        state: backup::State,
+    } = 3,
+    Contacts {
+        state: contacts::State,
+    } = 4,
+    Translation {
+        state: translation::State,
+    } = 5,
+    Templates {
+        state: templates::State,
+    } = 6,
+    Labels {
+        state: labels::State,
+    } = 7,
+    Storage {
+        state: storage::State,
+    } = 8,
+    Blocked {
+        state: blocked::State,
+    } = 9,
+    About {
+        state: about::State,
+    } = 10,
+    Console {
+        state: console::State,
+    } = 11,
+    Debug {
+        state: debug::State,
+    } = 12,
+    Insights {
+        state: insights::State,
+    } = 13,
+    Trash {
+        state: trash::State,
+    } = 14,
 }
 
 impl MenuState {
@@ -81,17 +179,35 @@ impl MenuState {
     const NETWORK: u8 = 2;
     const BACKUP: u8 = 3;
     const CONTACTS: u8 = 4;
+    const TRANSLATION: u8 = 5;
+    const TEMPLATES: u8 = 6;
+    const LABELS: u8 = 7;
+    const STORAGE: u8 = 8;
+    const BLOCKED: u8 = 9;
     const ABOUT: u8 = 10;
+    const CONSOLE: u8 = 11;
+    const DEBUG: u8 = 12;
+    const INSIGHTS: u8 = 13;
+    const TRASH: u8 = 14;
 
     pub fn is_same_type(&self, other: u8) -> bool {
         matches!(
             (self, other),
             (MenuState::Account { .. }, Self::ACCOUNT)
-                | (MenuState::Appearance, Self::APPEARANCE)
+                | (MenuState::Appearance { .. }, Self::APPEARANCE)
                 | (MenuState::Network { .. }, Self::NETWORK)
                 | (MenuState::Backup { .. }, Self::BACKUP)
                 | (MenuState::Contacts { .. }, Self::CONTACTS)
+                | (MenuState::Translation { .. }, Self::TRANSLATION)
+                | (MenuState::Templates { .. }, Self::TEMPLATES)
+                | (MenuState::Labels { .. }, Self::LABELS)
+                | (MenuState::Storage { .. }, Self::STORAGE)
+                | (MenuState::Blocked { .. }, Self::BLOCKED)
                 | (MenuState::About { .. }, Self::ABOUT)
+                | (MenuState::Console { .. }, Self::CONSOLE)
+                | (MenuState::Debug { .. }, Self::DEBUG)
+                | (MenuState::Insights { .. }, Self::INSIGHTS)
+                | (MenuState::Trash { .. }, Self::TRASH)
         )
     }
     fn account(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
@@ -114,6 +230,69 @@ impl MenuState {
             state: contacts::State::new(conn)?,
         })
     }
+    fn templates(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Templates {
+            state: templates::State::new(conn)?,
+        })
+    }
+    fn labels(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Labels {
+            state: labels::State::new(conn)?,
+        })
+    }
+    fn storage(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Storage {
+            state: storage::State::new(conn)?,
+        })
+    }
+    fn insights(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Insights {
+            state: insights::State::new(conn)?,
+        })
+    }
+    fn blocked(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Blocked {
+            state: blocked::State::new(conn)?,
+        })
+    }
+    fn trash(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Trash {
+            state: trash::State::new(conn)?,
+        })
+    }
+    fn translation(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Translation {
+            state: translation::State::new(conn)?,
+        })
+    }
+    fn console(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Console {
+            state: console::State::new(conn)?,
+        })
+    }
+    fn debug(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        Ok(Self::Debug {
+            state: debug::State::new(conn)?,
+        })
+    }
+    fn appearance(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(net::ToBackend::GetSendKey)?;
+        conn.send(net::ToBackend::GetDimImages)?;
+        conn.send(net::ToBackend::GetAutoLock)?;
+        conn.send(net::ToBackend::GetTimeFormat)?;
+        conn.send(net::ToBackend::GetTextDirection)?;
+        conn.send(net::ToBackend::GetStartupView)?;
+        conn.send(net::ToBackend::GetPresence)?;
+        Ok(Self::Appearance {
+            send_key: SendKey::default(),
+            dim_images: false,
+            auto_lock: AutoLockConfig::default(),
+            time_format: TimeFormatConfig::default(),
+            text_direction: TextDirection::default(),
+            startup_view: StartupView::default(),
+            presence: PresenceConfig::default(),
+        })
+    }
     fn backup(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
         Ok(Self::Backup {
             state: backup::State::new(conn)?,
@@ -122,16 +301,60 @@ impl MenuState {
     pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
         Self::account(conn)
     }
-    pub fn view(&self, selected_theme: Option<style::Theme>) -> Element<Message> {
+    pub fn view(
+        &self,
+        selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Message> {
         match self {
-            Self::Account { state } => state.view().map(Message::Account),
-            Self::Appearance => appearance::view(selected_theme).map(|m| match m {
+            Self::Account { state } => state.view(appearance).map(Message::Account),
+            Self::Appearance {
+                send_key,
+                dim_images,
+                auto_lock,
+                time_format,
+                text_direction,
+                startup_view,
+                presence,
+            } => appearance::view(
+                selected_theme,
+                *send_key,
+                *dim_images,
+                *auto_lock,
+                *time_format,
+                *text_direction,
+                *startup_view,
+                *presence,
+            )
+            .map(|m| match m {
                 appearance::Message::ChangeTheme(x) => Message::ChangeTheme(x),
+                appearance::Message::ChangeSendKey(x) => Message::ChangeSendKey(x),
+                appearance::Message::ToggleDimImages(x) => Message::ToggleDimImages(x),
+                appearance::Message::ToggleAutoLock(x) => Message::ToggleAutoLock(x),
+                appearance::Message::ChangeLockMinutes(x) => Message::ChangeLockMinutes(x),
+                appearance::Message::ChangeClockFormat(x) => Message::ChangeClockFormat(x),
+                appearance::Message::ToggleRelativeTimes(x) => Message::ToggleRelativeTimes(x),
+                appearance::Message::ChangeDateOrder(x) => Message::ChangeDateOrder(x),
+                appearance::Message::ChangeTextDirection(x) => Message::ChangeTextDirection(x),
+                appearance::Message::ChangeStartupView(x) => Message::ChangeStartupView(x),
+                appearance::Message::TogglePublishStatus(x) => Message::TogglePublishStatus(x),
+                appearance::Message::ToggleAutoAway(x) => Message::ToggleAutoAway(x),
+                appearance::Message::ChangeAwayMinutes(x) => Message::ChangeAwayMinutes(x),
+                appearance::Message::ChangeStatus(x) => Message::ChangeStatus(x),
             }),
             Self::Network { state } => state.view().map(Message::Network),
             Self::Backup { state } => state.view().map(Message::Backup),
             Self::Contacts { state } => state.view().map(Message::Contacts),
+            Self::Translation { state } => state.view().map(Message::Translation),
+            Self::Templates { state } => state.view().map(Message::Templates),
+            Self::Labels { state } => state.view().map(Message::Labels),
+            Self::Storage { state } => state.view().map(Message::Storage),
+            Self::Insights { state } => state.view().map(Message::Insights),
+            Self::Blocked { state } => state.view().map(Message::Blocked),
+            Self::Trash { state } => state.view().map(Message::Trash),
             Self::About { state } => state.view().map(Message::About),
+            Self::Console { state } => state.view().map(Message::Console),
+            Self::Debug { state } => state.view().map(Message::Debug),
         }
     }
 }
@@ -139,25 +362,36 @@ impl MenuState {
 pub struct Settings {
     menu_state: MenuState,
     modal_state: ModalState,
+    /// Shows the console menu
+    developer_console: bool,
 }
 impl Settings {
-    fn with_menu_state(menu_state: MenuState) -> Self {
-        Self {
+    fn with_menu_state(
+        menu_state: MenuState,
+        conn: &mut BackEndConnection,
+    ) -> Result<Self, BackendClosed> {
+        conn.send(net::ToBackend::GetDeveloperConsole)?;
+        Ok(Self {
             menu_state,
             modal_state: ModalState::Off,
-        }
+            developer_console: false,
+        })
     }
     pub fn new(db_conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
-        Ok(Self::with_menu_state(MenuState::new(db_conn)?))
+        let menu_state = MenuState::new(db_conn)?;
+        Self::with_menu_state(menu_state, db_conn)
     }
     pub fn contacts(db_conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
-        Ok(Self::with_menu_state(MenuState::contacts(db_conn)?))
+        let menu_state = MenuState::contacts(db_conn)?;
+        Self::with_menu_state(menu_state, db_conn)
     }
     pub fn network(db_conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
-        Ok(Self::with_menu_state(MenuState::network(db_conn)?))
+        let menu_state = MenuState::network(db_conn)?;
+        Self::with_menu_state(menu_state, db_conn)
     }
-    pub fn about(db_conn: &mut BackEndConnection) -> Self {
-        Self::with_menu_state(MenuState::about(db_conn))
+    pub fn about(db_conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        let menu_state = MenuState::about(db_conn);
+        Self::with_menu_state(menu_state, db_conn)
     }
     fn handle_menu_press(
         &mut self,
@@ -171,7 +405,7 @@ impl Settings {
             },
             Message::MenuAppearancePress => match self.menu_state {
                 MenuState::Appearance { .. } => (),
-                _ => self.menu_state = MenuState::Appearance,
+                _ => self.menu_state = MenuState::appearance(conn)?,
             },
             Message::MenuNetworkPress => match self.menu_state {
                 MenuState::Network { .. } => (),
@@ -185,10 +419,46 @@ impl Settings {
                 MenuState::Contacts { .. } => (),
                 _ => self.menu_state = MenuState::contacts(conn)?,
             },
+            Message::MenuBlockedPress => match self.menu_state {
+                MenuState::Blocked { .. } => (),
+                _ => self.menu_state = MenuState::blocked(conn)?,
+            },
+            Message::MenuTrashPress => match self.menu_state {
+                MenuState::Trash { .. } => (),
+                _ => self.menu_state = MenuState::trash(conn)?,
+            },
+            Message::MenuTemplatesPress => match self.menu_state {
+                MenuState::Templates { .. } => (),
+                _ => self.menu_state = MenuState::templates(conn)?,
+            },
+            Message::MenuLabelsPress => match self.menu_state {
+                MenuState::Labels { .. } => (),
+                _ => self.menu_state = MenuState::labels(conn)?,
+            },
+            Message::MenuStoragePress => match self.menu_state {
+                MenuState::Storage { .. } => (),
+                _ => self.menu_state = MenuState::storage(conn)?,
+            },
+            Message::MenuInsightsPress => match self.menu_state {
+                MenuState::Insights { .. } => (),
+                _ => self.menu_state = MenuState::insights(conn)?,
+            },
+            Message::MenuTranslationPress => match self.menu_state {
+                MenuState::Translation { .. } => (),
+                _ => self.menu_state = MenuState::translation(conn)?,
+            },
             Message::MenuAboutPress => match self.menu_state {
                 MenuState::About { .. } => (),
                 _ => self.menu_state = MenuState::about(conn),
             },
+            Message::MenuConsolePress => match self.menu_state {
+                MenuState::Console { .. } => (),
+                _ => self.menu_state = MenuState::console(conn)?,
+            },
+            Message::MenuDebugPress => match self.menu_state {
+                MenuState::Debug { .. } => (),
+                _ => self.menu_state = MenuState::debug(conn)?,
+            },
             _ => (),
         }
         Ok(())
@@ -219,6 +489,12 @@ impl Settings {
                 self.modal_state =
                     ModalState::ContactDetails(ContactDetails::viewer(&contact, conn)?)
             }
+            SettingsRouterMessage::OpenImportRelaysModal(existing) => {
+                self.modal_state = ModalState::ImportRelays(ImportRelays::new(existing));
+            }
+            SettingsRouterMessage::OpenContactListReview(diff) => {
+                self.modal_state = ModalState::ContactListReview(ContactListReview::new(diff));
+            }
             SettingsRouterMessage::RouterMessage(router_msg) => {
                 router_message = Some(router_msg);
             }
@@ -273,6 +549,15 @@ impl Route for Settings {
 
         self.modal_state.backend_event(event.clone(), conn)?;
 
+        if let BackendEvent::DeveloperConsole(enabled) = event {
+            self.developer_console = enabled;
+            if !enabled {
+                if let MenuState::Console { .. } = self.menu_state {
+                    self.menu_state = MenuState::network(conn)?;
+                }
+            }
+        }
+
         match &mut self.menu_state {
             MenuState::About { state } => {
                 state.backend_event(event, conn);
@@ -280,7 +565,24 @@ impl Route for Settings {
             MenuState::Account { state } => {
                 state.backend_event(event, conn)?;
             }
-            MenuState::Appearance => {}
+            MenuState::Appearance {
+                send_key,
+                dim_images,
+                auto_lock,
+                time_format,
+                text_direction,
+                startup_view,
+                presence,
+            } => match event {
+                BackendEvent::GotSendKey(new_send_key) => *send_key = new_send_key,
+                BackendEvent::DimImagesChanged(dim) => *dim_images = dim,
+                BackendEvent::AutoLockChanged(new_auto_lock) => *auto_lock = new_auto_lock,
+                BackendEvent::TimeFormatChanged(new_format) => *time_format = new_format,
+                BackendEvent::TextDirectionChanged(direction) => *text_direction = direction,
+                BackendEvent::StartupViewChanged(view) => *startup_view = view,
+                BackendEvent::PresenceChanged(new_presence) => *presence = new_presence,
+                _ => (),
+            },
             MenuState::Network { state } => {
                 state.backend_event(event, conn);
             }
@@ -290,6 +592,33 @@ impl Route for Settings {
             MenuState::Contacts { state } => {
                 state.backend_event(event, conn)?;
             }
+            MenuState::Translation { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Templates { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Labels { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Storage { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Insights { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Blocked { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Trash { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Console { state } => {
+                state.backend_event(event, conn);
+            }
+            MenuState::Debug { state } => {
+                state.backend_event(event, conn);
+            }
         }
 
         Ok(commands)
@@ -305,6 +634,90 @@ impl Route for Settings {
             Message::ChangeTheme(theme) => {
                 conn.send(net::ToBackend::SetTheme(theme))?;
             }
+            Message::ChangeSendKey(send_key) => {
+                conn.send(net::ToBackend::SetSendKey(send_key))?;
+            }
+            Message::ToggleDimImages(dim_images) => {
+                conn.send(net::ToBackend::SetDimImages(dim_images))?;
+            }
+            Message::ToggleAutoLock(enabled) => {
+                if let MenuState::Appearance { auto_lock, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetAutoLock(AutoLockConfig {
+                        enabled,
+                        ..*auto_lock
+                    }))?;
+                }
+            }
+            Message::ChangeLockMinutes(idle_minutes) => {
+                if let MenuState::Appearance { auto_lock, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetAutoLock(AutoLockConfig {
+                        idle_minutes,
+                        ..*auto_lock
+                    }))?;
+                }
+            }
+            Message::ChangeClockFormat(clock) => {
+                if let MenuState::Appearance { time_format, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetTimeFormat(TimeFormatConfig {
+                        clock,
+                        ..*time_format
+                    }))?;
+                }
+            }
+            Message::ToggleRelativeTimes(relative) => {
+                if let MenuState::Appearance { time_format, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetTimeFormat(TimeFormatConfig {
+                        relative,
+                        ..*time_format
+                    }))?;
+                }
+            }
+            Message::ChangeDateOrder(date_order) => {
+                if let MenuState::Appearance { time_format, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetTimeFormat(TimeFormatConfig {
+                        date_order,
+                        ..*time_format
+                    }))?;
+                }
+            }
+            Message::ChangeTextDirection(direction) => {
+                conn.send(net::ToBackend::SetTextDirection(direction))?;
+            }
+            Message::ChangeStartupView(view) => {
+                conn.send(net::ToBackend::SetStartupView(view))?;
+            }
+            Message::TogglePublishStatus(publish) => {
+                if let MenuState::Appearance { presence, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetPresence(PresenceConfig {
+                        publish,
+                        ..*presence
+                    }))?;
+                }
+            }
+            Message::ToggleAutoAway(auto_away) => {
+                if let MenuState::Appearance { presence, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetPresence(PresenceConfig {
+                        auto_away,
+                        ..*presence
+                    }))?;
+                }
+            }
+            Message::ChangeAwayMinutes(away_minutes) => {
+                if let MenuState::Appearance { presence, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetPresence(PresenceConfig {
+                        away_minutes,
+                        ..*presence
+                    }))?;
+                }
+            }
+            Message::ChangeStatus(status) => {
+                if let MenuState::Appearance { presence, .. } = &self.menu_state {
+                    conn.send(net::ToBackend::SetPresence(PresenceConfig {
+                        status,
+                        ..*presence
+                    }))?;
+                }
+            }
             Message::Account(msg) => {
                 if let MenuState::Account { state } = &mut self.menu_state {
                     match msg {
@@ -317,6 +730,9 @@ impl Route for Settings {
                                     ))
                             }
                         }
+                        account::Message::CopyPairingPress(payload) => {
+                            commands.push(clipboard::write(payload));
+                        }
                         other => {
                             state.update(other, conn)?;
                         }
@@ -346,6 +762,51 @@ impl Route for Settings {
                     commands.push(cmd.map(Message::Backup));
                 }
             }
+            Message::Templates(msg) => {
+                if let MenuState::Templates { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Labels(msg) => {
+                if let MenuState::Labels { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Storage(msg) => {
+                if let MenuState::Storage { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Insights(msg) => {
+                if let MenuState::Insights { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Blocked(msg) => {
+                if let MenuState::Blocked { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Trash(msg) => {
+                if let MenuState::Trash { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Translation(msg) => {
+                if let MenuState::Translation { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Console(msg) => {
+                if let MenuState::Console { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
+            Message::Debug(msg) => {
+                if let MenuState::Debug { state } = &mut self.menu_state {
+                    state.update(msg, conn)?;
+                }
+            }
             Message::Contacts(msg) => {
                 if let Some(router_message) = self.handle_contacts_message(msg, conn)? {
                     commands.change_route(router_message);
@@ -357,6 +818,15 @@ impl Route for Settings {
             | Message::MenuNetworkPress
             | Message::MenuBackupPress
             | Message::MenuContactsPress
+            | Message::MenuBlockedPress
+            | Message::MenuTrashPress
+            | Message::MenuTemplatesPress
+            | Message::MenuLabelsPress
+            | Message::MenuStoragePress
+            | Message::MenuInsightsPress
+            | Message::MenuTranslationPress
+            | Message::MenuConsolePress
+            | Message::MenuDebugPress
             | Message::MenuAboutPress => {
                 self.handle_menu_press(message, conn)?;
             }
@@ -365,7 +835,12 @@ impl Route for Settings {
                 commands.change_route(GoToView::Logout)
             }
             other => {
-                let cmd = self.modal_state.update(other, conn)?;
+                let (cmd, contact_op) = self.modal_state.update(other, conn)?;
+                if let (Some((request, op)), MenuState::Contacts { state }) =
+                    (contact_op, &mut self.menu_state)
+                {
+                    state.apply_optimistic(request, op);
+                }
                 commands.push(cmd);
             }
         }
@@ -373,7 +848,11 @@ impl Route for Settings {
         Ok(commands)
     }
 
-    fn view(&self, selected_theme: Option<style::Theme>) -> Element<Self::Message> {
+    fn view(
+        &self,
+        selected_theme: Option<style::Theme>,
+        appearance: Appearance,
+    ) -> Element<Self::Message> {
         let account_btn =
             create_menu_button("Account", &self.menu_state, 0, Message::MenuAccountPress);
         let appearance_btn = create_menu_button(
@@ -388,7 +867,39 @@ impl Route for Settings {
             create_menu_button("Backup", &self.menu_state, 3, Message::MenuBackupPress);
         let contacts_btn =
             create_menu_button("Contacts", &self.menu_state, 4, Message::MenuContactsPress);
+        let blocked_btn =
+            create_menu_button("Blocked", &self.menu_state, 9, Message::MenuBlockedPress);
+        let trash_btn = create_menu_button("Trash", &self.menu_state, 14, Message::MenuTrashPress);
+        let templates_btn = create_menu_button(
+            "Templates",
+            &self.menu_state,
+            6,
+            Message::MenuTemplatesPress,
+        );
+        let labels_btn =
+            create_menu_button("Labels", &self.menu_state, 7, Message::MenuLabelsPress);
+        let storage_btn =
+            create_menu_button("Storage", &self.menu_state, 8, Message::MenuStoragePress);
+        let insights_btn =
+            create_menu_button("Insights", &self.menu_state, 13, Message::MenuInsightsPress);
+        let translation_btn = create_menu_button(
+            "Translation",
+            &self.menu_state,
+            5,
+            Message::MenuTranslationPress,
+        );
         let about_btn = create_menu_button("About", &self.menu_state, 10, Message::MenuAboutPress);
+        let console_btn: Element<_> = if self.developer_console {
+            create_menu_button("Console", &self.menu_state, 11, Message::MenuConsolePress).into()
+        } else {
+            Space::with_height(0).into()
+        };
+        // only in dev mode, see `net::simulation`
+        let debug_btn: Element<_> = if dev_mode() {
+            create_menu_button("Debug", &self.menu_state, 12, Message::MenuDebugPress).into()
+        } else {
+            Space::with_height(0).into()
+        };
         let logout_btn = button("Logout")
             .padding(10)
             .on_press(Message::LogoutPress)
@@ -407,6 +918,15 @@ impl Route for Settings {
                 network_btn,
                 backup_btn,
                 contacts_btn,
+                blocked_btn,
+                trash_btn,
+                templates_btn,
+                labels_btn,
+                storage_btn,
+                insights_btn,
+                translation_btn,
+                console_btn,
+                debug_btn,
                 about_btn,
                 Space::with_height(Length::Fill),
                 logout_btn
@@ -418,7 +938,7 @@ impl Route for Settings {
         .width(Length::Fixed(MENU_WIDTH))
         .padding([10, 5]);
 
-        let view_ct = container(self.menu_state.view(selected_theme))
+        let view_ct = container(self.menu_state.view(selected_theme, appearance))
             .padding([0, 0, 0, 20])
             .height(Length::Fill)
             .width(Length::FillPortion(3));
@@ -431,14 +951,16 @@ impl Route for Settings {
         .width(Length::Fill)
         .height(Length::Fill);
 
-        self.modal_state.view(underlay)
+        self.modal_state.view(underlay, appearance)
     }
 }
 
 enum ModalState {
     RelaysConfirmation(RelaysConfirmation<Message>),
     ContactDetails(ContactDetails<Message>),
+    ContactListReview(ContactListReview<Message>),
     ImportList(ImportContactList<Message>),
+    ImportRelays(ImportRelays<Message>),
     RelayDocument(RelayDocState<Message>),
     RelayBasic(RelayBasic<Message>),
     Off,
@@ -466,12 +988,18 @@ impl ModalState {
             ModalState::ContactDetails(state) => {
                 state.backend_event(event, conn)?;
             }
+            ModalState::ContactListReview(state) => {
+                state.backend_event(event, conn)?;
+            }
             ModalState::RelaysConfirmation(state) => {
                 state.backend_event(event, conn)?;
             }
             ModalState::ImportList(state) => {
                 state.backend_event(event, conn)?;
             }
+            ModalState::ImportRelays(state) => {
+                state.backend_event(event, conn)?;
+            }
             ModalState::RelayBasic(state) => {
                 state.backend_event(event, conn)?;
             }
@@ -479,12 +1007,14 @@ impl ModalState {
         }
         Ok(command)
     }
+    /// Also returns the contact operation submitted by the modal, if any
     fn update(
         &mut self,
         message: Message,
         conn: &mut BackEndConnection,
-    ) -> Result<Command<Message>, BackendClosed> {
+    ) -> Result<(Command<Message>, Option<(RequestId, ContactOp)>), BackendClosed> {
         let mut command = Command::none();
+        let mut contact_op = None;
 
         match message {
             Message::CloseModal => *self = Self::Off,
@@ -531,6 +1061,7 @@ impl ModalState {
                         }
                         other => {
                             let (cmd, close_modal) = state.update(other, conn)?;
+                            contact_op = state.take_submitted_op();
                             if close_modal {
                                 *self = ModalState::Off;
                             }
@@ -539,6 +1070,22 @@ impl ModalState {
                     }
                 }
             }
+            Message::ModalContactListReview(modal_msg) => {
+                if let ModalState::ContactListReview(state) = self {
+                    match *modal_msg {
+                        contact_list_review::CMessage::UnderlayMessage(message) => {
+                            return self.update(message, conn);
+                        }
+                        other => {
+                            let (cmd, close_modal) = state.update(other, conn)?;
+                            if close_modal {
+                                *self = ModalState::Off
+                            }
+                            command = cmd.map(|m| Message::ModalContactListReview(Box::new(m)));
+                        }
+                    }
+                }
+            }
             Message::ModalImportContactList(modal_msg) => {
                 if let ModalState::ImportList(state) = self {
                     match *modal_msg {
@@ -555,6 +1102,22 @@ impl ModalState {
                     }
                 }
             }
+            Message::ModalImportRelays(modal_msg) => {
+                if let ModalState::ImportRelays(state) = self {
+                    match *modal_msg {
+                        import_relays::CMessage::UnderlayMessage(message) => {
+                            return self.update(message, conn);
+                        }
+                        other => {
+                            let (cmd, close_modal) = state.update(other, conn)?;
+                            if close_modal {
+                                *self = ModalState::Off
+                            }
+                            command = cmd.map(|m| Message::ModalImportRelays(Box::new(m)));
+                        }
+                    }
+                }
+            }
             Message::ModalRelayDocument(modal_msg) => {
                 if let ModalState::RelayDocument(state) = self {
                     match *modal_msg {
@@ -574,26 +1137,36 @@ impl ModalState {
             _ => (),
         }
 
-        Ok(command)
+        Ok((command, contact_op))
     }
 
-    fn view<'a>(&'a self, underlay: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    fn view<'a>(
+        &'a self,
+        underlay: impl Into<Element<'a, Message>>,
+        appearance: Appearance,
+    ) -> Element<'a, Message> {
         let view: Element<_> = match self {
             ModalState::RelayBasic(state) => state
-                .view(underlay)
+                .view(underlay, appearance)
                 .map(|m| Message::ModalRelayBasic(Box::new(m))),
             ModalState::RelayDocument(state) => state
-                .view(underlay)
+                .view(underlay, appearance)
                 .map(|m| Message::ModalRelayDocument(Box::new(m))),
             ModalState::RelaysConfirmation(state) => state
-                .view(underlay)
+                .view(underlay, appearance)
                 .map(|m| Message::ModalRelaysConfirmation(Box::new(m))),
             ModalState::ContactDetails(state) => state
-                .view(underlay)
+                .view(underlay, appearance)
                 .map(|m| Message::ModalContactDetails(Box::new(m))),
+            ModalState::ContactListReview(state) => state
+                .view(underlay, appearance)
+                .map(|m| Message::ModalContactListReview(Box::new(m))),
             ModalState::ImportList(state) => state
-                .view(underlay)
+                .view(underlay, appearance)
                 .map(|m| Message::ModalImportContactList(Box::new(m))),
+            ModalState::ImportRelays(state) => state
+                .view(underlay, appearance)
+                .map(|m| Message::ModalImportRelays(Box::new(m))),
             ModalState::Off => underlay.into(),
         };
 
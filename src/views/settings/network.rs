@@ -1,16 +1,20 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::components::text::title;
 use crate::components::{common_scrollable, relay_row, RelayRow};
+use crate::config::{MediaProxyConfig, RelayTuningConfig};
+use crate::db::{DbRelayDocumentChange, DbRelayProfile, DbRelayViolation, RelaySuggestion};
 use crate::error::BackendClosed;
-use crate::icon::plus_icon;
+use crate::icon::{delete_icon, plus_icon, triangle_warn_icon, xmark_icon};
 use crate::net::{self, BackEndConnection, BackendEvent};
 use crate::style;
-use crate::utils::url_matches_search;
+use crate::types::RelayExportFormat;
+use crate::utils::{from_naive_utc_to_local, url_matches_search};
 use crate::widget::Element;
 use iced::alignment::{self};
-use iced::widget::{button, column, container, row, text, text_input, tooltip, Space};
+use iced::widget::{button, checkbox, column, container, row, text, text_input, tooltip, Space};
 use iced::{Alignment, Length, Subscription};
+use url::Url;
 
 use super::SettingsRouterMessage;
 
@@ -21,6 +25,32 @@ pub enum Message {
     SearchInputChange(String),
     Tick,
     SyncWithNTP,
+    ToggleDoNotTrack(bool),
+    ToggleDeveloperConsole(bool),
+    ToggleMediaProxy(bool),
+    MediaProxyChange(String),
+    SaveMediaProxy,
+    ImportRelays,
+    ExportRelays(RelayExportFormat),
+    ToggleAutoTune(bool),
+    IdleDaysPress(u32),
+    RequestsPerMinutePress(u32),
+    ApplySuggestion(RelaySuggestion),
+    ApplyAllSuggestions,
+    DismissRepublished,
+    ClearViolations(Url),
+    DismissDocumentChanges(Url),
+    RelayProfileInputChange(String),
+    SaveRelayProfile,
+    SwitchRelayProfile(String),
+    DeleteRelayProfile(String),
+}
+
+/// What was sent again to the relays just added
+struct Republished {
+    relays: Vec<Url>,
+    published: Vec<String>,
+    shown_at: Instant,
 }
 
 pub struct NtpInfo {
@@ -33,6 +63,23 @@ pub struct State {
     search_input: String,
     ntp_info: Option<NtpInfo>,
     ntp_btn_enabled: bool,
+    do_not_track: bool,
+    developer_console: bool,
+    media_proxy: MediaProxyConfig,
+    /// Address being typed, saved with the button
+    media_proxy_input: String,
+    /// Waiting for the export file dialog
+    exporting: bool,
+    tuning_config: RelayTuningConfig,
+    suggestions: Vec<RelaySuggestion>,
+    auto_applied: Vec<RelaySuggestion>,
+    republished: Option<Republished>,
+    violations: Vec<DbRelayViolation>,
+    /// What changed in the relays' NIP-11 documents, most recent first
+    document_changes: Vec<DbRelayDocumentChange>,
+    relay_profiles: Vec<DbRelayProfile>,
+    active_relay_profile: Option<String>,
+    relay_profile_input: String,
 }
 impl State {
     pub fn subscription(&self) -> Subscription<Message> {
@@ -41,16 +88,76 @@ impl State {
     pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
         conn.send(net::ToBackend::FetchRelays)?;
         conn.send(net::ToBackend::GetNtpInfo)?;
+        conn.send(net::ToBackend::GetDoNotTrack)?;
+        conn.send(net::ToBackend::GetDeveloperConsole)?;
+        conn.send(net::ToBackend::GetMediaProxy)?;
+        conn.send(net::ToBackend::FetchRelayTuning)?;
+        conn.send(net::ToBackend::FetchRelayViolations)?;
+        conn.send(net::ToBackend::FetchRelayDocumentChanges)?;
+        conn.send(net::ToBackend::FetchRelayProfiles)?;
         Ok(Self {
             relays: vec![],
             search_input: "".into(),
             ntp_info: None,
             ntp_btn_enabled: false,
+            do_not_track: false,
+            developer_console: false,
+            media_proxy: MediaProxyConfig::default(),
+            media_proxy_input: "".into(),
+            exporting: false,
+            tuning_config: RelayTuningConfig::default(),
+            suggestions: vec![],
+            auto_applied: vec![],
+            republished: None,
+            violations: vec![],
+            document_changes: vec![],
+            relay_profiles: vec![],
+            active_relay_profile: None,
+            relay_profile_input: "".into(),
         })
     }
 
     pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
         match event {
+            BackendEvent::DoNotTrack(do_not_track) => self.do_not_track = do_not_track,
+            BackendEvent::DeveloperConsole(enabled) => self.developer_console = enabled,
+            BackendEvent::GotMediaProxy(media_proxy) => {
+                self.media_proxy_input = media_proxy.address.clone();
+                self.media_proxy = media_proxy;
+            }
+            BackendEvent::RelaysRepublished { relays, published } => {
+                self.republished = Some(Republished {
+                    relays,
+                    published,
+                    shown_at: Instant::now(),
+                });
+            }
+            BackendEvent::GotRelayTuning {
+                config,
+                suggestions,
+                auto_applied,
+            } => {
+                self.tuning_config = config;
+                self.suggestions = suggestions;
+                self.auto_applied = auto_applied;
+            }
+            BackendEvent::GotRelayViolations(violations) => self.violations = violations,
+            BackendEvent::GotRelayDocumentChanges(changes) => self.document_changes = changes,
+            BackendEvent::RelayDocumentChanged(changes) => {
+                self.document_changes.splice(0..0, changes);
+            }
+            BackendEvent::GotRelayProfiles { profiles, active } => {
+                self.relay_profiles = profiles;
+                self.active_relay_profile = active;
+            }
+            BackendEvent::RelayViolation(violation) => {
+                self.violations
+                    .retain(|v| v.relay_url != violation.relay_url || v.kind != violation.kind);
+                self.violations.insert(0, violation);
+            }
+            BackendEvent::RFDSavedFile(_)
+            | BackendEvent::RFDCancelPick
+            | BackendEvent::RFDPickError(_) => self.exporting = false,
             BackendEvent::NtpInfo {
                 last_ntp_offset,
                 ntp_server,
@@ -103,6 +210,32 @@ impl State {
                 if !self.relays.is_empty() {
                     conn.send(net::ToBackend::GetRelayInformation)?;
                 }
+                if let Some(republished) = &self.republished {
+                    if republished.shown_at.elapsed() > REPUBLISHED_NOTICE_DURATION {
+                        self.republished = None;
+                    }
+                }
+            }
+            Message::DismissRepublished => self.republished = None,
+            Message::ClearViolations(url) => {
+                conn.send(net::ToBackend::ClearRelayViolations(url))?;
+            }
+            Message::DismissDocumentChanges(url) => {
+                conn.send(net::ToBackend::DismissRelayDocumentChanges(url))?;
+            }
+            Message::RelayProfileInputChange(text) => self.relay_profile_input = text,
+            Message::SaveRelayProfile => {
+                let name = self.relay_profile_input.trim().to_owned();
+                if !name.is_empty() {
+                    conn.send(net::ToBackend::SaveRelayProfile(name))?;
+                    self.relay_profile_input.clear();
+                }
+            }
+            Message::SwitchRelayProfile(name) => {
+                conn.send(net::ToBackend::SwitchRelayProfile(name))?;
+            }
+            Message::DeleteRelayProfile(name) => {
+                conn.send(net::ToBackend::DeleteRelayProfile(name))?;
             }
             Message::SearchInputChange(text) => {
                 self.search_input = text;
@@ -125,6 +258,59 @@ impl State {
                 self.ntp_btn_enabled = false;
                 conn.send(net::ToBackend::SyncWithNTP)?;
             }
+            Message::ToggleDoNotTrack(do_not_track) => {
+                conn.send(net::ToBackend::SetDoNotTrack(do_not_track))?;
+            }
+            Message::ToggleDeveloperConsole(enabled) => {
+                conn.send(net::ToBackend::SetDeveloperConsole(enabled))?;
+            }
+            Message::ToggleMediaProxy(enabled) => {
+                conn.send(net::ToBackend::SetMediaProxy(MediaProxyConfig {
+                    enabled,
+                    address: self.media_proxy_input.trim().to_owned(),
+                }))?;
+            }
+            Message::MediaProxyChange(text) => self.media_proxy_input = text,
+            Message::SaveMediaProxy => {
+                conn.send(net::ToBackend::SetMediaProxy(MediaProxyConfig {
+                    enabled: self.media_proxy.enabled,
+                    address: self.media_proxy_input.trim().to_owned(),
+                }))?;
+            }
+            Message::ImportRelays => {
+                let existing = self.relays.iter().map(|r| r.db_relay.url.clone()).collect();
+                return Ok(Some(SettingsRouterMessage::OpenImportRelaysModal(existing)));
+            }
+            Message::ExportRelays(format) => {
+                self.exporting = true;
+                conn.send(net::ToBackend::ExportRelays(format))?;
+            }
+            Message::ToggleAutoTune(auto_apply) => {
+                conn.send(net::ToBackend::SetRelayTuningConfig(RelayTuningConfig {
+                    auto_apply,
+                    ..self.tuning_config.clone()
+                }))?;
+            }
+            Message::IdleDaysPress(idle_days) => {
+                conn.send(net::ToBackend::SetRelayTuningConfig(RelayTuningConfig {
+                    idle_days,
+                    ..self.tuning_config.clone()
+                }))?;
+            }
+            Message::RequestsPerMinutePress(requests_per_minute) => {
+                conn.send(net::ToBackend::SetRelayTuningConfig(RelayTuningConfig {
+                    requests_per_minute,
+                    ..self.tuning_config.clone()
+                }))?;
+            }
+            Message::ApplySuggestion(suggestion) => {
+                conn.send(net::ToBackend::ApplyRelaySuggestions(vec![suggestion]))?;
+            }
+            Message::ApplyAllSuggestions => {
+                conn.send(net::ToBackend::ApplyRelaySuggestions(
+                    self.suggestions.clone(),
+                ))?;
+            }
         }
 
         Ok(None)
@@ -166,6 +352,59 @@ impl State {
         };
         let ntp_gp = column![ntp_title, ntp_content,].spacing(10);
 
+        let privacy_title = text("Privacy").size(24);
+        let do_not_track = checkbox("Do not track", self.do_not_track, Message::ToggleDoNotTrack);
+        let do_not_track_hint =
+            text("Only send messages and actions you explicitly make, nothing else leaves the app")
+                .size(14)
+                .style(style::Text::Placeholder);
+        let media_proxy = checkbox(
+            "Load media through a proxy",
+            self.media_proxy.enabled,
+            Message::ToggleMediaProxy,
+        );
+        let media_proxy_input = text_input(
+            "https://images.example.com/?url={url} or http://proxy:8080",
+            &self.media_proxy_input,
+        )
+        .on_input(Message::MediaProxyChange)
+        .on_submit(Message::SaveMediaProxy);
+        let mut media_proxy_save = button("Save").padding(5);
+        if self.media_proxy_input.trim() != self.media_proxy.address {
+            media_proxy_save = media_proxy_save.on_press(Message::SaveMediaProxy);
+        }
+        let media_proxy_hint = text(
+            "Images are fetched by this service so content hosts don't see your IP. \
+            With {url} the image address is put there, otherwise it's used as an HTTP proxy. \
+            Nothing is loaded while it's enabled and invalid",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+        let privacy_gp = column![
+            privacy_title,
+            do_not_track,
+            do_not_track_hint,
+            media_proxy,
+            row![media_proxy_input, media_proxy_save]
+                .align_items(Alignment::Center)
+                .spacing(5),
+            media_proxy_hint
+        ]
+        .spacing(10);
+
+        let developer_title = text("Developer").size(24);
+        let developer_console = checkbox(
+            "Developer console",
+            self.developer_console,
+            Message::ToggleDeveloperConsole,
+        );
+        let developer_console_hint =
+            text("Adds a Console page to send raw REQs to the relays and inspect what they return")
+                .size(14)
+                .style(style::Text::Placeholder);
+        let developer_gp =
+            column![developer_title, developer_console, developer_console_hint].spacing(10);
+
         let relays_title = text("Relays").size(24);
 
         let add_btn = tooltip(
@@ -184,7 +423,37 @@ impl State {
             .on_input(Message::SearchInputChange)
             .style(style::TextInput::ChatSearch)
             .width(SEARCH_WIDTH);
-        let utils_row = row![search_input, Space::with_width(Length::Fill), add_btn];
+        let import_btn = button(text("Import").size(18))
+            .padding(5)
+            .style(style::Button::Bordered)
+            .on_press(Message::ImportRelays);
+        let mut export_text_btn = button(text("Export .txt").size(18))
+            .padding(5)
+            .style(style::Button::Bordered);
+        let mut export_nip65_btn = button(text("Export NIP-65").size(18))
+            .padding(5)
+            .style(style::Button::Bordered);
+        if !self.exporting {
+            export_text_btn =
+                export_text_btn.on_press(Message::ExportRelays(RelayExportFormat::Text));
+            export_nip65_btn =
+                export_nip65_btn.on_press(Message::ExportRelays(RelayExportFormat::Nip65));
+        }
+        let export_nip65_btn = tooltip(
+            export_nip65_btn,
+            "Signed relay list event, not published",
+            tooltip::Position::Top,
+        )
+        .style(style::Container::TooltipBg);
+        let utils_row = row![
+            search_input,
+            Space::with_width(Length::Fill),
+            import_btn,
+            export_text_btn,
+            export_nip65_btn,
+            add_btn
+        ]
+        .spacing(5);
 
         let table_header = column![RelayRow::view_header().map(|mut message| {
             message.from = -1;
@@ -201,16 +470,358 @@ impl State {
         let relays_gp = column![relays_title, utils_row, relays_table].spacing(5);
 
         container(common_scrollable(
-            column![page_title, ntp_gp, relays_gp]
-                .spacing(10)
-                .padding([20, 20, 0, 0]),
+            column![
+                page_title,
+                self.republished_notice(),
+                self.violations_group(),
+                self.document_changes_group(),
+                privacy_gp,
+                ntp_gp,
+                relays_gp,
+                self.profiles_group(),
+                self.tuning_group(),
+                developer_gp
+            ]
+            .spacing(10)
+            .padding([20, 20, 0, 0]),
         ))
         .width(Length::Fill)
         .height(Length::Fill)
         .into()
     }
+
+    fn republished_notice(&self) -> Element<Message> {
+        let Some(republished) = &self.republished else {
+            return text("").into();
+        };
+        let relays: Vec<_> = republished.relays.iter().map(|url| url.as_str()).collect();
+        let close_btn = button(xmark_icon().size(14))
+            .style(style::Button::Invisible)
+            .on_press(Message::DismissRepublished);
+        container(
+            row![
+                column![
+                    text(format!("Sent to {}", relays.join(", "))),
+                    text(republished.published.join(", "))
+                        .size(14)
+                        .style(style::Text::Placeholder)
+                ]
+                .width(Length::Fill),
+                close_btn
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10),
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(style::Container::Foreground)
+        .into()
+    }
+
+    fn violations_group(&self) -> Element<Message> {
+        if self.violations.is_empty() {
+            return text("").into();
+        }
+        let violations_title = row![
+            triangle_warn_icon().size(20).style(style::Text::Danger),
+            text("Relay Warnings").size(24)
+        ]
+        .align_items(Alignment::Center)
+        .spacing(5);
+        let hint = text("Events beyond what these relays should send are being dropped or cut")
+            .size(14)
+            .style(style::Text::Placeholder);
+        let rows = self
+            .violations
+            .iter()
+            .fold(column![].spacing(4), |col, violation| {
+                col.push(violation_row(violation))
+            });
+        column![violations_title, hint, rows].spacing(10).into()
+    }
+
+    fn document_changes_group(&self) -> Element<Message> {
+        if self.document_changes.is_empty() {
+            return text("").into();
+        }
+        let title = text("Relay Updates").size(24);
+        let hint = text("Changes found in the relays' information documents")
+            .size(14)
+            .style(style::Text::Placeholder);
+
+        // one row per relay, in order of the latest change
+        let mut relays: Vec<(&Url, Vec<&DbRelayDocumentChange>)> = vec![];
+        for change in &self.document_changes {
+            match relays.iter_mut().find(|(url, _)| *url == &change.relay_url) {
+                Some((_, changes)) => changes.push(change),
+                None => relays.push((&change.relay_url, vec![change])),
+            }
+        }
+        let rows = relays
+            .into_iter()
+            .fold(column![].spacing(4), |col, (url, changes)| {
+                col.push(document_changes_row(url, &changes))
+            });
+        column![title, hint, rows].spacing(10).into()
+    }
+
+    fn profiles_group(&self) -> Element<Message> {
+        let profiles_title = text("Relay Profiles").size(24);
+        let hint = text(
+            "Saves the relays above with their read and write flags. \
+            Switching to a profile, here or in the status bar, replaces the relays with its own",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let profile_input = text_input("Profile name, e.g. home", &self.relay_profile_input)
+            .on_input(Message::RelayProfileInputChange)
+            .on_submit(Message::SaveRelayProfile);
+        let mut save_btn = button("Save current relays").padding(5);
+        if !self.relay_profile_input.trim().is_empty() {
+            save_btn = save_btn.on_press(Message::SaveRelayProfile);
+        }
+
+        let rows = self
+            .relay_profiles
+            .iter()
+            .fold(column![].spacing(4), |col, profile| {
+                let is_active = self.active_relay_profile.as_ref() == Some(&profile.name);
+                col.push(profile_row(profile, is_active))
+            });
+
+        column![
+            profiles_title,
+            hint,
+            row![profile_input, save_btn]
+                .align_items(Alignment::Center)
+                .spacing(5),
+            rows
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn tuning_group(&self) -> Element<Message> {
+        let tuning_title = text("Relay Tuning").size(24);
+        let hint = text(format!(
+            "Relays that delivered nothing new from your contacts in {} days can stop being read, relays that reject your events can stop being written to",
+            self.tuning_config.idle_days
+        ))
+        .size(14)
+        .style(style::Text::Placeholder);
+        let auto_apply = checkbox(
+            "Apply suggestions automatically at login",
+            self.tuning_config.auto_apply,
+            Message::ToggleAutoTune,
+        );
+        let idle_days = IDLE_DAYS_CHOICES.iter().fold(
+            row![text("Idle after").width(200)]
+                .spacing(5)
+                .align_items(Alignment::Center),
+            |row, days| {
+                let btn_style = if *days == self.tuning_config.idle_days {
+                    style::Button::ActiveMenuBtn
+                } else {
+                    style::Button::MenuBtn
+                };
+                row.push(
+                    button(text(format!("{} days", days)).size(14))
+                        .style(btn_style)
+                        .on_press(Message::IdleDaysPress(*days)),
+                )
+            },
+        );
+
+        let requests_per_minute = REQUESTS_PER_MINUTE_CHOICES.iter().fold(
+            row![text("Profile requests").width(200)]
+                .spacing(5)
+                .align_items(Alignment::Center),
+            |row, requests| {
+                let btn_style = if *requests == self.tuning_config.requests_per_minute {
+                    style::Button::ActiveMenuBtn
+                } else {
+                    style::Button::MenuBtn
+                };
+                row.push(
+                    button(text(format!("{}/min", requests)).size(14))
+                        .style(btn_style)
+                        .on_press(Message::RequestsPerMinutePress(*requests)),
+                )
+            },
+        );
+        let requests_hint = text(
+            "Profile requests sent to each relay per minute, more wait their turn. \
+            Some relays ban clients that open too many",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let suggestions: Element<_> = if self.suggestions.is_empty() {
+            text("Every relay is pulling its weight")
+                .style(style::Text::Placeholder)
+                .into()
+        } else {
+            let rows = self
+                .suggestions
+                .iter()
+                .fold(column![].spacing(4), |col, suggestion| {
+                    col.push(suggestion_row(suggestion, true))
+                });
+            let apply_all = button(text("Apply all").size(14))
+                .style(style::Button::Primary)
+                .on_press(Message::ApplyAllSuggestions);
+            column![rows, row![Space::with_width(Length::Fill), apply_all]]
+                .spacing(5)
+                .into()
+        };
+
+        let mut group = column![
+            tuning_title,
+            hint,
+            auto_apply,
+            idle_days,
+            requests_per_minute,
+            requests_hint,
+            suggestions
+        ]
+        .spacing(10);
+        if !self.auto_applied.is_empty() {
+            let applied = self.auto_applied.iter().fold(
+                column![text("Applied at login")].spacing(4),
+                |col, suggestion| col.push(suggestion_row(suggestion, false)),
+            );
+            group = group.push(applied);
+        }
+        group.into()
+    }
+}
+
+fn suggestion_row(suggestion: &RelaySuggestion, can_apply: bool) -> Element<Message> {
+    let mut content = row![column![
+        text(format!("{}: {}", suggestion.change, suggestion.url)),
+        text(&suggestion.reason)
+            .size(14)
+            .style(style::Text::Placeholder)
+    ]
+    .width(Length::Fill)]
+    .align_items(Alignment::Center)
+    .spacing(10);
+    if can_apply {
+        content = content.push(
+            button(text("Apply").size(14))
+                .style(style::Button::Bordered)
+                .on_press(Message::ApplySuggestion(suggestion.to_owned())),
+        );
+    }
+    container(content)
+        .padding(5)
+        .style(style::Container::Foreground)
+        .into()
+}
+
+fn profile_row(profile: &DbRelayProfile, is_active: bool) -> Element<Message> {
+    let urls: Vec<_> = profile
+        .relays
+        .iter()
+        .map(|relay| match (relay.read, relay.write) {
+            (true, true) => relay.url.to_string(),
+            (true, false) => format!("{} (read)", relay.url),
+            (false, true) => format!("{} (write)", relay.url),
+            (false, false) => format!("{} (off)", relay.url),
+        })
+        .collect();
+    let mut name = text(&profile.name);
+    let mut switch_btn = button(text(if is_active { "Active" } else { "Switch" }).size(14))
+        .style(style::Button::Bordered);
+    if is_active {
+        name = name.style(style::Text::Primary);
+    } else {
+        switch_btn = switch_btn.on_press(Message::SwitchRelayProfile(profile.name.to_owned()));
+    }
+    let delete_btn = button(delete_icon().size(16))
+        .style(style::Button::Invisible)
+        .on_press(Message::DeleteRelayProfile(profile.name.to_owned()));
+    container(
+        row![
+            column![
+                name,
+                text(urls.join(", "))
+                    .size(14)
+                    .style(style::Text::Placeholder)
+            ]
+            .width(Length::Fill),
+            switch_btn,
+            delete_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
+}
+
+fn violation_row(violation: &DbRelayViolation) -> Element<Message> {
+    let last_at = from_naive_utc_to_local(violation.last_at);
+    let details = format!(
+        "{} times, last on {} at {}",
+        violation.count,
+        violation.subscription_id,
+        last_at.format("%Y-%m-%d %H:%M")
+    );
+    let dismiss_btn = button(text("Dismiss").size(14))
+        .style(style::Button::Bordered)
+        .on_press(Message::ClearViolations(violation.relay_url.to_owned()));
+    container(
+        row![
+            column![
+                text(format!("{}: {}", violation.relay_url, violation.kind)),
+                text(details).size(14).style(style::Text::Placeholder)
+            ]
+            .width(Length::Fill),
+            dismiss_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
+}
+
+fn document_changes_row<'a>(url: &Url, changes: &[&DbRelayDocumentChange]) -> Element<'a, Message> {
+    let lines = changes.iter().fold(column![].spacing(2), |col, change| {
+        let detected_at = from_naive_utc_to_local(change.detected_at);
+        col.push(
+            text(format!(
+                "{} ({})",
+                change.change,
+                detected_at.format("%Y-%m-%d %H:%M")
+            ))
+            .size(14)
+            .style(style::Text::Placeholder),
+        )
+    });
+    let dismiss_btn = button(text("Dismiss").size(14))
+        .style(style::Button::Bordered)
+        .on_press(Message::DismissDocumentChanges(url.to_owned()));
+    container(
+        row![
+            column![text(url.to_string()), lines].width(Length::Fill),
+            dismiss_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
 }
 
 const HEADER_HEIGHT: f32 = 50.0;
 const SEARCH_WIDTH: f32 = 200.0;
 const TICK_INTERVAL_MILLIS: u64 = 500;
+const IDLE_DAYS_CHOICES: [u32; 3] = [7, 14, 30];
+const REQUESTS_PER_MINUTE_CHOICES: [u32; 4] = [10, 20, 40, 80];
+const REPUBLISHED_NOTICE_DURATION: Duration = Duration::from_secs(8);
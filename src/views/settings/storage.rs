@@ -0,0 +1,496 @@
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::components::text_input_group::TextInputGroup;
+use crate::config::{DownloadConfig, StorageConfig};
+use crate::db::{DbEvent, PruneReport, StorageUsage};
+use crate::error::BackendClosed;
+use crate::icon::solid_circle_icon;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::utils::{format_bytes, hide_string, parse_event_link};
+use crate::widget::Element;
+use iced::widget::{button, checkbox, column, container, row, text, Space};
+use iced::{Alignment, Color, Length};
+use nostr::EventId;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    MaxSizeChange(String),
+    MaxChannelMessagesChange(String),
+    TogglePruneDms(bool),
+    SavePress,
+    PruneNowPress,
+    ChooseDownloadDirPress,
+    DefaultDownloadDirPress,
+    EventIdChange(String),
+    LookUpEventPress,
+    DeleteEventPress,
+    ConfirmDeleteEventPress,
+    CancelDeleteEventPress,
+}
+
+/// Stored event found by its id, `None` when it isn't stored
+struct InspectedEvent {
+    event_hash: EventId,
+    event: Option<DbEvent>,
+    reactions: i64,
+}
+
+pub struct State {
+    max_size: String,
+    max_channel_messages: String,
+    prune_dms: bool,
+    is_saved: bool,
+    usage: Option<StorageUsage>,
+    last_prune: Option<PruneReport>,
+    pruning: bool,
+    downloads: DownloadConfig,
+    event_id_input: String,
+    inspected: Option<InspectedEvent>,
+    confirm_delete: bool,
+    /// Events removed by the last deletion
+    last_deletion: Option<u64>,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::GetStorageConfig)?;
+        conn.send(ToBackend::FetchStorageUsage)?;
+        conn.send(ToBackend::GetDownloads)?;
+        Ok(Self {
+            max_size: "".into(),
+            max_channel_messages: "".into(),
+            prune_dms: false,
+            is_saved: true,
+            usage: None,
+            last_prune: None,
+            pruning: false,
+            downloads: DownloadConfig::default(),
+            event_id_input: "".into(),
+            inspected: None,
+            confirm_delete: false,
+            last_deletion: None,
+        })
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        match event {
+            BackendEvent::GotStorageConfig(config) => {
+                self.max_size = config
+                    .max_db_size_mb
+                    .map(|mb| mb.to_string())
+                    .unwrap_or_default();
+                self.max_channel_messages = config
+                    .max_channel_messages
+                    .map(|max| max.to_string())
+                    .unwrap_or_default();
+                self.prune_dms = config.prune_dms;
+                self.is_saved = true;
+            }
+            BackendEvent::GotStorageUsage(usage) => {
+                self.usage = Some(usage);
+                self.pruning = false;
+            }
+            BackendEvent::StoragePruned(report) => {
+                self.last_prune = Some(report);
+            }
+            BackendEvent::DownloadsChanged(downloads) => {
+                self.downloads = downloads;
+            }
+            BackendEvent::GotLocalEvent {
+                event_hash,
+                event,
+                reactions,
+            } => {
+                if parse_event_id(&self.event_id_input) == Some(event_hash) {
+                    self.inspected = Some(InspectedEvent {
+                        event_hash,
+                        event,
+                        reactions,
+                    });
+                }
+            }
+            BackendEvent::LocalEventDeleted(event_hash, removed) => {
+                if self.inspected.as_ref().map(|i| i.event_hash) == Some(event_hash) {
+                    self.inspected = None;
+                    self.event_id_input.clear();
+                }
+                self.last_deletion = Some(removed);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::MaxSizeChange(text) => self.max_size = text,
+            Message::MaxChannelMessagesChange(text) => self.max_channel_messages = text,
+            Message::TogglePruneDms(prune_dms) => self.prune_dms = prune_dms,
+            Message::SavePress => {
+                if let Some(config) = self.to_config() {
+                    conn.send(ToBackend::SetStorageConfig(config))?;
+                }
+                return Ok(());
+            }
+            Message::PruneNowPress => {
+                self.pruning = true;
+                conn.send(ToBackend::PruneStorage)?;
+                return Ok(());
+            }
+            Message::ChooseDownloadDirPress => {
+                conn.send(ToBackend::ChooseDownloadDirectory)?;
+                return Ok(());
+            }
+            Message::DefaultDownloadDirPress => {
+                conn.send(ToBackend::SetDownloads(DownloadConfig::default()))?;
+                return Ok(());
+            }
+            Message::EventIdChange(text) => {
+                self.event_id_input = text;
+                self.inspected = None;
+                self.confirm_delete = false;
+                return Ok(());
+            }
+            Message::LookUpEventPress => {
+                if let Some(event_hash) = parse_event_id(&self.event_id_input) {
+                    self.last_deletion = None;
+                    conn.send(ToBackend::FetchLocalEvent(event_hash))?;
+                }
+                return Ok(());
+            }
+            Message::DeleteEventPress => {
+                self.confirm_delete = true;
+                return Ok(());
+            }
+            Message::ConfirmDeleteEventPress => {
+                self.confirm_delete = false;
+                if let Some(inspected) = &self.inspected {
+                    conn.send(ToBackend::DeleteLocalEvent(inspected.event_hash))?;
+                }
+                return Ok(());
+            }
+            Message::CancelDeleteEventPress => {
+                self.confirm_delete = false;
+                return Ok(());
+            }
+        }
+        self.is_saved = false;
+        Ok(())
+    }
+
+    /// `None` when a limit isn't a positive number, empty means no limit
+    fn to_config(&self) -> Option<StorageConfig> {
+        Some(StorageConfig {
+            max_db_size_mb: parse_limit(&self.max_size)?,
+            max_channel_messages: parse_limit(&self.max_channel_messages)?,
+            prune_dms: self.prune_dms,
+        })
+    }
+
+    fn limits_form(&self) -> Element<Message> {
+        let mut max_size_input = TextInputGroup::new(
+            "Max database size (MB)",
+            &self.max_size,
+            Message::MaxSizeChange,
+        )
+        .placeholder("No limit")
+        .tooltip("The oldest messages are deleted when the database grows past this size");
+        if parse_limit::<u64>(&self.max_size).is_none() {
+            max_size_input = max_size_input.invalid("Must be a positive number");
+        }
+        let mut max_channel_input = TextInputGroup::new(
+            "Messages kept per channel",
+            &self.max_channel_messages,
+            Message::MaxChannelMessagesChange,
+        )
+        .placeholder("No limit");
+        if parse_limit::<u32>(&self.max_channel_messages).is_none() {
+            max_channel_input = max_channel_input.invalid("Must be a positive number");
+        }
+        let prune_dms = checkbox(
+            "Also delete direct messages",
+            self.prune_dms,
+            Message::TogglePruneDms,
+        );
+        let prune_dms_hint =
+            text("Direct messages are only deleted to meet the size limit, after channel messages")
+                .size(14)
+                .style(style::Text::Placeholder);
+
+        let mut save_btn = button("Save").padding(10);
+        if !self.is_saved && self.to_config().is_some() {
+            save_btn = save_btn.on_press(Message::SavePress);
+        }
+        let mut prune_btn = button("Prune now")
+            .padding(10)
+            .style(style::Button::Bordered);
+        if !self.pruning && self.is_saved {
+            prune_btn = prune_btn.on_press(Message::PruneNowPress);
+        }
+
+        column![
+            max_size_input.build(),
+            max_channel_input.build(),
+            prune_dms,
+            prune_dms_hint,
+            row![Space::with_width(Length::Fill), prune_btn, save_btn].spacing(10),
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn downloads_form(&self) -> Element<Message> {
+        let directory = match self.downloads.resolve_directory() {
+            Some(directory) => text(directory.display().to_string()),
+            None => text("No download directory").style(style::Text::Danger),
+        };
+        let hint = text(
+            "Files are saved in a folder named after the contact, from the chat's \
+             \"Show in folder\" menu or as they arrive in conversations with auto-save on",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let choose_btn = button("Choose...")
+            .padding(10)
+            .on_press(Message::ChooseDownloadDirPress);
+        let mut default_btn = button("Use default")
+            .padding(10)
+            .style(style::Button::Bordered);
+        if self.downloads.directory.is_some() {
+            default_btn = default_btn.on_press(Message::DefaultDownloadDirPress);
+        }
+
+        column![
+            row![directory.width(Length::Fill), default_btn, choose_btn]
+                .align_items(Alignment::Center)
+                .spacing(10),
+            hint,
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    /// Looks an event up by its id and deletes it with what was made from it
+    fn delete_event_form(&self) -> Element<Message> {
+        let is_valid = parse_event_id(&self.event_id_input).is_some();
+        let mut id_input =
+            TextInputGroup::new("Event id", &self.event_id_input, Message::EventIdChange)
+                .placeholder("note, nevent or hex id")
+                .on_submit(Message::LookUpEventPress);
+        if !self.event_id_input.is_empty() && !is_valid {
+            id_input = id_input.invalid("Not an event id");
+        }
+        let mut look_up_btn = button("Look up").padding(10);
+        if is_valid {
+            look_up_btn = look_up_btn.on_press(Message::LookUpEventPress);
+        }
+        let hint = text(
+            "Removes a single stored event, like spam, with its reactions and the messages \
+             made from it. Nothing else is touched",
+        )
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let mut content = column![
+            row![id_input.build(), look_up_btn]
+                .align_items(Alignment::End)
+                .spacing(10),
+            hint
+        ]
+        .spacing(10);
+
+        match &self.inspected {
+            Some(InspectedEvent {
+                event: Some(event),
+                reactions,
+                ..
+            }) => {
+                let preview: String = event.content.chars().take(PREVIEW_CHARS).collect();
+                let details = column![
+                    text(format!(
+                        "Kind {} by {}, {}",
+                        event.kind.as_u32(),
+                        hide_string(&event.pubkey.to_string(), 8),
+                        event.created_at.format("%Y-%m-%d %H:%M UTC")
+                    ))
+                    .size(14),
+                    text(preview),
+                    text(format!("Reactions deleted with it: {}", reactions))
+                        .size(14)
+                        .style(style::Text::Placeholder),
+                ]
+                .spacing(5)
+                .width(Length::Fill);
+                let actions: Element<_> = if self.confirm_delete {
+                    row![
+                        text("Delete for good?"),
+                        button("Cancel")
+                            .padding(10)
+                            .style(style::Button::Bordered)
+                            .on_press(Message::CancelDeleteEventPress),
+                        button("Delete")
+                            .padding(10)
+                            .style(style::Button::Danger)
+                            .on_press(Message::ConfirmDeleteEventPress),
+                    ]
+                    .align_items(Alignment::Center)
+                    .spacing(10)
+                    .into()
+                } else {
+                    button("Delete")
+                        .padding(10)
+                        .style(style::Button::Danger)
+                        .on_press(Message::DeleteEventPress)
+                        .into()
+                };
+                content = content.push(
+                    container(row![details, actions].spacing(10))
+                        .padding(10)
+                        .style(style::Container::Foreground),
+                );
+            }
+            Some(InspectedEvent { event: None, .. }) => {
+                content = content.push(text("This event isn't stored"));
+            }
+            None => (),
+        }
+        if let Some(removed) = self.last_deletion {
+            content = content.push(
+                text(format!("Deleted {} events", removed))
+                    .size(14)
+                    .style(style::Text::Placeholder),
+            );
+        }
+
+        content.into()
+    }
+
+    fn usage_chart(&self) -> Element<Message> {
+        let Some(usage) = &self.usage else {
+            return text("Loading...").into();
+        };
+
+        let messages = usage.messages + usage.channel_messages;
+        let other = usage.database.saturating_sub(usage.events + messages);
+        let parts = [
+            ("Events", usage.events, EVENTS_COLOR),
+            ("Messages", messages, MESSAGES_COLOR),
+            ("Images", usage.images, IMAGES_COLOR),
+            ("Indexes and other", other, OTHER_COLOR),
+        ];
+        let total = parts.iter().map(|(_, bytes, _)| bytes).sum::<u64>().max(1);
+
+        let bar = parts.iter().filter(|(_, bytes, _)| *bytes > 0).fold(
+            row![].height(BAR_HEIGHT),
+            |bar, (_, bytes, color)| {
+                let portion = (bytes * BAR_PORTIONS / total).max(1) as u16;
+                bar.push(
+                    container(Space::with_width(Length::Fill))
+                        .width(Length::FillPortion(portion))
+                        .height(Length::Fill)
+                        .style(style::Container::WithColor(rgb(*color))),
+                )
+            },
+        );
+
+        let legend = parts
+            .iter()
+            .fold(column![].spacing(5), |col, (name, bytes, color)| {
+                col.push(
+                    row![
+                        solid_circle_icon()
+                            .size(LEGEND_DOT_SIZE)
+                            .style(style::Text::Color(rgb(*color))),
+                        text(name).width(Length::Fill),
+                        text(format_bytes(*bytes)),
+                    ]
+                    .align_items(Alignment::Center)
+                    .spacing(10),
+                )
+            });
+
+        column![
+            row![
+                text("Total").width(Length::Fill),
+                text(format_bytes(usage.total()))
+            ],
+            bar,
+            legend
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Storage");
+        let limits_title = text("Limits").size(24);
+        let usage_title = text("Usage").size(24);
+        let downloads_title = text("Downloads").size(24);
+        let delete_event_title = text("Delete an event").size(24);
+
+        let mut content = column![
+            title,
+            usage_title,
+            self.usage_chart(),
+            limits_title,
+            self.limits_form(),
+            downloads_title,
+            self.downloads_form(),
+            delete_event_title,
+            self.delete_event_form()
+        ]
+        .spacing(10);
+        if let Some(report) = &self.last_prune {
+            content = content.push(
+                text(format!(
+                    "Last pruning removed {} channel messages and {} direct messages",
+                    report.channel_messages_removed, report.messages_removed
+                ))
+                .size(14)
+                .style(style::Text::Placeholder),
+            );
+        }
+
+        container(common_scrollable(content.padding([20, 20, 0, 0])))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+/// Accepts `note`, `nevent` and hex ids
+fn parse_event_id(input: &str) -> Option<EventId> {
+    let input = input.trim();
+    parse_event_link(input)
+        .map(|(event_id, _)| event_id)
+        .or_else(|| EventId::from_hex(input).ok())
+}
+
+/// Outer `None` when invalid, inner `None` when empty
+fn parse_limit<T: std::str::FromStr + Default + PartialEq>(input: &str) -> Option<Option<T>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Some(None);
+    }
+    match input.parse::<T>() {
+        Ok(value) if value != T::default() => Some(Some(value)),
+        _ => None,
+    }
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::from_rgb8(r, g, b)
+}
+
+const PREVIEW_CHARS: usize = 200;
+const BAR_HEIGHT: f32 = 24.0;
+const BAR_PORTIONS: u64 = 1000;
+const LEGEND_DOT_SIZE: u16 = 14;
+const EVENTS_COLOR: (u8, u8, u8) = (0x1e, 0x88, 0xe5);
+const MESSAGES_COLOR: (u8, u8, u8) = (0x43, 0xa0, 0x47);
+const IMAGES_COLOR: (u8, u8, u8) = (0xfb, 0x8c, 0x00);
+const OTHER_COLOR: (u8, u8, u8) = (0x75, 0x75, 0x75);
@@ -0,0 +1,207 @@
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::components::text_input_group::TextInputGroup;
+use crate::db::template::{CURSOR_PLACEHOLDER, NAME_PLACEHOLDER};
+use crate::db::DbTemplate;
+use crate::error::BackendClosed;
+use crate::icon::{delete_icon, edit_icon};
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::widget::Element;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Alignment, Length};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    NameChange(String),
+    ContentChange(String),
+    EditPress(DbTemplate),
+    DeletePress(DbTemplate),
+    CancelPress,
+    SavePress,
+}
+
+pub struct State {
+    templates: Vec<DbTemplate>,
+    editing: Option<DbTemplate>,
+    name: String,
+    content: String,
+    error: Option<String>,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchTemplates)?;
+        Ok(Self {
+            templates: vec![],
+            editing: None,
+            name: "".into(),
+            content: "".into(),
+            error: None,
+        })
+    }
+
+    fn clear_form(&mut self) {
+        self.editing = None;
+        self.name = "".into();
+        self.content = "".into();
+        self.error = None;
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        match event {
+            BackendEvent::GotTemplates(templates) => {
+                self.templates = templates;
+            }
+            BackendEvent::TemplateError(e) => {
+                self.error = Some(e);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::NameChange(text) => self.name = text,
+            Message::ContentChange(text) => self.content = text,
+            Message::EditPress(template) => {
+                self.name = template.name.to_owned();
+                self.content = template.content.to_owned();
+                self.editing = Some(template);
+                self.error = None;
+            }
+            Message::DeletePress(template) => {
+                if self.editing.as_ref() == Some(&template) {
+                    self.clear_form();
+                }
+                conn.send(ToBackend::DeleteTemplate(template))?;
+            }
+            Message::CancelPress => self.clear_form(),
+            Message::SavePress => {
+                match self.editing.take() {
+                    Some(template) => conn.send(ToBackend::UpdateTemplate(DbTemplate {
+                        name: self.name.to_owned(),
+                        content: self.content.to_owned(),
+                        ..template
+                    }))?,
+                    None => conn.send(ToBackend::AddTemplate(
+                        self.name.to_owned(),
+                        self.content.to_owned(),
+                    ))?,
+                }
+                self.clear_form();
+            }
+        }
+        Ok(())
+    }
+
+    fn form(&self) -> Element<Message> {
+        let name_input = TextInputGroup::new("Name", &self.name, Message::NameChange)
+            .placeholder("thanks")
+            .tooltip("Type /name in the chat input to insert it")
+            .build();
+        let content_input = TextInputGroup::new("Content", &self.content, Message::ContentChange)
+            .placeholder("Thanks {name}! {cursor}")
+            .on_submit(Message::SavePress)
+            .build();
+        let hint = text(format!(
+            "{} is replaced by the contact's name, {} is where the cursor goes",
+            NAME_PLACEHOLDER, CURSOR_PLACEHOLDER
+        ))
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let preview_template = DbTemplate {
+            id: 0,
+            name: self.name.to_owned(),
+            content: self.content.to_owned(),
+        };
+        let (preview, _) = preview_template.expand(PREVIEW_NAME);
+        let preview = container(text(preview))
+            .padding(10)
+            .width(Length::Fill)
+            .style(style::Container::ReceivedMessage);
+
+        let mut save_btn = button(if self.editing.is_some() {
+            "Save"
+        } else {
+            "Add"
+        })
+        .padding(10);
+        if !self.name.trim().is_empty() && !self.content.is_empty() {
+            save_btn = save_btn.on_press(Message::SavePress);
+        }
+        let mut buttons = row![Space::with_width(Length::Fill)].spacing(10);
+        if self.editing.is_some() {
+            buttons = buttons.push(
+                button("Cancel")
+                    .padding(10)
+                    .style(style::Button::Bordered)
+                    .on_press(Message::CancelPress),
+            );
+        }
+        buttons = buttons.push(save_btn);
+
+        let mut form =
+            column![name_input, content_input, hint, text("Preview"), preview].spacing(10);
+        if let Some(error) = &self.error {
+            form = form.push(text(error).style(style::Text::Danger));
+        }
+        form.push(buttons).into()
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Templates");
+
+        let list: Element<_> = if self.templates.is_empty() {
+            text("No templates yet")
+                .style(style::Text::Placeholder)
+                .into()
+        } else {
+            self.templates
+                .iter()
+                .fold(column![].spacing(5), |col, template| {
+                    col.push(template_row(template))
+                })
+                .into()
+        };
+
+        container(
+            column![title, self.form(), common_scrollable(list)]
+                .padding([20, 20, 0, 0])
+                .spacing(10),
+        )
+        .into()
+    }
+}
+
+fn template_row(template: &DbTemplate) -> Element<Message> {
+    let edit_btn = button(edit_icon().size(16))
+        .style(style::Button::Invisible)
+        .on_press(Message::EditPress(template.to_owned()));
+    let delete_btn = button(delete_icon().size(16))
+        .style(style::Button::Invisible)
+        .on_press(Message::DeletePress(template.to_owned()));
+
+    container(
+        row![
+            text(format!("/{}", template.name)).width(NAME_WIDTH),
+            text(&template.content)
+                .style(style::Text::Placeholder)
+                .width(Length::Fill),
+            edit_btn,
+            delete_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
+}
+
+const PREVIEW_NAME: &str = "Alice";
+const NAME_WIDTH: u16 = 150;
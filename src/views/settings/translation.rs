@@ -0,0 +1,112 @@
+use crate::components::text::title;
+use crate::components::text_input_group::TextInputGroup;
+use crate::config::TranslationConfig;
+use crate::error::BackendClosed;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::widget::Element;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::Length;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    EndpointChange(String),
+    ApiKeyChange(String),
+    TargetLangChange(String),
+    SavePress,
+}
+
+pub struct State {
+    endpoint: String,
+    api_key: String,
+    target_lang: String,
+    is_saved: bool,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::GetTranslationConfig)?;
+        Ok(Self {
+            endpoint: "".into(),
+            api_key: "".into(),
+            target_lang: "".into(),
+            is_saved: true,
+        })
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        if let BackendEvent::GotTranslationConfig(config) = event {
+            self.endpoint = config.endpoint.unwrap_or_default();
+            self.api_key = config.api_key.unwrap_or_default();
+            self.target_lang = config.target_lang;
+            self.is_saved = true;
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::EndpointChange(text) => self.endpoint = text,
+            Message::ApiKeyChange(text) => self.api_key = text,
+            Message::TargetLangChange(text) => self.target_lang = text,
+            Message::SavePress => {
+                conn.send(ToBackend::SetTranslationConfig(self.to_config()))?;
+                return Ok(());
+            }
+        }
+        self.is_saved = false;
+        Ok(())
+    }
+
+    fn to_config(&self) -> TranslationConfig {
+        let non_empty = |s: &str| Some(s.trim().to_owned()).filter(|s| !s.is_empty());
+        TranslationConfig {
+            endpoint: non_empty(&self.endpoint),
+            api_key: non_empty(&self.api_key),
+            target_lang: self.target_lang.trim().to_lowercase(),
+        }
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Translation");
+        let about = text("Messages are translated by a LibreTranslate compatible provider. Leave the endpoint empty to turn translation off.")
+            .style(style::Text::Placeholder);
+
+        let endpoint_input =
+            TextInputGroup::new("Endpoint", &self.endpoint, Message::EndpointChange)
+                .placeholder("https://libretranslate.example.com/translate")
+                .build();
+        let api_key_input = TextInputGroup::new("API Key", &self.api_key, Message::ApiKeyChange)
+            .placeholder("Optional")
+            .build();
+        let target_lang_input = TextInputGroup::new(
+            "Target language",
+            &self.target_lang,
+            Message::TargetLangChange,
+        )
+        .placeholder("en")
+        .tooltip("Language code messages are translated into")
+        .build();
+
+        let mut save_btn = button("Save").padding(10);
+        if !self.is_saved && !self.target_lang.trim().is_empty() {
+            save_btn = save_btn.on_press(Message::SavePress);
+        }
+
+        container(
+            column![
+                title,
+                about,
+                endpoint_input,
+                api_key_input,
+                target_lang_input,
+                row![Space::with_width(Length::Fill), save_btn],
+            ]
+            .padding([20, 20, 0, 0])
+            .spacing(10),
+        )
+        .into()
+    }
+}
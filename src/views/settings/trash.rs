@@ -0,0 +1,156 @@
+use crate::components::common_scrollable;
+use crate::components::text::title;
+use crate::consts::YMD_FORMAT;
+use crate::db::trash::RETENTION_DAYS;
+use crate::db::{TrashItem, TrashKind};
+use crate::error::BackendClosed;
+use crate::net::{BackEndConnection, BackendEvent, ToBackend};
+use crate::style;
+use crate::utils::{from_naive_utc_to_local, hide_string};
+use crate::widget::Element;
+use iced::widget::{button, column, container, row, text};
+use iced::{Alignment, Length};
+use nostr::prelude::ToBech32;
+use nostr::secp256k1::XOnlyPublicKey;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    RestorePress(TrashKind, XOnlyPublicKey),
+    PurgePress(TrashKind, XOnlyPublicKey),
+    EmptyPress,
+    ConfirmEmpty(bool),
+}
+
+pub struct State {
+    items: Vec<TrashItem>,
+    confirm_empty: bool,
+}
+impl State {
+    pub fn new(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::FetchTrash)?;
+        Ok(Self {
+            items: vec![],
+            confirm_empty: false,
+        })
+    }
+
+    pub fn backend_event(&mut self, event: BackendEvent, _conn: &mut BackEndConnection) {
+        if let BackendEvent::GotTrash(items) = event {
+            self.items = items;
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        conn: &mut BackEndConnection,
+    ) -> Result<(), BackendClosed> {
+        match message {
+            Message::RestorePress(kind, pubkey) => {
+                conn.send(ToBackend::RestoreFromTrash(kind, pubkey))?
+            }
+            Message::PurgePress(kind, pubkey) => {
+                conn.send(ToBackend::PurgeFromTrash(kind, pubkey))?
+            }
+            Message::EmptyPress => self.confirm_empty = true,
+            Message::ConfirmEmpty(confirmed) => {
+                self.confirm_empty = false;
+                if confirmed {
+                    conn.send(ToBackend::EmptyTrash)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let title = title("Trash");
+        let hint = text(format!(
+            "Deleted contacts and cleared conversations stay here for {} days before they are removed for good",
+            RETENTION_DAYS
+        ))
+        .size(14)
+        .style(style::Text::Placeholder);
+
+        let empty_row: Element<_> = if self.items.is_empty() {
+            text("The trash is empty")
+                .style(style::Text::Placeholder)
+                .into()
+        } else if self.confirm_empty {
+            row![
+                text("Delete everything in the trash for good?").width(Length::Fill),
+                button(text("Cancel").size(14))
+                    .style(style::Button::Bordered)
+                    .on_press(Message::ConfirmEmpty(false)),
+                button(text("Empty trash").size(14))
+                    .style(style::Button::Danger)
+                    .on_press(Message::ConfirmEmpty(true)),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .into()
+        } else {
+            button(text("Empty trash").size(14))
+                .style(style::Button::Danger)
+                .on_press(Message::EmptyPress)
+                .into()
+        };
+
+        let list = self
+            .items
+            .iter()
+            .fold(column![].spacing(5), |col, item| col.push(trash_row(item)));
+
+        container(
+            column![title, hint, empty_row, common_scrollable(list)]
+                .padding([20, 20, 0, 0])
+                .spacing(10),
+        )
+        .into()
+    }
+}
+
+fn trash_row(item: &TrashItem) -> Element<'_, Message> {
+    let npub = item
+        .pubkey
+        .to_bech32()
+        .unwrap_or_else(|_| item.pubkey.to_string());
+    let name = item.name.clone().unwrap_or_else(|| hide_string(&npub, 12));
+    let what = match item.kind {
+        TrashKind::Contact => format!("Contact {}", name),
+        TrashKind::Conversation => format!(
+            "Conversation with {}, {} message{}",
+            name,
+            item.messages,
+            if item.messages == 1 { "" } else { "s" }
+        ),
+    };
+    let details = format!(
+        "Deleted {}, removed for good on {}",
+        from_naive_utc_to_local(item.deleted_at).format(YMD_FORMAT),
+        from_naive_utc_to_local(item.expires_at()).format(YMD_FORMAT)
+    );
+    let restore_btn = button(text("Restore").size(14))
+        .style(style::Button::Bordered)
+        .on_press(Message::RestorePress(item.kind, item.pubkey));
+    let purge_btn = button(text("Delete").size(14))
+        .style(style::Button::Danger)
+        .on_press(Message::PurgePress(item.kind, item.pubkey));
+
+    container(
+        row![
+            column![
+                text(what),
+                text(details).size(14).style(style::Text::Placeholder)
+            ]
+            .width(Length::Fill),
+            restore_btn,
+            purge_btn
+        ]
+        .align_items(Alignment::Center)
+        .spacing(10),
+    )
+    .padding(5)
+    .style(style::Container::Foreground)
+    .into()
+}
@@ -12,6 +12,7 @@ use crate::components::chat_contact::ChatContact;
 use crate::components::chat_list_container::{self, ChatListContainer};
 use crate::components::common_scrollable;
 use crate::components::text::title;
+use crate::config::Appearance;
 use crate::db::{DbContact, MessageStatus};
 use crate::net::{BackEndConnection, BackendEvent, ToBackend};
 use crate::style::{AppPalette, Theme};
@@ -180,7 +181,11 @@ impl Route for State {
 
         command
     }
-    fn view(&self, selected_theme: Option<Theme>) -> Element<'_, Self::Message> {
+    fn view(
+        &self,
+        selected_theme: Option<Theme>,
+        _appearance: Appearance,
+    ) -> Element<'_, Self::Message> {
         let title = title("Theme Styling");
 
         if let Some(palette) = self.app_palette {
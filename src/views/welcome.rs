@@ -1,15 +1,19 @@
 use iced::alignment::Horizontal;
-use iced::widget::{button, column, container, image, row, text, Space};
+use iced::widget::{button, checkbox, column, container, image, row, text, Space};
 use iced::{Alignment, Length, Subscription};
 use iced_aw::{Card, Modal};
 
 use crate::components::text_input_group::TextInputGroup;
 use crate::components::{common_scrollable, inform_card, relay_row, RelayRow};
+use crate::config::Appearance;
 use crate::consts::{NOSTR_RESOURCES_LINK, RELAYS_IMAGE, RELAY_SUGGESTIONS, WELCOME_IMAGE};
+use crate::db::SyncDepth;
 use crate::error::BackendClosed;
 use crate::icon::{regular_circle_icon, solid_circle_icon};
 use crate::net::{BackEndConnection, BackendEvent, ToBackend};
 use crate::style;
+use crate::types::AccountPreview;
+use crate::utils::canonical_relay_url;
 use crate::{components::text::title, widget::Element};
 
 use std::time::Duration;
@@ -21,9 +25,10 @@ use super::{GoToView, RouterCommand};
 pub enum Message {
     RelayRow(Box<relay_row::MessageWrapper>),
     ToNextStep,
+    ExistingAccountPress,
     ToPreviousStep,
     Logout,
-    AddRelay(nostr::Url),
+    ToggleSuggestion(nostr::Url, bool),
     AddOtherPress,
 
     // Add Relay Modal
@@ -32,7 +37,8 @@ pub enum Message {
     AddRelayCancelButtonPressed,
     CloseAddRelayModal,
     OpenLink(&'static str),
-    AddAllRelays,
+    AddSelectedRelays,
+    SyncDepthPress(SyncDepth),
     Tick,
 }
 
@@ -112,31 +118,89 @@ impl ModalState {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Latency {
+    Measuring,
+    Reachable(Duration),
+    Unreachable,
+}
+
+pub struct SuggestedRelay {
+    url: nostr::Url,
+    latency: Latency,
+    selected: bool,
+}
+impl SuggestedRelay {
+    fn new(url: nostr::Url) -> Self {
+        Self {
+            url,
+            latency: Latency::Measuring,
+            selected: false,
+        }
+    }
+}
+
 pub enum StepView {
     Welcome,
     Relays {
-        relays_suggestion: Vec<nostr::Url>,
+        /// Fastest first
+        relays_suggestion: Vec<SuggestedRelay>,
         relays_added: Vec<RelayRow>,
         add_relay_modal: ModalState,
+        /// The fastest relays get selected once, when every measurement is done
+        preselected: bool,
+        sync_depth: SyncDepth,
+        /// The user has an account, the next step previews it
+        existing_account: bool,
+    },
+    /// What the selected relays have of an existing account
+    AccountPreview {
+        sync_depth: SyncDepth,
+        relays: usize,
+        preview: Option<AccountPreview>,
     },
     LoadingClient,
 }
 impl StepView {
-    fn relays_view(conn: &mut BackEndConnection) -> Result<Self, BackendClosed> {
+    fn relays_view(
+        conn: &mut BackEndConnection,
+        sync_depth: SyncDepth,
+        existing_account: bool,
+    ) -> Result<Self, BackendClosed> {
         conn.send(ToBackend::FetchRelays)?;
 
-        let relays_suggestion: Vec<_> = RELAY_SUGGESTIONS
+        let urls: Vec<_> = RELAY_SUGGESTIONS
             .iter()
             .filter_map(|s| nostr::Url::parse(s).ok())
             .collect();
+        conn.send(ToBackend::MeasureRelayLatency(urls.clone()))?;
 
         Ok(Self::Relays {
-            relays_suggestion,
+            relays_suggestion: urls.into_iter().map(SuggestedRelay::new).collect(),
             relays_added: vec![],
             add_relay_modal: ModalState::Off,
+            preselected: false,
+            sync_depth,
+            existing_account,
+        })
+    }
+    fn account_preview(
+        conn: &mut BackEndConnection,
+        sync_depth: SyncDepth,
+        relays: usize,
+    ) -> Result<Self, BackendClosed> {
+        conn.send(ToBackend::PreviewAccount)?;
+        Ok(Self::AccountPreview {
+            sync_depth,
+            relays,
+            preview: None,
         })
     }
-    fn loading_client(conn: &mut BackEndConnection) -> Result<StepView, BackendClosed> {
+    fn loading_client(
+        conn: &mut BackEndConnection,
+        sync_depth: SyncDepth,
+    ) -> Result<StepView, BackendClosed> {
+        conn.send(ToBackend::SetDmSyncDepth(sync_depth))?;
         conn.send(ToBackend::PrepareClient)?;
         Ok(Self::LoadingClient)
     }
@@ -144,8 +208,7 @@ impl StepView {
         match self {
             StepView::Welcome => 1,
             StepView::Relays { .. } => 2,
-            // StepView::DownloadEvents { .. } => 3,
-            StepView::LoadingClient => 3,
+            StepView::AccountPreview { .. } | StepView::LoadingClient => 3,
         }
     }
     const MAX_STEP: u8 = 3;
@@ -168,13 +231,25 @@ impl StepView {
         match self {
             StepView::Welcome => row![
                 button("Cancel").on_press(Message::Logout),
+                button("I already have an account")
+                    .style(style::Button::Bordered)
+                    .on_press(Message::ExistingAccountPress),
                 button("Next").on_press(Message::ToNextStep)
             ]
             .spacing(10)
             .into(),
-            StepView::Relays { .. } => row![
+            StepView::Relays {
+                existing_account, ..
+            } => row![
                 button("Back").on_press(Message::ToPreviousStep),
-                button("Start").on_press(Message::ToNextStep)
+                button(if *existing_account { "Next" } else { "Start" })
+                    .on_press(Message::ToNextStep)
+            ]
+            .spacing(10)
+            .into(),
+            StepView::AccountPreview { .. } => row![
+                button("Back").on_press(Message::ToPreviousStep),
+                button("Start sync").on_press(Message::ToNextStep)
             ]
             .spacing(10)
             .into(),
@@ -268,19 +343,30 @@ impl StepView {
                 relays_added,
                 relays_suggestion,
                 add_relay_modal,
+                sync_depth,
+                ..
             } => {
                 let title_2 = "Relays Setup";
                 let text_2 = "Add relays to connect";
-                let relays_suggestion =
+                let suggestion_rows =
                     relays_suggestion
                         .iter()
-                        .fold(column![].spacing(5), |column, url| {
+                        .fold(column![].spacing(5), |column, suggestion| {
+                            let url = suggestion.url.clone();
                             column.push(
                                 container(
                                     row![
-                                        text(url).size(20).width(Length::Fill),
-                                        button("Add").on_press(Message::AddRelay(url.clone()))
+                                        checkbox(
+                                            suggestion.url.as_str(),
+                                            suggestion.selected,
+                                            move |selected| {
+                                                Message::ToggleSuggestion(url.clone(), selected)
+                                            }
+                                        )
+                                        .width(Length::Fill),
+                                        latency_badge(suggestion.latency)
                                     ]
+                                    .spacing(10)
                                     .align_items(Alignment::Center),
                                 )
                                 .width(Length::Fill)
@@ -305,7 +391,7 @@ impl StepView {
                 .center_x()
                 .center_y();
                 let relays = container(common_scrollable(
-                    column![relay_rows, relays_suggestion, add_other_btn]
+                    column![relay_rows, suggestion_rows, add_other_btn]
                         .spacing(10)
                         .padding(20),
                 ))
@@ -314,10 +400,13 @@ impl StepView {
                 .width(Length::Fill)
                 .height(Length::Fill);
 
-                let add_all_btn = button("Add All")
+                let selected = relays_suggestion.iter().filter(|s| s.selected).count();
+                let mut add_all_btn = button(text(format!("Add {} selected", selected)))
                     .padding(5)
-                    .style(style::Button::Primary)
-                    .on_press(Message::AddAllRelays);
+                    .style(style::Button::Primary);
+                if selected > 0 {
+                    add_all_btn = add_all_btn.on_press(Message::AddSelectedRelays);
+                }
 
                 let content = column![
                     title(title_2)
@@ -342,7 +431,8 @@ impl StepView {
                                     )
                                     .padding(10)
                                     .width(Length::Fill),
-                                    relays
+                                    relays,
+                                    sync_depth_row(*sync_depth)
                                 ]
                                 .spacing(10)
                             )
@@ -369,6 +459,52 @@ impl StepView {
 
                 add_relay_modal.view(underlay)
             }
+            StepView::AccountPreview {
+                relays, preview, ..
+            } => {
+                let answered = preview.as_ref().map_or(0, |p| p.relays_done);
+                let summary = match preview {
+                    Some(preview) => text(preview.summary()).size(TEXT_SIZE_LARGE),
+                    None => text("Looking for your account...")
+                        .size(TEXT_SIZE_LARGE)
+                        .style(style::Text::Placeholder),
+                };
+                let progress = text(format!("{} of {} relays answered", answered, relays))
+                    .size(TEXT_SIZE_SMALL)
+                    .style(style::Text::Placeholder);
+                let hint = text(
+                    "Messages are counted from a sample, the sync fetches them all. \
+                    If nothing shows up, go back and add the relays you used before.",
+                )
+                .size(TEXT_SIZE_SMALL);
+
+                let content = column![
+                    title("Your Account")
+                        .height(Length::FillPortion(1))
+                        .width(Length::Fill)
+                        .center_x()
+                        .center_y(),
+                    container(
+                        column![summary, progress, hint]
+                            .spacing(10)
+                            .width(Length::Fixed(TEXT_WIDTH))
+                    )
+                    .height(Length::FillPortion(4))
+                    .width(Length::Fill)
+                    .center_y()
+                    .center_x(),
+                    container(self.make_step_buttons()).height(Length::FillPortion(1))
+                ]
+                .spacing(10);
+
+                container(content)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x()
+                    .center_y()
+                    .style(style::Container::WelcomeBg2)
+                    .into()
+            }
 
             StepView::LoadingClient => inform_card("Loading", "Please wait..."),
         }
@@ -386,22 +522,33 @@ impl State {
     fn next_step(&mut self, conn: &mut BackEndConnection) -> Result<(), BackendClosed> {
         match &self.step_view {
             StepView::Welcome => {
-                self.step_view = StepView::relays_view(conn)?;
+                self.step_view = StepView::relays_view(conn, SyncDepth::SixMonths, false)?;
+            }
+            StepView::Relays {
+                sync_depth,
+                existing_account: true,
+                relays_added,
+                ..
+            } => {
+                self.step_view = StepView::account_preview(conn, *sync_depth, relays_added.len())?;
             }
-            StepView::Relays { .. } => {
-                self.step_view = StepView::loading_client(conn)?;
+            StepView::Relays { sync_depth, .. } | StepView::AccountPreview { sync_depth, .. } => {
+                self.step_view = StepView::loading_client(conn, *sync_depth)?;
             }
             StepView::LoadingClient => {}
         }
         Ok(())
     }
-    fn previous_step(&mut self, _conn: &mut BackEndConnection) {
+    fn previous_step(&mut self, conn: &mut BackEndConnection) -> Result<(), BackendClosed> {
         match &self.step_view {
             StepView::Welcome => {}
             StepView::Relays { .. } => self.step_view = StepView::Welcome,
-            // StepView::DownloadEvents { .. } => {}
+            StepView::AccountPreview { sync_depth, .. } => {
+                self.step_view = StepView::relays_view(conn, *sync_depth, true)?;
+            }
             StepView::LoadingClient => {}
         }
+        Ok(())
     }
 }
 
@@ -450,21 +597,37 @@ impl Route for State {
             Message::ToNextStep => {
                 self.next_step(conn)?;
             }
-            Message::ToPreviousStep => self.previous_step(conn),
-            Message::AddRelay(relay_url) => {
-                if let StepView::Relays { .. } = &mut self.step_view {
-                    conn.send(ToBackend::AddRelay(relay_url))?;
+            Message::ExistingAccountPress => {
+                if let StepView::Welcome = self.step_view {
+                    self.step_view = StepView::relays_view(conn, SyncDepth::SixMonths, true)?;
                 }
             }
-            Message::AddAllRelays => {
-                if let StepView::Relays { .. } = &mut self.step_view {
-                    for relay_url in RELAY_SUGGESTIONS {
-                        if let Ok(relay_url) = nostr::Url::parse(relay_url) {
-                            conn.send(ToBackend::AddRelay(relay_url))?;
-                        }
+            Message::ToPreviousStep => self.previous_step(conn)?,
+            Message::ToggleSuggestion(url, selected) => {
+                if let StepView::Relays {
+                    relays_suggestion, ..
+                } = &mut self.step_view
+                {
+                    if let Some(suggestion) = relays_suggestion.iter_mut().find(|s| s.url == url) {
+                        suggestion.selected = selected;
                     }
                 }
             }
+            Message::AddSelectedRelays => {
+                if let StepView::Relays {
+                    relays_suggestion, ..
+                } = &mut self.step_view
+                {
+                    for suggestion in relays_suggestion.iter().filter(|s| s.selected) {
+                        conn.send(ToBackend::AddRelay(suggestion.url.clone()))?;
+                    }
+                }
+            }
+            Message::SyncDepthPress(depth) => {
+                if let StepView::Relays { sync_depth, .. } = &mut self.step_view {
+                    *sync_depth = depth;
+                }
+            }
             Message::AddOtherPress => {
                 if let StepView::Relays {
                     add_relay_modal, ..
@@ -518,7 +681,7 @@ impl Route for State {
     fn backend_event(
         &mut self,
         event: BackendEvent,
-        _conn: &mut BackEndConnection,
+        conn: &mut BackEndConnection,
     ) -> Result<RouterCommand<Self::Message>, BackendClosed> {
         let mut command = RouterCommand::new();
 
@@ -526,8 +689,24 @@ impl Route for State {
             StepView::Relays {
                 relays_added,
                 relays_suggestion,
+                preselected,
                 ..
             } => match event {
+                BackendEvent::RelayLatency(url, latency) => {
+                    if let Some(suggestion) = relays_suggestion.iter_mut().find(|s| s.url == url) {
+                        suggestion.latency = latency
+                            .map(Latency::Reachable)
+                            .unwrap_or(Latency::Unreachable);
+                    }
+                    sort_by_latency(relays_suggestion);
+                    let measuring = relays_suggestion
+                        .iter()
+                        .any(|s| s.latency == Latency::Measuring);
+                    if !*preselected && !measuring {
+                        *preselected = true;
+                        preselect_fastest(relays_suggestion, PRESELECTED_RELAYS);
+                    }
+                }
                 BackendEvent::RelayUpdated(db_relay) => {
                     if let Some(row) = relays_added
                         .iter_mut()
@@ -540,7 +719,7 @@ impl Route for State {
                 }
                 BackendEvent::GotRelays(mut db_relays) => {
                     for db_relay in db_relays.iter() {
-                        relays_suggestion.retain(|url| url != &db_relay.url);
+                        remove_suggestion(relays_suggestion, &db_relay.url);
                     }
                     db_relays.sort_by(|a, b| a.url.cmp(&b.url));
                     *relays_added = db_relays
@@ -550,19 +729,25 @@ impl Route for State {
                         .collect();
                 }
                 BackendEvent::RelayCreated(db_relay) => {
-                    relays_suggestion.retain(|url| url != &db_relay.url);
+                    remove_suggestion(relays_suggestion, &db_relay.url);
                     relays_added.push(RelayRow::new(relays_added.len() as i32, db_relay));
                 }
                 BackendEvent::RelayDeleted(url) => {
                     relays_added.retain(|row| row.db_relay.url != url);
-                    relays_suggestion.push(url);
+                    conn.send(ToBackend::MeasureRelayLatency(vec![url.clone()]))?;
+                    relays_suggestion.push(SuggestedRelay::new(url));
                 }
                 _ => (),
             },
+            StepView::AccountPreview { preview, .. } => {
+                if let BackendEvent::GotAccountPreview(new_preview) = event {
+                    *preview = Some(new_preview);
+                }
+            }
             StepView::Welcome => (),
             StepView::LoadingClient => {
                 if let BackendEvent::FinishedPreparing = event {
-                    command.change_route(GoToView::Chat);
+                    command.change_route(GoToView::Startup);
                 }
             }
         }
@@ -570,11 +755,82 @@ impl Route for State {
         Ok(command)
     }
 
-    fn view(&self, _selected_theme: Option<style::Theme>) -> Element<Self::Message> {
+    fn view(
+        &self,
+        _selected_theme: Option<style::Theme>,
+        _appearance: Appearance,
+    ) -> Element<Self::Message> {
         self.step_view.view()
     }
 }
 
+fn latency_badge(latency: Latency) -> Element<'static, Message> {
+    let badge = match latency {
+        Latency::Measuring => text("…").style(style::Text::Placeholder),
+        Latency::Unreachable => text("offline").style(style::Text::Danger),
+        Latency::Reachable(duration) => {
+            let millis = duration.as_millis();
+            let badge = text(format!("{} ms", millis));
+            if millis <= FAST_LATENCY_MILLIS {
+                badge.style(style::Text::Primary)
+            } else {
+                badge.style(style::Text::Placeholder)
+            }
+        }
+    };
+    badge.size(TEXT_SIZE_SMALL).into()
+}
+
+/// How far back the first sync fetches messages
+fn sync_depth_row(selected: SyncDepth) -> Element<'static, Message> {
+    SyncDepth::ALL
+        .iter()
+        .fold(
+            row![
+                text("Message history").size(TEXT_SIZE_SMALL),
+                Space::with_width(Length::Fill)
+            ]
+            .spacing(5)
+            .padding([0, 10])
+            .align_items(Alignment::Center),
+            |row, depth| {
+                let btn_style = if *depth == selected {
+                    style::Button::ActiveMenuBtn
+                } else {
+                    style::Button::MenuBtn
+                };
+                row.push(
+                    button(text(depth.to_string()).size(TEXT_SIZE_SMALL))
+                        .style(btn_style)
+                        .on_press(Message::SyncDepthPress(*depth)),
+                )
+            },
+        )
+        .into()
+}
+
+/// Reachable relays fastest first, then the ones still measuring, unreachable last
+fn sort_by_latency(suggestions: &mut [SuggestedRelay]) {
+    suggestions.sort_by_key(|s| match s.latency {
+        Latency::Reachable(duration) => (0, duration),
+        Latency::Measuring => (1, Duration::ZERO),
+        Latency::Unreachable => (2, Duration::ZERO),
+    });
+}
+
+/// Expects the suggestions sorted
+fn preselect_fastest(suggestions: &mut [SuggestedRelay], count: usize) {
+    for (idx, suggestion) in suggestions.iter_mut().enumerate() {
+        suggestion.selected = idx < count && matches!(suggestion.latency, Latency::Reachable(_));
+    }
+}
+
+/// Added relays may be written differently from the suggestion
+fn remove_suggestion(suggestions: &mut Vec<SuggestedRelay>, added: &nostr::Url) {
+    let added = canonical_relay_url(added);
+    suggestions.retain(|s| canonical_relay_url(&s.url) != added);
+}
+
 const WELCOME_IMAGE_MAX_WIDTH: f32 = 300.0;
 const TEXT_SIZE_BIG: u16 = 28;
 const TEXT_SIZE_LARGE: u16 = 24;
@@ -583,3 +839,52 @@ const TEXT_SIZE_SMALL: u16 = 16;
 const TEXT_WIDTH: f32 = 400.0;
 const CARD_MAX_WIDTH: f32 = 300.0;
 const TICK_INTERVAL_MILLIS: u64 = 500;
+const PRESELECTED_RELAYS: usize = 4;
+const FAST_LATENCY_MILLIS: u128 = 200;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(url: &str, latency: Latency) -> SuggestedRelay {
+        SuggestedRelay {
+            latency,
+            ..SuggestedRelay::new(nostr::Url::parse(url).unwrap())
+        }
+    }
+
+    #[test]
+    fn fastest_reachable_relays_are_preselected() {
+        let mut suggestions = vec![
+            suggestion(
+                "wss://slow.com",
+                Latency::Reachable(Duration::from_millis(300)),
+            ),
+            suggestion("wss://down.com", Latency::Unreachable),
+            suggestion(
+                "wss://fast.com",
+                Latency::Reachable(Duration::from_millis(40)),
+            ),
+        ];
+        sort_by_latency(&mut suggestions);
+        preselect_fastest(&mut suggestions, 4);
+        let order: Vec<_> = suggestions
+            .iter()
+            .map(|s| (s.url.host_str().unwrap(), s.selected))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("fast.com", true), ("slow.com", true), ("down.com", false)]
+        );
+    }
+
+    #[test]
+    fn added_relay_removes_equivalent_suggestion() {
+        let mut suggestions = vec![suggestion("wss://relay.damus.io", Latency::Measuring)];
+        remove_suggestion(
+            &mut suggestions,
+            &nostr::Url::parse("wss://Relay.damus.io:443/").unwrap(),
+        );
+        assert!(suggestions.is_empty());
+    }
+}
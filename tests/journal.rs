@@ -0,0 +1,62 @@
+use nostrtalk::db::{DbContact, DbPendingEvent};
+use nostrtalk::net::{process_message, ToBackend};
+
+use crate::common::make_random_contact;
+use crate::spawn_app;
+
+/// Unconfirmed events are saved to the journal when closing
+/// and sent again at the next login
+#[tokio::test]
+async fn unconfirmed_events_are_journaled() {
+    // PREPARE
+    let mut test_app = spawn_app().await;
+    let (mut output, _rx) = futures::channel::mpsc::channel(5);
+    let (tasks_tx, _) = tokio::sync::mpsc::channel(5);
+
+    let contact = make_random_contact(None);
+    let contact = DbContact::new(&contact.pk);
+    let message = ToBackend::SendDM(contact, "Hey amigo!".into());
+    process_message(
+        &mut output,
+        &test_app.keys,
+        &mut test_app.backend,
+        &tasks_tx,
+        message,
+    )
+    .await
+    .unwrap();
+    let event_hash = *test_app.backend.pending_events.keys().next().unwrap();
+
+    // PERFORM
+    let count = test_app.backend.persist_pending().await.unwrap();
+
+    // ASSERT
+    assert_eq!(count, 1);
+    assert!(test_app.backend.pending_events.is_empty());
+    let journal = DbPendingEvent::fetch(test_app.pool()).await.unwrap();
+    assert_eq!(journal.len(), 1);
+    assert_eq!(journal[0].ns_event.id, event_hash);
+
+    // PERFORM
+    test_app.backend.resend_journal().await.unwrap();
+
+    // ASSERT
+    assert!(test_app.backend.pending_events.contains_key(&event_hash));
+    let journal = DbPendingEvent::fetch(test_app.pool()).await.unwrap();
+    assert_eq!(journal.len(), 1, "Kept until a relay accepts it");
+
+    // PERFORM
+    let pending = test_app.backend.pending_events.remove(&event_hash).unwrap();
+    test_app
+        .backend
+        .remove_from_journal(&pending)
+        .await
+        .unwrap();
+
+    // ASSERT
+    let journal = DbPendingEvent::fetch(test_app.pool()).await.unwrap();
+    assert!(
+        journal.is_empty(),
+        "Journal should be empty once the event is accepted"
+    );
+}
@@ -21,9 +21,7 @@ pub async fn assert_event_and_contacts(
 
     assert!(db_event.is_some(), "Event not found in database");
 
-    let db_contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let db_contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     assert_eq!(
         db_contacts.len(),
@@ -52,9 +50,7 @@ pub async fn assert_message_received(test_app: &TestApp, receiver: &mut Receiver
 
 pub async fn assert_contact_with_message(test_app: &TestApp, message: BackendEvent) {
     if let BackendEvent::ContactCreated(contact) = message {
-        let db_contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-            .await
-            .unwrap();
+        let db_contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
         assert!(
             db_contacts.iter().any(|ct| ct.pubkey() == contact.pubkey()),
@@ -12,6 +12,7 @@ mod received_channel_metadata;
 mod received_channel_msg;
 mod received_contact_list;
 mod received_dm;
+mod received_poll;
 mod sent_channel_msg;
 mod sent_contact_list;
 mod sent_dm;
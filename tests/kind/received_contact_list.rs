@@ -328,9 +328,7 @@ async fn contact_list_new_bd_with_list_stored() {
     assert_received_contact_list_event(&mut rx).await;
 
     for first_cs in first_contacts {
-        let db_contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-            .await
-            .unwrap();
+        let db_contacts = DbContact::fetch(test_app.pool()).await.unwrap();
         assert!(
             !db_contacts.iter().any(|db_c| db_c.pubkey() == &first_cs.pk),
             "Old contact should be removed"
@@ -388,3 +386,87 @@ async fn contact_list_some_bd_no_list_stored_test_myself() {
     }
     assert_received_contact_list_event(&mut rx).await;
 }
+
+/// 6. Received NEW contact list keeping a contact. Its notes and creation date survive.
+#[tokio::test]
+async fn contact_list_new_keeps_contact_notes() {
+    // PREPARE
+    let mut test_app = spawn_app().await;
+    let (mut output, mut rx) = futures::channel::mpsc::channel(10);
+    let url = Url::parse("ws://192.168.15.15:8080").unwrap();
+
+    let kept = make_contact(
+        "9e45b5e573adfb70be9f81e6f19e3df334fa24b3a7273859104d399ccbf64e94",
+        Some("Vaderzzz"),
+    );
+    let dropped = make_contact(
+        "dafb7c5a8d3a061a8254eb9ffb132cceec0b5080357531006e127263121e3adc",
+        Some("Friendzin"),
+    );
+    let ns_event = users_contact_list_event(
+        &test_app.keys,
+        vec![kept.clone(), dropped.clone()].into_iter(),
+    );
+    let subscription_id = nostr::SubscriptionId::new("testing");
+
+    let result = handle_event(
+        &mut output,
+        &test_app.keys,
+        &mut test_app.backend,
+        url.clone(),
+        subscription_id.clone(),
+        ns_event,
+    )
+    .await;
+    assert!(result.is_ok(), "Error handling event: {:?}", result.err());
+    for _ in 0..2 {
+        assert_message_received(&test_app, &mut rx).await;
+    }
+    assert_received_contact_list_event(&mut rx).await;
+
+    let stored = DbContact::fetch_one(test_app.pool(), &kept.pk)
+        .await
+        .unwrap()
+        .unwrap()
+        .with_notes("Met at the Rust meetup");
+    DbContact::update(test_app.pool(), &stored).await.unwrap();
+
+    // PERFORM
+    // Insert second contact list that is newer and drops one contact
+    let new_ns_event =
+        users_contact_list_builder(vec![kept.clone(), make_random_contact(None)].into_iter());
+    let time = Utc::now() + chrono::Duration::minutes(10);
+    let new_ns_event = event_with_time(&test_app.keys, new_ns_event, time.naive_utc());
+    let second_event_hash = new_ns_event.id.clone();
+    let result = handle_event(
+        &mut output,
+        &test_app.keys,
+        &mut test_app.backend,
+        url,
+        subscription_id,
+        new_ns_event,
+    )
+    .await;
+
+    // ASSERT
+    assert!(result.is_ok(), "Error handling event: {:?}", result.err());
+    assert_event_and_contacts(&test_app, &second_event_hash, 2).await;
+
+    let db_contact = DbContact::fetch_one(test_app.pool(), &kept.pk)
+        .await
+        .unwrap()
+        .expect("Kept contact should be stored");
+    assert_eq!(db_contact.get_notes(), Some("Met at the Rust meetup"));
+    assert_eq!(
+        db_contact.get_created_at().timestamp_millis(),
+        stored.get_created_at().timestamp_millis(),
+        "Creation date should be kept"
+    );
+    let dropped_contact = DbContact::fetch_one(test_app.pool(), &dropped.pk)
+        .await
+        .unwrap();
+    assert!(
+        dropped_contact.is_none(),
+        "Dropped contact should be removed"
+    );
+}
@@ -1,5 +1,5 @@
 use nostr::Keys;
-use nostrtalk::db::{DbContact, MessageStatus};
+use nostrtalk::db::{DbContact, DbRelayResponse, MessageStatus};
 use nostrtalk::net::handle_event;
 use nostrtalk::types::{ChatMessage, UserMessage};
 use url::Url;
@@ -223,9 +223,7 @@ async fn dm_message_anyone_to_user_new_contact() {
 
     _ = assert_dm_in_database(&test_app, &event_hash, 1, &msg_content).await;
 
-    let contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     if let Some(first) = contacts.first() {
         assert_eq!(first.pubkey(), &sender_keys.public_key());
@@ -233,3 +231,38 @@ async fn dm_message_anyone_to_user_new_contact() {
         panic!("No contact in the database");
     }
 }
+
+/// The same message arrives from two relays -> both are conversation relay hints
+#[tokio::test]
+async fn dm_message_relays_become_conversation_hints() {
+    // PREPARE
+    let mut test_app = spawn_app().await;
+    let (mut output, _rx) = futures::channel::mpsc::channel(5);
+    let url_1 = Url::parse("ws://192.168.15.15:8080").unwrap();
+    let url_2 = Url::parse("ws://192.168.15.16:8080").unwrap();
+    let sender_keys = Keys::generate();
+    let ns_event = make_dm_event(&sender_keys, test_app.keys.public_key(), "over here");
+    let subscription_id = nostr::SubscriptionId::new("testing");
+
+    // PERFORM
+    for url in [&url_1, &url_2] {
+        let result = handle_event(
+            &mut output,
+            &test_app.keys,
+            &mut test_app.backend,
+            url.clone(),
+            subscription_id.clone(),
+            ns_event.clone(),
+        )
+        .await;
+        assert!(result.is_ok(), "Error handling event: {:?}", result.err());
+    }
+
+    // ASSERT
+    let relays = DbRelayResponse::fetch_chat_relays(test_app.pool(), &sender_keys.public_key(), 3)
+        .await
+        .unwrap();
+    assert_eq!(relays.len(), 2);
+    assert!(relays.contains(&url_1));
+    assert!(relays.contains(&url_2));
+}
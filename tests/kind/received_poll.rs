@@ -0,0 +1,85 @@
+use futures_util::StreamExt;
+use nostr::Keys;
+use nostrtalk::{
+    db::{DbPoll, PollTally},
+    net::{handle_event, BackendEvent},
+    utils::{poll_builder, poll_vote_builder},
+};
+use url::Url;
+
+use crate::spawn_app;
+
+/// Tests for Received polls and poll responses
+
+#[tokio::test]
+async fn vote_updates_poll_tally() {
+    // PREPARE
+    let mut test_app = spawn_app().await;
+    DbPoll::create_tables(test_app.pool()).await.unwrap();
+    let (mut output, mut rx) = futures::channel::mpsc::channel(5);
+    let url = Url::parse("ws://192.168.15.15:8080").unwrap();
+    let subscription_id = nostr::SubscriptionId::new("testing");
+
+    let cache = test_app.insert_random_channel_cache().await;
+    let channel_id = cache.channel_id;
+    let options = vec!["yes".to_string(), "no".to_string()];
+    let poll_event = poll_builder(&channel_id, "Coffee?", &options)
+        .to_event(&Keys::generate())
+        .unwrap();
+    let poll_hash = poll_event.id;
+    let voter = Keys::generate();
+    let vote_event = poll_vote_builder(&channel_id, &poll_hash, "1")
+        .to_event(&voter)
+        .unwrap();
+
+    // PERFORM
+    let result = handle_event(
+        &mut output,
+        &test_app.keys,
+        &mut test_app.backend,
+        url.clone(),
+        subscription_id.clone(),
+        poll_event,
+    )
+    .await;
+    assert!(result.is_ok(), "Error handling poll: {:?}", result.err());
+
+    let result = handle_event(
+        &mut output,
+        &test_app.keys,
+        &mut test_app.backend,
+        url,
+        subscription_id,
+        vote_event,
+    )
+    .await;
+    assert!(result.is_ok(), "Error handling vote: {:?}", result.err());
+
+    // ASSERT
+    match rx.next().await {
+        Some(BackendEvent::ReceivedPoll(poll, tally)) => {
+            assert_eq!(poll.poll_hash, poll_hash);
+            assert_eq!(poll.options.len(), 2);
+            assert_eq!(tally.total(), 0);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+    match rx.next().await {
+        Some(BackendEvent::PollTallyUpdated(rcv_channel_id, tally)) => {
+            assert_eq!(rcv_channel_id, channel_id);
+            assert_eq!(tally.count("1"), 1);
+            assert_eq!(tally.count("0"), 0);
+            assert_eq!(tally.user_vote, None);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    let poll = DbPoll::fetch_one(test_app.pool(), &poll_hash)
+        .await
+        .unwrap()
+        .unwrap();
+    let tally = PollTally::fetch(test_app.pool(), &poll, &voter.public_key())
+        .await
+        .unwrap();
+    assert_eq!(tally.user_vote.as_deref(), Some("1"));
+}
@@ -41,9 +41,7 @@ async fn add_new_contact() {
     assert!(result.is_ok(), "Error handling event: {:?}", result.err());
     assert_eq!(test_app.backend.pending_events.len(), 1);
 
-    let contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     assert_eq!(contacts.len(), 1, "Wrong number of contacts inserted");
 
@@ -81,9 +79,7 @@ async fn add_contact_same_as_user() {
     assert!(result.is_err(), "Should not add contact same as user");
     assert_eq!(test_app.backend.pending_events.len(), 0);
 
-    let contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     assert_eq!(
         contacts.len(),
@@ -134,9 +130,7 @@ async fn contact_must_be_unique() {
 
     assert_eq!(test_app.backend.pending_events.len(), 1);
 
-    let contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     assert_eq!(contacts.len(), 1, "Wrong number of contacts inserted");
 
@@ -201,9 +195,7 @@ async fn add_and_update_contact() {
 
     assert_eq!(test_app.backend.pending_events.len(), 2);
 
-    let contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     assert_eq!(contacts.len(), 1, "Wrong number of contacts inserted");
 
@@ -264,9 +256,7 @@ async fn update_contact() {
 
     assert_eq!(test_app.backend.pending_events.len(), 1);
 
-    let contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     assert_eq!(contacts.len(), 1, "Wrong number of contacts inserted");
 
@@ -315,9 +305,7 @@ async fn delete_contact() {
 
     assert_eq!(test_app.backend.pending_events.len(), 1);
 
-    let contacts = DbContact::fetch(test_app.pool(), test_app.cache_pool())
-        .await
-        .unwrap();
+    let contacts = DbContact::fetch(test_app.pool()).await.unwrap();
 
     assert_eq!(contacts.len(), 0, "Contact not deleted");
 
@@ -333,3 +321,22 @@ async fn delete_contact() {
 
     assert_channel_timeout(&mut rx).await;
 }
+
+#[tokio::test]
+async fn unpublished_contacts_show_in_diff() {
+    // PREPARE
+    let test_app = spawn_app().await;
+    let contact = make_random_contact(None);
+    test_app.insert_contacts(vec![contact.clone()]).await;
+
+    // PERFORM
+    let diff = test_app
+        .backend
+        .contact_list_diff(&test_app.keys)
+        .await
+        .unwrap();
+
+    // ASSERT
+    assert_eq!(diff.len(), 1);
+    assert_eq!(diff.added[0].pubkey(), &contact.pk);
+}
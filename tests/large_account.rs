@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use chrono::Duration;
+use nostrtalk::db::{
+    ChannelCache, ChannelSubscription, DbChannelMessage, DbContact, DbEvent, DbMessage,
+    MessageTagInfo,
+};
+use nostrtalk::test_support::{generate, message_content, AccountSpec, TOPICS};
+
+use crate::spawn_app;
+
+fn large_spec() -> AccountSpec {
+    AccountSpec {
+        contacts: 4,
+        messages_per_contact: 250,
+        channels: 2,
+        members_per_channel: 5,
+        messages_per_channel: 120,
+        interval: Duration::seconds(5),
+    }
+}
+
+/// Scrolling back a conversation longer than a page reaches every message once
+#[tokio::test]
+async fn chat_pages_cover_large_account() {
+    // PREPARE
+    let test_app = spawn_app().await;
+    let spec = large_spec();
+    let account = generate(test_app.pool(), test_app.cache_pool(), &spec)
+        .await
+        .unwrap();
+    assert_eq!(DbContact::fetch(test_app.pool()).await.unwrap().len(), 4);
+
+    for contact in &account.contacts {
+        // PERFORM
+        let mut page = DbMessage::fetch_chat(test_app.pool(), contact)
+            .await
+            .unwrap();
+        let mut pages = 0;
+        let mut messages = vec![];
+        while let Some(oldest) = page.last().map(|m| m.created_at) {
+            pages += 1;
+            messages.append(&mut page);
+            page = DbMessage::fetch_chat_more(test_app.pool(), contact, oldest)
+                .await
+                .unwrap();
+        }
+
+        // ASSERT
+        assert_eq!(pages, 3);
+        assert_eq!(messages.len(), spec.messages_per_contact);
+        let ids: HashSet<_> = messages.iter().map(|m| m.event_id).collect();
+        assert_eq!(ids.len(), messages.len());
+        assert!(messages
+            .windows(2)
+            .all(|pair| pair[0].created_at > pair[1].created_at));
+        assert_eq!(messages[0].created_at, account.newest);
+        assert_eq!(messages.iter().filter(|m| m.is_unseen()).count(), 1);
+    }
+}
+
+/// The generated messages decrypt to contents a search can rely on
+#[tokio::test]
+async fn search_finds_every_topic_in_first_page() {
+    // PREPARE
+    let test_app = spawn_app().await;
+    let spec = large_spec();
+    let account = generate(test_app.pool(), test_app.cache_pool(), &spec)
+        .await
+        .unwrap();
+    let page = DbMessage::fetch_chat(test_app.pool(), &account.contacts[0])
+        .await
+        .unwrap();
+
+    // PERFORM
+    let mut contents = vec![];
+    for db_message in &page {
+        let db_event = DbEvent::fetch_id(test_app.pool(), db_message.event_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let tag_info =
+            MessageTagInfo::from_event_tags(&db_event.event_hash, &db_event.pubkey, &db_event.tags)
+                .unwrap();
+        contents.push(
+            db_message
+                .decrypt_message(&account.keys, &tag_info)
+                .unwrap(),
+        );
+    }
+
+    // ASSERT
+    assert_eq!(contents[0], message_content(spec.messages_per_contact - 1));
+    for topic in TOPICS {
+        let found = contents.iter().filter(|c| c.contains(topic)).count();
+        assert_eq!(found, page.len() / TOPICS.len());
+    }
+}
+
+/// Every subscribed channel has its metadata, members and full history
+#[tokio::test]
+async fn channels_are_complete() {
+    // PREPARE
+    let test_app = spawn_app().await;
+    let spec = large_spec();
+
+    // PERFORM
+    let account = generate(test_app.pool(), test_app.cache_pool(), &spec)
+        .await
+        .unwrap();
+
+    // ASSERT
+    let subscriptions = ChannelSubscription::fetch(test_app.pool()).await.unwrap();
+    assert_eq!(subscriptions.len(), spec.channels);
+    for channel_id in &account.channels {
+        let cache = ChannelCache::fetch_by_channel_id(test_app.cache_pool(), channel_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cache.members.len(), spec.members_per_channel + 1);
+        let messages = DbChannelMessage::fetch(test_app.pool(), channel_id)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), spec.messages_per_channel);
+        assert_eq!(
+            messages.iter().filter(|m| m.is_users).count(),
+            spec.messages_per_channel / (spec.members_per_channel + 1)
+        );
+    }
+}
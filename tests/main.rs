@@ -1,14 +1,15 @@
 use common::make_channel_creation_event;
 use nostrtalk::{
-    db::{upgrade_cache_db, upgrade_db, ChannelCache, Database, DbContact},
+    db::{ChannelCache, DbContact, TestDatabase},
     types::{BackendState, ChannelMetadata},
 };
 use once_cell::sync::Lazy;
 use sqlx::SqlitePool;
-use tempfile::NamedTempFile;
 
 mod common;
+mod journal;
 mod kind;
+mod large_account;
 
 // Ensure that the `tracing` stack is only initialised once using `once_cell`
 static TRACING: Lazy<()> = Lazy::new(|| nostrtalk::setup_logger());
@@ -16,6 +17,8 @@ static TRACING: Lazy<()> = Lazy::new(|| nostrtalk::setup_logger());
 pub struct TestApp {
     pub backend: BackendState,
     pub keys: nostr::Keys,
+    // Keeps the database files alive for the duration of the test
+    _db: TestDatabase,
 }
 impl TestApp {
     pub fn pool(&self) -> &SqlitePool {
@@ -49,34 +52,22 @@ pub async fn spawn_app() -> TestApp {
     Lazy::force(&TRACING);
 
     let keys = nostr::Keys::generate();
-    let db_client = configure_database().await;
+    let db = TestDatabase::new().await.unwrap();
     let req_client = reqwest::Client::new();
     let backend = BackendState::new(
-        db_client,
+        db.db.clone(),
         req_client,
         ns_client::RelayPool::new(),
         Vec::new(),
         None,
+        false,
+        20,
     );
-    let test_app = TestApp { backend, keys };
+    let test_app = TestApp {
+        backend,
+        keys,
+        _db: db,
+    };
 
     test_app
 }
-
-async fn configure_database() -> Database {
-    let pool = configure_pool().await;
-    let cache_pool = configure_pool().await;
-
-    upgrade_db(&pool).await.unwrap();
-    upgrade_cache_db(&cache_pool).await.unwrap();
-
-    Database { cache_pool, pool }
-}
-
-async fn configure_pool() -> SqlitePool {
-    let tempfile = NamedTempFile::new().unwrap();
-    let pool = SqlitePool::connect(tempfile.path().to_str().unwrap())
-        .await
-        .unwrap();
-    pool
-}